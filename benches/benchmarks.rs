@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use ki::benchmark::{self, RustBuffer, SelectionMode, TextScreen};
+
+/// A Rust source file of a realistic size, which is this crate's largest module.
+const RUST_SOURCE: &str = include_str!("../src/app.rs");
+
+fn soft_wrap(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("soft_wrap");
+    for width in [40, 80, 120] {
+        group.bench_function(format!("width {width}"), |bencher| {
+            bencher.iter(|| benchmark::soft_wrap(black_box(RUST_SOURCE), width))
+        });
+    }
+    group.finish()
+}
+
+fn selection_mode_enumeration(criterion: &mut Criterion) {
+    let mut group = criterion.benchmark_group("selection_mode_enumeration");
+    for mode in [
+        SelectionMode::LineTrimmed,
+        SelectionMode::WordShort,
+        SelectionMode::Token,
+        SelectionMode::SyntaxNode,
+    ] {
+        group.bench_function(format!("{mode:?}"), |bencher| {
+            // A new buffer is needed for each iteration,
+            // because the selections are cached in the buffer
+            bencher.iter_batched(
+                || RustBuffer::new(RUST_SOURCE),
+                |buffer| benchmark::enumerate_selections(&buffer, mode).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish()
+}
+
+fn grid_diffing(criterion: &mut Criterion) {
+    let (width, height) = (200, 60);
+    let lines = RUST_SOURCE.lines().collect::<Vec<_>>();
+    let old = lines[..height as usize].join("\n");
+    let scrolled = lines[1..height as usize + 1].join("\n");
+    let mut group = criterion.benchmark_group("grid_diffing");
+    for (name, new) in [("unchanged", &old), ("scrolled by one line", &scrolled)] {
+        group.bench_function(name, |bencher| {
+            // New screens are needed for each iteration,
+            // because the cells of a screen are memoized
+            bencher.iter_batched(
+                || {
+                    (
+                        TextScreen::new(width, height, new),
+                        TextScreen::new(width, height, &old),
+                    )
+                },
+                |(mut new, mut old)| benchmark::diff_screens(&mut new, &mut old),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish()
+}
+
+criterion_group!(benches, soft_wrap, selection_mode_enumeration, grid_diffing);
+criterion_main!(benches);
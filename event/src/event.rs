@@ -67,21 +67,104 @@ pub enum KeyModifiers {
     CtrlShift,
     AltShift,
     CtrlAltShift,
+    /// Also known as Cmd (macOS), Win (Windows) or Meta.
+    /// Only reported by terminals that support the kitty keyboard protocol.
+    Super,
+    CtrlSuper,
+    AltSuper,
+    ShiftSuper,
+    CtrlAltSuper,
+    CtrlShiftSuper,
+    AltShiftSuper,
+    CtrlAltShiftSuper,
     Unknown,
 }
+
+/// The modifier keys that are held down.
+#[derive(Default, Clone, Copy)]
+struct Flags {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    super_: bool,
+}
+
+impl Flags {
+    fn union(self, other: Flags) -> Flags {
+        Flags {
+            ctrl: self.ctrl || other.ctrl,
+            alt: self.alt || other.alt,
+            shift: self.shift || other.shift,
+            super_: self.super_ || other.super_,
+        }
+    }
+}
+
 impl KeyModifiers {
     pub(crate) fn add_shift(self, shift: bool) -> KeyModifiers {
-        use KeyModifiers::*;
         if !shift {
             return self;
         }
-        match self {
-            None => Shift,
-            Ctrl => CtrlShift,
-            Alt => AltShift,
-            CtrlAlt => CtrlAltShift,
-            Unknown => Shift,
-            _ => self,
+        let flags = self.flags().unwrap_or_default();
+        KeyModifiers::from_flags(Flags {
+            shift: true,
+            ..flags
+        })
+    }
+
+    pub(crate) fn has_shift(&self) -> bool {
+        self.flags().is_some_and(|flags| flags.shift)
+    }
+
+    /// Returns `None` if `self` is `Unknown`.
+    fn flags(&self) -> Option<Flags> {
+        use KeyModifiers::*;
+        let (ctrl, alt, shift, super_) = match self {
+            None => (false, false, false, false),
+            Ctrl => (true, false, false, false),
+            Alt => (false, true, false, false),
+            Shift => (false, false, true, false),
+            CtrlAlt => (true, true, false, false),
+            CtrlShift => (true, false, true, false),
+            AltShift => (false, true, true, false),
+            CtrlAltShift => (true, true, true, false),
+            Super => (false, false, false, true),
+            CtrlSuper => (true, false, false, true),
+            AltSuper => (false, true, false, true),
+            ShiftSuper => (false, false, true, true),
+            CtrlAltSuper => (true, true, false, true),
+            CtrlShiftSuper => (true, false, true, true),
+            AltShiftSuper => (false, true, true, true),
+            CtrlAltShiftSuper => (true, true, true, true),
+            Unknown => return Option::None,
+        };
+        Some(Flags {
+            ctrl,
+            alt,
+            shift,
+            super_,
+        })
+    }
+
+    fn from_flags(flags: Flags) -> KeyModifiers {
+        use KeyModifiers::*;
+        match (flags.ctrl, flags.alt, flags.shift, flags.super_) {
+            (false, false, false, false) => None,
+            (true, false, false, false) => Ctrl,
+            (false, true, false, false) => Alt,
+            (false, false, true, false) => Shift,
+            (true, true, false, false) => CtrlAlt,
+            (true, false, true, false) => CtrlShift,
+            (false, true, true, false) => AltShift,
+            (true, true, true, false) => CtrlAltShift,
+            (false, false, false, true) => Super,
+            (true, false, false, true) => CtrlSuper,
+            (false, true, false, true) => AltSuper,
+            (false, false, true, true) => ShiftSuper,
+            (true, true, false, true) => CtrlAltSuper,
+            (true, false, true, true) => CtrlShiftSuper,
+            (false, true, true, true) => AltShiftSuper,
+            (true, true, true, true) => CtrlAltShiftSuper,
         }
     }
 }
@@ -89,49 +172,32 @@ impl KeyModifiers {
 impl From<crossterm::event::KeyModifiers> for KeyModifiers {
     fn from(value: crossterm::event::KeyModifiers) -> Self {
         use crossterm::event::KeyModifiers;
-        if value == KeyModifiers::NONE {
-            self::KeyModifiers::None
-        } else if value == KeyModifiers::CONTROL {
-            self::KeyModifiers::Ctrl
-        } else if value == KeyModifiers::ALT {
-            self::KeyModifiers::Alt
-        } else if value == KeyModifiers::SHIFT {
-            self::KeyModifiers::Shift
-        } else if value == KeyModifiers::CONTROL | KeyModifiers::ALT {
-            self::KeyModifiers::CtrlAlt
-        } else if value == KeyModifiers::CONTROL | KeyModifiers::SHIFT {
-            self::KeyModifiers::CtrlShift
-        } else if value == KeyModifiers::ALT | KeyModifiers::SHIFT {
-            self::KeyModifiers::AltShift
-        } else if value == KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT {
-            self::KeyModifiers::CtrlAltShift
-        } else {
-            self::KeyModifiers::Unknown
+        let known = KeyModifiers::CONTROL
+            | KeyModifiers::ALT
+            | KeyModifiers::SHIFT
+            | KeyModifiers::SUPER
+            | KeyModifiers::META;
+        if !known.contains(value) {
+            return self::KeyModifiers::Unknown;
         }
+        self::KeyModifiers::from_flags(Flags {
+            ctrl: value.contains(KeyModifiers::CONTROL),
+            alt: value.contains(KeyModifiers::ALT),
+            shift: value.contains(KeyModifiers::SHIFT),
+            // Terminals differ on whether the Cmd/Win key is reported as Super or Meta
+            super_: value.intersects(KeyModifiers::SUPER | KeyModifiers::META),
+        })
     }
 }
 
 impl From<HashSet<KeyModifiers>> for KeyModifiers {
     fn from(value: HashSet<KeyModifiers>) -> Self {
-        use KeyModifiers::*;
-        if value == HashSet::from([None]) || value.is_empty() {
-            None
-        } else if value == HashSet::from([Ctrl]) {
-            Ctrl
-        } else if value == HashSet::from([Alt]) {
-            Alt
-        } else if value == HashSet::from([Shift]) {
-            Shift
-        } else if value == HashSet::from([Ctrl, Alt]) {
-            CtrlAlt
-        } else if value == HashSet::from([Ctrl, Shift]) {
-            CtrlShift
-        } else if value == HashSet::from([Alt, Shift]) {
-            AltShift
-        } else if value == HashSet::from([Ctrl, Alt, Shift]) {
-            CtrlAltShift
-        } else {
-            Unknown
-        }
+        value
+            .iter()
+            .try_fold(Flags::default(), |flags, modifiers| {
+                Some(flags.union(modifiers.flags()?))
+            })
+            .map(KeyModifiers::from_flags)
+            .unwrap_or(KeyModifiers::Unknown)
     }
 }
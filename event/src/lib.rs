@@ -25,10 +25,12 @@ impl Token {
         match self.0.split('+').collect::<Vec<_>>().split_last() {
             Some((key, modifiers)) => {
                 let result = Token::parse_key_code(key)?;
-                Ok(KeyEvent::new(
-                    result.key_code,
-                    Token::parse_modifiers(modifiers)?.add_shift(result.shift),
-                ))
+                let modifiers = Token::parse_modifiers(modifiers)?.add_shift(result.shift);
+                let key_code = match (result.key_code, &modifiers) {
+                    (KeyCode::Tab, modifiers) if modifiers.has_shift() => KeyCode::BackTab,
+                    (key_code, _) => key_code,
+                };
+                Ok(KeyEvent::new(key_code, modifiers))
             }
             _ => {
                 let result = Token::parse_key_code(&self.0)?;
@@ -54,6 +56,7 @@ impl Token {
             "ctrl" => Ok(KeyModifiers::Ctrl),
             "alt" => Ok(KeyModifiers::Alt),
             "shift" => Ok(KeyModifiers::Shift),
+            "super" | "cmd" | "meta" => Ok(KeyModifiers::Super),
             _ => Err(ParseError::UnknownModifier(s.to_string())),
         }
     }
@@ -72,11 +75,19 @@ impl Token {
             "pageup" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::PageUp)),
             "pagedown" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::PageDown)),
             "tab" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::Tab)),
-            "backtab" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::BackTab)),
+            // Terminals report `shift+tab` as `BackTab` with the Shift modifier
+            "backtab" => Ok(ParseKeyCodeResult {
+                key_code: KeyCode::BackTab,
+                shift: true,
+            }),
             "delete" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::Delete)),
             "insert" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::Insert)),
             "space" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::Char(' '))),
             "backslash" => Ok(ParseKeyCodeResult::from_key_code(KeyCode::Char('\\'))),
+            _ if s.starts_with('f') && s.len() > 1 => match s[1..].parse::<u8>() {
+                Ok(number @ 1..=24) => Ok(ParseKeyCodeResult::from_key_code(KeyCode::F(number))),
+                _ => Err(ParseError::UnknownKeyCode(s.to_string())),
+            },
             _ if s.len() == 1 => {
                 let c = s.chars().next().unwrap();
                 Ok(ParseKeyCodeResult {
@@ -251,6 +262,63 @@ mod test_key_event {
         );
     }
 
+    #[test]
+    fn super_modifier() {
+        assert_eq!(
+            parse_key_events("super+a cmd+a meta+a").unwrap(),
+            vec![KeyEvent::new(KeyCode::Char('a'), KeyModifiers::Super); 3]
+        );
+
+        assert_eq!(
+            parse_key_events("ctrl+super+s").unwrap(),
+            vec![KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CtrlSuper)]
+        );
+
+        assert_eq!(
+            parse_key_events("cmd+S").unwrap(),
+            vec![KeyEvent::new(KeyCode::Char('S'), KeyModifiers::ShiftSuper)]
+        );
+
+        assert_eq!(
+            KeyModifiers::from(
+                crossterm::event::KeyModifiers::CONTROL | crossterm::event::KeyModifiers::SUPER
+            ),
+            KeyModifiers::CtrlSuper
+        );
+
+        assert_eq!(
+            KeyModifiers::from(crossterm::event::KeyModifiers::HYPER),
+            KeyModifiers::Unknown
+        );
+    }
+
+    #[test]
+    fn function_keys() {
+        assert_eq!(
+            parse_key_events("f1 f24 shift+f5").unwrap(),
+            vec![
+                KeyEvent::new(KeyCode::F(1), KeyModifiers::None),
+                KeyEvent::new(KeyCode::F(24), KeyModifiers::None),
+                KeyEvent::new(KeyCode::F(5), KeyModifiers::Shift),
+            ]
+        );
+
+        assert!(parse_key_events("f0").is_err());
+        assert!(parse_key_events("f25").is_err());
+    }
+
+    #[test]
+    fn shifted_tab_and_enter() {
+        assert_eq!(
+            parse_key_events("shift+tab backtab shift+enter").unwrap(),
+            vec![
+                KeyEvent::new(KeyCode::BackTab, KeyModifiers::Shift),
+                KeyEvent::new(KeyCode::BackTab, KeyModifiers::Shift),
+                KeyEvent::new(KeyCode::Enter, KeyModifiers::Shift),
+            ]
+        );
+    }
+
     #[test]
     fn multiple() {
         assert_eq!(
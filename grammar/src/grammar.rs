@@ -78,8 +78,29 @@ pub fn get_language(name: &str) -> Result<Language> {
     unimplemented!()
 }
 
+/// Grammars are loaded on demand, i.e. only when a file of their language is opened,
+/// and each grammar is loaded at most once.
 #[cfg(not(target_arch = "wasm32"))]
 pub fn get_language(name: &str) -> Result<Language> {
+    static LOADED_LANGUAGES: once_cell::sync::Lazy<
+        std::sync::Mutex<std::collections::HashMap<String, Language>>,
+    > = once_cell::sync::Lazy::new(Default::default);
+    if let Some(language) = LOADED_LANGUAGES
+        .lock()
+        .ok()
+        .and_then(|languages| languages.get(name).copied())
+    {
+        return Ok(language);
+    }
+    let language = load_language(name)?;
+    if let Ok(mut languages) = LOADED_LANGUAGES.lock() {
+        languages.insert(name.to_string(), language);
+    }
+    Ok(language)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn load_language(name: &str) -> Result<Language> {
     use libloading::{Library, Symbol};
     let mut rel_library_path = PathBuf::new().join("grammars").join(name);
     rel_library_path.set_extension(DYLIB_EXTENSION);
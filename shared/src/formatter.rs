@@ -1,54 +1,176 @@
-use std::io::Write;
+use std::{io::Write, ops::Range};
 
-use crate::language::ProcessCommand;
+use crate::language::{FormatterCommand, ProcessCommand};
 
+/// Runs the formatter commands of a language in sequence,
+/// each of which receives the output of the previous one.
 pub struct Formatter {
-    process_command: ProcessCommand,
+    commands: Vec<FormatterCommand>,
 }
 
-impl From<ProcessCommand> for Formatter {
-    fn from(value: ProcessCommand) -> Self {
-        Self {
-            process_command: value,
-        }
+impl Formatter {
+    pub(crate) fn new(commands: Vec<FormatterCommand>) -> Self {
+        Self { commands }
     }
-}
 
-impl Formatter {
     pub fn command_string(&self) -> String {
-        self.process_command.to_string()
+        self.commands
+            .iter()
+            .map(|command| command.process_command().to_string())
+            .collect::<Vec<_>>()
+            .join(" | ")
     }
+
     pub fn format(&self, content: &str) -> anyhow::Result<String> {
-        // Run the command with the args,
-        // pass in the content using stdin,
-        // get the output from the stdout
-
-        let mut child = self.process_command.spawn()?;
-
-        let stdin = child.stdin.as_mut().ok_or_else(|| {
-            anyhow::anyhow!(
-                "Failed to open stdin for the command: {:?}",
-                self.process_command
-            )
-        })?;
-
-        stdin.write_all(content.as_bytes())?;
-
-        // Read from stdout
-        let output = child.wait_with_output()?;
-
-        if !output.status.success() {
-            let stdout = String::from_utf8(output.stdout.clone())
-                .unwrap_or_else(|_| format!("{:?}", output.stdout));
-            let stderr = String::from_utf8(output.stderr.clone())
-                .unwrap_or_else(|_| format!("{:?}", output.stderr));
-            Err(anyhow::anyhow!(
-                "Failed to format the content:\n[[STDOUT]]:\n\n{:#?}\n\n[[STDERR]]:\n\n{:#?}",
-                stdout,
-                stderr
-            ))
-        } else {
-            Ok(String::from_utf8(output.stdout)?)
-        }
+        self.commands
+            .iter()
+            .try_fold(content.to_string(), |content, command| {
+                run(&command.process_command(), &content)
+            })
+    }
+
+    /// Formats only the `range` (in bytes) of `content`, and returns the whole content.
+    ///
+    /// Commands with range arguments receive the whole content along with the range,
+    /// while other commands receive only the content within the range.
+    pub fn format_range(&self, content: &str, range: Range<usize>) -> anyhow::Result<String> {
+        let (content, _) = self.commands.iter().try_fold(
+            (content.to_string(), range),
+            |(content, range), command| -> anyhow::Result<_> {
+                // The range is only an estimate after the first command,
+                // which might no longer lie on character boundaries
+                if content.get(range.clone()).is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Unable to run `{}` on the range {:?}, which is not a valid range of the output of the previous formatter",
+                        command.process_command().to_string().trim(),
+                        range
+                    ));
+                }
+                let formatted = match command.range_process_command(&content, &range) {
+                    Some(process_command) => run(&process_command, &content)?,
+                    None => {
+                        let selected = &content[range.clone()];
+                        let formatted = run(&command.process_command(), selected)?;
+                        // Formatters usually end their output with a newline,
+                        // which does not belong to a range that does not end with one
+                        let formatted = if selected.ends_with('\n') {
+                            formatted.as_str()
+                        } else {
+                            formatted.strip_suffix('\n').unwrap_or(&formatted)
+                        };
+                        format!(
+                            "{}{}{}",
+                            &content[..range.start],
+                            formatted,
+                            &content[range.end..]
+                        )
+                    }
+                };
+                // The content after the range is left untouched,
+                // so the range still ends at the same distance from the end of the content
+                let end = formatted
+                    .len()
+                    .saturating_sub(content.len() - range.end)
+                    .max(range.start);
+                Ok((formatted, range.start..end))
+            },
+        )?;
+        Ok(content)
+    }
+}
+
+/// Runs `process_command` with `content` as its stdin, and returns its stdout.
+fn run(process_command: &ProcessCommand, content: &str) -> anyhow::Result<String> {
+    let mut child = process_command.spawn()?;
+
+    let stdin = child.stdin.as_mut().ok_or_else(|| {
+        anyhow::anyhow!(
+            "Failed to open stdin for the command: {:?}",
+            process_command
+        )
+    })?;
+
+    stdin.write_all(content.as_bytes())?;
+
+    // Read from stdout
+    let output = child.wait_with_output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr.clone())
+            .unwrap_or_else(|_| format!("{:?}", output.stderr));
+        Err(anyhow::anyhow!(
+            "`{}` failed with {}:\n\n{}",
+            process_command.to_string().trim(),
+            output.status,
+            stderr.trim()
+        ))
+    } else {
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+#[cfg(test)]
+mod test_formatter {
+    use crate::language::Command;
+
+    use super::*;
+
+    fn formatter(commands: &'static [FormatterCommand]) -> Formatter {
+        Formatter::new(commands.to_vec())
+    }
+
+    #[test]
+    fn commands_are_chained() -> anyhow::Result<()> {
+        let formatter = formatter(&[
+            FormatterCommand {
+                command: Command("tr", &["a", "b"]),
+                range_args: None,
+            },
+            FormatterCommand {
+                command: Command("tr", &["b", "c"]),
+                range_args: None,
+            },
+        ]);
+        assert_eq!(formatter.format("abc")?, "ccc");
+        Ok(())
+    }
+
+    #[test]
+    fn format_range_passes_only_the_range_without_range_args() -> anyhow::Result<()> {
+        let formatter = formatter(&[FormatterCommand {
+            command: Command("tr", &["a", "b"]),
+            range_args: None,
+        }]);
+        assert_eq!(formatter.format_range("aaaa", 1..3)?, "abba");
+        Ok(())
+    }
+
+    #[test]
+    fn format_range_reports_a_range_that_is_not_on_char_boundaries() {
+        let error = formatter(&[
+            // The range that follows this output ends in the middle of `é`
+            FormatterCommand {
+                command: Command("printf", &["aé"]),
+                range_args: Some(&[]),
+            },
+            FormatterCommand {
+                command: Command("tr", &["a", "b"]),
+                range_args: None,
+            },
+        ])
+        .format_range("aaaa", 1..3)
+        .unwrap_err();
+        assert!(error.to_string().contains("not a valid range"));
+    }
+
+    #[test]
+    fn stderr_is_reported() {
+        let error = formatter(&[FormatterCommand {
+            command: Command("sh", &["-c", "echo oops >&2; exit 1"]),
+            range_args: None,
+        }])
+        .format("")
+        .unwrap_err();
+        assert!(error.to_string().contains("oops"));
     }
 }
@@ -42,7 +42,136 @@ pub struct Language {
     pub(crate) lsp_command: Option<LspCommand>,
     pub(crate) tree_sitter_grammar_config: Option<GrammarConfig>,
     pub(crate) highlight_query: Option<&'static str>,
-    pub(crate) formatter_command: Option<Command>,
+    /// A tree-sitter query that captures blocks of other languages embedded in this language
+    /// as `@injection.content`, and the name of their language as `@injection.language`.
+    pub(crate) injection_query: Option<&'static str>,
+    /// Run in sequence on save, each receiving the output of the previous one.
+    pub(crate) formatter_commands: &'static [FormatterCommand],
+    pub(crate) test_runner: Option<TestRunner>,
+    /// The prefix of a line comment, such as `//` for Rust.
+    pub(crate) line_comment_prefix: Option<&'static str>,
+}
+
+/// How the tests of a language are detected and run.
+///
+/// In the arguments of the commands, `{file}` is substituted with the path of the file
+/// relative to the working directory, `{test}` with the name of the test,
+/// and an argument of only `{tests}` is expanded into the names of all tests of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestRunner {
+    /// A tree-sitter query that captures each test as `@test`, and its name as `@name`.
+    ///
+    /// If `names` is `TestNames::RustPath`, it also captures each module as `@module`,
+    /// and its name as `@module.name`.
+    pub(crate) query: &'static str,
+    pub(crate) names: TestNames,
+    /// Runs a single test.
+    pub(crate) test_command: Command,
+    /// Runs all tests of a file.
+    pub(crate) file_command: Command,
+}
+
+/// How the name of a test is passed to the commands of a `TestRunner`,
+/// and found in their output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestNames {
+    /// The name of the test as it is written, which the test runner filters by itself,
+    /// such as `test_add` for pytest.
+    Plain,
+    /// The path of the test in its Rust crate, such as `foo::tests::add`
+    /// for the test `add` in the module `tests` of `src/foo.rs`,
+    /// which is matched exactly, because libtest filters tests by substring.
+    RustPath,
+}
+
+impl TestRunner {
+    pub fn query(&self) -> &'static str {
+        self.query
+    }
+
+    pub fn names(&self) -> TestNames {
+        self.names
+    }
+
+    pub fn test_command(&self, file: &str, test: &str) -> ProcessCommand {
+        Self::substitute(self.test_command.0, self.test_command.1, file, &[test])
+    }
+
+    pub fn file_command(&self, file: &str, tests: &[&str]) -> ProcessCommand {
+        Self::substitute(self.file_command.0, self.file_command.1, file, tests)
+    }
+
+    /// Substitutes the placeholders in `args`, see `TestRunner`.
+    ///
+    /// This is also used for the commands configured in place of the built-in ones.
+    pub fn substitute(program: &str, args: &[&str], file: &str, tests: &[&str]) -> ProcessCommand {
+        let test = tests.first().copied().unwrap_or_default();
+        let args: Vec<String> = args
+            .iter()
+            .flat_map(|arg| {
+                if *arg == "{tests}" {
+                    tests.iter().map(|test| test.to_string()).collect()
+                } else {
+                    vec![arg.replace("{file}", file).replace("{test}", test)]
+                }
+            })
+            .collect();
+        ProcessCommand::new(
+            program,
+            &args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>(),
+        )
+    }
+}
+
+/// A formatter which reads the content from stdin and writes the formatted content to stdout.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatterCommand {
+    pub(crate) command: Command,
+    /// The arguments appended to `command` for formatting only a range of the content,
+    /// in which `{start_line}` and `{end_line}` are substituted with the 1-based lines of the range (inclusive),
+    /// and `{start_byte}` and `{end_byte}` with its byte offsets (exclusive end).
+    ///
+    /// If this is `None`, only the content within the range is passed to the formatter.
+    pub(crate) range_args: Option<&'static [&'static str]>,
+}
+
+impl FormatterCommand {
+    pub(crate) fn process_command(&self) -> ProcessCommand {
+        ProcessCommand::new(self.command.0, self.command.1)
+    }
+
+    /// Returns the command for formatting only the `range` (in bytes) of `content`,
+    /// or `None` if this formatter has no range arguments.
+    pub(crate) fn range_process_command(
+        &self,
+        content: &str,
+        range: &std::ops::Range<usize>,
+    ) -> Option<ProcessCommand> {
+        let line = |byte: usize| content[..byte].matches('\n').count() + 1;
+        let start_line = line(range.start);
+        // A range that ends right after a newline does not include the following line
+        let end_line = if range.end > range.start && content[..range.end].ends_with('\n') {
+            line(range.end) - 1
+        } else {
+            line(range.end)
+        };
+        let args: Vec<String> = self
+            .command
+            .1
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain(self.range_args?.iter().map(|arg| {
+                arg.replace("{start_line}", &start_line.to_string())
+                    .replace("{end_line}", &end_line.to_string())
+                    .replace("{start_byte}", &range.start.to_string())
+                    .replace("{end_byte}", &range.end.to_string())
+            }))
+            .collect();
+        Some(ProcessCommand::new(
+            self.command.0,
+            &args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>(),
+        ))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -66,9 +195,12 @@ impl Language {
             file_names: &[""],
             lsp_language_id: None,
             highlight_query: None,
+            injection_query: None,
             lsp_command: None,
             tree_sitter_grammar_config: None,
-            formatter_command: None,
+            formatter_commands: &[],
+            test_runner: None,
+            line_comment_prefix: None,
         }
     }
 
@@ -143,7 +275,7 @@ impl Language {
     }
 
     pub fn injection_query(&self) -> Option<&'static str> {
-        None
+        self.injection_query
     }
 
     pub fn lsp_process_command(&self) -> Option<ProcessCommand> {
@@ -160,14 +292,20 @@ impl Language {
         self.lsp_language_id
     }
 
-    fn formatter_command(&self) -> Option<ProcessCommand> {
-        self.formatter_command
-            .as_ref()
-            .map(|command| ProcessCommand::new(command.0, command.1))
+    pub fn formatter(&self) -> Option<Formatter> {
+        if self.formatter_commands.is_empty() {
+            None
+        } else {
+            Some(Formatter::new(self.formatter_commands.to_vec()))
+        }
     }
 
-    pub fn formatter(&self) -> Option<Formatter> {
-        self.formatter_command().map(Formatter::from)
+    pub fn line_comment_prefix(&self) -> Option<&'static str> {
+        self.line_comment_prefix
+    }
+
+    pub fn test_runner(&self) -> Option<TestRunner> {
+        self.test_runner.clone()
     }
 }
 
@@ -184,6 +322,17 @@ pub fn from_extension(extension: &str) -> Option<Language> {
         .map(|language| (*language).clone())
 }
 
+/// The info string of a Markdown fenced code block is usually either
+/// a file extension (e.g. `rs`) or a language ID (e.g. `rust`).
+pub fn from_code_fence_info(info: &str) -> Option<Language> {
+    from_extension(info).or_else(|| {
+        LANGUAGES
+            .iter()
+            .find(|language| language.lsp_language_id.is_some_and(|id| id.0 == info))
+            .map(|language| (*language).clone())
+    })
+}
+
 pub(crate) fn from_filename(path: &CanonicalizedPath) -> Option<Language> {
     let file_name = path.file_name()?;
     LANGUAGES
@@ -213,4 +362,41 @@ mod test_language {
         run_test_case("justfile", "just")?;
         Ok(())
     }
+
+    #[test]
+    fn test_from_code_fence_info() {
+        let grammar_id = |info| {
+            from_code_fence_info(info).and_then(|language| language.tree_sitter_grammar_id())
+        };
+        assert_eq!(grammar_id("rs"), Some("rust".to_string()));
+        assert_eq!(grammar_id("rust"), Some("rust".to_string()));
+        assert_eq!(grammar_id("unknown"), None);
+    }
+
+    #[test]
+    fn test_formatter_range_process_command() {
+        let command = FormatterCommand {
+            command: Command("fmt", &["-"]),
+            range_args: Some(&[
+                "--lines={start_line}-{end_line}",
+                "--bytes={start_byte}-{end_byte}",
+            ]),
+        };
+        let content = "a\nb\nc\n";
+        let args = |range| {
+            command
+                .range_process_command(content, &range)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(args(2..3), "fmt - --lines=2-2 --bytes=2-3");
+        // The line after the trailing newline of the range is excluded
+        assert_eq!(args(0..4), "fmt - --lines=1-2 --bytes=0-4");
+        assert!(FormatterCommand {
+            command: Command("fmt", &[]),
+            range_args: None,
+        }
+        .range_process_command(content, &(0..1))
+        .is_none());
+    }
 }
@@ -1,4 +1,7 @@
-use super::language::{Command, GrammarConfig, Language, LanguageId, LspCommand};
+use super::language::{
+    Command, FormatterCommand, GrammarConfig, Language, LanguageId, LspCommand, TestNames,
+    TestRunner,
+};
 
 pub const LANGUAGES: &[&Language] = &[
     &common_lisp(),
@@ -23,6 +26,7 @@ pub const LANGUAGES: &[&Language] = &[
 
 const fn common_lisp() -> Language {
     Language {
+        line_comment_prefix: Some(";"),
         file_names: &[],
         lsp_language_id: None,
         lsp_command: None,
@@ -34,17 +38,22 @@ const fn common_lisp() -> Language {
             subpath: None,
         }),
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
     }
 }
 const fn csv() -> Language {
     Language {
+        line_comment_prefix: None,
         file_names: &[],
         extensions: &["csv"],
         lsp_language_id: None,
         lsp_command: None,
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "csv",
             url: "https://github.com/arnau/tree-sitter-csv",
@@ -56,12 +65,18 @@ const fn csv() -> Language {
 
 const fn css() -> Language {
     Language {
+        line_comment_prefix: None,
         file_names: &[],
         extensions: &["css"],
         lsp_language_id: None,
         lsp_command: None,
         highlight_query: None,
-        formatter_command: Some(Command("prettierd", &[".css"])),
+        injection_query: None,
+        formatter_commands: &[FormatterCommand {
+            command: Command("prettierd", &[".css"]),
+            range_args: None,
+        }],
+        test_runner: None,
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "css",
             url: "https://github.com/tree-sitter/tree-sitter-css",
@@ -73,12 +88,15 @@ const fn css() -> Language {
 
 const fn dockerfile() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         file_names: &["Dockerfile"],
         extensions: &[],
         lsp_language_id: None,
         lsp_command: None,
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "dockerfile",
             url: "https://github.com/camdencheek/tree-sitter-dockerfile",
@@ -90,6 +108,7 @@ const fn dockerfile() -> Language {
 
 const fn graphql() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         lsp_language_id: Some(LanguageId::new("graphql")),
         extensions: &["graphql", "gql"],
         tree_sitter_grammar_config: Some(GrammarConfig {
@@ -98,7 +117,10 @@ const fn graphql() -> Language {
             commit: "master",
             subpath: None,
         }),
-        formatter_command: Some(Command("prettierd", &[".graphql"])),
+        formatter_commands: &[FormatterCommand {
+            command: Command("prettierd", &[".graphql"]),
+            range_args: None,
+        }],
         lsp_command: Some(LspCommand {
             command: Command("graphql-lsp", &["server", "-m", "stream"]),
             initialization_options: Some(r#"{ "graphql-config.load.legacy": true }"#),
@@ -107,8 +129,22 @@ const fn graphql() -> Language {
     }
 }
 
+/// Shared by JavaScript and TypeScript, which have the same syntax for calls.
+const JEST: TestRunner = TestRunner {
+    query: r#"(
+        (call_expression
+            function: (identifier) @function
+            arguments: (arguments . [(string) (template_string)] @name)) @test
+        (#match? @function "^(it|test)$")
+    )"#,
+    names: TestNames::Plain,
+    test_command: Command("npx", &["jest", "--verbose", "{file}", "-t", "{test}"]),
+    file_command: Command("npx", &["jest", "--verbose", "{file}"]),
+};
+
 const fn javascript(jsx: bool) -> Language {
     Language {
+        line_comment_prefix: Some("//"),
         lsp_language_id: Some(LanguageId::new(if jsx {
             "javascriptreact"
         } else {
@@ -125,13 +161,25 @@ const fn javascript(jsx: bool) -> Language {
             commit: "master",
             subpath: None,
         }),
-        formatter_command: Some(Command("prettierd", if jsx { &[".jsx"] } else { &[".js"] })),
+        formatter_commands: if jsx {
+            &[FormatterCommand {
+                command: Command("prettierd", &[".jsx"]),
+                range_args: None,
+            }]
+        } else {
+            &[FormatterCommand {
+                command: Command("prettierd", &[".js"]),
+                range_args: None,
+            }]
+        },
+        test_runner: Some(JEST),
         ..Language::new()
     }
 }
 
 const fn json() -> Language {
     Language {
+        line_comment_prefix: None,
         file_names: &[],
         extensions: &["json"],
         lsp_language_id: None,
@@ -143,12 +191,18 @@ const fn json() -> Language {
             subpath: None,
         }),
         highlight_query: None,
-        formatter_command: Some(Command("prettierd", &[".json"])),
+        injection_query: None,
+        formatter_commands: &[FormatterCommand {
+            command: Command("prettierd", &[".json"]),
+            range_args: None,
+        }],
+        test_runner: None,
     }
 }
 
 const fn just() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         file_names: &["justfile"],
         extensions: &[],
         lsp_language_id: None,
@@ -160,12 +214,15 @@ const fn just() -> Language {
             subpath: None,
         }),
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
     }
 }
 
 const fn markdown() -> Language {
     Language {
+        line_comment_prefix: None,
         lsp_language_id: Some(LanguageId::new("markdown")),
         extensions: &["md"],
         lsp_command: Some(LspCommand {
@@ -178,13 +235,22 @@ const fn markdown() -> Language {
             commit: "split_parser",
             subpath: Some("tree-sitter-markdown"),
         }),
-        formatter_command: Some(Command("prettierd", &[".md"])),
+        formatter_commands: &[FormatterCommand {
+            command: Command("prettierd", &[".md"]),
+            range_args: None,
+        }],
+        injection_query: Some(
+            r#"(fenced_code_block
+                (info_string (language) @injection.language)
+                (code_fence_content) @injection.content)"#,
+        ),
         ..Language::new()
     }
 }
 
 const fn python() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         lsp_language_id: Some(LanguageId::new("python")),
         extensions: &["py"],
         lsp_command: Some(LspCommand {
@@ -197,13 +263,44 @@ const fn python() -> Language {
             commit: "master",
             subpath: None,
         }),
-        formatter_command: Some(Command("ruff", &["format", "--stdin-filename", ".py"])),
+        formatter_commands: &[
+            // Sort the imports first, which `ruff format` does not do
+            FormatterCommand {
+                command: Command(
+                    "ruff",
+                    &[
+                        "check",
+                        "--select=I",
+                        "--fix",
+                        "--exit-zero",
+                        "--quiet",
+                        "--stdin-filename",
+                        ".py",
+                    ],
+                ),
+                range_args: None,
+            },
+            FormatterCommand {
+                command: Command("ruff", &["format", "--stdin-filename", ".py"]),
+                range_args: Some(&["--range={start_line}-{end_line}"]),
+            },
+        ],
+        test_runner: Some(TestRunner {
+            query: r#"(
+                (function_definition name: (identifier) @name) @test
+                (#match? @name "^test")
+            )"#,
+            names: TestNames::Plain,
+            test_command: Command("python", &["-m", "pytest", "-v", "{file}", "-k", "{test}"]),
+            file_command: Command("python", &["-m", "pytest", "-v", "{file}"]),
+        }),
         ..Language::new()
     }
 }
 
 const fn rust() -> Language {
     Language {
+        line_comment_prefix: Some("//"),
         file_names: &[],
         lsp_language_id: Some(LanguageId::new("rust")),
         extensions: &["rs"],
@@ -212,18 +309,37 @@ const fn rust() -> Language {
             ..LspCommand::default()
         }),
         highlight_query: None,
+        injection_query: None,
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "rust",
             url: "https://github.com/tree-sitter/tree-sitter-rust",
             commit: "master",
             subpath: None,
         }),
-        formatter_command: Some(Command("rustfmt", &["--edition=2021"])),
+        formatter_commands: &[FormatterCommand {
+            command: Command("rustfmt", &["--edition=2021"]),
+            range_args: None,
+        }],
+        test_runner: Some(TestRunner {
+            query: r#"(
+                (attribute_item (attribute [(identifier) (scoped_identifier)] @attribute))
+                .
+                (attribute_item)*
+                .
+                (function_item name: (identifier) @name) @test
+                (#match? @attribute "(^|::)test$")
+            )
+            (mod_item name: (identifier) @module.name) @module"#,
+            names: TestNames::RustPath,
+            test_command: Command("cargo", &["test", "--", "--exact", "{test}"]),
+            file_command: Command("cargo", &["test", "--", "--exact", "{tests}"]),
+        }),
     }
 }
 
 const fn sql() -> Language {
     Language {
+        line_comment_prefix: Some("--"),
         lsp_language_id: Some(LanguageId::new("sql")),
         extensions: &["sql"],
         lsp_command: None,
@@ -233,13 +349,17 @@ const fn sql() -> Language {
             commit: "25f94f998de79bae9df28add9782f9ea6ea0e2b8",
             subpath: None,
         }),
-        formatter_command: Some(Command("sql-formatter", &["--language", "postgresql"])),
+        formatter_commands: &[FormatterCommand {
+            command: Command("sql-formatter", &["--language", "postgresql"]),
+            range_args: None,
+        }],
         ..Language::new()
     }
 }
 
 const fn toml() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         file_names: &[],
         extensions: &["toml"],
         lsp_language_id: None,
@@ -251,12 +371,15 @@ const fn toml() -> Language {
             subpath: None,
         }),
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
     }
 }
 
 const fn tree_sitter_query() -> Language {
     Language {
+        line_comment_prefix: Some(";"),
         file_names: &[],
         extensions: &["scm"],
         lsp_language_id: None,
@@ -268,7 +391,9 @@ const fn tree_sitter_query() -> Language {
             subpath: None,
         }),
         highlight_query: None,
-        formatter_command: None,
+        injection_query: None,
+        formatter_commands: &[],
+        test_runner: None,
     }
 }
 
@@ -282,6 +407,7 @@ const fn choice<T: Copy>(condition: bool, left: T, right: T) -> T {
 
 const fn typescript(tsx: bool) -> Language {
     Language {
+        line_comment_prefix: Some("//"),
         lsp_language_id: Some(LanguageId::new(choice(
             tsx,
             "typescriptreact",
@@ -298,13 +424,25 @@ const fn typescript(tsx: bool) -> Language {
             commit: "master",
             subpath: Some(choice(tsx, "tsx", "typescript")),
         }),
-        formatter_command: Some(Command("prettierd", choice(tsx, &[".tsx"], &[".ts"]))),
+        formatter_commands: choice(
+            tsx,
+            &[FormatterCommand {
+                command: Command("prettierd", &[".tsx"]),
+                range_args: None,
+            }],
+            &[FormatterCommand {
+                command: Command("prettierd", &[".ts"]),
+                range_args: None,
+            }],
+        ),
+        test_runner: Some(JEST),
         ..Language::new()
     }
 }
 
 const fn yaml() -> Language {
     Language {
+        line_comment_prefix: Some("#"),
         file_names: &[],
         lsp_language_id: Some(LanguageId::new("yaml")),
         extensions: &["yaml", "yml"],
@@ -315,7 +453,9 @@ const fn yaml() -> Language {
             subpath: None,
             commit: "master",
         }),
-        formatter_command: None,
+        formatter_commands: &[],
+        test_runner: None,
         highlight_query: None,
+        injection_query: None,
     }
 }
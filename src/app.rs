@@ -1,25 +1,35 @@
 use crate::{
     buffer::Buffer,
     clipboard::CopiedTexts,
+    color_literal::{self, ColorLiteral},
     components::{
-        component::{Component, ComponentId, GetGridResult},
+        component::{Component, ComponentId, Cursor, GetGridResult},
         dropdown::{DropdownItem, DropdownRender},
-        editor::{DispatchEditor, Editor, Movement},
+        editor::{DispatchEditor, Editor, JumpLabelStrategy, Movement},
+        editor_keymap_printer::KeymapCheatSheet,
         keymap_legend::{
             Keymap, KeymapLegendBody, KeymapLegendConfig, KeymapLegendSection, Keymaps,
         },
+        peek::Peek,
         prompt::{Prompt, PromptConfig, PromptHistoryKey},
         suggestive_editor::{
-            DispatchSuggestiveEditor, Info, SuggestiveEditor, SuggestiveEditorFilter,
+            CompletionSource, DispatchSuggestiveEditor, Info, InlineSuggestion,
+            InlineSuggestionRequest, SuggestiveEditor, SuggestiveEditorFilter,
         },
     },
-    context::{Context, GlobalMode, LocalSearchConfigMode, QuickfixListSource, Search},
-    frontend::Frontend,
+    context::{
+        Context, GlobalMode, LocalSearchConfigMode, QuickfixListSource, QuickfixListState, Search,
+        SearchOption,
+    },
+    error_format::ErrorFormat,
+    frontend::{Frontend, InputGate},
     git,
     grid::{Grid, LineUpdate},
-    history::History,
+    history::{ChangeList, History},
+    keyboard_layout::KeyboardLayout,
     layout::Layout,
     list::{self, grep::RegexConfig, WalkBuilderConfig},
+    local_completion::LocalCompletion,
     lsp::{
         completion::CompletionItem,
         goto_definition_response::GotoDefinitionResponse,
@@ -28,26 +38,43 @@ use crate::{
         symbols::Symbols,
         workspace_edit::WorkspaceEdit,
     },
+    persistence::Persistence,
     position::Position,
-    quickfix_list::{Location, QuickfixList, QuickfixListItem, QuickfixListType},
-    screen::{Screen, Window},
-    selection::{Filter, FilterKind, FilterMechanism, FilterTarget, SelectionMode},
+    quickfix_list::{
+        DiagnosticSeverityRange, Location, QuickfixList, QuickfixListItem, QuickfixListPreview,
+        QuickfixListType,
+    },
+    screen::{RenderMetrics, Screen, Window},
+    selection::{
+        CharIndex, Filter, FilterKind, FilterMechanism, FilterTarget, SelectionMode, SelectionSet,
+    },
+    startup_profile::StartupProfile,
     syntax_highlight::{HighlighedSpans, SyntaxHighlightRequest},
+    test_runner::{TestRun, TestStatus},
+    transformation::Transformation,
+    tutor::{Tutor, LESSONS},
     ui_tree::{ComponentKind, KindedComponent},
+    user_config::TestCommands,
 };
 use event::event::Event;
+use indexmap::IndexMap;
 use itertools::Itertools;
+use my_proc_macros::key;
 use name_variant::NamedVariant;
+use serde::{Deserialize, Serialize};
 use shared::{canonicalized_path::CanonicalizedPath, language::Language};
 use std::{
     any::TypeId,
     cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
     sync::{
         mpsc::{Receiver, Sender},
         Arc, Mutex,
     },
+    time::{Duration, Instant, SystemTime},
 };
 use DispatchEditor::*;
 
@@ -70,14 +97,88 @@ pub(crate) struct App<T: Frontend> {
     layout: Layout,
 
     frontend: Arc<Mutex<T>>,
+    /// Paused while a child process takes over the terminal, see `App::privileged_save`.
+    input_gate: Arc<InputGate>,
 
     syntax_highlight_request_sender: Option<Sender<SyntaxHighlightRequest>>,
+    /// The latest content of each changed component, which is only highlighted before the next render,
+    /// so that a burst of edits (e.g. a large paste typed character by character) is highlighted once.
+    pending_syntax_highlight_requests: IndexMap<ComponentId, (Language, String)>,
 
     /// Used for navigating between opened files
     file_path_history: History<CanonicalizedPath>,
+    /// The locations of the latest edits across all files.
+    change_list: ChangeList,
+
+    completion_source: Option<Box<dyn CompletionSource>>,
+    /// The id of the latest inline suggestion request,
+    /// suggestions of other ids are outdated.
+    inline_suggestion_request_id: usize,
+    tutor: Option<Tutor>,
+    pending_workspace_edit: Option<PendingWorkspaceEdit>,
+    linked_rename: Option<LinkedRename>,
+    pending_linked_rename: Option<PendingLinkedRename>,
+    /// Set by `Dispatch::ApplyPreferredCodeAction`,
+    /// so that the received code actions are applied instead of listed.
+    apply_preferred_code_action: bool,
+    render_metrics: RenderMetrics,
+    /// Show the render metrics in the global title.
+    show_fps: bool,
+    /// Completed by the first render, and printed when the editor exits.
+    startup_profile: Option<StartupProfile>,
+    /// Save the modified buffers when the terminal loses focus.
+    autosave_on_focus_lost: bool,
+    /// Refreshed in the background by `refresh_git_ahead_behind`.
+    git_ahead_behind: Option<git::AheadBehind>,
+    /// Set the title of the terminal to `terminal_title`.
+    terminal_title_enabled: bool,
+    /// The number of most recently viewed buffers that are kept fully loaded,
+    /// see `Layout::evict_background_buffers`.
+    loaded_buffers_limit: usize,
+    /// The terminal title that was last set, so that it is only set again when it changes.
+    last_terminal_title: Option<String>,
+    /// The cursor that was last rendered,
+    /// so that the cursor is not shown again when nothing on the screen changed.
+    last_rendered_cursor: Option<Cursor>,
+    /// The command for opening URLs, such as the links of hover documentation.
+    url_opener: String,
+    /// The command which writes its standard input into the file of the path appended to it
+    /// with elevated privileges, used when saving fails with a permission error.
+    elevation_command: String,
+    /// The commands replacing the built-in test commands, keyed by the language ID.
+    test_commands: HashMap<String, TestCommands>,
+    /// The number of scratch buffers created, for naming new scratch buffers.
+    scratch_buffer_count: usize,
+    /// The directory of the notes opened by `Dispatch::OpenDailyNote`.
+    notes_directory: PathBuf,
+    /// The formats for parsing diagnostics from the output of build commands, tried in order.
+    error_formats: Vec<ErrorFormat>,
+    /// The files having diagnostics of the latest build, which are cleared on the next build.
+    build_diagnostics_paths: Vec<CanonicalizedPath>,
+    /// The characters labelling the panes while jumping across panes,
+    /// see `Dispatch::ShowJumpsAcrossPanes`.
+    pane_jumps: Vec<(char, ComponentId)>,
 }
 
 const GLOBAL_TITLE_BAR_HEIGHT: u16 = 1;
+/// Caps the redraw frequency at around 120 frames per second.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(8);
+const DEFAULT_LOADED_BUFFERS_LIMIT: usize = 20;
+/// The number of latest edits that can still be undone in an evicted buffer.
+const EVICTED_UNDO_HISTORY_LIMIT: usize = 100;
+/// Listing every commit of a large repository takes too long.
+const COMMIT_LOG_LIMIT: usize = 1000;
+
+fn default_url_opener() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(target_os = "windows") {
+        "explorer"
+    } else {
+        "xdg-open"
+    }
+}
+
 impl<T: Frontend> App<T> {
     #[cfg(test)]
     pub(crate) fn new(
@@ -100,8 +201,9 @@ impl<T: Frontend> App<T> {
         receiver: Receiver<AppMessage>,
     ) -> anyhow::Result<App<T>> {
         let dimension = frontend.lock().unwrap().get_terminal_dimension()?;
+        let context = Context::new(working_directory.clone());
         let app = App {
-            context: Context::new(working_directory.clone()),
+            context,
             receiver,
             lsp_manager: LspManager::new(sender.clone(), working_directory.clone()),
             enable_lsp: true,
@@ -113,9 +215,36 @@ impl<T: Frontend> App<T> {
             working_directory,
             frontend,
             syntax_highlight_request_sender: None,
+            pending_syntax_highlight_requests: Default::default(),
             global_title: None,
 
             file_path_history: History::new(),
+            change_list: ChangeList::default(),
+            completion_source: None,
+            inline_suggestion_request_id: 0,
+            tutor: None,
+            pending_workspace_edit: None,
+            linked_rename: None,
+            pending_linked_rename: None,
+            apply_preferred_code_action: false,
+            render_metrics: RenderMetrics::default(),
+            show_fps: false,
+            startup_profile: None,
+            autosave_on_focus_lost: false,
+            git_ahead_behind: None,
+            terminal_title_enabled: true,
+            loaded_buffers_limit: DEFAULT_LOADED_BUFFERS_LIMIT,
+            last_terminal_title: None,
+            last_rendered_cursor: None,
+            url_opener: default_url_opener().to_string(),
+            elevation_command: "sudo tee".to_string(),
+            test_commands: HashMap::new(),
+            input_gate: Arc::default(),
+            scratch_buffer_count: 0,
+            notes_directory: grammar::config_dir().join("notes"),
+            error_formats: ErrorFormat::builtins(),
+            build_diagnostics_paths: Vec::new(),
+            pane_jumps: Vec::new(),
         };
         Ok(app)
     }
@@ -143,45 +272,110 @@ impl<T: Frontend> App<T> {
             self.open_file(&entry_path, OpenFileOption::Focus)?;
         }
 
+        self.refresh_git_ahead_behind();
         self.render()?;
+        if let Some(profile) = self.startup_profile.as_mut() {
+            profile.record("first render");
+            log::info!("Startup profile:\n{}", profile.display());
+        }
 
         while let Ok(message) = self.receiver.recv() {
-            match message {
-                AppMessage::Event(event) => self.handle_event(event),
-                AppMessage::LspNotification(notification) => {
-                    self.handle_lsp_notification(notification).map(|_| false)
-                }
-                AppMessage::QuitAll => {
-                    self.quit()?;
-                    Ok(true)
-                }
-                AppMessage::SyntaxHighlightResponse {
-                    component_id,
-                    highlighted_spans,
-                } => self
-                    .update_highlighted_spans(component_id, highlighted_spans)
-                    .map(|_| false),
-            }
-            .unwrap_or_else(|e| {
-                self.show_global_info(Info::new("ERROR".to_string(), e.to_string()));
-                false
-            });
-
-            if self.should_quit() {
+            if self.handle_message_and_render(message)? {
                 break;
             }
-
-            self.render()?;
         }
 
         self.quit()
     }
 
+    /// Handles `message` and the messages that arrive before the next frame is due,
+    /// then renders, so that a burst of messages (e.g. key repeats or syntax highlight responses)
+    /// only causes one redraw.
+    ///
+    /// Stops handling messages once the next frame is due, even if more messages are queued,
+    /// so that a steady stream of messages cannot hold back rendering.
+    ///
+    /// Returns true if the app should quit.
+    pub(crate) fn handle_message_and_render(
+        &mut self,
+        message: AppMessage,
+    ) -> anyhow::Result<bool> {
+        if self.handle_message(message)? {
+            return Ok(true);
+        }
+        let next_frame = self
+            .render_metrics
+            .last_frame()
+            .map(|last_frame| last_frame + MIN_FRAME_INTERVAL)
+            .unwrap_or_else(Instant::now);
+        loop {
+            let now = Instant::now();
+            if now >= next_frame {
+                break;
+            }
+            match self.receiver.recv_timeout(next_frame - now) {
+                Ok(message) => {
+                    if self.handle_message(message)? {
+                        return Ok(true);
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        self.render()?;
+        Ok(false)
+    }
+
+    /// Returns true if the app should quit.
+    fn handle_message(&mut self, message: AppMessage) -> anyhow::Result<bool> {
+        match message {
+            AppMessage::Event(event) => self.handle_event(event),
+            AppMessage::LspNotification(notification) => {
+                self.handle_lsp_notification(notification).map(|_| false)
+            }
+            AppMessage::QuitAll => {
+                self.quit()?;
+                Ok(true)
+            }
+            AppMessage::SyntaxHighlightResponse {
+                component_id,
+                highlighted_spans,
+            } => self
+                .update_highlighted_spans(component_id, highlighted_spans)
+                .map(|_| false),
+            AppMessage::InlineSuggestion(suggestion) => {
+                self.handle_inline_suggestion(suggestion).map(|_| false)
+            }
+            AppMessage::TestRun(test_run) => self
+                .handle_dispatch(Dispatch::ReceiveTestRun(test_run))
+                .map(|_| false),
+            AppMessage::BuildOutput(output) => self
+                .handle_dispatch(Dispatch::ReceiveBuildOutput(output))
+                .map(|_| false),
+            AppMessage::DirectoryEntries { directory, entries } => self
+                .handle_dispatch(Dispatch::ReceiveDirectoryEntries { directory, entries })
+                .map(|_| false),
+            AppMessage::GitAheadBehind(ahead_behind) => {
+                self.git_ahead_behind = ahead_behind;
+                Ok(false)
+            }
+        }
+        .unwrap_or_else(|e| {
+            self.show_global_info(Info::new("ERROR".to_string(), e.to_string()));
+            false
+        });
+
+        Ok(self.should_quit())
+    }
+
     pub(crate) fn quit(&mut self) -> anyhow::Result<()> {
         let mut frontend = self.frontend.lock().unwrap();
         frontend.leave_alternate_screen()?;
         frontend.disable_raw_mode()?;
         // self.lsp_manager.shutdown();
+        if let Some(profile) = &self.startup_profile {
+            eprintln!("{}", profile.display());
+        }
 
         std::process::exit(0);
     }
@@ -192,6 +386,17 @@ impl<T: Frontend> App<T> {
 
     /// Returns true if the app should quit.
     fn handle_event(&mut self, event: Event) -> anyhow::Result<bool> {
+        if let Event::Key(key_event) = &event {
+            if self.handle_linked_rename_key(key_event)? {
+                return Ok(false);
+            }
+            if self.handle_workspace_edit_preview_key(key_event)? {
+                return Ok(false);
+            }
+            if self.handle_pane_jump_key(key_event) {
+                return Ok(false);
+            }
+        }
         // Pass event to focused window
         let component = self.current_component();
         self.context
@@ -203,12 +408,26 @@ impl<T: Frontend> App<T> {
                     width: columns,
                 });
             }
+            Event::FocusGained => {
+                // Commits might have been made or fetched elsewhere
+                self.refresh_git_ahead_behind();
+                self.reload_externally_changed_buffers()
+                    .unwrap_or_else(|e| {
+                        self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
+                    })
+            }
+            Event::FocusLost if self.autosave_on_focus_lost => {
+                self.save_modified_buffers().unwrap_or_else(|e| {
+                    self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
+                })
+            }
             event => {
                 let dispatches = component.borrow_mut().handle_event(&self.context, event);
                 self.handle_dispatches_result(dispatches)
                     .unwrap_or_else(|e| {
                         self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
                     });
+                self.update_tutor();
             }
         }
 
@@ -220,15 +439,58 @@ impl<T: Frontend> App<T> {
         self.layout.components().is_empty()
     }
 
-    fn render(&mut self) -> Result<(), anyhow::Error> {
+    pub(crate) fn render(&mut self) -> Result<(), anyhow::Error> {
+        self.evict_background_buffers()?;
+        self.flush_syntax_highlight_requests()?;
         let screen = self.get_screen()?;
         self.render_screen(screen)?;
+        self.update_terminal_title()?;
+        Ok(())
+    }
+
+    fn update_terminal_title(&mut self) -> anyhow::Result<()> {
+        if !self.terminal_title_enabled {
+            return Ok(());
+        }
+        let title = self.terminal_title();
+        if self.last_terminal_title.as_ref() != Some(&title) {
+            self.frontend.lock().unwrap().set_title(&title)?;
+            self.last_terminal_title = Some(title);
+        }
         Ok(())
     }
 
+    /// For example, `main.rs — ki (modified)`, where `ki` is the name of the working directory.
+    pub(crate) fn terminal_title(&self) -> String {
+        let file_name = |path: &CanonicalizedPath| {
+            path.to_path_buf()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display_absolute())
+        };
+        let workspace = file_name(&self.working_directory);
+        let component = self.current_component();
+        let component = component.borrow();
+        let buffer = component.editor().buffer();
+        match buffer.path() {
+            Some(path) => format!(
+                "{} — {}{}",
+                file_name(&path),
+                workspace,
+                if buffer.is_modified() {
+                    " (modified)"
+                } else {
+                    ""
+                }
+            ),
+            None => workspace,
+        }
+    }
+
     pub(crate) fn get_screen(&mut self) -> Result<Screen, anyhow::Error> {
         // Recalculate layout before each render
         self.layout.recalculate_layout();
+        self.layout.refresh_outline()?;
 
         // Generate layout
         let dimension = self.layout.terminal_dimension();
@@ -286,17 +548,32 @@ impl<T: Frontend> App<T> {
                 title.clone()
             } else {
                 let branch = if let Some(current_branch) = self.current_branch() {
-                    format!(" ({}) ", current_branch,)
+                    let ahead_behind = self
+                        .git_ahead_behind
+                        .map(|ahead_behind| ahead_behind.display())
+                        .filter(|display| !display.is_empty())
+                        .map(|display| format!(" {display}"))
+                        .unwrap_or_default();
+                    format!(" ({}{}) ", current_branch, ahead_behind)
                 } else {
                     " ".to_string()
                 };
+                let find_match = self.find_match_summary();
+                let diagnostics = self.diagnostics_summary();
                 format!(
-                    "{}{}{}",
+                    "{}{}{}{}{}",
                     self.working_directory.display_absolute(),
                     branch,
-                    mode
+                    mode,
+                    find_match,
+                    diagnostics
                 )
             };
+            let title = if self.show_fps {
+                format!("{} [{}]", title, self.render_metrics.display())
+            } else {
+                title
+            };
 
             let grid = Grid::new(Dimension {
                 height: 1,
@@ -330,6 +607,48 @@ impl<T: Frontend> App<T> {
         Ok(screen)
     }
 
+    /// The index of the current match while in the Find selection mode, for example ` match 12/87`.
+    fn find_match_summary(&self) -> String {
+        self.current_component()
+            .borrow()
+            .editor()
+            .find_match_index()
+            .map(|(index, count)| {
+                let index = index.map_or("-".to_string(), |index| index.to_string());
+                format!(" match {}/{}", index, count)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The numbers of errors and warnings of the current buffer, for example ` E2 W1`.
+    fn diagnostics_summary(&self) -> String {
+        use lsp_types::DiagnosticSeverity;
+        let diagnostics = self
+            .current_component()
+            .borrow()
+            .editor()
+            .buffer()
+            .diagnostics();
+        let count = |severity: DiagnosticSeverity| {
+            diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Some(severity))
+                .count()
+        };
+        [
+            ("E", count(DiagnosticSeverity::ERROR)),
+            ("W", count(DiagnosticSeverity::WARNING)),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(label, count)| format!(" {label}{count}"))
+        .collect()
+    }
+
+    fn refresh_git_ahead_behind(&self) {
+        git::refresh_ahead_behind(self.working_directory.clone(), self.sender.clone())
+    }
+
     fn current_branch(&self) -> Option<String> {
         // Open the repository
         let repo = git2::Repository::open(self.working_directory.display_absolute()).ok()?;
@@ -341,13 +660,19 @@ impl<T: Frontend> App<T> {
     }
 
     fn render_screen(&mut self, screen: Screen) -> Result<(), anyhow::Error> {
-        let mut frontend = self.frontend.lock().unwrap();
-        frontend.hide_cursor()?;
+        let started_at = Instant::now();
         let cursor = screen.cursor();
-        frontend.render_screen(screen)?;
-        if let Some(cursor) = cursor {
-            frontend.show_cursor(&cursor)?;
+        let mut frontend = self.frontend.lock().unwrap();
+        let repainted_cells = frontend.render_screen(screen)?;
+        if repainted_cells == 0 && cursor == self.last_rendered_cursor {
+            return Ok(());
         }
+        match &cursor {
+            Some(cursor) => frontend.show_cursor(cursor)?,
+            None => frontend.hide_cursor()?,
+        }
+        self.render_metrics.record(started_at, repainted_cells);
+        self.last_rendered_cursor = cursor;
 
         Ok(())
     }
@@ -389,7 +714,10 @@ impl<T: Frontend> App<T> {
             Dispatch::CloseCurrentWindowAndFocusParent => {
                 self.close_current_window_and_focus_parent();
             }
-            Dispatch::OpenSearchPrompt { scope } => self.open_search_prompt(scope)?,
+            Dispatch::OpenSearchPrompt {
+                scope,
+                current_line,
+            } => self.open_search_prompt(scope, current_line)?,
             Dispatch::OpenFile(path) => {
                 self.open_file(&path, OpenFileOption::Focus)?;
             }
@@ -397,11 +725,17 @@ impl<T: Frontend> App<T> {
             Dispatch::OpenFileFromPathBuf(path) => {
                 self.open_file(&path.try_into()?, OpenFileOption::Focus)?;
             }
+            Dispatch::OpenFileInBackground(path) => {
+                self.open_file(&path.try_into()?, OpenFileOption::Background)?;
+            }
+            Dispatch::CloseFile(path) => self.close_file(&path),
 
             Dispatch::OpenFilePicker(kind) => {
                 self.open_file_picker(kind)?;
             }
             Dispatch::RequestCompletion => {
+                self.request_local_completion()?;
+                self.request_inline_suggestion()?;
                 if let Some(params) = self.get_request_params() {
                     self.lsp_manager.send_message(
                         params.path.clone(),
@@ -443,12 +777,37 @@ impl<T: Frontend> App<T> {
                 }
             }
             Dispatch::RequestHover => {
+                // Requesting hover while the info is shown focuses it, so that it can be scrolled
+                if self.layout.focus_editor_info() {
+                    return Ok(());
+                }
                 if let Some(params) = self.get_request_params() {
                     let params = params.set_description("Hover");
                     self.lsp_manager
                         .send_message(params.path.clone(), FromEditor::TextDocumentHover(params))?;
                 }
             }
+            Dispatch::PeekDefinitions => {
+                if let Some(params) = self.get_request_params() {
+                    let params = params.set_description("Peek definitions").set_peek();
+                    self.lsp_manager.send_message(
+                        params.path.clone(),
+                        FromEditor::TextDocumentDefinition(params),
+                    )?;
+                }
+            }
+            Dispatch::PeekReferences => {
+                if let Some(params) = self.get_request_params() {
+                    let params = params.set_description("Peek references").set_peek();
+                    self.lsp_manager.send_message(
+                        params.path.clone(),
+                        FromEditor::TextDocumentReferences {
+                            params,
+                            include_declaration: false,
+                        },
+                    )?;
+                }
+            }
             Dispatch::RequestDefinitions(scope) => {
                 if let Some(params) = self.get_request_params() {
                     let params = params.set_kind(Some(scope)).set_description("Definitions");
@@ -515,16 +874,14 @@ impl<T: Frontend> App<T> {
                 }
             }
             Dispatch::RequestCodeAction { diagnostics } => {
-                if let Some(params) = self.get_request_params() {
-                    self.lsp_manager.send_message(
-                        params.path.clone(),
-                        FromEditor::TextDocumentCodeAction {
-                            params,
-                            diagnostics,
-                        },
-                    )?;
-                }
+                self.apply_preferred_code_action = false;
+                self.request_code_action(diagnostics)?
             }
+            Dispatch::ApplyPreferredCodeAction { diagnostics } => {
+                self.apply_preferred_code_action = true;
+                self.request_code_action(diagnostics)?
+            }
+            Dispatch::ApplyCodeAction(code_action) => self.apply_code_action(code_action)?,
             Dispatch::RequestSignatureHelp => {
                 if let Some(params) = self.get_request_params() {
                     self.lsp_manager.send_message(
@@ -533,6 +890,9 @@ impl<T: Frontend> App<T> {
                     )?;
                 }
             }
+            Dispatch::OpenEditColorPrompt(color_literal) => {
+                self.open_edit_color_prompt(color_literal)?
+            }
             Dispatch::DocumentDidChange {
                 path,
                 content,
@@ -540,9 +900,8 @@ impl<T: Frontend> App<T> {
                 component_id,
             } => {
                 if let Some(language) = language {
-                    self.request_syntax_highlight(component_id, language, content.clone())?;
-                    // let highlight_spans = self.context.highlight(language, &content)?;
-                    // self.update_highlighted_spans(component_id, highlight_spans)?
+                    self.pending_syntax_highlight_requests
+                        .insert(component_id, (language, content.clone()));
                 }
                 if let Some(path) = path {
                     self.lsp_manager.send_message(
@@ -555,6 +914,19 @@ impl<T: Frontend> App<T> {
                     )?;
                 }
             }
+            Dispatch::PrivilegedSave(path) => self.privileged_save(path)?,
+            Dispatch::PushEditLocation(location) => self.change_list.push(location),
+            Dispatch::GoToLastEdit => {
+                if let Some(location) = self.change_list.latest() {
+                    self.go_to_location(&location)?
+                }
+            }
+            Dispatch::CycleEditLocations => {
+                if let Some(location) = self.change_list.cycle() {
+                    self.go_to_location(&location)?
+                }
+            }
+            Dispatch::CollectAnnotations => self.collect_annotations()?,
             Dispatch::DocumentDidSave { path } => {
                 self.lsp_manager.send_message(
                     path.clone(),
@@ -566,9 +938,53 @@ impl<T: Frontend> App<T> {
                 self.set_quickfix_list_type(Default::default(), r#type)?;
             }
             Dispatch::GotoQuickfixListItem(movement) => self.goto_quickfix_list_item(movement)?,
+            Dispatch::SwitchQuickfixList(movement) => self.switch_quickfix_list(movement)?,
+            Dispatch::OpenQuickfixListsPrompt => self.open_quickfix_lists_prompt()?,
+            Dispatch::OpenQuickfixListFilterPrompt => self.open_quickfix_list_filter_prompt()?,
+            Dispatch::SetQuickfixListFilter(filter) => self.set_quickfix_list_filter(filter)?,
+            Dispatch::RemoveCurrentQuickfixListItem => self.remove_current_quickfix_list_item()?,
+            Dispatch::ToggleQuickfixListPreview => {
+                self.context.toggle_quickfix_list_preview();
+                self.goto_quickfix_list_item(Movement::Current)?
+            }
+            Dispatch::StartTutor => self.start_tutor(),
+            Dispatch::ShowKeymapCheatSheet => self.show_keymap_cheat_sheet(),
+            Dispatch::OpenExportKeymapCheatSheetPrompt => {
+                self.open_export_keymap_cheat_sheet_prompt()?
+            }
+            Dispatch::OpenIncrementNumberPrompt { sequential } => {
+                self.open_increment_number_prompt(sequential)?
+            }
+            Dispatch::OpenSortByKeyPrompt { numeric } => self.open_sort_by_key_prompt(numeric)?,
+            Dispatch::OpenPipeToShellPrompt { per_selection } => {
+                self.open_pipe_to_shell_prompt(per_selection)?
+            }
+            Dispatch::OpenInsertShellOutputPrompt => self.open_insert_shell_output_prompt()?,
+            Dispatch::OpenInsertExpressionResultPrompt => {
+                self.open_insert_expression_result_prompt()?
+            }
+            Dispatch::OpenUnicodeCodePointPrompt => self.open_unicode_code_point_prompt()?,
+            Dispatch::OpenCharacterPicker => self.open_character_picker()?,
+            Dispatch::ExportKeymapCheatSheet(path) => self.export_keymap_cheat_sheet(&path)?,
+            Dispatch::OpenExportHighlightedPrompt { selection } => {
+                self.open_export_highlighted_prompt(selection)?
+            }
+            Dispatch::ExportHighlighted { path, selection } => {
+                self.export_highlighted(&path, selection)?
+            }
+            Dispatch::ShowKeyboardLayout => self.show_global_info(Info::new(
+                format!("Keyboard layout: {}", self.context.keyboard_layout().name()),
+                self.context.keyboard_layout().preview(),
+            )),
             Dispatch::ApplyWorkspaceEdit(workspace_edit) => {
-                self.apply_workspace_edit(workspace_edit)?;
+                self.handle_workspace_edit(workspace_edit)?;
             }
+            Dispatch::AcceptWorkspaceEdit => self.accept_workspace_edit()?,
+            Dispatch::DiscardWorkspaceEdit => self.discard_workspace_edit()?,
+            Dispatch::OpenRejectWorkspaceEditFilePrompt => {
+                self.open_reject_workspace_edit_file_prompt()?
+            }
+            Dispatch::RejectWorkspaceEditFile(path) => self.reject_workspace_edit_file(&path)?,
             Dispatch::ShowKeymapLegend(keymap_legend_config) => {
                 self.show_keymap_legend(keymap_legend_config)
             }
@@ -578,12 +994,21 @@ impl<T: Frontend> App<T> {
             Dispatch::RemainOnlyCurrentComponent => self.layout.remain_only_current_component(),
             Dispatch::ToEditor(dispatch_editor) => self.handle_dispatch_editor(dispatch_editor)?,
             Dispatch::GotoLocation(location) => self.go_to_location(&location)?,
+            Dispatch::OpenUrl(url) => self.open_url(&url)?,
             Dispatch::OpenMoveToIndexPrompt => self.open_move_to_index_prompt()?,
             Dispatch::RunCommand(command) => self.run_command(command)?,
             Dispatch::QuitAll => self.quit_all()?,
             Dispatch::OpenCommandPrompt => self.open_command_prompt()?,
             Dispatch::SaveQuitAll => self.save_quit_all()?,
             Dispatch::RevealInExplorer(path) => self.reveal_path_in_explorer(&path)?,
+            Dispatch::RevealCurrentFileInExplorer => {
+                let path = self
+                    .current_component()
+                    .borrow()
+                    .path()
+                    .unwrap_or_else(|| self.working_directory.clone());
+                self.reveal_path_in_explorer(&path)?
+            }
             Dispatch::OpenYesNoPrompt(prompt) => self.open_yes_no_prompt(prompt)?,
             Dispatch::OpenMoveFilePrompt(path) => self.open_move_file_prompt(path)?,
             Dispatch::OpenAddPathPrompt(path) => self.open_add_path_prompt(path)?,
@@ -592,10 +1017,57 @@ impl<T: Frontend> App<T> {
                 // do nothing
             }
             Dispatch::MoveFile { from, to } => self.move_file(from, to)?,
+            Dispatch::NewScratchBuffer => self.new_scratch_buffer(),
+            Dispatch::OpenScratchBufferPicker => self.open_scratch_buffer_picker()?,
+            Dispatch::SwitchScratchBuffer(index) => self.layout.focus_scratch_editor(index)?,
+            Dispatch::OpenDailyNote => {
+                let path = crate::notes::daily_note(&self.notes_directory, SystemTime::now())?;
+                self.open_file(&path.try_into()?, OpenFileOption::Focus)?;
+            }
+            Dispatch::OpenSaveAsPrompt => self.open_save_as_prompt()?,
+            Dispatch::SaveAs(to) => self.save_as(to)?,
+            Dispatch::OpenRenameFilePrompt => self.open_rename_file_prompt()?,
+            Dispatch::RenameFile(to) => self.rename_file(to)?,
+            Dispatch::OpenChangeWorkingDirectoryPrompt => {
+                self.open_change_working_directory_prompt()?
+            }
+            Dispatch::ChangeWorkingDirectory(path) => self.change_working_directory(path)?,
+            Dispatch::OpenBranchPicker => self.open_branch_picker()?,
+            Dispatch::CheckoutBranch(branch) => {
+                self.git_repo()?.checkout_branch(&branch)?;
+                self.refresh_after_checkout()?
+            }
+            Dispatch::CreateBranch(name) => {
+                self.git_repo()?.create_branch(&name)?;
+                self.refresh_after_checkout()?
+            }
+            Dispatch::OpenCommitLog { current_file } => self.open_commit_log(current_file)?,
+            Dispatch::ShowCommit(hash) => self.show_commit(&hash)?,
+            Dispatch::GitIntentToAdd(path) => {
+                self.git_repo_containing(&path)?.intent_to_add(&path)?;
+                self.show_global_info(Info::new(
+                    "Intent to add".to_string(),
+                    format!("{} will be added", path.display_absolute()),
+                ))
+            }
+            Dispatch::CopyCommitHash(hash) => {
+                self.context
+                    .set_clipboard_content(CopiedTexts::one(hash.clone()), true)?;
+                self.show_global_info(Info::new("Copied commit hash".to_string(), hash))
+            }
+            Dispatch::ConfirmDeleteBranch(branch) => self.confirm_delete_branch(branch)?,
+            Dispatch::DeleteBranch(branch) => {
+                self.git_repo()?.delete_branch(&branch)?;
+                self.show_global_info(Info::new(
+                    "Branch deleted".to_string(),
+                    format!("Deleted branch {:?}", branch.name),
+                ))
+            }
             Dispatch::AddPath(path) => self.add_path(path)?,
             Dispatch::RefreshFileExplorer => {
                 self.layout.refresh_file_explorer(&self.working_directory)?
             }
+            Dispatch::CopyPath { path, format } => self.copy_path(&path, format)?,
             Dispatch::SetClipboardContent {
                 copied_texts: contents,
                 use_system_clipboard,
@@ -609,6 +1081,9 @@ impl<T: Frontend> App<T> {
                 self.handle_event(Event::Key(key_event))?;
             }
             Dispatch::GetRepoGitHunks(diff_mode) => self.get_repo_git_hunks(diff_mode)?,
+            Dispatch::OpenGitHunkBaselinePrompt(scope) => {
+                self.open_git_hunk_baseline_prompt(scope)?
+            }
             Dispatch::SaveAll => self.save_all()?,
             #[cfg(test)]
             Dispatch::TerminalDimensionChanged(dimension) => self.resize(dimension),
@@ -619,6 +1094,14 @@ impl<T: Frontend> App<T> {
                 target,
                 make_mechanism,
             } => self.open_omit_prompt(kind, target, make_mechanism)?,
+            Dispatch::OpenFilterSelectionsPrompt(kind) => {
+                self.open_filter_selections_prompt(kind)?
+            }
+            Dispatch::OpenKeepEveryNthSelectionPrompt => {
+                self.open_keep_every_nth_selection_prompt()?
+            }
+            Dispatch::OpenSplitSelectionsPrompt => self.open_split_selections_prompt()?,
+            Dispatch::ShowJumpsAcrossPanes => self.show_jumps_across_panes(),
 
             Dispatch::LspExecuteCommand { command } => {
                 if let Some(params) = self.get_request_params() {
@@ -633,6 +1116,9 @@ impl<T: Frontend> App<T> {
                 scope,
                 show_config_after_enter,
             } => self.update_local_search_config(update, scope, show_config_after_enter)?,
+            Dispatch::ToggleSearchOption { scope, option } => {
+                self.toggle_search_option(scope, option)
+            }
             Dispatch::UpdateGlobalSearchConfig { update } => {
                 self.update_global_search_config(update)?;
             }
@@ -660,6 +1146,12 @@ impl<T: Frontend> App<T> {
             }
             #[cfg(test)]
             Dispatch::HandleKeyEvents(key_events) => self.handle_key_events(key_events)?,
+            #[cfg(test)]
+            Dispatch::HandlePaste(content) => {
+                self.handle_event(Event::Paste(content))?;
+            }
+            #[cfg(test)]
+            Dispatch::SetJumpLabelStrategy(strategy) => self.set_jump_label_strategy(strategy),
             Dispatch::CloseDropdown => self.layout.close_dropdown(),
             Dispatch::CloseEditorInfo => self.layout.close_editor_info(),
             Dispatch::RenderDropdown { render } => {
@@ -675,9 +1167,52 @@ impl<T: Frontend> App<T> {
             } => self.open_prompt(config, key, current_line)?,
             Dispatch::ShowEditorInfo(info) => self.show_editor_info(info)?,
             Dispatch::ReceiveCodeActions(code_actions) => {
-                self.open_code_actions_prompt(code_actions)?;
+                self.receive_code_actions(code_actions)?;
             }
             Dispatch::OtherWindow => self.layout.cycle_window(),
+            Dispatch::ResizeWindow { grow } => self.layout.resize_focused_window(grow),
+            Dispatch::EqualizeWindows => self.layout.equalize_windows(),
+            Dispatch::SwitchLayoutKind => self.layout.switch_layout_kind(),
+            Dispatch::ToggleStickyContext => {
+                let sticky_context = !self.context.sticky_context();
+                self.context.set_sticky_context(sticky_context)
+            }
+            Dispatch::ToggleBreadcrumbs => {
+                let breadcrumbs = !self.context.breadcrumbs();
+                self.context.set_breadcrumbs(breadcrumbs)
+            }
+            Dispatch::ToggleInlineDiagnostics => {
+                let inline_diagnostics = !self.context.inline_diagnostics();
+                self.context.set_inline_diagnostics(inline_diagnostics)
+            }
+            Dispatch::ToggleAutoCenter => {
+                let auto_center = !self.context.auto_center();
+                self.context.set_auto_center(auto_center)
+            }
+            Dispatch::ToggleExplorerAutoFollow => {
+                let explorer_auto_follow = !self.context.explorer_auto_follow();
+                self.context.set_explorer_auto_follow(explorer_auto_follow)
+            }
+            Dispatch::OpenBreadcrumbsPrompt => self.open_breadcrumbs_prompt()?,
+            Dispatch::OpenOutline => self.layout.open_outline()?,
+            Dispatch::RunTests { all } => self.run_tests(all)?,
+            Dispatch::ReceiveTestRun(test_run) => self.receive_test_run(test_run)?,
+            Dispatch::OpenBuildPrompt => self.open_build_prompt()?,
+            Dispatch::OpenDiagnosticsPicker => self.open_diagnostics_picker()?,
+            Dispatch::OpenBookmarkNotePrompt => self.open_bookmark_note_prompt()?,
+            Dispatch::OpenBookmarksPicker => self.open_bookmarks_picker()?,
+            Dispatch::RunBuild(command) => {
+                crate::error_format::run(&command, self.sender.clone())?;
+                self.show_global_info(Info::new("Building".to_string(), command));
+            }
+            Dispatch::ReceiveBuildOutput(output) => self.receive_build_output(output)?,
+            Dispatch::ReceiveDirectoryEntries { directory, entries } => {
+                self.receive_directory_entries(directory, entries)?
+            }
+            Dispatch::OpenSaveLayoutPresetPrompt => self.open_save_layout_preset_prompt()?,
+            Dispatch::SaveLayoutPreset(name) => self.save_layout_preset(&name)?,
+            Dispatch::OpenRestoreLayoutPresetPrompt => self.open_restore_layout_preset_prompt()?,
+            Dispatch::RestoreLayoutPreset(name) => self.restore_layout_preset(&name)?,
             Dispatch::GoToPreviousFile => self.go_to_previous_file()?,
             Dispatch::GoToNextFile => self.go_to_next_file()?,
             Dispatch::PushPromptHistory { key, line } => self.push_history_prompt(key, line),
@@ -694,6 +1229,14 @@ impl<T: Frontend> App<T> {
         self.layout.close_current_window()
     }
 
+    fn close_file(&mut self, path: &CanonicalizedPath) {
+        if self.current_component().borrow().path().as_ref() == Some(path) {
+            self.layout.close_current_window()
+        } else {
+            self.layout.remove_suggestive_editor(path)
+        }
+    }
+
     fn local_search(&mut self) -> anyhow::Result<()> {
         let config = self.context.local_search_config();
         let search = config.search();
@@ -701,7 +1244,7 @@ impl<T: Frontend> App<T> {
             self.handle_dispatch_editor_custom(
                 SetSelectionMode(SelectionMode::Find {
                     search: Search {
-                        mode: config.mode,
+                        mode: config.effective_mode(),
                         search,
                     },
                 }),
@@ -747,23 +1290,281 @@ impl<T: Frontend> App<T> {
         )
     }
 
-    fn open_search_prompt(&mut self, scope: Scope) -> anyhow::Result<()> {
-        let config = self.context.get_local_search_config(scope);
-        let mode = config.mode;
+    fn open_edit_color_prompt(&mut self, color_literal: ColorLiteral) -> anyhow::Result<()> {
+        let component = self.current_component();
+        let (current_color, dispatches) = {
+            let mut component = component.borrow_mut();
+            let editor = component.editor_mut();
+            let dispatches = editor.select_char_index_range(color_literal.range);
+            let current_color = editor.buffer().slice(&color_literal.range)?.to_string();
+            (current_color, dispatches)
+        };
+        self.handle_dispatches(dispatches)?;
+        self.open_prompt(
+            PromptConfig {
+                title: "Edit color".to_string(),
+                on_enter: DispatchPrompt::EditColor,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::EditColor,
+            Some(current_color),
+        )
+    }
+
+    fn show_peek(
+        &mut self,
+        context: ResponseContext,
+        locations: Vec<Location>,
+    ) -> anyhow::Result<()> {
+        let title = context.description.unwrap_or_else(|| "Peek".to_string());
+        if locations.is_empty() {
+            self.show_global_info(Info::new(title, "No locations found".to_string()));
+            return Ok(());
+        }
+        let peek = Peek::new(
+            format!("{} ({})", title, locations.len()),
+            locations,
+            &self.layout.buffers(),
+            &self.working_directory,
+        )?;
+        self.layout.show_peek(Rc::new(RefCell::new(peek)));
+        Ok(())
+    }
+
+    /// Requests the occurrences of the symbol at `range`, which are edited together by the linked rename.
+    /// `textDocument/documentHighlight` is requested if `textDocument/linkedEditingRange` provides none,
+    /// and the occurrences are found in the enclosing syntax node if neither provides any,
+    /// see `Editor::start_linked_editing`.
+    fn request_linked_rename_occurrences(&mut self, range: Range<Position>) -> anyhow::Result<()> {
+        let Some(params) = self.get_request_params() else {
+            return Ok(());
+        };
+        self.pending_linked_rename = Some(PendingLinkedRename {
+            range,
+            params: params.clone(),
+        });
+        self.lsp_manager.send_message(
+            params.path.clone(),
+            FromEditor::TextDocumentLinkedEditingRange(params),
+        )
+    }
+
+    fn handle_linked_editing_ranges(&mut self, ranges: Vec<Range<Position>>) -> anyhow::Result<()> {
+        let Some(pending_linked_rename) = self.pending_linked_rename.as_ref() else {
+            return Ok(());
+        };
+        if ranges.is_empty() {
+            let params = pending_linked_rename.params.clone();
+            self.lsp_manager.send_message(
+                params.path.clone(),
+                FromEditor::TextDocumentDocumentHighlight(params),
+            )
+        } else {
+            self.start_linked_rename(ranges)
+        }
+    }
+
+    fn start_linked_rename(&mut self, occurrences: Vec<Range<Position>>) -> anyhow::Result<()> {
+        let Some(PendingLinkedRename { range, params }) = self.pending_linked_rename.take() else {
+            return Ok(());
+        };
+        let component = self.current_component();
+        // The occurrences are ignored if another file is focused while waiting for them
+        if component.borrow().path().as_ref() != Some(&params.path) {
+            return Ok(());
+        }
+        let (original_undo_depth, original_selection_set) = {
+            let component = component.borrow();
+            let editor = component.editor();
+            (editor.buffer().undo_depth(), editor.selection_set.clone())
+        };
+        let (name, dispatches) = component
+            .borrow_mut()
+            .editor_mut()
+            .start_linked_editing(range, occurrences)?;
+        self.linked_rename = Some(LinkedRename {
+            component_id: component.borrow().id(),
+            original_undo_depth,
+            original_selection_set,
+            params,
+        });
+        self.handle_dispatches(dispatches)?;
+        self.show_global_info(Info::new(
+            "Rename".to_string(),
+            format!("Type the new name of `{name}`, then press enter to rename, or esc to cancel."),
+        ));
+        Ok(())
+    }
+
+    /// Labels the jumps of every visible pane with the character of the pane,
+    /// so that typing the character of a pane focuses it and narrows the jumps down to its jumps.
+    fn show_jumps_across_panes(&mut self) {
+        self.clear_pane_jumps();
+        let jump_characters = self.context.jump_characters();
+        let mut characters = jump_characters.iter().copied();
+        for component in self.components() {
+            let component = component.component();
+            let mut component = component.borrow_mut();
+            let editor = component.editor_mut();
+            // The letter of the pane takes the place of the label strategy,
+            // and panes whose selection mode cannot jump are skipped
+            if editor
+                .show_jumps(true, JumpLabelStrategy::FirstChar, &jump_characters)
+                .is_err()
+                || editor.jumps().is_empty()
+            {
+                editor.jumps = None;
+                continue;
+            }
+            let Some(character) = characters.next() else {
+                editor.jumps = None;
+                continue;
+            };
+            editor.set_jumps_character(character);
+            self.pane_jumps.push((character, component.id()))
+        }
+    }
+
+    fn clear_pane_jumps(&mut self) {
+        for component in self.components() {
+            component.component().borrow_mut().editor_mut().jumps = None;
+        }
+        self.pane_jumps.clear()
+    }
+
+    /// Returns true if the key event is consumed by the jumps across panes.
+    /// Typing the character of a pane focuses it, and the key event is then handled by the pane,
+    /// which either jumps, or labels its jumps with new characters.
+    fn handle_pane_jump_key(&mut self, key_event: &event::KeyEvent) -> bool {
+        if self.pane_jumps.is_empty() {
+            return false;
+        }
+        let pane = match key_event.code {
+            crossterm::event::KeyCode::Char(character) => self
+                .pane_jumps
+                .iter()
+                .find(|(pane_character, _)| *pane_character == character)
+                .map(|(_, component_id)| *component_id),
+            _ => None,
+        };
+        self.pane_jumps.clear();
+        for component in self.components() {
+            let component = component.component();
+            let mut component = component.borrow_mut();
+            if Some(component.id()) != pane {
+                component.editor_mut().jumps = None;
+            }
+        }
+        match pane {
+            Some(component_id) if self.layout.focus_component(component_id) => false,
+            _ => {
+                self.clear_pane_jumps();
+                true
+            }
+        }
+    }
+
+    /// Returns true if the key event is consumed by the linked rename.
+    fn handle_linked_rename_key(&mut self, key_event: &event::KeyEvent) -> anyhow::Result<bool> {
+        let component = self.current_component();
+        if self
+            .linked_rename
+            .as_ref()
+            .map(|linked_rename| linked_rename.component_id)
+            != Some(component.borrow().id())
+        {
+            return Ok(false);
+        }
+        let commit = match key_event {
+            key!("enter") => true,
+            key!("esc") => false,
+            _ => return Ok(false),
+        };
+        let Some(linked_rename) = self.linked_rename.take() else {
+            return Ok(false);
+        };
+        let new_name = component.borrow().editor().get_current_word()?;
+
+        // Undo the linked edits, so that the edits of the LSP rename apply to the original content,
+        // and the undo history stays consistent with the content
+        let dispatches = {
+            let mut component = component.borrow_mut();
+            let editor = component.editor_mut();
+            editor.enter_normal_mode()?;
+            let mut dispatches = Dispatches::default();
+            while editor.buffer().undo_depth() > linked_rename.original_undo_depth {
+                let undo_depth = editor.buffer().undo_depth();
+                dispatches = dispatches.chain(editor.undo()?);
+                if editor.buffer().undo_depth() == undo_depth {
+                    break;
+                }
+            }
+            editor.update_selection_set(linked_rename.original_selection_set, false);
+            dispatches.chain(editor.get_document_did_change_dispatch())
+        };
+        self.handle_dispatches(dispatches)?;
+        if commit && !new_name.is_empty() {
+            self.lsp_manager.send_message(
+                linked_rename.params.path.clone(),
+                FromEditor::TextDocumentRename {
+                    params: linked_rename.params,
+                    new_name,
+                },
+            )?;
+        }
+        Ok(true)
+    }
+
+    /// Shows the toggles of the search config of `scope`, which can be toggled in the search prompt,
+    /// see `Prompt::handle_key_event`.
+    fn search_prompt_title(&self, scope: Scope) -> String {
+        format!(
+            "{:?} search ({}) alt+i: Case-sensitive, alt+w: Match whole word, alt+s: Smart-case, alt+p: Preserve case",
+            scope,
+            self.context.get_local_search_config(scope).display_mode()
+        )
+    }
+
+    fn toggle_search_option(&mut self, scope: Scope, option: SearchOption) {
+        self.context.toggle_search_option(scope, option);
+        self.persist_search_toggles();
+        let title = self.search_prompt_title(scope);
+        self.current_component().borrow_mut().set_title(title);
+    }
+
+    fn persist_search_toggles(&self) {
+        let Some(workspace) = self.context.persistence().workspace() else {
+            return;
+        };
+        if let Err(error) =
+            crate::search_toggles::persist(workspace, &self.context.search_toggles())
+        {
+            log::error!("Failed to persist search toggles: {:?}", error);
+        }
+    }
+
+    fn open_search_prompt(
+        &mut self,
+        scope: Scope,
+        current_line: Option<String>,
+    ) -> anyhow::Result<()> {
         self.open_prompt(
             PromptConfig {
-                title: format!("{:?} search ({})", scope, mode.display()),
+                title: self.search_prompt_title(scope),
                 items: self.words(),
                 on_enter: DispatchPrompt::UpdateLocalSearchConfigSearch {
                     scope,
                     show_config_after_enter: false,
                 },
                 enter_selects_first_matching_item: false,
-                leaves_current_line_empty: true,
+                leaves_current_line_empty: current_line.is_none(),
                 fire_dispatches_on_change: None,
             },
             PromptHistoryKey::Search(scope),
-            None,
+            current_line,
         )
     }
 
@@ -797,14 +1598,241 @@ impl<T: Frontend> App<T> {
         )
     }
 
-    fn open_symbol_picker(&mut self, symbols: Symbols) -> anyhow::Result<()> {
-        self.open_prompt(
-            PromptConfig {
-                title: "Symbols".to_string(),
-                items: symbols
-                    .symbols
-                    .clone()
-                    .into_iter()
+    fn current_buffer_path(&self) -> anyhow::Result<CanonicalizedPath> {
+        self.current_component()
+            .borrow()
+            .editor()
+            .buffer()
+            .path()
+            .ok_or_else(|| anyhow::anyhow!("The current buffer has no path"))
+    }
+
+    fn new_scratch_buffer(&mut self) {
+        self.scratch_buffer_count += 1;
+        let buffer = Rc::new(RefCell::new(Buffer::new(None, "")));
+        let mut editor = SuggestiveEditor::from_buffer(buffer, SuggestiveEditorFilter::CurrentWord);
+        editor.set_title(format!("[Scratch {}]", self.scratch_buffer_count));
+        self.layout
+            .add_scratch_editor(Rc::new(RefCell::new(editor)));
+    }
+
+    fn open_scratch_buffer_picker(&mut self) -> anyhow::Result<()> {
+        let items = self
+            .layout
+            .scratch_editors()
+            .iter()
+            .enumerate()
+            .map(|(index, editor)| {
+                let editor = editor.borrow();
+                let first_line = editor.editor().buffer().get_line_by_line_index(0);
+                DropdownItem::new(editor.title(&self.context))
+                    .set_info(
+                        first_line
+                            .map(|line| Info::new("Scratch buffer".to_string(), line.to_string())),
+                    )
+                    .set_dispatches(Dispatches::one(Dispatch::SwitchScratchBuffer(index)))
+            })
+            .collect_vec();
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("There are no scratch buffers"));
+        }
+        self.open_prompt(
+            PromptConfig {
+                title: "Scratch buffers".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items,
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::ScratchBuffer,
+            None,
+        )
+    }
+
+    fn open_save_as_prompt(&mut self) -> anyhow::Result<()> {
+        let path = self.current_buffer_path().ok();
+        self.open_prompt(
+            PromptConfig {
+                title: "Save as".to_string(),
+                on_enter: DispatchPrompt::SaveAs,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::AddPath,
+            Some(
+                path.unwrap_or_else(|| self.working_directory.clone())
+                    .display_absolute(),
+            ),
+        )
+    }
+
+    fn open_rename_file_prompt(&mut self) -> anyhow::Result<()> {
+        let path = self.current_buffer_path()?;
+        self.open_prompt(
+            PromptConfig {
+                title: "Rename file".to_string(),
+                on_enter: DispatchPrompt::RenameFile,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::MovePath,
+            Some(path.display_absolute()),
+        )
+    }
+
+    fn open_change_working_directory_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Change working directory".to_string(),
+                on_enter: DispatchPrompt::ChangeWorkingDirectory,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: false,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::WorkingDirectory,
+            Some(self.working_directory.display_absolute()),
+        )
+    }
+
+    /// Enter checks out the selected branch, or creates a branch named by the typed text if no
+    /// branch matches it, and alt+enter asks to delete the selected local branch.
+    fn open_branch_picker(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Branches".to_string(),
+                on_enter: DispatchPrompt::CreateBranch,
+                items: self
+                    .git_repo()?
+                    .branches()?
+                    .into_iter()
+                    .map(|branch| {
+                        let item = DropdownItem::new(if branch.is_head {
+                            format!("* {}", branch.name)
+                        } else {
+                            branch.name.clone()
+                        })
+                        .set_group(Some(
+                            if branch.is_remote { "Remote" } else { "Local" }.to_string(),
+                        ))
+                        .set_dispatches(Dispatches::one(Dispatch::CheckoutBranch(branch.clone())));
+                        // Remote branches cannot be deleted, see `GitRepo::delete_branch`
+                        if branch.is_remote {
+                            item
+                        } else {
+                            item.set_secondary_dispatches(Dispatches::one(
+                                Dispatch::ConfirmDeleteBranch(branch),
+                            ))
+                        }
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::Branch,
+            None,
+        )
+    }
+
+    /// Enter shows the diff of the selected commit, and alt+enter copies its hash.
+    fn open_commit_log(&mut self, current_file: bool) -> anyhow::Result<()> {
+        let path = if current_file {
+            Some(self.current_buffer_path()?)
+        } else {
+            None
+        };
+        self.open_prompt(
+            PromptConfig {
+                title: match &path {
+                    Some(path) => format!(
+                        "Commit log ({})",
+                        path.display_relative_to(&self.working_directory)
+                            .unwrap_or_else(|_| path.display_absolute())
+                    ),
+                    None => "Commit log".to_string(),
+                },
+                on_enter: DispatchPrompt::Null,
+                items: self
+                    .git_repo()?
+                    .log(path.as_ref(), COMMIT_LOG_LIMIT)?
+                    .into_iter()
+                    .map(|commit| {
+                        DropdownItem::new(format!(
+                            "{}{} {}",
+                            commit.graph,
+                            commit.short_hash(),
+                            commit.subject()
+                        ))
+                        .set_info(Some(Info::new("Commit".to_string(), commit.header())))
+                        .set_group(Some(format!("{} {}", commit.date(), commit.author)))
+                        .set_dispatches(Dispatches::one(Dispatch::ShowCommit(commit.hash.clone())))
+                        .set_secondary_dispatches(Dispatches::one(Dispatch::CopyCommitHash(
+                            commit.hash,
+                        )))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::CommitLog,
+            None,
+        )
+    }
+
+    /// Whether the branch is merged is only computed here, instead of for every branch
+    /// when the branch picker is opened, because it walks the commit graph.
+    fn confirm_delete_branch(&mut self, branch: git::Branch) -> anyhow::Result<()> {
+        let title = if self.git_repo()?.is_merged(&branch).unwrap_or(false) {
+            format!("Delete branch {:?}?", branch.name)
+        } else {
+            format!(
+                "Delete branch {:?}? It is not merged into HEAD or its upstream, so its commits may be lost.",
+                branch.name
+            )
+        };
+        self.open_yes_no_prompt(YesNoPrompt {
+            title,
+            yes: Box::new(Dispatch::DeleteBranch(branch)),
+        })
+    }
+
+    fn show_commit(&mut self, hash: &str) -> anyhow::Result<()> {
+        let mut buffer = Buffer::new(None, &self.git_repo()?.show(hash)?);
+        buffer.set_read_only(true);
+        let mut editor = SuggestiveEditor::from_buffer(
+            Rc::new(RefCell::new(buffer)),
+            SuggestiveEditorFilter::CurrentWord,
+        );
+        editor.set_title(format!("Commit {}", &hash[..hash.len().min(7)]));
+        self.layout
+            .replace_and_focus_current_suggestive_editor(Rc::new(RefCell::new(editor)));
+        Ok(())
+    }
+
+    /// Checking out a branch changes the files on disk and the baselines of the diff modes,
+    /// so the buffers, the file explorer and the ahead/behind counts are refreshed.
+    fn refresh_after_checkout(&mut self) -> anyhow::Result<()> {
+        self.reload_externally_changed_buffers()?;
+        self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.refresh_git_ahead_behind();
+        Ok(())
+    }
+
+    fn open_symbol_picker(&mut self, symbols: Symbols) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Symbols".to_string(),
+                items: symbols
+                    .symbols
+                    .clone()
+                    .into_iter()
                     .map(|symbol| symbol.into())
                     .collect_vec(),
                 on_enter: DispatchPrompt::SelectSymbol { symbols },
@@ -842,20 +1870,20 @@ impl<T: Frontend> App<T> {
                 title: format!("Open file: {}", kind.display()),
                 on_enter: DispatchPrompt::OpenFile { working_directory },
                 items: {
-                    match kind {
+                    match kind.clone() {
                         FilePickerKind::NonGitIgnored => {
                             // Note: we should not use CanonicalizedPath here, as it is resource-intensive
-                            list::WalkBuilderConfig::non_git_ignored_files(
-                                self.working_directory.clone(),
+                            list::WalkBuilderConfig::workspace_non_git_ignored_files(
+                                &self.working_directory,
                             )?
                         }
-                        FilePickerKind::GitStatus(diff_mode) => {
-                            git::GitRepo::try_from(&self.working_directory)?
-                                .diff_entries(diff_mode)?
-                                .into_iter()
-                                .map(|entry| entry.new_path().into_path_buf())
-                                .collect_vec()
-                        }
+                        FilePickerKind::GitStatus(diff_mode) => self
+                            .git_repos()?
+                            .into_iter()
+                            .map(|repo| repo.diff_entries(diff_mode.clone()))
+                            .flatten_ok()
+                            .map_ok(|entry| entry.new_path().into_path_buf())
+                            .collect::<anyhow::Result<Vec<_>>>()?,
                         FilePickerKind::Opened => self
                             .layout
                             .get_opened_files()
@@ -881,8 +1909,19 @@ impl<T: Frontend> App<T> {
                                 .unwrap_or_else(|_| parent.display().to_string());
                             format!("{} {}", shared::icons::get_icon_config().folder, relative,)
                         }))
-                        .set_dispatches(Dispatches::one(
-                            crate::app::Dispatch::OpenFileFromPathBuf(path),
+                        .set_dispatches(Dispatches::one(crate::app::Dispatch::OpenFileFromPathBuf(
+                            path.clone(),
+                        )))
+                        .set_secondary_dispatches(Dispatches::one(
+                            // Opened files are closed, other files are opened in the background
+                            if kind == FilePickerKind::Opened {
+                                CanonicalizedPath::try_from(path.as_path())
+                                    .ok()
+                                    .map(Dispatch::CloseFile)
+                            } else {
+                                None
+                            }
+                            .unwrap_or(Dispatch::OpenFileInBackground(path)),
                         ))
                     })
                     .collect_vec()
@@ -908,11 +1947,15 @@ impl<T: Frontend> App<T> {
         }
         // Check if the file is opened before
         // so that we won't notify the LSP twice
+        if option.is_focus() && self.context.explorer_auto_follow() {
+            self.layout.follow_path_in_explorer(path)?;
+        }
         if let Some(matching_editor) = self.layout.open_file(path, option.is_focus()) {
             return Ok(matching_editor);
         }
 
-        let buffer = Buffer::from_path(path, true)?;
+        let mut buffer = Buffer::from_path(path, true)?;
+        buffer.set_persistence(self.context.persistence().clone());
         let language = buffer.language();
         let content = buffer.content();
         let buffer = Rc::new(RefCell::new(buffer));
@@ -941,10 +1984,22 @@ impl<T: Frontend> App<T> {
         notification: LspNotification,
     ) -> anyhow::Result<()> {
         match notification {
-            LspNotification::Hover(hover) => self.show_editor_info(Info::new(
-                "Hover Info".to_string(),
-                hover.contents.join("\n\n"),
-            )),
+            LspNotification::Hover(hover) => {
+                let info = hover.into_info(|language, source_code| {
+                    self.context.highlight(language, source_code)
+                });
+                self.show_editor_info(info)
+            }
+            LspNotification::Definition(context, response) if context.peek => {
+                let locations = match response {
+                    GotoDefinitionResponse::Single(location) => vec![location],
+                    GotoDefinitionResponse::Multiple(locations) => locations,
+                };
+                self.show_peek(context, locations)
+            }
+            LspNotification::References(context, locations) if context.peek => {
+                self.show_peek(context, locations)
+            }
             LspNotification::Definition(context, response) => {
                 match response {
                     GotoDefinitionResponse::Single(location) => self.go_to_location(&location)?,
@@ -1013,6 +2068,7 @@ impl<T: Frontend> App<T> {
                     let buffer = editor.editor().buffer();
                     response
                         .range
+                        .clone()
                         .map(|range| {
                             let range = buffer.position_to_char(range.start)?
                                 ..buffer.position_to_char(range.end)?;
@@ -1022,21 +2078,33 @@ impl<T: Frontend> App<T> {
                         .unwrap_or_default()
                         .map(|rope| rope.to_string())
                 };
-                self.open_rename_prompt(current_name)?;
+                match response.range {
+                    Some(range) if self.get_request_params().is_some() => {
+                        self.request_linked_rename_occurrences(range)?
+                    }
+                    _ => self.open_rename_prompt(current_name)?,
+                }
 
                 Ok(())
             }
+            LspNotification::LinkedEditingRanges(ranges) => {
+                self.handle_linked_editing_ranges(ranges)
+            }
+            LspNotification::DocumentHighlights(ranges) => self.start_linked_rename(ranges),
             LspNotification::Error(error) => {
                 self.show_global_info(Info::new("LSP Error".to_string(), error));
                 Ok(())
             }
             LspNotification::WorkspaceEdit(workspace_edit) => {
-                self.apply_workspace_edit(workspace_edit)
+                self.handle_workspace_edit(workspace_edit)
             }
             LspNotification::CodeAction(code_actions) => {
                 self.handle_dispatch(Dispatch::ReceiveCodeActions(code_actions))?;
                 Ok(())
             }
+            LspNotification::CodeActionResolve(code_action) => {
+                self.handle_dispatches(code_action.dispatches())
+            }
             LspNotification::SignatureHelp(signature_help) => {
                 self.handle_signature_help(signature_help)?;
                 Ok(())
@@ -1048,6 +2116,14 @@ impl<T: Frontend> App<T> {
             LspNotification::CompletionItemResolve(completion_item) => {
                 self.update_current_completion_item(completion_item.into())
             }
+            LspNotification::DocumentColor { path, colors } => {
+                self.open_file(&path, OpenFileOption::Background)?
+                    .borrow_mut()
+                    .editor_mut()
+                    .buffer_mut()
+                    .set_document_colors(colors);
+                Ok(())
+            }
         }
     }
 
@@ -1063,16 +2139,39 @@ impl<T: Frontend> App<T> {
             .editor_mut()
             .buffer_mut()
             .set_diagnostics(diagnostics);
-        Ok(())
+        self.refresh_diagnostics_picker()
+    }
+
+    /// Like `update_diagnostics`, but only replaces the diagnostics of the last build,
+    /// so that the diagnostics of LSP servers are kept.
+    fn update_build_diagnostics(
+        &mut self,
+        path: CanonicalizedPath,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> anyhow::Result<()> {
+        let component = self.open_file(&path, OpenFileOption::Background)?;
+
+        component
+            .borrow_mut()
+            .editor_mut()
+            .buffer_mut()
+            .set_build_diagnostics(diagnostics);
+        self.refresh_diagnostics_picker()
     }
 
     pub(crate) fn get_quickfix_list(&self) -> Option<QuickfixList> {
-        self.context.quickfix_list_state().as_ref().map(|state| {
-            QuickfixList::new(
-                self.layout.get_quickfix_list_items(&state.source),
-                self.layout.buffers(),
-            )
-            .set_current_item_index(state.current_item_index)
+        self.context.quickfix_list_state().map(|state| {
+            let buffers = self.layout.buffers();
+            let removed_locations = self.layout.removed_quickfix_list_locations();
+            let items = self
+                .layout
+                .get_quickfix_list_items(&state.source)
+                .into_iter()
+                .filter(|item| state.shows(item, &removed_locations, &buffers))
+                .collect_vec();
+            QuickfixList::new(items, buffers)
+                .set_title(state.display())
+                .set_current_item_index(state.current_item_index)
         })
     }
 
@@ -1081,10 +2180,17 @@ impl<T: Frontend> App<T> {
             if let Some((current_item_index, dispatches)) = quickfix_list.get_item(movement) {
                 self.context
                     .set_quickfix_list_current_item_index(current_item_index);
-                self.handle_dispatches(dispatches)?;
-                self.render_quickfix_list(
-                    quickfix_list.set_current_item_index(current_item_index),
-                )?;
+                let quickfix_list = quickfix_list.set_current_item_index(current_item_index);
+                let preview = if self.context.quickfix_list_preview() {
+                    quickfix_list
+                        .current_item_location()
+                        .map(|location| QuickfixListPreview::new(&location, &self.layout.buffers()))
+                        .transpose()?
+                } else {
+                    self.handle_dispatches(dispatches)?;
+                    None
+                };
+                self.render_quickfix_list(quickfix_list, preview)?;
             }
         }
 
@@ -1103,6 +2209,9 @@ impl<T: Frontend> App<T> {
             .borrow_mut()
             .editor_mut()
             .set_position_range(range.clone())?;
+        if self.context.auto_center() {
+            component.borrow_mut().editor_mut().center_cursor()
+        }
         self.handle_dispatches(dispatches)
     }
 
@@ -1113,35 +2222,32 @@ impl<T: Frontend> App<T> {
     ) -> anyhow::Result<()> {
         let title = context.description.unwrap_or_default();
         self.context.set_mode(Some(GlobalMode::QuickfixListItem));
-        match r#type {
-            QuickfixListType::Diagnostic(severity_range) => {
-                self.context
-                    .set_quickfix_list_source(QuickfixListSource::Diagnostic(severity_range));
-            }
+        self.stash_current_quickfix_list_items();
+        self.layout.set_removed_quickfix_list_locations(&[]);
+        let state = match r#type {
+            QuickfixListType::Diagnostic(severity_range) => QuickfixListState::new(
+                format!("Diagnostics ({:?})", severity_range),
+                QuickfixListSource::Diagnostic(severity_range),
+            ),
             QuickfixListType::Items(items) => {
-                self.layout.clear_quickfix_list_items();
-                items
-                    .into_iter()
-                    .group_by(|item| item.location().path.clone())
-                    .into_iter()
-                    .map(|(path, items)| -> anyhow::Result<()> {
-                        let editor = self.open_file(&path, OpenFileOption::Background)?;
-                        editor
-                            .borrow_mut()
-                            .editor_mut()
-                            .buffer_mut()
-                            .update_quickfix_list_items(items.collect_vec());
-                        Ok(())
-                    })
-                    .collect::<anyhow::Result<Vec<_>>>()?;
-                self.context
-                    .set_quickfix_list_source(QuickfixListSource::Custom);
+                if let Some(workspace) = self.context.persistence().workspace() {
+                    if let Err(error) = crate::quickfix_list::persist(workspace, &title, &items) {
+                        log::error!("Failed to persist quickfix list: {:?}", error);
+                    }
+                }
+                self.load_quickfix_list_items(items.clone())?;
+                let title = if title.is_empty() {
+                    "Custom".to_string()
+                } else {
+                    title.clone()
+                };
+                QuickfixListState::new(title, QuickfixListSource::Custom).set_items(items)
             }
             QuickfixListType::Bookmark => {
-                self.context
-                    .set_quickfix_list_source(QuickfixListSource::Bookmark);
+                QuickfixListState::new("Bookmarks".to_string(), QuickfixListSource::Bookmark)
             }
-        }
+        };
+        self.context.push_quickfix_list(state);
         match context.scope {
             None | Some(Scope::Global) => {
                 self.goto_quickfix_list_item(Movement::Current)?;
@@ -1153,95 +2259,1249 @@ impl<T: Frontend> App<T> {
         }
     }
 
-    fn apply_workspace_edit(&mut self, workspace_edit: WorkspaceEdit) -> Result<(), anyhow::Error> {
-        // TODO: should we wrap this in a transaction so that if one of the edit/operation fails, the whole transaction fails?
-        // Such that it won't leave the workspace in an half-edited messed up state
-        for edit in workspace_edit.edits {
-            let component = self.open_file(&edit.path, OpenFileOption::Background)?;
-            let dispatches = component
-                .borrow_mut()
-                .editor_mut()
-                .apply_positional_edits(edit.edits)?;
-
-            self.handle_dispatches(dispatches)?;
-
-            let dispatches = component.borrow_mut().editor_mut().save()?;
-
-            self.handle_dispatches(dispatches)?;
-        }
-        use crate::lsp::workspace_edit::ResourceOperation;
-        for operation in workspace_edit.resource_operations {
-            match operation {
-                ResourceOperation::Create(path) => self.add_path(path)?,
-                ResourceOperation::Rename { old, new } => self.move_file(old, new)?,
-                ResourceOperation::Delete(path) => self.delete_path(&path)?,
-            }
-        }
+    fn load_quickfix_list_items(&mut self, items: Vec<QuickfixListItem>) -> anyhow::Result<()> {
+        self.layout.clear_quickfix_list_items();
+        items
+            .into_iter()
+            .group_by(|item| item.location().path.clone())
+            .into_iter()
+            .map(|(path, items)| -> anyhow::Result<()> {
+                let editor = self.open_file(&path, OpenFileOption::Background)?;
+                editor
+                    .borrow_mut()
+                    .editor_mut()
+                    .buffer_mut()
+                    .update_quickfix_list_items(items.collect_vec());
+                Ok(())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
         Ok(())
     }
 
-    fn show_keymap_legend(&mut self, keymap_legend_config: KeymapLegendConfig) {
-        self.layout.show_keymap_legend(keymap_legend_config)
+    /// Snapshot the items of the current custom quickfix list and the removed locations of the
+    /// current quickfix list (which live in the buffers),
+    /// so that they can be restored when the list is activated again.
+    fn stash_current_quickfix_list_items(&mut self) {
+        let items = self
+            .layout
+            .get_quickfix_list_items(&QuickfixListSource::Custom);
+        let removed_locations = self.layout.removed_quickfix_list_locations();
+        if let Some(state) = self.context.quickfix_list_state_mut() {
+            state.removed_locations = removed_locations;
+            if state.source == QuickfixListSource::Custom {
+                state.items = items;
+            }
+        }
     }
 
-    fn global_replace(&mut self) -> anyhow::Result<()> {
-        let working_directory = self.working_directory.clone();
-        let global_search_config = self.context.global_search_config();
-        let walk_builder_config = WalkBuilderConfig {
-            root: working_directory.clone().into(),
-            include: global_search_config.include_glob(),
-            exclude: global_search_config.exclude_glob(),
+    fn switch_quickfix_list(&mut self, movement: Movement) -> anyhow::Result<()> {
+        let Some(index) = self.context.quickfix_lists().index_after(movement) else {
+            return Ok(());
         };
-        let config = self.context.global_search_config().local_config();
-        let affected_paths = list::grep::replace(walk_builder_config, config.clone())?;
-        self.layout.reload_buffers(affected_paths)
+        self.stash_current_quickfix_list_items();
+        let (custom_items, removed_locations) = self
+            .context
+            .quickfix_lists_mut()
+            .set_current(index)
+            .map(|state| {
+                let custom_items =
+                    (state.source == QuickfixListSource::Custom).then(|| state.items.clone());
+                (custom_items, state.removed_locations.clone())
+            })
+            .unwrap_or_default();
+        if let Some(items) = custom_items {
+            self.load_quickfix_list_items(items)?;
+        }
+        self.layout
+            .set_removed_quickfix_list_locations(&removed_locations);
+        self.context.set_mode(Some(GlobalMode::QuickfixListItem));
+        self.goto_quickfix_list_item(Movement::Current)
     }
 
-    fn global_search(&mut self) -> anyhow::Result<()> {
-        let working_directory = self.working_directory.clone();
+    fn open_quickfix_lists_prompt(&mut self) -> anyhow::Result<()> {
+        let items = self
+            .context
+            .quickfix_lists()
+            .titles()
+            .into_iter()
+            .enumerate()
+            .map(|(index, title)| {
+                DropdownItem::new(title).set_dispatches(Dispatches::one(
+                    Dispatch::SwitchQuickfixList(Movement::Index(index)),
+                ))
+            })
+            .collect_vec();
+        self.open_prompt(
+            PromptConfig {
+                title: "Quickfix lists".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items,
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::QuickfixList,
+            None,
+        )
+    }
 
-        let global_search_config = self.context.global_search_config();
-        let walk_builder_config = WalkBuilderConfig {
-            root: working_directory.clone().into(),
-            include: global_search_config.include_glob(),
-            exclude: global_search_config.exclude_glob(),
-        };
-        let config = global_search_config.local_config();
-        if config.search().is_empty() {
-            return Ok(());
-        }
-        let locations = match config.mode {
-            LocalSearchConfigMode::Regex(regex) => {
-                list::grep::run(&config.search(), walk_builder_config, regex)
-            }
-            LocalSearchConfigMode::AstGrep => {
-                list::ast_grep::run(config.search().clone(), walk_builder_config)
-            }
-            LocalSearchConfigMode::CaseAgnostic => {
-                list::case_agnostic::run(config.search().clone(), walk_builder_config)
-            }
-        }?;
-        self.set_quickfix_list_type(
-            ResponseContext::default().set_description("Global search"),
-            QuickfixListType::Items(
-                locations
-                    .into_iter()
-                    .map(|location| QuickfixListItem::new(location, None))
-                    .collect_vec(),
-            ),
-        )?;
-        Ok(())
+    fn open_quickfix_list_filter_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Filter quickfix list (regex)".to_string(),
+                on_enter: DispatchPrompt::SetQuickfixListFilter,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::QuickfixListFilter,
+            None,
+        )
     }
 
-    pub(crate) fn quit_all(&self) -> Result<(), anyhow::Error> {
-        Ok(self.sender.send(AppMessage::QuitAll)?)
+    pub(crate) fn start_tutor(&mut self) {
+        self.tutor = Some(Tutor::default());
+        self.open_tutor_lesson()
     }
 
-    pub(crate) fn sender(&self) -> Sender<AppMessage> {
-        self.sender.clone()
+    fn open_tutor_lesson(&mut self) {
+        let Some(tutor) = self.tutor.as_mut() else {
+            return;
+        };
+        let Some(lesson) = tutor.current_lesson() else {
+            self.tutor = None;
+            self.show_global_info(Info::new(
+                "Tutor".to_string(),
+                "Congratulations, you have completed all lessons!".to_string(),
+            ));
+            return;
+        };
+        let title = format!(
+            "Tutor {}/{}: {}",
+            tutor.lesson_index() + 1,
+            LESSONS.len(),
+            lesson.title
+        );
+        let buffer = Buffer::new(None, lesson.content);
+        let mut editor = SuggestiveEditor::from_buffer(
+            Rc::new(RefCell::new(buffer)),
+            SuggestiveEditorFilter::CurrentWord,
+        );
+        editor.set_title(title.clone());
+        tutor.set_component_id(editor.id());
+        self.layout
+            .replace_and_focus_current_suggestive_editor(Rc::new(RefCell::new(editor)));
+        self.show_global_info(Info::new(title, lesson.instruction.to_string()))
     }
 
-    fn run_command(&mut self, command: String) -> anyhow::Result<()> {
+    /// Advances to the next lesson if the current lesson is passed.
+    fn update_tutor(&mut self) {
+        let component = self.current_component();
+        let Some(tutor) = self.tutor.as_mut() else {
+            return;
+        };
+        let component = component.borrow();
+        if tutor.component_id() != Some(component.id()) {
+            return;
+        }
+        if tutor
+            .current_lesson()
+            .is_some_and(|lesson| lesson.is_passed(component.editor()))
+        {
+            tutor.next_lesson();
+            drop(component);
+            self.open_tutor_lesson()
+        }
+    }
+
+    fn keymap_cheat_sheet(&self) -> KeymapCheatSheet {
+        let component = self.current_component();
+        let component = component.borrow();
+        let editor = component.editor();
+        KeymapCheatSheet::new(
+            &[
+                editor.normal_mode_keymap_legend_config(&self.context),
+                editor.insert_mode_keymap_legend_config(),
+            ],
+            self.context.keyboard_layout(),
+        )
+    }
+
+    fn show_keymap_cheat_sheet(&mut self) {
+        let mut buffer = Buffer::new(
+            Some(tree_sitter_md::language()),
+            &self.keymap_cheat_sheet().to_markdown(),
+        );
+        buffer.set_read_only(true);
+        let mut editor = SuggestiveEditor::from_buffer(
+            Rc::new(RefCell::new(buffer)),
+            SuggestiveEditorFilter::CurrentWord,
+        );
+        editor.set_title("Keymap cheat sheet".to_string());
+        self.layout
+            .replace_and_focus_current_suggestive_editor(Rc::new(RefCell::new(editor)))
+    }
+
+    fn open_export_keymap_cheat_sheet_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Export keymap cheat sheet to (.md or .html)".to_string(),
+                on_enter: DispatchPrompt::ExportKeymapCheatSheet,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::ExportKeymapCheatSheet,
+            None,
+        )
+    }
+
+    fn open_export_highlighted_prompt(&mut self, selection: bool) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Export highlighted {} to (.html, or ANSI otherwise)",
+                    if selection { "selection" } else { "buffer" }
+                ),
+                on_enter: DispatchPrompt::ExportHighlighted { selection },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::ExportHighlighted,
+            None,
+        )
+    }
+
+    fn open_increment_number_prompt(&mut self, sequential: bool) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: if sequential {
+                    "Number sequence step (negative to decrement)".to_string()
+                } else {
+                    "Increment by (negative to decrement)".to_string()
+                },
+                on_enter: DispatchPrompt::IncrementNumber { sequential },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::IncrementNumber,
+            None,
+        )
+    }
+
+    fn open_sort_by_key_prompt(&mut self, numeric: bool) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: if numeric {
+                    "Sort numerically by key (regex)".to_string()
+                } else {
+                    "Sort by key (regex)".to_string()
+                },
+                on_enter: DispatchPrompt::SortByKey { numeric },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SortByKey,
+            None,
+        )
+    }
+
+    fn open_pipe_to_shell_prompt(&mut self, per_selection: bool) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: if per_selection {
+                    "Pipe each selection to shell ({} is the selection)".to_string()
+                } else {
+                    "Pipe selections to shell".to_string()
+                },
+                on_enter: DispatchPrompt::PipeToShell { per_selection },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::PipeToShell,
+            None,
+        )
+    }
+
+    fn open_insert_shell_output_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Insert output of shell command ($KI_SELECTION, $KI_CURSOR_INDEX)"
+                    .to_string(),
+                on_enter: DispatchPrompt::InsertShellOutput,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::InsertShellOutput,
+            None,
+        )
+    }
+
+    fn open_insert_expression_result_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Insert result of expression (i is the cursor index)".to_string(),
+                on_enter: DispatchPrompt::InsertExpressionResult,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::InsertExpressionResult,
+            None,
+        )
+    }
+
+    fn open_unicode_code_point_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Insert Unicode code point (hexadecimal)".to_string(),
+                on_enter: DispatchPrompt::InsertUnicodeCodePoint,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::UnicodeCodePoint,
+            None,
+        )
+    }
+
+    fn open_character_picker(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Insert character".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: crate::unicode_input::CHARACTERS
+                    .iter()
+                    .map(|(name, character)| {
+                        DropdownItem::new(format!("{character} {name} U+{:04X}", *character as u32))
+                            .set_dispatches(Dispatches::one(Dispatch::ToEditor(Insert(
+                                character.to_string(),
+                            ))))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::CharacterPicker,
+            None,
+        )
+    }
+
+    fn open_save_layout_preset_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Save layout preset as".to_string(),
+                on_enter: DispatchPrompt::SaveLayoutPreset,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::LayoutPreset,
+            None,
+        )
+    }
+
+    fn save_layout_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        if name.is_empty() {
+            return Err(anyhow::anyhow!(
+                "The name of the layout preset cannot be empty"
+            ));
+        }
+        crate::layout::save_layout_preset(
+            &self.working_directory,
+            name,
+            self.layout.layout_preset(),
+        )
+    }
+
+    fn open_restore_layout_preset_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Restore layout preset".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: crate::layout::load_layout_presets(&self.working_directory)?
+                    .into_keys()
+                    .map(|name| {
+                        DropdownItem::new(name.clone())
+                            .set_dispatches(Dispatches::one(Dispatch::RestoreLayoutPreset(name)))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::LayoutPreset,
+            None,
+        )
+    }
+
+    fn run_tests(&mut self, all: bool) -> anyhow::Result<()> {
+        let (test_runner, test_runner_language_id, path, tests) = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let buffer = editor.buffer();
+            let path = buffer
+                .path()
+                .ok_or_else(|| anyhow::anyhow!("The current buffer has no path"))?;
+            let test_runner = buffer
+                .language()
+                .and_then(|language| language.test_runner())
+                .ok_or_else(|| anyhow::anyhow!("No test runner is configured for this language"))?;
+            let tests = crate::test_runner::detect_tests(&buffer, &test_runner)?;
+            let tests = if all {
+                tests
+            } else {
+                let cursor = editor.get_cursor_position()?;
+                tests
+                    .into_iter()
+                    .filter(|test| test.range.start <= cursor && cursor <= test.range.end)
+                    .last()
+                    .into_iter()
+                    .collect_vec()
+            };
+            (
+                test_runner,
+                buffer.language().and_then(|language| language.id()),
+                path,
+                tests,
+            )
+        };
+        if tests.is_empty() {
+            return Err(anyhow::anyhow!(if all {
+                "No tests are found in this file"
+            } else {
+                "No test is under the cursor"
+            }));
+        }
+        let test_commands = test_runner_language_id
+            .and_then(|language_id| self.test_commands.get(&language_id.to_string()));
+        let command = crate::test_runner::run(
+            &test_runner,
+            test_commands,
+            &self.working_directory,
+            path,
+            tests,
+            all,
+            self.sender.clone(),
+        )?;
+        self.show_global_info(Info::new("Running tests".to_string(), command));
+        Ok(())
+    }
+
+    fn receive_test_run(&mut self, test_run: TestRun) -> anyhow::Result<()> {
+        let results =
+            crate::test_runner::parse_results(&test_run.tests, test_run.names, &test_run.output);
+        if let Some(editor) = self.layout.get_existing_editor(&test_run.path) {
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .set_test_results(results.clone());
+        }
+        let passed_count = results
+            .iter()
+            .filter(|result| result.status == TestStatus::Passed)
+            .count();
+        let failures = results
+            .into_iter()
+            .filter(|result| result.status == TestStatus::Failed)
+            .map(|result| {
+                QuickfixListItem::new(
+                    Location {
+                        path: test_run.path.clone(),
+                        range: result.test.range,
+                    },
+                    result.message.map(|message| {
+                        Info::new(format!("Test {:?} failed", result.test.name), message)
+                    }),
+                )
+            })
+            .collect_vec();
+        if failures.is_empty() {
+            let summary = if passed_count == 0 {
+                // The results cannot be parsed, so the output is shown instead
+                test_run.output
+            } else {
+                format!("{} passed", passed_count)
+            };
+            self.show_global_info(Info::new("Test results".to_string(), summary));
+            Ok(())
+        } else {
+            self.set_quickfix_list_type(
+                ResponseContext::default().set_description(&format!(
+                    "Failed tests ({} failed, {} passed)",
+                    failures.len(),
+                    passed_count
+                )),
+                QuickfixListType::Items(failures),
+            )
+        }
+    }
+
+    fn open_build_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Build command".to_string(),
+                on_enter: DispatchPrompt::RunBuild,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::Build,
+            None,
+        )
+    }
+
+    fn receive_build_output(&mut self, output: String) -> anyhow::Result<()> {
+        let mut diagnostics: BTreeMap<CanonicalizedPath, Vec<lsp_types::Diagnostic>> =
+            BTreeMap::new();
+        for (file, diagnostic) in crate::error_format::parse(&self.error_formats, &output) {
+            // Files that cannot be found, such as those of dependencies, are ignored
+            if let Ok(path) = self.working_directory.join(&file) {
+                diagnostics.entry(path).or_default().push(diagnostic);
+            }
+        }
+        for path in std::mem::take(&mut self.build_diagnostics_paths) {
+            if !diagnostics.contains_key(&path) {
+                self.update_build_diagnostics(path, Vec::new())?;
+            }
+        }
+        self.build_diagnostics_paths = diagnostics.keys().cloned().collect();
+        if diagnostics.is_empty() {
+            self.show_global_info(Info::new(
+                "Build".to_string(),
+                "No diagnostics are found in the build output".to_string(),
+            ));
+            return Ok(());
+        }
+        for (path, diagnostics) in diagnostics {
+            self.update_build_diagnostics(path, diagnostics)?;
+        }
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("Build diagnostics"),
+            QuickfixListType::Diagnostic(DiagnosticSeverityRange::All),
+        )
+    }
+
+    /// Returns the diagnostics of all opened files, grouped by their file and severity.
+    /// The source of each diagnostic is displayed, so that the picker can be filtered by source.
+    fn diagnostics_picker_items(&self) -> Vec<DropdownItem> {
+        use lsp_types::DiagnosticSeverity;
+        self.layout
+            .buffers()
+            .into_iter()
+            .flat_map(|buffer| {
+                let buffer = buffer.borrow();
+                let Some(path) = buffer.path() else {
+                    return Vec::new();
+                };
+                buffer
+                    .diagnostics()
+                    .into_iter()
+                    .filter_map(|diagnostic| {
+                        let range = buffer
+                            .char_index_range_to_position_range(diagnostic.range)
+                            .ok()?;
+                        let (rank, severity) = match diagnostic.severity {
+                            Some(DiagnosticSeverity::ERROR) => (0, "Error"),
+                            Some(DiagnosticSeverity::WARNING) => (1, "Warning"),
+                            Some(DiagnosticSeverity::INFORMATION) => (2, "Information"),
+                            Some(DiagnosticSeverity::HINT) => (3, "Hint"),
+                            _ => (4, "Unknown"),
+                        };
+                        let source = diagnostic
+                            .original_value
+                            .as_ref()
+                            .and_then(|diagnostic| diagnostic.source.clone())
+                            .map(|source| format!("[{source}] "))
+                            .unwrap_or_default();
+                        Some(
+                            DropdownItem::new(format!(
+                                "{}:{} {}{}",
+                                range.start.line + 1,
+                                range.start.column + 1,
+                                source,
+                                diagnostic.message.lines().next().unwrap_or_default()
+                            ))
+                            .set_group(Some(format!(
+                                "{} ({})",
+                                path.try_display_relative(),
+                                severity
+                            )))
+                            .set_info(Some(Info::new(
+                                severity.to_string(),
+                                diagnostic.message.clone(),
+                            )))
+                            .set_rank(Some(Box::new([rank, range.start.line, range.start.column])))
+                            .set_dispatches(Dispatches::one(Dispatch::GotoLocation(Location {
+                                path: path.clone(),
+                                range,
+                            }))),
+                        )
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    fn open_diagnostics_picker(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Diagnostics".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: self.diagnostics_picker_items(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::Diagnostics,
+            None,
+        )
+    }
+
+    /// Updates the items of the diagnostics picker if it is the current component,
+    /// so that it reflects the latest diagnostics.
+    fn refresh_diagnostics_picker(&mut self) -> anyhow::Result<()> {
+        let items = self.diagnostics_picker_items();
+        let component = self.current_component();
+        let dispatches = {
+            let mut component = component.borrow_mut();
+            match component.as_any_mut().downcast_mut::<Prompt>() {
+                Some(prompt) if prompt.prompt_history_key() == PromptHistoryKey::Diagnostics => {
+                    prompt.set_items(items)
+                }
+                _ => return Ok(()),
+            }
+        };
+        self.handle_dispatches(dispatches)
+    }
+
+    fn open_bookmark_note_prompt(&mut self) -> anyhow::Result<()> {
+        let current_note = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let range = editor.selection_set.primary_selection().extended_range();
+            editor
+                .buffer()
+                .bookmarks()
+                .into_iter()
+                .find(|bookmark| bookmark.range == range)
+                .and_then(|bookmark| bookmark.note)
+        };
+        self.open_prompt(
+            PromptConfig {
+                title: "Bookmark note".to_string(),
+                on_enter: DispatchPrompt::SetBookmarkNote,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: current_note.is_none(),
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::BookmarkNote,
+            current_note,
+        )
+    }
+
+    /// The bookmarks of opened files are taken from their buffers, as their ranges might have been
+    /// shifted by unsaved edits, while the bookmarks of the other files are the persisted ones.
+    fn bookmarks_picker_items(&self) -> anyhow::Result<Vec<DropdownItem>> {
+        let opened = self
+            .layout
+            .buffers()
+            .into_iter()
+            .filter_map(|buffer| {
+                let buffer = buffer.borrow();
+                Some((buffer.path()?, buffer.bookmarks(), buffer.content()))
+            })
+            .collect_vec();
+        let persisted = self
+            .context
+            .persistence()
+            .workspace()
+            .map(|workspace| crate::bookmark::load_all(workspace.as_ref()))
+            .transpose()?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(path, _)| {
+                !opened
+                    .iter()
+                    .any(|(opened_path, _, _)| opened_path.as_ref() == path.as_path())
+            })
+            .filter_map(|(path, bookmarks)| {
+                let path: CanonicalizedPath = path.try_into().ok()?;
+                let content = path.read().ok()?;
+                Some((path, bookmarks, content))
+            })
+            .collect_vec();
+        Ok(opened
+            .into_iter()
+            .chain(persisted)
+            .flat_map(|(path, bookmarks, content)| {
+                let buffer = Buffer::new(None, &content);
+                bookmarks
+                    .into_iter()
+                    .filter_map(|bookmark| {
+                        let range = buffer
+                            .char_index_range_to_position_range(bookmark.range)
+                            .ok()?;
+                        let text = buffer.slice(&bookmark.range).ok()?.to_string();
+                        let text = text.lines().next().unwrap_or_default().trim().to_string();
+                        Some(
+                            DropdownItem::new(format!(
+                                "{}:{} {}",
+                                range.start.line + 1,
+                                range.start.column + 1,
+                                bookmark.note.clone().unwrap_or_else(|| text.clone())
+                            ))
+                            .set_group(Some(path.try_display_relative()))
+                            .set_info(bookmark.note.map(|note| {
+                                Info::new("Bookmark".to_string(), format!("{note}\n\n{text}"))
+                            }))
+                            .set_rank(Some(Box::new([range.start.line, range.start.column])))
+                            .set_dispatches(Dispatches::one(Dispatch::GotoLocation(Location {
+                                path: path.clone(),
+                                range,
+                            }))),
+                        )
+                    })
+                    .collect_vec()
+            })
+            .collect_vec())
+    }
+
+    fn open_bookmarks_picker(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Bookmarks".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: self.bookmarks_picker_items()?,
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::Bookmarks,
+            None,
+        )
+    }
+
+    fn open_breadcrumbs_prompt(&mut self) -> anyhow::Result<()> {
+        let (path, breadcrumbs) = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            (
+                editor.buffer().path(),
+                editor
+                    .buffer()
+                    .breadcrumbs(editor.get_cursor_char_index())?,
+            )
+        };
+        let path = path.ok_or_else(|| anyhow::anyhow!("The current buffer has no path"))?;
+        self.open_prompt(
+            PromptConfig {
+                title: "Breadcrumbs".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: breadcrumbs
+                    .into_iter()
+                    .map(|breadcrumb| {
+                        DropdownItem::new(breadcrumb.name).set_dispatches(Dispatches::one(
+                            Dispatch::GotoLocation(Location {
+                                path: path.clone(),
+                                range: breadcrumb.range,
+                            }),
+                        ))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::Breadcrumbs,
+            None,
+        )
+    }
+
+    fn restore_layout_preset(&mut self, name: &str) -> anyhow::Result<()> {
+        let preset = crate::layout::load_layout_presets(&self.working_directory)?
+            .remove(name)
+            .ok_or_else(|| anyhow::anyhow!("No layout preset is named {:?}", name))?;
+        self.layout.apply_layout_preset(&preset)
+    }
+
+    fn export_keymap_cheat_sheet(&mut self, path: &Path) -> anyhow::Result<()> {
+        let path = self.working_directory.to_path_buf().join(path);
+        self.keymap_cheat_sheet().export(&path)?;
+        self.show_global_info(Info::new(
+            "Keymap cheat sheet".to_string(),
+            format!("Exported to {}", path.display()),
+        ));
+        Ok(())
+    }
+
+    /// Exports the primary selection if `selection` is true, otherwise the whole buffer.
+    fn export_highlighted(&mut self, path: &Path, selection: bool) -> anyhow::Result<()> {
+        let path = self.working_directory.to_path_buf().join(path);
+        let content = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let buffer = editor.buffer();
+            let range = if selection {
+                editor.selection_set.primary_selection().extended_range()
+            } else {
+                (CharIndex(0)..CharIndex(buffer.len_chars())).into()
+            };
+            crate::export::export(
+                &buffer,
+                range,
+                self.context.theme(),
+                crate::export::ExportFormat::from_path(&path),
+            )?
+        };
+        std::fs::write(&path, content)?;
+        self.show_global_info(Info::new(
+            "Export".to_string(),
+            format!("Exported to {}", path.display()),
+        ));
+        Ok(())
+    }
+
+    fn set_quickfix_list_filter(&mut self, filter: Option<String>) -> anyhow::Result<()> {
+        let filter = filter
+            .filter(|filter| !filter.is_empty())
+            .map(|filter| regex::Regex::new(&filter))
+            .transpose()?;
+        if let Some(state) = self.context.quickfix_list_state_mut() {
+            state.filter = filter;
+            state.current_item_index = 0;
+        }
+        self.goto_quickfix_list_item(Movement::Current)
+    }
+
+    fn remove_current_quickfix_list_item(&mut self) -> anyhow::Result<()> {
+        let Some(location) = self
+            .get_quickfix_list()
+            .and_then(|quickfix_list| quickfix_list.current_item_location())
+        else {
+            return Ok(());
+        };
+        if let Some(buffer) = self
+            .layout
+            .buffers()
+            .into_iter()
+            .find(|buffer| buffer.borrow().path().as_ref() == Some(&location.path))
+        {
+            buffer
+                .borrow_mut()
+                .remove_quickfix_list_item(location.range);
+        }
+        self.goto_quickfix_list_item(Movement::Current)
+    }
+
+    fn handle_workspace_edit(&mut self, workspace_edit: WorkspaceEdit) -> anyhow::Result<()> {
+        if !workspace_edit.requires_preview() {
+            return self.apply_workspace_edit(workspace_edit);
+        }
+        let previous_path = self.current_component().borrow().editor().buffer().path();
+        self.pending_workspace_edit = Some(PendingWorkspaceEdit {
+            workspace_edit,
+            previous_path,
+            preview_component_id: None,
+        });
+        self.show_workspace_edit_preview()
+    }
+
+    fn show_workspace_edit_preview(&mut self) -> anyhow::Result<()> {
+        let Some(workspace_edit) = self
+            .pending_workspace_edit
+            .as_ref()
+            .map(|pending| pending.workspace_edit.clone())
+        else {
+            return Ok(());
+        };
+        let mut sections = Vec::new();
+        for path in workspace_edit.affected_paths() {
+            let component = self.open_file(&path, OpenFileOption::Background)?;
+            let component = component.borrow();
+            let buffer = component.editor().buffer();
+            let hunks = workspace_edit
+                .edits
+                .iter()
+                .filter(|edit| edit.path == path)
+                .flat_map(|edit| edit.edits.iter())
+                .map(|edit| {
+                    let old_text = buffer
+                        .position_range_to_char_index_range(&edit.range)
+                        .and_then(|range| buffer.slice(&range))
+                        .map(|old_text| old_text.to_string())
+                        .unwrap_or_default();
+                    format!(
+                        "- {}:{} {:?} → {:?}",
+                        edit.range.start.line + 1,
+                        edit.range.start.column + 1,
+                        old_text,
+                        edit.new_text
+                    )
+                })
+                .join("\n");
+            sections.push(format!("## {}\n\n{}", self.display_path(&path), hunks))
+        }
+        use crate::lsp::workspace_edit::ResourceOperation;
+        if !workspace_edit.resource_operations.is_empty() {
+            let operations = workspace_edit
+                .resource_operations
+                .iter()
+                .map(|operation| match operation {
+                    ResourceOperation::Create(path) => format!("- Create {}", path),
+                    ResourceOperation::Rename { old, new } => {
+                        format!("- Rename {} → {}", self.display_path(old), new.display())
+                    }
+                    ResourceOperation::Delete(path) => {
+                        format!("- Delete {}", self.display_path(path))
+                    }
+                })
+                .join("\n");
+            sections.push(format!("## File operations\n\n{}", operations))
+        }
+        let content = format!(
+            "# Workspace edit ({} files)\n\nPress `a` to apply, `f` to reject the edits of a file, or `x` to discard.\n\n{}",
+            workspace_edit.affected_paths().len(),
+            sections.join("\n\n")
+        );
+        let mut buffer = Buffer::new(Some(tree_sitter_md::language()), &content);
+        buffer.set_read_only(true);
+        let mut editor = SuggestiveEditor::from_buffer(
+            Rc::new(RefCell::new(buffer)),
+            SuggestiveEditorFilter::CurrentWord,
+        );
+        editor.set_title("Workspace edit preview".to_string());
+        if let Some(pending) = self.pending_workspace_edit.as_mut() {
+            pending.preview_component_id = Some(editor.id());
+        }
+        self.layout
+            .replace_and_focus_current_suggestive_editor(Rc::new(RefCell::new(editor)));
+        self.show_keymap_legend(KeymapLegendConfig {
+            title: "Workspace edit".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(&[
+                    Keymap::new("a", "Apply".to_string(), Dispatch::AcceptWorkspaceEdit),
+                    Keymap::new(
+                        "f",
+                        "Reject file".to_string(),
+                        Dispatch::OpenRejectWorkspaceEditFilePrompt,
+                    ),
+                    Keymap::new("x", "Discard".to_string(), Dispatch::DiscardWorkspaceEdit),
+                ]),
+            },
+        });
+        Ok(())
+    }
+
+    /// Returns true if the key event is consumed by the workspace edit preview.
+    ///
+    /// The actions of the keymap legend shown with the preview are also bound on the preview itself,
+    /// so that they stay available after the legend is dismissed.
+    /// The pending workspace edit is dropped once its preview is closed.
+    fn handle_workspace_edit_preview_key(
+        &mut self,
+        key_event: &event::KeyEvent,
+    ) -> anyhow::Result<bool> {
+        let Some(preview_component_id) = self
+            .pending_workspace_edit
+            .as_ref()
+            .and_then(|pending| pending.preview_component_id)
+        else {
+            return Ok(false);
+        };
+        if !self
+            .components()
+            .iter()
+            .any(|component| component.component().borrow().id() == preview_component_id)
+        {
+            self.pending_workspace_edit = None;
+            return Ok(false);
+        }
+        if self.current_component().borrow().id() != preview_component_id {
+            return Ok(false);
+        }
+        let dispatch = match self.context.keyboard_layout().to_qwerty(key_event.clone()) {
+            key!("a") => Dispatch::AcceptWorkspaceEdit,
+            key!("f") => Dispatch::OpenRejectWorkspaceEditFilePrompt,
+            key!("x") => Dispatch::DiscardWorkspaceEdit,
+            _ => return Ok(false),
+        };
+        self.handle_dispatch(dispatch)?;
+        Ok(true)
+    }
+
+    fn display_path(&self, path: &CanonicalizedPath) -> String {
+        path.display_relative_to(&self.working_directory)
+            .unwrap_or_else(|_| path.display_absolute())
+    }
+
+    fn accept_workspace_edit(&mut self) -> anyhow::Result<()> {
+        let Some(PendingWorkspaceEdit { workspace_edit, .. }) = self.pending_workspace_edit.take()
+        else {
+            return Ok(());
+        };
+        let affected_paths = workspace_edit.affected_paths();
+        self.apply_workspace_edit(workspace_edit)?;
+        if let Some(path) = affected_paths.first() {
+            self.open_file(path, OpenFileOption::Focus)?;
+        }
+        Ok(())
+    }
+
+    fn discard_workspace_edit(&mut self) -> anyhow::Result<()> {
+        if let Some(PendingWorkspaceEdit {
+            previous_path: Some(path),
+            ..
+        }) = self.pending_workspace_edit.take()
+        {
+            self.open_file(&path, OpenFileOption::FocusNoHistory)?;
+        }
+        Ok(())
+    }
+
+    fn open_reject_workspace_edit_file_prompt(&mut self) -> anyhow::Result<()> {
+        let Some(pending) = self.pending_workspace_edit.as_ref() else {
+            return Ok(());
+        };
+        let items = pending
+            .workspace_edit
+            .affected_paths()
+            .into_iter()
+            .map(|path| {
+                DropdownItem::new(self.display_path(&path))
+                    .set_dispatches(Dispatches::one(Dispatch::RejectWorkspaceEditFile(path)))
+            })
+            .collect();
+        self.open_prompt(
+            PromptConfig {
+                title: "Reject edits of file".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items,
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::RejectWorkspaceEditFile,
+            None,
+        )
+    }
+
+    fn reject_workspace_edit_file(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let Some(pending) = self.pending_workspace_edit.take() else {
+            return Ok(());
+        };
+        let pending = PendingWorkspaceEdit {
+            workspace_edit: pending.workspace_edit.reject_path(path),
+            ..pending
+        };
+        if pending.workspace_edit.is_empty() {
+            self.pending_workspace_edit = Some(pending);
+            return self.discard_workspace_edit();
+        }
+        self.pending_workspace_edit = Some(pending);
+        self.show_workspace_edit_preview()
+    }
+
+    /// The edits are only applied if all of them are valid,
+    /// so that an invalid edit won't leave the workspace half-edited.
+    fn apply_workspace_edit(&mut self, workspace_edit: WorkspaceEdit) -> Result<(), anyhow::Error> {
+        for edit in &workspace_edit.edits {
+            let component = self.open_file(&edit.path, OpenFileOption::Background)?;
+            let component = component.borrow();
+            let buffer = component.editor().buffer();
+            for positional_edit in &edit.edits {
+                buffer
+                    .position_range_to_char_index_range(&positional_edit.range)
+                    .map_err(|error| {
+                        anyhow::anyhow!(
+                            "Workspace edit is not applied: invalid edit range {:?} in {}: {}",
+                            positional_edit.range,
+                            self.display_path(&edit.path),
+                            error
+                        )
+                    })?;
+            }
+        }
+        for edit in workspace_edit.edits {
+            let component = self.open_file(&edit.path, OpenFileOption::Background)?;
+            let dispatches = component
+                .borrow_mut()
+                .editor_mut()
+                .apply_positional_edits(edit.edits)?;
+
+            self.handle_dispatches(dispatches)?;
+
+            let dispatches = component.borrow_mut().editor_mut().save()?;
+
+            self.handle_dispatches(dispatches)?;
+        }
+        use crate::lsp::workspace_edit::ResourceOperation;
+        for operation in workspace_edit.resource_operations {
+            match operation {
+                ResourceOperation::Create(path) => self.add_path(path)?,
+                ResourceOperation::Rename { old, new } => self.move_file(old, new)?,
+                ResourceOperation::Delete(path) => self.delete_path(&path)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn show_keymap_legend(&mut self, keymap_legend_config: KeymapLegendConfig) {
+        self.layout.show_keymap_legend(keymap_legend_config)
+    }
+
+    /// One walk per root of the workspace, restricted by the global search config.
+    fn global_search_walk_builder_configs(&self) -> anyhow::Result<Vec<WalkBuilderConfig>> {
+        let global_search_config = self.context.global_search_config();
+        WalkBuilderConfig::workspace(
+            &self.working_directory,
+            global_search_config.include_glob(),
+            global_search_config.exclude_glob(),
+        )?
+        .into_iter()
+        .map(|walk_builder_config| {
+            Ok(WalkBuilderConfig {
+                scope: global_search_config.scope_globs()?,
+                ..walk_builder_config
+            })
+        })
+        .collect()
+    }
+
+    fn global_replace(&mut self) -> anyhow::Result<()> {
+        let config = self.context.global_search_config().local_config();
+        let affected_paths = self
+            .global_search_walk_builder_configs()?
+            .into_iter()
+            .map(|walk_builder_config| {
+                list::grep::replace(
+                    walk_builder_config,
+                    config.clone(),
+                    self.context.persistence().clone(),
+                )
+            })
+            .flatten_ok()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.layout.reload_buffers(affected_paths)
+    }
+
+    fn global_search(&mut self) -> anyhow::Result<()> {
+        let working_directory = self.working_directory.clone();
+
+        let config = self.context.global_search_config().local_config();
+        if config.search().is_empty() {
+            return Ok(());
+        }
+        let mut locations = Vec::new();
+        let mut errors = Vec::new();
+        for walk_builder_config in self.global_search_walk_builder_configs()? {
+            match config.effective_mode() {
+                LocalSearchConfigMode::Regex(regex) => {
+                    let output = list::grep::run(&config.search(), walk_builder_config, regex)?;
+                    locations.extend(output.locations);
+                    errors.extend(output.errors);
+                }
+                LocalSearchConfigMode::AstGrep => locations.extend(list::ast_grep::run(
+                    config.search().clone(),
+                    walk_builder_config,
+                )?),
+                LocalSearchConfigMode::CaseAgnostic => locations.extend(list::case_agnostic::run(
+                    config.search().clone(),
+                    walk_builder_config,
+                )?),
+            }
+        }
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description(&format!(
+                "Global search ({}): {} match(es) in {} file(s)",
+                config.search(),
+                locations.len(),
+                locations
+                    .iter()
+                    .map(|location| &location.path)
+                    .unique()
+                    .count()
+            )),
+            QuickfixListType::Items(
+                locations
+                    .into_iter()
+                    .map(|location| QuickfixListItem::new(location, None))
+                    .collect_vec(),
+            ),
+        )?;
+        if !errors.is_empty() {
+            self.show_global_info(Info::new(
+                "Global search".to_string(),
+                format!(
+                    "Failed to search {} file(s):\n{}",
+                    errors.len(),
+                    errors
+                        .iter()
+                        .map(|error| format!(
+                            "{}: {}",
+                            error
+                                .path
+                                .strip_prefix(&working_directory)
+                                .unwrap_or(&error.path)
+                                .display(),
+                            error.message
+                        ))
+                        .join("\n")
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn collect_annotations(&mut self) -> anyhow::Result<()> {
+        let items = WalkBuilderConfig::workspace(&self.working_directory, None, None)?
+            .into_iter()
+            .map(list::annotation::run)
+            .flatten_ok()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("Annotations"),
+            QuickfixListType::Items(items),
+        )
+    }
+
+    pub(crate) fn quit_all(&self) -> Result<(), anyhow::Error> {
+        Ok(self.sender.send(AppMessage::QuitAll)?)
+    }
+
+    pub(crate) fn sender(&self) -> Sender<AppMessage> {
+        self.sender.clone()
+    }
+
+    /// The gate through which terminal events must be read.
+    pub(crate) fn input_gate(&self) -> Arc<InputGate> {
+        self.input_gate.clone()
+    }
+
+    fn run_command(&mut self, command: String) -> anyhow::Result<()> {
         let dispatch = crate::command::find(&command)
             .map(|cmd| cmd.dispatch())
             .ok_or_else(|| anyhow::anyhow!("Unknown command: {}", command))?;
@@ -1274,6 +3534,52 @@ impl<T: Frontend> App<T> {
         }))
     }
 
+    fn copy_path(&mut self, path: &CanonicalizedPath, format: PathFormat) -> anyhow::Result<()> {
+        let relative_path = || {
+            path.display_relative_to(&self.working_directory)
+                .unwrap_or_else(|_| path.display_absolute())
+        };
+        let text = match format {
+            PathFormat::Relative => relative_path(),
+            PathFormat::Absolute => path.display_absolute(),
+            PathFormat::RelativeWithLine(line) => format!("{}:{}", relative_path(), line + 1),
+            PathFormat::FileName => path
+                .to_path_buf()
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(relative_path),
+            PathFormat::Permalink {
+                start_line,
+                end_line,
+            } => self.permalink(path, start_line, end_line)?,
+        };
+        self.context
+            .set_clipboard_content(CopiedTexts::one(text.clone()), true)?;
+        let title = match format {
+            PathFormat::Permalink { .. } => "Copied permalink",
+            _ => "Copied path",
+        };
+        self.show_global_info(Info::new(title.to_string(), text));
+        Ok(())
+    }
+
+    fn permalink(
+        &self,
+        path: &CanonicalizedPath,
+        start_line: usize,
+        end_line: usize,
+    ) -> anyhow::Result<String> {
+        let repo = self.git_repo_containing(path)?;
+        crate::permalink::permalink(
+            &crate::project_config::ProjectConfig::load(&self.working_directory)?.permalinks,
+            &repo.remote_url()?,
+            &repo.head_commit_hash()?,
+            &repo.relative_path(path)?.display().to_string(),
+            start_line,
+            end_line,
+        )
+    }
+
     fn delete_path(&mut self, path: &CanonicalizedPath) -> anyhow::Result<()> {
         if path.is_dir() {
             std::fs::remove_dir_all(path)?;
@@ -1281,27 +3587,127 @@ impl<T: Frontend> App<T> {
             std::fs::remove_file(path)?;
         }
         self.layout.remove_suggestive_editor(path);
-        self.layout.refresh_file_explorer(&self.working_directory)?;
+        self.layout
+            .refresh_file_explorer_paths(&self.working_directory, &[path.to_path_buf()])?;
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceDidDeleteFiles { path: path.clone() })?;
         Ok(())
     }
 
     fn move_file(&mut self, from: CanonicalizedPath, to: PathBuf) -> anyhow::Result<()> {
         use std::fs;
+        let is_dir = from.is_dir();
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceWillRenameFiles {
+                old: from.clone(),
+                new: to.clone(),
+            })?;
         self.add_path_parent(&to)?;
         fs::rename(from.clone(), to.clone())?;
-        self.layout.refresh_file_explorer(&self.working_directory)?;
-        let to = to.try_into()?;
+        self.layout
+            .refresh_file_explorer_paths(&self.working_directory, &[from.to_path_buf(), &to])?;
+        let to: CanonicalizedPath = to.try_into()?;
+        if is_dir {
+            self.layout.move_suggestive_editors(&from, &to)?;
+        }
         self.reveal_path_in_explorer(&to)?;
-        self.lsp_manager.send_message(
-            from.clone(),
-            FromEditor::WorkspaceDidRenameFiles {
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceDidRenameFiles {
                 old: from.clone(),
                 new: to,
-            },
+            })?;
+        if !is_dir {
+            self.layout.remove_suggestive_editor(&from);
+        }
+        Ok(())
+    }
+    /// Creates the file of `to`, which must not exist yet, so that it can be canonicalized.
+    fn create_new_file(&self, to: &Path) -> anyhow::Result<CanonicalizedPath> {
+        if to.exists() {
+            return Err(anyhow::anyhow!(
+                "The path \"{}\" already exists",
+                to.display()
+            ));
+        }
+        self.add_path_parent(to)?;
+        std::fs::File::create(to)?;
+        to.try_into()
+    }
+
+    fn save_as(&mut self, to: PathBuf) -> anyhow::Result<()> {
+        let from = self.current_buffer_path().ok();
+        let to = self.create_new_file(&self.working_directory.to_path_buf().join(to))?;
+        self.current_component()
+            .borrow_mut()
+            .editor_mut()
+            .buffer_mut()
+            .set_path(to.clone());
+        match from {
+            Some(from) => self.layout.rename_suggestive_editor(&from, to.clone()),
+            None => {
+                // A scratch buffer is titled by its number until it is saved
+                self.current_component()
+                    .borrow_mut()
+                    .editor_mut()
+                    .clear_title();
+                self.layout.store_saved_scratch_editors()
+            }
+        }
+        self.handle_dispatch_editor(Save)?;
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceDidCreateFiles { path: to.clone() })?;
+        self.lsp_manager.open_file(to.clone())?;
+        self.layout
+            .refresh_file_explorer_paths(&self.working_directory, &[to.to_path_buf()])?;
+        self.reveal_path_in_explorer(&to)?;
+        Ok(())
+    }
+
+    /// Unlike `move_file`, the editor of the renamed file stays open,
+    /// and the move is staged if the file is tracked by git.
+    fn rename_file(&mut self, to: PathBuf) -> anyhow::Result<()> {
+        let from = self.current_buffer_path()?;
+        let to = self.working_directory.to_path_buf().join(to);
+        if to.exists() {
+            return Err(anyhow::anyhow!(
+                "The path \"{}\" already exists",
+                to.display()
+            ));
+        }
+        // Unsaved changes are written before moving, so that they are not lost
+        self.handle_dispatch_editor(Save)?;
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceWillRenameFiles {
+                old: from.clone(),
+                new: to.clone(),
+            })?;
+        self.add_path_parent(&to)?;
+        std::fs::rename(&from, &to)?;
+        let to: CanonicalizedPath = to.try_into()?;
+        if let Ok(repo) = git::GitRepo::try_from(&self.working_directory) {
+            if let Err(error) = repo.stage_move(&from, &to) {
+                log::error!("Failed to stage the move of {:?}: {:?}", from, error);
+            }
+        }
+        self.current_component()
+            .borrow_mut()
+            .editor_mut()
+            .buffer_mut()
+            .set_path(to.clone());
+        self.layout.rename_suggestive_editor(&from, to.clone());
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceDidRenameFiles {
+                old: from.clone(),
+                new: to.clone(),
+            })?;
+        self.layout.refresh_file_explorer_paths(
+            &self.working_directory,
+            &[from.to_path_buf(), to.to_path_buf()],
         )?;
-        self.layout.remove_suggestive_editor(&from);
+        self.reveal_path_in_explorer(&to)?;
         Ok(())
     }
+
     fn add_path_parent(&self, path: &Path) -> anyhow::Result<()> {
         if let Some(new_dir) = path.parent() {
             std::fs::create_dir_all(new_dir)?;
@@ -1309,6 +3715,48 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    /// The file picker, global search, file explorer, git status and the title follow the new working directory.
+    /// Language servers are restarted in it, and the opened files are opened again in them.
+    fn change_working_directory(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        let working_directory: CanonicalizedPath =
+            self.working_directory.to_path_buf().join(path).try_into()?;
+        if !working_directory.to_path_buf().is_dir() {
+            return Err(anyhow::anyhow!(
+                "\"{}\" is not a directory",
+                working_directory.display_absolute()
+            ));
+        }
+        // Shell commands inherit the working directory of the process.
+        // It is left untouched in tests, because it is shared by the tests running in parallel.
+        #[cfg(not(test))]
+        std::env::set_current_dir(&working_directory)?;
+        self.working_directory = working_directory.clone();
+        self.context
+            .set_current_working_directory(working_directory.clone());
+        self.layout.set_working_directory(&working_directory)?;
+        // The persisted state belongs to the workspace, so the state of the new workspace is restored
+        let persistence = self
+            .context
+            .persistence()
+            .clone()
+            .set_workspace(working_directory.clone());
+        for buffer in self.layout.buffers() {
+            buffer.borrow_mut().update_persistence(persistence.clone())
+        }
+        self.set_persistence(persistence);
+        self.lsp_manager = LspManager::new(self.sender.clone(), working_directory.clone());
+        if self.enable_lsp {
+            for path in self.layout.get_opened_files() {
+                self.lsp_manager.open_file(path)?;
+            }
+        }
+        self.show_global_info(Info::new(
+            "Working directory".to_string(),
+            working_directory.display_absolute(),
+        ));
+        Ok(())
+    }
+
     fn add_path(&mut self, path: String) -> anyhow::Result<()> {
         if PathBuf::from(path.clone()).exists() {
             return Err(anyhow::anyhow!("The path \"{}\" already exists", path));
@@ -1320,8 +3768,12 @@ impl<T: Frontend> App<T> {
             self.add_path_parent(&path)?;
             std::fs::File::create(&path)?;
         }
-        self.layout.refresh_file_explorer(&self.working_directory)?;
-        self.reveal_path_in_explorer(&path.try_into()?)?;
+        let path: CanonicalizedPath = path.try_into()?;
+        self.lsp_manager
+            .broadcast_message(FromEditor::WorkspaceDidCreateFiles { path: path.clone() })?;
+        self.layout
+            .refresh_file_explorer_paths(&self.working_directory, &[path.to_path_buf()])?;
+        self.reveal_path_in_explorer(&path)?;
 
         Ok(())
     }
@@ -1342,6 +3794,29 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    fn evict_background_buffers(&mut self) -> anyhow::Result<()> {
+        let restored = self
+            .layout
+            .evict_background_buffers(self.loaded_buffers_limit, EVICTED_UNDO_HISTORY_LIMIT)?;
+        for editor in restored {
+            let editor = editor.borrow();
+            let buffer = editor.editor().buffer();
+            if let Some(language) = buffer.language() {
+                self.pending_syntax_highlight_requests
+                    .insert(editor.id(), (language, buffer.content()));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_syntax_highlight_requests(&mut self) -> anyhow::Result<()> {
+        std::mem::take(&mut self.pending_syntax_highlight_requests)
+            .into_iter()
+            .try_for_each(|(component_id, (language, content))| {
+                self.request_syntax_highlight(component_id, language, content)
+            })
+    }
+
     #[cfg(test)]
     pub(crate) fn get_current_selected_texts(&self) -> Vec<String> {
         let _content = self.current_component().borrow().content();
@@ -1351,72 +3826,353 @@ impl<T: Frontend> App<T> {
             .get_selected_texts()
     }
 
-    #[cfg(test)]
-    pub(crate) fn get_file_content(&self, path: &CanonicalizedPath) -> String {
-        self.layout
-            .get_existing_editor(path)
-            .unwrap()
-            .borrow()
-            .content()
+    #[cfg(test)]
+    pub(crate) fn get_file_content(&self, path: &CanonicalizedPath) -> String {
+        self.layout
+            .get_existing_editor(path)
+            .unwrap()
+            .borrow()
+            .content()
+    }
+
+    pub(crate) fn handle_dispatch_editor(
+        &mut self,
+        dispatch_editor: DispatchEditor,
+    ) -> anyhow::Result<()> {
+        self.handle_dispatch_editor_custom(dispatch_editor, self.current_component())
+    }
+
+    fn handle_dispatch_editor_custom(
+        &mut self,
+        dispatch_editor: DispatchEditor,
+        component: Rc<RefCell<dyn Component>>,
+    ) -> anyhow::Result<()> {
+        let cursor_row = component.borrow().editor().cursor_row();
+        let dispatches = component
+            .borrow_mut()
+            .handle_dispatch_editor(&mut self.context, dispatch_editor)?;
+        if self.context.auto_center() {
+            component
+                .borrow_mut()
+                .editor_mut()
+                .center_cursor_after_large_movement(cursor_row)
+        }
+
+        self.handle_dispatches(dispatches)?;
+        Ok(())
+    }
+
+    /// The git repositories of the roots of the workspace, see `ProjectConfig::roots`.
+    ///
+    /// Only the working directory must be a git repository,
+    /// extra workspace folders that are not git repositories are skipped.
+    fn git_repo(&self) -> anyhow::Result<git::GitRepo> {
+        git::GitRepo::try_from(&self.working_directory)
+    }
+
+    /// The first of `Self::git_repos` that contains `path`.
+    fn git_repo_containing(&self, path: &CanonicalizedPath) -> anyhow::Result<git::GitRepo> {
+        self.git_repos()?
+            .into_iter()
+            .find(|repo| repo.relative_path(path).is_ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!("{} is not in a Git repository", path.display_absolute())
+            })
+    }
+
+    fn git_repos(&self) -> anyhow::Result<Vec<git::GitRepo>> {
+        let roots = crate::project_config::ProjectConfig::roots(&self.working_directory)?;
+        Ok(Some(self.git_repo()?)
+            .into_iter()
+            .chain(
+                roots
+                    .iter()
+                    .skip(1)
+                    .filter_map(|root| git::GitRepo::try_from(root).ok()),
+            )
+            .collect())
+    }
+
+    fn open_git_hunk_baseline_prompt(&mut self, scope: Scope) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Git hunk baseline".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: git::DiffMode::all()
+                    .into_iter()
+                    .map(|diff_mode| {
+                        DropdownItem::new(diff_mode.display()).set_dispatches(Dispatches::one(
+                            match scope {
+                                Scope::Global => Dispatch::GetRepoGitHunks(diff_mode),
+                                Scope::Local => {
+                                    Dispatch::ToEditor(DispatchEditor::SetSelectionMode(
+                                        SelectionMode::GitHunk(diff_mode),
+                                    ))
+                                }
+                            },
+                        ))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::GitHunkBaseline,
+            None,
+        )
+    }
+
+    fn get_repo_git_hunks(&mut self, diff_mode: git::DiffMode) -> anyhow::Result<()> {
+        let diffs = self
+            .git_repos()?
+            .into_iter()
+            .map(|repo| repo.diffs(diff_mode.clone()))
+            .flatten_ok()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("Git Hunks"),
+            QuickfixListType::Items(
+                diffs
+                    .into_iter()
+                    .flat_map(|file_diff| {
+                        file_diff
+                            .hunks()
+                            .iter()
+                            .map(|hunk| {
+                                let line_range = hunk.line_range();
+                                let location = Location {
+                                    path: file_diff.path().clone(),
+                                    range: Position {
+                                        line: line_range.start,
+                                        column: 0,
+                                    }..Position {
+                                        line: line_range.end,
+                                        column: 0,
+                                    },
+                                };
+                                QuickfixListItem::new(location, hunk.to_info())
+                            })
+                            .collect_vec()
+                    })
+                    .collect_vec(),
+            ),
+        )
+    }
+
+    #[cfg(test)]
+    fn set_global_title(&mut self, title: String) {
+        self.global_title = Some(title)
+    }
+
+    pub(crate) fn set_show_fps(&mut self, show_fps: bool) {
+        self.show_fps = show_fps
+    }
+
+    pub(crate) fn set_startup_profile(&mut self, startup_profile: StartupProfile) {
+        self.startup_profile = Some(startup_profile)
+    }
+
+    pub(crate) fn set_loaded_buffers_limit(&mut self, loaded_buffers_limit: usize) {
+        self.loaded_buffers_limit = loaded_buffers_limit
+    }
+
+    pub(crate) fn set_terminal_title_enabled(&mut self, terminal_title_enabled: bool) {
+        self.terminal_title_enabled = terminal_title_enabled
+    }
+
+    pub(crate) fn set_autosave_on_focus_lost(&mut self, autosave_on_focus_lost: bool) {
+        self.autosave_on_focus_lost = autosave_on_focus_lost
+    }
+
+    pub(crate) fn set_jump_label_strategy(&mut self, strategy: JumpLabelStrategy) {
+        self.context.set_jump_label_strategy(strategy)
+    }
+
+    pub(crate) fn set_sticky_context(&mut self, sticky_context: bool) {
+        self.context.set_sticky_context(sticky_context)
+    }
+
+    pub(crate) fn set_auto_center(&mut self, auto_center: bool) {
+        self.context.set_auto_center(auto_center)
+    }
+
+    pub(crate) fn set_explorer_auto_follow(&mut self, explorer_auto_follow: bool) {
+        self.context.set_explorer_auto_follow(explorer_auto_follow)
+    }
+
+    pub(crate) fn set_scroll_lines(&mut self, scroll_lines: usize) {
+        self.context.set_scroll_lines(scroll_lines)
+    }
+
+    pub(crate) fn set_breadcrumbs(&mut self, breadcrumbs: bool) {
+        self.context.set_breadcrumbs(breadcrumbs)
+    }
+
+    pub(crate) fn set_inline_diagnostics(&mut self, inline_diagnostics: bool) {
+        self.context.set_inline_diagnostics(inline_diagnostics)
+    }
+
+    /// Restores the quickfix list, prompt histories and search toggles persisted for the workspace
+    /// of `persistence`, which applies to the files opened from now on.
+    pub(crate) fn set_persistence(&mut self, persistence: Persistence) {
+        if let Some(workspace) = persistence.workspace() {
+            match crate::quickfix_list::load_persisted(workspace) {
+                Ok(Some((title, items))) => self.context.quickfix_lists_mut().stash(
+                    QuickfixListState::new(title, QuickfixListSource::Custom).set_items(items),
+                ),
+                Ok(None) => {}
+                Err(error) => log::error!("Failed to load persisted quickfix list: {:?}", error),
+            }
+            match crate::prompt_history::load_persisted(workspace) {
+                Ok(histories) => self.context.set_prompt_histories(histories),
+                Err(error) => log::error!("Failed to load persisted prompt histories: {:?}", error),
+            }
+            match crate::search_toggles::load_persisted(workspace) {
+                Ok(toggles) => {
+                    for (scope, toggles) in toggles {
+                        self.context.set_search_toggles(scope, toggles)
+                    }
+                }
+                Err(error) => log::error!("Failed to load persisted search toggles: {:?}", error),
+            }
+        }
+        self.context.set_persistence(persistence)
+    }
+
+    /// Adds a format for parsing the output of build commands, which takes precedence over the existing formats.
+    pub(crate) fn add_error_format(&mut self, regex: &str) -> anyhow::Result<()> {
+        self.error_formats.insert(0, ErrorFormat::new(regex)?);
+        Ok(())
+    }
+
+    pub(crate) fn set_notes_directory(&mut self, notes_directory: PathBuf) {
+        self.notes_directory = notes_directory
+    }
+
+    pub(crate) fn set_url_opener(&mut self, url_opener: String) {
+        self.url_opener = url_opener
+    }
+
+    pub(crate) fn set_elevation_command(&mut self, elevation_command: String) {
+        self.elevation_command = elevation_command
+    }
+
+    pub(crate) fn set_test_commands(&mut self, test_commands: HashMap<String, TestCommands>) {
+        self.test_commands = test_commands
+    }
+
+    /// Writes the buffer of `path` using the elevation command.
+    ///
+    /// The terminal is restored while the command runs, so that it can ask for a password.
+    /// The file is overwritten in place rather than replaced, so its owner and permissions are preserved.
+    fn privileged_save(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        let Some(editor) = self.layout.get_existing_editor(&path) else {
+            return Err(anyhow::anyhow!(
+                "No buffer is opened for {}",
+                path.display_absolute()
+            ));
+        };
+        let content = editor.borrow().content();
+        let mut words = self.elevation_command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("The elevation command is empty"))?;
+        // Otherwise the keys typed into the child process (e.g. a password)
+        // would also be read as events, and inserted into the buffer
+        let input_gate = self.input_gate.clone();
+        let input_pause = input_gate.pause();
+        {
+            let mut frontend = self.frontend.lock().unwrap();
+            frontend.leave_alternate_screen()?;
+            frontend.disable_raw_mode()?;
+        }
+        let status = (|| -> anyhow::Result<std::process::ExitStatus> {
+            use std::io::Write;
+            let mut child = std::process::Command::new(program)
+                .args(words)
+                .arg(path.display_absolute())
+                .stdin(std::process::Stdio::piped())
+                .stdout(std::process::Stdio::null())
+                .spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(content.as_bytes())?;
+            }
+            Ok(child.wait()?)
+        })();
+        {
+            let mut frontend = self.frontend.lock().unwrap();
+            frontend.enable_raw_mode()?;
+            frontend.enter_alternate_screen()?;
+            frontend.clear_screen()?;
+        }
+        drop(input_pause);
+        let status = status?;
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "`{}` failed with {}",
+                self.elevation_command,
+                status
+            ));
+        }
+        editor
+            .borrow_mut()
+            .editor_mut()
+            .buffer_mut()
+            .mark_as_saved();
+        self.handle_dispatch(Dispatch::DocumentDidSave { path })
+    }
+
+    fn reload_externally_changed_buffers(&mut self) -> anyhow::Result<()> {
+        let (dispatches, conflicts) = self.layout.reload_externally_changed_buffers()?;
+        self.handle_dispatches(dispatches)?;
+        if !conflicts.is_empty() {
+            self.show_global_info(Info::new(
+                "Modified by another program".to_string(),
+                format!(
+                    "The following files are not reloaded, because they have unsaved changes:\n{}",
+                    conflicts
+                        .iter()
+                        .map(|path| path.display_absolute())
+                        .join("\n")
+                ),
+            ))
+        }
+        Ok(())
     }
 
-    pub(crate) fn handle_dispatch_editor(
-        &mut self,
-        dispatch_editor: DispatchEditor,
-    ) -> anyhow::Result<()> {
-        self.handle_dispatch_editor_custom(dispatch_editor, self.current_component())
+    fn save_modified_buffers(&mut self) -> anyhow::Result<()> {
+        let paths = self.layout.save_modified_buffers()?;
+        self.handle_dispatches(Dispatches::new(
+            paths
+                .into_iter()
+                .map(|path| Dispatch::DocumentDidSave { path })
+                .collect(),
+        ))
     }
 
-    fn handle_dispatch_editor_custom(
-        &mut self,
-        dispatch_editor: DispatchEditor,
-        component: Rc<RefCell<dyn Component>>,
-    ) -> anyhow::Result<()> {
-        let dispatches = component
-            .borrow_mut()
-            .handle_dispatch_editor(&mut self.context, dispatch_editor)?;
-
-        self.handle_dispatches(dispatches)?;
+    fn open_url(&self, url: &str) -> anyhow::Result<()> {
+        let mut words = self.url_opener.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("The URL opener is empty"))?;
+        let mut child = std::process::Command::new(program)
+            .args(words)
+            .arg(url)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+        // Wait in the background so that the exited opener does not linger as a zombie process
+        std::thread::spawn(move || child.wait());
         Ok(())
     }
 
-    fn get_repo_git_hunks(&mut self, diff_mode: git::DiffMode) -> anyhow::Result<()> {
-        let working_directory = self.working_directory.clone();
-        let repo = git::GitRepo::try_from(&working_directory)?;
-        let diffs = repo.diffs(diff_mode)?;
-        self.set_quickfix_list_type(
-            ResponseContext::default().set_description("Git Hunks"),
-            QuickfixListType::Items(
-                diffs
-                    .into_iter()
-                    .flat_map(|file_diff| {
-                        file_diff
-                            .hunks()
-                            .iter()
-                            .map(|hunk| {
-                                let line_range = hunk.line_range();
-                                let location = Location {
-                                    path: file_diff.path().clone(),
-                                    range: Position {
-                                        line: line_range.start,
-                                        column: 0,
-                                    }..Position {
-                                        line: line_range.end,
-                                        column: 0,
-                                    },
-                                };
-                                QuickfixListItem::new(location, hunk.to_info())
-                            })
-                            .collect_vec()
-                    })
-                    .collect_vec(),
-            ),
-        )
+    pub(crate) fn set_keyboard_layout(&mut self, keyboard_layout: KeyboardLayout) {
+        self.context.set_keyboard_layout(keyboard_layout)
     }
 
-    #[cfg(test)]
-    fn set_global_title(&mut self, title: String) {
-        self.global_title = Some(title)
+    pub(crate) fn set_completion_source(&mut self, completion_source: Box<dyn CompletionSource>) {
+        self.completion_source = Some(completion_source);
     }
 
     pub(crate) fn set_syntax_highlight_request_sender(
@@ -1462,6 +4218,54 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_filter_selections_prompt(&mut self, kind: FilterKind) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: match kind {
+                    FilterKind::Keep => "Keep selections matching (regex)".to_string(),
+                    FilterKind::Remove => "Remove selections matching (regex)".to_string(),
+                },
+                on_enter: DispatchPrompt::FilterSelections { kind },
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::FilterSelections,
+            None,
+        )
+    }
+
+    fn open_keep_every_nth_selection_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Keep every nth selection, starting from the first".to_string(),
+                on_enter: DispatchPrompt::KeepEveryNthSelection,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::KeepEveryNthSelection,
+            None,
+        )
+    }
+
+    fn open_split_selections_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Split selections by regex (default: newline)".to_string(),
+                on_enter: DispatchPrompt::SplitSelections,
+                items: Vec::new(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SplitSelections,
+            None,
+        )
+    }
+
     #[cfg(test)]
     pub(crate) fn context(&self) -> &Context {
         &self.context
@@ -1473,7 +4277,16 @@ impl<T: Frontend> App<T> {
         scope: Scope,
         show_legend: bool,
     ) -> Result<(), anyhow::Error> {
+        let is_toggle = matches!(
+            update,
+            LocalSearchConfigUpdate::Mode(_)
+                | LocalSearchConfigUpdate::SmartCase(_)
+                | LocalSearchConfigUpdate::PreserveCase(_)
+        );
         self.context.update_local_search_config(update, scope);
+        if is_toggle {
+            self.persist_search_toggles();
+        }
         match scope {
             Scope::Local => self.local_search()?,
             Scope::Global => {
@@ -1654,6 +4467,65 @@ impl<T: Frontend> App<T> {
                     },
                 ]
                 .into_iter()
+                .chain(global_search_confing.map(|config| {
+                    // The scope toggles capture the file type and directory of the current file when turned on
+                    let path = self.current_component().borrow().editor().buffer().path();
+                    let extension = path.as_ref().and_then(|path| {
+                        Some(
+                            path.to_path_buf()
+                                .extension()?
+                                .to_string_lossy()
+                                .to_string(),
+                        )
+                    });
+                    let directory = path.as_ref().and_then(|path| {
+                        path.parent()
+                            .ok()
+                            .flatten()?
+                            .display_relative_to(&self.working_directory)
+                            .ok()
+                            .filter(|directory| !directory.is_empty())
+                    });
+                    let toggle_keymap = |key: &'static str,
+                                         name: &str,
+                                         current: Option<&String>,
+                                         candidate: Option<String>,
+                                         update: fn(Option<String>) -> GlobalSearchConfigUpdate|
+                     -> Option<Keymap> {
+                        let value = current.cloned().or(candidate)?;
+                        Some(Keymap::new(
+                            key,
+                            show_checkbox(&format!("{name} ({value})"), current.is_some()),
+                            Dispatch::UpdateGlobalSearchConfig {
+                                update: update(current.is_none().then_some(value)),
+                            },
+                        ))
+                    };
+                    KeymapLegendSection {
+                        title: "Scope".to_string(),
+                        keymaps: Keymaps::new(
+                            &[
+                                toggle_keymap(
+                                    "t",
+                                    "Current file extension",
+                                    config.file_extension(),
+                                    extension,
+                                    GlobalSearchConfigUpdate::SetFileExtension,
+                                ),
+                                toggle_keymap(
+                                    "d",
+                                    "Current directory",
+                                    config.directory(),
+                                    directory,
+                                    GlobalSearchConfigUpdate::SetDirectory,
+                                ),
+                            ]
+                            .into_iter()
+                            .flatten()
+                            .collect_vec(),
+                        ),
+                    }
+                }))
                 .chain(regex.map(|regex| {
                     KeymapLegendSection {
                         title: "Options".to_string(),
@@ -1677,6 +4549,23 @@ impl<T: Frontend> App<T> {
                                     }),
                                     regex.match_whole_word,
                                 ),
+                                update_keymap(
+                                    "S",
+                                    show_checkbox("Smart case", local_search_config.smart_case()),
+                                    LocalSearchConfigUpdate::SmartCase(
+                                        !local_search_config.smart_case(),
+                                    ),
+                                ),
+                                update_keymap(
+                                    "p",
+                                    show_checkbox(
+                                        "Preserve case",
+                                        local_search_config.preserve_case(),
+                                    ),
+                                    LocalSearchConfigUpdate::PreserveCase(
+                                        !local_search_config.preserve_case(),
+                                    ),
+                                ),
                             ]
                             .into_iter()
                             .collect_vec(),
@@ -1727,10 +4616,10 @@ impl<T: Frontend> App<T> {
             .editor()
             .buffer()
             .words()
-            .into_iter()
+            .iter()
             .map(|word| {
                 DropdownItem::new(word.clone()).set_dispatches(Dispatches::one(Dispatch::ToEditor(
-                    ReplaceCurrentSelectionWith(word),
+                    ReplaceCurrentSelectionWith(word.clone()),
                 )))
             })
             .collect_vec()
@@ -1763,6 +4652,111 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    fn request_local_completion(&mut self) -> anyhow::Result<()> {
+        let component = self.current_component();
+        let (line_before_cursor, current_word, path) = {
+            let component = component.borrow();
+            let editor = component.editor();
+            let Some(path) = editor.buffer().path() else {
+                // Local completion is only for file buffers, not for prompts
+                return Ok(());
+            };
+            (
+                editor.current_line_before_cursor()?,
+                editor.get_current_word()?,
+                path,
+            )
+        };
+        let directory = path
+            .parent()?
+            .unwrap_or_else(|| self.working_directory.clone());
+        match crate::local_completion::completion(
+            &line_before_cursor,
+            &current_word,
+            directory.to_path_buf(),
+            &self.layout.buffers(),
+        ) {
+            LocalCompletion::Items(items) => self.handle_dispatch_suggestive_editor(
+                DispatchSuggestiveEditor::LocalCompletion(items),
+            ),
+            LocalCompletion::Directory(directory) => {
+                let sender = self.sender.clone();
+                std::thread::spawn(move || {
+                    let entries = crate::local_completion::read_directory(&directory);
+                    let _ = sender.send(AppMessage::DirectoryEntries { directory, entries });
+                });
+                Ok(())
+            }
+        }
+    }
+
+    /// Shows `entries` as the path completion items,
+    /// unless the path before the cursor is no longer in `directory`.
+    fn receive_directory_entries(
+        &mut self,
+        directory: PathBuf,
+        entries: Vec<String>,
+    ) -> anyhow::Result<()> {
+        let current_directory = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let Some(path) = editor.buffer().path() else {
+                return Ok(());
+            };
+            let file_directory = path
+                .parent()?
+                .unwrap_or_else(|| self.working_directory.clone());
+            crate::local_completion::path_directory(
+                &editor.current_line_before_cursor()?,
+                file_directory.to_path_buf(),
+            )
+        };
+        if current_directory != Some(directory) {
+            return Ok(());
+        }
+        self.handle_dispatch_suggestive_editor(DispatchSuggestiveEditor::LocalCompletion(
+            crate::local_completion::path_completion_items(entries),
+        ))
+    }
+
+    fn request_inline_suggestion(&mut self) -> anyhow::Result<()> {
+        if self.completion_source.is_none() {
+            return Ok(());
+        }
+        let request = {
+            let component = self.current_component();
+            let component = component.borrow();
+            let editor = component.editor();
+            let buffer = editor.buffer();
+            let Some(path) = buffer.path() else {
+                return Ok(());
+            };
+            let cursor = buffer.char_to_byte(editor.get_cursor_char_index())?;
+            let content = buffer.content();
+            self.inline_suggestion_request_id += 1;
+            InlineSuggestionRequest::new(
+                self.inline_suggestion_request_id,
+                path.display_absolute(),
+                &content,
+                cursor,
+            )
+        };
+        if let Some(completion_source) = self.completion_source.as_mut() {
+            completion_source.request(request)?;
+        }
+        Ok(())
+    }
+
+    fn handle_inline_suggestion(&mut self, suggestion: InlineSuggestion) -> anyhow::Result<()> {
+        if suggestion.id != self.inline_suggestion_request_id || suggestion.text.is_empty() {
+            return Ok(());
+        }
+        self.handle_dispatch_suggestive_editor(DispatchSuggestiveEditor::InlineSuggestion(
+            suggestion.text,
+        ))
+    }
+
     pub(crate) fn handle_dispatch_suggestive_editor(
         &mut self,
         dispatch: DispatchSuggestiveEditor,
@@ -1837,8 +4831,9 @@ impl<T: Frontend> App<T> {
     pub(crate) fn render_quickfix_list(
         &mut self,
         quickfix_list: QuickfixList,
+        preview: Option<QuickfixListPreview>,
     ) -> anyhow::Result<()> {
-        let dispatches = self.layout.show_quickfix_list(quickfix_list)?;
+        let dispatches = self.layout.show_quickfix_list(quickfix_list, preview)?;
         self.handle_dispatches(dispatches)
     }
 
@@ -1866,6 +4861,58 @@ impl<T: Frontend> App<T> {
         self.layout.file_explorer_content()
     }
 
+    fn request_code_action(
+        &mut self,
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    ) -> anyhow::Result<()> {
+        if let Some(params) = self.get_request_params() {
+            self.lsp_manager.send_message(
+                params.path.clone(),
+                FromEditor::TextDocumentCodeAction {
+                    params,
+                    diagnostics,
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    fn receive_code_actions(
+        &mut self,
+        code_actions: Vec<crate::lsp::code_action::CodeAction>,
+    ) -> anyhow::Result<()> {
+        if std::mem::take(&mut self.apply_preferred_code_action) {
+            if let Some(code_action) = crate::lsp::code_action::preferred_code_action(&code_actions)
+            {
+                return self.apply_code_action(code_action.clone());
+            }
+        }
+        self.open_code_actions_prompt(code_actions)
+    }
+
+    /// Applies `code_action`, or requests the server to resolve it first
+    /// if its edit is not computed yet, see `CodeAction::unresolved`.
+    fn apply_code_action(
+        &mut self,
+        code_action: crate::lsp::code_action::CodeAction,
+    ) -> anyhow::Result<()> {
+        match code_action.unresolved {
+            Some(code_action) => {
+                if let Some(params) = self.get_request_params() {
+                    self.lsp_manager.send_message(
+                        params.path.clone(),
+                        FromEditor::CodeActionResolve {
+                            code_action,
+                            params,
+                        },
+                    )?;
+                }
+                Ok(())
+            }
+            None => self.handle_dispatches(code_action.dispatches()),
+        }
+    }
+
     fn open_code_actions_prompt(
         &mut self,
         code_actions: Vec<crate::lsp::code_action::CodeAction>,
@@ -1940,14 +4987,23 @@ impl<T: Frontend> App<T> {
     }
 
     fn push_history_prompt(&mut self, key: PromptHistoryKey, line: String) {
-        self.context.push_history_prompt(key, line)
+        self.context.push_history_prompt(key, line);
+        let Some(workspace) = self.context.persistence().workspace() else {
+            return;
+        };
+        if let Err(error) =
+            crate::prompt_history::persist(workspace, self.context.prompt_histories())
+        {
+            log::error!("Failed to persist prompt histories: {:?}", error);
+        }
     }
 
     fn open_theme_prompt(&mut self) -> anyhow::Result<()> {
+        let (themes, errors) = crate::themes::themes()?;
         self.open_prompt(
             PromptConfig {
                 on_enter: DispatchPrompt::Null,
-                items: crate::themes::themes()?
+                items: themes
                     .into_iter()
                     .map(|theme| {
                         DropdownItem::new(theme.name.to_string())
@@ -1963,7 +5019,14 @@ impl<T: Frontend> App<T> {
             },
             PromptHistoryKey::Theme,
             None,
-        )
+        )?;
+        if !errors.is_empty() {
+            self.show_global_info(Info::new(
+                "Invalid theme files".to_string(),
+                errors.join("\n\n"),
+            ));
+        }
+        Ok(())
     }
 
     fn update_current_completion_item(
@@ -2055,14 +5118,24 @@ pub(crate) enum Dispatch {
     OpenFilePicker(FilePickerKind),
     OpenSearchPrompt {
         scope: Scope,
+        /// If defined, the search prompt is pre-filled with it.
+        current_line: Option<String>,
     },
     OpenFile(CanonicalizedPath),
     OpenFileFromPathBuf(PathBuf),
+    /// Open the file without focusing it, so that it is listed among the opened files.
+    OpenFileInBackground(PathBuf),
+    /// Close the editor of the file, if it is opened.
+    CloseFile(CanonicalizedPath),
     ShowGlobalInfo(Info),
     RequestCompletion,
     RequestSignatureHelp,
     RequestHover,
     RequestDefinitions(Scope),
+    /// Show the definitions in a peek window, see `Peek`.
+    PeekDefinitions,
+    /// Show the references in a peek window, see `Peek`.
+    PeekReferences,
     RequestDeclarations(Scope),
     RequestImplementations(Scope),
     RequestTypeDefinitions(Scope),
@@ -2074,21 +5147,100 @@ pub(crate) enum Dispatch {
     RequestCodeAction {
         diagnostics: Vec<lsp_types::Diagnostic>,
     },
+    /// Request the code actions of `diagnostics`, and apply the preferred one
+    /// without opening the menu, see `preferred_code_action`.
+    /// The menu is opened if no action stands out.
+    ApplyPreferredCodeAction {
+        diagnostics: Vec<lsp_types::Diagnostic>,
+    },
+    /// Apply the edit and the command of the code action, resolving it first if needed.
+    ApplyCodeAction(crate::lsp::code_action::CodeAction),
     RenameSymbol {
         new_name: String,
     },
+    /// Select the color literal and prompt for its new value,
+    /// which can be a hex color, `rgb()` or a named color.
+    OpenEditColorPrompt(ColorLiteral),
     DocumentDidChange {
         component_id: ComponentId,
         path: Option<CanonicalizedPath>,
         content: String,
         language: Option<Language>,
     },
+    /// Record the location of an edit in the change list, see `ChangeList`.
+    PushEditLocation(Location),
+    /// Go to the location of the latest edit across all files.
+    GoToLastEdit,
+    /// Go to the location of the edit before the one last gone to, across all files.
+    CycleEditLocations,
+    /// Populate the quickfix list with the TODO, FIXME, HACK and NOTE comments of the workspace.
+    CollectAnnotations,
+    /// Save the buffer of the path using the elevation command, see `App::set_elevation_command`.
+    PrivilegedSave(CanonicalizedPath),
     DocumentDidSave {
         path: CanonicalizedPath,
     },
     SetQuickfixList(QuickfixListType),
     GotoQuickfixListItem(Movement),
+    /// Activate another quickfix list from the stack of quickfix lists.
+    SwitchQuickfixList(Movement),
+    OpenQuickfixListsPrompt,
+    OpenQuickfixListFilterPrompt,
+    /// `None` means clear the filter.
+    SetQuickfixListFilter(Option<String>),
+    RemoveCurrentQuickfixListItem,
+    /// Turning the preview off opens the current quickfix list item.
+    ToggleQuickfixListPreview,
+    /// Start the interactive tutorial from the first lesson.
+    StartTutor,
+    /// Show all keymaps in a searchable buffer.
+    ShowKeymapCheatSheet,
+    OpenExportKeymapCheatSheetPrompt,
+    /// Prompt for the amount to increment the numbers under the cursors by,
+    /// see `DispatchEditor::IncrementNumber`.
+    OpenIncrementNumberPrompt {
+        sequential: bool,
+    },
+    /// Prompt for the regex that extracts the sort key of each line or selection,
+    /// see `Transformation::Sort`.
+    OpenSortByKeyPrompt {
+        numeric: bool,
+    },
+    /// Prompt for the shell command to replace the selections with the output of,
+    /// see `DispatchEditor::PipeToShell`.
+    OpenPipeToShellPrompt {
+        per_selection: bool,
+    },
+    /// See `DispatchEditor::InsertShellOutput`.
+    OpenInsertShellOutputPrompt,
+    /// See `DispatchEditor::InsertExpressionResult`.
+    OpenInsertExpressionResultPrompt,
+    /// Prompt for a hexadecimal Unicode code point, and insert its character at each cursor.
+    OpenUnicodeCodePointPrompt,
+    /// Pick a character by its name, and insert it at each cursor.
+    OpenCharacterPicker,
+    /// Export all keymaps as HTML (if the extension is `.html`) or Markdown.
+    ExportKeymapCheatSheet(PathBuf),
+    OpenExportHighlightedPrompt {
+        selection: bool,
+    },
+    /// Export the primary selection (if `selection` is true) or the whole buffer with its
+    /// syntax highlighting as HTML (if the extension is `.html`) or ANSI text.
+    ExportHighlighted {
+        path: PathBuf,
+        selection: bool,
+    },
+    /// Show how the keys of the active keyboard layout are mapped to the QWERTY keymaps.
+    ShowKeyboardLayout,
+    /// Multi-file workspace edits are previewed before being applied.
     ApplyWorkspaceEdit(WorkspaceEdit),
+    /// Apply the workspace edit being previewed.
+    AcceptWorkspaceEdit,
+    /// Discard the workspace edit being previewed.
+    DiscardWorkspaceEdit,
+    OpenRejectWorkspaceEditFilePrompt,
+    /// Exclude the edits of the given file from the workspace edit being previewed.
+    RejectWorkspaceEditFile(CanonicalizedPath),
     ShowKeymapLegend(KeymapLegendConfig),
     RemainOnlyCurrentComponent,
 
@@ -2098,12 +5250,17 @@ pub(crate) enum Dispatch {
     ToEditor(DispatchEditor),
     RequestDocumentSymbols,
     GotoLocation(Location),
+    /// Open the URL using the URL opener, see `App::set_url_opener`.
+    OpenUrl(String),
     OpenMoveToIndexPrompt,
     RunCommand(String),
     QuitAll,
     OpenCommandPrompt,
     SaveQuitAll,
     RevealInExplorer(CanonicalizedPath),
+    /// Reveal the file of the current editor (or the working directory if it has no file)
+    /// in the file explorer, expanding its parent folders and focusing it.
+    RevealCurrentFileInExplorer,
     OpenYesNoPrompt(YesNoPrompt),
     OpenMoveFilePrompt(CanonicalizedPath),
     OpenAddPathPrompt(CanonicalizedPath),
@@ -2113,18 +5270,61 @@ pub(crate) enum Dispatch {
         from: CanonicalizedPath,
         to: PathBuf,
     },
+    /// Open a new buffer without a path, which can be saved with `SaveAs`.
+    NewScratchBuffer,
+    OpenScratchBufferPicker,
+    /// Focus the scratch buffer of the given index, see `Layout::scratch_editors`.
+    SwitchScratchBuffer(usize),
+    /// Open the note of today in the notes directory, creating it if it does not exist.
+    OpenDailyNote,
+    OpenSaveAsPrompt,
+    /// Write the current buffer to a new path, which becomes the path of the buffer.
+    SaveAs(PathBuf),
+    OpenRenameFilePrompt,
+    /// Move the file of the current buffer to a new path, and keep editing it there.
+    RenameFile(PathBuf),
+    OpenChangeWorkingDirectoryPrompt,
+    /// Change the working directory of the session, relative paths are relative to the current one.
+    ChangeWorkingDirectory(PathBuf),
+    OpenBranchPicker,
+    CheckoutBranch(git::Branch),
+    /// Create a local branch at the current commit, and check it out.
+    CreateBranch(String),
+    /// Ask whether to delete the given local branch, warning if its commits may be lost.
+    ConfirmDeleteBranch(git::Branch),
+    DeleteBranch(git::Branch),
+    /// Open the commit log of the repository, or only of the commits that modify the current file.
+    OpenCommitLog {
+        current_file: bool,
+    },
+    /// Show the diff of the commit of the given hash in a read-only buffer.
+    ShowCommit(String),
+    CopyCommitHash(String),
+    /// Record that the untracked file will be added, like `git add --intent-to-add`.
+    GitIntentToAdd(CanonicalizedPath),
     AddPath(String),
     RefreshFileExplorer,
     SetClipboardContent {
         copied_texts: CopiedTexts,
         use_system_clipboard: bool,
     },
+    /// Copy `path` in the given format to the system clipboard.
+    CopyPath {
+        path: CanonicalizedPath,
+        format: PathFormat,
+    },
     SetGlobalMode(Option<GlobalMode>),
     #[cfg(test)]
     HandleKeyEvent(event::KeyEvent),
     #[cfg(test)]
     HandleKeyEvents(Vec<event::KeyEvent>),
+    #[cfg(test)]
+    HandlePaste(String),
+    #[cfg(test)]
+    SetJumpLabelStrategy(JumpLabelStrategy),
     GetRepoGitHunks(git::DiffMode),
+    /// Pick the diff mode against which the git hunks of the given scope are computed.
+    OpenGitHunkBaselinePrompt(Scope),
     SaveAll,
     #[cfg(test)]
     TerminalDimensionChanged(Dimension),
@@ -2135,6 +5335,12 @@ pub(crate) enum Dispatch {
         target: FilterTarget,
         make_mechanism: MakeFilterMechanism,
     },
+    /// Prompt for a regex to keep or remove the current selections whose content matches it.
+    OpenFilterSelectionsPrompt(FilterKind),
+    OpenKeepEveryNthSelectionPrompt,
+    OpenSplitSelectionsPrompt,
+    /// Show the jumps of every visible pane, prefixed with the character of the pane.
+    ShowJumpsAcrossPanes,
     LspExecuteCommand {
         command: crate::lsp::code_action::Command,
     },
@@ -2143,6 +5349,11 @@ pub(crate) enum Dispatch {
         scope: Scope,
         show_config_after_enter: bool,
     },
+    /// Toggle an option of the search config of `scope` from the search prompt.
+    ToggleSearchOption {
+        scope: Scope,
+        option: SearchOption,
+    },
     UpdateGlobalSearchConfig {
         update: GlobalSearchConfigUpdate,
     },
@@ -2176,6 +5387,53 @@ pub(crate) enum Dispatch {
     ShowEditorInfo(Info),
     ReceiveCodeActions(Vec<crate::lsp::code_action::CodeAction>),
     OtherWindow,
+    /// Grow or shrink the focused window.
+    ResizeWindow {
+        grow: bool,
+    },
+    EqualizeWindows,
+    /// Switch between placing the other windows beside or below the main panel.
+    SwitchLayoutKind,
+    /// See `Context::sticky_context`.
+    ToggleStickyContext,
+    /// See `Context::breadcrumbs`.
+    ToggleBreadcrumbs,
+    /// Pick one of the symbols enclosing the cursor to select it.
+    OpenBreadcrumbsPrompt,
+    /// See `Context::inline_diagnostics`.
+    ToggleInlineDiagnostics,
+    /// See `Context::auto_center`.
+    ToggleAutoCenter,
+    /// See `Context::explorer_auto_follow`.
+    ToggleExplorerAutoFollow,
+    /// Open the outline of the main editor, see `Outline`.
+    OpenOutline,
+    /// Run all tests of the current file, or only the test under the cursor.
+    RunTests {
+        all: bool,
+    },
+    /// Show the results of a test run, see `test_runner::run`.
+    ReceiveTestRun(TestRun),
+    OpenBuildPrompt,
+    /// Pick one of the diagnostics of all opened files.
+    OpenDiagnosticsPicker,
+    /// Prompt for the note of the bookmarks of the current selections.
+    OpenBookmarkNotePrompt,
+    /// Pick one of the bookmarks of the workspace, including those of files that are not opened.
+    OpenBookmarksPicker,
+    /// Run the build command, and parse its output into diagnostics, see `error_format`.
+    RunBuild(String),
+    ReceiveBuildOutput(String),
+    /// Show the entries of a directory as path completion items, see `local_completion::completion`.
+    ReceiveDirectoryEntries {
+        directory: PathBuf,
+        entries: Vec<String>,
+    },
+    OpenSaveLayoutPresetPrompt,
+    /// Save the current layout as a preset of the given name, persisted per working directory.
+    SaveLayoutPreset(String),
+    OpenRestoreLayoutPresetPrompt,
+    RestoreLayoutPreset(String),
     CloseCurrentWindowAndFocusParent,
     CloseEditorInfo,
     GoToPreviousFile,
@@ -2191,17 +5449,40 @@ pub(crate) enum Dispatch {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum GlobalSearchConfigUpdate {
     SetGlob(GlobalSearchFilterGlob, String),
+    /// Restrict the search to files of the given extension.
+    SetFileExtension(Option<String>),
+    /// Restrict the search to the given directory, relative to the working directory.
+    SetDirectory(Option<String>),
 }
 
-#[derive(Clone, Hash, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Hash, Debug, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub(crate) enum GlobalSearchFilterGlob {
     Include,
     Exclude,
 }
 
+/// The formats of the path copied by `Dispatch::CopyPath`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PathFormat {
+    /// Relative to the working directory.
+    Relative,
+    Absolute,
+    /// The relative path followed by `:` and the given (0-based) line, which is shown 1-based.
+    RelativeWithLine(usize),
+    FileName,
+    /// The URL of the given (0-based, inclusive) lines at the head commit on the code host of the remote,
+    /// see `permalink::permalink`.
+    Permalink {
+        start_line: usize,
+        end_line: usize,
+    },
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum LocalSearchConfigUpdate {
     Mode(LocalSearchConfigMode),
+    SmartCase(bool),
+    PreserveCase(bool),
     Replacement(String),
     Search(String),
 }
@@ -2254,9 +5535,19 @@ impl RequestParams {
             ..self
         }
     }
+
+    pub(crate) fn set_peek(self) -> Self {
+        Self {
+            context: ResponseContext {
+                peek: true,
+                ..self.context
+            },
+            ..self
+        }
+    }
 }
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub(crate) enum Scope {
     Local,
     Global,
@@ -2271,6 +5562,15 @@ pub(crate) enum AppMessage {
         component_id: ComponentId,
         highlighted_spans: HighlighedSpans,
     },
+    InlineSuggestion(InlineSuggestion),
+    TestRun(TestRun),
+    BuildOutput(String),
+    GitAheadBehind(Option<git::AheadBehind>),
+    /// The entries of a directory read for path completion, see `local_completion::read_directory`.
+    DirectoryEntries {
+        directory: PathBuf,
+        entries: Vec<String>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -2291,6 +5591,7 @@ pub(crate) enum DispatchPrompt {
     },
     MoveSelectionByIndex,
     RenameSymbol,
+    EditColor,
     UpdateLocalSearchConfigSearch {
         scope: Scope,
         show_config_after_enter: bool,
@@ -2299,6 +5600,10 @@ pub(crate) enum DispatchPrompt {
     MovePath {
         from: CanonicalizedPath,
     },
+    SaveAs,
+    RenameFile,
+    ChangeWorkingDirectory,
+    CreateBranch,
     Null,
     // TODO: remove the following variants
     // Because the following action already embeds dispatches
@@ -2312,6 +5617,31 @@ pub(crate) enum DispatchPrompt {
     UpdateLocalSearchConfigReplacement {
         scope: Scope,
     },
+    SetQuickfixListFilter,
+    ExportKeymapCheatSheet,
+    ExportHighlighted {
+        selection: bool,
+    },
+    IncrementNumber {
+        sequential: bool,
+    },
+    SortByKey {
+        numeric: bool,
+    },
+    PipeToShell {
+        per_selection: bool,
+    },
+    RunBuild,
+    InsertShellOutput,
+    InsertExpressionResult,
+    InsertUnicodeCodePoint,
+    SaveLayoutPreset,
+    SetBookmarkNote,
+    FilterSelections {
+        kind: FilterKind,
+    },
+    KeepEveryNthSelection,
+    SplitSelections,
     #[cfg(test)]
     SetContent,
 }
@@ -2349,6 +5679,15 @@ impl DispatchPrompt {
             DispatchPrompt::RenameSymbol => Ok(Dispatches::new(vec![Dispatch::RenameSymbol {
                 new_name: text.to_string(),
             }])),
+            DispatchPrompt::EditColor => {
+                let color = text.trim();
+                if color_literal::parse(color).is_none() {
+                    return Err(anyhow::anyhow!("Invalid color: {color}"));
+                }
+                Ok(Dispatches::one(Dispatch::ToEditor(
+                    ReplaceCurrentSelectionWith(color.to_string()),
+                )))
+            }
             DispatchPrompt::UpdateLocalSearchConfigSearch {
                 scope,
                 show_config_after_enter,
@@ -2363,6 +5702,14 @@ impl DispatchPrompt {
             DispatchPrompt::AddPath => {
                 Ok(Dispatches::new([Dispatch::AddPath(text.into())].to_vec()))
             }
+            DispatchPrompt::SaveAs => Ok(Dispatches::one(Dispatch::SaveAs(text.into()))),
+            DispatchPrompt::RenameFile => Ok(Dispatches::one(Dispatch::RenameFile(text.into()))),
+            DispatchPrompt::ChangeWorkingDirectory => Ok(Dispatches::one(
+                Dispatch::ChangeWorkingDirectory(text.into()),
+            )),
+            DispatchPrompt::CreateBranch => {
+                Ok(Dispatches::one(Dispatch::CreateBranch(text.to_string())))
+            }
             DispatchPrompt::MovePath { from } => Ok(Dispatches::new(
                 [Dispatch::MoveFile {
                     from,
@@ -2404,6 +5751,78 @@ impl DispatchPrompt {
                 }]
                 .to_vec(),
             )),
+            DispatchPrompt::SetQuickfixListFilter => Ok(Dispatches::one(
+                Dispatch::SetQuickfixListFilter(Some(text.to_string())),
+            )),
+            DispatchPrompt::ExportKeymapCheatSheet => Ok(Dispatches::one(
+                Dispatch::ExportKeymapCheatSheet(PathBuf::from(text.trim())),
+            )),
+            DispatchPrompt::ExportHighlighted { selection } => {
+                Ok(Dispatches::one(Dispatch::ExportHighlighted {
+                    path: PathBuf::from(text.trim()),
+                    selection,
+                }))
+            }
+            DispatchPrompt::IncrementNumber { sequential } => {
+                let by = text.trim().parse::<i64>()?;
+                Ok(Dispatches::one(Dispatch::ToEditor(IncrementNumber {
+                    by,
+                    sequential,
+                })))
+            }
+            DispatchPrompt::SortByKey { numeric } => {
+                let key = Some(text.trim())
+                    .filter(|key| !key.is_empty())
+                    .map(|key| -> anyhow::Result<_> {
+                        regex::Regex::new(key)?;
+                        Ok(key.to_string())
+                    })
+                    .transpose()?;
+                Ok(Dispatches::one(Dispatch::ToEditor(Transform(
+                    Transformation::Sort { numeric, key },
+                ))))
+            }
+            DispatchPrompt::PipeToShell { per_selection } => {
+                Ok(Dispatches::one(Dispatch::ToEditor(PipeToShell {
+                    command: text.to_string(),
+                    per_selection,
+                })))
+            }
+            DispatchPrompt::SaveLayoutPreset => Ok(Dispatches::one(Dispatch::SaveLayoutPreset(
+                text.trim().to_string(),
+            ))),
+            DispatchPrompt::SetBookmarkNote => Ok(Dispatches::one(Dispatch::ToEditor(
+                SetBookmarkNote(text.to_string()),
+            ))),
+            DispatchPrompt::FilterSelections { kind } => Ok(Dispatches::one(Dispatch::ToEditor(
+                FilterSelections(Filter::new(
+                    kind,
+                    FilterTarget::Content,
+                    FilterMechanism::Regex(regex::Regex::new(text)?),
+                )),
+            ))),
+            DispatchPrompt::KeepEveryNthSelection => Ok(Dispatches::one(Dispatch::ToEditor(
+                KeepEveryNthSelection(text.trim().parse()?),
+            ))),
+            DispatchPrompt::SplitSelections => {
+                let regex = if text.is_empty() { r"\r?\n" } else { text };
+                regex::Regex::new(regex)?;
+                Ok(Dispatches::one(Dispatch::ToEditor(SplitSelections(
+                    regex.to_string(),
+                ))))
+            }
+            DispatchPrompt::RunBuild => {
+                Ok(Dispatches::one(Dispatch::RunBuild(text.trim().to_string())))
+            }
+            DispatchPrompt::InsertShellOutput => Ok(Dispatches::one(Dispatch::ToEditor(
+                InsertShellOutput(text.to_string()),
+            ))),
+            DispatchPrompt::InsertExpressionResult => Ok(Dispatches::one(Dispatch::ToEditor(
+                InsertExpressionResult(text.to_string()),
+            ))),
+            DispatchPrompt::InsertUnicodeCodePoint => Ok(Dispatches::one(Dispatch::ToEditor(
+                Insert(crate::unicode_input::parse_code_point(text)?.to_string()),
+            ))),
             #[cfg(test)]
             DispatchPrompt::SetContent => Ok(Dispatches::new(
                 [Dispatch::ToEditor(SetContent(text.to_string()))].to_vec(),
@@ -2413,6 +5832,32 @@ impl DispatchPrompt {
     }
 }
 
+/// Renaming a symbol by editing all of its occurrences in the current buffer,
+/// the rename is committed via LSP when `enter` is pressed.
+struct LinkedRename {
+    component_id: ComponentId,
+    /// The linked edits are undone until the undo history is back to this depth.
+    original_undo_depth: usize,
+    original_selection_set: SelectionSet,
+    params: RequestParams,
+}
+
+/// A linked rename waiting for the occurrences of the symbol at `range`,
+/// see `App::request_linked_rename_occurrences`.
+struct PendingLinkedRename {
+    range: Range<Position>,
+    params: RequestParams,
+}
+
+/// A workspace edit that is being previewed, waiting to be accepted or discarded.
+struct PendingWorkspaceEdit {
+    workspace_edit: WorkspaceEdit,
+    /// The file to return to when the workspace edit is discarded.
+    previous_path: Option<CanonicalizedPath>,
+    /// The editor showing the preview, see `App::handle_workspace_edit_preview_key`.
+    preview_component_id: Option<ComponentId>,
+}
+
 #[derive(PartialEq)]
 enum OpenFileOption {
     Focus,
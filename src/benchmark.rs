@@ -0,0 +1,101 @@
+//! Entry points for the benchmarks under `benches/`.
+//!
+//! Benchmarks can only reach the public API of this crate,
+//! so this module wraps the internals that are worth benchmarking.
+
+use crate::{
+    app::Dimension,
+    buffer::Buffer,
+    components::editor::Direction,
+    grid::{Cell, Grid},
+    position::Position,
+    rectangle::Rectangle,
+    screen::{Screen, Window},
+    selection::{Filters, Selection},
+    selection_mode::{self, SelectionModeParams},
+};
+
+/// Returns the number of wrapped lines.
+pub fn soft_wrap(text: &str, width: usize) -> usize {
+    crate::soft_wrap::soft_wrap(text, width).wrapped_lines_count()
+}
+
+/// A Rust buffer, parsed with Tree-sitter.
+pub struct RustBuffer(Buffer);
+
+impl RustBuffer {
+    pub fn new(content: &str) -> RustBuffer {
+        let language = shared::language::from_extension("rs");
+        RustBuffer(Buffer::new(
+            language.and_then(|language| language.tree_sitter_language()),
+            content,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SelectionMode {
+    LineTrimmed,
+    WordShort,
+    Token,
+    SyntaxNode,
+}
+
+/// Returns the number of selections of `mode` in `buffer`.
+///
+/// Selection modes cache their selections in the buffer,
+/// so each measurement should use a new buffer.
+pub fn enumerate_selections(buffer: &RustBuffer, mode: SelectionMode) -> anyhow::Result<usize> {
+    let params = SelectionModeParams {
+        buffer: &buffer.0,
+        current_selection: &Selection::default(),
+        cursor_direction: &Direction::Start,
+        filters: &Filters::default(),
+    };
+    use selection_mode::SelectionMode as _;
+    let count = match mode {
+        SelectionMode::LineTrimmed => selection_mode::LineTrimmed.iter_filtered(params)?.count(),
+        SelectionMode::WordShort => selection_mode::WordShort::as_regex()?
+            .iter_filtered(params)?
+            .count(),
+        SelectionMode::Token => selection_mode::Token.iter_filtered(params)?.count(),
+        SelectionMode::SyntaxNode => selection_mode::SyntaxNode { coarse: false }
+            .iter_filtered(params)?
+            .count(),
+    };
+    Ok(count)
+}
+
+/// A screen of one window filled with `text`.
+pub struct TextScreen(Screen);
+
+impl TextScreen {
+    pub fn new(width: u16, height: u16, text: &str) -> TextScreen {
+        let dimension = Dimension { height, width };
+        let mut grid = Grid::new(dimension);
+        for (row, line) in grid.rows.iter_mut().zip(text.lines()) {
+            for (cell, char) in row.iter_mut().zip(line.chars()) {
+                *cell = Cell {
+                    symbol: char.to_string(),
+                    ..Cell::default()
+                }
+            }
+        }
+        let rectangle = Rectangle {
+            origin: Position::new(0, 0),
+            width,
+            height,
+        };
+        TextScreen(Screen::new(
+            [Window::new(grid, rectangle)].to_vec(),
+            Vec::new(),
+            None,
+            Default::default(),
+        ))
+    }
+}
+
+/// Returns the number of cells that have to be repainted to turn `old` into `new`.
+pub fn diff_screens(new: &mut TextScreen, old: &mut TextScreen) -> usize {
+    new.0.diff(&mut old.0).len()
+}
@@ -0,0 +1,166 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{char_index_range::CharIndexRange, selection::CharIndex};
+
+/// A mark on a range of a buffer, optionally with a note typed by the user,
+/// such as a reminder left during a code review.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Bookmark {
+    pub(crate) range: CharIndexRange,
+    pub(crate) note: Option<String>,
+}
+
+impl Bookmark {
+    pub(crate) fn new(range: CharIndexRange) -> Bookmark {
+        Bookmark { range, note: None }
+    }
+
+    /// Empty notes are not kept.
+    pub(crate) fn set_note(self, note: &str) -> Bookmark {
+        let note = note.trim();
+        Bookmark {
+            note: (!note.is_empty()).then(|| note.to_string()),
+            ..self
+        }
+    }
+}
+
+/// The persisted form of a bookmark, keyed by the path of its file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PersistedBookmark {
+    start: usize,
+    end: usize,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+impl From<&Bookmark> for PersistedBookmark {
+    fn from(bookmark: &Bookmark) -> Self {
+        PersistedBookmark {
+            start: bookmark.range.start.0,
+            end: bookmark.range.end.0,
+            note: bookmark.note.clone(),
+        }
+    }
+}
+
+impl From<PersistedBookmark> for Bookmark {
+    fn from(bookmark: PersistedBookmark) -> Self {
+        Bookmark {
+            range: (CharIndex(bookmark.start)..CharIndex(bookmark.end)).into(),
+            note: bookmark.note,
+        }
+    }
+}
+
+type Bookmarks = BTreeMap<PathBuf, Vec<PersistedBookmark>>;
+
+/// The bookmarks of every file of a workspace are stored in one file,
+/// named after the path of the workspace, see `persistence::file_name`.
+fn bookmarks_path(directory: &Path, workspace: &Path) -> PathBuf {
+    directory.join(format!("{}.json", crate::persistence::file_name(workspace)))
+}
+
+fn directory() -> PathBuf {
+    grammar::cache_dir().join("bookmarks")
+}
+
+fn read(bookmarks_path: &Path) -> anyhow::Result<Bookmarks> {
+    if !bookmarks_path.exists() {
+        return Ok(Default::default());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(
+        bookmarks_path,
+    )?)?)
+}
+
+/// Persist `bookmarks` as the bookmarks of the file of `path` in `workspace`.
+pub(crate) fn persist(workspace: &Path, path: &Path, bookmarks: &[Bookmark]) -> anyhow::Result<()> {
+    persist_in(&directory(), workspace, path, bookmarks)
+}
+
+fn persist_in(
+    directory: &Path,
+    workspace: &Path,
+    path: &Path,
+    bookmarks: &[Bookmark],
+) -> anyhow::Result<()> {
+    let bookmarks_path = bookmarks_path(directory, workspace);
+    let mut all = read(&bookmarks_path)?;
+    if bookmarks.is_empty() {
+        if all.remove(path).is_none() {
+            return Ok(());
+        }
+    } else {
+        all.insert(
+            path.to_path_buf(),
+            bookmarks.iter().map(PersistedBookmark::from).collect(),
+        );
+    }
+    std::fs::create_dir_all(directory)?;
+    std::fs::write(bookmarks_path, serde_json::to_string(&all)?)?;
+    Ok(())
+}
+
+/// Returns the persisted bookmarks of the file of `path` in `workspace`.
+pub(crate) fn load(workspace: &Path, path: &Path) -> anyhow::Result<Vec<Bookmark>> {
+    Ok(load_all(workspace)?.remove(path).unwrap_or_default())
+}
+
+/// Returns the persisted bookmarks of every file of `workspace`.
+pub(crate) fn load_all(workspace: &Path) -> anyhow::Result<BTreeMap<PathBuf, Vec<Bookmark>>> {
+    load_all_in(&directory(), workspace)
+}
+
+fn load_all_in(
+    directory: &Path,
+    workspace: &Path,
+) -> anyhow::Result<BTreeMap<PathBuf, Vec<Bookmark>>> {
+    Ok(read(&bookmarks_path(directory, workspace))?
+        .into_iter()
+        .map(|(path, bookmarks)| (path, bookmarks.into_iter().map(Bookmark::from).collect()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test_bookmark {
+    use super::*;
+
+    #[test]
+    fn persist_and_load() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path();
+        let workspace = Path::new("/workspace");
+        let path = Path::new("/workspace/a.txt");
+        let bookmarks = [
+            Bookmark::new((CharIndex(0)..CharIndex(3)).into()),
+            Bookmark::new((CharIndex(5)..CharIndex(6)).into()).set_note("Check this"),
+        ];
+        persist_in(directory, workspace, path, &bookmarks)?;
+        assert!(directory
+            .join(format!("{}.json", crate::persistence::file_name(workspace)))
+            .exists());
+        assert_eq!(
+            load_all_in(directory, workspace)?.remove(path).unwrap(),
+            bookmarks
+        );
+        // Bookmarks are kept per workspace
+        assert!(load_all_in(directory, Path::new("/other"))?.is_empty());
+        // Removing every bookmark of a file removes the file from the persisted bookmarks
+        persist_in(directory, workspace, path, &[])?;
+        assert!(load_all_in(directory, workspace)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn empty_notes_are_not_kept() {
+        let bookmark = Bookmark::new(Default::default());
+        assert_eq!(bookmark.clone().set_note("  ").note, None);
+        assert_eq!(bookmark.set_note(" todo ").note, Some("todo".to_string()));
+    }
+}
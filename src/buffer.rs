@@ -1,16 +1,21 @@
+use crate::color_literal::{self, ColorLiteral};
 use crate::lsp::diagnostic::Diagnostic;
 use crate::quickfix_list::QuickfixListItem;
 use crate::selection_mode::case_agnostic::CaseAgnostic;
+use crate::test_runner::TestResult;
 use crate::tree_sitter_traversal::{traverse, Order};
 use crate::{
+    bookmark::Bookmark,
     char_index_range::CharIndexRange,
     components::{editor::Movement, suggestive_editor::Decoration},
     context::{LocalSearchConfig, LocalSearchConfigMode},
     edit::{Action, ActionGroup, Edit, EditTransaction},
+    persistence::Persistence,
     position::Position,
     selection::{CharIndex, Selection, SelectionSet},
     selection_mode::{AstGrep, ByteRange},
     syntax_highlight::{HighlighedSpan, HighlighedSpans},
+    undo_history::PersistedPatch,
     undo_tree::{Applicable, OldNew, UndoTree},
     utils::find_previous,
 };
@@ -19,11 +24,33 @@ use regex::Regex;
 use ropey::Rope;
 use shared::{
     canonicalized_path::CanonicalizedPath,
+    formatter::Formatter,
     language::{self, Language},
 };
-use std::{collections::HashSet, ops::Range};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+};
 use tree_sitter::{Node, Parser, Tree};
 
+/// A symbol enclosing the cursor, see `Buffer::breadcrumbs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Breadcrumb {
+    pub(crate) name: String,
+    pub(crate) range: Range<Position>,
+}
+
+/// A symbol listed in the outline of a buffer, see `Buffer::outline`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct OutlineSymbol {
+    pub(crate) name: String,
+    /// The number of symbols enclosing this symbol
+    pub(crate) depth: usize,
+    pub(crate) range: Range<Position>,
+}
+
 #[derive(Clone)]
 pub(crate) struct Buffer {
     rope: Rope,
@@ -33,10 +60,57 @@ pub(crate) struct Buffer {
     language: Option<Language>,
     path: Option<CanonicalizedPath>,
     highlighted_spans: HighlighedSpans,
-    bookmarks: Vec<CharIndexRange>,
+    bookmarks: Vec<Bookmark>,
     diagnostics: Vec<Diagnostic>,
+    /// The diagnostics parsed from the output of a build, see `error_format`.
+    /// They are kept apart from the diagnostics of LSP servers, so that neither overwrites the other.
+    build_diagnostics: Vec<Diagnostic>,
+    /// The colors provided by the LSP server.
+    /// If `None`, color literals are detected using regexes instead.
+    document_colors: Option<Vec<ColorLiteral>>,
     quickfix_list_items: Vec<QuickfixListItem>,
+    /// The ranges of the items of the current quickfix list that are removed by the user.
+    /// They are kept here (instead of in `QuickfixListState`) so that they are updated
+    /// as the buffer is edited, like `quickfix_list_items`.
+    removed_quickfix_list_ranges: Vec<Range<Position>>,
     decorations: Vec<Decoration>,
+    /// The results of the last test run and the revision when they were received,
+    /// as their positions are outdated once the buffer is modified.
+    test_results: (usize, Vec<TestResult>),
+    /// Incremented whenever the content or the syntax tree changes.
+    revision: usize,
+    /// Editing dispatches are blocked if true, see `DispatchEditor::is_edit`.
+    read_only: bool,
+    persistence: Persistence,
+    selection_mode_cache: RefCell<SelectionModeCache>,
+    /// The hash of the content last read from or written to the file,
+    /// for telling whether the buffer or the file was modified since then.
+    saved_content_hash: u64,
+    /// The result of `is_modified` and the revision when it was computed,
+    /// because hashing the content on every render is slow for large files.
+    modified_cache: std::cell::Cell<Option<(usize, bool)>>,
+    /// The result of `words` and the revision when it was computed,
+    /// because word completion asks for the words of every buffer on each keystroke.
+    words_cache: RefCell<Option<(usize, Rc<[String]>)>>,
+    /// Set by `evict`, and unset by `restore_if_evicted`.
+    evicted: bool,
+}
+
+/// The result of `Buffer::reload_if_changed_externally`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExternalChange {
+    Unchanged,
+    Reloaded,
+    /// Both the file and the buffer were modified, so the buffer is kept as is.
+    Conflict,
+}
+
+/// The selections of content-dependent selection modes (e.g. Word, Token),
+/// so that they are not recomputed on every movement until the buffer is modified.
+#[derive(Clone, Default)]
+struct SelectionModeCache {
+    revision: usize,
+    entries: HashMap<String, Rc<[ByteRange]>>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -68,9 +142,56 @@ impl Buffer {
             decorations: Vec::new(),
             undo_tree: UndoTree::new(),
             diagnostics: Vec::new(),
+            build_diagnostics: Vec::new(),
+            document_colors: None,
             quickfix_list_items: Vec::new(),
+            removed_quickfix_list_ranges: Vec::new(),
+            test_results: Default::default(),
+            revision: 0,
+            read_only: false,
+            persistence: Persistence::default(),
+            selection_mode_cache: Default::default(),
+            saved_content_hash: content_hash(text),
+            modified_cache: Default::default(),
+            words_cache: Default::default(),
+            evicted: false,
         }
     }
+
+    pub(crate) fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    pub(crate) fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only
+    }
+
+    pub(crate) fn revision(&self) -> usize {
+        self.revision
+    }
+
+    /// Returns the cached selections of the given `key` if the buffer has not been modified
+    /// since they were computed, otherwise computes and caches them.
+    pub(crate) fn cached_byte_ranges(
+        &self,
+        key: &str,
+        compute: impl FnOnce() -> anyhow::Result<Vec<ByteRange>>,
+    ) -> anyhow::Result<Rc<[ByteRange]>> {
+        let mut cache = self.selection_mode_cache.borrow_mut();
+        if cache.revision != self.revision {
+            *cache = SelectionModeCache {
+                revision: self.revision,
+                entries: HashMap::new(),
+            }
+        }
+        if let Some(byte_ranges) = cache.entries.get(key) {
+            return Ok(byte_ranges.clone());
+        }
+        let byte_ranges: Rc<[ByteRange]> = compute()?.into();
+        cache.entries.insert(key.to_string(), byte_ranges.clone());
+        Ok(byte_ranges)
+    }
+
     pub(crate) fn clear_quickfix_list_items(&mut self) {
         self.quickfix_list_items.clear()
     }
@@ -80,14 +201,66 @@ impl Buffer {
     ) {
         self.quickfix_list_items = quickfix_list_items
     }
+
+    pub(crate) fn removed_quickfix_list_ranges(&self) -> Vec<Range<Position>> {
+        self.removed_quickfix_list_ranges.clone()
+    }
+
+    pub(crate) fn set_removed_quickfix_list_ranges(&mut self, ranges: Vec<Range<Position>>) {
+        self.removed_quickfix_list_ranges = ranges
+    }
+
+    pub(crate) fn remove_quickfix_list_item(&mut self, range: Range<Position>) {
+        self.removed_quickfix_list_ranges.push(range)
+    }
     pub(crate) fn reload(&mut self) -> anyhow::Result<()> {
         if let Some(path) = self.path() {
             let updated_content = path.read()?;
 
             self.update_content(&updated_content, SelectionSet::default())?;
+            self.set_saved_content_hash(content_hash(&updated_content));
         }
         Ok(())
     }
+
+    /// Returns true if the content differs from the content last read from or written to the file.
+    pub(crate) fn is_modified(&self) -> bool {
+        if let Some((revision, modified)) = self.modified_cache.get() {
+            if revision == self.revision {
+                return modified;
+            }
+        }
+        let modified = content_hash(&self.content()) != self.saved_content_hash;
+        self.modified_cache.set(Some((self.revision, modified)));
+        modified
+    }
+
+    pub(crate) fn mark_as_saved(&mut self) {
+        self.set_saved_content_hash(content_hash(&self.content()))
+    }
+
+    fn set_saved_content_hash(&mut self, hash: u64) {
+        self.saved_content_hash = hash;
+        self.modified_cache.set(None);
+    }
+
+    /// Reloads the buffer if its file was modified by another program,
+    /// unless the buffer also has unsaved changes.
+    pub(crate) fn reload_if_changed_externally(&mut self) -> anyhow::Result<ExternalChange> {
+        // Deleted files are left alone, so that their content can still be saved
+        let Some(content) = self.path().and_then(|path| path.read().ok()) else {
+            return Ok(ExternalChange::Unchanged);
+        };
+        if content_hash(&content) == self.saved_content_hash {
+            return Ok(ExternalChange::Unchanged);
+        }
+        if self.is_modified() {
+            return Ok(ExternalChange::Conflict);
+        }
+        self.update_content(&content, SelectionSet::default())?;
+        self.set_saved_content_hash(content_hash(&content));
+        Ok(ExternalChange::Reloaded)
+    }
     pub(crate) fn content(&self) -> String {
         self.rope.to_string()
     }
@@ -100,23 +273,58 @@ impl Buffer {
     }
 
     pub(crate) fn save_bookmarks(&mut self, new_ranges: Vec<CharIndexRange>) {
-        let old_ranges = std::mem::take(&mut self.bookmarks)
-            .into_iter()
-            .collect::<HashSet<_>>();
         let new_ranges = new_ranges.into_iter().collect::<HashSet<_>>();
+        let old_ranges = self
+            .bookmarks
+            .iter()
+            .map(|bookmark| bookmark.range)
+            .collect::<HashSet<_>>();
         // We take the symmetric difference between the old ranges and the new ranges
-        // so that user can unmark existing bookmark
-        self.bookmarks = new_ranges
-            .symmetric_difference(&old_ranges)
-            .cloned()
+        // so that user can unmark existing bookmark.
+        // The notes of the bookmarks that are kept are preserved.
+        self.bookmarks = std::mem::take(&mut self.bookmarks)
+            .into_iter()
+            .filter(|bookmark| !new_ranges.contains(&bookmark.range))
+            .chain(
+                new_ranges
+                    .difference(&old_ranges)
+                    .map(|range| Bookmark::new(*range)),
+            )
             .collect_vec();
+        self.persist_bookmarks();
+    }
+
+    /// Sets the note of the bookmarks of `ranges` to `note`,
+    /// marking the ranges that are not bookmarked yet.
+    pub(crate) fn set_bookmark_notes(&mut self, ranges: Vec<CharIndexRange>, note: &str) {
+        for range in ranges {
+            let bookmark = match self
+                .bookmarks
+                .iter()
+                .position(|bookmark| bookmark.range == range)
+            {
+                Some(index) => self.bookmarks.remove(index),
+                None => Bookmark::new(range),
+            };
+            self.bookmarks.push(bookmark.set_note(note));
+        }
+        self.persist_bookmarks();
+    }
+
+    fn persist_bookmarks(&self) {
+        if let (Some(path), Some(workspace)) = (&self.path, self.persistence.workspace()) {
+            if let Err(error) =
+                crate::bookmark::persist(workspace.as_ref(), path.as_ref(), &self.bookmarks)
+            {
+                log::error!("Failed to persist the bookmarks of {:?}: {:?}", path, error);
+            }
+        }
     }
 
     pub(crate) fn path(&self) -> Option<CanonicalizedPath> {
         self.path.clone()
     }
 
-    #[cfg(test)]
     pub(crate) fn set_path(&mut self, path: CanonicalizedPath) {
         self.path = Some(path);
     }
@@ -128,18 +336,85 @@ impl Buffer {
             .collect()
     }
 
+    pub(crate) fn set_build_diagnostics(&mut self, diagnostics: Vec<lsp_types::Diagnostic>) {
+        self.build_diagnostics = diagnostics
+            .into_iter()
+            .filter_map(|diagnostic| Diagnostic::try_from(self, diagnostic).ok())
+            .collect()
+    }
+
+    /// Returns the diagnostics of LSP servers followed by the diagnostics of the last build.
     pub(crate) fn diagnostics(&self) -> Vec<Diagnostic> {
-        self.diagnostics.clone()
+        self.diagnostics
+            .iter()
+            .chain(&self.build_diagnostics)
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn set_test_results(&mut self, test_results: Vec<TestResult>) {
+        self.test_results = (self.revision, test_results)
     }
 
-    pub(crate) fn words(&self) -> Vec<String> {
-        let regex = regex::Regex::new(r"\b\w+").unwrap();
+    pub(crate) fn test_results(&self) -> &[TestResult] {
+        match &self.test_results {
+            (revision, test_results) if *revision == self.revision => test_results,
+            _ => &[],
+        }
+    }
+
+    pub(crate) fn set_document_colors(&mut self, colors: Vec<lsp_types::ColorInformation>) {
+        self.document_colors = Some(
+            colors
+                .into_iter()
+                .filter_map(|color| {
+                    Some(ColorLiteral {
+                        range: self
+                            .position_range_to_char_index_range(
+                                &(color.range.start.into()..color.range.end.into()),
+                            )
+                            .ok()?,
+                        color: color.color.into(),
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    pub(crate) fn color_literals(&self) -> Vec<ColorLiteral> {
+        if let Some(document_colors) = &self.document_colors {
+            return document_colors.clone();
+        }
+        let is_stylesheet = self
+            .language
+            .as_ref()
+            .and_then(|language| language.tree_sitter_grammar_id())
+            .is_some_and(|id| id == "css");
+        color_literal::find(&self.rope.to_string(), is_stylesheet)
+            .into_iter()
+            .filter_map(|(byte_range, color)| {
+                Some(ColorLiteral {
+                    range: self.byte_range_to_char_index_range(&byte_range).ok()?,
+                    color,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn words(&self) -> Rc<[String]> {
+        if let Some((revision, words)) = self.words_cache.borrow().as_ref() {
+            if *revision == self.revision {
+                return words.clone();
+            }
+        }
         let str = self.rope.to_string();
-        regex
+        let words: Rc<[String]> = lazy_regex::regex!(r"\b\w+")
             .find_iter(&str)
             .map(|m| m.as_str().to_string())
             .unique()
-            .collect()
+            .collect();
+        *self.words_cache.borrow_mut() = Some((self.revision, words.clone()));
+        words
     }
 
     pub(crate) fn get_parent_lines(&self, line_number: usize) -> anyhow::Result<Vec<Line>> {
@@ -188,6 +463,124 @@ impl Buffer {
             .collect_vec())
     }
 
+    /// Returns the symbols enclosing `char_index`, from the outermost to the innermost.
+    pub(crate) fn breadcrumbs(&self, char_index: CharIndex) -> anyhow::Result<Vec<Breadcrumb>> {
+        let byte = self.char_to_byte(char_index)?;
+        let Some(node) = self
+            .tree
+            .as_ref()
+            .and_then(|tree| tree.root_node().descendant_for_byte_range(byte, byte))
+        else {
+            return Ok(Vec::new());
+        };
+        let breadcrumbs = std::iter::successors(Some(node), |node| node.parent())
+            .filter_map(|node| Some((self.symbol_name(node)?, node.byte_range())))
+            .map(|(name, range)| -> anyhow::Result<Breadcrumb> {
+                Ok(Breadcrumb {
+                    name,
+                    range: self.byte_to_position(range.start)?..self.byte_to_position(range.end)?,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(breadcrumbs.into_iter().rev().collect())
+    }
+
+    /// Returns the occurrences of the name at `range` within the innermost node with a body
+    /// (such as a function) enclosing it, other than the node that the name defines,
+    /// so that the same name in other functions is not an occurrence.
+    ///
+    /// Words that are not a syntax node of their own, such as words in comments, are excluded.
+    /// This is how linked editing finds the occurrences when the LSP server provides none.
+    pub(crate) fn occurrences_in_enclosing_scope(
+        &self,
+        range: CharIndexRange,
+    ) -> anyhow::Result<Vec<CharIndexRange>> {
+        let name = self.slice(&range)?.to_string();
+        let byte_range = self.char_to_byte(range.start)?..self.char_to_byte(range.end)?;
+        let scope = self
+            .tree
+            .as_ref()
+            .and_then(|tree| {
+                let node = tree
+                    .root_node()
+                    .descendant_for_byte_range(byte_range.start, byte_range.end)?;
+                std::iter::successors(node.parent(), |node| node.parent()).find(|node| {
+                    node.child_by_field_name("body").is_some()
+                        && node
+                            .child_by_field_name("name")
+                            .map(|name| name.byte_range())
+                            != Some(byte_range.clone())
+                })
+            })
+            .map(|node| node.byte_range())
+            .unwrap_or(0..self.rope.len_bytes());
+        let regex = crate::selection_mode::regex::get_regex(
+            &name,
+            crate::list::grep::RegexConfig {
+                escaped: true,
+                case_sensitive: true,
+                match_whole_word: true,
+            },
+        )?;
+        let content = self.rope.to_string();
+        regex
+            .find_iter(&content[scope.clone()])
+            .map(|found| -> anyhow::Result<CharIndexRange> {
+                Ok((self.byte_to_char(scope.start + found.start())?
+                    ..self.byte_to_char(scope.start + found.end())?)
+                    .into())
+            })
+            .filter_ok(|range| self.tree.is_none() || self.given_range_is_node(range))
+            .collect()
+    }
+
+    /// Returns all symbols of this buffer in document order.
+    pub(crate) fn outline(&self) -> anyhow::Result<Vec<OutlineSymbol>> {
+        let Some(tree) = self.tree.as_ref() else {
+            return Ok(Vec::new());
+        };
+        // The end bytes of the symbols enclosing the current symbol
+        let mut enclosing_ends: Vec<usize> = Vec::new();
+        traverse(tree.walk(), Order::Pre)
+            .filter_map(|node| Some((self.symbol_name(node)?, node.byte_range())))
+            .map(|(name, range)| -> anyhow::Result<OutlineSymbol> {
+                enclosing_ends.retain(|end| *end > range.start);
+                let depth = enclosing_ends.len();
+                enclosing_ends.push(range.end);
+                Ok(OutlineSymbol {
+                    name,
+                    depth,
+                    range: self.byte_to_position(range.start)?..self.byte_to_position(range.end)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the name of `node` if it is a symbol.
+    ///
+    /// A symbol is a definition node (such as a module, class or function) with a `name` field,
+    /// or an `impl` block, which is named by its header.
+    fn symbol_name(&self, node: Node) -> Option<String> {
+        let is_definition = ["_item", "_definition", "_declaration"]
+            .iter()
+            .any(|suffix| node.kind().ends_with(suffix));
+        if !is_definition {
+            return None;
+        }
+        let range = if node.kind() == "impl_item" {
+            node.start_byte()..node.child_by_field_name("body")?.start_byte()
+        } else {
+            node.child_by_field_name("name")?.byte_range()
+        };
+        Some(
+            self.rope
+                .get_byte_slice(range)?
+                .to_string()
+                .split_whitespace()
+                .join(" "),
+        )
+    }
+
     fn get_rope_and_tree(
         language: Option<tree_sitter::Language>,
         text: &str,
@@ -249,12 +642,51 @@ impl Buffer {
             .unwrap_or(false)
     }
 
+    /// Frees the memory held by a buffer that has not been viewed recently,
+    /// by dropping its syntax tree, highlighted spans and cached selections,
+    /// and the undo history older than the latest `undo_history_limit` edits.
+    ///
+    /// The redo history and the selections saved in the undo history are dropped as well.
+    pub(crate) fn evict(&mut self, undo_history_limit: usize) -> anyhow::Result<()> {
+        if self.evicted {
+            return Ok(());
+        }
+        self.evicted = true;
+        self.tree = None;
+        self.highlighted_spans = HighlighedSpans::default();
+        *self.selection_mode_cache.borrow_mut() = SelectionModeCache::default();
+        *self.words_cache.borrow_mut() = None;
+        let history = self.undo_history();
+        if history.len() > undo_history_limit {
+            let kept = history[history.len() - undo_history_limit..].to_vec();
+            self.restore_undo_history(kept)?;
+        }
+        Ok(())
+    }
+
+    /// Reparses the syntax tree dropped by `evict`.
+    /// Returns true if the buffer was evicted, in which case its syntax highlighting
+    /// has to be requested again.
+    pub(crate) fn restore_if_evicted(&mut self) -> bool {
+        if !self.evicted {
+            return false;
+        }
+        self.evicted = false;
+        if self.tree.is_none() {
+            (_, self.tree) =
+                Self::get_rope_and_tree(self.treesitter_language.clone(), &self.rope.to_string());
+        }
+        self.revision += 1;
+        true
+    }
+
     pub(crate) fn update_highlighted_spans(&mut self, spans: HighlighedSpans) {
         self.highlighted_spans = spans;
     }
 
     pub(crate) fn update(&mut self, text: &str) {
         (self.rope, self.tree) = Self::get_rope_and_tree(self.treesitter_language.clone(), text);
+        self.revision += 1;
     }
 
     pub(crate) fn get_line_by_char_index(&self, char_index: CharIndex) -> anyhow::Result<Rope> {
@@ -477,11 +909,17 @@ impl Buffer {
                     ))
                 })
                 .collect_vec();
+        let removed_quickfix_list_char_index_ranges =
+            std::mem::take(&mut self.removed_quickfix_list_ranges)
+                .into_iter()
+                .filter_map(|range| self.position_range_to_char_index_range(&range).ok())
+                .collect_vec();
 
         // Update the content
         self.rope.try_remove(edit.range.start.0..edit.end().0)?;
         self.rope
             .try_insert(edit.range.start.0, edit.new.to_string().as_str())?;
+        self.revision += 1;
 
         // Update all the positional spans (by using the char index ranges computed before the content is updated
         self.quickfix_list_items = quickfix_list_items_with_char_index_range
@@ -493,21 +931,43 @@ impl Buffer {
                 Some(item.set_location_range(position_range))
             })
             .collect_vec();
+        self.removed_quickfix_list_ranges = removed_quickfix_list_char_index_ranges
+            .into_iter()
+            .filter_map(|char_index_range| {
+                self.char_index_range_to_position_range(char_index_range.apply_edit(edit)?)
+                    .ok()
+            })
+            .collect_vec();
 
         // Update all the non-positional spans
         self.bookmarks = std::mem::take(&mut self.bookmarks)
             .into_iter()
-            .filter_map(|bookmark| bookmark.apply_edit(edit))
-            .collect();
-        self.diagnostics = std::mem::take(&mut self.diagnostics)
-            .into_iter()
-            .filter_map(|diagnostic| {
-                Some(Diagnostic {
-                    range: diagnostic.range.apply_edit(edit)?,
-                    ..diagnostic
+            .filter_map(|bookmark| {
+                Some(Bookmark {
+                    range: bookmark.range.apply_edit(edit)?,
+                    ..bookmark
                 })
             })
-            .collect_vec();
+            .collect();
+        let apply_edit = |diagnostics: Vec<Diagnostic>| {
+            diagnostics
+                .into_iter()
+                .filter_map(|diagnostic| {
+                    Some(Diagnostic {
+                        range: diagnostic.range.apply_edit(edit)?,
+                        ..diagnostic
+                    })
+                })
+                .collect_vec()
+        };
+        self.diagnostics = apply_edit(std::mem::take(&mut self.diagnostics));
+        self.build_diagnostics = apply_edit(std::mem::take(&mut self.build_diagnostics));
+        self.document_colors = std::mem::take(&mut self.document_colors).map(|colors| {
+            colors
+                .into_iter()
+                .filter_map(|color| color.apply_edit(edit))
+                .collect()
+        });
         if let Ok(byte_range) = self.char_index_range_to_byte_range(edit.range()) {
             self.highlighted_spans = std::mem::take(&mut self.highlighted_spans).apply_edit(
                 &byte_range,
@@ -543,6 +1003,60 @@ impl Buffer {
             .unwrap();
     }
 
+    /// Returns the undo history leading to the current content, from the oldest patch.
+    fn undo_history(&self) -> Vec<PersistedPatch> {
+        self.undo_tree
+            .current_branch()
+            .into_iter()
+            .map(|old_new| PersistedPatch {
+                old_to_new: old_new.old_to_new.patch,
+                new_to_old: old_new.new_to_old.patch,
+            })
+            .collect()
+    }
+
+    /// Replaces the undo history with `patches`, which must lead to the current content.
+    /// Undoing a restored patch does not restore the selections, since they are not persisted.
+    fn restore_undo_history(&mut self, patches: Vec<PersistedPatch>) -> anyhow::Result<()> {
+        let current = self.content();
+        let mut content = current.clone();
+        for patch in patches.iter().rev() {
+            content = diffy::apply(&content, &diffy::Patch::from_str(&patch.new_to_old)?)?;
+        }
+        let state = || BufferState {
+            selection_set: SelectionSet::default(),
+            bookmarks: Vec::new(),
+        };
+        let mut undo_tree = UndoTree::new();
+        for patch in patches {
+            undo_tree.edit(
+                &mut content,
+                OldNew {
+                    old_to_new: Patch {
+                        patch: patch.old_to_new,
+                        state: state(),
+                    },
+                    new_to_old: Patch {
+                        patch: patch.new_to_old,
+                        state: state(),
+                    },
+                },
+            )?;
+        }
+        if content != current {
+            return Err(anyhow::anyhow!(
+                "The undo history does not lead to the current content"
+            ));
+        }
+        self.undo_tree = undo_tree;
+        Ok(())
+    }
+
+    /// The number of undoable edits leading to the current content.
+    pub(crate) fn undo_depth(&self) -> usize {
+        self.undo_tree.depth()
+    }
+
     pub(crate) fn display_history(&self) -> String {
         self.undo_tree.display()
     }
@@ -602,41 +1116,143 @@ impl Buffer {
 
         buffer.path = Some(path.clone());
         buffer.language = language;
+        // Files without write permission cannot be saved, so editing them is blocked in the first place
+        buffer.read_only = std::fs::metadata(path)
+            .map(|metadata| metadata.permissions().readonly())
+            .unwrap_or(false);
 
         Ok(buffer)
     }
 
+    /// Persist the undo history and the bookmarks of this buffer according to `persistence`,
+    /// and restore the ones persisted for its file.
+    pub(crate) fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence;
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if self.persistence.undo_history() {
+            if let Err(error) = crate::undo_history::load(path.as_ref(), &self.content())
+                .and_then(|patches| self.restore_undo_history(patches))
+            {
+                log::error!(
+                    "Failed to restore the undo history of {:?}: {:?}",
+                    path,
+                    error
+                );
+            }
+        }
+        if let Some(workspace) = self.persistence.workspace() {
+            match crate::bookmark::load(workspace.as_ref(), path.as_ref()) {
+                Ok(bookmarks) => {
+                    // Bookmarks beyond the end of the file, which was modified elsewhere, are dropped
+                    let len_chars = self.len_chars();
+                    self.bookmarks = bookmarks
+                        .into_iter()
+                        .filter(|bookmark| bookmark.range.end.0 <= len_chars)
+                        .collect()
+                }
+                Err(error) => {
+                    log::error!("Failed to load the bookmarks of {:?}: {:?}", path, error)
+                }
+            }
+        }
+    }
+
+    /// Replaces the persistence of this buffer, whose persisted state was already restored,
+    /// so that its bookmarks are persisted in the new workspace.
+    pub(crate) fn update_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence;
+        self.persist_bookmarks();
+    }
+
     pub(crate) fn reparse_tree(&mut self) -> anyhow::Result<()> {
         let mut parser = tree_sitter::Parser::new();
         if let Some(tree) = self.tree.as_ref() {
             parser.set_language(&tree.language())?;
             self.tree = parser.parse(&self.rope.to_string(), None);
+            self.revision += 1;
         }
         Ok(())
     }
 
-    pub(crate) fn get_formatted_content(&self) -> Option<String> {
-        if let Some(content) = self.language.as_ref().and_then(|language| {
-            language.formatter().map(|formatter| {
-                log::info!("[FORMAT]: {}", formatter.command_string());
-                formatter.format(&self.rope.to_string())
-            })
-        }) {
-            match content {
-                Ok(content) => {
-                    return Some(content);
-                }
-                Err(error) => {
-                    log::info!("Error formatting: {}", error);
-                }
-            }
+    fn formatter(&self) -> Option<Formatter> {
+        let formatter = self.language.as_ref()?.formatter()?;
+        log::info!("[FORMAT]: {}", formatter.command_string());
+        Some(formatter)
+    }
+
+    /// Formats the content with the formatters of the language, undoably.
+    /// The content is left unchanged if any formatter fails.
+    ///
+    /// Blocks of other languages embedded in the content, such as fenced code blocks of Markdown,
+    /// are formatted with the formatters of their own languages first, see `crate::injection`.
+    pub(crate) fn format(&mut self, current_selection_set: SelectionSet) -> anyhow::Result<()> {
+        let content = self.rope.to_string();
+        let injections = match (&self.language, &self.tree) {
+            (Some(language), Some(tree)) => crate::injection::injections(language, tree, &content)
+                .unwrap_or_else(|error| {
+                    log::info!("Failed to detect injections: {:?}", error);
+                    Vec::new()
+                }),
+            _ => Vec::new(),
+        };
+        let formatted_content = crate::injection::format_injections(&content, injections);
+        let formatted_content = match self.formatter() {
+            Some(formatter) => formatter.format(&formatted_content)?,
+            None => formatted_content,
+        };
+        if formatted_content != content {
+            self.update_content(&formatted_content, current_selection_set)?;
         }
-        None
+        Ok(())
+    }
+
+    /// Formats only the content within `ranges`, see `Formatter::format_range`.
+    /// Returns `None` if the language has no formatter.
+    pub(crate) fn format_ranges(
+        &mut self,
+        ranges: Vec<CharIndexRange>,
+        current_selection_set: SelectionSet,
+    ) -> anyhow::Result<Option<SelectionSet>> {
+        let Some(formatter) = self.formatter() else {
+            return Ok(None);
+        };
+        let ranges = ranges
+            .into_iter()
+            .map(|range| Ok(self.char_to_byte(range.start)?..self.char_to_byte(range.end)?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        // Formatting from the last range keeps the byte offsets of the earlier ranges valid
+        let formatted_content = ranges
+            .into_iter()
+            .sorted_by_key(|range| std::cmp::Reverse(range.start))
+            .try_fold(self.rope.to_string(), |content, range| {
+                formatter.format_range(&content, range)
+            })?;
+        Ok(Some(self.update_content(
+            &formatted_content,
+            current_selection_set,
+        )?))
     }
 
     pub(crate) fn save_without_formatting(&mut self) -> anyhow::Result<Option<CanonicalizedPath>> {
         if let Some(path) = &self.path.clone() {
-            path.write(&self.content())?;
+            let content = self.content();
+            crate::save::save(path.as_ref(), &content, self.persistence.backup_count())?;
+            self.set_saved_content_hash(content_hash(&content));
+            if self.persistence.undo_history() {
+                if let Err(error) =
+                    crate::undo_history::persist(path.as_ref(), &content, &self.undo_history())
+                {
+                    log::error!(
+                        "Failed to persist the undo history of {:?}: {:?}",
+                        path,
+                        error
+                    );
+                }
+            }
+            // The ranges of the bookmarks might have been shifted by the edits since they were persisted
+            self.persist_bookmarks();
 
             Ok(Some(path.clone()))
         } else {
@@ -645,17 +1261,6 @@ impl Buffer {
         }
     }
 
-    pub(crate) fn save(
-        &mut self,
-        current_selection_set: SelectionSet,
-    ) -> anyhow::Result<Option<CanonicalizedPath>> {
-        if let Some(formatted_content) = self.get_formatted_content() {
-            self.update_content(&formatted_content, current_selection_set)?;
-        }
-
-        self.save_without_formatting()
-    }
-
     fn update_content(
         &mut self,
         new_content: &str,
@@ -707,7 +1312,7 @@ impl Buffer {
         Ok(ByteRange::new(start..end))
     }
 
-    pub(crate) fn bookmarks(&self) -> Vec<CharIndexRange> {
+    pub(crate) fn bookmarks(&self) -> Vec<Bookmark> {
         self.bookmarks.clone()
     }
 
@@ -724,6 +1329,20 @@ impl Buffer {
         self.rope.get_line(line_index)
     }
 
+    /// The number of characters of the given line, excluding its trailing newline.
+    pub(crate) fn line_len_without_newline(&self, line_index: usize) -> usize {
+        self.get_line_by_line_index(line_index)
+            .map(|line| {
+                let len = line.len_chars();
+                if len > 0 && line.char(len - 1) == '\n' {
+                    len - 1
+                } else {
+                    len
+                }
+            })
+            .unwrap_or_default()
+    }
+
     pub(crate) fn position_range_to_byte_range(
         &self,
         range: &Range<Position>,
@@ -836,15 +1455,19 @@ impl Buffer {
         current_selection_set: SelectionSet,
     ) -> anyhow::Result<(bool, SelectionSet)> {
         let before = self.rope.to_string();
-        let edit_transaction = match config.mode {
+        let edit_transaction = match config.effective_mode() {
             LocalSearchConfigMode::CaseAgnostic => {
                 let replaced =
                     CaseAgnostic::new(config.search()).replace_all(&before, config.replacement());
                 self.get_edit_transaction(&replaced)?
             }
             LocalSearchConfigMode::Regex(regex_config) => {
-                let regex = regex_config.to_regex(&config.search())?;
-                let replaced = regex.replace_all(&before, config.replacement()).to_string();
+                let replaced = config.replace_regex(
+                    &regex_config.to_regex(&config.search())?,
+                    regex_config.escaped,
+                    &before,
+                    false,
+                );
                 self.get_edit_transaction(&replaced)?
             }
             LocalSearchConfigMode::AstGrep => {
@@ -898,6 +1521,13 @@ impl Buffer {
     }
 }
 
+fn content_hash(content: &str) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
 #[cfg(test)]
 mod test_buffer {
     use itertools::Itertools;
@@ -906,6 +1536,28 @@ mod test_buffer {
 
     use super::Buffer;
 
+    #[test]
+    fn restore_undo_history() -> anyhow::Result<()> {
+        let mut buffer = Buffer::new(None, "a");
+        buffer.update_content("ab", SelectionSet::default())?;
+        buffer.update_content("abc", SelectionSet::default())?;
+        let patches = buffer.undo_history();
+        assert_eq!(patches.len(), 2);
+
+        let mut restored = Buffer::new(None, "abc");
+        restored.restore_undo_history(patches.clone())?;
+        restored.undo_tree_apply_movement(crate::components::editor::Movement::Previous)?;
+        assert_eq!(restored.content(), "ab");
+        restored.undo_tree_apply_movement(crate::components::editor::Movement::Previous)?;
+        assert_eq!(restored.content(), "a");
+
+        // The history cannot be restored onto other content
+        assert!(Buffer::new(None, "xyz")
+            .restore_undo_history(patches)
+            .is_err());
+        Ok(())
+    }
+
     #[test]
     fn get_parent_lines_1() {
         let buffer = Buffer::new(
@@ -969,6 +1621,67 @@ fn f(
         pretty_assertions::assert_eq!(actual, expected)
     }
 
+    #[test]
+    fn breadcrumbs() -> anyhow::Result<()> {
+        let buffer = Buffer::new(
+            shared::language::from_extension("rs")
+                .unwrap()
+                .tree_sitter_language(),
+            "mod m { impl<T> Trait for\n  S<T> { fn f() { let x = 1; } } }",
+        );
+        let char_index = crate::selection::CharIndex(buffer.content().find("x =").unwrap());
+        let actual = buffer
+            .breadcrumbs(char_index)?
+            .into_iter()
+            .map(|breadcrumb| breadcrumb.name)
+            .collect_vec();
+        assert_eq!(actual, ["m", "impl<T> Trait for S<T>", "f"]);
+        Ok(())
+    }
+
+    #[test]
+    fn outline() -> anyhow::Result<()> {
+        let buffer = Buffer::new(
+            shared::language::from_extension("rs")
+                .unwrap()
+                .tree_sitter_language(),
+            "mod m {\n  struct S;\n  impl S {\n    fn f() {}\n  }\n}\nfn g() {}",
+        );
+        let actual = buffer
+            .outline()?
+            .into_iter()
+            .map(|symbol| (symbol.depth, symbol.name, symbol.range.start.line))
+            .collect_vec();
+        assert_eq!(
+            actual,
+            [
+                (0, "m".to_string(), 0),
+                (1, "S".to_string(), 1),
+                (1, "impl S".to_string(), 2),
+                (2, "f".to_string(), 3),
+                (0, "g".to_string(), 6),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cached_byte_ranges_are_invalidated_by_edits() -> anyhow::Result<()> {
+        use crate::selection_mode::ByteRange;
+        let mut buffer = Buffer::new(None, "hello world");
+        let cached = buffer.cached_byte_ranges("key", || Ok(vec![ByteRange::new(0..5)]))?;
+        assert_eq!(cached[0].range(), &(0..5));
+
+        // Cache hit: the selections should not be recomputed
+        let cached = buffer.cached_byte_ranges("key", || Err(anyhow::anyhow!("recomputed")))?;
+        assert_eq!(cached[0].range(), &(0..5));
+
+        buffer.update("hi world");
+        let cached = buffer.cached_byte_ranges("key", || Ok(vec![ByteRange::new(0..2)]))?;
+        assert_eq!(cached[0].range(), &(0..2));
+        Ok(())
+    }
+
     mod replace {
 
         use crate::{
@@ -1023,6 +1736,55 @@ fn f(
             )
         }
 
+        #[test]
+        fn regex_named_capture_group() -> anyhow::Result<()> {
+            test(
+                "x = 1; y = 2",
+                LocalSearchConfig::new(Regex(RegexConfig {
+                    escaped: false,
+                    case_sensitive: false,
+                    match_whole_word: false,
+                }))
+                .set_search(r"(?P<name>\w) = (?P<value>\d)".to_string())
+                .set_replacment(r"${value} = ${name}".to_string())
+                .to_owned(),
+                "1 = x; 2 = y",
+            )
+        }
+
+        #[test]
+        fn literal_replacement_is_not_expanded() -> anyhow::Result<()> {
+            test(
+                "price",
+                LocalSearchConfig::new(Regex(RegexConfig {
+                    escaped: true,
+                    case_sensitive: false,
+                    match_whole_word: false,
+                }))
+                .set_search("price".to_string())
+                .set_replacment("$1".to_string())
+                .to_owned(),
+                "$1",
+            )
+        }
+
+        #[test]
+        fn regex_preserve_case() -> anyhow::Result<()> {
+            test(
+                "FooBar foo_bar FOO_BAR foo-bar",
+                LocalSearchConfig::new(Regex(RegexConfig {
+                    escaped: false,
+                    case_sensitive: false,
+                    match_whole_word: false,
+                }))
+                .set_search(r"foo[-_]?bar".to_string())
+                .set_replacment("BazQux".to_string())
+                .set_preserve_case(true)
+                .to_owned(),
+                "BazQux baz_qux BAZ_QUX baz-qux",
+            )
+        }
+
         #[test]
         fn ast_group_1() -> anyhow::Result<()> {
             test(
@@ -1067,8 +1829,9 @@ fn f(
                 // Update the buffer with unformatted code
                 buffer.update(" fn main\n() {}");
 
-                // Save the buffer
-                buffer.save(SelectionSet::default()).unwrap();
+                // Format and save the buffer
+                buffer.format(SelectionSet::default()).unwrap();
+                buffer.save_without_formatting().unwrap();
 
                 // Expect the output is formatted
                 let saved_content = path.read().unwrap();
@@ -1096,7 +1859,7 @@ fn f(
                 let original = " fn main\n() {}";
                 buffer.update(original);
 
-                buffer.save(SelectionSet::default()).unwrap();
+                buffer.format(SelectionSet::default()).unwrap();
 
                 // Expect the buffer is formatted
                 assert_ne!(buffer.rope.to_string(), original);
@@ -1119,8 +1882,8 @@ fn f(
                 // Update the buffer to be invalid Rust code
                 buffer.update("fn main() {");
 
-                // Save the buffer
-                buffer.save(SelectionSet::default()).unwrap();
+                // Format the buffer
+                assert!(buffer.format(SelectionSet::default()).is_err());
 
                 // Expect the buffer remain unchanged,
                 // because the syntax node is invalid
@@ -1143,7 +1906,7 @@ fn f(
                 // but not to the formatter
                 assert!(!buffer.tree.as_ref().unwrap().root_node().has_error());
 
-                buffer.save(SelectionSet::default()).unwrap();
+                assert!(buffer.format(SelectionSet::default()).is_err());
 
                 // Expect the buffer remain unchanged
                 assert_eq!(buffer.rope.to_string(), code);
@@ -1151,6 +1914,87 @@ fn f(
         }
     }
 
+    #[test]
+    fn evict_and_restore() -> anyhow::Result<()> {
+        let language = shared::language::from_extension("rs")
+            .and_then(|language| language.tree_sitter_language());
+        let mut buffer = Buffer::new(language, "fn main() {}");
+        for content in ["fn a() {}", "fn b() {}", "fn c() {}"] {
+            buffer.update_content(content, SelectionSet::default())?;
+        }
+        buffer.update_highlighted_spans(crate::syntax_highlight::HighlighedSpans(
+            [crate::syntax_highlight::HighlighedSpan {
+                byte_range: 0..2,
+                style_key: crate::grid::StyleKey::Syntax("keyword".to_string()),
+            }]
+            .to_vec(),
+        ));
+
+        buffer.evict(2)?;
+        assert!(buffer.tree().is_none());
+        assert!(buffer.highlighted_spans().is_empty());
+        assert_eq!(buffer.undo_history().len(), 2);
+        assert_eq!(buffer.content(), "fn c() {}");
+
+        assert!(buffer.restore_if_evicted());
+        assert!(buffer.tree().is_some());
+        assert!(!buffer.restore_if_evicted());
+
+        // Only the latest edits can be undone
+        use crate::components::editor::Movement;
+        buffer.undo_tree_apply_movement(Movement::Previous)?;
+        buffer.undo_tree_apply_movement(Movement::Previous)?;
+        assert_eq!(buffer.content(), "fn a() {}");
+        buffer.undo_tree_apply_movement(Movement::Previous)?;
+        assert_eq!(buffer.content(), "fn a() {}");
+        Ok(())
+    }
+
+    #[test]
+    fn reload_if_changed_externally() -> anyhow::Result<()> {
+        use crate::buffer::ExternalChange;
+        use shared::canonicalized_path::CanonicalizedPath;
+
+        let dir = tempfile::tempdir()?;
+        let file_path = dir.path().join("main.rs");
+        std::fs::write(&file_path, "fn main() {}")?;
+        let path = CanonicalizedPath::try_from(file_path)?;
+        let mut buffer = Buffer::from_path(&path, false)?;
+
+        assert_eq!(
+            buffer.reload_if_changed_externally()?,
+            ExternalChange::Unchanged
+        );
+
+        // Unmodified buffers are reloaded
+        path.write("fn foo() {}")?;
+        assert!(!buffer.is_modified());
+        assert_eq!(
+            buffer.reload_if_changed_externally()?,
+            ExternalChange::Reloaded
+        );
+        assert_eq!(buffer.content(), "fn foo() {}");
+
+        // Modified buffers are kept as is
+        buffer.update("fn bar() {}");
+        path.write("fn spam() {}")?;
+        assert!(buffer.is_modified());
+        assert_eq!(
+            buffer.reload_if_changed_externally()?,
+            ExternalChange::Conflict
+        );
+        assert_eq!(buffer.content(), "fn bar() {}");
+
+        // Saving the buffer overwrites the external change
+        buffer.save_without_formatting()?;
+        assert!(!buffer.is_modified());
+        assert_eq!(
+            buffer.reload_if_changed_externally()?,
+            ExternalChange::Unchanged
+        );
+        Ok(())
+    }
+
     mod patch_edit {
         use crate::edit::EditTransaction;
 
@@ -1277,7 +2121,7 @@ pub(crate) struct Patch {
 #[derive(Clone)]
 pub(crate) struct BufferState {
     pub(crate) selection_set: SelectionSet,
-    pub(crate) bookmarks: Vec<CharIndexRange>,
+    pub(crate) bookmarks: Vec<Bookmark>,
 }
 
 impl std::fmt::Display for Patch {
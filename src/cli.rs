@@ -6,6 +6,16 @@ use shared::canonicalized_path::CanonicalizedPath;
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
+    /// Start the interactive tutorial
+    #[arg(long)]
+    tutor: bool,
+    /// Show the rendering frame rate, frame time and repainted cells in the title bar
+    #[arg(long, global = true)]
+    show_fps: bool,
+    /// Print the time spent loading persistence, config, grammars and the first render
+    /// when the editor exits
+    #[arg(long, global = true)]
+    profile_startup: bool,
 }
 
 #[derive(Subcommand)]
@@ -25,6 +35,9 @@ enum Commands {
     Log,
     /// Run Ki in the given path, treating the path as the working directory
     In(InArgs),
+    /// Print the file of the given path with its syntax highlighting, as ANSI text
+    /// by default
+    Export(ExportArgs),
 }
 #[derive(Args)]
 struct EditArgs {
@@ -34,6 +47,13 @@ struct EditArgs {
 struct InArgs {
     path: String,
 }
+#[derive(Args)]
+struct ExportArgs {
+    path: String,
+    /// Print a standalone HTML document instead
+    #[arg(long)]
+    html: bool,
+}
 #[derive(Subcommand)]
 enum Grammar {
     Build,
@@ -76,6 +96,8 @@ pub(crate) fn cli() -> anyhow::Result<()> {
                 }
                 crate::run(crate::RunConfig {
                     entry_path: Some(args.path.try_into()?),
+                    show_fps: cli.show_fps,
+                    profile_startup: cli.profile_startup,
                     ..Default::default()
                 })
             }
@@ -86,12 +108,31 @@ pub(crate) fn cli() -> anyhow::Result<()> {
                 );
                 Ok(())
             }
+            Commands::Export(args) => {
+                let format = if args.html {
+                    crate::export::ExportFormat::Html
+                } else {
+                    crate::export::ExportFormat::Ansi
+                };
+                print!(
+                    "{}",
+                    crate::export::export_file(&args.path.try_into()?, format)?
+                );
+                Ok(())
+            }
             Commands::In(args) => crate::run(crate::RunConfig {
                 working_directory: Some(args.path.try_into()?),
+                show_fps: cli.show_fps,
+                profile_startup: cli.profile_startup,
                 ..Default::default()
             }),
         }
     } else {
-        crate::run(Default::default())
+        crate::run(crate::RunConfig {
+            tutor: cli.tutor,
+            show_fps: cli.show_fps,
+            profile_startup: cli.profile_startup,
+            ..Default::default()
+        })
     }
 }
@@ -48,6 +48,11 @@ impl Clipboard {
         self.history.get(history_offset)
     }
 
+    /// 0 means latest, 1 means the one before the latest, and so on, without cycling.
+    pub(crate) fn get_nth_latest(&self, n: usize) -> Option<CopiedTexts> {
+        self.history.get_nth_latest(n)
+    }
+
     pub(crate) fn get_from_system_clipboard(&self) -> anyhow::Result<String> {
         Ok(arboard::Clipboard::new()?.get_text()?)
     }
@@ -94,6 +99,10 @@ impl<T: Clone> RingHistory<T> {
         }
     }
 
+    pub(crate) fn get_nth_latest(&self, n: usize) -> Option<T> {
+        self.items.iter().rev().nth(n).cloned()
+    }
+
     pub(crate) fn add(&mut self, item: T) {
         self.items.push(item)
     }
@@ -128,5 +137,8 @@ mod test_ring_history {
         for (offset, expected) in expected {
             assert_eq!(history.get(offset), Some(expected.to_string()))
         }
+        assert_eq!(history.get_nth_latest(0), Some("c".to_string()));
+        assert_eq!(history.get_nth_latest(2), Some("a".to_string()));
+        assert_eq!(history.get_nth_latest(3), None);
     }
 }
@@ -0,0 +1,311 @@
+use std::ops::Range;
+
+use itertools::Itertools;
+use my_proc_macros::hex;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::{char_index_range::CharIndexRange, edit::Edit, themes::Color};
+
+/// A color value in a buffer, such as `#ff0000`, `rgb(255, 0, 0)` or `red`,
+/// which is decorated with a swatch of its color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ColorLiteral {
+    pub(crate) range: CharIndexRange,
+    pub(crate) color: Color,
+}
+
+impl ColorLiteral {
+    pub(crate) fn apply_edit(self, edit: &Edit) -> Option<Self> {
+        Some(Self {
+            range: self.range.apply_edit(edit)?,
+            ..self
+        })
+    }
+}
+
+impl From<lsp_types::Color> for Color {
+    fn from(value: lsp_types::Color) -> Self {
+        let to_u8 = |component: f32| (component.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(to_u8(value.red), to_u8(value.green), to_u8(value.blue))
+    }
+}
+
+/// Parses `text` as a single color value.
+pub(crate) fn parse(text: &str) -> Option<Color> {
+    let text = text.trim();
+    if text.starts_with('#') {
+        // `Color::from_hex` does not expand the shorthand `#rgb`
+        return match text.len() {
+            7 | 9 => Color::from_hex(text).ok(),
+            4 => Color::from_hex(&format!(
+                "#{}",
+                text[1..].chars().flat_map(|c| [c, c]).collect::<String>()
+            ))
+            .ok(),
+            _ => None,
+        };
+    }
+    if let Some(captures) = rgb_regex()
+        .captures(text)
+        .filter(|captures| captures.get(0).is_some_and(|m| m.len() == text.len()))
+    {
+        return parse_rgb(&captures);
+    }
+    named_color(text)
+}
+
+/// Returns the byte ranges and the colors of the color values in `content`.
+///
+/// Named colors are only detected when `include_named_colors` is true,
+/// because words like `red` or `tan` are mostly not colors outside of stylesheets.
+pub(crate) fn find(content: &str, include_named_colors: bool) -> Vec<(Range<usize>, Color)> {
+    let hexes = lazy_regex::regex!(r"(?i)#(?:[0-9a-f]{8}|[0-9a-f]{6})\b")
+        .find_iter(content)
+        .filter_map(|m| Some((m.range(), Color::from_hex(m.as_str()).ok()?)));
+    let rgbs = rgb_regex()
+        .captures_iter(content)
+        .filter_map(|captures| Some((captures.get(0)?.range(), parse_rgb(&captures)?)));
+    let named = include_named_colors
+        .then(|| {
+            named_colors_regex()
+                .find_iter(content)
+                .filter_map(|m| Some((m.range(), named_color(m.as_str())?)))
+                .collect_vec()
+        })
+        .unwrap_or_default();
+    hexes
+        .chain(rgbs)
+        .chain(named)
+        .sorted_by_key(|(range, _)| range.start)
+        .collect()
+}
+
+fn rgb_regex() -> &'static Regex {
+    lazy_regex::regex!(
+        r"(?i)rgba?\(\s*(\d{1,3})\s*,\s*(\d{1,3})\s*,\s*(\d{1,3})\s*(?:,\s*[0-9.]+%?\s*)?\)"
+    )
+}
+
+fn parse_rgb(captures: &regex::Captures) -> Option<Color> {
+    let component = |index: usize| captures.get(index)?.as_str().parse::<u8>().ok();
+    Some(Color::new(component(1)?, component(2)?, component(3)?))
+}
+
+fn named_colors_regex() -> &'static Regex {
+    static REGEX: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(&format!(
+            r"(?i)\b(?:{})\b",
+            NAMED_COLORS.iter().map(|(name, _)| *name).join("|")
+        ))
+        .unwrap()
+    });
+    &REGEX
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    NAMED_COLORS
+        .iter()
+        .find(|(named, _)| named.eq_ignore_ascii_case(name))
+        .map(|(_, color)| *color)
+}
+
+/// Refer https://developer.mozilla.org/en-US/docs/Web/CSS/named-color
+const NAMED_COLORS: &[(&str, Color)] = &[
+    ("aliceblue", hex!("#f0f8ff")),
+    ("antiquewhite", hex!("#faebd7")),
+    ("aqua", hex!("#00ffff")),
+    ("aquamarine", hex!("#7fffd4")),
+    ("azure", hex!("#f0ffff")),
+    ("beige", hex!("#f5f5dc")),
+    ("bisque", hex!("#ffe4c4")),
+    ("black", hex!("#000000")),
+    ("blanchedalmond", hex!("#ffebcd")),
+    ("blue", hex!("#0000ff")),
+    ("blueviolet", hex!("#8a2be2")),
+    ("brown", hex!("#a52a2a")),
+    ("burlywood", hex!("#deb887")),
+    ("cadetblue", hex!("#5f9ea0")),
+    ("chartreuse", hex!("#7fff00")),
+    ("chocolate", hex!("#d2691e")),
+    ("coral", hex!("#ff7f50")),
+    ("cornflowerblue", hex!("#6495ed")),
+    ("cornsilk", hex!("#fff8dc")),
+    ("crimson", hex!("#dc143c")),
+    ("cyan", hex!("#00ffff")),
+    ("darkblue", hex!("#00008b")),
+    ("darkcyan", hex!("#008b8b")),
+    ("darkgoldenrod", hex!("#b8860b")),
+    ("darkgray", hex!("#a9a9a9")),
+    ("darkgreen", hex!("#006400")),
+    ("darkgrey", hex!("#a9a9a9")),
+    ("darkkhaki", hex!("#bdb76b")),
+    ("darkmagenta", hex!("#8b008b")),
+    ("darkolivegreen", hex!("#556b2f")),
+    ("darkorange", hex!("#ff8c00")),
+    ("darkorchid", hex!("#9932cc")),
+    ("darkred", hex!("#8b0000")),
+    ("darksalmon", hex!("#e9967a")),
+    ("darkseagreen", hex!("#8fbc8f")),
+    ("darkslateblue", hex!("#483d8b")),
+    ("darkslategray", hex!("#2f4f4f")),
+    ("darkslategrey", hex!("#2f4f4f")),
+    ("darkturquoise", hex!("#00ced1")),
+    ("darkviolet", hex!("#9400d3")),
+    ("deeppink", hex!("#ff1493")),
+    ("deepskyblue", hex!("#00bfff")),
+    ("dimgray", hex!("#696969")),
+    ("dimgrey", hex!("#696969")),
+    ("dodgerblue", hex!("#1e90ff")),
+    ("firebrick", hex!("#b22222")),
+    ("floralwhite", hex!("#fffaf0")),
+    ("forestgreen", hex!("#228b22")),
+    ("fuchsia", hex!("#ff00ff")),
+    ("gainsboro", hex!("#dcdcdc")),
+    ("ghostwhite", hex!("#f8f8ff")),
+    ("gold", hex!("#ffd700")),
+    ("goldenrod", hex!("#daa520")),
+    ("gray", hex!("#808080")),
+    ("green", hex!("#008000")),
+    ("greenyellow", hex!("#adff2f")),
+    ("grey", hex!("#808080")),
+    ("honeydew", hex!("#f0fff0")),
+    ("hotpink", hex!("#ff69b4")),
+    ("indianred", hex!("#cd5c5c")),
+    ("indigo", hex!("#4b0082")),
+    ("ivory", hex!("#fffff0")),
+    ("khaki", hex!("#f0e68c")),
+    ("lavender", hex!("#e6e6fa")),
+    ("lavenderblush", hex!("#fff0f5")),
+    ("lawngreen", hex!("#7cfc00")),
+    ("lemonchiffon", hex!("#fffacd")),
+    ("lightblue", hex!("#add8e6")),
+    ("lightcoral", hex!("#f08080")),
+    ("lightcyan", hex!("#e0ffff")),
+    ("lightgoldenrodyellow", hex!("#fafad2")),
+    ("lightgray", hex!("#d3d3d3")),
+    ("lightgreen", hex!("#90ee90")),
+    ("lightgrey", hex!("#d3d3d3")),
+    ("lightpink", hex!("#ffb6c1")),
+    ("lightsalmon", hex!("#ffa07a")),
+    ("lightseagreen", hex!("#20b2aa")),
+    ("lightskyblue", hex!("#87cefa")),
+    ("lightslategray", hex!("#778899")),
+    ("lightslategrey", hex!("#778899")),
+    ("lightsteelblue", hex!("#b0c4de")),
+    ("lightyellow", hex!("#ffffe0")),
+    ("lime", hex!("#00ff00")),
+    ("limegreen", hex!("#32cd32")),
+    ("linen", hex!("#faf0e6")),
+    ("magenta", hex!("#ff00ff")),
+    ("maroon", hex!("#800000")),
+    ("mediumaquamarine", hex!("#66cdaa")),
+    ("mediumblue", hex!("#0000cd")),
+    ("mediumorchid", hex!("#ba55d3")),
+    ("mediumpurple", hex!("#9370db")),
+    ("mediumseagreen", hex!("#3cb371")),
+    ("mediumslateblue", hex!("#7b68ee")),
+    ("mediumspringgreen", hex!("#00fa9a")),
+    ("mediumturquoise", hex!("#48d1cc")),
+    ("mediumvioletred", hex!("#c71585")),
+    ("midnightblue", hex!("#191970")),
+    ("mintcream", hex!("#f5fffa")),
+    ("mistyrose", hex!("#ffe4e1")),
+    ("moccasin", hex!("#ffe4b5")),
+    ("navajowhite", hex!("#ffdead")),
+    ("navy", hex!("#000080")),
+    ("oldlace", hex!("#fdf5e6")),
+    ("olive", hex!("#808000")),
+    ("olivedrab", hex!("#6b8e23")),
+    ("orange", hex!("#ffa500")),
+    ("orangered", hex!("#ff4500")),
+    ("orchid", hex!("#da70d6")),
+    ("palegoldenrod", hex!("#eee8aa")),
+    ("palegreen", hex!("#98fb98")),
+    ("paleturquoise", hex!("#afeeee")),
+    ("palevioletred", hex!("#db7093")),
+    ("papayawhip", hex!("#ffefd5")),
+    ("peachpuff", hex!("#ffdab9")),
+    ("peru", hex!("#cd853f")),
+    ("pink", hex!("#ffc0cb")),
+    ("plum", hex!("#dda0dd")),
+    ("powderblue", hex!("#b0e0e6")),
+    ("purple", hex!("#800080")),
+    ("rebeccapurple", hex!("#663399")),
+    ("red", hex!("#ff0000")),
+    ("rosybrown", hex!("#bc8f8f")),
+    ("royalblue", hex!("#4169e1")),
+    ("saddlebrown", hex!("#8b4513")),
+    ("salmon", hex!("#fa8072")),
+    ("sandybrown", hex!("#f4a460")),
+    ("seagreen", hex!("#2e8b57")),
+    ("seashell", hex!("#fff5ee")),
+    ("sienna", hex!("#a0522d")),
+    ("silver", hex!("#c0c0c0")),
+    ("skyblue", hex!("#87ceeb")),
+    ("slateblue", hex!("#6a5acd")),
+    ("slategray", hex!("#708090")),
+    ("slategrey", hex!("#708090")),
+    ("snow", hex!("#fffafa")),
+    ("springgreen", hex!("#00ff7f")),
+    ("steelblue", hex!("#4682b4")),
+    ("tan", hex!("#d2b48c")),
+    ("teal", hex!("#008080")),
+    ("thistle", hex!("#d8bfd8")),
+    ("tomato", hex!("#ff6347")),
+    ("turquoise", hex!("#40e0d0")),
+    ("violet", hex!("#ee82ee")),
+    ("wheat", hex!("#f5deb3")),
+    ("white", hex!("#ffffff")),
+    ("whitesmoke", hex!("#f5f5f5")),
+    ("yellow", hex!("#ffff00")),
+    ("yellowgreen", hex!("#9acd32")),
+];
+
+#[cfg(test)]
+mod test_color_literal {
+    use super::*;
+
+    #[test]
+    fn find_hex_rgb_and_named_colors() {
+        let content = "a { color: #ff0000; background: rgb(0, 128, 255); border: red; }";
+        let found = |include_named_colors| {
+            find(content, include_named_colors)
+                .into_iter()
+                .map(|(range, color)| (&content[range], color))
+                .collect_vec()
+        };
+        assert_eq!(
+            found(true),
+            [
+                ("#ff0000", hex!("#ff0000")),
+                ("rgb(0, 128, 255)", Color::new(0, 128, 255)),
+                ("red", hex!("#ff0000")),
+            ]
+        );
+        assert_eq!(
+            found(false),
+            [
+                ("#ff0000", hex!("#ff0000")),
+                ("rgb(0, 128, 255)", Color::new(0, 128, 255)),
+            ]
+        );
+    }
+
+    #[test]
+    fn named_colors_are_whole_words() {
+        assert!(find("bored credit", true).is_empty())
+    }
+
+    #[test]
+    fn parse_single_color() {
+        assert_eq!(parse("#f00"), Some(hex!("#ff0000")));
+        assert_eq!(parse(" #00FF00 "), Some(hex!("#00ff00")));
+        assert_eq!(parse("rgba(1, 2, 3, 0.5)"), Some(Color::new(1, 2, 3)));
+        assert_eq!(parse("RebeccaPurple"), Some(hex!("#663399")));
+        assert_eq!(parse("rgb(256, 0, 0)"), None);
+        assert_eq!(parse("rgb(0, 0, 0) x"), None);
+        assert_eq!(parse("#ff00"), None);
+        assert_eq!(parse("reddish"), None);
+    }
+}
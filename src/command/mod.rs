@@ -1,6 +1,6 @@
 use crate::{
     app::{Dispatch, Dispatches},
-    components::{dropdown::DropdownItem, suggestive_editor::Info},
+    components::{dropdown::DropdownItem, editor::DispatchEditor, suggestive_editor::Info},
 };
 
 pub(crate) struct Command {
@@ -50,4 +50,96 @@ pub const COMMANDS: &[Command] = &[
         description: "Save all buffers",
         dispatch: Dispatch::SaveAll,
     },
+    Command {
+        name: "save-as",
+        description: "Write the current buffer to a new file, and continue editing that file",
+        dispatch: Dispatch::OpenSaveAsPrompt,
+    },
+    Command {
+        name: "rename-file",
+        description:
+            "Move the file of the current buffer, staging the move if it is tracked by git",
+        dispatch: Dispatch::OpenRenameFilePrompt,
+    },
+    Command {
+        name: "cd",
+        description: "Change the working directory of the session",
+        dispatch: Dispatch::OpenChangeWorkingDirectoryPrompt,
+    },
+    Command {
+        name: "scratch",
+        description: "Open a new scratch buffer, which is not saved until `save-as`",
+        dispatch: Dispatch::NewScratchBuffer,
+    },
+    Command {
+        name: "scratch-buffers",
+        description: "Switch to one of the scratch buffers",
+        dispatch: Dispatch::OpenScratchBufferPicker,
+    },
+    Command {
+        name: "daily-note",
+        description: "Open the note of today in the notes directory",
+        dispatch: Dispatch::OpenDailyNote,
+    },
+    Command {
+        name: "view",
+        description: "Make the current buffer read-only",
+        dispatch: Dispatch::ToEditor(DispatchEditor::SetReadOnly(true)),
+    },
+    Command {
+        name: "edit",
+        description: "Make the current buffer editable again",
+        dispatch: Dispatch::ToEditor(DispatchEditor::SetReadOnly(false)),
+    },
+    Command {
+        name: "todos",
+        description:
+            "List the TODO, FIXME, HACK and NOTE comments of the workspace, grouped by tag",
+        dispatch: Dispatch::CollectAnnotations,
+    },
+    Command {
+        name: "format-selections",
+        description: "Format only the selections with the formatters of the current language",
+        dispatch: Dispatch::ToEditor(DispatchEditor::FormatSelections),
+    },
+    Command {
+        name: "go-to-last-edit",
+        description: "Go to the location of the latest edit, across all files",
+        dispatch: Dispatch::GoToLastEdit,
+    },
+    Command {
+        name: "cycle-edit-locations",
+        description: "Go to the location of the edit before the one last gone to, across all files",
+        dispatch: Dispatch::CycleEditLocations,
+    },
+    Command {
+        name: "tutor",
+        description: "Start the interactive tutorial",
+        dispatch: Dispatch::StartTutor,
+    },
+    Command {
+        name: "keymap-cheat-sheet",
+        description: "Show all keymaps in a searchable buffer",
+        dispatch: Dispatch::ShowKeymapCheatSheet,
+    },
+    Command {
+        name: "export-keymap-cheat-sheet",
+        description: "Export all keymaps as Markdown or HTML",
+        dispatch: Dispatch::OpenExportKeymapCheatSheetPrompt,
+    },
+    Command {
+        name: "export-highlighted",
+        description: "Export the buffer with syntax highlighting as HTML or ANSI",
+        dispatch: Dispatch::OpenExportHighlightedPrompt { selection: false },
+    },
+    Command {
+        name: "export-highlighted-selection",
+        description: "Export the primary selection with syntax highlighting as HTML or ANSI",
+        dispatch: Dispatch::OpenExportHighlightedPrompt { selection: true },
+    },
+    Command {
+        name: "keyboard-layout",
+        description: "Show how the keys of the active keyboard layout are mapped",
+        dispatch: Dispatch::ShowKeyboardLayout,
+    },
 ];
@@ -34,14 +34,14 @@ impl std::fmt::Display for GetGridResult {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) struct Cursor {
     position: Position,
     style: SetCursorStyle,
 }
 
 /// Why is this necessary? Because `crossterm::cursor::SetCursorStyle` does not implement `Debug`.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetCursorStyle {
     DefaultUserShape,
     BlinkingBlock,
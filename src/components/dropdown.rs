@@ -13,6 +13,9 @@ use super::suggestive_editor::{Decoration, Info};
 /// Note: filtering will be done on the combination of `display` and `group` (if applicable)
 pub(crate) struct DropdownItem {
     pub(crate) dispatches: Dispatches,
+    /// Fired by `alt+enter` in prompts instead of `dispatches`,
+    /// for example to close a buffer instead of opening it.
+    secondary_dispatches: Option<Dispatches>,
     display: String,
     group: Option<String>,
     info: Option<Info>,
@@ -32,6 +35,7 @@ impl DropdownItem {
     pub(crate) fn new(display: String) -> Self {
         Self {
             dispatches: Default::default(),
+            secondary_dispatches: None,
             display,
             group: Default::default(),
             info: Default::default(),
@@ -49,6 +53,17 @@ impl DropdownItem {
         Self { dispatches, ..self }
     }
 
+    pub(crate) fn set_secondary_dispatches(self, secondary_dispatches: Dispatches) -> DropdownItem {
+        Self {
+            secondary_dispatches: Some(secondary_dispatches),
+            ..self
+        }
+    }
+
+    pub(crate) fn secondary_dispatches(&self) -> Option<Dispatches> {
+        self.secondary_dispatches.clone()
+    }
+
     pub(crate) fn set_group(self, group: Option<String>) -> Self {
         Self { group, ..self }
     }
@@ -57,6 +72,20 @@ impl DropdownItem {
         Self { rank, ..self }
     }
 
+    /// Append `rank` as the least significant component of the existing rank.
+    pub(crate) fn append_rank(self, rank: usize) -> DropdownItem {
+        let rank = self
+            .rank
+            .iter()
+            .flat_map(|rank| rank.iter().copied())
+            .chain(Some(rank))
+            .collect();
+        Self {
+            rank: Some(rank),
+            ..self
+        }
+    }
+
     pub(crate) fn set_on_focused(self, on_focused: Dispatches) -> DropdownItem {
         Self { on_focused, ..self }
     }
@@ -105,6 +134,60 @@ pub(crate) struct Dropdown {
     items: Vec<DropdownItem>,
     filtered_item_groups: Vec<FilteredDropdownItemGroup>,
     current_item_index: usize,
+    /// In the order they were marked.
+    marked_items: Vec<DropdownItem>,
+    /// Whether the filter is parsed as a `PathQuery`, which is meant for file pickers.
+    path_query: bool,
+}
+
+/// The path terms of the filter of file pickers, which are matched as case-insensitive substrings,
+/// while the other terms are matched fuzzily.
+#[derive(Debug, Default, PartialEq)]
+struct PathQuery {
+    /// From `dir/`, the folder of the item must contain each of them.
+    directories: Vec<String>,
+    /// From `!pattern`, neither the folder nor the name of the item may contain any of them.
+    excludes: Vec<String>,
+    /// From `.rs$`, the name of the item must end with each of them.
+    suffixes: Vec<String>,
+}
+
+impl PathQuery {
+    /// Returns the query and the remaining terms of `filter`.
+    fn parse(filter: &str) -> (Self, String) {
+        let mut query = PathQuery::default();
+        let mut rest = Vec::new();
+        for term in filter.split_whitespace() {
+            let lowercase = term.to_lowercase();
+            if let Some(pattern) = lowercase.strip_prefix('!').filter(|s| !s.is_empty()) {
+                query.excludes.push(pattern.to_string())
+            } else if let Some(suffix) = lowercase.strip_suffix('$').filter(|s| !s.is_empty()) {
+                query.suffixes.push(suffix.to_string())
+            } else if let Some(directory) = lowercase
+                .strip_suffix('/')
+                .map(|s| s.trim_end_matches('/'))
+                .filter(|s| !s.is_empty())
+            {
+                query.directories.push(directory.to_string())
+            } else {
+                rest.push(term)
+            }
+        }
+        (query, rest.join(" "))
+    }
+
+    fn matches(&self, item: &DropdownItem) -> bool {
+        let group = item.group.as_deref().unwrap_or_default().to_lowercase();
+        let display = item.display.to_lowercase();
+        self.directories
+            .iter()
+            .all(|directory| group.contains(directory))
+            && !self
+                .excludes
+                .iter()
+                .any(|pattern| group.contains(pattern) || display.contains(pattern))
+            && self.suffixes.iter().all(|suffix| display.ends_with(suffix))
+    }
 }
 
 pub(crate) struct DropdownConfig {
@@ -118,10 +201,18 @@ impl Dropdown {
             items: vec![],
             filtered_item_groups: vec![],
             current_item_index: 0,
+            marked_items: Vec::new(),
             title: config.title,
+            path_query: false,
         }
     }
 
+    /// See `PathQuery`.
+    pub(crate) fn set_path_query(&mut self, path_query: bool) {
+        self.path_query = path_query;
+        self.compute_filtered_items();
+    }
+
     pub(crate) fn change_index(&mut self, index: usize) {
         if !self.filtered_item_groups.iter().any(|group| {
             group
@@ -247,6 +338,7 @@ impl Dropdown {
         if items == self.items {
             return;
         }
+        self.marked_items.retain(|item| items.contains(item));
         self.items = items;
         self.current_item_index = 0;
         self.compute_filtered_items();
@@ -258,30 +350,39 @@ impl Dropdown {
             Config, Matcher,
         };
         let mut matcher = Matcher::new(Config::DEFAULT);
-        let pattern = Pattern::parse(&self.filter, CaseMatching::Ignore, Normalization::Smart);
+        let (path_query, filter) = if self.path_query {
+            PathQuery::parse(&self.filter)
+        } else {
+            (PathQuery::default(), self.filter.clone())
+        };
+        let pattern = Pattern::parse(&filter, CaseMatching::Ignore, Normalization::Smart);
         let mut haystack = Vec::new();
-        let matches = self.items.iter().filter_map(|item| {
-            let score = pattern
-                .atoms
-                .iter()
-                .map(|atom| {
-                    let score_group = item.group.as_ref().and_then(|group| {
-                        haystack.clear();
-                        atom.score(Utf32Str::new(group, &mut haystack), &mut matcher)
-                    });
-                    let score_display = {
-                        haystack.clear();
-                        atom.score(Utf32Str::new(&item.display, &mut haystack), &mut matcher)
-                    };
-                    match (score_group, score_display) {
-                        (None, None) => None,
-                        (None, Some(score)) | (Some(score), None) => Some(score),
-                        (Some(a), Some(b)) => Some(a + b),
-                    }
-                })
-                .try_fold(0, |total_score, score| Some(total_score + score?))?;
-            Some((item, score as u32))
-        });
+        let matches = self
+            .items
+            .iter()
+            .filter(|item| path_query.matches(item))
+            .filter_map(|item| {
+                let score = pattern
+                    .atoms
+                    .iter()
+                    .map(|atom| {
+                        let score_group = item.group.as_ref().and_then(|group| {
+                            haystack.clear();
+                            atom.score(Utf32Str::new(group, &mut haystack), &mut matcher)
+                        });
+                        let score_display = {
+                            haystack.clear();
+                            atom.score(Utf32Str::new(&item.display, &mut haystack), &mut matcher)
+                        };
+                        match (score_group, score_display) {
+                            (None, None) => None,
+                            (None, Some(score)) | (Some(score), None) => Some(score),
+                            (Some(a), Some(b)) => Some(a + b),
+                        }
+                    })
+                    .try_fold(0, |total_score, score| Some(total_score + score?))?;
+                Some((item, score as u32))
+            });
         let mut haystack_buf = Vec::new();
         let mut matched_char_indices = Vec::new();
         let mut item_index = 0;
@@ -402,6 +503,10 @@ impl Dropdown {
             .collect_vec();
     }
 
+    pub(crate) fn set_title(&mut self, title: String) {
+        self.title = title
+    }
+
     pub(crate) fn set_filter(&mut self, filter: &str) {
         if filter == self.filter {
             return;
@@ -411,9 +516,29 @@ impl Dropdown {
         self.compute_filtered_items();
     }
 
+    /// Marks the current item, or unmarks it if it is already marked.
+    pub(crate) fn toggle_mark_current_item(&mut self) {
+        let Some(item) = self.current_item() else {
+            return;
+        };
+        if let Some(index) = self.marked_items.iter().position(|marked| marked == &item) {
+            self.marked_items.remove(index);
+        } else {
+            self.marked_items.push(item)
+        }
+    }
+
+    pub(crate) fn marked_items(&self) -> Vec<DropdownItem> {
+        self.marked_items.clone()
+    }
+
     pub(crate) fn render(&self) -> DropdownRender {
         DropdownRender {
-            title: self.title.clone(),
+            title: if self.marked_items.is_empty() {
+                self.title.clone()
+            } else {
+                format!("{} ({} marked)", self.title, self.marked_items.len())
+            },
             content: self.content(),
             decorations: self.decorations(),
             highlight_line_index: self.current_item_line_index(),
@@ -432,7 +557,7 @@ impl Dropdown {
                         .iter()
                         .enumerate()
                         .map(|(index, item)| {
-                            let content = item.item.display();
+                            let content = self.item_content(&item.item);
                             let indicator = if index == items_len.saturating_sub(1) {
                                 "└─"
                             } else {
@@ -446,7 +571,7 @@ impl Dropdown {
                     group
                         .items
                         .iter()
-                        .map(|item| item.item.display())
+                        .map(|item| self.item_content(&item.item))
                         .join("\n")
                 }
             })
@@ -454,6 +579,16 @@ impl Dropdown {
             .join("\n\n")
     }
 
+    /// Marked items are suffixed with a check mark, which does not shift the decorations
+    /// of the fuzzy matched characters.
+    fn item_content(&self, item: &DropdownItem) -> String {
+        if self.marked_items.contains(item) {
+            format!("{} ✓", item.display())
+        } else {
+            item.display()
+        }
+    }
+
     pub(crate) fn apply_movement(&mut self, movement: Movement) {
         match movement {
             Movement::Next => self.next_item(),
@@ -555,6 +690,8 @@ impl Dropdown {
             debug_assert!(!matching.resolved());
             *matching = DropdownItem {
                 resolved: true,
+                // Keep the rank, because it is computed when the completion list is received
+                rank: matching.rank.clone(),
                 ..item
             }
         }
@@ -750,6 +887,45 @@ mod test_dropdown {
         )
     }
 
+    #[test]
+    fn path_query() {
+        let mut dropdown = Dropdown::new(DropdownConfig {
+            title: "test".to_string(),
+        });
+        dropdown.set_items(
+            [
+                Item::new("main.rs", "", "src"),
+                Item::new("main.ts", "", "web/src"),
+                Item::new("Button.test.ts", "", "web/src/components"),
+                Item::new("Button.ts", "", "web/src/components"),
+            ]
+            .into_iter()
+            .map(|s| s.into())
+            .collect(),
+        );
+        dropdown.set_path_query(true);
+        let displays = |dropdown: &Dropdown| {
+            dropdown
+                .filtered_item_groups
+                .iter()
+                .flat_map(|group| &group.items)
+                .map(|item| item.item.display())
+                .sorted()
+                .collect_vec()
+        };
+        dropdown.set_filter("WEB/ main");
+        assert_eq!(displays(&dropdown), ["main.ts"]);
+        dropdown.set_filter("comp/ !test");
+        assert_eq!(displays(&dropdown), ["Button.ts"]);
+        dropdown.set_filter(".TS$ !comp");
+        assert_eq!(displays(&dropdown), ["main.ts"]);
+
+        // Expect path terms are matched fuzzily by other dropdowns
+        dropdown.set_path_query(false);
+        dropdown.set_filter("src/");
+        assert_eq!(displays(&dropdown), ["Button.test.ts", "Button.ts"]);
+    }
+
     #[test]
     fn test_next_prev_group() {
         let mut dropdown = Dropdown::new(DropdownConfig {
@@ -1,13 +1,18 @@
 use crate::{
-    app::{Dispatches, RequestParams},
+    app::{Dispatches, RequestParams, YesNoPrompt},
     buffer::Line,
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
     context::{Context, GlobalMode, LocalSearchConfigMode, Search},
+    expression,
     history::History,
+    increment,
     lsp::{completion::CompletionItemEdit, process::ResponseContext},
+    path_or_url::{self, PathOrUrl},
+    quickfix_list::Location,
     selection::Filter,
     selection_mode::{self, CaseAgnostic},
+    shell,
     surround::EnclosureKind,
     transformation::Transformation,
 };
@@ -62,6 +67,36 @@ pub(crate) struct Jump {
     pub(crate) character: char,
     pub(crate) selection: Selection,
 }
+
+/// How the jumps are labelled when jumping with `DispatchEditor::ShowJumps`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum JumpLabelStrategy {
+    /// Label each selection of the viewport with its first character,
+    /// the selections sharing the typed character are then relabelled.
+    #[default]
+    FirstChar,
+    /// Label the start of every word of the viewport with distinct characters,
+    /// regardless of the current selection mode.
+    WordStarts,
+    /// Type the first two characters of the selection, then its label,
+    /// like avy and leap.nvim.
+    TwoChars,
+}
+
+impl std::str::FromStr for JumpLabelStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "first-char" => Ok(Self::FirstChar),
+            "word-starts" => Ok(Self::WordStarts),
+            "two-chars" => Ok(Self::TwoChars),
+            _ => Err(anyhow::anyhow!(
+                "Unknown jump label strategy {s:?}, expected one of first-char, word-starts or two-chars"
+            )),
+        }
+    }
+}
 const WINDOW_TITLE_HEIGHT: usize = 1;
 
 impl Component for Editor {
@@ -92,7 +127,22 @@ impl Component for Editor {
                     .display_relative_to(current_working_directory)
                     .unwrap_or_else(|_| path.display_absolute());
                 let icon = path.icon();
-                Some(format!(" {} {}", icon, string))
+                let breadcrumbs = if context.breadcrumbs() {
+                    self.buffer()
+                        .breadcrumbs(self.get_cursor_char_index())
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|breadcrumb| format!(" › {}", breadcrumb.name))
+                        .join("")
+                } else {
+                    String::new()
+                };
+                let read_only = if self.buffer().read_only() {
+                    " [read-only]"
+                } else {
+                    ""
+                };
+                Some(format!(" {} {}{}{}", icon, string, read_only, breadcrumbs))
             })
             .unwrap_or_else(|| "[No title]".to_string())
     }
@@ -101,8 +151,18 @@ impl Component for Editor {
         self.title = Some(title);
     }
 
+    /// The pasted content is inserted as one edit (hence one undo entry),
+    /// without going through the keymaps.
     fn handle_paste_event(&mut self, content: String) -> anyhow::Result<Dispatches> {
-        self.insert(&content)
+        if self.buffer().read_only() {
+            return Ok(self.read_only_dispatches());
+        }
+        // Some terminals send newlines as carriage returns in bracketed paste
+        let content = content.replace("\r\n", "\n").replace('\r', "\n");
+        self.paste_text(
+            Direction::End,
+            CopiedTexts::new(NonEmpty::singleton(content)),
+        )
     }
 
     fn get_cursor_position(&self) -> anyhow::Result<Position> {
@@ -169,10 +229,7 @@ impl Component for Editor {
     ) -> anyhow::Result<Dispatches> {
         match event {
             event::event::Event::Key(event) => self.handle_key_event(context, event),
-            event::event::Event::Paste(content) => self.paste_text(
-                Direction::End,
-                CopiedTexts::new(NonEmpty::singleton(content)),
-            ),
+            event::event::Event::Paste(content) => self.handle_paste_event(content),
             event::event::Event::Mouse(event) => self.handle_mouse_event(event),
             _ => Ok(Default::default()),
         }
@@ -183,12 +240,27 @@ impl Component for Editor {
         context: &mut Context,
         dispatch: DispatchEditor,
     ) -> anyhow::Result<Dispatches> {
+        if self.buffer().read_only() && dispatch.is_edit() {
+            return Ok(self.read_only_dispatches());
+        }
         match dispatch {
             #[cfg(test)]
             AlignViewTop => self.align_cursor_to_top(),
             #[cfg(test)]
             AlignViewBottom => self.align_cursor_to_bottom(),
             Transform(transformation) => return self.transform_selection(transformation),
+            JoinLines => return self.join_lines(),
+            SplitLine => return self.split_line(),
+            ToggleArgumentsLayout => return self.toggle_arguments_layout(),
+            IncrementNumber { by, sequential } => return self.increment_number(by, sequential),
+            PipeToShell {
+                command,
+                per_selection,
+            } => return self.pipe_to_shell(&command, per_selection),
+            InsertShellOutput(command) => return self.insert_shell_output(&command),
+            InsertExpressionResult(expression) => {
+                return self.insert_expression_result(&expression)
+            }
             SetSelectionMode(selection_mode) => {
                 return self.set_selection_mode(selection_mode);
             }
@@ -199,6 +271,10 @@ impl Component for Editor {
             Copy {
                 use_system_clipboard,
             } => return self.copy(use_system_clipboard),
+            RevertGitHunk => return self.revert_git_hunk(),
+            CopyGitHunkOldText {
+                use_system_clipboard,
+            } => return self.copy_git_hunk_old_text(use_system_clipboard),
             ReplaceWithCopiedText {
                 cut,
                 use_system_clipboard,
@@ -207,12 +283,25 @@ impl Component for Editor {
             SetContent(content) => self.set_content(&content)?,
             ToggleVisualMode => self.toggle_visual_mode(),
             EnterUndoTreeMode => return Ok(self.enter_undo_tree_mode()),
+            OpenPathOrUrlUnderCursor => return self.open_path_or_url_under_cursor(context),
             EnterInsertMode(direction) => return self.enter_insert_mode(direction),
             Delete { backward } => return self.delete(backward),
             Insert(string) => return self.insert(&string),
             #[cfg(test)]
             MatchLiteral(literal) => return self.match_literal(&literal),
             ToggleBookmark => self.toggle_bookmarks(),
+            SelectLastInserted => return self.select_last_inserted(),
+            FilterSelections(filter) => return self.filter_selections(filter),
+            KeepEveryNthSelection(n) => return self.keep_every_nth_selection(n),
+            SplitSelections(regex) => return self.split_selections(&regex),
+            SelectNextOccurrence { skip_current } => {
+                return self.select_next_occurrence(skip_current)
+            }
+            CyclePrimarySelection(direction) => self.cycle_primary_selection(&direction),
+            RotateSelectionsContent(direction) => {
+                return self.rotate_selections_content(&direction)
+            }
+            SetBookmarkNote(note) => self.set_bookmark_note(&note),
             EnterNormalMode => self.enter_normal_mode()?,
             FilterPush(filter) => return Ok(self.filters_push(context, filter)),
             CursorAddToAllSelections => self.add_cursor_to_all_selections()?,
@@ -245,11 +334,19 @@ impl Component for Editor {
             } => return self.change_cut(use_system_clipboard),
             #[cfg(test)]
             SetRectangle(rectangle) => self.set_rectangle(rectangle),
-            ScrollPageDown => return self.scroll_page_down(),
-            ScrollPageUp => return self.scroll_page_up(),
+            ScrollPageDown => return self.scroll(Direction::End, self.page_height()),
+            ScrollPageUp => return self.scroll(Direction::Start, self.page_height()),
+            ScrollHalfPageDown => return self.scroll(Direction::End, self.half_page_height()),
+            ScrollHalfPageUp => return self.scroll(Direction::Start, self.half_page_height()),
+            ScrollLinesDown => return self.scroll(Direction::End, context.scroll_lines()),
+            ScrollLinesUp => return self.scroll(Direction::Start, context.scroll_lines()),
             ShowJumps {
                 use_current_selection_mode,
-            } => self.show_jumps(use_current_selection_mode)?,
+            } => self.show_jumps(
+                use_current_selection_mode,
+                context.jump_label_strategy(),
+                &context.jump_characters(),
+            )?,
             SwitchViewAlignment => self.switch_view_alignment(),
             #[cfg(test)]
             SetScrollOffset(n) => self.set_scroll_offset(n),
@@ -300,17 +397,41 @@ impl Component for Editor {
                 self.selection_set
                     .move_right(&self.cursor_direction, len_chars)
             }
+            MoveWord(direction) => {
+                let rope = self.buffer().rope().clone();
+                self.selection_set
+                    .move_word(&self.cursor_direction, &rope, &direction)
+            }
+            InsertRegister(register) => return self.insert_register(context, register),
             Open(direction) => return self.open(direction),
+            Duplicate(direction) => return self.duplicate(direction),
+            MoveSelectionContent(direction) => {
+                return self.exchange(match direction {
+                    Direction::Start => Movement::Previous,
+                    Direction::End => Movement::Next,
+                })
+            }
             TryReplaceCurrentLongWord(replacement) => {
                 return self.try_replace_current_long_word(replacement)
             }
+            TryReplaceCurrentPathSegment(replacement) => {
+                return self.try_replace_current_path_segment(replacement)
+            }
             GoBack => self.go_back(),
             GoForward => self.go_forward(),
             SelectSurround { enclosure, kind } => return self.select_surround(enclosure, kind),
+            GoToMatchingPair { extend } => return self.go_to_matching_pair(extend),
             DeleteSurround(enclosure) => return self.delete_surround(enclosure),
             ChangeSurround { from, to } => return self.change_surround(from, Some(to)),
             ReplaceWithPattern => return self.replace_with_pattern(context),
             Replace(movement) => return self.replace_with_movement(&movement),
+            FormatSelections => return self.format_selections(),
+            SetReadOnly(read_only) => {
+                self.buffer_mut().set_read_only(read_only);
+                if read_only {
+                    self.enter_normal_mode()?
+                }
+            }
             ApplyPositionalEdits(edits) => {
                 return self.apply_positional_edits(
                     edits
@@ -334,12 +455,61 @@ impl Component for Editor {
     }
 }
 
+/// Returns the length of the comma that ends `node`, which is 0 if `node` does not end with one.
+///
+/// Some grammars include the separator in a list item, such as the `match_arm` of Rust,
+/// which ends with a comma unless it is the last arm.
+fn trailing_separator_byte_len(node: &tree_sitter::Node) -> usize {
+    node.child(node.child_count().saturating_sub(1))
+        .filter(|last_child| !last_child.is_named() && last_child.kind() == ",")
+        .map(|last_child| last_child.byte_range().len())
+        .unwrap_or_default()
+}
+
+/// Whether `node` is a list of items separated by commas and enclosed by brackets,
+/// such as the arguments of a call or the elements of an array.
+///
+/// Lists with comments are excluded, because a line comment would swallow the items after it
+/// when the items are joined into one line.
+fn is_bracketed_list(node: &tree_sitter::Node) -> bool {
+    let children = (0..node.child_count())
+        .filter_map(|index| node.child(index))
+        .collect_vec();
+    let [first, middle @ .., last] = children.as_slice() else {
+        return false;
+    };
+    let items = middle
+        .iter()
+        .filter(|child| child.kind() != ",")
+        .collect_vec();
+    matches!(
+        (first.kind(), last.kind()),
+        ("(", ")") | ("[", "]") | ("{", "}")
+    ) && !items.is_empty()
+        && items
+            .iter()
+            .all(|item| item.is_named() && !item.kind().contains("comment"))
+        && middle.len() - items.len() + 1 >= items.len()
+}
+
+/// Edits within `range`, including those at its boundaries such as typing at its end,
+/// grow or shrink it, while the other edits shift it.
+fn extend_inserted_range(range: CharIndexRange, edit: &Edit) -> Option<CharIndexRange> {
+    if range.start <= edit.range.start && edit.range.end <= range.end {
+        let end = (range.end.0 as isize + edit.chars_offset()).max(range.start.0 as isize);
+        Some((range.start..CharIndex(end as usize)).into())
+    } else {
+        range.apply_edit(edit)
+    }
+}
+
 impl Clone for Editor {
     fn clone(&self) -> Self {
         Editor {
             mode: self.mode.clone(),
             selection_set: self.selection_set.clone(),
             jumps: None,
+            jump_search: None,
             cursor_direction: self.cursor_direction.clone(),
             scroll_offset: self.scroll_offset,
             rectangle: self.rectangle.clone(),
@@ -350,6 +520,9 @@ impl Clone for Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            ghost_text: None,
+            last_inserted_ranges: Vec::new(),
+            line_number_offset: self.line_number_offset,
         }
     }
 }
@@ -361,6 +534,9 @@ pub(crate) struct Editor {
     pub(crate) selection_set: SelectionSet,
 
     pub(crate) jumps: Option<Vec<Jump>>,
+    /// The characters typed so far when the jumps are labelled by `JumpLabelStrategy::TwoChars`,
+    /// the jumps are hidden until two characters are typed.
+    jump_search: Option<String>,
     pub(crate) cursor_direction: Direction,
 
     /// This means the number of lines to be skipped from the top during rendering.
@@ -374,6 +550,15 @@ pub(crate) struct Editor {
     pub(crate) current_view_alignment: Option<ViewAlignment>,
     selection_set_history: History<SelectionSet>,
     copied_text_history_offset: Counter,
+    /// Inline suggestion rendered as dimmed text after the cursor,
+    /// see `CompletionSource`.
+    ghost_text: Option<String>,
+    /// The ranges of the text inserted by the last paste or insert session, one for each cursor,
+    /// see `Editor::select_last_inserted`.
+    last_inserted_ranges: Vec<CharIndexRange>,
+    /// The number of lines above the content, which is added to the rendered line numbers
+    /// so that an excerpt of a file shows the line numbers of the file.
+    line_number_offset: usize,
 }
 
 #[derive(Default)]
@@ -497,6 +682,7 @@ impl Editor {
         Self {
             selection_set: SelectionSet::default(),
             jumps: None,
+            jump_search: None,
             mode: Mode::Normal,
             cursor_direction: Direction::Start,
             scroll_offset: 0,
@@ -508,6 +694,9 @@ impl Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            ghost_text: None,
+            last_inserted_ranges: Vec::new(),
+            line_number_offset: 0,
         }
     }
 
@@ -515,6 +704,7 @@ impl Editor {
         Self {
             selection_set: SelectionSet::default(),
             jumps: None,
+            jump_search: None,
             mode: Mode::Normal,
             cursor_direction: Direction::Start,
             scroll_offset: 0,
@@ -526,9 +716,27 @@ impl Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            ghost_text: None,
+            last_inserted_ranges: Vec::new(),
+            line_number_offset: 0,
         }
     }
 
+    /// The first and last (0-based) lines of the primary selection.
+    /// The line at which the selection ends is excluded if the selection ends at its start,
+    /// for example when the selection includes the trailing newline of the previous line.
+    pub(crate) fn primary_selection_lines(&self) -> anyhow::Result<(usize, usize)> {
+        let range = self.buffer().char_index_range_to_position_range(
+            self.selection_set.primary_selection().extended_range(),
+        )?;
+        let end_line = if range.end.column == 0 && range.end.line > range.start.line {
+            range.end.line - 1
+        } else {
+            range.end.line
+        };
+        Ok((range.start.line, end_line))
+    }
+
     pub(crate) fn current_line(&self) -> anyhow::Result<String> {
         let cursor = self.get_cursor_char_index();
         Ok(self
@@ -540,6 +748,13 @@ impl Editor {
             .into())
     }
 
+    pub(crate) fn current_line_before_cursor(&self) -> anyhow::Result<String> {
+        let cursor = self.get_cursor_char_index();
+        let buffer = self.buffer.borrow();
+        let line_start = buffer.line_to_char(buffer.char_to_line(cursor)?)?;
+        Ok(buffer.slice(&(line_start..cursor).into())?.to_string())
+    }
+
     pub(crate) fn get_current_word(&self) -> anyhow::Result<String> {
         let cursor = self.get_cursor_char_index();
         self.buffer.borrow().get_word_before_char_index(cursor)
@@ -550,8 +765,21 @@ impl Editor {
     }
 
     pub(crate) fn select_line_at(&mut self, line: usize) -> anyhow::Result<Dispatches> {
+        let selection_set = self.line_selection_set(line)?;
+        Ok(self.update_selection_set(selection_set, false))
+    }
+
+    /// Selects the given line without showing the info of the selection,
+    /// for editors that are never focused, such as previews.
+    pub(crate) fn highlight_line_at(&mut self, line: usize) -> anyhow::Result<()> {
+        let selection_set = self.line_selection_set(line)?;
+        self.set_selection_set(selection_set);
+        Ok(())
+    }
+
+    fn line_selection_set(&self, line: usize) -> anyhow::Result<SelectionSet> {
         let start = self.buffer.borrow().line_to_char(line)?;
-        let selection_set = SelectionSet::new(NonEmpty::singleton(Selection::new(
+        Ok(SelectionSet::new(NonEmpty::singleton(Selection::new(
             (start
                 ..start
                     + self
@@ -560,9 +788,72 @@ impl Editor {
                         .get_line_by_char_index(start)?
                         .len_chars())
                 .into(),
-        )));
+        ))))
+    }
 
-        Ok(self.update_selection_set(selection_set, false))
+    pub(crate) fn set_line_number_offset(&mut self, line_number_offset: usize) {
+        self.line_number_offset = line_number_offset;
+    }
+
+    pub(crate) fn select_byte_range(&mut self, range: &Range<usize>) -> anyhow::Result<Dispatches> {
+        let range = self.buffer.borrow().byte_range_to_char_index_range(range)?;
+        Ok(self.select_char_index_range(range))
+    }
+
+    /// The content of the string node under the cursor is tried before the word under the cursor,
+    /// so that paths containing spaces can be opened.
+    fn open_path_or_url_under_cursor(&self, context: &Context) -> anyhow::Result<Dispatches> {
+        let directory = self
+            .path()
+            .and_then(|path| path.parent().ok().flatten())
+            .unwrap_or_else(|| context.current_working_directory().clone());
+        let buffer = self.buffer();
+        let cursor = self.get_cursor_char_index();
+        let string_content = buffer.tree().and_then(|tree| {
+            let byte = buffer.char_to_byte(cursor).ok()?;
+            let mut node = tree.root_node().descendant_for_byte_range(byte, byte)?;
+            while !node.kind().contains("string") {
+                node = node.parent()?;
+            }
+            let content = buffer.content();
+            Some(
+                content
+                    .get(node.byte_range())?
+                    .trim_matches(|c| matches!(c, '"' | '\'' | '`'))
+                    .to_string(),
+            )
+        });
+        let word = {
+            let line = buffer.get_line_by_char_index(cursor)?.to_string();
+            let column = buffer.char_to_position(cursor)?.column;
+            let byte_index = line
+                .char_indices()
+                .nth(column)
+                .map(|(byte_index, _)| byte_index)
+                .unwrap_or(line.len());
+            path_or_url::word_at(&line, byte_index).map(|word| word.to_string())
+        };
+        let path_or_url = string_content
+            .into_iter()
+            .chain(word)
+            .find_map(|text| path_or_url::parse(&text, &directory))
+            .ok_or_else(|| anyhow::anyhow!("No URL or existing path under the cursor"))?;
+        Ok(Dispatches::one(match path_or_url {
+            PathOrUrl::Url(url) => Dispatch::OpenUrl(url),
+            PathOrUrl::Path { path, .. } if path.is_dir() => Dispatch::RevealInExplorer(path),
+            PathOrUrl::Path { path, position } => {
+                let position = position.unwrap_or_default();
+                Dispatch::GotoLocation(Location {
+                    path,
+                    range: position..position,
+                })
+            }
+        }))
+    }
+
+    pub(crate) fn select_char_index_range(&mut self, range: CharIndexRange) -> Dispatches {
+        let selection_set = SelectionSet::new(NonEmpty::singleton(Selection::new(range)));
+        self.update_selection_set(selection_set, false)
     }
 
     #[cfg(test)]
@@ -610,7 +901,7 @@ impl Editor {
         Ok(SelectionSet::new(NonEmpty::new(primary)).set_mode(mode))
     }
 
-    fn cursor_row(&self) -> u16 {
+    pub(crate) fn cursor_row(&self) -> u16 {
         self.get_cursor_char_index()
             .to_position(&self.buffer.borrow())
             .line as u16
@@ -666,10 +957,6 @@ impl Editor {
         }
     }
 
-    fn jump_characters() -> Vec<char> {
-        ('a'..='z').chain('A'..='Z').chain('0'..='9').collect_vec()
-    }
-
     pub(crate) fn get_selection_mode_trait_object(
         &self,
         selection: &Selection,
@@ -692,8 +979,9 @@ impl Editor {
         &mut self,
         selection: &Selection,
         use_current_selection_mode: bool,
+        jump_characters: &[char],
     ) -> anyhow::Result<()> {
-        let chars = Self::jump_characters();
+        let chars = jump_characters.to_vec();
 
         let object = self.get_selection_mode_trait_object(selection, use_current_selection_mode)?;
 
@@ -713,11 +1001,51 @@ impl Editor {
         Ok(())
     }
 
-    pub(crate) fn show_jumps(&mut self, use_current_selection_mode: bool) -> anyhow::Result<()> {
-        self.jump_from_selection(
-            &self.selection_set.primary_selection().clone(),
-            use_current_selection_mode,
-        )
+    pub(crate) fn show_jumps(
+        &mut self,
+        use_current_selection_mode: bool,
+        strategy: JumpLabelStrategy,
+        jump_characters: &[char],
+    ) -> anyhow::Result<()> {
+        let selection = self.selection_set.primary_selection().clone();
+        self.jump_search = None;
+        match strategy {
+            JumpLabelStrategy::FirstChar => {
+                self.jump_from_selection(&selection, use_current_selection_mode, jump_characters)
+            }
+            JumpLabelStrategy::WordStarts => {
+                self.jump_from_selection(&selection, false, jump_characters)?;
+                self.jumps = self
+                    .jumps
+                    .take()
+                    .map(|jumps| Self::relabel_jumps(jumps, jump_characters));
+                Ok(())
+            }
+            JumpLabelStrategy::TwoChars => {
+                self.jump_from_selection(&selection, use_current_selection_mode, jump_characters)?;
+                self.jump_search = Some(String::new());
+                Ok(())
+            }
+        }
+    }
+
+    /// Labels `jumps` with the jump characters in order, repeating them if there are more jumps than characters.
+    fn relabel_jumps(jumps: impl IntoIterator<Item = Jump>, jump_characters: &[char]) -> Vec<Jump> {
+        jumps
+            .into_iter()
+            .zip(jump_characters.iter().copied().cycle())
+            .map(|(jump, character)| Jump { character, ..jump })
+            .collect_vec()
+    }
+
+    /// Labels every jump with `character`, such as the character of the pane of this editor,
+    /// so that the jumps are relabelled with distinct characters once `character` is typed.
+    pub(crate) fn set_jumps_character(&mut self, character: char) {
+        if let Some(jumps) = self.jumps.as_mut() {
+            for jump in jumps {
+                jump.character = character
+            }
+        }
     }
 
     pub(crate) fn delete(&mut self, backward: bool) -> anyhow::Result<Dispatches> {
@@ -821,6 +1149,42 @@ impl Editor {
         }))
     }
 
+    /// The ranges of the git hunks of the buffer with their old content.
+    /// Only available in the Git Hunk selection mode.
+    fn git_hunk_old_contents(&self) -> anyhow::Result<Vec<(CharIndexRange, String)>> {
+        let SelectionMode::GitHunk(diff_mode) = &self.selection_set.mode else {
+            return Err(anyhow::anyhow!(
+                "The git hunk actions are only available in the Git Hunk selection mode"
+            ));
+        };
+        crate::selection_mode::GitHunk::old_contents(diff_mode, &self.buffer())
+    }
+
+    fn revert_git_hunk(&mut self) -> anyhow::Result<Dispatches> {
+        let old_contents = self.git_hunk_old_contents()?;
+        self.replace_current_selection_with(|selection| {
+            old_contents
+                .iter()
+                .find(|(range, _)| *range == selection.extended_range())
+                .map(|(_, old_content)| Rope::from_str(old_content))
+        })
+    }
+
+    /// Selections that are not hunks copy an empty text.
+    fn copy_git_hunk_old_text(&self, use_system_clipboard: bool) -> anyhow::Result<Dispatches> {
+        let old_contents = self.git_hunk_old_contents()?;
+        Ok(Dispatches::one(Dispatch::SetClipboardContent {
+            use_system_clipboard,
+            copied_texts: CopiedTexts::new(self.selection_set.map(|selection| {
+                old_contents
+                    .iter()
+                    .find(|(range, _)| *range == selection.extended_range())
+                    .map(|(_, old_content)| old_content.clone())
+                    .unwrap_or_default()
+            })),
+        }))
+    }
+
     fn replace_current_selection_with<F>(&mut self, f: F) -> anyhow::Result<Dispatches>
     where
         F: Fn(&Selection) -> Option<Rope>,
@@ -854,6 +1218,28 @@ impl Editor {
     }
 
     fn try_replace_current_long_word(&mut self, replacement: String) -> anyhow::Result<Dispatches> {
+        self.replace_before_cursor_while(replacement, |c| {
+            c.is_alphanumeric() || c == '_' || c == '-'
+        })
+    }
+
+    /// Replaces the last segment of the path before the cursor,
+    /// i.e. everything after the last `/` or `\`, such as `foo.r` of `./foo.r`.
+    fn try_replace_current_path_segment(
+        &mut self,
+        replacement: String,
+    ) -> anyhow::Result<Dispatches> {
+        self.replace_before_cursor_while(replacement, |c| {
+            c != '/' && c != '\\' && !c.is_whitespace()
+        })
+    }
+
+    /// Replaces the characters that satisfy `predicate` right before each cursor with `replacement`.
+    fn replace_before_cursor_while(
+        &mut self,
+        replacement: String,
+        predicate: impl Fn(char) -> bool,
+    ) -> anyhow::Result<Dispatches> {
         let replacement: Rope = replacement.into();
         let buffer = self.buffer();
         let edit_transactions = self.selection_set.map(move |selection| {
@@ -866,7 +1252,7 @@ impl Editor {
                 .collect_vec()
                 .iter()
                 .rev()
-                .take_while(|(_, c)| c.is_alphanumeric() || c == &'_' || c == &'-')
+                .take_while(|(_, c)| predicate(*c))
                 .last()
                 .map(|(char_index, _)| CharIndex(*char_index))
                 .unwrap_or(current_char_index);
@@ -953,7 +1339,45 @@ impl Editor {
                 })
                 .collect()
         });
-        self.apply_edit_transaction(edit_transaction)
+        let dispatches = self.apply_edit_transaction(edit_transaction)?;
+        self.record_pasted_ranges();
+        Ok(dispatches)
+    }
+
+    /// Pasting in Insert mode is tracked as part of the insert session instead.
+    fn record_pasted_ranges(&mut self) {
+        if self.mode != Mode::Insert {
+            self.last_inserted_ranges = self
+                .selection_set
+                .map(|selection| selection.extended_range())
+                .into()
+        }
+    }
+
+    /// Selects the text inserted by the last paste or insert session, with one selection for each cursor,
+    /// so that it can be indented, surrounded or replaced right away.
+    fn select_last_inserted(&mut self) -> anyhow::Result<Dispatches> {
+        let len_chars = self.buffer().len_chars();
+        let selections = self
+            .last_inserted_ranges
+            .iter()
+            .filter(|range| range.end.0 <= len_chars)
+            .map(|range| Selection::new(*range))
+            .collect_vec();
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return Err(anyhow::anyhow!("Nothing has been inserted or pasted yet"));
+        };
+        Ok(self.update_selection_set(
+            SelectionSet::new(selections).set_mode(SelectionMode::Custom),
+            true,
+        ))
+    }
+
+    fn insert_register(&mut self, context: &Context, register: char) -> anyhow::Result<Dispatches> {
+        let Some(copied_texts) = context.get_register_content(register)? else {
+            return Err(anyhow::anyhow!("Register {register} is empty"));
+        };
+        self.paste_text(Direction::End, copied_texts)
     }
 
     pub(crate) fn paste(
@@ -1008,7 +1432,7 @@ impl Editor {
                                     range,
                                     new: replacement,
                                 }),
-                                Action::Select(selection.clone().set_range(
+                                Action::Select(selection.set_range(
                                     (range.start..range.start + replacement_text_len).into(),
                                 )),
                             ]
@@ -1019,14 +1443,28 @@ impl Editor {
                 })
                 .collect(),
         );
-        self.apply_edit_transaction(edit_transaction)
-            .map(|d| d.chain(dispatches))
+        let result = self
+            .apply_edit_transaction(edit_transaction)
+            .map(|d| d.chain(dispatches));
+        self.record_pasted_ranges();
+        result
     }
 
     fn apply_edit_transaction(
         &mut self,
         edit_transaction: EditTransaction,
     ) -> anyhow::Result<Dispatches> {
+        if self.mode == Mode::Insert {
+            let edits = edit_transaction.edits();
+            self.last_inserted_ranges = std::mem::take(&mut self.last_inserted_ranges)
+                .into_iter()
+                .filter_map(|range| {
+                    edits
+                        .iter()
+                        .try_fold(range, |range, edit| extend_inserted_range(range, edit))
+                })
+                .collect_vec();
+        }
         let new_selection_set = self.buffer.borrow_mut().apply_edit_transaction(
             &edit_transaction,
             self.selection_set.clone(),
@@ -1037,7 +1475,19 @@ impl Editor {
 
         self.recalculate_scroll_offset();
 
-        Ok(self.get_document_did_change_dispatch())
+        let edit_location = self.buffer().path().and_then(|path| {
+            let position = self
+                .buffer()
+                .char_to_position(self.get_cursor_char_index())
+                .ok()?;
+            Some(Dispatch::PushEditLocation(Location {
+                path,
+                range: position..position,
+            }))
+        });
+        Ok(self
+            .get_document_did_change_dispatch()
+            .append_some(edit_location))
     }
 
     pub(crate) fn get_document_did_change_dispatch(&mut self) -> Dispatches {
@@ -1140,6 +1590,7 @@ impl Editor {
         key_event: KeyEvent,
         jumps: Vec<Jump>,
     ) -> anyhow::Result<Dispatches> {
+        let jump_search = self.jump_search.take();
         match key_event {
             key!("esc") => {
                 self.jumps = None;
@@ -1149,26 +1600,42 @@ impl Editor {
                 let KeyCode::Char(c) = key.code else {
                     return Ok(Default::default());
                 };
-                let matching_jumps = jumps
-                    .iter()
-                    .filter(|jump| c == jump.character)
-                    .collect_vec();
+                let matching_jumps = match jump_search {
+                    Some(mut search) => {
+                        search.push(c.to_ascii_lowercase());
+                        if search.chars().count() < 2 {
+                            self.jump_search = Some(search);
+                            self.jumps = Some(jumps);
+                            return Ok(Default::default());
+                        }
+                        let buffer = self.buffer();
+                        jumps
+                            .into_iter()
+                            .filter(|jump| {
+                                buffer
+                                    .slice(&jump.selection.range())
+                                    .map(|text| {
+                                        text.to_string().to_lowercase().starts_with(&search)
+                                    })
+                                    .unwrap_or(false)
+                            })
+                            .collect_vec()
+                    }
+                    None => jumps
+                        .into_iter()
+                        .filter(|jump| c == jump.character)
+                        .collect_vec(),
+                };
                 match matching_jumps.split_first() {
                     None => Ok(Default::default()),
                     Some((jump, [])) => Ok(self
                         .handle_movement(context, Movement::Jump(jump.selection.extended_range()))?
                         .append(Dispatch::ToEditor(EnterNormalMode))),
                     Some(_) => {
-                        self.jumps = Some(
-                            matching_jumps
-                                .into_iter()
-                                .zip(Self::jump_characters().into_iter().cycle())
-                                .map(|(jump, character)| Jump {
-                                    character,
-                                    ..jump.clone()
-                                })
-                                .collect_vec(),
-                        );
+                        self.jumps = Some(Self::relabel_jumps(
+                            matching_jumps,
+                            &context.jump_characters(),
+                        ));
                         Ok(Default::default())
                     }
                 }
@@ -1213,29 +1680,32 @@ impl Editor {
     }
 
     pub(crate) fn insert(&mut self, s: &str) -> anyhow::Result<Dispatches> {
-        let edit_transaction =
-            EditTransaction::from_action_groups(
-                self.selection_set
-                    .map(|selection| {
-                        let range = selection.extended_range();
-                        ActionGroup::new(
-                            [
-                                Action::Edit(Edit {
-                                    range: {
-                                        let start = selection.to_char_index(&Direction::End);
-                                        (start..start).into()
-                                    },
-                                    new: Rope::from_str(s),
-                                }),
-                                Action::Select(selection.clone().set_range(
-                                    (range.start + s.len()..range.start + s.len()).into(),
-                                )),
-                            ]
-                            .to_vec(),
-                        )
-                    })
-                    .into(),
-            );
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| {
+                    let range = selection.extended_range();
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: {
+                                    let start = selection.to_char_index(&Direction::End);
+                                    (start..start).into()
+                                },
+                                new: Rope::from_str(s),
+                            }),
+                            Action::Select(
+                                selection.clone().set_range(
+                                    (range.start + s.chars().count()
+                                        ..range.start + s.chars().count())
+                                        .into(),
+                                ),
+                            ),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .into(),
+        );
 
         self.apply_edit_transaction(edit_transaction)
     }
@@ -1248,6 +1718,8 @@ impl Editor {
             context: ResponseContext {
                 scope: None,
                 description: None,
+                peek: false,
+                moved_path: None,
             },
         })
     }
@@ -1313,6 +1785,16 @@ impl Editor {
         self.buffer_mut().save_bookmarks(selections.into())
     }
 
+    /// Sets the note of the bookmarks of the current selections,
+    /// bookmarking the selections that are not bookmarked yet.
+    pub(crate) fn set_bookmark_note(&mut self, note: &str) {
+        let selections = self
+            .selection_set
+            .map(|selection| selection.extended_range());
+        self.buffer_mut()
+            .set_bookmark_notes(selections.into(), note)
+    }
+
     pub(crate) fn path(&self) -> Option<CanonicalizedPath> {
         self.editor().buffer().path()
     }
@@ -1333,6 +1815,10 @@ impl Editor {
             },
         )?);
         self.mode = Mode::Insert;
+        self.last_inserted_ranges = self
+            .selection_set
+            .map(|selection| selection.extended_range())
+            .into();
         self.cursor_direction = Direction::Start;
         Ok(Dispatches::one(Dispatch::RequestSignatureHelp))
     }
@@ -1378,6 +1864,9 @@ impl Editor {
     }
 
     pub(crate) fn jumps(&self) -> Vec<&Jump> {
+        if self.jump_search.is_some() {
+            return Vec::new();
+        }
         self.jumps
             .as_ref()
             .map(|jumps| jumps.iter().collect())
@@ -1475,7 +1964,9 @@ impl Editor {
                             (Some(current_node), Some(next_node)) => {
                                 current_node.kind_id() == next_node.kind_id()
                                     && current_node.byte_range().len()
+                                        - trailing_separator_byte_len(&current_node)
                                         == next_node.byte_range().len()
+                                            - trailing_separator_byte_len(&next_node)
                             }
                             (_, _) => true,
                         })
@@ -1518,9 +2009,27 @@ impl Editor {
         let get_edit_transaction = |current_selection: &Selection,
                                     next_selection: &Selection|
          -> anyhow::Result<_> {
-            let current_selection_range = current_selection.extended_range();
+            // Only the items are exchanged, the separators stay where they were,
+            // so that exchanging a list item that includes its trailing separator
+            // with one that does not (e.g. the last match arm of Rust) does not move the separator.
+            let range_without_trailing_separator = |selection: &Selection| -> anyhow::Result<_> {
+                let range = selection.extended_range();
+                let separator_len = if selection_mode.is_node() {
+                    let end_byte = buffer.char_to_byte(range.end)?;
+                    buffer
+                        .get_current_node(selection, false)?
+                        .filter(|node| node.end_byte() == end_byte)
+                        .map(|node| trailing_separator_byte_len(&node))
+                        .unwrap_or_default()
+                } else {
+                    0
+                };
+                Ok(CharIndexRange::from(range.start..range.end - separator_len))
+            };
+            let current_selection_range = range_without_trailing_separator(current_selection)?;
+            let next_selection_range = range_without_trailing_separator(next_selection)?;
             let text_at_current_selection: Rope = buffer.slice(&current_selection_range)?;
-            let text_at_next_selection: Rope = buffer.slice(&next_selection.extended_range())?;
+            let text_at_next_selection: Rope = buffer.slice(&next_selection_range)?;
 
             Ok(EditTransaction::from_action_groups(
                 [
@@ -1534,13 +2043,13 @@ impl Editor {
                     ActionGroup::new(
                         [
                             Action::Edit(Edit {
-                                range: next_selection.extended_range(),
+                                range: next_selection_range,
                                 new: text_at_current_selection.clone(),
                             }),
                             Action::Select(
                                 current_selection.clone().set_range(
-                                    (next_selection.extended_range().start
-                                        ..(next_selection.extended_range().start
+                                    (next_selection_range.start
+                                        ..(next_selection_range.start
                                             + text_at_current_selection.len_chars()))
                                         .into(),
                                 ),
@@ -1577,11 +2086,11 @@ impl Editor {
             movement,
             &self.cursor_direction,
         )?;
+        self.selection_set_history.push(self.selection_set.clone());
         self.recalculate_scroll_offset();
         Ok(())
     }
 
-    #[cfg(test)]
     pub(crate) fn get_selected_texts(&self) -> Vec<String> {
         let buffer = self.buffer.borrow();
         let mut selections = self
@@ -1774,7 +2283,11 @@ impl Editor {
         self.buffer.borrow_mut().update(s)
     }
 
+    /// Moves the selections by `scroll_height` lines, and the view by as many lines as the cursor
+    /// actually moved, so that the cursor stays on the same row of the screen.
     fn scroll(&mut self, direction: Direction, scroll_height: usize) -> anyhow::Result<Dispatches> {
+        let cursor_row = self.cursor_row();
+        let scroll_offset = self.scroll_offset;
         let dispatch = self.update_selection_set(
             self.selection_set
                 .apply(self.selection_set.mode.clone(), |selection| {
@@ -1784,13 +2297,30 @@ impl Editor {
                     } else {
                         position.line.saturating_sub(scroll_height)
                     };
-                    let position = Position { line, ..position };
+                    let column = selection.sticky_column().unwrap_or(position.column);
+                    let position = Position {
+                        line,
+                        column: column.min(
+                            self.buffer()
+                                .line_len_without_newline(line)
+                                .saturating_sub(1),
+                        ),
+                    };
                     let start = position.to_char_index(&self.buffer())?;
-                    Ok(selection.clone().set_range((start..start).into()))
+                    Ok(selection
+                        .clone()
+                        .set_range((start..start).into())
+                        .set_sticky_column(Some(column)))
                 })?,
             false,
         );
-        self.align_cursor_to_center();
+        let moved = self.cursor_row().abs_diff(cursor_row);
+        self.scroll_offset = match direction {
+            Direction::Start => scroll_offset.saturating_sub(moved),
+            Direction::End => scroll_offset.saturating_add(moved),
+        };
+        // The cursor might not have been visible before scrolling
+        self.recalculate_scroll_offset();
 
         Ok(dispatch)
     }
@@ -1848,6 +2378,50 @@ impl Editor {
             .collect_vec()
     }
 
+    fn duplicate(&mut self, direction: Direction) -> anyhow::Result<Dispatches> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.get_selection_set_with_gap()
+                .into_iter()
+                .map(|(selection, gap)| -> anyhow::Result<_> {
+                    let range = selection.extended_range();
+                    let text = self.buffer().slice(&range)?;
+                    let gap = if gap.len_chars() == 0 && !text.to_string().ends_with('\n') {
+                        Rope::from_str(" ")
+                    } else {
+                        gap
+                    };
+                    let (start, new, copy_start) = match direction {
+                        Direction::Start => {
+                            let mut new = text.clone();
+                            new.append(gap);
+                            (range.start, new, range.start)
+                        }
+                        Direction::End => {
+                            let mut new = gap.clone();
+                            new.append(text.clone());
+                            (range.end, new, range.end + gap.len_chars())
+                        }
+                    };
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: (start..start).into(),
+                                new,
+                            }),
+                            Action::Select(
+                                selection
+                                    .clone()
+                                    .set_range((copy_start..copy_start + text.len_chars()).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .try_collect()?,
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
     fn open(&mut self, direction: Direction) -> Result<Dispatches, anyhow::Error> {
         let edit_transaction = EditTransaction::from_action_groups(
             self.get_selection_set_with_gap()
@@ -1918,9 +2492,46 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    fn read_only_dispatches(&self) -> Dispatches {
+        Dispatches::one(Dispatch::ShowGlobalInfo(Info::new(
+            "Read-only".to_string(),
+            "This buffer is read-only, run the `edit` command to make it editable.".to_string(),
+        )))
+    }
+
+    /// Shows the path of the buffer as the title again.
+    pub(crate) fn clear_title(&mut self) {
+        self.title = None
+    }
+
     pub(crate) fn save(&mut self) -> anyhow::Result<Dispatches> {
-        let Some(path) = self.buffer.borrow_mut().save(self.selection_set.clone())? else {
-            return Ok(Default::default());
+        // A formatter error is shown rather than returned, so that it never prevents saving
+        let format_error = self
+            .buffer
+            .borrow_mut()
+            .format(self.selection_set.clone())
+            .err();
+        let result = self.buffer.borrow_mut().save_without_formatting();
+        let path = match result {
+            Ok(Some(path)) => path,
+            Ok(None) => return Ok(Default::default()),
+            Err(error) => {
+                let permission_denied = error
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|error| error.kind() == std::io::ErrorKind::PermissionDenied);
+                return match self.buffer().path() {
+                    Some(path) if permission_denied => {
+                        Ok(Dispatches::one(Dispatch::OpenYesNoPrompt(YesNoPrompt {
+                            title: format!(
+                                "Permission denied, save \"{}\" with elevated privileges?",
+                                path.display_absolute()
+                            ),
+                            yes: Box::new(Dispatch::PrivilegedSave(path)),
+                        })))
+                    }
+                    _ => Err(error),
+                };
+            }
         };
 
         self.clamp()?;
@@ -1934,16 +2545,45 @@ impl Editor {
                 Some(Dispatch::ToEditor(MoveSelection(Movement::Current)))
             } else {
                 None
-            }))
+            })
+            .append_some(format_error.map(Self::format_error_dispatch)))
     }
 
-    /// Clamp everything that might be out of bound after the buffer content is modified elsewhere
-    fn clamp(&mut self) -> anyhow::Result<()> {
-        let len_chars = self.buffer().len_chars();
-        self.set_selection_set(self.selection_set.clamp(CharIndex(len_chars))?);
+    fn format_error_dispatch(error: anyhow::Error) -> Dispatch {
+        Dispatch::ShowGlobalInfo(Info::new("Formatter error".to_string(), error.to_string()))
+    }
 
-        let len_lines = self.buffer().len_lines();
-        self.scroll_offset = self.scroll_offset.clamp(0, len_lines as u16);
+    fn format_selections(&mut self) -> anyhow::Result<Dispatches> {
+        let ranges = self
+            .selection_set
+            .map(|selection| selection.extended_range())
+            .into_iter()
+            .collect_vec();
+        let result = self
+            .buffer
+            .borrow_mut()
+            .format_ranges(ranges, self.selection_set.clone());
+        match result {
+            Ok(Some(selection_set)) => {
+                self.set_selection_set(selection_set);
+                self.clamp()?;
+                Ok(self.get_document_did_change_dispatch())
+            }
+            Ok(None) => Ok(Dispatches::one(Dispatch::ShowGlobalInfo(Info::new(
+                "Formatter error".to_string(),
+                "No formatter is configured for the language of this buffer.".to_string(),
+            )))),
+            Err(error) => Ok(Dispatches::one(Self::format_error_dispatch(error))),
+        }
+    }
+
+    /// Clamp everything that might be out of bound after the buffer content is modified elsewhere
+    fn clamp(&mut self) -> anyhow::Result<()> {
+        let len_chars = self.buffer().len_chars();
+        self.set_selection_set(self.selection_set.clamp(CharIndex(len_chars))?);
+
+        let len_lines = self.buffer().len_lines();
+        self.scroll_offset = self.scroll_offset.clamp(0, len_lines as u16);
 
         Ok(())
     }
@@ -1982,18 +2622,21 @@ impl Editor {
         &mut self,
         transformation: Transformation,
     ) -> anyhow::Result<Dispatches> {
+        if transformation.rearranges_items() && self.selection_set.len() > 1 {
+            return self.rearrange_selections(transformation);
+        }
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
                 .map(|selection| -> anyhow::Result<_> {
+                    let range = if transformation.rearranges_items() {
+                        self.full_lines_range(selection.extended_range())?
+                    } else {
+                        selection.extended_range()
+                    };
                     let new: Rope = transformation
-                        .apply(
-                            self.buffer()
-                                .slice(&selection.extended_range())?
-                                .to_string(),
-                        )
+                        .apply(self.buffer().slice(&range)?.to_string())
                         .into();
                     let new_char_count = new.chars().count();
-                    let range = selection.extended_range();
                     Ok(ActionGroup::new(
                         [
                             Action::Edit(Edit { range, new }),
@@ -2013,6 +2656,392 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// Joins the lines of each selection, or the line of each selection with the next line
+    /// if the selection is within a single line, see `transformation::join_lines`.
+    fn join_lines(&mut self) -> anyhow::Result<Dispatches> {
+        let line_comment_prefix = self
+            .buffer()
+            .language()
+            .and_then(|language| language.line_comment_prefix());
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let range = self.full_lines_range(selection.extended_range())?;
+                    let buffer = self.buffer();
+                    let range = if range.end.0 < buffer.len_chars()
+                        && buffer.char_to_line(range.start)? == buffer.char_to_line(range.end)?
+                    {
+                        let next_line =
+                            self.full_lines_range((range.end + 1..range.end + 1).into())?;
+                        (range.start..next_line.end).into()
+                    } else {
+                        range
+                    };
+                    let new: Rope = crate::transformation::join_lines(
+                        &buffer.slice(&range)?.to_string(),
+                        line_comment_prefix,
+                    )
+                    .into();
+                    let new_char_count = new.len_chars();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .clone()
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .into_iter()
+                .flatten()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Breaks the line at the start of each selection, removing the whitespace before it.
+    /// The new line has the indentation of the current line,
+    /// and continues the line comment that the selection is in, if any.
+    fn split_line(&mut self) -> anyhow::Result<Dispatches> {
+        let line_comment_prefix = self
+            .buffer()
+            .language()
+            .and_then(|language| language.line_comment_prefix());
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let buffer = self.buffer();
+                    let range = selection.extended_range();
+                    let line_start = buffer.line_to_char(buffer.char_to_line(range.start)?)?;
+                    let before = buffer.slice(&(line_start..range.start).into())?.to_string();
+                    let indentation = before
+                        .chars()
+                        .take_while(|c| c.is_whitespace())
+                        .collect::<String>();
+                    let comment_prefix = line_comment_prefix
+                        .filter(|prefix| before.trim_start().starts_with(prefix))
+                        .map(|prefix| format!("{prefix} "))
+                        .unwrap_or_default();
+                    let start =
+                        range.start - (before.chars().count() - before.trim_end().chars().count());
+                    let new = format!("\n{indentation}{comment_prefix}");
+                    let new_char_count = new.chars().count();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: (start..range.start).into(),
+                                new: new.into(),
+                            }),
+                            Action::Select(
+                                selection.clone().set_range(
+                                    (start + new_char_count..start + new_char_count + range.len())
+                                        .into(),
+                                ),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .into_iter()
+                .flatten()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Puts each item of the innermost bracketed list around each selection
+    /// (such as the arguments of a call or the elements of an array) on its own line,
+    /// indented one level deeper than the line of the list,
+    /// or joins them into one line if the list spans multiple lines.
+    /// A trailing comma is kept when the items are split onto separate lines.
+    fn toggle_arguments_layout(&mut self) -> anyhow::Result<Dispatches> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let buffer = self.buffer();
+                    let Some(list) =
+                        std::iter::successors(buffer.get_current_node(selection, false)?, |node| {
+                            node.parent()
+                        })
+                        .find(is_bracketed_list)
+                    else {
+                        return Ok(ActionGroup::new(Default::default()));
+                    };
+                    let children = (0..list.child_count())
+                        .filter_map(|index| list.child(index))
+                        .collect_vec();
+                    let (open, close) = (children[0].kind(), children[children.len() - 1].kind());
+                    let has_trailing_comma = children[children.len() - 2].kind() == ",";
+                    let items = children[1..children.len() - 1]
+                        .iter()
+                        .filter(|child| child.kind() != ",")
+                        .map(|item| -> anyhow::Result<_> {
+                            Ok(buffer
+                                .slice(&buffer.byte_range_to_char_index_range(&item.byte_range())?)?
+                                .to_string())
+                        })
+                        .collect::<anyhow::Result<Vec<_>>>()?;
+                    let range = buffer.byte_range_to_char_index_range(&list.byte_range())?;
+                    let new = if buffer.slice(&range)?.to_string().contains('\n') {
+                        format!("{open}{}{close}", items.join(", "))
+                    } else {
+                        let indentation = buffer
+                            .get_line_by_char_index(range.start)?
+                            .chars()
+                            .take_while(|c| *c != '\n' && c.is_whitespace())
+                            .collect::<String>();
+                        let unit = if indentation.contains('\t') {
+                            "\t"
+                        } else {
+                            "    "
+                        };
+                        let items = items
+                            .iter()
+                            .map(|item| format!("\n{indentation}{unit}{item}"))
+                            .join(",");
+                        let trailing_comma = if has_trailing_comma { "," } else { "" };
+                        format!("{open}{items}{trailing_comma}\n{indentation}{close}")
+                    };
+                    let new_char_count = new.chars().count();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range,
+                                new: new.into(),
+                            }),
+                            Action::Select(
+                                selection
+                                    .clone()
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .into_iter()
+                .flatten()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Extends `range` to cover the full lines that it touches, excluding the last newline character,
+    /// so that the indentation of the first line is included.
+    fn full_lines_range(&self, range: CharIndexRange) -> anyhow::Result<CharIndexRange> {
+        let buffer = self.buffer();
+        let start = buffer.line_to_char(buffer.char_to_line(range.start)?)?;
+        let last_line = buffer.char_to_line(if range.end > range.start {
+            range.end - 1
+        } else {
+            range.end
+        })?;
+        let end = if last_line + 1 < buffer.len_lines() {
+            buffer.line_to_char(last_line + 1)? - 1
+        } else {
+            CharIndex(buffer.len_chars())
+        };
+        Ok((start..end).into())
+    }
+
+    /// Rearranges the texts of the selections in their order of appearance,
+    /// where the selections left over by `Transformation::Unique` are emptied.
+    fn rearrange_selections(
+        &mut self,
+        transformation: Transformation,
+    ) -> anyhow::Result<Dispatches> {
+        let (selections, texts) = self.selections_with_texts()?;
+        self.replace_selections(
+            selections,
+            transformation
+                .apply_to_items(texts)
+                .into_iter()
+                .chain(std::iter::repeat(String::new())),
+        )
+    }
+
+    /// Replaces each selection with the output of `command`, see `shell::pipe`.
+    ///
+    /// If `per_selection` is false and there are multiple selections,
+    /// the command is run once with the selections as its lines of input,
+    /// and each line of its output replaces the corresponding selection.
+    fn pipe_to_shell(&mut self, command: &str, per_selection: bool) -> anyhow::Result<Dispatches> {
+        let (selections, texts) = self.selections_with_texts()?;
+        let outputs = if per_selection || selections.len() == 1 {
+            texts
+                .iter()
+                .map(|text| shell::pipe(command, text))
+                .collect::<anyhow::Result<Vec<_>>>()?
+        } else {
+            let output = shell::pipe(command, &texts.join("\n"))?;
+            let lines = output
+                .split('\n')
+                .map(|line| line.to_string())
+                .collect_vec();
+            if lines.len() != selections.len() {
+                return Err(anyhow::anyhow!(
+                    "Expected {} lines of output (one for each selection), but got {} lines",
+                    selections.len(),
+                    lines.len()
+                ));
+            }
+            lines
+        };
+        self.replace_selections(selections, outputs)
+    }
+
+    /// Inserts the output of `command` after each selection (or at each cursor in Insert mode),
+    /// where the command is run once per cursor with the environment variables
+    /// `KI_SELECTION` (the selected text) and `KI_CURSOR_INDEX` (0-based).
+    fn insert_shell_output(&mut self, command: &str) -> anyhow::Result<Dispatches> {
+        let outputs = self
+            .selection_set
+            .map(Selection::clone)
+            .into_iter()
+            .enumerate()
+            .map(|(index, selection)| {
+                let text = self
+                    .buffer()
+                    .slice(&selection.extended_range())?
+                    .to_string();
+                shell::run(
+                    command,
+                    &[
+                        ("KI_SELECTION", text.as_str()),
+                        ("KI_CURSOR_INDEX", index.to_string().as_str()),
+                    ],
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.insert_texts(outputs)
+    }
+
+    /// Inserts the result of `expression` after each selection (or at each cursor in Insert mode),
+    /// where the variable `i` is the 0-based index of each cursor, see `expression::evaluate`.
+    fn insert_expression_result(&mut self, expression: &str) -> anyhow::Result<Dispatches> {
+        let results = (0..self.selection_set.len())
+            .map(|index| {
+                Ok(expression::format_number(expression::evaluate(
+                    expression,
+                    &[("i", index as f64)],
+                )?))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        self.insert_texts(results)
+    }
+
+    /// The nth text is inserted like pasting at the nth selection.
+    fn insert_texts(&mut self, texts: Vec<String>) -> anyhow::Result<Dispatches> {
+        let Some(texts) = NonEmpty::from_vec(texts) else {
+            return Ok(Default::default());
+        };
+        self.paste_text(Direction::End, CopiedTexts::new(texts))
+    }
+
+    /// Returns the selections in their order of appearance, along with their texts.
+    fn selections_with_texts(&self) -> anyhow::Result<(Vec<Selection>, Vec<String>)> {
+        let selections = self
+            .selection_set
+            .map(Selection::clone)
+            .into_iter()
+            .sorted_by_key(|selection| selection.extended_range().start)
+            .collect_vec();
+        let texts = selections
+            .iter()
+            .map(|selection| {
+                Ok(self
+                    .buffer()
+                    .slice(&selection.extended_range())?
+                    .to_string())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok((selections, texts))
+    }
+
+    fn replace_selections(
+        &mut self,
+        selections: Vec<Selection>,
+        texts: impl IntoIterator<Item = String>,
+    ) -> anyhow::Result<Dispatches> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            selections
+                .into_iter()
+                .zip(texts)
+                .map(|(selection, new)| {
+                    let new: Rope = new.into();
+                    let range = selection.extended_range();
+                    let new_char_count = new.chars().count();
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Increments the number under each selection, see `increment::increment_number`.
+    fn increment_number(&mut self, by: i64, sequential: bool) -> anyhow::Result<Dispatches> {
+        let edit_transaction =
+            {
+                let buffer = self.buffer();
+                EditTransaction::from_action_groups(
+                    self.selection_set
+                        .map(Selection::clone)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, selection)| -> anyhow::Result<_> {
+                            let by = if sequential {
+                                by.checked_mul(index as i64).ok_or_else(|| {
+                                    anyhow::anyhow!(
+                                        "Incrementing by {} for cursor {} overflows",
+                                        by,
+                                        index + 1
+                                    )
+                                })?
+                            } else {
+                                by
+                            };
+                            let start = selection.extended_range().start;
+                            let line_start = buffer.line_to_char(buffer.char_to_line(start)?)?;
+                            let line = buffer.get_line_by_char_index(start)?.to_string();
+                            let byte_index =
+                                buffer.char_to_byte(start)? - buffer.char_to_byte(line_start)?;
+                            let (byte_range, incremented) =
+                                increment::increment_number(&line, byte_index, by)?;
+                            let range: CharIndexRange = (line_start
+                                + line[..byte_range.start].chars().count()
+                                ..line_start + line[..byte_range.end].chars().count())
+                                .into();
+                            let new_char_count = incremented.chars().count();
+                            Ok(ActionGroup::new(
+                                [
+                                    Action::Edit(Edit {
+                                        range,
+                                        new: incremented.into(),
+                                    }),
+                                    Action::Select(selection.clone().set_range(
+                                        (range.start..range.start + new_char_count).into(),
+                                    )),
+                                ]
+                                .to_vec(),
+                            ))
+                        })
+                        .try_collect()?,
+                )
+            };
+        self.apply_edit_transaction(edit_transaction)
+    }
+
     pub(crate) fn display_mode(&self) -> String {
         let selection_mode = self.selection_set.mode.display();
         let filters = self
@@ -2039,6 +3068,47 @@ impl Editor {
         }
     }
 
+    /// The 1-based index of the primary selection among the matches of the Find selection mode
+    /// (`None` if the primary selection is not a match), and the number of matches.
+    ///
+    /// The matches are cached in the buffer until it is modified,
+    /// so they are not recomputed on every movement.
+    pub(crate) fn find_match_index(&self) -> Option<(Option<usize>, usize)> {
+        if !matches!(self.selection_set.mode, SelectionMode::Find { .. }) {
+            return None;
+        }
+        let selection = self.selection_set.primary_selection();
+        let object = self.get_selection_mode_trait_object(selection, true).ok()?;
+        let buffer = self.buffer();
+        let key = format!(
+            "find:{}:{}",
+            self.selection_set.mode.display(),
+            self.selection_set.filters.display().unwrap_or_default()
+        );
+        let matches = buffer
+            .cached_byte_ranges(&key, || {
+                Ok(object
+                    .iter_filtered(selection_mode::SelectionModeParams {
+                        buffer: &buffer,
+                        current_selection: selection,
+                        cursor_direction: &self.cursor_direction,
+                        filters: &self.selection_set.filters,
+                    })?
+                    .sorted_by_key(|byte_range| byte_range.range().start)
+                    .collect())
+            })
+            .ok()?;
+        let byte_range = buffer
+            .char_index_range_to_byte_range(selection.extended_range())
+            .ok()?;
+        let index = matches
+            .binary_search_by_key(&byte_range.start, |byte_range| byte_range.range().start)
+            .ok()
+            .filter(|index| matches[*index].range() == &byte_range)
+            .map(|index| index + 1);
+        Some((index, matches.len()))
+    }
+
     pub(crate) fn visible_line_range(&self) -> Range<usize> {
         let start = self.scroll_offset;
         let end = (start as usize + self.rectangle.height as usize).min(self.buffer().len_lines());
@@ -2064,12 +3134,192 @@ impl Editor {
     pub(crate) fn add_cursor_to_all_selections(&mut self) -> Result<(), anyhow::Error> {
         self.selection_set
             .add_all(&self.buffer.borrow(), &self.cursor_direction)?;
+        self.selection_set_history.push(self.selection_set.clone());
         self.recalculate_scroll_offset();
         Ok(())
     }
 
+    fn filter_selections(&mut self, filter: Filter) -> anyhow::Result<Dispatches> {
+        let selection_set = {
+            let buffer = self.buffer();
+            self.selection_set.retain(|_, selection| {
+                buffer
+                    .slice(&selection.extended_range())
+                    .map(|text| filter.matches(&text.to_string()))
+                    .unwrap_or(false)
+            })
+        }
+        .ok_or_else(|| anyhow::anyhow!("No selections would be left"))?;
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    fn keep_every_nth_selection(&mut self, n: usize) -> anyhow::Result<Dispatches> {
+        if n == 0 {
+            return Err(anyhow::anyhow!("n should be at least 1"));
+        }
+        let selection_set = self
+            .selection_set
+            .retain(|index, _| index % n == 0)
+            .ok_or_else(|| anyhow::anyhow!("No selections would be left"))?;
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    /// Splits each selection on the matches of `regex`, dropping the empty parts,
+    /// such as splitting a block of lines into one selection for each line.
+    fn split_selections(&mut self, regex: &str) -> anyhow::Result<Dispatches> {
+        let regex = regex::Regex::new(regex)?;
+        let selections = {
+            let buffer = self.buffer();
+            self.selection_set
+                .map(|selection| -> anyhow::Result<Vec<Selection>> {
+                    let range = selection.extended_range();
+                    let text = buffer.slice(&range)?.to_string();
+                    let to_char_index = |byte: usize| range.start + text[..byte].chars().count();
+                    let mut start = 0;
+                    let mut parts = Vec::new();
+                    for separator in regex
+                        .find_iter(&text)
+                        .map(|match_| match_.range())
+                        .chain(Some(text.len()..text.len()))
+                    {
+                        if separator.start > start {
+                            parts.push(
+                                selection
+                                    .clone()
+                                    .set_range(
+                                        (to_char_index(start)..to_char_index(separator.start))
+                                            .into(),
+                                    )
+                                    .set_initial_range(None),
+                            );
+                        }
+                        start = separator.end;
+                    }
+                    Ok(parts)
+                })
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect_vec()
+        };
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return Err(anyhow::anyhow!("No selections would be left"));
+        };
+        Ok(self.update_selection_set(
+            SelectionSet::new(selections).set_mode(SelectionMode::Custom),
+            true,
+        ))
+    }
+
+    fn cycle_primary_selection(&mut self, direction: &Direction) {
+        self.selection_set.cycle_primary_selection(direction);
+        self.recalculate_scroll_offset()
+    }
+
+    /// Rotates the contents of the selections, such as swapping two arguments when there are two selections.
+    fn rotate_selections_content(&mut self, direction: &Direction) -> anyhow::Result<Dispatches> {
+        let indices = self.selection_set.indices_by_position();
+        let selections = self
+            .selection_set
+            .map(|selection| selection.clone())
+            .into_iter()
+            .collect_vec();
+        let texts = indices
+            .iter()
+            .map(|index| {
+                Ok(self
+                    .buffer()
+                    .slice(&selections[*index].extended_range())?
+                    .to_string())
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let len = indices.len();
+        let edit_transaction = EditTransaction::from_action_groups(
+            indices
+                .iter()
+                .enumerate()
+                .map(|(position, index)| {
+                    let source = match direction {
+                        Direction::End => (position + len - 1) % len,
+                        Direction::Start => (position + 1) % len,
+                    };
+                    let new: Rope = texts[source].as_str().into();
+                    let selection = &selections[*index];
+                    let range = selection.extended_range();
+                    let new_len = new.len_chars();
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .clone()
+                                    .set_range((range.start..range.start + new_len).into())
+                                    .set_initial_range(None),
+                            ),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .collect(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Adds a cursor to the next occurrence of the primary selection's content,
+    /// wrapping around and skipping occurrences that are already selected.
+    /// If `skip_current` is true, the primary selection is moved instead,
+    /// so that an unwanted occurrence can be skipped.
+    fn select_next_occurrence(&mut self, skip_current: bool) -> anyhow::Result<Dispatches> {
+        let primary = self.selection_set.primary_selection().clone();
+        let range = primary.extended_range();
+        let next = {
+            let buffer = self.buffer();
+            let text = buffer.slice(&range)?.to_string();
+            if text.is_empty() {
+                return Err(anyhow::anyhow!("The primary selection is empty"));
+            }
+            let selected = self
+                .selection_set
+                .map(|selection| selection.extended_range())
+                .into_iter()
+                .collect_vec();
+            let occurrences = buffer
+                .content()
+                .match_indices(&text)
+                .map(|(byte, _)| -> anyhow::Result<CharIndexRange> {
+                    let start = buffer.byte_to_char(byte)?;
+                    Ok((start..start + text.chars().count()).into())
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|occurrence| {
+                    !selected.iter().any(|selected| {
+                        occurrence.start < selected.end && selected.start < occurrence.end
+                    })
+                })
+                .collect_vec();
+            occurrences
+                .iter()
+                .find(|occurrence| occurrence.start >= range.end)
+                .or_else(|| occurrences.first())
+                .copied()
+        }
+        .ok_or_else(|| anyhow::anyhow!("No more occurrences"))?;
+        let selection_set = self
+            .selection_set
+            .clone()
+            .add_primary_selection(
+                primary.set_range(next).set_initial_range(None),
+                skip_current,
+            )
+            .set_mode(SelectionMode::Custom);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
     pub(crate) fn cursor_keep_primary_only(&mut self) {
         self.selection_set.only();
+        self.selection_set_history.push(self.selection_set.clone());
     }
 
     fn enter_single_character_mode(&mut self) {
@@ -2107,7 +3357,59 @@ impl Editor {
     }
 
     fn half_page_height(&self) -> usize {
-        (self.dimension().height / 2) as usize
+        (self.dimension().height / 2).max(1) as usize
+    }
+
+    /// The number of visible lines, minus two lines which stay visible after scrolling a page.
+    fn page_height(&self) -> usize {
+        (self.render_area().height as usize)
+            .saturating_sub(2)
+            .max(1)
+    }
+
+    /// Adds a cursor to each of the `occurrences` of the symbol at `range`
+    /// and enters insert mode at their ends, so that typing edits all the occurrences at once.
+    /// If there are no `occurrences`, they are found by `Buffer::occurrences_in_enclosing_scope`.
+    /// Returns the original name of the symbol.
+    pub(crate) fn start_linked_editing(
+        &mut self,
+        range: Range<Position>,
+        occurrences: Vec<Range<Position>>,
+    ) -> anyhow::Result<(String, Dispatches)> {
+        let (name, selection_set) = {
+            let buffer = self.buffer();
+            let to_char_index_range = |range: Range<Position>| -> anyhow::Result<CharIndexRange> {
+                Ok(
+                    (buffer.position_to_char(range.start)?..buffer.position_to_char(range.end)?)
+                        .into(),
+                )
+            };
+            let range = to_char_index_range(range)?;
+            let occurrences = if occurrences.is_empty() {
+                buffer.occurrences_in_enclosing_scope(range)?
+            } else {
+                occurrences
+                    .into_iter()
+                    .map(to_char_index_range)
+                    .collect::<anyhow::Result<Vec<_>>>()?
+            };
+            // The occurrence at `range` is the primary selection
+            let selections = NonEmpty {
+                head: Selection::new(range),
+                tail: occurrences
+                    .into_iter()
+                    .filter(|occurrence| occurrence != &range)
+                    .map(Selection::new)
+                    .collect(),
+            };
+            (
+                buffer.slice(&range)?.to_string(),
+                SelectionSet::new(selections).set_mode(SelectionMode::Custom),
+            )
+        };
+        let dispatches = self.update_selection_set(selection_set, false);
+        let dispatches = dispatches.chain(self.enter_insert_mode(Direction::End)?);
+        Ok((name, dispatches))
     }
 
     #[cfg(test)]
@@ -2163,17 +3465,25 @@ impl Editor {
         Ok(dispatches)
     }
 
-    pub(crate) fn scroll_page_down(&mut self) -> Result<Dispatches, anyhow::Error> {
-        self.scroll(Direction::End, self.half_page_height())
+    #[cfg(test)]
+    pub(crate) fn current_view_alignment(&self) -> Option<ViewAlignment> {
+        self.current_view_alignment
     }
 
-    pub(crate) fn scroll_page_up(&mut self) -> Result<Dispatches, anyhow::Error> {
-        self.scroll(Direction::Start, self.half_page_height())
+    /// Centers the cursor in the view, as if `ViewAlignment::Center` was chosen by
+    /// `Self::switch_view_alignment`.
+    pub(crate) fn center_cursor(&mut self) {
+        self.align_cursor_to_center();
+        self.current_view_alignment = Some(ViewAlignment::Center);
     }
 
-    #[cfg(test)]
-    pub(crate) fn current_view_alignment(&self) -> Option<ViewAlignment> {
-        self.current_view_alignment
+    /// Centers the cursor if it moved further than half of the view away from `cursor_row`,
+    /// so that the surroundings of the new location are visible.
+    /// Smaller movements only scroll as much as needed to keep the cursor in view.
+    pub(crate) fn center_cursor_after_large_movement(&mut self, cursor_row: u16) {
+        if self.cursor_row().abs_diff(cursor_row) as usize > self.half_page_height() {
+            self.center_cursor()
+        }
     }
 
     pub(crate) fn switch_view_alignment(&mut self) {
@@ -2202,7 +3512,6 @@ impl Editor {
             .chain(self.get_document_did_change_dispatch()))
     }
 
-    #[cfg(test)]
     pub(crate) fn set_scroll_offset(&mut self, scroll_offset: u16) {
         self.scroll_offset = scroll_offset
     }
@@ -2328,6 +3637,18 @@ impl Editor {
         self.scroll_offset
     }
 
+    pub(crate) fn line_number_offset(&self) -> usize {
+        self.line_number_offset
+    }
+
+    pub(crate) fn ghost_text(&self) -> Option<&String> {
+        self.ghost_text.as_ref()
+    }
+
+    pub(crate) fn set_ghost_text(&mut self, ghost_text: Option<String>) {
+        self.ghost_text = ghost_text
+    }
+
     pub(crate) fn set_regex_highlight_rules(
         &mut self,
         regex_highlight_rules: Vec<RegexHighlightRule>,
@@ -2336,6 +3657,9 @@ impl Editor {
     }
 
     fn go_back(&mut self) {
+        // The current selections might not be recorded yet, for example when they are moved by edits,
+        // so they are recorded first, to be restored by `go_forward`
+        self.selection_set_history.push(self.selection_set.clone());
         if let Some(selection_set) = self.selection_set_history.undo() {
             self.set_selection_set(selection_set)
         }
@@ -2397,6 +3721,36 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    fn go_to_matching_pair(&mut self, extend: bool) -> anyhow::Result<Dispatches> {
+        let content = self.buffer().content();
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| {
+                    let cursor = selection.to_char_index(&self.cursor_direction);
+                    let Some((open_index, close_index)) =
+                        crate::surround::get_innermost_bracket_indices(&content, cursor)
+                    else {
+                        return ActionGroup::new(Default::default());
+                    };
+                    let target = if cursor == close_index {
+                        open_index
+                    } else {
+                        close_index
+                    };
+                    let range = if extend {
+                        (cursor.min(target)..cursor.max(target) + 1).into()
+                    } else {
+                        (target..target + 1).into()
+                    };
+                    ActionGroup::new([Action::Select(selection.clone().set_range(range))].to_vec())
+                })
+                .into_iter()
+                .collect_vec(),
+        );
+        let _ = self.set_selection_mode(SelectionMode::Custom);
+        self.apply_edit_transaction(edit_transaction)
+    }
+
     fn delete_surround(&mut self, enclosure: EnclosureKind) -> Result<Dispatches, anyhow::Error> {
         self.change_surround(enclosure, None)
     }
@@ -2463,7 +3817,7 @@ impl Editor {
 
     fn replace_with_pattern(&mut self, context: &Context) -> Result<Dispatches, anyhow::Error> {
         let config = context.local_search_config();
-        let edit_transaction = match config.mode {
+        let edit_transaction = match config.effective_mode() {
             LocalSearchConfigMode::AstGrep => {
                 let edits = if let Some(language) = self.buffer().treesitter_language() {
                     selection_mode::AstGrep::replace(
@@ -2516,10 +3870,12 @@ impl Editor {
                     .map(|selection| -> anyhow::Result<_> {
                         let range = selection.extended_range();
                         let text = self.buffer().slice(&range)?.to_string();
-                        let replacement = {
-                            let regex = regex_config.to_regex(&config.search())?;
-                            regex.replace(&text, &config.replacement()).to_string()
-                        };
+                        let replacement = config.replace_regex(
+                            &regex_config.to_regex(&config.search())?,
+                            regex_config.escaped,
+                            &text,
+                            true,
+                        );
                         let replacement_len = replacement.chars().count();
                         Ok(ActionGroup::new(
                             [
@@ -2595,11 +3951,36 @@ pub(crate) enum DispatchEditor {
     },
     ScrollPageDown,
     ScrollPageUp,
+    ScrollHalfPageDown,
+    ScrollHalfPageUp,
+    /// Scroll by the number of lines of `Context::scroll_lines`.
+    ScrollLinesDown,
+    ScrollLinesUp,
     #[cfg(test)]
     AlignViewTop,
     #[cfg(test)]
     AlignViewBottom,
     Transform(Transformation),
+    /// Join the lines of each selection, or the line of each selection with the next line.
+    JoinLines,
+    /// Break the line at the start of each selection.
+    SplitLine,
+    /// Put each item of the innermost bracketed list around each selection on its own line,
+    /// or join them into one line.
+    ToggleArgumentsLayout,
+    /// If `sequential` is true, the number of the nth selection (0-based) is incremented by n * `by`,
+    /// so that a sequence of numbers can be created using multiple cursors.
+    IncrementNumber {
+        by: i64,
+        sequential: bool,
+    },
+    /// Replace the selections with the output of a shell command.
+    PipeToShell {
+        command: String,
+        per_selection: bool,
+    },
+    InsertShellOutput(String),
+    InsertExpressionResult(String),
     SetSelectionMode(SelectionMode),
     Save,
     FindOneChar,
@@ -2608,6 +3989,12 @@ pub(crate) enum DispatchEditor {
     Copy {
         use_system_clipboard: bool,
     },
+    /// Replaces the git hunks of the selections with their old content.
+    RevertGitHunk,
+    /// Copies the old content of the git hunks of the selections.
+    CopyGitHunkOldText {
+        use_system_clipboard: bool,
+    },
     GoBack,
     GoForward,
     SelectAll,
@@ -2621,6 +4008,8 @@ pub(crate) enum DispatchEditor {
         use_system_clipboard: bool,
     },
     EnterUndoTreeMode,
+    /// URLs are opened in the browser, while paths are opened in Ki.
+    OpenPathOrUrlUnderCursor,
     EnterInsertMode(Direction),
     ReplaceWithCopiedText {
         cut: bool,
@@ -2641,8 +4030,40 @@ pub(crate) enum DispatchEditor {
         enclosure: EnclosureKind,
         kind: SurroundKind,
     },
+    /// Move the cursor to the bracket matching the bracket under it,
+    /// or to the closing bracket of the innermost enclosing pair.
+    /// If `extend` is true, select from the cursor up to that bracket instead.
+    GoToMatchingPair {
+        extend: bool,
+    },
     Open(Direction),
+    /// Insert a copy of each selection before or after it, separated by the gap
+    /// between the selection and its sibling (such as a newline and indentation in Line mode),
+    /// and select the copy.
+    Duplicate(Direction),
+    /// Exchange the content of each selection with the previous or next selection,
+    /// which is a shortcut of exchanging in the Exchange mode, such as moving a line up or down.
+    MoveSelectionContent(Direction),
     ToggleBookmark,
+    /// Select the text inserted by the last paste or insert session.
+    SelectLastInserted,
+    /// Keep or remove the current selections whose content matches the filter.
+    FilterSelections(Filter),
+    /// Keep the 1st, (n+1)th, (2n+1)th, ... of the current selections.
+    KeepEveryNthSelection(usize),
+    /// Split each of the current selections on the matches of the regex.
+    SplitSelections(String),
+    /// Add a cursor to the next occurrence of the content of the primary selection,
+    /// or move the primary selection there if `skip_current` is true.
+    SelectNextOccurrence {
+        skip_current: bool,
+    },
+    /// Make the next (`End`) or previous (`Start`) selection by position primary.
+    CyclePrimarySelection(Direction),
+    /// Move the content of each selection to the next (`End`) or previous (`Start`) selection by position.
+    RotateSelectionsContent(Direction),
+    /// Bookmark the current selections with the given note.
+    SetBookmarkNote(String),
     EnterNormalMode,
     EnterExchangeMode,
     EnterReplaceMode,
@@ -2668,6 +4089,7 @@ pub(crate) enum DispatchEditor {
     ApplySyntaxHighlight,
     ReplaceCurrentSelectionWith(String),
     TryReplaceCurrentLongWord(String),
+    TryReplaceCurrentPathSegment(String),
     SelectLineAt(usize),
     ShowKeymapLegendNormalMode,
     ShowKeymapLegendInsertMode,
@@ -2678,6 +4100,10 @@ pub(crate) enum DispatchEditor {
     SwapCursorWithAnchor,
     MoveCharacterBack,
     MoveCharacterForward,
+    /// Move each cursor to the start of the previous word, or to the end of the next word.
+    MoveWord(Direction),
+    /// Insert the content of the register at each cursor, see `Context::get_register_content`.
+    InsertRegister(char),
     ShowKeymapLegendHelp,
     DeleteSurround(EnclosureKind),
     ChangeSurround {
@@ -2688,6 +4114,60 @@ pub(crate) enum DispatchEditor {
     ApplyPositionalEdits(Vec<CompletionItemEdit>),
     ReplaceWithPreviousCopiedText,
     ReplaceWithNextCopiedText,
+    /// See `Buffer::read_only`.
+    SetReadOnly(bool),
+    /// Format only the selections with the formatters of the language.
+    FormatSelections,
+}
+
+impl DispatchEditor {
+    /// Returns true if this dispatch modifies the buffer, or enters a mode for modifying the buffer.
+    pub(crate) fn is_edit(&self) -> bool {
+        matches!(
+            self,
+            Surround(..)
+                | Transform(_)
+                | JoinLines
+                | SplitLine
+                | ToggleArgumentsLayout
+                | IncrementNumber { .. }
+                | PipeToShell { .. }
+                | InsertShellOutput(_)
+                | InsertExpressionResult(_)
+                | Change
+                | ChangeCut { .. }
+                | EnterInsertMode(_)
+                | ReplaceWithCopiedText { .. }
+                | ReplaceWithPattern
+                | Backspace
+                | Delete { .. }
+                | Insert(_)
+                | Open(_)
+                | InsertRegister(_)
+                | Duplicate(_)
+                | MoveSelectionContent(_)
+                | RotateSelectionsContent(_)
+                | EnterExchangeMode
+                | EnterReplaceMode
+                | ReplacePattern { .. }
+                | Undo
+                | Redo
+                | KillLine(_)
+                | DeleteWordBackward { .. }
+                | ReplaceCurrentSelectionWith(_)
+                | TryReplaceCurrentLongWord(_)
+                | TryReplaceCurrentPathSegment(_)
+                | Paste { .. }
+                | DeleteSurround(_)
+                | ChangeSurround { .. }
+                | Replace(_)
+                | ApplyPositionalEdits(_)
+                | ReplaceWithPreviousCopiedText
+                | ReplaceWithNextCopiedText
+                | FormatSelections
+                | RevertGitHunk
+        )
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -6,7 +6,7 @@ use event::KeyEvent;
 use itertools::Itertools;
 
 use crate::{
-    app::{Dispatch, Dispatches, FilePickerKind, MakeFilterMechanism, Scope},
+    app::{Dispatch, Dispatches, FilePickerKind, MakeFilterMechanism, PathFormat, Scope},
     components::{editor::Movement, keymap_legend::KeymapLegendSection},
     context::{Context, LocalSearchConfigMode, Search},
     git::DiffMode,
@@ -17,6 +17,8 @@ use crate::{
     transformation::Transformation,
 };
 
+use shared::canonicalized_path::CanonicalizedPath;
+
 use super::{
     editor::{Direction, DispatchEditor, Editor, HandleEventResult, SurroundKind},
     keymap_legend::{Keymap, KeymapLegendBody, KeymapLegendConfig, Keymaps},
@@ -63,6 +65,11 @@ impl Editor {
                         use_current_selection_mode: true,
                     }),
                 ),
+                Keymap::new(
+                    "S",
+                    "Spring across panes".to_string(),
+                    Dispatch::ShowJumpsAcrossPanes,
+                ),
                 Keymap::new(
                     "-",
                     "Parent Line".to_string(),
@@ -88,14 +95,34 @@ impl Editor {
                 ),
                 Keymap::new(
                     "ctrl+d",
+                    "Scroll half page down".to_string(),
+                    Dispatch::ToEditor(ScrollHalfPageDown),
+                ),
+                Keymap::new(
+                    "ctrl+u",
+                    "Scroll half page up".to_string(),
+                    Dispatch::ToEditor(ScrollHalfPageUp),
+                ),
+                Keymap::new(
+                    "ctrl+f",
                     "Scroll page down".to_string(),
                     Dispatch::ToEditor(ScrollPageDown),
                 ),
                 Keymap::new(
-                    "ctrl+u",
+                    "ctrl+b",
                     "Scroll page up".to_string(),
                     Dispatch::ToEditor(ScrollPageUp),
                 ),
+                Keymap::new(
+                    "alt+d",
+                    "Scroll lines down".to_string(),
+                    Dispatch::ToEditor(ScrollLinesDown),
+                ),
+                Keymap::new(
+                    "alt+u",
+                    "Scroll lines up".to_string(),
+                    Dispatch::ToEditor(ScrollLinesUp),
+                ),
                 Keymap::new("[", "Go back".to_string(), Dispatch::ToEditor(GoBack)),
                 Keymap::new("]", "Go forward".to_string(), Dispatch::ToEditor(GoForward)),
                 Keymap::new(
@@ -213,6 +240,31 @@ impl Editor {
                     "Mark (Toggle)".to_string(),
                     Dispatch::ToEditor(ToggleBookmark),
                 ),
+                Keymap::new(
+                    "M",
+                    "Mark with note".to_string(),
+                    Dispatch::OpenBookmarkNotePrompt,
+                ),
+                Keymap::new(
+                    ")",
+                    "Next primary selection".to_string(),
+                    Dispatch::ToEditor(CyclePrimarySelection(Direction::End)),
+                ),
+                Keymap::new(
+                    "(",
+                    "Previous primary selection".to_string(),
+                    Dispatch::ToEditor(CyclePrimarySelection(Direction::Start)),
+                ),
+                Keymap::new(
+                    ">",
+                    "Rotate selections content forward".to_string(),
+                    Dispatch::ToEditor(RotateSelectionsContent(Direction::End)),
+                ),
+                Keymap::new(
+                    "<",
+                    "Rotate selections content backward".to_string(),
+                    Dispatch::ToEditor(RotateSelectionsContent(Direction::Start)),
+                ),
                 Keymap::new(
                     "o",
                     "Open (after selection)".to_string(),
@@ -223,6 +275,26 @@ impl Editor {
                     "Open (before selection)".to_string(),
                     Dispatch::ToEditor(Open(Direction::Start)),
                 ),
+                Keymap::new(
+                    "alt+J",
+                    "Duplicate after selection".to_string(),
+                    Dispatch::ToEditor(Duplicate(Direction::End)),
+                ),
+                Keymap::new(
+                    "alt+K",
+                    "Duplicate before selection".to_string(),
+                    Dispatch::ToEditor(Duplicate(Direction::Start)),
+                ),
+                Keymap::new(
+                    "alt+j",
+                    "Exchange with next selection".to_string(),
+                    Dispatch::ToEditor(MoveSelectionContent(Direction::End)),
+                ),
+                Keymap::new(
+                    "alt+k",
+                    "Exchange with previous selection".to_string(),
+                    Dispatch::ToEditor(MoveSelectionContent(Direction::Start)),
+                ),
                 Keymap::new(
                     "ctrl+r",
                     "Replace with pattern".to_string(),
@@ -384,6 +456,21 @@ impl Editor {
                                 "Delete word (short) backward".to_string(),
                                 Dispatch::ToEditor(DeleteWordBackward { short: true }),
                             ),
+                            Keymap::new(
+                                "alt+b",
+                                "Move back a word".to_string(),
+                                Dispatch::ToEditor(MoveWord(Direction::Start)),
+                            ),
+                            Keymap::new(
+                                "alt+f",
+                                "Move forward a word".to_string(),
+                                Dispatch::ToEditor(MoveWord(Direction::End)),
+                            ),
+                            Keymap::new(
+                                "ctrl+r",
+                                "Insert register".to_string(),
+                                Dispatch::ShowKeymapLegend(self.register_keymap_legend_config()),
+                            ),
                         ]),
                     },
                     KeymapLegendSection {
@@ -429,6 +516,14 @@ impl Editor {
                                 "Move to line end".to_string(),
                                 Dispatch::ToEditor(MoveToLineEnd),
                             ),
+                            // Not `ctrl+v` as in Vim, because `ctrl+v` is the universal paste
+                            Keymap::new(
+                                "ctrl+x",
+                                "Insert special".to_string(),
+                                Dispatch::ShowKeymapLegend(
+                                    self.insert_special_keymap_legend_config(),
+                                ),
+                            ),
                         ]),
                     },
                 ]
@@ -439,6 +534,59 @@ impl Editor {
         }
     }
 
+    pub(crate) fn insert_special_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Insert special".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(&[
+                    Keymap::new(
+                        "u",
+                        "Unicode code point".to_string(),
+                        Dispatch::OpenUnicodeCodePointPrompt,
+                    ),
+                    Keymap::new(
+                        "c",
+                        "Character picker".to_string(),
+                        Dispatch::OpenCharacterPicker,
+                    ),
+                ]),
+            },
+        }
+    }
+
+    /// The registers `0` to `9` are the copied texts from the latest to the 10th latest,
+    /// and `+` is the system clipboard.
+    pub(crate) fn register_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Insert register".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, key)| {
+                            Keymap::new(
+                                key,
+                                match index {
+                                    0 => "Latest copied text".to_string(),
+                                    _ => format!("Copied text {index} before the latest"),
+                                },
+                                Dispatch::ToEditor(InsertRegister(
+                                    key.chars().next().unwrap_or_default(),
+                                )),
+                            )
+                        })
+                        .chain(Some(Keymap::new(
+                            "+",
+                            "System clipboard".to_string(),
+                            Dispatch::ToEditor(InsertRegister('+')),
+                        )))
+                        .collect_vec(),
+                ),
+            },
+        }
+    }
+
     pub(crate) fn handle_insert_mode(&mut self, event: KeyEvent) -> anyhow::Result<Dispatches> {
         if let Some(dispatches) = self
             .insert_mode_keymap_legend_config()
@@ -555,6 +703,11 @@ impl Editor {
                         "Normal mode".to_string(),
                         Dispatch::ToEditor(ShowKeymapLegendNormalMode),
                     ),
+                    Keymap::new(
+                        "c",
+                        "Cheat sheet".to_string(),
+                        Dispatch::ShowKeymapCheatSheet,
+                    ),
                 ]),
             },
         }
@@ -593,6 +746,7 @@ impl Editor {
         context: &Context,
         event: KeyEvent,
     ) -> anyhow::Result<Dispatches> {
+        let event = context.keyboard_layout().to_qwerty(event);
         if let Some(keymap) = self.normal_mode_keymaps(context).get(&event) {
             return Ok([keymap.dispatch()].to_vec().into());
         }
@@ -611,14 +765,7 @@ impl Editor {
                         keymaps: Keymaps::new(
                             &[
                                 ("a", "aLtErNaTiNg CaSe", Case::Toggle),
-                                ("c", "camelCase", Case::Camel),
                                 ("l", "lowercase", Case::Lower),
-                                ("k", "kebab-case", Case::Kebab),
-                                ("K", "Upper-Kebab", Case::UpperKebab),
-                                ("p", "PascalCase", Case::Pascal),
-                                ("s", "snake_case", Case::Snake),
-                                ("S", "UPPER_SNAKE_CASE", Case::UpperSnake),
-                                ("t", "Title Case", Case::Title),
                                 ("u", "UPPERCASE", Case::Upper),
                             ]
                             .into_iter()
@@ -629,6 +776,13 @@ impl Editor {
                                     Dispatch::ToEditor(Transform(Transformation::Case(case))),
                                 )
                             })
+                            .chain(Some(Keymap::new(
+                                "c",
+                                "Naming convention".to_string(),
+                                Dispatch::ShowKeymapLegend(
+                                    self.naming_convention_keymap_legend_config(),
+                                ),
+                            )))
                             .collect_vec(),
                         ),
                     },
@@ -645,6 +799,115 @@ impl Editor {
                                 "Wrap".to_string(),
                                 Dispatch::ToEditor(Transform(Transformation::Wrap)),
                             ),
+                            Keymap::new(
+                                "J",
+                                "Join lines".to_string(),
+                                Dispatch::ToEditor(JoinLines),
+                            ),
+                            Keymap::new(
+                                "s",
+                                "Split line".to_string(),
+                                Dispatch::ToEditor(SplitLine),
+                            ),
+                            Keymap::new(
+                                "A",
+                                "Split or join arguments".to_string(),
+                                Dispatch::ToEditor(ToggleArgumentsLayout),
+                            ),
+                            Keymap::new(
+                                "|",
+                                "Pipe each selection to shell".to_string(),
+                                Dispatch::OpenPipeToShellPrompt {
+                                    per_selection: true,
+                                },
+                            ),
+                            Keymap::new(
+                                "!",
+                                "Pipe selections to shell (one line each)".to_string(),
+                                Dispatch::OpenPipeToShellPrompt {
+                                    per_selection: false,
+                                },
+                            ),
+                        ]),
+                    },
+                    KeymapLegendSection {
+                        title: "Lines / Selections".to_string(),
+                        keymaps: Keymaps::new(&[
+                            Keymap::new(
+                                "o",
+                                "Sort".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Sort {
+                                    numeric: false,
+                                    key: None,
+                                })),
+                            ),
+                            Keymap::new(
+                                "O",
+                                "Sort by key".to_string(),
+                                Dispatch::OpenSortByKeyPrompt { numeric: false },
+                            ),
+                            Keymap::new(
+                                "n",
+                                "Sort numerically".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Sort {
+                                    numeric: true,
+                                    key: None,
+                                })),
+                            ),
+                            Keymap::new(
+                                "N",
+                                "Sort numerically by key".to_string(),
+                                Dispatch::OpenSortByKeyPrompt { numeric: true },
+                            ),
+                            Keymap::new(
+                                "r",
+                                "Reverse".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Reverse)),
+                            ),
+                            Keymap::new(
+                                "d",
+                                "Unique (remove duplicates)".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Unique)),
+                            ),
+                        ]),
+                    },
+                    KeymapLegendSection {
+                        title: "Number".to_string(),
+                        keymaps: Keymaps::new(&[
+                            Keymap::new(
+                                "+",
+                                "Increment".to_string(),
+                                Dispatch::ToEditor(IncrementNumber {
+                                    by: 1,
+                                    sequential: false,
+                                }),
+                            ),
+                            Keymap::new(
+                                "-",
+                                "Decrement".to_string(),
+                                Dispatch::ToEditor(IncrementNumber {
+                                    by: -1,
+                                    sequential: false,
+                                }),
+                            ),
+                            Keymap::new(
+                                "=",
+                                "Increment by count".to_string(),
+                                Dispatch::OpenIncrementNumberPrompt { sequential: false },
+                            ),
+                            Keymap::new(
+                                "#",
+                                "Sequence (increment by cursor index)".to_string(),
+                                Dispatch::ToEditor(IncrementNumber {
+                                    by: 1,
+                                    sequential: true,
+                                }),
+                            ),
+                            Keymap::new(
+                                "*",
+                                "Sequence with step".to_string(),
+                                Dispatch::OpenIncrementNumberPrompt { sequential: true },
+                            ),
                         ]),
                     },
                 ]
@@ -653,6 +916,124 @@ impl Editor {
         }
     }
 
+    fn window_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Window".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(&[
+                    Keymap::new(
+                        "+",
+                        "Grow focused window".to_string(),
+                        Dispatch::ResizeWindow { grow: true },
+                    ),
+                    Keymap::new(
+                        "-",
+                        "Shrink focused window".to_string(),
+                        Dispatch::ResizeWindow { grow: false },
+                    ),
+                    Keymap::new(
+                        "=",
+                        "Equalize windows".to_string(),
+                        Dispatch::EqualizeWindows,
+                    ),
+                    Keymap::new(
+                        "c",
+                        "Toggle sticky context".to_string(),
+                        Dispatch::ToggleStickyContext,
+                    ),
+                    Keymap::new(
+                        "b",
+                        "Toggle breadcrumbs".to_string(),
+                        Dispatch::ToggleBreadcrumbs,
+                    ),
+                    Keymap::new(
+                        "B",
+                        "Jump to breadcrumb".to_string(),
+                        Dispatch::OpenBreadcrumbsPrompt,
+                    ),
+                    Keymap::new(
+                        "d",
+                        "Toggle inline diagnostics".to_string(),
+                        Dispatch::ToggleInlineDiagnostics,
+                    ),
+                    Keymap::new(
+                        "a",
+                        "Toggle auto-center".to_string(),
+                        Dispatch::ToggleAutoCenter,
+                    ),
+                    Keymap::new(
+                        "f",
+                        "Toggle explorer auto-follow".to_string(),
+                        Dispatch::ToggleExplorerAutoFollow,
+                    ),
+                    Keymap::new(
+                        "l",
+                        "Switch layout (beside/below)".to_string(),
+                        Dispatch::SwitchLayoutKind,
+                    ),
+                    Keymap::new(
+                        "s",
+                        "Save layout preset".to_string(),
+                        Dispatch::OpenSaveLayoutPresetPrompt,
+                    ),
+                    Keymap::new(
+                        "r",
+                        "Restore layout preset".to_string(),
+                        Dispatch::OpenRestoreLayoutPresetPrompt,
+                    ),
+                ]),
+            },
+        }
+    }
+
+    fn naming_convention_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Naming convention".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[
+                        ("c", "camelCase", Case::Camel),
+                        ("k", "kebab-case", Case::Kebab),
+                        ("K", "Upper-Kebab", Case::UpperKebab),
+                        ("p", "PascalCase", Case::Pascal),
+                        ("s", "snake_case", Case::Snake),
+                        ("S", "SCREAMING_SNAKE_CASE", Case::UpperSnake),
+                        ("t", "Title Case", Case::Title),
+                    ]
+                    .into_iter()
+                    .map(|(key, description, case)| {
+                        Keymap::new(
+                            key,
+                            description.to_string(),
+                            Dispatch::ToEditor(Transform(Transformation::Case(case))),
+                        )
+                    })
+                    .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn test_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Test".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(&[
+                    Keymap::new(
+                        "t",
+                        "Run test under cursor".to_string(),
+                        Dispatch::RunTests { all: false },
+                    ),
+                    Keymap::new(
+                        "f",
+                        "Run tests of file".to_string(),
+                        Dispatch::RunTests { all: true },
+                    ),
+                ]),
+            },
+        }
+    }
+
     fn space_keymap_legend_config(&self, context: &Context) -> KeymapLegendConfig {
         KeymapLegendConfig {
             title: "Space".to_string(),
@@ -661,6 +1042,37 @@ impl Editor {
                 sections: context
                     .contextual_keymaps()
                     .into_iter()
+                    .chain(match self.selection_set.mode {
+                        GitHunk(_) => Some(KeymapLegendSection {
+                            title: "Git Hunk".to_string(),
+                            keymaps: Keymaps::new(
+                                &[
+                                    Keymap::new(
+                                        "U",
+                                        "Revert hunk".to_string(),
+                                        Dispatch::ToEditor(DispatchEditor::RevertGitHunk),
+                                    ),
+                                    Keymap::new(
+                                        "Y",
+                                        "Copy old text of hunk".to_string(),
+                                        Dispatch::ToEditor(DispatchEditor::CopyGitHunkOldText {
+                                            use_system_clipboard: false,
+                                        }),
+                                    ),
+                                ]
+                                .into_iter()
+                                .chain(self.path().map(|path| {
+                                    Keymap::new(
+                                        "A",
+                                        "Intent to add file".to_string(),
+                                        Dispatch::GitIntentToAdd(path),
+                                    )
+                                }))
+                                .collect_vec(),
+                            ),
+                        }),
+                        _ => None,
+                    })
                     .chain([KeymapLegendSection {
                         title: "Pick".to_string(),
                         keymaps: Keymaps::new(
@@ -696,11 +1108,40 @@ impl Editor {
                                     )
                                 }),
                             )
+                            .chain(Some(Keymap::new(
+                                "v",
+                                "Git branches".to_string(),
+                                Dispatch::OpenBranchPicker,
+                            )))
+                            .chain([
+                                Keymap::new(
+                                    "l",
+                                    "Git log".to_string(),
+                                    Dispatch::OpenCommitLog {
+                                        current_file: false,
+                                    },
+                                ),
+                                Keymap::new(
+                                    "L",
+                                    "Git log (current file)".to_string(),
+                                    Dispatch::OpenCommitLog { current_file: true },
+                                ),
+                            ])
                             .chain(Some(Keymap::new(
                                 "s",
                                 "Symbols".to_string(),
                                 Dispatch::RequestDocumentSymbols,
                             )))
+                            .chain(Some(Keymap::new(
+                                "D",
+                                "Diagnostics".to_string(),
+                                Dispatch::OpenDiagnosticsPicker,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "M",
+                                "Bookmarks".to_string(),
+                                Dispatch::OpenBookmarksPicker,
+                            )))
                             .chain(Some(Keymap::new(
                                 "t",
                                 "Theme".to_string(),
@@ -722,6 +1163,45 @@ impl Editor {
                                 "Keep only primary cursor".to_string(),
                                 Dispatch::ToEditor(DispatchEditor::CursorKeepPrimaryOnly),
                             ),
+                            Keymap::new(
+                                "i",
+                                "Select last inserted text".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SelectLastInserted),
+                            ),
+                            Keymap::new(
+                                "K",
+                                "Keep selections matching regex".to_string(),
+                                Dispatch::OpenFilterSelectionsPrompt(FilterKind::Keep),
+                            ),
+                            Keymap::new(
+                                "R",
+                                "Remove selections matching regex".to_string(),
+                                Dispatch::OpenFilterSelectionsPrompt(FilterKind::Remove),
+                            ),
+                            Keymap::new(
+                                "n",
+                                "Keep every nth selection".to_string(),
+                                Dispatch::OpenKeepEveryNthSelectionPrompt,
+                            ),
+                            Keymap::new(
+                                "S",
+                                "Split selections by regex".to_string(),
+                                Dispatch::OpenSplitSelectionsPrompt,
+                            ),
+                            Keymap::new(
+                                "d",
+                                "Add cursor to next occurrence".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SelectNextOccurrence {
+                                    skip_current: false,
+                                }),
+                            ),
+                            Keymap::new(
+                                "k",
+                                "Skip to next occurrence".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SelectNextOccurrence {
+                                    skip_current: true,
+                                }),
+                            ),
                         ]),
                     }))
                     .chain(Some(KeymapLegendSection {
@@ -730,16 +1210,62 @@ impl Editor {
                             &Some(Keymap::new(
                                 "e",
                                 "Reveal current file in Explorer".to_string(),
-                                Dispatch::RevealInExplorer(self.path().unwrap_or_else(|| {
-                                    context.current_working_directory().clone()
-                                })),
+                                Dispatch::RevealCurrentFileInExplorer,
                             ))
                             .into_iter()
+                            .chain(self.path().map(|path| {
+                                Keymap::new(
+                                    "y",
+                                    "Copy path".to_string(),
+                                    Dispatch::ShowKeymapLegend(copy_path_keymap_legend_config(
+                                        &path,
+                                        self.get_cursor_position()
+                                            .ok()
+                                            .map(|position| position.line),
+                                        self.primary_selection_lines().ok(),
+                                    )),
+                                )
+                            }))
                             .chain(Some(Keymap::new(
                                 "z",
                                 "Undo Tree".to_string(),
                                 Dispatch::ToEditor(DispatchEditor::EnterUndoTreeMode),
                             )))
+                            .chain(Some(Keymap::new(
+                                "u",
+                                "Open URL or path under cursor".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::OpenPathOrUrlUnderCursor),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "O",
+                                "Outline".to_string(),
+                                Dispatch::OpenOutline,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "T",
+                                "Test".to_string(),
+                                Dispatch::ShowKeymapLegend(self.test_keymap_legend_config()),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "B",
+                                "Build".to_string(),
+                                Dispatch::OpenBuildPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "w",
+                                "Window".to_string(),
+                                Dispatch::ShowKeymapLegend(self.window_keymap_legend_config()),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "!",
+                                "Insert output of shell command".to_string(),
+                                Dispatch::OpenInsertShellOutputPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "=",
+                                "Insert result of expression".to_string(),
+                                Dispatch::OpenInsertExpressionResultPrompt,
+                            )))
                             .chain(
                                 self.buffer()
                                     .get_current_node(self.selection_set.primary_selection(), false)
@@ -756,6 +1282,22 @@ impl Editor {
                                         )
                                     }),
                             )
+                            .chain({
+                                let cursor_char_index = self.get_cursor_char_index();
+                                self.buffer()
+                                    .color_literals()
+                                    .into_iter()
+                                    .find(|color_literal| {
+                                        color_literal.range.contains(&cursor_char_index)
+                                    })
+                                    .map(|color_literal| {
+                                        Keymap::new(
+                                            "C",
+                                            "Edit color".to_string(),
+                                            Dispatch::OpenEditColorPrompt(color_literal),
+                                        )
+                                    })
+                            })
                             .collect_vec(),
                         ),
                     }))
@@ -783,39 +1325,69 @@ impl Editor {
                         Keymap::new(
                             "s",
                             "Search".to_string(),
-                            Dispatch::OpenSearchPrompt { scope },
+                            Dispatch::OpenSearchPrompt {
+                                scope,
+                                current_line: None,
+                            },
                         ),
                     ]
                     .into_iter()
                     .chain(
                         self.buffer()
                             .slice(&self.selection_set.primary_selection().extended_range())
-                            .map(|search| {
-                                Keymap::new(
-                                    "c",
-                                    "Current selection".to_string(),
-                                    Dispatch::UpdateLocalSearchConfig {
-                                        scope,
-                                        update: crate::app::LocalSearchConfigUpdate::Search(
-                                            search.to_string(),
-                                        ),
-                                        show_config_after_enter: false,
-                                    },
-                                )
+                            .into_iter()
+                            .flat_map(|search| {
+                                let search = search.to_string();
+                                [
+                                    Some(Keymap::new(
+                                        "c",
+                                        "Current selection".to_string(),
+                                        Dispatch::UpdateLocalSearchConfig {
+                                            scope,
+                                            update: crate::app::LocalSearchConfigUpdate::Search(
+                                                search.clone(),
+                                            ),
+                                            show_config_after_enter: false,
+                                        },
+                                    )),
+                                    // Only a single-line selection fits in the search prompt
+                                    (!search.contains('\n')).then(|| {
+                                        Keymap::new(
+                                            "C",
+                                            "Search (edit current selection)".to_string(),
+                                            Dispatch::OpenSearchPrompt {
+                                                scope,
+                                                current_line: Some(search.clone()),
+                                            },
+                                        )
+                                    }),
+                                ]
+                                .into_iter()
+                                .flatten()
                             }),
                     )
-                    .chain(config.last_search().map(|search| {
-                        Keymap::new(
-                            "p",
-                            "Search (using previous search)".to_string(),
-                            Dispatch::UpdateLocalSearchConfig {
-                                scope,
-                                update: crate::app::LocalSearchConfigUpdate::Search(
-                                    search.search.to_string(),
-                                ),
-                                show_config_after_enter: false,
-                            },
-                        )
+                    .chain(config.last_search().into_iter().flat_map(|search| {
+                        [
+                            Keymap::new(
+                                "p",
+                                "Search (using previous search)".to_string(),
+                                Dispatch::UpdateLocalSearchConfig {
+                                    scope,
+                                    update: crate::app::LocalSearchConfigUpdate::Search(
+                                        search.search.to_string(),
+                                    ),
+                                    show_config_after_enter: false,
+                                },
+                            ),
+                            Keymap::new(
+                                "P",
+                                "Search (edit previous search)".to_string(),
+                                Dispatch::OpenSearchPrompt {
+                                    scope,
+                                    current_line: Some(search.search.to_string()),
+                                },
+                            ),
+                        ]
                     }))
                     .collect_vec(),
                 ),
@@ -865,6 +1437,19 @@ impl Editor {
                         )
                     }),
                 )
+                .chain(Some(Keymap::new(
+                    "b",
+                    "Git hunk (pick baseline)".to_string(),
+                    Dispatch::OpenGitHunkBaselinePrompt(scope),
+                )))
+                .chain(match scope {
+                    Scope::Global => Some(Keymap::new(
+                        "Q",
+                        "Quickfix lists".to_string(),
+                        Dispatch::ShowKeymapLegend(self.quickfix_lists_keymap_legend_config()),
+                    )),
+                    Scope::Local => None,
+                })
                 .collect_vec(),
             ),
         };
@@ -960,6 +1545,51 @@ impl Editor {
         }
     }
 
+    pub(crate) fn quickfix_lists_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Quickfix lists".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(&[
+                    Keymap::new(
+                        "[",
+                        "Older list".to_string(),
+                        Dispatch::SwitchQuickfixList(Movement::Previous),
+                    ),
+                    Keymap::new(
+                        "]",
+                        "Newer list".to_string(),
+                        Dispatch::SwitchQuickfixList(Movement::Next),
+                    ),
+                    Keymap::new(
+                        "l",
+                        "Pick list".to_string(),
+                        Dispatch::OpenQuickfixListsPrompt,
+                    ),
+                    Keymap::new(
+                        "f",
+                        "Filter".to_string(),
+                        Dispatch::OpenQuickfixListFilterPrompt,
+                    ),
+                    Keymap::new(
+                        "F",
+                        "Clear filter".to_string(),
+                        Dispatch::SetQuickfixListFilter(None),
+                    ),
+                    Keymap::new(
+                        "d",
+                        "Remove current item".to_string(),
+                        Dispatch::RemoveCurrentQuickfixListItem,
+                    ),
+                    Keymap::new(
+                        "p",
+                        "Toggle preview".to_string(),
+                        Dispatch::ToggleQuickfixListPreview,
+                    ),
+                ]),
+            },
+        }
+    }
+
     pub(crate) fn between_mode_keymap_legend_config(&self) -> KeymapLegendConfig {
         KeymapLegendConfig {
             title: "Between".to_string(),
@@ -985,6 +1615,21 @@ impl Editor {
                             ),
                         ]),
                     },
+                    KeymapLegendSection {
+                        title: "Matching Pair".to_string(),
+                        keymaps: Keymaps::new(&[
+                            Keymap::new(
+                                "m",
+                                "Go to matching pair".to_string(),
+                                Dispatch::ToEditor(GoToMatchingPair { extend: false }),
+                            ),
+                            Keymap::new(
+                                "M",
+                                "Select to matching pair".to_string(),
+                                Dispatch::ToEditor(GoToMatchingPair { extend: true }),
+                            ),
+                        ]),
+                    },
                     KeymapLegendSection {
                         title: "Action".to_string(),
                         keymaps: Keymaps::new(&[
@@ -1224,3 +1869,54 @@ fn generate_enclosures_keymaps(get_dispatch: impl Fn(EnclosureKind) -> Dispatch)
         .collect_vec(),
     )
 }
+
+/// The menu of the formats of `path` to copy, where `line` is the line of the cursor (if any),
+/// and `selection_lines` are the first and last lines of the primary selection (if any).
+pub(crate) fn copy_path_keymap_legend_config(
+    path: &CanonicalizedPath,
+    line: Option<usize>,
+    selection_lines: Option<(usize, usize)>,
+) -> KeymapLegendConfig {
+    KeymapLegendConfig {
+        title: "Copy path".to_string(),
+        body: KeymapLegendBody::SingleSection {
+            keymaps: Keymaps::new(
+                &[
+                    Some(("r", "Relative path", PathFormat::Relative)),
+                    Some(("a", "Absolute path", PathFormat::Absolute)),
+                    line.map(|line| {
+                        (
+                            "l",
+                            "Relative path with line",
+                            PathFormat::RelativeWithLine(line),
+                        )
+                    }),
+                    Some(("n", "File name", PathFormat::FileName)),
+                    selection_lines.map(|(start_line, end_line)| {
+                        (
+                            "p",
+                            "Permalink of selection",
+                            PathFormat::Permalink {
+                                start_line,
+                                end_line,
+                            },
+                        )
+                    }),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|(key, description, format)| {
+                    Keymap::new(
+                        key,
+                        description.to_string(),
+                        Dispatch::CopyPath {
+                            path: path.clone(),
+                            format,
+                        },
+                    )
+                })
+                .collect_vec(),
+            ),
+        },
+    }
+}
@@ -0,0 +1,211 @@
+use std::path::Path;
+
+use itertools::Itertools;
+
+use crate::{app::Dispatch, keyboard_layout::KeyboardLayout};
+
+use super::keymap_legend::{KeymapLegendBody, KeymapLegendConfig, Keymaps};
+
+/// A flattened view of keymap legends, where the keymaps of nested legends (e.g. the Space menu)
+/// are listed in their own sections, prefixed by the keys that lead to them.
+pub(crate) struct KeymapCheatSheet {
+    sections: Vec<CheatSheetSection>,
+}
+
+struct CheatSheetSection {
+    title: String,
+    /// Pairs of (key sequence, description)
+    keymaps: Vec<(String, String)>,
+}
+
+impl KeymapCheatSheet {
+    /// The keys are displayed in the given `layout`.
+    pub(crate) fn new(configs: &[KeymapLegendConfig], layout: &KeyboardLayout) -> Self {
+        let mut sections = Vec::new();
+        for config in configs {
+            collect_sections(config, &[], layout, &mut sections)
+        }
+        Self { sections }
+    }
+
+    pub(crate) fn to_markdown(&self) -> String {
+        self.sections
+            .iter()
+            .map(|section| {
+                let rows = section
+                    .keymaps
+                    .iter()
+                    .map(|(key, description)| {
+                        format!(
+                            "| {} | {} |",
+                            markdown_code(key),
+                            description.replace('|', "\\|")
+                        )
+                    })
+                    .join("\n");
+                format!(
+                    "## {}\n\n| Key | Description |\n| --- | --- |\n{}",
+                    section.title, rows
+                )
+            })
+            .join("\n\n")
+    }
+
+    pub(crate) fn to_html(&self) -> String {
+        let body = self
+            .sections
+            .iter()
+            .map(|section| {
+                let rows = section
+                    .keymaps
+                    .iter()
+                    .map(|(key, description)| {
+                        format!(
+                            "<tr><td><kbd>{}</kbd></td><td>{}</td></tr>",
+                            html_escape(key),
+                            html_escape(description)
+                        )
+                    })
+                    .join("\n");
+                format!(
+                    "<h2>{}</h2>\n<table>\n<tr><th>Key</th><th>Description</th></tr>\n{}\n</table>",
+                    html_escape(&section.title),
+                    rows
+                )
+            })
+            .join("\n");
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Ki keymap</title>\n</head>\n<body>\n{body}\n</body>\n</html>\n"
+        )
+    }
+
+    /// Exports as HTML if the extension of `path` is `html` or `htm`, otherwise as Markdown.
+    pub(crate) fn export(&self, path: &Path) -> anyhow::Result<()> {
+        let content = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("html" | "htm") => self.to_html(),
+            _ => self.to_markdown(),
+        };
+        Ok(std::fs::write(path, content)?)
+    }
+}
+
+fn collect_sections(
+    config: &KeymapLegendConfig,
+    prefix: &[String],
+    layout: &KeyboardLayout,
+    sections: &mut Vec<CheatSheetSection>,
+) {
+    let title = if prefix.is_empty() {
+        config.title.clone()
+    } else {
+        format!("{} ({})", config.title, prefix.join(" "))
+    };
+    let legend_sections: Vec<(String, &Keymaps)> = match &config.body {
+        KeymapLegendBody::SingleSection { keymaps } => vec![(title, keymaps)],
+        KeymapLegendBody::MultipleSections { sections } => sections
+            .iter()
+            .map(|section| (format!("{}: {}", title, section.title), &section.keymaps))
+            .collect_vec(),
+    };
+    let mut nested_configs = Vec::new();
+    for (title, keymaps) in legend_sections {
+        let keymaps = keymaps
+            .iter()
+            .map(|keymap| {
+                let keys = prefix
+                    .iter()
+                    .cloned()
+                    .chain([layout.from_qwerty(keymap.key())])
+                    .collect_vec();
+                if let Dispatch::ShowKeymapLegend(config) = keymap.dispatch() {
+                    nested_configs.push((keys.clone(), config))
+                }
+                (keys.join(" "), keymap.description().to_string())
+            })
+            .collect_vec();
+        sections.push(CheatSheetSection { title, keymaps });
+    }
+    for (keys, config) in nested_configs {
+        collect_sections(&config, &keys, layout, sections)
+    }
+}
+
+fn markdown_code(text: &str) -> String {
+    if text.contains('`') {
+        format!("`` {} ``", text)
+    } else {
+        format!("`{}`", text.replace('|', "\\|"))
+    }
+}
+
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test_editor_keymap_printer {
+    use crate::{
+        app::Dispatch,
+        components::keymap_legend::{
+            Keymap, KeymapLegendBody, KeymapLegendConfig, KeymapLegendSection, Keymaps,
+        },
+        keyboard_layout::KeyboardLayout,
+    };
+
+    use super::KeymapCheatSheet;
+
+    fn config() -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Normal mode".to_string(),
+            body: KeymapLegendBody::MultipleSections {
+                sections: [KeymapLegendSection {
+                    title: "Actions".to_string(),
+                    keymaps: Keymaps::new(&[
+                        Keymap::new("a", "Append".to_string(), Dispatch::Null),
+                        Keymap::new(
+                            "space",
+                            "Space".to_string(),
+                            Dispatch::ShowKeymapLegend(KeymapLegendConfig {
+                                title: "Space".to_string(),
+                                body: KeymapLegendBody::SingleSection {
+                                    keymaps: Keymaps::new(&[Keymap::new(
+                                        "f",
+                                        "Files <all>".to_string(),
+                                        Dispatch::Null,
+                                    )]),
+                                },
+                            }),
+                        ),
+                    ]),
+                }]
+                .to_vec(),
+            },
+        }
+    }
+
+    #[test]
+    fn nested_legends_are_flattened() {
+        let cheat_sheet = KeymapCheatSheet::new(&[config()], &KeyboardLayout::default());
+        assert_eq!(
+            cheat_sheet.to_markdown(),
+            "## Normal mode: Actions
+
+| Key | Description |
+| --- | --- |
+| `a` | Append |
+| `space` | Space |
+
+## Space (space)
+
+| Key | Description |
+| --- | --- |
+| `space f` | Files <all> |"
+        );
+        assert!(cheat_sheet
+            .to_html()
+            .contains("<tr><td><kbd>space f</kbd></td><td>Files &lt;all&gt;</td></tr>"));
+    }
+}
@@ -1,23 +1,32 @@
+use std::{collections::HashMap, path::Path};
+
 use itertools::Itertools;
 use my_proc_macros::key;
 
-use crate::app::{Dispatch, Dispatches, YesNoPrompt};
+use crate::{
+    app::{Dispatch, Dispatches, YesNoPrompt},
+    project_config::ProjectConfig,
+};
 use shared::canonicalized_path::CanonicalizedPath;
 
 use super::{
     component::Component,
     editor::Editor,
+    editor_keymap_legend::copy_path_keymap_legend_config,
     keymap_legend::{Keymap, Keymaps},
 };
 
 pub(crate) struct FileExplorer {
     editor: Editor,
     tree: Tree,
+    /// The working directory followed by the extra workspace folders, see `ProjectConfig::roots`.
+    roots: Vec<CanonicalizedPath>,
 }
 
 impl FileExplorer {
     pub(crate) fn new(path: &CanonicalizedPath) -> anyhow::Result<Self> {
-        let tree = Tree::new(path)?;
+        let roots = ProjectConfig::roots(path)?;
+        let tree = Tree::workspace(path, roots.get(1..).unwrap_or_default())?;
         let text = tree.render();
         let mut editor = Editor::from_text(
             shared::language::from_extension("yaml")
@@ -25,7 +34,11 @@ impl FileExplorer {
             &format!("{}\n", text),
         );
         editor.set_title("File Explorer".to_string());
-        Ok(Self { editor, tree })
+        Ok(Self {
+            editor,
+            tree,
+            roots,
+        })
     }
 
     pub(crate) fn reveal(&mut self, path: &CanonicalizedPath) -> anyhow::Result<Dispatches> {
@@ -46,6 +59,31 @@ impl FileExplorer {
         Ok(())
     }
 
+    /// Unlike `refresh`, only the parent folders of `paths` are read again,
+    /// so that adding, moving or deleting a path is fast even if many folders are opened.
+    pub(crate) fn refresh_paths(
+        &mut self,
+        working_directory: &CanonicalizedPath,
+        paths: &[&Path],
+    ) -> anyhow::Result<()> {
+        let tree = std::mem::take(&mut self.tree);
+        self.tree = paths
+            .iter()
+            .flat_map(|path| path.ancestors().skip(1))
+            .filter(|directory| {
+                self.roots
+                    .iter()
+                    .any(|root| directory.starts_with(root.to_path_buf()))
+            })
+            .unique()
+            // Parent folders are read first, so that new folders are listed before their children
+            .sorted_by_key(|directory| directory.components().count())
+            .try_fold(tree, |tree, directory| {
+                tree.refresh_directory(working_directory, directory)
+            })?;
+        self.refresh_editor()
+    }
+
     fn refresh_editor(&mut self) -> anyhow::Result<()> {
         let text = self.tree.render();
         self.editor_mut().set_content(&text)
@@ -81,9 +119,15 @@ fn get_nodes(path: &CanonicalizedPath) -> anyhow::Result<Vec<Node>> {
         .collect())
 }
 
+/// The number of entries of a folder shown at a time,
+/// so that folders with thousands of entries do not slow down rendering.
+const PAGE_SIZE: usize = 500;
+
 #[derive(Clone, Default)]
 struct Tree {
     nodes: Vec<Node>,
+    /// The number of `nodes` shown, the rest are summarized by a `NodeKind::More` node.
+    shown: usize,
 }
 
 struct Continuation<T> {
@@ -99,7 +143,38 @@ enum ContinuationKind {
 impl Tree {
     fn new(working_directory: &CanonicalizedPath) -> anyhow::Result<Self> {
         let nodes = get_nodes(working_directory)?;
-        Ok(Self { nodes })
+        Ok(Self {
+            nodes,
+            shown: PAGE_SIZE,
+        })
+    }
+
+    /// The entries of the working directory, preceded by the extra workspace folders.
+    fn workspace(
+        working_directory: &CanonicalizedPath,
+        workspace_folders: &[CanonicalizedPath],
+    ) -> anyhow::Result<Self> {
+        let tree = Tree::new(working_directory)?;
+        Ok(Tree {
+            nodes: workspace_folders
+                .iter()
+                .map(|folder| Node {
+                    name: folder
+                        .to_path_buf()
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    path: folder.clone(),
+                    kind: NodeKind::Directory {
+                        open: false,
+                        children: None,
+                    },
+                })
+                .chain(tree.nodes)
+                .collect(),
+            ..tree
+        })
     }
 
     fn map<F>(self, f: F) -> Self
@@ -108,30 +183,35 @@ impl Tree {
     {
         Tree {
             nodes: self.nodes.into_iter().map(f).collect(),
+            ..self
         }
     }
 
+    /// The children of a folder are only read when it is opened for the first time.
     fn toggle<F>(self, path: &CanonicalizedPath, change_open: F) -> Self
     where
         F: Fn(bool) -> bool + Clone,
     {
         self.map(|node| {
             let kind = match node.kind {
-                NodeKind::File => node.kind,
-                NodeKind::Directory { open, children } => NodeKind::Directory {
-                    open: if node.path == *path {
-                        change_open(open)
-                    } else {
-                        open
-                    },
-                    children: children.or_else(|| Tree::new(&node.path).ok()).map(|tree| {
-                        if open {
-                            tree.toggle(path, change_open.clone())
+                NodeKind::File | NodeKind::More { .. } => node.kind,
+                NodeKind::Directory { open, children } => {
+                    let is_target = node.path == *path;
+                    let new_open = if is_target { change_open(open) } else { open };
+                    NodeKind::Directory {
+                        open: new_open,
+                        children: if open
+                            && !is_target
+                            && path.to_path_buf().starts_with(node.path.to_path_buf())
+                        {
+                            children.map(|tree| tree.toggle(path, change_open.clone()))
+                        } else if new_open {
+                            children.or_else(|| Tree::new(&node.path).ok())
                         } else {
-                            tree
-                        }
-                    }),
-                },
+                            children
+                        },
+                    }
+                }
             };
             Node { kind, ..node }
         })
@@ -141,46 +221,54 @@ impl Tree {
     where
         F: Fn(T, &Node) -> Continuation<T> + Clone,
     {
-        self.nodes
-            .iter()
-            .fold(
-                Continuation {
-                    state: result,
-                    kind: ContinuationKind::Continue,
-                },
-                |continuation, node| match continuation.kind {
-                    ContinuationKind::Continue => match &node.kind {
-                        NodeKind::File => {
-                            let result = f(continuation.state, node);
-                            Continuation {
-                                state: result.state,
-                                kind: ContinuationKind::Continue,
-                            }
+        let continuation = self.nodes.iter().take(self.shown).fold(
+            Continuation {
+                state: result,
+                kind: ContinuationKind::Continue,
+            },
+            |continuation, node| match continuation.kind {
+                ContinuationKind::Continue => match &node.kind {
+                    NodeKind::File | NodeKind::More { .. } => {
+                        let result = f(continuation.state, node);
+                        Continuation {
+                            state: result.state,
+                            kind: result.kind,
                         }
-                        NodeKind::Directory { open, children } => {
-                            let result = f(continuation.state, node);
-                            if *open {
-                                Continuation {
-                                    state: children
-                                        .as_ref()
-                                        .map(|tree| {
-                                            tree.walk_visible(result.state.clone(), f.clone())
-                                        })
-                                        .unwrap_or(result.state),
-                                    kind: ContinuationKind::Continue,
-                                }
-                            } else {
-                                Continuation {
-                                    state: result.state,
-                                    kind: ContinuationKind::Continue,
-                                }
-                            }
+                    }
+                    NodeKind::Directory { open, children } => {
+                        let result = f(continuation.state, node);
+                        match (result.kind, children) {
+                            (ContinuationKind::Continue, Some(tree)) if *open => Continuation {
+                                state: tree.walk_visible(result.state, f.clone()),
+                                kind: ContinuationKind::Continue,
+                            },
+                            (kind, _) => Continuation {
+                                state: result.state,
+                                kind,
+                            },
                         }
-                    },
-                    ContinuationKind::Stop => continuation,
+                    }
                 },
-            )
-            .state
+                ContinuationKind::Stop => continuation,
+            },
+        );
+        match (continuation.kind, self.more()) {
+            (ContinuationKind::Continue, Some(more)) => f(continuation.state, &more).state,
+            (_, _) => continuation.state,
+        }
+    }
+
+    /// The node that summarizes the nodes that are not shown yet, if any.
+    /// Its path is the path of the first node that is not shown.
+    fn more(&self) -> Option<Node> {
+        let first_hidden = self.nodes.get(self.shown)?;
+        Some(Node {
+            name: String::new(),
+            path: first_hidden.path.clone(),
+            kind: NodeKind::More {
+                count: self.nodes.len() - self.shown,
+            },
+        })
     }
 
     fn find_map<T: Clone, F>(&self, f: F) -> Option<T>
@@ -217,9 +305,12 @@ impl Tree {
     fn render_with_indent(&self, indent: usize) -> String {
         self.nodes
             .iter()
+            .take(self.shown)
+            .chain(self.more().as_ref())
             .map(|node| {
                 let content = match &node.kind {
                     NodeKind::File => format!("{}  {}", node.path.icon(), node.name),
+                    NodeKind::More { count } => format!("… {} more", count),
                     NodeKind::Directory { open, children } => {
                         let icon = if *open { "📂" } else { "📁" };
                         let head = format!("{}  {}{}", icon, node.name, "/");
@@ -261,12 +352,66 @@ impl Tree {
 
         Ok(paths
             .into_iter()
-            .fold(self, |tree, path| tree.toggle(&path, |_| true)))
+            .fold(self, |tree, path| tree.toggle(&path, |_| true))
+            .show_until(path))
+    }
+
+    /// Shows the nodes up to `path` and its parent folders,
+    /// if they are in the part of their folders that is not shown yet.
+    fn show_until(self, path: &CanonicalizedPath) -> Self {
+        let shown = match self
+            .nodes
+            .iter()
+            .position(|node| path.to_path_buf().starts_with(node.path.to_path_buf()))
+        {
+            Some(index) => self.shown.max(index + 1),
+            None => self.shown,
+        };
+        Tree { shown, ..self }.map(|node| match node.kind {
+            NodeKind::Directory {
+                open,
+                children: Some(children),
+            } if path.to_path_buf().starts_with(node.path.to_path_buf()) => Node {
+                kind: NodeKind::Directory {
+                    open,
+                    children: Some(children.show_until(path)),
+                },
+                ..node
+            },
+            _ => node,
+        })
+    }
+
+    /// Shows the next page of the folder containing the `NodeKind::More` node of `path`.
+    fn show_more(self, path: &CanonicalizedPath) -> Self {
+        if self
+            .nodes
+            .get(self.shown)
+            .is_some_and(|node| node.path == *path)
+        {
+            return Tree {
+                shown: self.shown + PAGE_SIZE,
+                ..self
+            };
+        }
+        self.map(|node| match node.kind {
+            NodeKind::Directory {
+                open,
+                children: Some(children),
+            } if path.to_path_buf().starts_with(node.path.to_path_buf()) => Node {
+                kind: NodeKind::Directory {
+                    open,
+                    children: Some(children.show_more(path)),
+                },
+                ..node
+            },
+            _ => node,
+        })
     }
 
     fn find_index(&self, path: &CanonicalizedPath) -> Option<usize> {
         self.find_map(|node, current_index| {
-            if node.path == *path {
+            if node.path == *path && !matches!(node.kind, NodeKind::More { .. }) {
                 Some(current_index)
             } else {
                 None
@@ -274,11 +419,12 @@ impl Tree {
         })
     }
 
+    /// Reads the entries of every opened folder again.
     fn refresh(self, working_directory: &CanonicalizedPath) -> anyhow::Result<Self> {
         let opened_paths = self.walk_visible(Vec::new(), |result, node| Continuation {
             kind: ContinuationKind::Continue,
             state: match &node.kind {
-                NodeKind::File => result,
+                NodeKind::File | NodeKind::More { .. } => result,
                 NodeKind::Directory { open, .. } => {
                     if *open {
                         result
@@ -291,12 +437,79 @@ impl Tree {
                 }
             },
         });
-        let tree = Tree::new(working_directory)?;
         log::info!("opened_paths = {:?}", opened_paths);
-        let tree = opened_paths
+        Some(working_directory.to_path_buf().clone())
             .into_iter()
-            .fold(tree, |tree, path| tree.toggle(&path, |_| true));
-        Ok(tree)
+            .chain(
+                opened_paths
+                    .into_iter()
+                    .map(CanonicalizedPath::into_path_buf),
+            )
+            .try_fold(self, |tree, directory| {
+                tree.refresh_directory(working_directory, &directory)
+            })
+    }
+
+    /// Reads the entries of `directory` again, if it is loaded, where `path` is the folder of this tree.
+    ///
+    /// The opened folders and the shown pages are kept, and the other folders are not read.
+    fn refresh_directory(self, path: &CanonicalizedPath, directory: &Path) -> anyhow::Result<Self> {
+        if path.to_path_buf() == directory {
+            // Nodes outside of `path` are the extra workspace folders, which are kept
+            let workspace_folders = self
+                .nodes
+                .iter()
+                .filter(|node| node.path.to_path_buf().parent() != Some(directory))
+                .cloned()
+                .collect_vec();
+            let tree = Tree::new(path)?;
+            return Ok(Tree {
+                nodes: workspace_folders.into_iter().chain(tree.nodes).collect(),
+                ..tree
+            }
+            .merge(self));
+        }
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|node| -> anyhow::Result<_> {
+                Ok(match node.kind {
+                    NodeKind::Directory {
+                        open,
+                        children: Some(children),
+                    } if directory.starts_with(node.path.to_path_buf()) => Node {
+                        kind: NodeKind::Directory {
+                            open,
+                            children: Some(children.refresh_directory(&node.path, directory)?),
+                        },
+                        ..node
+                    },
+                    _ => node,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Tree { nodes, ..self })
+    }
+
+    /// Keeps the states of the folders of `old` that still exist in this tree.
+    fn merge(self, old: Tree) -> Self {
+        let mut old_kinds: HashMap<CanonicalizedPath, NodeKind> = old
+            .nodes
+            .into_iter()
+            .filter(|node| matches!(node.kind, NodeKind::Directory { .. }))
+            .map(|node| (node.path, node.kind))
+            .collect();
+        Tree {
+            shown: self.shown.max(old.shown),
+            ..self
+        }
+        .map(|node| match node.kind {
+            NodeKind::Directory { .. } => Node {
+                kind: old_kinds.remove(&node.path).unwrap_or(node.kind),
+                ..node
+            },
+            _ => node,
+        })
     }
 }
 
@@ -314,6 +527,10 @@ enum NodeKind {
         /// Should be populated lazily
         children: Option<Tree>,
     },
+    /// Stands for the `count` entries of a folder that are not shown yet, see `PAGE_SIZE`.
+    More {
+        count: usize,
+    },
 }
 
 impl Component for FileExplorer {
@@ -329,29 +546,50 @@ impl Component for FileExplorer {
         self.get_current_node()
             .ok()
             .flatten()
+            .filter(|node| !matches!(node.kind, NodeKind::More { .. }))
             .map(|node| super::keymap_legend::KeymapLegendSection {
                 title: "File Explorer".to_string(),
-                keymaps: Keymaps::new(&[
-                    Keymap::new(
-                        "a",
-                        "Add file (or postfix with / for folder)".to_string(),
-                        Dispatch::OpenAddPathPrompt(node.path.clone()),
-                    ),
-                    Keymap::new(
-                        "d",
-                        "Delete path".to_string(),
-                        Dispatch::OpenYesNoPrompt(YesNoPrompt {
-                            title: format!("Delete \"{}\"?", node.path.display_absolute()),
-                            yes: Box::new(Dispatch::DeletePath(node.path.clone())),
-                        }),
-                    ),
-                    Keymap::new(
-                        "m",
-                        "Move path".to_string(),
-                        Dispatch::OpenMoveFilePrompt(node.path.clone()),
-                    ),
-                    Keymap::new("r", "Refresh".to_string(), Dispatch::RefreshFileExplorer),
-                ]),
+                keymaps: Keymaps::new(
+                    &[
+                        Keymap::new(
+                            "a",
+                            "Add file (or postfix with / for folder)".to_string(),
+                            Dispatch::OpenAddPathPrompt(node.path.clone()),
+                        ),
+                        Keymap::new(
+                            "d",
+                            "Delete path".to_string(),
+                            Dispatch::OpenYesNoPrompt(YesNoPrompt {
+                                title: format!("Delete \"{}\"?", node.path.display_absolute()),
+                                yes: Box::new(Dispatch::DeletePath(node.path.clone())),
+                            }),
+                        ),
+                        Keymap::new(
+                            "m",
+                            "Move path".to_string(),
+                            Dispatch::OpenMoveFilePrompt(node.path.clone()),
+                        ),
+                        Keymap::new("r", "Refresh".to_string(), Dispatch::RefreshFileExplorer),
+                        Keymap::new(
+                            "y",
+                            "Copy path".to_string(),
+                            Dispatch::ShowKeymapLegend(copy_path_keymap_legend_config(
+                                &node.path, None, None,
+                            )),
+                        ),
+                    ]
+                    .into_iter()
+                    .chain(matches!(node.kind, NodeKind::File).then(|| {
+                        // Unlike `enter`, the file explorer is not closed,
+                        // so the file is opened in the main panel beside it
+                        Keymap::new(
+                            "v",
+                            "Open file in split (keep explorer open)".to_string(),
+                            Dispatch::OpenFile(node.path.clone()),
+                        )
+                    }))
+                    .collect_vec(),
+                ),
             })
             .into_iter()
             .collect()
@@ -378,6 +616,12 @@ impl Component for FileExplorer {
                             self.refresh_editor()?;
                             Ok(Vec::new().into())
                         }
+                        NodeKind::More { .. } => {
+                            let tree = std::mem::take(&mut self.tree);
+                            self.tree = tree.show_more(&node.path);
+                            self.refresh_editor()?;
+                            Ok(Vec::new().into())
+                        }
                     }
                 } else {
                     Ok(Vec::new().into())
@@ -390,7 +634,9 @@ impl Component for FileExplorer {
 
 #[cfg(test)]
 mod test_file_explorer {
+    use itertools::Itertools;
     use my_proc_macros::{key, keys};
+    use shared::canonicalized_path::CanonicalizedPath;
 
     use crate::test_app::*;
 
@@ -419,6 +665,18 @@ mod test_file_explorer {
         })
     }
 
+    #[test]
+    fn built_on_first_use() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Expect(FileExplorerContent(String::new())),
+                App(RevealInExplorer(s.main_rs())),
+                Expect(CurrentComponentTitle("File Explorer")),
+            ])
+        })
+    }
+
     #[test]
     fn move_path() -> anyhow::Result<()> {
         execute_test(|s| {
@@ -450,4 +708,169 @@ mod test_file_explorer {
             ])
         })
     }
+
+    #[test]
+    fn reveal_current_file() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                App(RevealCurrentFileInExplorer),
+                Expect(CurrentComponentTitle("File Explorer")),
+                Expect(CurrentSelectedTexts(&["   - 🦀  main.rs"])),
+            ])
+        })
+    }
+
+    #[test]
+    fn open_file_in_split() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(RevealInExplorer(s.foo_rs())),
+                Expect(ComponentCount(1)),
+                App(HandleKeyEvents(keys!("space v").to_vec())),
+                // Expect the file explorer is kept open beside the file
+                Expect(ComponentCount(2)),
+                Expect(CurrentComponentPath(Some(s.foo_rs()))),
+            ])
+        })
+    }
+
+    #[test]
+    fn auto_follow() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(RevealCurrentFileInExplorer),
+                Expect(FileExplorerContent(
+                    "
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📁  src/ :
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+                App(ToggleExplorerAutoFollow),
+                App(OpenFile(s.main_rs())),
+                Expect(ComponentCount(2)),
+                Expect(FileExplorerContent(
+                    "
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📂  src/ :
+   - 🦀  foo.rs
+   - 🦀  main.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
+    #[test]
+    fn paginate_large_folders() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let directory = s.new_path("large");
+            std::fs::create_dir(&directory).unwrap();
+            let names = (0..=super::PAGE_SIZE)
+                .map(|index| format!("{:04}.rs", index))
+                .collect_vec();
+            for name in &names {
+                std::fs::write(directory.join(name), "").unwrap();
+            }
+            let header = "
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📂  large/ :
+"
+            .trim_matches('\n');
+            let files = |count: usize| {
+                names
+                    .iter()
+                    .take(count)
+                    .map(|name| format!("   - 🦀  {}", name))
+                    .join("\n")
+            };
+            let last: CanonicalizedPath = directory.join(names.last().unwrap()).try_into().unwrap();
+            Box::new([
+                App(RevealInExplorer(directory.try_into().unwrap())),
+                // Expect only the first page is shown
+                Expect(FileExplorerContent(format!(
+                    "{}\n{}\n   - … 1 more\n - 📁  src/ :",
+                    header,
+                    files(super::PAGE_SIZE)
+                ))),
+                // Expect revealing a path that is not shown yet shows it
+                App(RevealInExplorer(last)),
+                Expect(FileExplorerContent(format!(
+                    "{}\n{}\n - 📁  src/ :",
+                    header,
+                    files(names.len())
+                ))),
+            ])
+        })
+    }
+
+    #[test]
+    fn workspace_folders() -> anyhow::Result<()> {
+        execute_test(|s| {
+            std::fs::create_dir_all(s.new_path(".ki")).unwrap();
+            std::fs::create_dir_all(s.new_path("vendor/lib")).unwrap();
+            std::fs::write(
+                s.new_path(".ki/config.toml"),
+                "[workspace]\nfolders = [\"vendor/lib\"]",
+            )
+            .unwrap();
+            Box::new([
+                App(RevealCurrentFileInExplorer),
+                // Expect the extra workspace folders are listed before the working directory entries
+                Expect(FileExplorerContent(
+                    "
+ - 📁  lib/ :
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 📁  .ki/ :
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📁  src/ :
+ - 📁  vendor/ :
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
+    #[test]
+    fn refresh_affected_folders_only() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(RevealInExplorer(s.main_rs())),
+                App(AddPath(s.new_path("hello/world.rs").display().to_string())),
+                // Expect the opened folders are kept open
+                Expect(FileExplorerContent(
+                    "
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📂  hello/ :
+   - 🦀  world.rs
+ - 📂  src/ :
+   - 🦀  foo.rs
+   - 🦀  main.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
 }
@@ -98,6 +98,10 @@ impl Keymaps {
     pub(crate) fn get(&self, event: &KeyEvent) -> std::option::Option<&Keymap> {
         self.0.iter().find(|key| &key.event == event)
     }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Keymap> {
+        self.0.iter()
+    }
 }
 
 fn dedent(s: &str) -> String {
@@ -271,6 +275,14 @@ impl Keymap {
     pub(crate) fn event(&self) -> &KeyEvent {
         &self.event
     }
+
+    pub(crate) fn key(&self) -> &'static str {
+        self.key
+    }
+
+    pub(crate) fn description(&self) -> &str {
+        &self.description
+    }
 }
 
 impl KeymapLegend {
@@ -335,6 +347,7 @@ impl Component for KeymapLegend {
                     Ok(Default::default())
                 }
                 key_event => {
+                    let key_event = &context.keyboard_layout().to_qwerty(key_event.clone());
                     if let Some(keymap) = self
                         .config
                         .keymaps()
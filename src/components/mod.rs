@@ -4,8 +4,12 @@ pub(crate) mod prompt;
 
 pub(crate) mod editor;
 mod editor_keymap_legend;
+pub(crate) mod editor_keymap_printer;
 pub(crate) mod file_explorer;
 pub(crate) mod keymap_legend;
+pub(crate) mod outline;
+pub(crate) mod pager;
+pub(crate) mod peek;
 pub(crate) mod render_editor;
 pub(crate) mod suggestive_editor;
 #[cfg(test)]
@@ -0,0 +1,212 @@
+use crossterm::event::KeyCode;
+use event::KeyModifiers;
+use itertools::Itertools;
+use my_proc_macros::key;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    app::{Dispatch, Dispatches},
+    buffer::OutlineSymbol,
+    quickfix_list::Location,
+};
+
+use super::{component::Component, editor::Editor};
+
+/// A sidebar listing the symbols of the main editor hierarchically,
+/// which follows the cursor of the main editor by highlighting the innermost symbol containing it.
+///
+/// Typing filters the symbols by name, and pressing enter jumps to the symbol under the cursor.
+pub(crate) struct Outline {
+    editor: Editor,
+    path: Option<CanonicalizedPath>,
+    /// The revision of the buffer when `symbols` was computed
+    revision: usize,
+    symbols: Vec<OutlineSymbol>,
+    filter: String,
+    /// The indices of the symbols matching `filter`, one per line of `editor`
+    visible_symbols: Vec<usize>,
+    /// The index of the innermost symbol containing the cursor of the main editor
+    current_symbol: Option<usize>,
+}
+
+impl Outline {
+    pub(crate) fn new() -> Self {
+        let mut editor = Editor::from_text(None, "");
+        editor.set_title("Outline".to_string());
+        Self {
+            editor,
+            path: None,
+            revision: 0,
+            symbols: Vec::new(),
+            filter: String::new(),
+            visible_symbols: Vec::new(),
+            current_symbol: None,
+        }
+    }
+
+    /// Updates the symbols if the buffer of `editor` is changed,
+    /// and highlights the symbol containing the cursor of `editor` if it is moved to another symbol.
+    pub(crate) fn track(&mut self, editor: &Editor) -> anyhow::Result<()> {
+        let (path, revision) = {
+            let buffer = editor.buffer();
+            (buffer.path(), buffer.revision())
+        };
+        let symbols_changed = path != self.path || revision != self.revision;
+        if symbols_changed {
+            self.symbols = editor.buffer().outline()?;
+            self.path = path;
+            self.revision = revision;
+            self.refresh_editor()?;
+        }
+        let cursor = editor.get_cursor_position()?;
+        let current_symbol = self
+            .symbols
+            .iter()
+            .rposition(|symbol| symbol.range.contains(&cursor));
+        if symbols_changed || current_symbol != self.current_symbol {
+            self.current_symbol = current_symbol;
+            self.select_current_symbol()?;
+        }
+        Ok(())
+    }
+
+    fn set_filter(&mut self, filter: String) -> anyhow::Result<Dispatches> {
+        self.filter = filter;
+        self.refresh_editor()?;
+        self.select_current_symbol()?;
+        Ok(Default::default())
+    }
+
+    fn refresh_editor(&mut self) -> anyhow::Result<()> {
+        let filter = self.filter.to_lowercase();
+        self.visible_symbols = self
+            .symbols
+            .iter()
+            .positions(|symbol| symbol.name.to_lowercase().contains(&filter))
+            .collect();
+        let content = self
+            .visible_symbols
+            .iter()
+            .map(|index| {
+                let symbol = &self.symbols[*index];
+                format!("{}{}", "  ".repeat(symbol.depth), symbol.name)
+            })
+            .join("\n");
+        self.editor.set_content(&content)?;
+        self.editor.set_title(if self.filter.is_empty() {
+            "Outline".to_string()
+        } else {
+            format!("Outline (filter: {})", self.filter)
+        });
+        Ok(())
+    }
+
+    fn select_current_symbol(&mut self) -> anyhow::Result<()> {
+        let line = self.current_symbol.and_then(|current_symbol| {
+            self.visible_symbols
+                .iter()
+                .position(|index| *index == current_symbol)
+        });
+        self.editor.select_line_at(line.unwrap_or_default())?;
+        Ok(())
+    }
+
+    fn move_line(&mut self, forward: bool) -> anyhow::Result<Dispatches> {
+        let line = self.editor.get_cursor_position()?.line;
+        let line = if forward {
+            (line + 1).min(self.visible_symbols.len().saturating_sub(1))
+        } else {
+            line.saturating_sub(1)
+        };
+        self.editor.select_line_at(line)
+    }
+
+    fn go_to_symbol_under_cursor(&self) -> anyhow::Result<Dispatches> {
+        let line = self.editor.get_cursor_position()?.line;
+        Ok(Dispatches::default().append_some(
+            self.path
+                .clone()
+                .zip(self.visible_symbols.get(line))
+                .map(|(path, index)| {
+                    Dispatch::GotoLocation(Location {
+                        path,
+                        range: self.symbols[*index].range.clone(),
+                    })
+                }),
+        ))
+    }
+}
+
+impl Component for Outline {
+    fn editor(&self) -> &Editor {
+        &self.editor
+    }
+
+    fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.editor
+    }
+
+    fn handle_key_event(
+        &mut self,
+        _context: &crate::context::Context,
+        event: event::KeyEvent,
+    ) -> anyhow::Result<Dispatches> {
+        match event {
+            key!("esc") if self.filter.is_empty() => {
+                Ok(Dispatches::one(Dispatch::CloseCurrentWindow))
+            }
+            key!("esc") => self.set_filter(String::new()),
+            key!("enter") => self.go_to_symbol_under_cursor(),
+            key!("down") => self.move_line(true),
+            key!("up") => self.move_line(false),
+            key!("backspace") => {
+                let mut filter = self.filter.clone();
+                filter.pop();
+                self.set_filter(filter)
+            }
+            event::KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::None | KeyModifiers::Shift,
+            } => self.set_filter(format!("{}{}", self.filter, c)),
+            _ => Ok(Default::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_outline {
+    use my_proc_macros::keys;
+
+    use crate::{app::Dispatch::*, components::editor::DispatchEditor::*, test_app::*};
+
+    #[test]
+    fn outline_tracks_cursor_and_filters_symbols() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent(
+                    "mod m {\n  fn first() {}\n  fn second() {}\n}\nfn third() {}".to_string(),
+                )),
+                Editor(MatchLiteral("second".to_string())),
+                App(OpenOutline),
+                Expect(CurrentComponentTitle("Outline")),
+                Expect(CurrentComponentContent("m\n  first\n  second\nthird")),
+                Expect(CurrentSelectedTexts(&["  second\n"])),
+                // The outline follows the cursor of the main editor
+                App(OtherWindow),
+                Editor(MatchLiteral("third".to_string())),
+                Expect(AppGridContains("third")),
+                App(OtherWindow),
+                Expect(CurrentComponentTitle("Outline")),
+                Expect(CurrentSelectedTexts(&["third"])),
+                App(HandleKeyEvents(keys!("i r").to_vec())),
+                Expect(CurrentComponentTitle("Outline (filter: ir)")),
+                Expect(CurrentComponentContent("  first\nthird")),
+                Expect(CurrentSelectedTexts(&["third"])),
+                App(HandleKeyEvents(keys!("up enter").to_vec())),
+                Expect(CurrentComponentPath(Some(s.main_rs()))),
+                Expect(CurrentSelectedTexts(&["fn first() {}"])),
+            ])
+        })
+    }
+}
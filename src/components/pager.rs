@@ -0,0 +1,212 @@
+use my_proc_macros::key;
+
+use crate::{
+    app::{Dispatch, Dispatches},
+    components::editor::Direction,
+    context::Context,
+    position::Position,
+    quickfix_list::Location,
+    rectangle::Rectangle,
+};
+
+use super::{component::Component, editor::Editor, suggestive_editor::Info};
+
+/// A read-only view of an `Info` (e.g. hover documentation),
+/// which can be scrolled and whose links can be followed when it is focused.
+pub(crate) struct Pager {
+    editor: Editor,
+    info: Info,
+    /// The index of the selected link of `info`
+    current_link_index: Option<usize>,
+}
+
+impl Pager {
+    pub(crate) fn new(info: Info) -> anyhow::Result<Self> {
+        let mut editor = Editor::from_text(None, "");
+        editor.show_info(info.clone())?;
+        Ok(Self {
+            editor,
+            info,
+            current_link_index: None,
+        })
+    }
+
+    pub(crate) fn info(&self) -> &Info {
+        &self.info
+    }
+
+    fn scroll(&mut self, direction: Direction, height: usize) {
+        let scroll_offset = self.editor.scroll_offset() as usize;
+        let scroll_offset = match direction {
+            Direction::Start => scroll_offset.saturating_sub(height),
+            Direction::End => scroll_offset
+                .saturating_add(height)
+                .min(self.editor.buffer().len_lines().saturating_sub(1)),
+        };
+        self.editor.set_scroll_offset(scroll_offset as u16)
+    }
+
+    fn half_page_height(&self) -> usize {
+        (self.editor.dimension().height / 2).max(1) as usize
+    }
+
+    fn select_link(&mut self, direction: Direction) -> anyhow::Result<Dispatches> {
+        let links = self.info.links();
+        if links.is_empty() {
+            return Ok(Default::default());
+        }
+        let index = match (direction, self.current_link_index) {
+            (Direction::End, Some(index)) => (index + 1).min(links.len() - 1),
+            (Direction::Start, Some(index)) => index.saturating_sub(1),
+            (_, None) => 0,
+        };
+        self.current_link_index = Some(index);
+        self.editor.select_byte_range(&links[index].range)
+    }
+
+    fn follow_link(&self) -> anyhow::Result<Dispatches> {
+        let Some(link) = self
+            .current_link_index
+            .and_then(|index| self.info.links().get(index))
+        else {
+            return Ok(Default::default());
+        };
+        let url = match lsp_types::Url::parse(&link.target) {
+            Ok(url) if url.scheme() == "file" => url,
+            _ => return Ok(Dispatches::one(Dispatch::OpenUrl(link.target.clone()))),
+        };
+        let path = url
+            .to_file_path()
+            .map_err(|_| anyhow::anyhow!("Invalid file URL: {}", link.target))?
+            .try_into()?;
+        // Links to a line are usually suffixed with `#L<line>`, where the line is 1-based
+        let line = url
+            .fragment()
+            .and_then(|fragment| fragment.strip_prefix('L'))
+            .and_then(|line| line.parse::<usize>().ok())
+            .map(|line| line.saturating_sub(1))
+            .unwrap_or_default();
+        Ok(
+            Dispatches::one(Dispatch::CloseCurrentWindowAndFocusParent).append(
+                Dispatch::GotoLocation(Location {
+                    path,
+                    range: Position::new(line, 0)..Position::new(line, 0),
+                }),
+            ),
+        )
+    }
+}
+
+impl Component for Pager {
+    fn editor(&self) -> &Editor {
+        &self.editor
+    }
+
+    fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.editor
+    }
+
+    /// The scroll position is kept, because it is controlled by the user instead of the cursor.
+    fn set_rectangle(&mut self, rectangle: Rectangle) {
+        let scroll_offset = self.editor.scroll_offset();
+        self.editor.set_rectangle(rectangle);
+        self.editor.set_scroll_offset(scroll_offset)
+    }
+
+    fn handle_key_event(
+        &mut self,
+        context: &Context,
+        event: event::KeyEvent,
+    ) -> anyhow::Result<Dispatches> {
+        match context.keyboard_layout().to_qwerty(event) {
+            key!("esc") | key!("q") => {
+                Ok(Dispatches::one(Dispatch::CloseCurrentWindowAndFocusParent))
+            }
+            key!("j") | key!("down") => {
+                self.scroll(Direction::End, 1);
+                Ok(Default::default())
+            }
+            key!("k") | key!("up") => {
+                self.scroll(Direction::Start, 1);
+                Ok(Default::default())
+            }
+            key!("ctrl+d") | key!("space") => {
+                self.scroll(Direction::End, self.half_page_height());
+                Ok(Default::default())
+            }
+            key!("ctrl+u") => {
+                self.scroll(Direction::Start, self.half_page_height());
+                Ok(Default::default())
+            }
+            key!("l") | key!("tab") => self.select_link(Direction::End),
+            key!("h") => self.select_link(Direction::Start),
+            key!("enter") => self.follow_link(),
+            // Other keys are ignored, as the content is read-only
+            _ => Ok(Default::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_pager {
+    use my_proc_macros::key;
+
+    use crate::{
+        app::Dispatch,
+        components::suggestive_editor::{Info, Link},
+        position::Position,
+        test_app::*,
+    };
+
+    #[test]
+    fn scroll_and_follow_file_link() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let content = "see foo\n".to_string() + &"line\n".repeat(30);
+            let link = Link {
+                range: 4..7,
+                target: format!("file://{}#L2", s.foo_rs().display_absolute()),
+            };
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                App(Dispatch::ShowEditorInfo(
+                    Info::new("Hover Info".to_string(), content).set_links(vec![link]),
+                )),
+                // Requesting hover again focuses the opened info
+                App(Dispatch::RequestHover),
+                Expect(CurrentComponentTitle("Hover Info")),
+                App(HandleKeyEvent(key!("j"))),
+                App(HandleKeyEvent(key!("j"))),
+                Expect(CurrentScrollOffset(2)),
+                App(HandleKeyEvent(key!("k"))),
+                Expect(CurrentScrollOffset(1)),
+                // The content is read-only
+                App(HandleKeyEvent(key!("d"))),
+                Expect(CurrentScrollOffset(1)),
+                App(HandleKeyEvent(key!("l"))),
+                Expect(CurrentSelectedTexts(&["foo"])),
+                App(HandleKeyEvent(key!("enter"))),
+                Expect(CurrentComponentPath(Some(s.foo_rs()))),
+                Expect(EditorCursorPosition(Position::new(1, 0))),
+            ])
+        })
+    }
+
+    #[test]
+    fn scroll_position_is_kept_when_the_same_info_is_shown_again() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let info = Info::new("Info".to_string(), "line\n".repeat(30));
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                App(Dispatch::ShowEditorInfo(info.clone())),
+                App(Dispatch::RequestHover),
+                App(HandleKeyEvent(key!("j"))),
+                Expect(CurrentScrollOffset(1)),
+                App(Dispatch::OtherWindow),
+                Expect(CurrentComponentPath(Some(s.main_rs()))),
+                App(Dispatch::ShowEditorInfo(info)),
+                App(Dispatch::RequestHover),
+                Expect(CurrentScrollOffset(1)),
+            ])
+        })
+    }
+}
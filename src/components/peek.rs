@@ -0,0 +1,166 @@
+use std::{cell::RefCell, rc::Rc};
+
+use itertools::Itertools;
+use my_proc_macros::key;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    app::{Dispatch, Dispatches},
+    buffer::Buffer,
+    quickfix_list::Location,
+};
+
+use super::{component::Component, editor::Editor};
+
+/// The number of lines shown above and below each location.
+const CONTEXT_LINES: usize = 2;
+
+/// A read-only view of locations (e.g. references), showing the lines around each of them,
+/// for quick lookups that do not replace the quickfix list or the current window.
+pub(crate) struct Peek {
+    editor: Editor,
+    locations: Vec<Location>,
+    /// The line of each location in the content of `editor`
+    location_lines: Vec<usize>,
+    current_index: usize,
+}
+
+impl Peek {
+    /// The content of `buffers` is preferred over the file content, as they might be unsaved.
+    pub(crate) fn new(
+        title: String,
+        locations: Vec<Location>,
+        buffers: &[Rc<RefCell<Buffer>>],
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<Self> {
+        let mut sections = Vec::new();
+        let mut location_lines = Vec::new();
+        let mut line_count = 0;
+        for location in &locations {
+            let content = match buffers
+                .iter()
+                .find(|buffer| buffer.borrow().path().as_ref() == Some(&location.path))
+            {
+                Some(buffer) => buffer.borrow().content(),
+                None => location.path.read()?,
+            };
+            let lines = content.lines().collect_vec();
+            let target = location.range.start.line;
+            let start = target.saturating_sub(CONTEXT_LINES);
+            let end = (target + CONTEXT_LINES + 1).min(lines.len());
+            let header = format!(
+                "{}:{}",
+                location
+                    .path
+                    .display_relative_to(working_directory)
+                    .unwrap_or_else(|_| location.path.display_absolute()),
+                target + 1
+            );
+            let width = end.to_string().len();
+            let body = (start..end)
+                .map(|line| format!("{:>width$} │ {}", line + 1, lines[line]))
+                .collect_vec();
+            location_lines.push(line_count + 1 + target.saturating_sub(start));
+            line_count += body.len() + 2;
+            sections.push(Some(header).into_iter().chain(body).join("\n"))
+        }
+        let mut editor = Editor::from_text(None, &sections.join("\n\n"));
+        editor.set_title(title);
+        let mut peek = Self {
+            editor,
+            locations,
+            location_lines,
+            current_index: 0,
+        };
+        peek.select_location(0)?;
+        Ok(peek)
+    }
+
+    fn select_location(&mut self, index: usize) -> anyhow::Result<Dispatches> {
+        let Some(line) = self.location_lines.get(index) else {
+            return Ok(Default::default());
+        };
+        self.current_index = index;
+        self.editor.select_line_at(*line)
+    }
+}
+
+impl Component for Peek {
+    fn editor(&self) -> &Editor {
+        &self.editor
+    }
+
+    fn editor_mut(&mut self) -> &mut Editor {
+        &mut self.editor
+    }
+
+    fn handle_key_event(
+        &mut self,
+        context: &crate::context::Context,
+        event: event::KeyEvent,
+    ) -> anyhow::Result<Dispatches> {
+        match context.keyboard_layout().to_qwerty(event) {
+            key!("esc") => Ok(Dispatches::one(Dispatch::CloseCurrentWindowAndFocusParent)),
+            key!("enter") => Ok(Dispatches::one(Dispatch::CloseCurrentWindowAndFocusParent)
+                .append_some(
+                    self.locations
+                        .get(self.current_index)
+                        .cloned()
+                        .map(Dispatch::GotoLocation),
+                )),
+            key!("l") | key!("down") => self.select_location(self.current_index + 1),
+            key!("h") | key!("up") => self.select_location(self.current_index.saturating_sub(1)),
+            // Other keys are ignored, as the content is read-only
+            _ => Ok(Default::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_peek {
+    use my_proc_macros::key;
+
+    use crate::{
+        app::{Dispatch, Scope},
+        lsp::process::{LspNotification, ResponseContext},
+        position::Position,
+        quickfix_list::Location,
+        test_app::*,
+    };
+
+    #[test]
+    fn peek_references() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let location = |line: usize| Location {
+                path: s.main_rs(),
+                range: Position::new(line, 0)..Position::new(line, 1),
+            };
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("a\nb\nc\nd\ne\nf\ng".to_string())),
+                App(Dispatch::HandleLspNotification(
+                    LspNotification::References(
+                        ResponseContext {
+                            scope: Some(Scope::Local),
+                            description: Some("References".to_string()),
+                            peek: true,
+                            moved_path: None,
+                        },
+                        [location(0), location(5)].to_vec(),
+                    ),
+                )),
+                Expect(CurrentComponentTitle("References (2)")),
+                Expect(CurrentSelectedTexts(&["1 │ a\n"])),
+                App(HandleKeyEvent(key!("l"))),
+                Expect(CurrentSelectedTexts(&["6 │ f\n"])),
+                // The content is read-only
+                App(HandleKeyEvent(key!("d"))),
+                Expect(CurrentSelectedTexts(&["6 │ f\n"])),
+                App(HandleKeyEvent(key!("enter"))),
+                Expect(CurrentComponentPath(Some(s.main_rs()))),
+                Expect(CurrentSelectedTexts(&["f"])),
+                Expect(ExpectKind::Quickfixes(Box::new([]))),
+            ])
+        })
+    }
+}
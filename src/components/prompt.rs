@@ -1,12 +1,16 @@
 use std::{cell::RefCell, rc::Rc};
 
+use crossterm::event::KeyCode;
+use event::KeyModifiers;
+use itertools::Itertools;
 use my_proc_macros::key;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     app::{Dispatch, DispatchPrompt, Dispatches, GlobalSearchFilterGlob, Scope},
     buffer::Buffer,
     components::editor::{self, DispatchEditor},
-    context::Context,
+    context::{Context, SearchOption},
     lsp::completion::Completion,
 };
 
@@ -24,6 +28,9 @@ pub(crate) struct Prompt {
     enter_selects_first_matching_item: bool,
     prompt_history_key: PromptHistoryKey,
     fire_dispatches_on_change: Option<Dispatches>,
+    /// True when the prompt is opened with a pre-filled current line that is
+    /// not yet edited, so that the first character typed replaces it.
+    overwrite_current_line: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -38,7 +45,7 @@ pub(crate) struct PromptConfig {
     pub(crate) fire_dispatches_on_change: Option<Dispatches>,
 }
 
-#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub(crate) enum PromptHistoryKey {
     MoveToIndex,
     Search(Scope),
@@ -52,17 +59,63 @@ pub(crate) enum PromptHistoryKey {
     FilterGlob(GlobalSearchFilterGlob),
     Replacement(Scope),
     CodeAction,
+    QuickfixList,
+    QuickfixListFilter,
+    ExportKeymapCheatSheet,
+    ExportHighlighted,
+    RejectWorkspaceEditFile,
+    EditColor,
+    IncrementNumber,
+    SortByKey,
+    PipeToShell,
+    InsertShellOutput,
+    InsertExpressionResult,
+    UnicodeCodePoint,
+    CharacterPicker,
+    LayoutPreset,
+    Breadcrumbs,
+    Build,
+    ScratchBuffer,
+    Diagnostics,
+    BookmarkNote,
+    Bookmarks,
+    WorkingDirectory,
+    Branch,
+    CommitLog,
+    GitHunkBaseline,
+    FilterSelections,
+    KeepEveryNthSelection,
+    SplitSelections,
     #[cfg(test)]
     Null,
     Theme,
 }
 
+impl PromptHistoryKey {
+    /// Whether typing into the pre-filled current line of this prompt replaces it.
+    ///
+    /// This is false for prompts whose pre-filled line is usually edited rather than replaced,
+    /// such as paths and bookmark notes.
+    fn overwrites_current_line(&self) -> bool {
+        !matches!(
+            self,
+            PromptHistoryKey::AddPath
+                | PromptHistoryKey::MovePath
+                | PromptHistoryKey::BookmarkNote
+                | PromptHistoryKey::WorkingDirectory
+        )
+    }
+}
+
 impl Prompt {
     pub(crate) fn new(
         config: PromptConfig,
         prompt_history_key: PromptHistoryKey,
         mut history: Vec<String>,
     ) -> (Self, Dispatches) {
+        let overwrite_current_line = prompt_history_key.overwrites_current_line()
+            && !config.leaves_current_line_empty
+            && history.last().is_some_and(|line| !line.is_empty());
         let text = {
             history.reverse();
             format!(
@@ -86,6 +139,8 @@ impl Prompt {
         }));
         // TODO: set cursor to last line
         editor.set_title(config.title);
+        // The file picker is the only prompt whose items are paths
+        editor.set_completion_path_query(prompt_history_key == PromptHistoryKey::OpenFile);
         editor.set_completion(Completion {
             items: config.items,
             trigger_characters: vec![" ".to_string()],
@@ -98,12 +153,28 @@ impl Prompt {
                 enter_selects_first_matching_item: config.enter_selects_first_matching_item,
                 prompt_history_key,
                 fire_dispatches_on_change: config.fire_dispatches_on_change,
+                overwrite_current_line,
             },
             dispatches,
         )
     }
 }
 
+impl Prompt {
+    pub(crate) fn prompt_history_key(&self) -> PromptHistoryKey {
+        self.prompt_history_key
+    }
+
+    /// Replaces the items, keeping the current filter.
+    pub(crate) fn set_items(&mut self, items: Vec<DropdownItem>) -> Dispatches {
+        self.editor.set_completion(Completion {
+            items,
+            trigger_characters: vec![" ".to_string()],
+        });
+        self.editor.render_completion_dropdown(true)
+    }
+}
+
 impl Component for Prompt {
     fn editor(&self) -> &Editor {
         self.editor.editor()
@@ -123,6 +194,37 @@ impl Component for Prompt {
         context: &Context,
         event: event::KeyEvent,
     ) -> anyhow::Result<Dispatches> {
+        if let PromptHistoryKey::Search(scope) = self.prompt_history_key {
+            let option = match event {
+                key!("alt+i") => Some(SearchOption::CaseSensitive),
+                key!("alt+w") => Some(SearchOption::MatchWholeWord),
+                key!("alt+s") => Some(SearchOption::SmartCase),
+                key!("alt+p") => Some(SearchOption::PreserveCase),
+                _ => None,
+            };
+            if let Some(option) = option {
+                return Ok(Dispatches::one(Dispatch::ToggleSearchOption {
+                    scope,
+                    option,
+                }));
+            }
+        }
+        // Any key other than a character keeps the pre-filled current line
+        if std::mem::take(&mut self.overwrite_current_line) && self.editor().mode == Mode::Insert {
+            if let event::KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::None | KeyModifiers::Shift,
+            } = event
+            {
+                return Ok(Dispatches::new(
+                    [
+                        Dispatch::ToEditor(DispatchEditor::KillLine(editor::Direction::Start)),
+                        Dispatch::ToEditor(DispatchEditor::Insert(c.to_string())),
+                    ]
+                    .to_vec(),
+                ));
+            }
+        }
         match event {
             key!("esc") if self.editor().mode == Mode::Normal => {
                 Ok(Dispatches::one(Dispatch::CloseCurrentWindow)
@@ -139,8 +241,38 @@ impl Component for Prompt {
                     self.editor_mut().handle_key_event(context, event)
                 }
             }
+            key!("tab") if self.editor.completion_dropdown_opened() => {
+                Ok(self.editor.toggle_mark_completion_dropdown_current_item())
+            }
+            key!("alt+enter") => {
+                let marked_items = self.editor.completion_dropdown_marked_items();
+                let items = if marked_items.is_empty() {
+                    self.editor
+                        .completion_dropdown_current_item()
+                        .into_iter()
+                        .collect_vec()
+                } else {
+                    marked_items
+                };
+                let dispatches = items
+                    .into_iter()
+                    .filter_map(|item| item.secondary_dispatches())
+                    .flat_map(|dispatches| dispatches.into_vec())
+                    .collect_vec();
+                if dispatches.is_empty() {
+                    return Ok(Default::default());
+                }
+                Ok(Dispatches::one(Dispatch::CloseCurrentWindow).chain(dispatches.into()))
+            }
             key!("enter") => {
-                let (line, dispatches) = if self.enter_selects_first_matching_item
+                let marked_items = self.editor.completion_dropdown_marked_items();
+                let (line, dispatches) = if !marked_items.is_empty() {
+                    let dispatches = marked_items
+                        .into_iter()
+                        .flat_map(|item| item.dispatches.into_vec())
+                        .collect_vec();
+                    (self.editor().current_line()?, dispatches.into())
+                } else if self.enter_selects_first_matching_item
                     && self.editor.completion_dropdown_current_item().is_some()
                 {
                     self.editor
@@ -288,6 +420,38 @@ mod test_prompt {
         .unwrap();
     }
 
+    #[test]
+    fn typing_replaces_prefilled_current_line() {
+        fn test(keys: &[event::KeyEvent], expected_text: &'static str) {
+            let keys = keys.to_vec();
+            execute_test(|s| {
+                Box::new([
+                    App(OpenFile(s.main_rs())),
+                    App(OpenPrompt {
+                        key: PromptHistoryKey::Null,
+                        current_line: Some("hello world".to_string()),
+                        config: PromptConfig {
+                            on_enter: DispatchPrompt::Null,
+                            items: Default::default(),
+                            title: "".to_string(),
+                            enter_selects_first_matching_item: true,
+                            leaves_current_line_empty: false,
+                            fire_dispatches_on_change: None,
+                        },
+                    }),
+                    App(HandleKeyEvents(keys.clone())),
+                    Expect(CurrentComponentContent(expected_text)),
+                ])
+            })
+            .unwrap();
+        }
+        // Typing a character replaces the pre-filled current line
+        test(keys!("y o"), "yo");
+        // Any other key keeps the pre-filled current line for editing
+        test(keys!("left x"), "hello worlxd");
+        test(keys!("ctrl+w x"), "hello x");
+    }
+
     #[test]
     fn enter_selects_first_matching_item() {
         fn run_test(
@@ -480,4 +644,42 @@ mod test_prompt {
             ])
         })
     }
+
+    #[test]
+    fn mark_multiple_items() -> anyhow::Result<()> {
+        execute_test(|s| {
+            let item = |display: &str, path: shared::canonicalized_path::CanonicalizedPath| {
+                DropdownItem::new(display.to_string())
+                    .set_dispatches(Dispatches::one(Dispatch::OpenFile(path.clone())))
+                    .set_secondary_dispatches(Dispatches::one(Dispatch::CloseFile(path)))
+            };
+            let open_prompt = Dispatch::OpenPrompt {
+                key: PromptHistoryKey::Null,
+                current_line: None,
+                config: PromptConfig {
+                    on_enter: DispatchPrompt::Null,
+                    items: [item("foo", s.foo_rs()), item("main", s.main_rs())].to_vec(),
+                    title: "".to_string(),
+                    enter_selects_first_matching_item: true,
+                    leaves_current_line_empty: true,
+                    fire_dispatches_on_change: None,
+                },
+            };
+            Box::new([
+                App(open_prompt.clone()),
+                // Mark both items
+                App(HandleKeyEvent(key!("tab"))),
+                Expect(CompletionDropdownContent("foo ✓\nmain")),
+                App(HandleKeyEvent(key!("tab"))),
+                Expect(CompletionDropdownContent("foo ✓\nmain ✓")),
+                // Expect the dispatches of every marked item are fired
+                App(HandleKeyEvent(key!("enter"))),
+                Expect(OpenedFilesCount(2)),
+                // Expect alt+enter fires the secondary dispatches of the marked items
+                App(open_prompt),
+                App(HandleKeyEvents(keys!("tab tab alt+enter").to_vec())),
+                Expect(OpenedFilesCount(0)),
+            ])
+        })
+    }
 }
@@ -2,20 +2,25 @@ use std::ops::Range;
 
 use itertools::Itertools;
 use lsp_types::DiagnosticSeverity;
+use my_proc_macros::hex;
 
 use crate::{
     app::Dimension,
     buffer::Buffer,
     char_index_range::CharIndexRange,
+    color_literal::ColorLiteral,
     components::{
         component::{Component, Cursor, SetCursorStyle},
         editor::Mode,
     },
     context::Context,
-    grid::{CellUpdate, Grid, LineUpdate, RenderContentLineNumber, StyleKey},
+    grid::{CellUpdate, Grid, LineSign, LineUpdate, RenderContentLineNumber, StyleKey},
+    lsp::diagnostic::Diagnostic,
+    position::Position,
     selection::{CharIndex, Selection},
     selection_mode::{self, ByteRange},
     style::Style,
+    test_runner::TestStatus,
     themes::Theme,
 };
 
@@ -23,6 +28,16 @@ use super::{component::GetGridResult, editor::Editor};
 
 use StyleKey::*;
 
+/// Appended to lines to show the colors of their color literals.
+const COLOR_SWATCH: char = '■';
+
+/// Appended to the first line of each test to show the result of its last run.
+const TEST_PASSED_MARKER: char = '✔';
+const TEST_FAILED_MARKER: char = '✘';
+
+/// Separates the content of a line from its inline diagnostic.
+const INLINE_DIAGNOSTIC_GAP: &str = "  ";
+
 impl Editor {
     pub(crate) fn get_grid(&self, context: &Context, focused: bool) -> GetGridResult {
         let editor = self;
@@ -34,11 +49,15 @@ impl Editor {
         let diagnostics = buffer.diagnostics();
 
         let len_lines = rope.len_lines().max(1) as u16;
-        let (hidden_parent_lines, visible_parent_lines) =
-            self.get_parent_lines().unwrap_or_default();
+        let max_line_number = len_lines as usize + self.line_number_offset();
+        let (hidden_parent_lines, visible_parent_lines) = if context.sticky_context() {
+            self.get_parent_lines().unwrap_or_default()
+        } else {
+            Default::default()
+        };
         let top_offset = hidden_parent_lines.len() as u16;
         let scroll_offset = self.scroll_offset();
-        let visible_lines = &rope
+        let mut visible_lines = rope
             .lines()
             .enumerate()
             .skip(scroll_offset as usize)
@@ -74,7 +93,7 @@ impl Editor {
                 set_symbol: None,
                 is_cursor: false,
                 source: Source::StyleKey(UiBookmark),
-                ranges: HighlightSpanRange::CharIndexRange(bookmark),
+                ranges: HighlightSpanRange::CharIndexRange(bookmark.range),
             })
             .collect_vec();
         let secondary_selections = &editor.selection_set.secondary_selections();
@@ -216,21 +235,44 @@ impl Editor {
                 source: Source::StyleKey(highlighted_span.style_key),
             })
             .collect_vec();
-        let custom_regex_highlights = lazy_regex::regex!("(?i)#[0-9a-f]{6}")
-            .find_iter(&rope.to_string())
-            .map(|m| (m.as_str().to_string(), m.range()))
-            .filter_map(|(hex, range)| {
-                let color = crate::themes::Color::from_hex(&hex).ok()?;
-                Some(HighlightSpan {
-                    set_symbol: None,
-                    is_cursor: false,
-                    ranges: HighlightSpanRange::ByteRange(range),
-                    source: Source::Style(
-                        Style::new()
-                            .background_color(color)
-                            .foreground_color(color.get_contrasting_color()),
-                    ),
-                })
+        // Tags such as `TODO` in comments are highlighted with their own styles
+        let annotation_highlights = buffer
+            .highlighted_spans()
+            .into_iter()
+            .filter(|highlighted_span| {
+                matches!(&highlighted_span.style_key, StyleKey::Syntax(group) if group.starts_with("comment"))
+            })
+            .flat_map(|highlighted_span| {
+                let start = highlighted_span.byte_range.start;
+                content
+                    .get(highlighted_span.byte_range)
+                    .map(|comment| {
+                        crate::list::annotation::find_tags(comment)
+                            .map(|(tag, range)| HighlightSpan {
+                                set_symbol: None,
+                                is_cursor: false,
+                                ranges: HighlightSpanRange::ByteRange(
+                                    start + range.start..start + range.end,
+                                ),
+                                source: Source::StyleKey(tag.style_key()),
+                            })
+                            .collect_vec()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect_vec();
+        let color_literals = buffer.color_literals();
+        let color_literal_highlights = color_literals
+            .iter()
+            .map(|color_literal| HighlightSpan {
+                set_symbol: None,
+                is_cursor: false,
+                ranges: HighlightSpanRange::CharIndexRange(color_literal.range),
+                source: Source::Style(
+                    Style::new()
+                        .background_color(color_literal.color)
+                        .foreground_color(color_literal.color.get_contrasting_color()),
+                ),
             })
             .collect_vec();
 
@@ -273,6 +315,7 @@ impl Editor {
             .into_iter()
             .chain(visible_parent_lines)
             .chain(highlighted_spans)
+            .chain(annotation_highlights)
             .chain(extra_decorations)
             .chain(possible_selections)
             .chain(Some(primary_selection))
@@ -284,23 +327,67 @@ impl Editor {
             .chain(jumps)
             .chain(primary_selection_secondary_cursor)
             .chain(secondary_selection_cursors)
-            .chain(custom_regex_highlights)
+            .chain(color_literal_highlights)
             .chain(regex_highlight_rules)
             .collect_vec();
+        let ghost_text_updates = self.render_ghost_text(&buffer, &mut visible_lines, theme);
+        let color_swatch_updates =
+            self.render_color_swatches(&buffer, &color_literals, &mut visible_lines);
+        let test_marker_updates = self.render_test_markers(&buffer, &mut visible_lines);
+        let inline_diagnostic_updates = if context.inline_diagnostics() {
+            // The width of the content excludes the line numbers and their separator
+            let content_width =
+                (width as usize).saturating_sub(max_line_number.to_string().len() + 1);
+            self.render_inline_diagnostics(
+                &buffer,
+                &buffer.diagnostics(),
+                &mut visible_lines,
+                theme,
+                content_width,
+            )
+        } else {
+            Vec::new()
+        };
         let visible_lines_updates = {
             let boundaries = [Boundary::new(&buffer, self.visible_line_range())];
             updates
                 .iter()
                 .flat_map(|span| span.to_cell_update(&buffer, theme, &boundaries))
                 .chain(primary_selection_primary_cursor)
+                .chain(ghost_text_updates)
+                .chain(color_swatch_updates)
+                .chain(test_marker_updates)
+                .chain(inline_diagnostic_updates)
                 .collect_vec()
         };
 
+        // Bookmarked lines are marked in the gutter, with a filled sign if the bookmark has a note
+        let bookmark_signs = buffer
+            .bookmarks()
+            .into_iter()
+            .filter_map(|bookmark| {
+                Some(LineSign {
+                    line_index: buffer.char_to_line(bookmark.range.start).ok()?,
+                    symbol: if bookmark.note.is_some() {
+                        "◆"
+                    } else {
+                        "◇"
+                    }
+                    .to_string(),
+                    style: theme
+                        .ui
+                        .border
+                        .set_some_foreground_color(theme.ui.bookmark.background_color),
+                })
+            })
+            .collect_vec();
+
         let visible_lines_grid = visible_lines_grid.render_content(
             &visible_lines.iter().map(|(_, line)| line).join(""),
             RenderContentLineNumber::LineNumber {
-                start_line_index: scroll_offset as usize,
-                max_line_number: len_lines as usize,
+                start_line_index: scroll_offset as usize + self.line_number_offset(),
+                max_line_number,
+                signs: bookmark_signs.clone(),
             },
             visible_lines_updates
                 .clone()
@@ -346,8 +433,9 @@ impl Editor {
                     grid.merge_vertical(Grid::new(Dimension { height: 1, width }).render_content(
                         &line.content,
                         RenderContentLineNumber::LineNumber {
-                            start_line_index: line.line,
-                            max_line_number: len_lines as usize,
+                            start_line_index: line.line + self.line_number_offset(),
+                            max_line_number,
+                            signs: bookmark_signs.clone(),
                         },
                         updates,
                         Default::default(),
@@ -416,6 +504,164 @@ impl Editor {
         }
     }
 
+    /// Appends the first line of the ghost text to the cursor line of `visible_lines`,
+    /// and returns the cell updates that dim the ghost text.
+    ///
+    /// The ghost text is only rendered when the cursor is at the end of the line,
+    /// so that the positions of the characters of the buffer are not shifted.
+    fn render_ghost_text(
+        &self,
+        buffer: &Buffer,
+        visible_lines: &mut [(usize, String)],
+        theme: &Theme,
+    ) -> Vec<CellUpdate> {
+        let Some(ghost_text) = self
+            .ghost_text
+            .as_ref()
+            .filter(|_| self.mode == Mode::Insert)
+        else {
+            return Vec::new();
+        };
+        let Ok(cursor) = buffer.char_to_position(self.get_cursor_char_index()) else {
+            return Vec::new();
+        };
+        let Some((_, line)) = visible_lines
+            .iter_mut()
+            .find(|(line_index, _)| *line_index == cursor.line)
+        else {
+            return Vec::new();
+        };
+        let line_ending = if line.ends_with('\n') { "\n" } else { "" };
+        let content = line.trim_end_matches('\n');
+        if content.chars().count() != cursor.column {
+            return Vec::new();
+        }
+        let ghost_text = ghost_text.lines().next().unwrap_or_default();
+        *line = format!("{content}{ghost_text}{line_ending}");
+        (0..ghost_text.chars().count())
+            .map(|offset| CellUpdate {
+                style: theme.ui.ghost_text,
+                ..CellUpdate::new(Position::new(cursor.line, cursor.column + offset))
+            })
+            .collect_vec()
+    }
+
+    /// Appends a swatch of each color literal to the end of its line in `visible_lines`,
+    /// and returns the cell updates that paint the swatches with their colors.
+    fn render_color_swatches(
+        &self,
+        buffer: &Buffer,
+        color_literals: &[ColorLiteral],
+        visible_lines: &mut [(usize, String)],
+    ) -> Vec<CellUpdate> {
+        color_literals
+            .iter()
+            .filter_map(|color_literal| {
+                let line_index = buffer
+                    .char_to_position(color_literal.range.start)
+                    .ok()?
+                    .line;
+                let (_, line) = visible_lines
+                    .iter_mut()
+                    .find(|(index, _)| *index == line_index)?;
+                let line_ending = if line.ends_with('\n') { "\n" } else { "" };
+                let content = line.trim_end_matches('\n');
+                let column = content.chars().count() + 1;
+                *line = format!("{content} {COLOR_SWATCH}{line_ending}");
+                Some(CellUpdate {
+                    style: Style::new().foreground_color(color_literal.color),
+                    ..CellUpdate::new(Position::new(line_index, column))
+                })
+            })
+            .collect_vec()
+    }
+
+    /// Appends a pass or fail marker to the first line of each test in `visible_lines`,
+    /// and returns the cell updates that paint the markers.
+    fn render_test_markers(
+        &self,
+        buffer: &Buffer,
+        visible_lines: &mut [(usize, String)],
+    ) -> Vec<CellUpdate> {
+        buffer
+            .test_results()
+            .iter()
+            .filter_map(|result| {
+                let line_index = result.test.range.start.line;
+                let (_, line) = visible_lines
+                    .iter_mut()
+                    .find(|(index, _)| *index == line_index)?;
+                let (marker, color) = match result.status {
+                    TestStatus::Passed => (TEST_PASSED_MARKER, hex!("#008000")),
+                    TestStatus::Failed => (TEST_FAILED_MARKER, hex!("#ff0000")),
+                };
+                let line_ending = if line.ends_with('\n') { "\n" } else { "" };
+                let content = line.trim_end_matches('\n');
+                let column = content.chars().count() + 1;
+                *line = format!("{content} {marker}{line_ending}");
+                Some(CellUpdate {
+                    style: Style::new().foreground_color(color),
+                    ..CellUpdate::new(Position::new(line_index, column))
+                })
+            })
+            .collect_vec()
+    }
+
+    /// Appends the first line of the message of the first diagnostic of each line in `visible_lines`,
+    /// and returns the cell updates that dim the messages.
+    ///
+    /// Messages are truncated to fit in `content_width`, so that they never wrap.
+    fn render_inline_diagnostics(
+        &self,
+        buffer: &Buffer,
+        diagnostics: &[Diagnostic],
+        visible_lines: &mut [(usize, String)],
+        theme: &Theme,
+        content_width: usize,
+    ) -> Vec<CellUpdate> {
+        diagnostics
+            .iter()
+            .filter_map(|diagnostic| {
+                Some((
+                    buffer.char_to_position(diagnostic.range.start).ok()?,
+                    diagnostic,
+                ))
+            })
+            .sorted_by_key(|(position, _)| *position)
+            .unique_by(|(position, _)| position.line)
+            .filter_map(|(position, diagnostic)| {
+                let (_, line) = visible_lines
+                    .iter_mut()
+                    .find(|(index, _)| *index == position.line)?;
+                let line_ending = if line.ends_with('\n') { "\n" } else { "" };
+                let content = line.trim_end_matches('\n');
+                let column = content.chars().count() + INLINE_DIAGNOSTIC_GAP.len();
+                // One column is left for the cursor at the end of the line
+                let available_width = content_width.checked_sub(column + 1)?;
+                let message = diagnostic.message.lines().next().unwrap_or_default();
+                let message = if message.chars().count() > available_width {
+                    let truncated = message
+                        .chars()
+                        .take(available_width.checked_sub(1)?)
+                        .collect::<String>();
+                    format!("{truncated}…")
+                } else {
+                    message.to_string()
+                };
+                *line = format!("{content}{INLINE_DIAGNOSTIC_GAP}{message}{line_ending}");
+                Some(
+                    (0..message.chars().count())
+                        .map(|offset| CellUpdate {
+                            style: theme.ui.ghost_text,
+                            ..CellUpdate::new(Position::new(position.line, column + offset))
+                        })
+                        .collect_vec(),
+                )
+            })
+            .flatten()
+            .collect_vec()
+    }
+
     pub(crate) fn possible_selections_in_line_number_range(
         &self,
         selection: &Selection,
@@ -11,7 +11,12 @@ use crate::{
 
 use itertools::Itertools;
 use my_proc_macros::key;
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+};
 
 use super::dropdown::{Dropdown, DropdownConfig};
 use super::editor::DispatchEditor;
@@ -29,6 +34,10 @@ pub(crate) struct SuggestiveEditor {
 
     trigger_characters: Vec<String>,
     filter: SuggestiveEditorFilter,
+    /// Completion items from the LSP server
+    lsp_completion_items: Vec<DropdownItem>,
+    /// Completion items that do not require any LSP server, see `local_completion`
+    local_completion_items: Vec<DropdownItem>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -37,6 +46,75 @@ pub(crate) enum SuggestiveEditorFilter {
     CurrentLine,
 }
 
+/// A provider of inline suggestions (for example, an AI code assistant),
+/// which are rendered as ghost text after the cursor and accepted with `tab`.
+///
+/// Requests are fire-and-forget because suggestions can be slow to compute,
+/// the suggestion should be sent back via `AppMessage::InlineSuggestion`.
+pub(crate) trait CompletionSource {
+    fn request(&mut self, request: InlineSuggestionRequest) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub(crate) struct InlineSuggestionRequest {
+    /// Used for discarding outdated suggestions.
+    pub(crate) id: usize,
+    pub(crate) path: String,
+    /// The content before the cursor, up to `INLINE_SUGGESTION_CONTEXT_LINES` lines above it.
+    pub(crate) prefix: String,
+    /// The content after the cursor, up to `INLINE_SUGGESTION_CONTEXT_LINES` lines below it.
+    pub(crate) suffix: String,
+}
+
+/// The number of lines above and below the cursor that are sent to the `CompletionSource`,
+/// so that the size of a request does not grow with the size of the file.
+const INLINE_SUGGESTION_CONTEXT_LINES: usize = 100;
+
+impl InlineSuggestionRequest {
+    /// `cursor` is the byte index of the cursor in `content`.
+    pub(crate) fn new(id: usize, path: String, content: &str, cursor: usize) -> Self {
+        let (prefix, suffix) = content.split_at(cursor);
+        let prefix_start = prefix
+            .rmatch_indices('\n')
+            .nth(INLINE_SUGGESTION_CONTEXT_LINES)
+            .map(|(index, _)| index + 1)
+            .unwrap_or(0);
+        let suffix_end = suffix
+            .match_indices('\n')
+            .nth(INLINE_SUGGESTION_CONTEXT_LINES)
+            .map(|(index, _)| index)
+            .unwrap_or(suffix.len());
+        Self {
+            id,
+            path,
+            prefix: prefix[prefix_start..].to_string(),
+            suffix: suffix[..suffix_end].to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct InlineSuggestion {
+    /// The `id` of the corresponding `InlineSuggestionRequest`.
+    pub(crate) id: usize,
+    /// The text to be inserted at the cursor.
+    pub(crate) text: String,
+}
+
+/// Only words within this number of lines from the cursor contribute to the locality rank.
+const LOCALITY_MAX_DISTANCE: usize = 100;
+
+/// Extract the identifier from the display of a completion item,
+/// which is of the format "<icon> <label>", where label might be suffixed with
+/// non-identifier characters, for example "foo(…)".
+fn completion_label_word(display: &str) -> Option<&str> {
+    let label = display.split_whitespace().last()?;
+    let end = label
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(label.len());
+    Some(&label[..end]).filter(|word| !word.is_empty())
+}
+
 impl From<CompletionItem> for DropdownItem {
     fn from(item: CompletionItem) -> Self {
         DropdownItem::new(format!("{} {}", item.emoji(), item.label()))
@@ -75,6 +153,15 @@ impl Component for SuggestiveEditor {
         context: &Context,
         event: event::KeyEvent,
     ) -> anyhow::Result<Dispatches> {
+        if self.editor.mode == Mode::Insert {
+            // Any key other than `tab` dismisses the ghost text
+            if let Some(ghost_text) = self.editor.ghost_text().cloned() {
+                self.editor.set_ghost_text(None);
+                if event == key!("tab") {
+                    return Ok(Dispatches::one(Dispatch::ToEditor(Insert(ghost_text))));
+                }
+            }
+        }
         if self.editor.mode == Mode::Insert && self.completion_dropdown_opened() {
             match event {
                 key!("ctrl+n") | key!("down") => {
@@ -88,6 +175,8 @@ impl Component for SuggestiveEditor {
                 key!("ctrl+space") => {
                     let current_item = self.completion_dropdown.current_item();
                     if let Some(completion) = current_item {
+                        self.lsp_completion_items.clear();
+                        self.local_completion_items.clear();
                         self.completion_dropdown.set_items(Vec::new());
                         return Ok(
                             Dispatches::one(Dispatch::CloseDropdown).chain(completion.dispatches)
@@ -122,28 +211,42 @@ impl Component for SuggestiveEditor {
     }
 
     fn contextual_keymaps(&self) -> Vec<super::keymap_legend::KeymapLegendSection> {
+        let cursor_char_index = self.editor().get_cursor_char_index();
+        let diagnostics = self
+            .editor()
+            .buffer()
+            .diagnostics()
+            .into_iter()
+            .filter_map(|diagnostic| {
+                if diagnostic.range.contains(&cursor_char_index) {
+                    diagnostic.original_value.clone()
+                } else {
+                    None
+                }
+            })
+            .collect_vec();
         [KeymapLegendSection {
             title: "LSP".to_string(),
             keymaps: Keymaps::new(&[
-                Keymap::new("c", "Code Actions".to_string(), {
-                    let cursor_char_index = self.editor().get_cursor_char_index();
+                Keymap::new(
+                    "c",
+                    "Code Actions".to_string(),
                     Dispatch::RequestCodeAction {
-                        diagnostics: self
-                            .editor()
-                            .buffer()
-                            .diagnostics()
-                            .into_iter()
-                            .filter_map(|diagnostic| {
-                                if diagnostic.range.contains(&cursor_char_index) {
-                                    diagnostic.original_value.clone()
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect_vec(),
-                    }
-                }),
+                        diagnostics: diagnostics.clone(),
+                    },
+                ),
+                Keymap::new(
+                    "F",
+                    "Apply preferred fix".to_string(),
+                    Dispatch::ApplyPreferredCodeAction { diagnostics },
+                ),
                 Keymap::new("h", "Hover".to_string(), Dispatch::RequestHover),
+                Keymap::new(
+                    "p",
+                    "Peek definitions".to_string(),
+                    Dispatch::PeekDefinitions,
+                ),
+                Keymap::new("P", "Peek references".to_string(), Dispatch::PeekReferences),
                 Keymap::new("r", "Rename".to_string(), Dispatch::PrepareRename),
             ]),
         }]
@@ -160,6 +263,8 @@ impl SuggestiveEditor {
             }),
             trigger_characters: vec![],
             filter,
+            lsp_completion_items: Vec::new(),
+            local_completion_items: Vec::new(),
         }
     }
 
@@ -181,9 +286,26 @@ impl SuggestiveEditor {
                     Ok(Vec::new().into())
                 }
             }
+            DispatchSuggestiveEditor::LocalCompletion(items) => {
+                if self.editor.mode == Mode::Insert
+                    && self.filter == SuggestiveEditorFilter::CurrentWord
+                {
+                    self.local_completion_items = items;
+                    self.update_completion_dropdown_items();
+                    Ok(self.render_completion_dropdown(false))
+                } else {
+                    Ok(Vec::new().into())
+                }
+            }
             DispatchSuggestiveEditor::UpdateCurrentCompletionItem(completion_item) => {
                 Ok(self.update_current_completion_item(completion_item))
             }
+            DispatchSuggestiveEditor::InlineSuggestion(text) => {
+                if self.editor.mode == Mode::Insert {
+                    self.editor.set_ghost_text(Some(text))
+                }
+                Ok(Default::default())
+            }
         }
     }
 
@@ -191,13 +313,108 @@ impl SuggestiveEditor {
         self.completion_dropdown.current_item()
     }
 
+    /// Marks or unmarks the current item of the completion dropdown, then focuses the next item.
+    pub(crate) fn toggle_mark_completion_dropdown_current_item(&mut self) -> Dispatches {
+        self.completion_dropdown.toggle_mark_current_item();
+        self.completion_dropdown.next_item();
+        self.render_completion_dropdown(true)
+    }
+
+    pub(crate) fn completion_dropdown_marked_items(&self) -> Vec<DropdownItem> {
+        self.completion_dropdown.marked_items()
+    }
+
     pub(crate) fn completion_dropdown_opened(&self) -> bool {
         !self.completion_dropdown.items().is_empty()
     }
 
+    /// See `Dropdown::set_path_query`.
+    pub(crate) fn set_completion_path_query(&mut self, path_query: bool) {
+        self.completion_dropdown.set_path_query(path_query)
+    }
+
     pub(crate) fn set_completion(&mut self, completion: Completion) {
-        self.completion_dropdown.set_items(completion.items);
+        self.lsp_completion_items = completion.items;
         self.trigger_characters = completion.trigger_characters;
+        self.update_completion_dropdown_items();
+    }
+
+    /// Merge the LSP completion items with the local completion items,
+    /// local items that are already suggested by the LSP server are omitted,
+    /// and the remaining local items are ranked after all LSP items.
+    fn update_completion_dropdown_items(&mut self) {
+        let lsp_words = self
+            .lsp_completion_items
+            .iter()
+            .filter_map(|item| completion_label_word(&item.display()).map(|word| word.to_string()))
+            .collect::<HashSet<_>>();
+        let items = self
+            .lsp_completion_items
+            .iter()
+            .cloned()
+            .chain(
+                self.local_completion_items
+                    .iter()
+                    .filter(|item| {
+                        completion_label_word(&item.display())
+                            .map(|word| !lsp_words.contains(word))
+                            .unwrap_or(true)
+                    })
+                    .map(|item| item.clone().set_rank(Some(Box::new([usize::MAX])))),
+            )
+            .collect_vec();
+        let items = match self.filter {
+            SuggestiveEditorFilter::CurrentWord => {
+                let distances = self.word_distances_from_cursor();
+                items
+                    .into_iter()
+                    .map(|item| {
+                        let distance = completion_label_word(&item.display())
+                            .and_then(|word| distances.get(word))
+                            .copied()
+                            .unwrap_or(usize::MAX);
+                        item.append_rank(distance)
+                    })
+                    .collect_vec()
+            }
+            SuggestiveEditorFilter::CurrentLine => items,
+        };
+        self.completion_dropdown.set_items(items);
+    }
+
+    /// Returns the distance (in lines) between the cursor and the nearest
+    /// occurrence of each word around the cursor.
+    /// This is used for ranking completion items by locality.
+    fn word_distances_from_cursor(&self) -> HashMap<String, usize> {
+        let Ok(cursor_line) = self
+            .editor
+            .get_cursor_position()
+            .map(|position| position.line)
+        else {
+            return Default::default();
+        };
+        let first_line = cursor_line.saturating_sub(LOCALITY_MAX_DISTANCE);
+        let buffer = self.editor.buffer();
+        let rope = buffer.rope();
+        let mut distances = HashMap::new();
+        // Only the lines around the cursor are scanned, without copying the whole content
+        for (line_index, line) in (first_line..)
+            .zip(rope.lines_at(first_line.min(rope.len_lines())))
+            .take(cursor_line - first_line + LOCALITY_MAX_DISTANCE + 1)
+        {
+            let distance = line_index.abs_diff(cursor_line);
+            let line = std::borrow::Cow::<str>::from(line);
+            for word in line
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .filter(|word| !word.is_empty())
+            {
+                distances
+                    .entry(word.to_string())
+                    .and_modify(|current: &mut usize| *current = (*current).min(distance))
+                    .or_insert(distance);
+            }
+        }
+        distances
     }
 
     pub(crate) fn render_completion_dropdown(&self, ignore_insert_mode: bool) -> Dispatches {
@@ -233,11 +450,7 @@ impl SuggestiveEditor {
                 match self.editor().buffer().get_char_at_position(cursor_position) {
                     // The filter should be empty if the current character is a trigger
                     // character, so that we can show all the completion items.
-                    Some(current_char)
-                        if self.trigger_characters.contains(&current_char.to_string()) =>
-                    {
-                        "".to_string()
-                    }
+                    Some(current_char) if self.is_trigger_character(current_char) => "".to_string(),
 
                     // If the current character is not a trigger character, we should
                     // filter based on the current word under the cursor.
@@ -253,6 +466,13 @@ impl SuggestiveEditor {
         Ok(render_completion_dropdown)
     }
 
+    /// The trigger characters of the LSP server and of the local completion,
+    /// see `local_completion::TRIGGER_CHARACTERS`.
+    fn is_trigger_character(&self, c: char) -> bool {
+        crate::local_completion::TRIGGER_CHARACTERS.contains(&c)
+            || self.trigger_characters.contains(&c.to_string())
+    }
+
     fn update_current_completion_item(&mut self, completion_item: CompletionItem) -> Dispatches {
         self.completion_dropdown
             .update_current_item(completion_item.into());
@@ -265,6 +485,8 @@ pub(crate) enum DispatchSuggestiveEditor {
     #[cfg(test)]
     CompletionFilter(SuggestiveEditorFilter),
     Completion(Completion),
+    LocalCompletion(Vec<DropdownItem>),
+    InlineSuggestion(String),
     UpdateCurrentCompletionItem(CompletionItem),
 }
 
@@ -290,7 +512,10 @@ mod test_suggestive_editor {
     use std::{cell::RefCell, rc::Rc};
     use Dispatch::*;
 
-    use super::{Info, SuggestiveEditor, SuggestiveEditorFilter};
+    use super::{
+        Info, InlineSuggestionRequest, SuggestiveEditor, SuggestiveEditorFilter,
+        INLINE_SUGGESTION_CONTEXT_LINES,
+    };
 
     fn dummy_completion() -> Completion {
         Completion {
@@ -310,6 +535,31 @@ mod test_suggestive_editor {
         SuggestiveEditor::from_buffer(Rc::new(RefCell::new(Buffer::new(None, ""))), filter)
     }
 
+    #[test]
+    fn inline_suggestion_request_only_contains_the_lines_around_the_cursor() {
+        let lines = (0..1000)
+            .map(|index| format!("line {index}"))
+            .collect::<Vec<_>>();
+        let content = lines.join("\n");
+        let cursor = content.find("line 500").unwrap() + "line".len();
+        let request = InlineSuggestionRequest::new(0, "main.rs".to_string(), &content, cursor);
+        let first_line = 500 - INLINE_SUGGESTION_CONTEXT_LINES;
+        let last_line = 500 + INLINE_SUGGESTION_CONTEXT_LINES;
+        assert_eq!(
+            request.prefix,
+            format!("{}\nline", lines[first_line..500].join("\n"))
+        );
+        assert_eq!(
+            request.suffix,
+            format!(" 500\n{}", lines[501..=last_line].join("\n"))
+        );
+
+        // Expect the whole content to be sent if it is near the cursor
+        let request = InlineSuggestionRequest::new(0, "main.rs".to_string(), "ab\ncd", 4);
+        assert_eq!(request.prefix, "ab\nc");
+        assert_eq!(request.suffix, "d");
+    }
+
     #[test]
     fn typing_in_insert_mode_should_request_completion() {
         let mut editor = editor(SuggestiveEditorFilter::CurrentWord);
@@ -685,6 +935,138 @@ mod test_suggestive_editor {
         })
     }
 
+    #[test]
+    fn completion_items_are_sorted_by_sort_text_then_locality() -> Result<(), anyhow::Error> {
+        let item = |label: &str, sort_text: &str| lsp_types::CompletionItem {
+            label: label.to_string(),
+            sort_text: Some(sort_text.to_string()),
+            ..Default::default()
+        };
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("\nfooBar\n\n\n\nfooBaz".to_string())),
+                Editor(EnterInsertMode(Direction::Start)),
+                SuggestiveEditor(CompletionFilter(SuggestiveEditorFilter::CurrentWord)),
+                SuggestiveEditor(Completion(Completion::from_lsp_items(
+                    [
+                        item("fooBaz", "1"),
+                        item("fooBar", "1"),
+                        item("fooSpam", "0"),
+                    ]
+                    .to_vec(),
+                    vec![".".to_string()],
+                ))),
+                // `fooSpam` comes first because of its sort text,
+                // `fooBar` comes before `fooBaz` because it is nearer to the cursor
+                Expect(CompletionDropdownContent(" fooSpam\n fooBar\n fooBaz")),
+            ])
+        })
+    }
+
+    #[test]
+    fn word_completion_from_opened_buffers() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.foo_rs())),
+                Editor(SetContent("spongebob squarepants".to_string())),
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("".to_string())),
+                Editor(EnterInsertMode(Direction::Start)),
+                App(HandleKeyEvents(keys!("s q").to_vec())),
+                Expect(CompletionDropdownIsOpen(true)),
+                Expect(CompletionDropdownContent("squarepants")),
+                App(HandleKeyEvent(key!("ctrl+space"))),
+                Expect(CurrentComponentContent("squarepants")),
+                // Words are not suggested when no word is being typed
+                App(HandleKeyEvent(key!("space"))),
+                Expect(CompletionDropdownIsOpen(false)),
+            ])
+        })
+    }
+
+    #[test]
+    fn path_completion() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("".to_string())),
+                Editor(EnterInsertMode(Direction::Start)),
+                App(HandleKeyEvents(keys!(". / f o").to_vec())),
+                // The entries of the directory are read in another thread
+                Expect(CompletionDropdownIsOpen(false)),
+                // Entries of a directory other than the one being typed are outdated
+                App(ReceiveDirectoryEntries {
+                    directory: s.temp_dir().to_path_buf().clone(),
+                    entries: ["src/".to_string()].to_vec(),
+                }),
+                Expect(CompletionDropdownIsOpen(false)),
+                App(ReceiveDirectoryEntries {
+                    directory: s.temp_dir().to_path_buf().join("src"),
+                    entries: ["foo.rs".to_string(), "main.rs".to_string()].to_vec(),
+                }),
+                Expect(CompletionDropdownIsOpen(true)),
+                Expect(CompletionDropdownContent("foo.rs")),
+                App(HandleKeyEvent(key!("ctrl+space"))),
+                Expect(CurrentComponentContent("./foo.rs")),
+            ])
+        })
+    }
+
+    #[test]
+    fn path_completion_replaces_dotted_file_name() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("".to_string())),
+                Editor(EnterInsertMode(Direction::Start)),
+                App(HandleKeyEvents(keys!(". / f o o . r").to_vec())),
+                App(ReceiveDirectoryEntries {
+                    directory: s.temp_dir().to_path_buf().join("src"),
+                    entries: ["foo.rs".to_string()].to_vec(),
+                }),
+                Expect(CompletionDropdownContent("foo.rs")),
+                App(HandleKeyEvent(key!("ctrl+space"))),
+                // The whole file name is replaced, not only the word before the cursor
+                Expect(CurrentComponentContent("./foo.rs")),
+            ])
+        })
+    }
+
+    #[test]
+    fn inline_suggestion_accepted_with_tab() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("fn main".to_string())),
+                Editor(MatchLiteral("main".to_string())),
+                Editor(EnterInsertMode(Direction::End)),
+                SuggestiveEditor(InlineSuggestion("() {}".to_string())),
+                // The ghost text is rendered but not inserted into the buffer
+                Expect(AppGridContains("fn main() {}")),
+                Expect(CurrentComponentContent("fn main")),
+                App(HandleKeyEvent(key!("tab"))),
+                Expect(CurrentComponentContent("fn main() {}")),
+            ])
+        })
+    }
+
+    #[test]
+    fn inline_suggestion_dismissed_by_other_keys() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("fn main".to_string())),
+                Editor(MatchLiteral("main".to_string())),
+                Editor(EnterInsertMode(Direction::End)),
+                SuggestiveEditor(InlineSuggestion("() {}".to_string())),
+                App(HandleKeyEvent(key!("x"))),
+                Expect(Not(Box::new(AppGridContains("() {}")))),
+                Expect(CurrentComponentContent("fn mainx")),
+            ])
+        })
+    }
+
     #[test]
     fn trigger_characters() -> Result<(), anyhow::Error> {
         execute_test(|s| {
@@ -874,6 +1256,7 @@ pub(crate) struct Info {
     title: String,
     content: String,
     decorations: Vec<Decoration>,
+    links: Vec<Link>,
 }
 impl Info {
     pub(crate) fn new(title: String, content: String) -> Info {
@@ -881,6 +1264,7 @@ impl Info {
             title,
             content,
             decorations: Vec::new(),
+            links: Vec::new(),
         }
     }
 
@@ -899,8 +1283,17 @@ impl Info {
         }
     }
 
+    pub(crate) fn links(&self) -> &Vec<Link> {
+        &self.links
+    }
+
+    pub(crate) fn set_links(self, links: Vec<Link>) -> Info {
+        Info { links, ..self }
+    }
+
     pub(crate) fn join(self, other: Info) -> Info {
         let separator = "=".repeat(10).to_string();
+        let links_offset = self.content.len() + separator.len() + 2;
         let content = format!("{}\n{}\n{}", self.content, separator, other.content);
         let other_decorations = other
             .decorations
@@ -912,10 +1305,19 @@ impl Info {
             .into_iter()
             .chain(other_decorations)
             .collect_vec();
+        let links = self
+            .links
+            .into_iter()
+            .chain(other.links.into_iter().map(|link| Link {
+                range: link.range.start + links_offset..link.range.end + links_offset,
+                ..link
+            }))
+            .collect_vec();
         Info {
             title: self.title.clone(),
             content,
             decorations,
+            links,
         }
     }
 
@@ -924,6 +1326,14 @@ impl Info {
     }
 }
 
+/// A link in the content of an `Info`, such as a Markdown link of hover documentation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct Link {
+    /// The byte range of the link label in the content
+    pub(crate) range: Range<usize>,
+    pub(crate) target: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct Decoration {
     selection_range: SelectionRange,
@@ -15,9 +15,10 @@ use crate::rectangle::Rectangle;
 use crate::selection::CharIndex;
 use crate::style::Style;
 use crate::test_app::*;
+use crate::transformation::Transformation;
 
 use crate::{
-    components::editor::{Direction, Mode, ViewAlignment},
+    components::editor::{Direction, JumpLabelStrategy, Mode, ViewAlignment},
     grid::StyleKey,
     position::Position,
     selection::{Filter, FilterKind, FilterMechanism, FilterTarget, SelectionMode},
@@ -203,6 +204,165 @@ fn toggle_untoggle_bookmark() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn bookmark_notes_and_signs() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo\nbar\nspam".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 4,
+            })),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(ToggleBookmark),
+            Editor(MoveSelection(Next)),
+            Editor(SetBookmarkNote("later".to_string())),
+            // Toggling other bookmarks keeps the existing notes
+            Editor(MoveSelection(Next)),
+            Editor(ToggleBookmark),
+            Editor(ToggleBookmark),
+            // Bookmarks with notes have a filled sign in the gutter
+            Expect(EditorGrid("🦀  src/main.rs\n1◇foo\n2◆bar\n3│█pam")),
+        ])
+    })
+}
+
+#[test]
+fn select_last_inserted() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(EnterInsertMode(Direction::End)),
+            Editor(Insert("xy".to_string())),
+            Editor(Backspace),
+            Editor(Insert("z".to_string())),
+            Editor(EnterNormalMode),
+            Expect(CurrentComponentContent("fooxz barxz")),
+            Editor(SelectLastInserted),
+            Expect(CurrentSelectedTexts(&["xz", "xz"])),
+            // Pasted text can be selected as well
+            Editor(CursorKeepPrimaryOnly),
+            App(SetClipboardContent {
+                copied_texts: CopiedTexts::one("spam".to_string()),
+                use_system_clipboard: false,
+            }),
+            Editor(Paste {
+                direction: Direction::End,
+                use_system_clipboard: false,
+            }),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(SelectLastInserted),
+            Expect(CurrentSelectedTexts(&["spam"])),
+        ])
+    })
+}
+
+#[test]
+fn filter_selections() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let regex = |kind, regex| {
+            Editor(FilterSelections(Filter::new(
+                kind,
+                FilterTarget::Content,
+                FilterMechanism::Regex(regex::Regex::new(regex).unwrap()),
+            )))
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("alpha beta gamma delta epsilon".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            regex(FilterKind::Keep, "a$"),
+            Expect(CurrentSelectedTexts(&["alpha", "beta", "gamma", "delta"])),
+            regex(FilterKind::Remove, "^d"),
+            Expect(CurrentSelectedTexts(&["alpha", "beta", "gamma"])),
+            App(OpenKeepEveryNthSelectionPrompt),
+            App(HandleKeyEvents(keys!("2 enter").to_vec())),
+            Expect(CurrentSelectedTexts(&["alpha", "gamma"])),
+        ])
+    })
+}
+
+#[test]
+fn split_selections() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo, bar\n\nspam, baz\n".to_string())),
+            Editor(SelectAll),
+            // An empty regex splits by newline, and the empty lines are dropped
+            App(OpenSplitSelectionsPrompt),
+            App(HandleKeyEvent(key!("enter"))),
+            Expect(CurrentSelectedTexts(&["foo, bar", "spam, baz"])),
+            Editor(SplitSelections(", ".to_string())),
+            Expect(CurrentSelectedTexts(&["foo", "bar", "spam", "baz"])),
+        ])
+    })
+}
+
+#[test]
+fn select_next_occurrence() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar foo baz foo".to_string())),
+            Editor(MatchLiteral("foo".to_string())),
+            // Skipping moves the primary selection to the next occurrence
+            Editor(SelectNextOccurrence { skip_current: true }),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            Editor(SelectNextOccurrence {
+                skip_current: false,
+            }),
+            Expect(CurrentSelectedTexts(&["foo", "foo"])),
+            // The search wraps around, skipping the selected occurrences
+            Editor(SelectNextOccurrence {
+                skip_current: false,
+            }),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("xfoo bar xfoo baz xfoo")),
+        ])
+    })
+}
+
+#[test]
+fn rotate_selections_content_and_cycle_primary_selection() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("f(alpha, b, gamma)".to_string())),
+            Editor(MatchLiteral("alpha".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(EnterMultiCursorMode),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Editor(EnterNormalMode),
+            Expect(CurrentSelectedTexts(&["alpha", "b", "gamma"])),
+            Editor(RotateSelectionsContent(Direction::End)),
+            Expect(CurrentComponentContent("f(gamma, alpha, b)")),
+            Expect(CurrentSelectedTexts(&["gamma", "alpha", "b"])),
+            Editor(RotateSelectionsContent(Direction::Start)),
+            Expect(CurrentComponentContent("f(alpha, b, gamma)")),
+            // Rotating is an edit, so it is blocked in read-only buffers
+            Editor(SetReadOnly(true)),
+            Editor(RotateSelectionsContent(Direction::End)),
+            Expect(CurrentComponentContent("f(alpha, b, gamma)")),
+            Editor(SetReadOnly(false)),
+            // The last added selection is primary, and cycling wraps around
+            Editor(CyclePrimarySelection(Direction::End)),
+            Editor(CyclePrimarySelection(Direction::Start)),
+            Editor(CyclePrimarySelection(Direction::Start)),
+            Editor(CursorKeepPrimaryOnly),
+            Expect(CurrentSelectedTexts(&["b"])),
+        ])
+    })
+}
+
 #[test]
 fn test_delete_word_short_backward_from_end_of_file() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -327,6 +487,34 @@ fn kill_line_to_start() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn insert_mode_word_movements_and_registers() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar.spam".to_string())),
+            Editor(MatchLiteral("foo".to_string())),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            Editor(MatchLiteral("bar".to_string())),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            Editor(EnterInsertMode(Direction::End)),
+            Editor(MoveWord(Direction::End)),
+            Editor(Insert("1".to_string())),
+            Expect(CurrentComponentContent("foo bar.spam1")),
+            Editor(MoveWord(Direction::Start)),
+            Editor(MoveWord(Direction::Start)),
+            Editor(InsertRegister('1')),
+            Expect(CurrentComponentContent("foo foobar.spam1")),
+            Editor(InsertRegister('0')),
+            Expect(CurrentComponentContent("foo foobarbar.spam1")),
+        ])
+    })
+}
+
 #[test]
 #[ignore = "Undo tree should be removed soon, I don't use it at all."]
 fn undo_tree() -> anyhow::Result<()> {
@@ -475,6 +663,109 @@ fn exchange_sibling_2() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn exchange_sibling_keeps_trailing_separator_in_place() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn f() { match x { A => 1, B => 2 } }".to_string(),
+            )),
+            // Every match arm of Rust except the last one includes its trailing comma
+            Editor(MatchLiteral("A => 1,".to_string())),
+            Editor(SetSelectionMode(SyntaxNodeCoarse)),
+            Expect(CurrentSelectedTexts(&["A => 1,"])),
+            Editor(EnterExchangeMode),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentComponentContent(
+                "fn f() { match x { B => 2, A => 1 } }",
+            )),
+            Expect(CurrentSelectedTexts(&["A => 1"])),
+            Editor(MoveSelection(Previous)),
+            Expect(CurrentComponentContent(
+                "fn f() { match x { A => 1, B => 2 } }",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn join_and_split_lines() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "    // hello\n    // world\nfoo(\n    a,\n)".to_string(),
+            )),
+            Editor(MatchLiteral("hello".to_string())),
+            Editor(JoinLines),
+            Expect(CurrentComponentContent(
+                "    // hello world\nfoo(\n    a,\n)",
+            )),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(SplitLine),
+            Expect(CurrentComponentContent(
+                "    // hello\n    // world\nfoo(\n    a,\n)",
+            )),
+            Expect(CurrentSelectedTexts(&["world"])),
+            Editor(MatchLiteral("foo(\n    a,\n)".to_string())),
+            Editor(JoinLines),
+            Expect(CurrentComponentContent(
+                "    // hello\n    // world\nfoo(a)",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn toggle_arguments_layout() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    foo(a, b);\n}".to_string())),
+            Editor(MatchLiteral("b".to_string())),
+            Editor(ToggleArgumentsLayout),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    foo(\n        a,\n        b\n    );\n}",
+            )),
+            Editor(ToggleArgumentsLayout),
+            Expect(CurrentComponentContent("fn main() {\n    foo(a, b);\n}")),
+        ])
+    })
+}
+
+#[test]
+fn duplicate_and_move_selection_content() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {\n    let x = 1;\n    let y = 2;\n}".to_string(),
+            )),
+            Editor(MatchLiteral("let x = 1;".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(Duplicate(Direction::End)),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let x = 1;\n    let x = 1;\n    let y = 2;\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["let x = 1;"])),
+            Editor(MoveSelectionContent(Direction::End)),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let x = 1;\n    let y = 2;\n    let x = 1;\n}",
+            )),
+            Editor(MoveSelectionContent(Direction::Start)),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let x = 1;\n    let x = 1;\n    let y = 2;\n}",
+            )),
+            Editor(SetContent("hello(x, y);".to_string())),
+            Editor(MatchLiteral("y".to_string())),
+            Editor(SetSelectionMode(SyntaxNodeCoarse)),
+            Editor(Duplicate(Direction::Start)),
+            Expect(CurrentComponentContent("hello(x, y, y);")),
+        ])
+    })
+}
+
 #[test]
 fn select_character() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -996,6 +1287,38 @@ fn highlight_change() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn scroll_keeps_cursor_row() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent((1..=20).map(|line| line.to_string()).join("\n"))),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 10,
+            })),
+            Editor(ScrollLinesDown),
+            Expect(CurrentLine("4")),
+            Expect(CurrentScrollOffset(3)),
+            // A page is the 9 visible lines minus 2 lines that stay visible
+            Editor(ScrollPageDown),
+            Expect(CurrentLine("11")),
+            Expect(CurrentScrollOffset(10)),
+            Editor(ScrollHalfPageUp),
+            Expect(CurrentLine("6")),
+            Expect(CurrentScrollOffset(5)),
+            Editor(ScrollLinesUp),
+            Expect(CurrentLine("3")),
+            Expect(CurrentScrollOffset(2)),
+            // The view stops at the top of the buffer
+            Editor(ScrollPageUp),
+            Expect(CurrentLine("1")),
+            Expect(CurrentScrollOffset(0)),
+        ])
+    })
+}
+
 #[test]
 fn scroll_page() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1128,6 +1451,42 @@ fn jump_all_selection_start_with_same_char() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn jump_label_strategies() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("who lives on sea shore".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 1,
+            })),
+            // Every word start is labelled with a distinct character
+            App(SetJumpLabelStrategy(JumpLabelStrategy::WordStarts)),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(ShowJumps {
+                use_current_selection_mode: true,
+            }),
+            Expect(JumpChars(&['a', 'b', 'c', 'd', 'e'])),
+            App(HandleKeyEvent(key!("d"))),
+            Expect(CurrentSelectedTexts(&["sea"])),
+            // The jumps are only shown after typing the first two characters of the selection
+            App(SetJumpLabelStrategy(JumpLabelStrategy::TwoChars)),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(ShowJumps {
+                use_current_selection_mode: true,
+            }),
+            Expect(JumpChars(&[])),
+            App(HandleKeyEvent(key!("s"))),
+            Expect(JumpChars(&[])),
+            App(HandleKeyEvent(key!("h"))),
+            Expect(JumpChars(&[])),
+            Expect(CurrentSelectedTexts(&["shore"])),
+        ])
+    })
+}
+
 #[test]
 fn switch_view_alignment() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1255,14 +1614,15 @@ fn main() {
             // Go to "print()" and skip the first 3 lines for rendering
             Editor(MatchLiteral("print()".to_string())),
             Editor(SetScrollOffset(3)),
+            // The bookmarked lines are marked in the gutter
             Expect(EditorGrid(
                 "
 🦀  src/main.rs
-2│fn main() {
+2◇fn main() {
 4│  let y = 2; //
 ↪│too long, wrapped
 5│  for a in b {
-6│    let z = 4;
+6◇    let z = 4;
 7│    █rint()"
                     .trim(),
             )),
@@ -1545,7 +1905,7 @@ fn main() { // too long
             Expect(EditorGrid(
                 "
 🦀  src/main.rs
-1│fn main() { // too
+1◇fn main() { // too
 ↪│ long
 2│  █et foo = 1;
 "
@@ -1946,6 +2306,31 @@ fn selection_set_history() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn selection_set_history_of_multi_cursors() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar spam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["foo", "bar", "spam"])),
+            // An accidental movement after building the selections can be undone
+            Editor(CursorKeepPrimaryOnly),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["bar"])),
+            App(ToEditor(GoBack)),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            App(ToEditor(GoBack)),
+            Expect(CurrentSelectedTexts(&["foo", "bar", "spam"])),
+            App(ToEditor(GoForward)),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            App(ToEditor(GoForward)),
+            Expect(CurrentSelectedTexts(&["bar"])),
+        ])
+    })
+}
+
 #[test]
 fn select_surround_inside_with_multiwidth_character() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -1997,6 +2382,31 @@ fn select_surround_around() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn go_to_matching_pair() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("{ f(a[0], b) }".to_string())),
+            Editor(MatchLiteral("(".to_string())),
+            Editor(GoToMatchingPair { extend: false }),
+            Expect(CurrentSelectedTexts(&[")"])),
+            Expect(CurrentSelectionMode(SelectionMode::Custom)),
+            Editor(GoToMatchingPair { extend: false }),
+            Expect(CurrentSelectedTexts(&["("])),
+            Editor(MatchLiteral("0".to_string())),
+            Editor(GoToMatchingPair { extend: false }),
+            Expect(CurrentSelectedTexts(&["]"])),
+            Editor(MatchLiteral("b".to_string())),
+            Editor(GoToMatchingPair { extend: true }),
+            Expect(CurrentSelectedTexts(&["b)"])),
+            Editor(MatchLiteral("{".to_string())),
+            Editor(GoToMatchingPair { extend: true }),
+            Expect(CurrentSelectedTexts(&["{ f(a[0], b) }"])),
+        ])
+    })
+}
+
 #[test]
 fn select_surround_inside_same_symbols() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -2244,3 +2654,254 @@ fn multi_cursor_insert() -> Result<(), anyhow::Error> {
         }
     })
 }
+
+#[test]
+fn increment_number_sequence_with_multiple_cursors() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("item-0\nitem-0\nitem-0".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(CursorAddToAllSelections),
+            Editor(IncrementNumber {
+                by: 1,
+                sequential: true,
+            }),
+            Expect(CurrentComponentContent("item-0\nitem-1\nitem-2")),
+            Editor(IncrementNumber {
+                by: 10,
+                sequential: false,
+            }),
+            Expect(CurrentComponentContent("item-10\nitem-11\nitem-12")),
+            Expect(CurrentSelectedTexts(&["10", "11", "12"])),
+        ])
+    })
+}
+
+#[test]
+fn naming_convention_transform_with_case_agnostic_search() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "let fooBar = foo_bar(FOO_BAR, FooBar);".to_string(),
+            )),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Mode(LocalSearchConfigMode::CaseAgnostic),
+                scope: Scope::Local,
+                show_config_after_enter: false,
+            }),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("foo bar".to_string()),
+                scope: Scope::Local,
+                show_config_after_enter: false,
+            }),
+            Editor(CursorAddToAllSelections),
+            App(HandleKeyEvents(keys!("! c k").to_vec())),
+            Expect(CurrentComponentContent(
+                "let foo-bar = foo-bar(foo-bar, foo-bar);",
+            )),
+            Expect(CurrentSelectedTexts(&[
+                "foo-bar", "foo-bar", "foo-bar", "foo-bar",
+            ])),
+        ])
+    })
+}
+
+#[test]
+fn sort_and_unique_lines_preserving_indentation() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("  c\n  a\n  b\n  a\nx".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(ToggleVisualMode),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["c\n  a\n  b\n  a"])),
+            Editor(Transform(Transformation::Sort {
+                numeric: false,
+                key: None,
+            })),
+            Expect(CurrentComponentContent("  a\n  a\n  b\n  c\nx")),
+            Editor(Transform(Transformation::Unique)),
+            Expect(CurrentComponentContent("  a\n  b\n  c\nx")),
+            Expect(CurrentSelectedTexts(&["  a\n  b\n  c"])),
+        ])
+    })
+}
+
+#[test]
+fn sort_reverse_and_unique_multiple_selections() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("(c, a, b, a)".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(Transform(Transformation::Sort {
+                numeric: false,
+                key: None,
+            })),
+            Expect(CurrentComponentContent("(a, a, b, c)")),
+            Editor(Transform(Transformation::Reverse)),
+            Expect(CurrentComponentContent("(c, b, a, a)")),
+            Editor(Transform(Transformation::Unique)),
+            Expect(CurrentComponentContent("(c, b, a, )")),
+        ])
+    })
+}
+
+#[test]
+fn pipe_each_selection_to_shell() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello\nworld".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(CursorAddToAllSelections),
+            Editor(PipeToShell {
+                command: "base64".to_string(),
+                per_selection: true,
+            }),
+            Expect(CurrentComponentContent("aGVsbG8=\nd29ybGQ=")),
+            Editor(PipeToShell {
+                command: "printf '%s!' {}".to_string(),
+                per_selection: true,
+            }),
+            Expect(CurrentSelectedTexts(&["aGVsbG8=!", "d29ybGQ=!"])),
+            Editor(PipeToShell {
+                command: "sort -r".to_string(),
+                per_selection: false,
+            }),
+            Expect(CurrentComponentContent("d29ybGQ=!\naGVsbG8=!")),
+        ])
+    })
+}
+
+#[test]
+fn insert_expression_result_and_shell_output_at_each_cursor() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("x\ny".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(CursorAddToAllSelections),
+            Editor(InsertExpressionResult("i * 10 + 1".to_string())),
+            Expect(CurrentComponentContent("x\n1\ny\n11")),
+            Expect(CurrentSelectedTexts(&["1", "11"])),
+            Editor(InsertShellOutput(
+                "echo \"$KI_SELECTION-$KI_CURSOR_INDEX\"".to_string(),
+            )),
+            Expect(CurrentComponentContent("x\n1\n1-0\ny\n11\n11-1")),
+        ])
+    })
+}
+
+#[test]
+fn read_only_buffer_blocks_editing() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(SetReadOnly(true)),
+            Expect(AppGridContains("[read-only]")),
+            Editor(EnterInsertMode(Direction::Start)),
+            Expect(CurrentMode(Mode::Normal)),
+            Expect(AppGridContains("This buffer is read-only")),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(Delete { backward: false }),
+            Expect(CurrentComponentContent("hello world")),
+            // Navigation is still functional
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["world"])),
+            Editor(SetReadOnly(false)),
+            Editor(Delete { backward: false }),
+            Expect(CurrentComponentContent("hello ")),
+        ])
+    })
+}
+
+#[test]
+fn format_selections() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn  main ( ) {}\nfn  f ( ) {}".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(FormatSelections),
+            // Only the selected line is formatted
+            Expect(CurrentComponentContent("fn main() {}\nfn  f ( ) {}")),
+        ])
+    })
+}
+
+#[test]
+fn sticky_column() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("abcdef\nab\nabcdef\nabcdef".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 3,
+            })),
+            Editor(SetSelectionMode(Column)),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentSelectedTexts(&["e"])),
+            // Moving across a shorter line returns to the original column
+            Editor(MoveSelection(Down)),
+            Expect(CurrentSelectedTexts(&["b"])),
+            Editor(MoveSelection(Down)),
+            Expect(CurrentSelectedTexts(&["e"])),
+            // The column is kept across page movements
+            Editor(ScrollPageUp),
+            Editor(ScrollPageUp),
+            Editor(MoveSelection(Current)),
+            Expect(CurrentSelectedTexts(&["e"])),
+            Editor(ScrollPageDown),
+            Editor(ScrollPageDown),
+            Editor(ScrollPageDown),
+            Editor(MoveSelection(Current)),
+            Expect(CurrentSelectedTexts(&["e"])),
+            // The column is kept when leaving and re-entering a selection mode
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(MoveSelection(Up)),
+            Editor(MoveSelection(Up)),
+            Expect(CurrentSelectedTexts(&["ab"])),
+            Editor(SetSelectionMode(Column)),
+            Expect(CurrentSelectedTexts(&["b"])),
+            Editor(MoveSelection(Down)),
+            Expect(CurrentSelectedTexts(&["e"])),
+            // Other movements forget the column
+            Editor(MoveSelection(Up)),
+            Editor(MoveSelection(Previous)),
+            Expect(CurrentSelectedTexts(&["a"])),
+            Editor(MoveSelection(Down)),
+            Expect(CurrentSelectedTexts(&["a"])),
+        ])
+    })
+}
+
+#[test]
+fn primary_selection_lines() -> anyhow::Result<()> {
+    let mut editor = crate::components::editor::Editor::from_text(None, "a\nb\nc\n");
+    assert_eq!(editor.primary_selection_lines()?, (0, 0));
+    // The trailing newline of the last selected line does not include the next line
+    let _ = editor.apply_dispatches(
+        &mut crate::context::Context::default(),
+        [
+            SetSelectionMode(LineFull),
+            ToggleVisualMode,
+            MoveSelection(Down),
+        ]
+        .to_vec(),
+    )?;
+    assert_eq!(editor.primary_selection_lines()?, (0, 1));
+    Ok(())
+}
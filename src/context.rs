@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use globset::Glob;
 
@@ -8,10 +8,19 @@ use shared::canonicalized_path::CanonicalizedPath;
 
 use crate::{
     app::{GlobalSearchConfigUpdate, GlobalSearchFilterGlob, LocalSearchConfigUpdate, Scope},
+    buffer::Buffer,
     clipboard::{Clipboard, CopiedTexts},
-    components::{keymap_legend::KeymapLegendSection, prompt::PromptHistoryKey},
+    components::{
+        editor::{JumpLabelStrategy, Movement},
+        keymap_legend::KeymapLegendSection,
+        prompt::PromptHistoryKey,
+    },
+    keyboard_layout::KeyboardLayout,
     list::grep::RegexConfig,
-    quickfix_list::DiagnosticSeverityRange,
+    persistence::Persistence,
+    quickfix_list::{DiagnosticSeverityRange, Location, QuickfixListItem},
+    search_toggles::SearchToggles,
+    selection_mode::CaseAgnostic,
     themes::Theme,
 };
 
@@ -20,27 +29,170 @@ pub(crate) struct Context {
     mode: Option<GlobalMode>,
     theme: Theme,
 
-    #[cfg(test)]
+    /// For highlighting code blocks in info panels, which are not backed by a file.
     highlight_configs: crate::syntax_highlight::HighlightConfigs,
     current_working_directory: CanonicalizedPath,
     local_search_config: LocalSearchConfig,
     global_search_config: GlobalSearchConfig,
-    quickfix_list_state: Option<QuickfixListState>,
+    quickfix_lists: QuickfixLists,
     contextual_keymaps: Vec<KeymapLegendSection>,
     prompt_histories: HashMap<PromptHistoryKey, IndexSet<String>>,
+    /// When true, navigating the quickfix list previews the location of each item
+    /// instead of opening it.
+    quickfix_list_preview: bool,
+    /// When true, the first lines of the syntax nodes enclosing the cursor are pinned at the top
+    /// of the editor when they are scrolled out of view.
+    sticky_context: bool,
+    /// When true, the symbols enclosing the cursor are shown in the title of the editor.
+    breadcrumbs: bool,
+    /// Show the message of the first diagnostic of each line after the line.
+    inline_diagnostics: bool,
+    /// When true, the view is centered on the cursor after large movements,
+    /// see `Editor::center_cursor_after_large_movement`.
+    auto_center: bool,
+    /// When true, the file explorer (if opened) reveals the file of the focused editor,
+    /// see `Layout::follow_path_in_explorer`.
+    explorer_auto_follow: bool,
+    /// The number of lines scrolled by `DispatchEditor::ScrollLinesDown` and `ScrollLinesUp`.
+    scroll_lines: usize,
+    keyboard_layout: KeyboardLayout,
+    jump_label_strategy: JumpLabelStrategy,
+    persistence: Persistence,
 }
 
 pub(crate) struct QuickfixListState {
+    pub(crate) title: String,
     pub(crate) source: QuickfixListSource,
     pub(crate) current_item_index: usize,
+    /// Only items matching this regex will be shown.
+    pub(crate) filter: Option<regex::Regex>,
+    /// Locations that are manually removed by the user.
+    ///
+    /// Like `items`, this is only a snapshot, because the removed locations of the current list
+    /// are stored in their respective buffers, see `Buffer::remove_quickfix_list_item`.
+    pub(crate) removed_locations: Vec<Location>,
+    /// The items of a `QuickfixListSource::Custom` list.
+    ///
+    /// Note that this is only a snapshot, it is only refreshed when the list is no longer the
+    /// current list, because the items of the current list are stored in their respective buffers
+    /// so that their ranges are updated as the buffers are edited.
+    pub(crate) items: Vec<QuickfixListItem>,
 }
 
+impl QuickfixListState {
+    pub(crate) fn new(title: String, source: QuickfixListSource) -> Self {
+        Self {
+            title,
+            source,
+            current_item_index: 0,
+            filter: None,
+            removed_locations: Vec::new(),
+            items: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_items(self, items: Vec<QuickfixListItem>) -> Self {
+        Self { items, ..self }
+    }
+
+    /// Returns true if `item` is neither removed (at one of `removed_locations`) nor filtered out.
+    pub(crate) fn shows(
+        &self,
+        item: &QuickfixListItem,
+        removed_locations: &[Location],
+        buffers: &[Rc<RefCell<Buffer>>],
+    ) -> bool {
+        !removed_locations.contains(item.location())
+            && self
+                .filter
+                .as_ref()
+                .map(|filter| item.matches(filter, buffers))
+                .unwrap_or(true)
+    }
+
+    pub(crate) fn display(&self) -> String {
+        format!(
+            "{}{}",
+            self.title,
+            self.filter
+                .as_ref()
+                .map(|filter| format!(" (filter = {})", filter.as_str()))
+                .unwrap_or_default()
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum QuickfixListSource {
     Diagnostic(DiagnosticSeverityRange),
     Bookmark,
     Custom,
 }
 
+/// A stack of quickfix lists, so that the user can go back to previous lists
+/// (e.g. from the references list back to the global search results).
+#[derive(Default)]
+pub(crate) struct QuickfixLists {
+    lists: Vec<QuickfixListState>,
+    /// `None` means no quickfix list is activated yet.
+    current: Option<usize>,
+}
+
+const QUICKFIX_LISTS_MAX_COUNT: usize = 10;
+
+const DEFAULT_SCROLL_LINES: usize = 3;
+
+impl QuickfixLists {
+    /// Lists of the same title are replaced, because they are likely to be outdated.
+    pub(crate) fn push(&mut self, state: QuickfixListState) {
+        self.lists.retain(|list| list.title != state.title);
+        self.lists.push(state);
+        if self.lists.len() > QUICKFIX_LISTS_MAX_COUNT {
+            self.lists.remove(0);
+        }
+        self.current = Some(self.lists.len() - 1);
+    }
+
+    /// Push the given state without activating it.
+    pub(crate) fn stash(&mut self, state: QuickfixListState) {
+        self.lists.insert(0, state);
+        self.current = self.current.map(|current| current + 1);
+    }
+
+    pub(crate) fn current(&self) -> Option<&QuickfixListState> {
+        self.lists.get(self.current?)
+    }
+
+    pub(crate) fn current_mut(&mut self) -> Option<&mut QuickfixListState> {
+        self.lists.get_mut(self.current?)
+    }
+
+    pub(crate) fn set_current(&mut self, index: usize) -> Option<&QuickfixListState> {
+        if index < self.lists.len() {
+            self.current = Some(index);
+        }
+        self.current()
+    }
+
+    /// Returns the index of the list after applying `movement`, without activating it.
+    pub(crate) fn index_after(&self, movement: Movement) -> Option<usize> {
+        let last = self.lists.len().checked_sub(1)?;
+        let current = self.current.unwrap_or(last);
+        Some(match movement {
+            Movement::Previous => current.saturating_sub(1),
+            Movement::Next => (current + 1).min(last),
+            Movement::First => 0,
+            Movement::Last => last,
+            Movement::Index(index) => index.min(last),
+            _ => current,
+        })
+    }
+
+    pub(crate) fn titles(&self) -> Vec<String> {
+        self.lists.iter().map(|list| list.display()).collect_vec()
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub(crate) enum GlobalMode {
     QuickfixListItem,
@@ -65,14 +217,23 @@ impl Default for Context {
             clipboard: Clipboard::new(),
             theme: Theme::default(),
             mode: None,
-            #[cfg(test)]
             highlight_configs: crate::syntax_highlight::HighlightConfigs::new(),
             current_working_directory: CanonicalizedPath::try_from(".").unwrap(),
             local_search_config: LocalSearchConfig::default(),
             global_search_config: GlobalSearchConfig::default(),
-            quickfix_list_state: Default::default(),
+            quickfix_lists: Default::default(),
             contextual_keymaps: Default::default(),
             prompt_histories: Default::default(),
+            quickfix_list_preview: false,
+            sticky_context: true,
+            breadcrumbs: false,
+            inline_diagnostics: false,
+            auto_center: false,
+            explorer_auto_follow: false,
+            scroll_lines: DEFAULT_SCROLL_LINES,
+            keyboard_layout: KeyboardLayout::default(),
+            jump_label_strategy: JumpLabelStrategy::default(),
+            persistence: Persistence::default(),
         }
     }
 }
@@ -100,6 +261,20 @@ impl Context {
         })
     }
 
+    /// Returns the content of `register`, where the registers `0` to `9` are the copied texts
+    /// from the latest to the 10th latest, and `+` is the system clipboard.
+    pub(crate) fn get_register_content(
+        &self,
+        register: char,
+    ) -> anyhow::Result<Option<CopiedTexts>> {
+        Ok(match register {
+            '+' => self.get_clipboard_content(true, 0)?,
+            _ => register
+                .to_digit(10)
+                .and_then(|n| self.clipboard.get_nth_latest(n as usize)),
+        })
+    }
+
     pub(crate) fn set_clipboard_content(
         &mut self,
         contents: CopiedTexts,
@@ -122,7 +297,6 @@ impl Context {
         Self { theme, ..self }
     }
 
-    #[cfg(test)]
     pub(crate) fn highlight(
         &mut self,
         language: shared::language::Language,
@@ -135,6 +309,10 @@ impl Context {
         &self.current_working_directory
     }
 
+    pub(crate) fn set_current_working_directory(&mut self, working_directory: CanonicalizedPath) {
+        self.current_working_directory = working_directory
+    }
+
     pub(crate) fn local_search_config(&self) -> &LocalSearchConfig {
         &self.local_search_config
     }
@@ -148,11 +326,7 @@ impl Context {
         update: LocalSearchConfigUpdate,
         scope: Scope,
     ) {
-        match scope {
-            Scope::Local => &mut self.local_search_config,
-            Scope::Global => &mut self.global_search_config.local_config,
-        }
-        .update(update)
+        self.local_search_config_mut(scope).update(update)
     }
 
     pub(crate) fn update_global_search_config(
@@ -176,10 +350,39 @@ impl Context {
                     }
                 };
             }
+            GlobalSearchConfigUpdate::SetFileExtension(extension) => {
+                self.global_search_config.file_extension = extension
+            }
+            GlobalSearchConfigUpdate::SetDirectory(directory) => {
+                self.global_search_config.directory = directory
+            }
         };
         Ok(())
     }
 
+    fn local_search_config_mut(&mut self, scope: Scope) -> &mut LocalSearchConfig {
+        match scope {
+            Scope::Local => &mut self.local_search_config,
+            Scope::Global => &mut self.global_search_config.local_config,
+        }
+    }
+
+    pub(crate) fn toggle_search_option(&mut self, scope: Scope, option: SearchOption) {
+        self.local_search_config_mut(scope).toggle(option)
+    }
+
+    /// The search toggles of every scope, see `search_toggles::persist`.
+    pub(crate) fn search_toggles(&self) -> Vec<(Scope, SearchToggles)> {
+        [Scope::Local, Scope::Global]
+            .into_iter()
+            .map(|scope| (scope, self.get_local_search_config(scope).toggles()))
+            .collect()
+    }
+
+    pub(crate) fn set_search_toggles(&mut self, scope: Scope, toggles: SearchToggles) {
+        self.local_search_config_mut(scope).set_toggles(toggles)
+    }
+
     pub(crate) fn get_local_search_config(&self, scope: Scope) -> &LocalSearchConfig {
         match scope {
             Scope::Local => &self.local_search_config,
@@ -187,24 +390,115 @@ impl Context {
         }
     }
 
-    pub(crate) fn quickfix_list_state(&self) -> &Option<QuickfixListState> {
-        &self.quickfix_list_state
+    pub(crate) fn quickfix_list_state(&self) -> Option<&QuickfixListState> {
+        self.quickfix_lists.current()
+    }
+
+    pub(crate) fn quickfix_list_state_mut(&mut self) -> Option<&mut QuickfixListState> {
+        self.quickfix_lists.current_mut()
+    }
+
+    pub(crate) fn quickfix_lists(&self) -> &QuickfixLists {
+        &self.quickfix_lists
+    }
+
+    pub(crate) fn quickfix_lists_mut(&mut self) -> &mut QuickfixLists {
+        &mut self.quickfix_lists
     }
 
     pub(crate) fn set_quickfix_list_current_item_index(&mut self, current_item_index: usize) {
-        if let Some(state) = self.quickfix_list_state.take() {
-            self.quickfix_list_state = Some(QuickfixListState {
-                current_item_index,
-                ..state
-            })
+        if let Some(state) = self.quickfix_lists.current_mut() {
+            state.current_item_index = current_item_index
         }
     }
 
-    pub(crate) fn set_quickfix_list_source(&mut self, source: QuickfixListSource) {
-        self.quickfix_list_state = Some(QuickfixListState {
-            source,
-            current_item_index: 0,
-        })
+    pub(crate) fn quickfix_list_preview(&self) -> bool {
+        self.quickfix_list_preview
+    }
+
+    pub(crate) fn toggle_quickfix_list_preview(&mut self) {
+        self.quickfix_list_preview = !self.quickfix_list_preview
+    }
+
+    pub(crate) fn sticky_context(&self) -> bool {
+        self.sticky_context
+    }
+
+    pub(crate) fn set_sticky_context(&mut self, sticky_context: bool) {
+        self.sticky_context = sticky_context
+    }
+
+    pub(crate) fn auto_center(&self) -> bool {
+        self.auto_center
+    }
+
+    pub(crate) fn set_auto_center(&mut self, auto_center: bool) {
+        self.auto_center = auto_center
+    }
+
+    pub(crate) fn explorer_auto_follow(&self) -> bool {
+        self.explorer_auto_follow
+    }
+
+    pub(crate) fn set_explorer_auto_follow(&mut self, explorer_auto_follow: bool) {
+        self.explorer_auto_follow = explorer_auto_follow
+    }
+
+    pub(crate) fn scroll_lines(&self) -> usize {
+        self.scroll_lines
+    }
+
+    pub(crate) fn set_scroll_lines(&mut self, scroll_lines: usize) {
+        self.scroll_lines = scroll_lines
+    }
+
+    pub(crate) fn breadcrumbs(&self) -> bool {
+        self.breadcrumbs
+    }
+
+    pub(crate) fn set_breadcrumbs(&mut self, breadcrumbs: bool) {
+        self.breadcrumbs = breadcrumbs
+    }
+
+    pub(crate) fn inline_diagnostics(&self) -> bool {
+        self.inline_diagnostics
+    }
+
+    pub(crate) fn set_inline_diagnostics(&mut self, inline_diagnostics: bool) {
+        self.inline_diagnostics = inline_diagnostics
+    }
+
+    pub(crate) fn jump_label_strategy(&self) -> JumpLabelStrategy {
+        self.jump_label_strategy
+    }
+
+    pub(crate) fn set_jump_label_strategy(&mut self, jump_label_strategy: JumpLabelStrategy) {
+        self.jump_label_strategy = jump_label_strategy
+    }
+
+    pub(crate) fn persistence(&self) -> &Persistence {
+        &self.persistence
+    }
+
+    pub(crate) fn set_persistence(&mut self, persistence: Persistence) {
+        self.persistence = persistence
+    }
+
+    /// The characters labelling the jumps, see `KeyboardLayout::jump_characters`.
+    pub(crate) fn jump_characters(&self) -> Vec<char> {
+        self.keyboard_layout.jump_characters()
+    }
+
+    pub(crate) fn keyboard_layout(&self) -> &KeyboardLayout {
+        &self.keyboard_layout
+    }
+
+    pub(crate) fn set_keyboard_layout(&mut self, keyboard_layout: KeyboardLayout) {
+        self.keyboard_layout = keyboard_layout
+    }
+
+    pub(crate) fn push_quickfix_list(&mut self, state: QuickfixListState) {
+        self.quickfix_lists.push(state)
     }
 
     pub(crate) fn contextual_keymaps(&self) -> Vec<KeymapLegendSection> {
@@ -220,6 +514,9 @@ impl Context {
             map.shift_remove(&line);
             let inserted = map.insert(line);
             debug_assert!(inserted);
+            if map.len() > crate::prompt_history::MAX_ENTRIES {
+                map.shift_remove_index(0);
+            }
         } else {
             self.prompt_histories.insert(key, {
                 let mut set = IndexSet::new();
@@ -229,6 +526,17 @@ impl Context {
         }
     }
 
+    pub(crate) fn prompt_histories(&self) -> &HashMap<PromptHistoryKey, IndexSet<String>> {
+        &self.prompt_histories
+    }
+
+    pub(crate) fn set_prompt_histories(
+        &mut self,
+        prompt_histories: HashMap<PromptHistoryKey, IndexSet<String>>,
+    ) {
+        self.prompt_histories = prompt_histories
+    }
+
     pub(crate) fn get_prompt_history(
         &mut self,
         key: PromptHistoryKey,
@@ -250,6 +558,10 @@ impl Context {
 pub(crate) struct GlobalSearchConfig {
     include_glob: Option<Glob>,
     exclude_glob: Option<Glob>,
+    /// Restricts the search to files of this extension.
+    file_extension: Option<String>,
+    /// Restricts the search to this directory, relative to the working directory.
+    directory: Option<String>,
     local_config: LocalSearchConfig,
 }
 impl GlobalSearchConfig {
@@ -272,9 +584,31 @@ impl GlobalSearchConfig {
     pub(crate) fn exclude_glob(&self) -> Option<Glob> {
         self.exclude_glob.clone()
     }
+
+    pub(crate) fn file_extension(&self) -> Option<&String> {
+        self.file_extension.as_ref()
+    }
+
+    pub(crate) fn directory(&self) -> Option<&String> {
+        self.directory.as_ref()
+    }
+
+    /// The globs of `file_extension` and `directory`, which every searched file must match.
+    pub(crate) fn scope_globs(&self) -> anyhow::Result<Vec<Glob>> {
+        self.file_extension
+            .iter()
+            .map(|extension| format!("**/*.{}", extension))
+            .chain(
+                self.directory
+                    .iter()
+                    .map(|directory| format!("{}/**", directory)),
+            )
+            .map(|glob| Ok(Glob::new(&glob)?))
+            .collect()
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, serde::Serialize, serde::Deserialize)]
 pub(crate) enum LocalSearchConfigMode {
     Regex(RegexConfig),
     AstGrep,
@@ -283,7 +617,7 @@ pub(crate) enum LocalSearchConfigMode {
 impl LocalSearchConfigMode {
     pub(crate) fn display(&self) -> String {
         match self {
-            LocalSearchConfigMode::Regex(regex) => regex.display(),
+            LocalSearchConfigMode::Regex(regex) => regex.display(false, false),
 
             LocalSearchConfigMode::AstGrep => "AST Grep".to_string(),
             LocalSearchConfigMode::CaseAgnostic => "Case Agnostic".to_string(),
@@ -298,13 +632,15 @@ impl Default for LocalSearchConfigMode {
 }
 
 impl RegexConfig {
-    fn display(&self) -> String {
+    fn display(&self, smart_case: bool, preserve_case: bool) -> String {
         format!(
             "{}{}",
             if self.escaped { "Literal" } else { "Regex" },
             parenthesize(
                 [
                     self.case_sensitive.then_some("Case-sensitive".to_string()),
+                    (smart_case && !self.case_sensitive).then_some("Smart-case".to_string()),
+                    preserve_case.then_some("Preserve case".to_string()),
                     self.match_whole_word
                         .then_some("Match whole word".to_string()),
                 ]
@@ -327,15 +663,32 @@ fn parenthesize(values: Vec<String>) -> String {
 #[derive(Default, Clone, Debug, PartialEq, Eq)]
 pub(crate) struct LocalSearchConfig {
     pub(crate) mode: LocalSearchConfigMode,
+    /// When true, a regex search is case-sensitive if the search contains uppercase characters,
+    /// see `effective_mode`.
+    smart_case: bool,
+    /// When true, each replacement of a regex search follows the case of the text it replaces,
+    /// see `replace_regex`.
+    preserve_case: bool,
     search: Option<String>,
     replacement: Option<String>,
 }
 
+/// The toggles of a regex search, which can be toggled in the search prompt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SearchOption {
+    CaseSensitive,
+    MatchWholeWord,
+    SmartCase,
+    PreserveCase,
+}
+
 impl LocalSearchConfig {
     #[cfg(test)]
     pub(crate) fn new(mode: LocalSearchConfigMode) -> Self {
         Self {
             mode,
+            smart_case: false,
+            preserve_case: false,
             search: Default::default(),
             replacement: Default::default(),
         }
@@ -344,6 +697,10 @@ impl LocalSearchConfig {
     fn update(&mut self, update: LocalSearchConfigUpdate) {
         match update {
             LocalSearchConfigUpdate::Mode(mode) => self.mode = mode,
+            LocalSearchConfigUpdate::SmartCase(smart_case) => self.smart_case = smart_case,
+            LocalSearchConfigUpdate::PreserveCase(preserve_case) => {
+                self.set_preserve_case(preserve_case);
+            }
             LocalSearchConfigUpdate::Replacement(replacement) => {
                 self.set_replacment(replacement);
             }
@@ -368,10 +725,111 @@ impl LocalSearchConfig {
     }
 
     pub(crate) fn last_search(&self) -> Option<Search> {
-        self.search.clone().map(|search| Search {
-            search,
+        let mode = self.effective_mode();
+        self.search.clone().map(|search| Search { search, mode })
+    }
+
+    /// The mode used for searching, which is made case-sensitive
+    /// if smart case is enabled and the search contains uppercase characters.
+    pub(crate) fn effective_mode(&self) -> LocalSearchConfigMode {
+        match self.mode {
+            LocalSearchConfigMode::Regex(regex)
+                if self.smart_case && self.search().chars().any(char::is_uppercase) =>
+            {
+                LocalSearchConfigMode::Regex(RegexConfig {
+                    case_sensitive: true,
+                    ..regex
+                })
+            }
+            mode => mode,
+        }
+    }
+
+    /// Like `LocalSearchConfigMode::display`, but shows whether smart case is enabled.
+    pub(crate) fn display_mode(&self) -> String {
+        match self.mode {
+            LocalSearchConfigMode::Regex(regex) => {
+                regex.display(self.smart_case, self.preserve_case)
+            }
+            mode => mode.display(),
+        }
+    }
+
+    pub(crate) fn smart_case(&self) -> bool {
+        self.smart_case
+    }
+
+    pub(crate) fn preserve_case(&self) -> bool {
+        self.preserve_case
+    }
+
+    pub(crate) fn set_preserve_case(&mut self, preserve_case: bool) -> &mut Self {
+        self.preserve_case = preserve_case;
+        self
+    }
+
+    /// Replaces the matches of `regex`, which is the regex of this search, in `haystack`,
+    /// or only the first match if `first_only` is true.
+    ///
+    /// Unless the search is literal, `$1` and `${name}` in the replacement
+    /// are expanded to the corresponding capture groups of each match.
+    /// If `preserve_case` is enabled, the case of each replacement follows the case of its match,
+    /// so that replacing `foo_bar` with `BazQux` gives `baz_qux`.
+    pub(crate) fn replace_regex(
+        &self,
+        regex: &regex::Regex,
+        escaped: bool,
+        haystack: &str,
+        first_only: bool,
+    ) -> String {
+        let replacement = self.replacement();
+        let replace = |captures: &regex::Captures| {
+            let mut expanded = String::new();
+            if escaped {
+                expanded.push_str(&replacement)
+            } else {
+                captures.expand(&replacement, &mut expanded)
+            }
+            if self.preserve_case {
+                CaseAgnostic::replace(&captures[0], "", &expanded).unwrap_or(expanded)
+            } else {
+                expanded
+            }
+        };
+        if first_only {
+            regex.replacen(haystack, 1, replace).to_string()
+        } else {
+            regex.replace_all(haystack, replace).to_string()
+        }
+    }
+
+    pub(crate) fn toggles(&self) -> SearchToggles {
+        SearchToggles {
             mode: self.mode,
-        })
+            smart_case: self.smart_case,
+            preserve_case: self.preserve_case,
+        }
+    }
+
+    fn set_toggles(&mut self, toggles: SearchToggles) {
+        self.mode = toggles.mode;
+        self.smart_case = toggles.smart_case;
+        self.preserve_case = toggles.preserve_case;
+    }
+
+    /// Options of a regex search are ignored by the other modes, except smart case and preserve case.
+    fn toggle(&mut self, option: SearchOption) {
+        match (option, &mut self.mode) {
+            (SearchOption::SmartCase, _) => self.smart_case = !self.smart_case,
+            (SearchOption::PreserveCase, _) => self.preserve_case = !self.preserve_case,
+            (SearchOption::CaseSensitive, LocalSearchConfigMode::Regex(regex)) => {
+                regex.case_sensitive = !regex.case_sensitive
+            }
+            (SearchOption::MatchWholeWord, LocalSearchConfigMode::Regex(regex)) => {
+                regex.match_whole_word = !regex.match_whole_word
+            }
+            _ => {}
+        }
     }
 
     pub(crate) fn replacement(&self) -> String {
@@ -0,0 +1,231 @@
+use std::{
+    ops::Range,
+    process::{Command, Stdio},
+    sync::mpsc::Sender,
+};
+
+use lsp_types::DiagnosticSeverity;
+use regex::Regex;
+
+use crate::app::AppMessage;
+
+/// Parses diagnostics from the output of build commands, like the `errorformat` of Vim.
+///
+/// The regex of a format is matched against the whole output, and should capture the named groups
+/// `file`, `line` and `message`, and optionally `column` and `severity`.
+/// `line` and `column` are 1-based.
+#[derive(Debug, Clone)]
+pub(crate) struct ErrorFormat {
+    regex: Regex,
+}
+
+impl ErrorFormat {
+    pub(crate) fn new(regex: &str) -> anyhow::Result<Self> {
+        let regex = Regex::new(regex)?;
+        if let Some(name) = ["file", "line", "message"]
+            .into_iter()
+            .find(|name| !regex.capture_names().flatten().any(|group| group == *name))
+        {
+            return Err(anyhow::anyhow!(
+                "The error format {:?} does not capture the group {:?}",
+                regex.as_str(),
+                name
+            ));
+        }
+        Ok(Self { regex })
+    }
+
+    /// The formats of `rustc` (including `cargo`), `tsc`, and `gcc`,
+    /// which is also the format of many other compilers and linters.
+    pub(crate) fn builtins() -> Vec<ErrorFormat> {
+        [
+            // error[E0425]: cannot find value `x` in this scope
+            //   --> src/main.rs:2:5
+            r"(?m)^(?P<severity>error|warning)(?:\[\w+\])?: (?P<message>.+)\n\s*--> (?P<file>[^:\n]+):(?P<line>\d+):(?P<column>\d+)",
+            // src/main.ts(2,5): error TS2304: Cannot find name 'x'.
+            r"(?m)^(?P<file>[^\s(][^(\n]*)\((?P<line>\d+),(?P<column>\d+)\): (?P<severity>error|warning) (?P<message>.+)$",
+            // src/main.ts:2:5 - error TS2304: Cannot find name 'x'.
+            r"(?m)^(?P<file>[^\s:][^:\n]*):(?P<line>\d+):(?P<column>\d+) - (?P<severity>error|warning) (?P<message>.+)$",
+            // main.c:2:5: error: 'x' undeclared
+            r"(?m)^(?P<file>[^\s:][^:\n]*):(?P<line>\d+):(?:(?P<column>\d+):)? (?P<severity>fatal error|error|warning|note|info): (?P<message>.+)$",
+        ]
+        .into_iter()
+        .map(|regex| ErrorFormat::new(regex).unwrap())
+        .collect()
+    }
+}
+
+/// Returns the diagnostics in `output` with the paths of their files as written in `output`.
+///
+/// The formats are tried in order, and matches overlapping the match of a previous format are ignored.
+pub(crate) fn parse(formats: &[ErrorFormat], output: &str) -> Vec<(String, lsp_types::Diagnostic)> {
+    let mut matched_ranges: Vec<Range<usize>> = Vec::new();
+    let mut diagnostics = Vec::new();
+    for format in formats {
+        for captures in format.regex.captures_iter(output) {
+            let match_range = captures.get(0).map(|m| m.range()).unwrap_or_default();
+            if matched_ranges
+                .iter()
+                .any(|matched| matched.start < match_range.end && match_range.start < matched.end)
+            {
+                continue;
+            }
+            let group = |name| captures.name(name).map(|m| m.as_str());
+            let (Some(file), Some(Ok(line)), Some(message)) = (
+                group("file"),
+                group("line").map(str::parse::<u32>),
+                group("message"),
+            ) else {
+                continue;
+            };
+            let line = line.saturating_sub(1);
+            let range = match group("column").and_then(|column| column.parse::<u32>().ok()) {
+                Some(column) => lsp_types::Range::new(
+                    lsp_types::Position::new(line, column.saturating_sub(1)),
+                    lsp_types::Position::new(line, column),
+                ),
+                None => lsp_types::Range::new(
+                    lsp_types::Position::new(line, 0),
+                    lsp_types::Position::new(line + 1, 0),
+                ),
+            };
+            let severity = group("severity").map(|severity| match severity {
+                "warning" => DiagnosticSeverity::WARNING,
+                "note" | "info" => DiagnosticSeverity::INFORMATION,
+                _ => DiagnosticSeverity::ERROR,
+            });
+            matched_ranges.push(match_range);
+            diagnostics.push((
+                file.trim().to_string(),
+                lsp_types::Diagnostic {
+                    range,
+                    severity,
+                    message: message.trim().to_string(),
+                    source: Some("build".to_string()),
+                    ..Default::default()
+                },
+            ));
+        }
+    }
+    diagnostics.sort_by_key(|(file, diagnostic)| {
+        (
+            file.clone(),
+            diagnostic.range.start.line,
+            diagnostic.range.start.character,
+        )
+    });
+    diagnostics
+}
+
+/// Runs `command` with `sh` in another thread,
+/// and sends its output back as `AppMessage::BuildOutput` when it exits.
+pub(crate) fn run(command: &str, sender: Sender<AppMessage>) -> anyhow::Result<()> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    std::thread::spawn(move || {
+        let output = match child.wait_with_output() {
+            Ok(output) => format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+            Err(error) => error.to_string(),
+        };
+        let _ = sender.send(AppMessage::BuildOutput(output));
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_error_format {
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn parse_builtins(output: &str) -> Vec<(String, u32, u32, DiagnosticSeverity, String)> {
+        parse(&ErrorFormat::builtins(), output)
+            .into_iter()
+            .map(|(file, diagnostic)| {
+                (
+                    file,
+                    diagnostic.range.start.line,
+                    diagnostic.range.start.character,
+                    diagnostic.severity.unwrap(),
+                    diagnostic.message,
+                )
+            })
+            .collect_vec()
+    }
+
+    #[test]
+    fn rustc() {
+        let output = "
+   Compiling ki v0.1.0
+error[E0425]: cannot find value `x` in this scope
+ --> src/main.rs:2:5
+  |
+2 |     x
+  |     ^ not found in this scope
+
+warning: unused variable: `y`
+  --> src/lib.rs:10:9
+";
+        assert_eq!(
+            parse_builtins(output),
+            [
+                (
+                    "src/lib.rs".to_string(),
+                    9,
+                    8,
+                    DiagnosticSeverity::WARNING,
+                    "unused variable: `y`".to_string()
+                ),
+                (
+                    "src/main.rs".to_string(),
+                    1,
+                    4,
+                    DiagnosticSeverity::ERROR,
+                    "cannot find value `x` in this scope".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tsc_and_gcc() {
+        let output = "
+src/a.ts(3,7): error TS2322: Type 'string' is not assignable to type 'number'.
+src/b.ts:1:1 - error TS2304: Cannot find name 'x'.
+main.c:4:3: warning: unused variable 'y'
+main.c:9: error: expected ';'
+";
+        assert_eq!(
+            parse_builtins(output)
+                .into_iter()
+                .map(|(file, line, column, severity, _)| (file, line, column, severity))
+                .collect_vec(),
+            [
+                ("main.c".to_string(), 3, 2, DiagnosticSeverity::WARNING),
+                ("main.c".to_string(), 8, 0, DiagnosticSeverity::ERROR),
+                ("src/a.ts".to_string(), 2, 6, DiagnosticSeverity::ERROR),
+                ("src/b.ts".to_string(), 0, 0, DiagnosticSeverity::ERROR),
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_format() -> anyhow::Result<()> {
+        let format = ErrorFormat::new(r"(?m)^(?P<file>\S+) line (?P<line>\d+): (?P<message>.+)$")?;
+        let diagnostics = parse(&[format], "x.py line 3: oops");
+        assert_eq!(diagnostics[0].0, "x.py");
+        assert_eq!(diagnostics[0].1.range.start, lsp_types::Position::new(2, 0));
+        assert_eq!(diagnostics[0].1.severity, None);
+        assert!(ErrorFormat::new(r"(?P<file>\S+)").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use std::path::Path;
+
+use itertools::Itertools;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    buffer::Buffer,
+    char_index_range::CharIndexRange,
+    components::editor_keymap_printer::html_escape,
+    grid::{Cell, CellLineStyle, CellUpdate},
+    selection::CharIndex,
+    syntax_highlight::HighlightConfigs,
+    themes::{Color, Theme},
+};
+
+/// The format of a buffer rendered with its syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    /// A standalone HTML document.
+    Html,
+    /// Text with 24-bit color escape sequences, which can be printed to a terminal.
+    Ansi,
+}
+
+impl ExportFormat {
+    /// HTML if the extension of `path` is `html` or `htm`, otherwise ANSI.
+    pub(crate) fn from_path(path: &Path) -> ExportFormat {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("html" | "htm") => ExportFormat::Html,
+            _ => ExportFormat::Ansi,
+        }
+    }
+}
+
+/// Renders the content of `buffer` within `range` with its syntax highlighting.
+pub(crate) fn export(
+    buffer: &Buffer,
+    range: CharIndexRange,
+    theme: &Theme,
+    format: ExportFormat,
+) -> anyhow::Result<String> {
+    let lines = styled_lines(buffer, range, theme)?;
+    Ok(match format {
+        ExportFormat::Html => to_html(&lines, theme),
+        ExportFormat::Ansi => to_ansi(&lines),
+    })
+}
+
+/// Renders the whole file of `path` using the default theme, for printing outside of the editor.
+pub(crate) fn export_file(
+    path: &CanonicalizedPath,
+    format: ExportFormat,
+) -> anyhow::Result<String> {
+    let mut buffer = Buffer::from_path(path, true)?;
+    if let Some(language) = buffer.language() {
+        let spans = HighlightConfigs::new().highlight(language, &buffer.content())?;
+        buffer.update_highlighted_spans(spans);
+    }
+    let range = (CharIndex(0)..CharIndex(buffer.len_chars())).into();
+    export(&buffer, range, &Theme::default(), format)
+}
+
+/// Returns the cells of each line within `range`, styled the same way as the
+/// syntax highlighting of the editor.
+fn styled_lines(
+    buffer: &Buffer,
+    range: CharIndexRange,
+    theme: &Theme,
+) -> anyhow::Result<Vec<Vec<Cell>>> {
+    let text = buffer.slice(&range)?.to_string();
+    let default_cell = Cell {
+        foreground_color: theme.ui.text_foreground,
+        background_color: theme.ui.background_color,
+        ..Default::default()
+    };
+    let mut cells = text
+        .chars()
+        .map(|char| Cell {
+            symbol: char.to_string(),
+            ..default_cell.clone()
+        })
+        .collect_vec();
+    let byte_range = buffer.char_to_byte(range.start)?..buffer.char_to_byte(range.end)?;
+    for span in buffer.highlighted_spans() {
+        let start = span.byte_range.start.max(byte_range.start);
+        let end = span.byte_range.end.min(byte_range.end);
+        if start >= end {
+            continue;
+        }
+        let style = theme.get_style(&span.style_key);
+        for char_index in buffer.byte_range_to_char_index_range(&(start..end))?.iter() {
+            if let Some(cell) = cells.get_mut(char_index.0 - range.start.0) {
+                *cell = cell.apply_update(CellUpdate {
+                    style,
+                    source: Some(span.style_key.clone()),
+                    ..Default::default()
+                })
+            }
+        }
+    }
+    Ok(cells
+        .split(|cell| cell.symbol == "\n")
+        .map(|line| line.to_vec())
+        .collect_vec())
+}
+
+/// Two cells of the same run share every attribute except their symbol.
+fn same_style(a: &Cell, b: &Cell) -> bool {
+    a.foreground_color == b.foreground_color
+        && a.background_color == b.background_color
+        && a.line == b.line
+        && a.is_bold == b.is_bold
+        && a.is_italic == b.is_italic
+}
+
+/// Groups consecutive cells of the same style, so that each group is styled only once.
+fn runs(line: &[Cell]) -> Vec<(&Cell, String)> {
+    let mut runs: Vec<(&Cell, String)> = Vec::new();
+    for cell in line {
+        match runs.last_mut() {
+            Some((first, text)) if same_style(first, cell) => text.push_str(&cell.symbol),
+            _ => runs.push((cell, cell.symbol.clone())),
+        }
+    }
+    runs
+}
+
+fn to_html(lines: &[Vec<Cell>], theme: &Theme) -> String {
+    let body = lines
+        .iter()
+        .map(|line| {
+            runs(line)
+                .into_iter()
+                .map(|(cell, text)| {
+                    format!(
+                        "<span style=\"{}\">{}</span>",
+                        html_style(cell),
+                        html_escape(&text)
+                    )
+                })
+                .join("")
+        })
+        .join("\n");
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Ki export</title>\n</head>\n<body style=\"background-color:{}\">\n<pre style=\"color:{}\">{body}</pre>\n</body>\n</html>\n",
+        theme.ui.background_color.to_hex(),
+        theme.ui.text_foreground.to_hex(),
+    )
+}
+
+fn html_style(cell: &Cell) -> String {
+    [
+        Some(format!("color:{}", cell.foreground_color.to_hex())),
+        Some(format!(
+            "background-color:{}",
+            cell.background_color.to_hex()
+        )),
+        cell.is_bold.then(|| "font-weight:bold".to_string()),
+        cell.is_italic.then(|| "font-style:italic".to_string()),
+        cell.line.map(|line| {
+            let style = match line.style {
+                CellLineStyle::Undercurl => "wavy",
+                CellLineStyle::Underline => "solid",
+            };
+            format!(
+                "text-decoration:underline {} {}",
+                style,
+                line.color.to_hex()
+            )
+        }),
+    ]
+    .into_iter()
+    .flatten()
+    .join(";")
+}
+
+fn to_ansi(lines: &[Vec<Cell>]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let text = runs(line)
+                .into_iter()
+                .map(|(cell, text)| format!("{}{}", ansi_style(cell), text))
+                .join("");
+            format!("{}\x1b[0m", text)
+        })
+        .join("\n")
+}
+
+fn ansi_style(cell: &Cell) -> String {
+    let rgb = |color: Color| {
+        let (r, g, b) = color.to_rgb();
+        format!("{};{};{}", r, g, b)
+    };
+    let mut style = format!(
+        "\x1b[0m\x1b[38;2;{}m\x1b[48;2;{}m",
+        rgb(cell.foreground_color),
+        rgb(cell.background_color)
+    );
+    if cell.is_bold {
+        style.push_str("\x1b[1m")
+    }
+    if cell.is_italic {
+        style.push_str("\x1b[3m")
+    }
+    if let Some(line) = cell.line {
+        let underline = match line.style {
+            CellLineStyle::Undercurl => "\x1b[4:3m",
+            CellLineStyle::Underline => "\x1b[4m",
+        };
+        style.push_str(underline);
+        style.push_str(&format!("\x1b[58;2;{}m", rgb(line.color)))
+    }
+    style
+}
+
+#[cfg(test)]
+mod test_export {
+    use my_proc_macros::hex;
+
+    use crate::{
+        buffer::Buffer,
+        char_index_range::CharIndexRange,
+        grid::StyleKey,
+        selection::CharIndex,
+        style::fg,
+        syntax_highlight::{HighlighedSpan, HighlighedSpans},
+        themes::{HighlightName, SyntaxStyles, Theme},
+    };
+
+    use super::{export, ExportFormat};
+
+    fn buffer() -> Buffer {
+        let mut buffer = Buffer::new(None, "fn <a>\nb");
+        buffer.update_highlighted_spans(HighlighedSpans(
+            [HighlighedSpan {
+                byte_range: 0..2,
+                style_key: StyleKey::Syntax("keyword".to_string()),
+            }]
+            .to_vec(),
+        ));
+        buffer
+    }
+
+    fn theme() -> Theme {
+        let mut theme = Theme::default();
+        theme.ui.text_foreground = hex!("#000000");
+        theme.ui.background_color = hex!("#ffffff");
+        theme.syntax = SyntaxStyles::new(&[(HighlightName::Keyword, fg(hex!("#ff0000")))]);
+        theme
+    }
+
+    fn whole(buffer: &Buffer) -> CharIndexRange {
+        (CharIndex(0)..CharIndex(buffer.len_chars())).into()
+    }
+
+    #[test]
+    fn format_from_path() {
+        use std::path::Path;
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.html")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.htm")),
+            ExportFormat::Html
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("a.txt")),
+            ExportFormat::Ansi
+        );
+    }
+
+    #[test]
+    fn html() -> anyhow::Result<()> {
+        let buffer = buffer();
+        let html = export(&buffer, whole(&buffer), &theme(), ExportFormat::Html)?;
+        assert!(html.contains(
+            "<span style=\"color:#ff0000;background-color:#ffffff\">fn</span><span style=\"color:#000000;background-color:#ffffff\"> &lt;a&gt;</span>\n<span style=\"color:#000000;background-color:#ffffff\">b</span>"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn ansi() -> anyhow::Result<()> {
+        let buffer = buffer();
+        let ansi = export(&buffer, whole(&buffer), &theme(), ExportFormat::Ansi)?;
+        assert_eq!(
+            ansi,
+            [
+                "\x1b[0m\x1b[38;2;255;0;0m\x1b[48;2;255;255;255mfn\x1b[0m\x1b[38;2;0;0;0m\x1b[48;2;255;255;255m <a>\x1b[0m",
+                "\x1b[0m\x1b[38;2;0;0;0m\x1b[48;2;255;255;255mb\x1b[0m"
+            ]
+            .join("\n")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_given_range_is_exported() -> anyhow::Result<()> {
+        let buffer = buffer();
+        let ansi = export(
+            &buffer,
+            (CharIndex(1)..CharIndex(4)).into(),
+            &theme(),
+            ExportFormat::Ansi,
+        )?;
+        assert_eq!(
+            ansi,
+            "\x1b[0m\x1b[38;2;255;0;0m\x1b[48;2;255;255;255mn\x1b[0m\x1b[38;2;0;0;0m\x1b[48;2;255;255;255m <\x1b[0m"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+/// Evaluates an arithmetic expression, such as `(1 + 2) * 3 ^ 2 / 4`.
+///
+/// Supported are the operators `+ - * / % ^` (`^` is right-associative),
+/// parentheses, the constants `pi` and `e`, the functions `abs ceil floor round sqrt`,
+/// and the variables defined in `variables`.
+pub(crate) fn evaluate(expression: &str, variables: &[(&str, f64)]) -> anyhow::Result<f64> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        position: 0,
+        variables,
+        depth: 0,
+    };
+    let result = parser.expression()?;
+    match parser.tokens.get(parser.position) {
+        Some(token) => Err(anyhow::anyhow!("Unexpected {:?}", token)),
+        None => Ok(result),
+    }
+}
+
+/// Formats `number` without a fractional part if it is an integer.
+pub(crate) fn format_number(number: f64) -> String {
+    if number.is_finite() && number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+}
+
+fn tokenize(expression: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = expression.trim_start();
+    while let Some(first) = rest.chars().next() {
+        let (token, length) =
+            if let Some(number) = lazy_regex::regex_find!(r"^(\d+\.?\d*|\.\d+)", rest) {
+                (Token::Number(number.parse()?), number.len())
+            } else if let Some(identifier) = lazy_regex::regex_find!(r"^[a-zA-Z_]\w*", rest) {
+                (Token::Identifier(identifier.to_string()), identifier.len())
+            } else if "+-*/%^()".contains(first) {
+                (Token::Operator(first), 1)
+            } else {
+                return Err(anyhow::anyhow!("Unexpected character {:?}", first));
+            };
+        tokens.push(token);
+        rest = rest[length..].trim_start();
+    }
+    Ok(tokens)
+}
+
+/// The maximum nesting of parentheses and unary operators,
+/// so that deeply nested expressions are reported instead of overflowing the stack.
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    variables: &'a [(&'a str, f64)],
+    /// The current nesting, see `MAX_DEPTH`.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn consume_operator(&mut self, operators: &[char]) -> Option<char> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                self.position += 1;
+                Some(*operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_operator(&mut self, operator: char) -> anyhow::Result<()> {
+        self.consume_operator(&[operator])
+            .map(|_| ())
+            .ok_or_else(|| anyhow::anyhow!("Expected {:?}", operator))
+    }
+
+    fn expression(&mut self) -> anyhow::Result<f64> {
+        let mut result = self.term()?;
+        while let Some(operator) = self.consume_operator(&['+', '-']) {
+            let right = self.term()?;
+            result = if operator == '+' {
+                result + right
+            } else {
+                result - right
+            };
+        }
+        Ok(result)
+    }
+
+    fn term(&mut self) -> anyhow::Result<f64> {
+        let mut result = self.unary()?;
+        while let Some(operator) = self.consume_operator(&['*', '/', '%']) {
+            let right = self.unary()?;
+            result = match operator {
+                '*' => result * right,
+                '/' => result / right,
+                _ => result % right,
+            };
+        }
+        Ok(result)
+    }
+
+    /// Every nested expression is parsed through here, so the nesting is limited here.
+    fn unary(&mut self) -> anyhow::Result<f64> {
+        if self.depth >= MAX_DEPTH {
+            return Err(anyhow::anyhow!(
+                "Expression is nested more than {} levels deep",
+                MAX_DEPTH
+            ));
+        }
+        self.depth += 1;
+        let result = match self.consume_operator(&['-', '+']) {
+            Some('-') => self.unary().map(|value| -value),
+            Some(_) => self.unary(),
+            None => self.power(),
+        };
+        self.depth -= 1;
+        result
+    }
+
+    fn power(&mut self) -> anyhow::Result<f64> {
+        let base = self.primary()?;
+        if self.consume_operator(&['^']).is_some() {
+            Ok(base.powf(self.unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> anyhow::Result<f64> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Operator('(')) => {
+                let result = self.expression()?;
+                self.expect_operator(')')?;
+                Ok(result)
+            }
+            Some(Token::Identifier(name)) => {
+                if self.consume_operator(&['(']).is_some() {
+                    let argument = self.expression()?;
+                    self.expect_operator(')')?;
+                    return match name.as_str() {
+                        "abs" => Ok(argument.abs()),
+                        "ceil" => Ok(argument.ceil()),
+                        "floor" => Ok(argument.floor()),
+                        "round" => Ok(argument.round()),
+                        "sqrt" => Ok(argument.sqrt()),
+                        _ => Err(anyhow::anyhow!("Unknown function {:?}", name)),
+                    };
+                }
+                match name.as_str() {
+                    "pi" => Ok(std::f64::consts::PI),
+                    "e" => Ok(std::f64::consts::E),
+                    _ => self
+                        .variables
+                        .iter()
+                        .find(|(variable, _)| *variable == name)
+                        .map(|(_, value)| *value)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown variable {:?}", name)),
+                }
+            }
+            Some(token) => Err(anyhow::anyhow!("Unexpected {:?}", token)),
+            None => Err(anyhow::anyhow!("Unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_expression {
+    use super::*;
+
+    fn evaluate_and_format(expression: &str) -> anyhow::Result<String> {
+        Ok(format_number(evaluate(expression, &[("i", 2.0)])?))
+    }
+
+    #[test]
+    fn precedence_and_associativity() -> anyhow::Result<()> {
+        assert_eq!(evaluate_and_format("1 + 2 * 3")?, "7");
+        assert_eq!(evaluate_and_format("(1 + 2) * 3")?, "9");
+        assert_eq!(evaluate_and_format("10 - 4 - 3")?, "3");
+        assert_eq!(evaluate_and_format("2 ^ 3 ^ 2")?, "512");
+        assert_eq!(evaluate_and_format("-2 ^ 2")?, "-4");
+        assert_eq!(evaluate_and_format("7 % 4 / 2")?, "1.5");
+        Ok(())
+    }
+
+    #[test]
+    fn functions_constants_and_variables() -> anyhow::Result<()> {
+        assert_eq!(evaluate_and_format("sqrt(16) + abs(-1)")?, "5");
+        assert_eq!(evaluate_and_format("round(pi * 100) / 100")?, "3.14");
+        assert_eq!(evaluate_and_format("i * 10 + 1")?, "21");
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        assert!(evaluate_and_format("1 +").is_err());
+        assert!(evaluate_and_format("(1 + 2").is_err());
+        assert!(evaluate_and_format("1 $ 2").is_err());
+        assert!(evaluate_and_format("x + 1").is_err());
+        assert!(evaluate_and_format("1 2").is_err());
+    }
+
+    #[test]
+    fn deeply_nested_expressions() -> anyhow::Result<()> {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(evaluate_and_format(&nested(100))?, "1");
+        for expression in [nested(100_000), format!("{}1", "-".repeat(100_000))] {
+            assert_eq!(
+                evaluate_and_format(&expression).unwrap_err().to_string(),
+                "Expression is nested more than 256 levels deep"
+            );
+        }
+        Ok(())
+    }
+}
@@ -6,6 +6,11 @@ pub(crate) struct Crossterm {
     stdout: std::io::Stdout,
     /// Used for diffing to reduce unnecessary re-painting.
     previous_screen: Screen,
+    color_support: ColorSupport,
+    /// Whether the kitty keyboard protocol is enabled.
+    keyboard_enhancement_enabled: bool,
+    /// Whether the original title of the terminal is saved, so that it can be restored on leaving.
+    title_saved: bool,
 }
 
 impl Crossterm {
@@ -13,8 +18,142 @@ impl Crossterm {
         Crossterm {
             stdout: std::io::stdout(),
             previous_screen: Screen::default(),
+            color_support: ColorSupport::detect(),
+            keyboard_enhancement_enabled: false,
+            title_saved: false,
         }
     }
+
+    /// Overrides the detected color support, see `ColorSupport::detect`.
+    pub(crate) fn set_color_support(&mut self, color_support: ColorSupport) {
+        self.color_support = color_support
+    }
+}
+
+/// The colors that can be displayed by the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+/// The RGB values of the 16 ANSI colors, as defined by xterm.
+const ANSI_16_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+impl std::str::FromStr for ColorSupport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" => Ok(ColorSupport::TrueColor),
+            "256" => Ok(ColorSupport::Ansi256),
+            "16" => Ok(ColorSupport::Ansi16),
+            _ => Err(anyhow::anyhow!("Unknown color support {:?}", s)),
+        }
+    }
+}
+
+/// The levels of each channel of the 6x6x6 color cube of the 256-color palette.
+const ANSI_256_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorSupport {
+    pub(crate) fn detect() -> ColorSupport {
+        Self::from_env(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(colorterm: Option<&str>, term: Option<&str>) -> ColorSupport {
+        if matches!(colorterm, Some("truecolor" | "24bit")) {
+            return ColorSupport::TrueColor;
+        }
+        match term {
+            // Terminals without `TERM` (e.g. Windows Terminal) are assumed to support true color
+            None => ColorSupport::TrueColor,
+            Some(term) if term.contains("truecolor") || term.contains("direct") => {
+                ColorSupport::TrueColor
+            }
+            Some(term) if term.contains("256") => ColorSupport::Ansi256,
+            // Only the terminals known to be limited to 16 colors are downgraded,
+            // since most terminals reporting a plain `TERM` (e.g. `xterm`) support at least 256 colors
+            Some("linux" | "vt100" | "vt220" | "ansi" | "cons25" | "screen" | "tmux") => {
+                ColorSupport::Ansi16
+            }
+            Some(_) => ColorSupport::Ansi256,
+        }
+    }
+
+    /// Maps `color` to the nearest color that can be displayed.
+    pub(crate) fn convert(&self, color: crate::themes::Color) -> Color {
+        let color: Color = color.into();
+        let Color::Rgb { r, g, b } = color else {
+            return color;
+        };
+        match self {
+            ColorSupport::TrueColor => color,
+            ColorSupport::Ansi256 => Color::AnsiValue(nearest_ansi_256((r, g, b))),
+            ColorSupport::Ansi16 => ANSI_16_COLORS
+                .iter()
+                .min_by_key(|(_, rgb)| distance(*rgb, (r, g, b)))
+                .map(|(color, _)| *color)
+                .unwrap_or(color),
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let square = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    square(r1, r2) + square(g1, g2) + square(b1, b2)
+}
+
+/// Returns the nearest entry of either the color cube (16-231) or the grayscale ramp (232-255).
+fn nearest_ansi_256(rgb: (u8, u8, u8)) -> u8 {
+    let nearest_level = |value: u8| {
+        (0..ANSI_256_CUBE_LEVELS.len())
+            .min_by_key(|index| (ANSI_256_CUBE_LEVELS[*index] as i32 - value as i32).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (
+        nearest_level(rgb.0),
+        nearest_level(rgb.1),
+        nearest_level(rgb.2),
+    );
+    let cube = (
+        16 + 36 * r as u8 + 6 * g as u8 + b as u8,
+        (
+            ANSI_256_CUBE_LEVELS[r],
+            ANSI_256_CUBE_LEVELS[g],
+            ANSI_256_CUBE_LEVELS[b],
+        ),
+    );
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray_index = (average.saturating_sub(8) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray_index;
+    let gray = (232 + gray_index, (gray_level, gray_level, gray_level));
+    [cube, gray]
+        .into_iter()
+        .min_by_key(|(_, candidate)| distance(*candidate, rgb))
+        .map(|(index, _)| index)
+        .unwrap_or(cube.0)
 }
 
 impl Default for Crossterm {
@@ -25,13 +164,17 @@ impl Default for Crossterm {
 
 use crossterm::{
     cursor::{Hide, MoveTo, SetCursorStyle, Show},
-    event::{DisableBracketedPaste, EnableBracketedPaste, EnableMouseCapture},
+    event::{
+        DisableBracketedPaste, DisableFocusChange, EnableBracketedPaste, EnableFocusChange,
+        EnableMouseCapture, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+        PushKeyboardEnhancementFlags,
+    },
     execute, queue,
     style::{
         Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
         SetUnderlineColor,
     },
-    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
     ExecutableCommand,
 };
 
@@ -43,6 +186,14 @@ impl Frontend for Crossterm {
     fn enter_alternate_screen(&mut self) -> anyhow::Result<()> {
         self.stdout.execute(EnterAlternateScreen)?;
         self.stdout.execute(EnableBracketedPaste)?;
+        self.stdout.execute(EnableFocusChange)?;
+        // The kitty keyboard protocol is needed to receive keys such as `super+a` and `shift+enter`
+        if terminal::supports_keyboard_enhancement().unwrap_or(false) {
+            self.stdout.execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES,
+            ))?;
+            self.keyboard_enhancement_enabled = true;
+        }
         Ok(())
     }
 
@@ -52,8 +203,18 @@ impl Frontend for Crossterm {
     }
 
     fn leave_alternate_screen(&mut self) -> anyhow::Result<()> {
+        if self.keyboard_enhancement_enabled {
+            self.stdout.execute(PopKeyboardEnhancementFlags)?;
+            self.keyboard_enhancement_enabled = false;
+        }
+        if self.title_saved {
+            // Restore the title saved by `set_title` (XTWINOPS)
+            self.stdout.execute(Print("\x1b[23;0t"))?;
+            self.title_saved = false;
+        }
         self.stdout.execute(LeaveAlternateScreen)?;
         self.stdout.execute(DisableBracketedPaste)?;
+        self.stdout.execute(DisableFocusChange)?;
         Ok(())
     }
 
@@ -87,10 +248,22 @@ impl Frontend for Crossterm {
 
     fn clear_screen(&mut self) -> anyhow::Result<()> {
         queue!(self.stdout, Clear(ClearType::All))?;
+        // Repaint every cell on the next render
+        self.previous_screen = Screen::default();
         Ok(())
     }
 
-    fn render_screen(&mut self, mut screen: Screen) -> anyhow::Result<()> {
+    fn set_title(&mut self, title: &str) -> anyhow::Result<()> {
+        if !self.title_saved {
+            // Save the original title (XTWINOPS), which is ignored by terminals that do not support it
+            queue!(self.stdout, Print("\x1b[22;0t"))?;
+            self.title_saved = true;
+        }
+        execute!(self.stdout, SetTitle(title))?;
+        Ok(())
+    }
+
+    fn render_screen(&mut self, mut screen: Screen) -> anyhow::Result<usize> {
         let cells = {
             // Only perform diff if the dimension is the same
             let diff = if self.previous_screen.dimension() == screen.dimension() {
@@ -103,6 +276,12 @@ impl Frontend for Crossterm {
 
             diff
         };
+        let repainted_cells = cells.len();
+        if repainted_cells > 0 {
+            // Hide the cursor while repainting, it is shown again by `show_cursor`
+            queue!(self.stdout, Hide)?;
+        }
+        let color_support = self.color_support;
         for cell in cells {
             queue!(
                 self.stdout,
@@ -112,10 +291,15 @@ impl Frontend for Crossterm {
                 } else {
                     Attribute::NoBold
                 }),
+                SetAttribute(if cell.cell.is_italic {
+                    Attribute::Italic
+                } else {
+                    Attribute::NoItalic
+                }),
                 SetUnderlineColor(
                     cell.cell
                         .line
-                        .map(|line| line.color.into())
+                        .map(|line| color_support.convert(line.color))
                         .unwrap_or(Color::Reset),
                 ),
                 SetAttribute(
@@ -127,13 +311,13 @@ impl Frontend for Crossterm {
                         })
                         .unwrap_or(Attribute::NoUnderline),
                 ),
-                SetBackgroundColor(cell.cell.background_color.into()),
-                SetForegroundColor(cell.cell.foreground_color.into()),
+                SetBackgroundColor(color_support.convert(cell.cell.background_color)),
+                SetForegroundColor(color_support.convert(cell.cell.foreground_color)),
                 Print(reveal(&cell.cell.symbol)),
                 SetAttribute(Attribute::Reset),
             )?;
         }
-        Ok(())
+        Ok(repainted_cells)
     }
 }
 
@@ -145,3 +329,71 @@ fn reveal(s: &str) -> String {
         _ => s.into(),
     }
 }
+
+#[cfg(test)]
+mod test_color_support {
+    use crossterm::style::Color;
+    use my_proc_macros::hex;
+
+    use super::ColorSupport;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "truecolor".parse::<ColorSupport>().ok(),
+            Some(ColorSupport::TrueColor)
+        );
+        assert_eq!(
+            "256".parse::<ColorSupport>().ok(),
+            Some(ColorSupport::Ansi256)
+        );
+        assert_eq!(
+            "16".parse::<ColorSupport>().ok(),
+            Some(ColorSupport::Ansi16)
+        );
+        assert!("8".parse::<ColorSupport>().is_err());
+    }
+
+    #[test]
+    fn detect_from_env() {
+        use ColorSupport::*;
+        assert_eq!(
+            ColorSupport::from_env(Some("truecolor"), Some("xterm")),
+            TrueColor
+        );
+        assert_eq!(
+            ColorSupport::from_env(None, Some("xterm-256color")),
+            Ansi256
+        );
+        assert_eq!(
+            ColorSupport::from_env(None, Some("xterm-direct")),
+            TrueColor
+        );
+        assert_eq!(ColorSupport::from_env(None, Some("linux")), Ansi16);
+        assert_eq!(ColorSupport::from_env(None, Some("xterm")), Ansi256);
+        assert_eq!(ColorSupport::from_env(None, Some("unknown")), Ansi256);
+        assert_eq!(ColorSupport::from_env(None, None), TrueColor);
+    }
+
+    #[test]
+    fn convert_to_nearest_palette_entry() {
+        assert_eq!(
+            ColorSupport::TrueColor.convert(hex!("#123456")),
+            Color::Rgb {
+                r: 0x12,
+                g: 0x34,
+                b: 0x56
+            }
+        );
+        assert_eq!(
+            ColorSupport::Ansi256.convert(hex!("#ff0000")),
+            Color::AnsiValue(196)
+        );
+        assert_eq!(
+            ColorSupport::Ansi256.convert(hex!("#1e1e1e")),
+            Color::AnsiValue(234)
+        );
+        assert_eq!(ColorSupport::Ansi16.convert(hex!("#fe0101")), Color::Red);
+        assert_eq!(ColorSupport::Ansi16.convert(hex!("#1e1e1e")), Color::Black);
+    }
+}
@@ -4,6 +4,9 @@ use crate::{components::component::Cursor, screen::Screen};
 #[derive(Clone, Default)]
 pub(crate) struct MockFrontend {
     screen: Option<Screen>,
+    /// The number of times the cursor is shown, which happens once per repainted screen.
+    shown_cursor_count: usize,
+    raw_mode_enabled: bool,
 }
 
 const WIDTH: u16 = 80;
@@ -31,14 +34,17 @@ impl super::Frontend for MockFrontend {
     }
 
     fn enable_raw_mode(&mut self) -> anyhow::Result<()> {
+        self.raw_mode_enabled = true;
         Ok(())
     }
 
     fn disable_raw_mode(&mut self) -> anyhow::Result<()> {
+        self.raw_mode_enabled = false;
         Ok(())
     }
 
     fn show_cursor(&mut self, _: &Cursor) -> anyhow::Result<()> {
+        self.shown_cursor_count += 1;
         Ok(())
     }
 
@@ -50,10 +56,26 @@ impl super::Frontend for MockFrontend {
         Ok(())
     }
 
-    fn render_screen(&mut self, grid: Screen) -> anyhow::Result<()> {
-        self.screen = Some(grid);
+    fn set_title(&mut self, _: &str) -> anyhow::Result<()> {
         Ok(())
     }
+
+    fn render_screen(&mut self, mut grid: Screen) -> anyhow::Result<usize> {
+        let repainted_cells = match self.screen.as_mut() {
+            Some(previous) if previous.dimension() == grid.dimension() => grid.diff(previous).len(),
+            _ => grid.get_positioned_cells().len(),
+        };
+        self.screen = Some(grid);
+        Ok(repainted_cells)
+    }
 }
 
-impl MockFrontend {}
+impl MockFrontend {
+    pub(crate) fn shown_cursor_count(&self) -> usize {
+        self.shown_cursor_count
+    }
+
+    pub(crate) fn raw_mode_enabled(&self) -> bool {
+        self.raw_mode_enabled
+    }
+}
@@ -2,6 +2,11 @@ pub(crate) mod crossterm;
 #[cfg(test)]
 pub(crate) mod mock;
 
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, MutexGuard,
+};
+
 use crate::{app::Dimension, components::component::Cursor, screen::Screen};
 
 pub trait Frontend {
@@ -14,5 +19,56 @@ pub trait Frontend {
     fn show_cursor(&mut self, cursor: &Cursor) -> anyhow::Result<()>;
     fn hide_cursor(&mut self) -> anyhow::Result<()>;
     fn clear_screen(&mut self) -> anyhow::Result<()>;
-    fn render_screen(&mut self, screen: Screen) -> anyhow::Result<()>;
+    /// Sets the title of the terminal window (or tab).
+    fn set_title(&mut self, title: &str) -> anyhow::Result<()>;
+    /// Returns the number of repainted cells.
+    fn render_screen(&mut self, screen: Screen) -> anyhow::Result<usize>;
+}
+
+/// Guards the reading of terminal events, so that the reading can be paused while a child process
+/// (e.g. `sudo` prompting for a password) takes over the terminal.
+#[derive(Default)]
+pub(crate) struct InputGate {
+    paused: AtomicBool,
+    reading: Mutex<()>,
+}
+
+impl InputGate {
+    /// Waits for the ongoing read to finish, and prevents further reads until the returned guard is dropped.
+    pub(crate) fn pause(&self) -> InputPause<'_> {
+        // Set before locking, so that the reader does not take the lock again in the meantime
+        self.paused.store(true, Ordering::SeqCst);
+        InputPause {
+            gate: self,
+            _reading: self
+                .reading
+                .lock()
+                .unwrap_or_else(|error| error.into_inner()),
+        }
+    }
+
+    /// Returns the result of `read`, or `None` without calling it if the reading is paused.
+    /// `read` should return shortly (e.g. poll with a timeout), because `pause` waits for it.
+    pub(crate) fn read<R>(&self, read: impl FnOnce() -> R) -> Option<R> {
+        if self.paused.load(Ordering::SeqCst) {
+            return None;
+        }
+        let _reading = self
+            .reading
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        Some(read())
+    }
+}
+
+/// Resumes the reading of terminal events when dropped, see `InputGate::pause`.
+pub(crate) struct InputPause<'a> {
+    gate: &'a InputGate,
+    _reading: MutexGuard<'a, ()>,
+}
+
+impl Drop for InputPause<'_> {
+    fn drop(&mut self) {
+        self.gate.paused.store(false, Ordering::SeqCst)
+    }
 }
@@ -15,6 +15,10 @@ pub(crate) struct Hunk {
     /// 0-based index
     new_line_range: Range<usize>,
 
+    /// The lines replaced by this hunk in the old content, including their line endings.
+    /// It is empty if this hunk only inserts lines.
+    old_content: String,
+
     /// Used for displaying the diff.
     /// This field contains both the old content and the new content.
     content: String,
@@ -30,28 +34,29 @@ impl Hunk {
             .iter()
             .filter_map(|group| {
                 // I'm going to assume each group only has one change (i.e. Delete/Insert/Replace), while the other diff_ops are Equal
-                let (_, new_line_range) = group.iter().find_map(|diff_op| match diff_op {
-                    similar::DiffOp::Equal { .. } => None,
-                    similar::DiffOp::Delete {
-                        new_index,
-                        old_index,
-                        old_len,
-                    } => Some((*old_index..(old_index + old_len), *new_index..*new_index)),
-                    similar::DiffOp::Insert {
-                        new_index,
-                        new_len,
-                        old_index,
-                    } => Some((*old_index..*old_index, *new_index..(new_index + new_len))),
-                    similar::DiffOp::Replace {
-                        new_index,
-                        new_len,
-                        old_index,
-                        old_len,
-                    } => Some((
-                        *old_index..(old_index + old_len),
-                        *new_index..(new_index + new_len),
-                    )),
-                })?;
+                let (old_line_range, new_line_range) =
+                    group.iter().find_map(|diff_op| match diff_op {
+                        similar::DiffOp::Equal { .. } => None,
+                        similar::DiffOp::Delete {
+                            new_index,
+                            old_index,
+                            old_len,
+                        } => Some((*old_index..(old_index + old_len), *new_index..*new_index)),
+                        similar::DiffOp::Insert {
+                            new_index,
+                            new_len,
+                            old_index,
+                        } => Some((*old_index..*old_index, *new_index..(new_index + new_len))),
+                        similar::DiffOp::Replace {
+                            new_index,
+                            new_len,
+                            old_index,
+                            old_len,
+                        } => Some((
+                            *old_index..(old_index + old_len),
+                            *new_index..(new_index + new_len),
+                        )),
+                    })?;
 
                 #[derive(PartialEq)]
                 enum LineKind {
@@ -110,6 +115,7 @@ impl Hunk {
                 let content = trim_start(content, min_leading_whitespaces_count);
                 Some(Hunk {
                     new_line_range,
+                    old_content: diff.old_slices()[old_line_range].concat(),
                     content,
                     decorations,
                 })
@@ -120,9 +126,25 @@ impl Hunk {
         &self.new_line_range
     }
 
+    pub(crate) fn old_content(&self) -> &str {
+        &self.old_content
+    }
+
+    /// The hunks of a file that is not in the commit, which insert its whole content.
+    /// An empty file has a placeholder hunk, so that it is still listed.
+    pub(crate) fn untracked(content: &str) -> Vec<Hunk> {
+        let hunks = Hunk::get("", content);
+        if hunks.is_empty() {
+            [Hunk::one_insert("[This file is untracked and empty]")].to_vec()
+        } else {
+            hunks
+        }
+    }
+
     pub(crate) fn one_insert(message: &str) -> Hunk {
         Hunk {
             new_line_range: 0..0,
+            old_content: String::new(),
             content: message.to_string(),
             decorations: Vec::new(),
         }
@@ -233,6 +255,20 @@ mod test_hunk {
         assert_eq!(hunk.to_info().unwrap().content(), "b\nc")
     }
 
+    #[test]
+    fn old_content() {
+        let hunks = Hunk::get("a\n  b\nc\nd\ne\n", "a\nB\nc\ne\nf\n");
+        assert_eq!(
+            hunks
+                .iter()
+                .map(|hunk| (hunk.line_range().clone(), hunk.old_content()))
+                .collect_vec(),
+            // The old content is not trimmed, and keeps its line endings,
+            // so that a hunk can be reverted by replacing its lines with it
+            [(1..2, "  b\n"), (3..3, "d\n"), (4..5, "")]
+        );
+    }
+
     #[test]
     fn should_trim_common_leading_whitespace() {
         let old = indoc!(
@@ -0,0 +1,263 @@
+use std::{
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use git2::{Oid, Sort};
+use shared::canonicalized_path::CanonicalizedPath;
+
+use super::GitRepo;
+
+/// A commit listed in the commit log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Commit {
+    pub(crate) hash: String,
+    pub(crate) author: String,
+    /// Seconds since the Unix epoch.
+    pub(crate) time: i64,
+    /// The full message, of which the first line is the subject.
+    pub(crate) message: String,
+    /// The ASCII graph preceding this commit, for example `| * `.
+    /// Empty if the log is filtered by path.
+    pub(crate) graph: String,
+}
+
+impl Commit {
+    fn new(commit: &git2::Commit, graph: String) -> Self {
+        Self {
+            hash: commit.id().to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            time: commit.time().seconds(),
+            message: commit.message().unwrap_or_default().trim_end().to_string(),
+            graph,
+        }
+    }
+
+    pub(crate) fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    pub(crate) fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    /// Formatted as `YYYY-MM-DD` in UTC.
+    pub(crate) fn date(&self) -> String {
+        crate::notes::date(UNIX_EPOCH + Duration::from_secs(self.time.max(0) as u64))
+            .unwrap_or_default()
+    }
+
+    /// The header shown by `git show`, followed by the indented message.
+    pub(crate) fn header(&self) -> String {
+        format!(
+            "commit {}\nAuthor: {}\nDate:   {}\n\n{}\n",
+            self.hash,
+            self.author,
+            self.date(),
+            self.message
+                .lines()
+                .map(|line| format!("    {line}").trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+impl GitRepo {
+    /// Returns at most `limit` commits reachable from the head, newest first.
+    /// If `path` is given, only the commits that modify it are returned, without a graph.
+    pub(crate) fn log(
+        &self,
+        path: Option<&CanonicalizedPath>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<Commit>> {
+        let pathspec = path.map(|path| self.relative_path(path)).transpose()?;
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+        revwalk.push_head()?;
+        let mut graph = Graph::default();
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            if commits.len() >= limit {
+                break;
+            }
+            let commit = self.repo.find_commit(oid?)?;
+            let graph = match &pathspec {
+                Some(pathspec) if !self.modifies(&commit, pathspec)? => continue,
+                Some(_) => String::new(),
+                None => graph.next(&commit),
+            };
+            commits.push(Commit::new(&commit, graph));
+        }
+        Ok(commits)
+    }
+
+    /// Returns the header and the patch of the commit `hash` against its first parent, like `git show`.
+    pub(crate) fn show(&self, hash: &str) -> anyhow::Result<String> {
+        let commit = self.repo.find_commit(Oid::from_str(hash)?)?;
+        let diff = self.commit_diff(&commit, None)?;
+        let mut patch = String::new();
+        diff.print(git2::DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(format!(
+            "{}\n{}",
+            Commit::new(&commit, String::new()).header(),
+            patch
+        ))
+    }
+
+    fn modifies(&self, commit: &git2::Commit, path: &Path) -> anyhow::Result<bool> {
+        Ok(self.commit_diff(commit, Some(path))?.deltas().len() > 0)
+    }
+
+    fn commit_diff(
+        &self,
+        commit: &git2::Commit,
+        pathspec: Option<&Path>,
+    ) -> anyhow::Result<git2::Diff<'_>> {
+        let parent_tree = commit
+            .parents()
+            .next()
+            .map(|parent| parent.tree())
+            .transpose()?;
+        let mut options = git2::DiffOptions::new();
+        if let Some(pathspec) = pathspec {
+            options.pathspec(pathspec);
+        }
+        Ok(self.repo.diff_tree_to_tree(
+            parent_tree.as_ref(),
+            Some(&commit.tree()?),
+            Some(&mut options),
+        )?)
+    }
+}
+
+/// Lays out the commits of a topologically sorted walk into columns,
+/// where each column is reserved for the next expected commit of a line of development.
+#[derive(Default)]
+struct Graph {
+    columns: Vec<Option<Oid>>,
+}
+
+impl Graph {
+    /// Returns the graph of `commit`, and reserves columns for its parents.
+    fn next(&mut self, commit: &git2::Commit) -> String {
+        let id = commit.id();
+        let column = self
+            .columns
+            .iter()
+            .position(|column| *column == Some(id))
+            .or_else(|| self.columns.iter().position(Option::is_none))
+            .unwrap_or_else(|| {
+                self.columns.push(None);
+                self.columns.len() - 1
+            });
+        let graph = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(index, reserved)| match (index == column, reserved) {
+                (true, _) => "* ",
+                (false, Some(_)) => "| ",
+                (false, None) => "  ",
+            })
+            .collect();
+
+        // Other lines of development that lead to this commit end here
+        for reserved in self.columns.iter_mut() {
+            if *reserved == Some(id) {
+                *reserved = None
+            }
+        }
+        let mut parents = commit.parent_ids();
+        let first_parent = parents
+            .next()
+            .filter(|parent| !self.columns.contains(&Some(*parent)));
+        self.columns[column] = first_parent;
+        for parent in parents {
+            if self.columns.contains(&Some(parent)) {
+                continue;
+            }
+            match self.columns.iter().position(Option::is_none) {
+                Some(index) => self.columns[index] = Some(parent),
+                None => self.columns.push(Some(parent)),
+            }
+        }
+        while self.columns.last() == Some(&None) {
+            self.columns.pop();
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod test_log {
+    use std::process::Command;
+
+    use itertools::Itertools;
+    use tempfile::tempdir;
+
+    fn run_command(dir: &tempfile::TempDir, args: &[&str], date: &str) {
+        Command::new("git")
+            .args(args)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(dir.path())
+            .output()
+            .expect("Failed to run command");
+    }
+
+    #[test]
+    fn log() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        // The commits have distinct dates, so that their order is deterministic
+        let date = |day: usize| format!("2024-01-0{day}T00:00:00Z");
+        let git = |args: &[&str], day: usize| run_command(&dir, args, &date(day));
+        let commit = |file: &str, message: &str, day: usize| {
+            std::fs::write(dir.path().join(file), format!("{message}\n")).unwrap();
+            git(&["add", "."], day);
+            git(&["commit", "-m", message], day);
+        };
+        git(&["init"], 1);
+        commit("a.txt", "First", 1);
+        git(&["branch", "-M", "main"], 1);
+        git(&["checkout", "-b", "feature"], 1);
+        commit("b.txt", "Second", 2);
+        git(&["checkout", "main"], 2);
+        commit("a.txt", "Third", 3);
+        git(&["merge", "--no-ff", "-m", "Merge", "feature"], 4);
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let log = repo.log(None, 10)?;
+        let display = |log: &[super::Commit]| {
+            log.iter()
+                .map(|commit| format!("{}{}", commit.graph, commit.subject()))
+                .collect_vec()
+        };
+        assert_eq!(
+            display(&log),
+            ["* Merge", "* | Third", "| * Second", "* First"]
+        );
+
+        // Filtering by path
+        let path = dir.path().join("a.txt").try_into()?;
+        assert_eq!(display(&repo.log(Some(&path), 10)?), ["Third", "First"]);
+
+        // The limit
+        assert_eq!(repo.log(None, 2)?.len(), 2);
+
+        // The diff of a commit
+        let third = &log[1];
+        let show = repo.show(&third.hash)?;
+        assert!(show.starts_with(&format!("commit {}\n", third.hash)));
+        assert!(show.contains("\n    Third\n"));
+        assert!(show.contains("Date:   2024-01-03\n"));
+        assert!(show.contains("\n-First\n+Third\n"));
+        Ok(())
+    }
+}
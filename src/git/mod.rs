@@ -1,4 +1,5 @@
 pub(crate) mod hunk;
+pub(crate) mod log;
 
 use rayon::prelude::*;
 
@@ -25,7 +26,188 @@ impl TryFrom<&CanonicalizedPath> for GitRepo {
     }
 }
 
+/// The numbers of commits of the current branch that are ahead of and behind its upstream branch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct AheadBehind {
+    pub(crate) ahead: usize,
+    pub(crate) behind: usize,
+}
+
+impl AheadBehind {
+    /// For example, `↑2 ↓1`, or an empty string if the branch is up to date.
+    pub(crate) fn display(&self) -> String {
+        [("↑", self.ahead), ("↓", self.behind)]
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(arrow, count)| format!("{arrow}{count}"))
+            .join(" ")
+    }
+}
+
+/// A local or remote branch of a repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Branch {
+    /// For example, `main` for a local branch, or `origin/main` for a remote branch.
+    pub(crate) name: String,
+    pub(crate) is_remote: bool,
+    /// Whether this is the branch that is currently checked out.
+    pub(crate) is_head: bool,
+}
+
+/// Computes the `AheadBehind` of the repository at `path` in another thread,
+/// because it can take a while on large repositories.
+pub(crate) fn refresh_ahead_behind(
+    path: CanonicalizedPath,
+    sender: std::sync::mpsc::Sender<crate::app::AppMessage>,
+) {
+    std::thread::spawn(move || {
+        let ahead_behind = GitRepo::try_from(&path)
+            .and_then(|repo| repo.ahead_behind())
+            .unwrap_or_else(|error| {
+                log::info!("Failed to compute ahead/behind: {:?}", error);
+                None
+            });
+        let _ = sender.send(crate::app::AppMessage::GitAheadBehind(ahead_behind));
+    });
+}
+
 impl GitRepo {
+    /// Returns `None` if the head is detached, or the current branch has no upstream.
+    pub(crate) fn ahead_behind(&self) -> anyhow::Result<Option<AheadBehind>> {
+        let head = self.repo.head()?;
+        if !head.is_branch() {
+            return Ok(None);
+        }
+        let Some(name) = head.shorthand() else {
+            return Ok(None);
+        };
+        let branch = self.repo.find_branch(name, git2::BranchType::Local)?;
+        let Ok(upstream) = branch.upstream() else {
+            return Ok(None);
+        };
+        let (Some(local), Some(upstream)) = (branch.get().target(), upstream.get().target()) else {
+            return Ok(None);
+        };
+        let (ahead, behind) = self.repo.graph_ahead_behind(local, upstream)?;
+        Ok(Some(AheadBehind { ahead, behind }))
+    }
+
+    /// Returns the local branches followed by the remote branches, with the current branch first.
+    pub(crate) fn branches(&self) -> anyhow::Result<Vec<Branch>> {
+        Ok(self
+            .repo
+            .branches(None)?
+            .map(|branch| {
+                let (branch, branch_type) = branch?;
+                let name = branch
+                    .name()?
+                    .ok_or_else(|| anyhow::anyhow!("The branch name is not valid UTF-8"))?
+                    .to_string();
+                Ok(Branch {
+                    name,
+                    is_remote: branch_type == git2::BranchType::Remote,
+                    is_head: branch.is_head(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            // `origin/HEAD` only points to another remote branch
+            .filter(|branch| !(branch.is_remote && branch.name.ends_with("/HEAD")))
+            .sorted_by_key(|branch| (!branch.is_head, branch.is_remote, branch.name.clone()))
+            .collect())
+    }
+
+    /// Checks out the given branch without overwriting uncommitted changes that conflict with it.
+    ///
+    /// A remote branch such as `origin/feature` is checked out as the local branch `feature`,
+    /// which is created to track the remote branch if it does not exist.
+    pub(crate) fn checkout_branch(&self, branch: &Branch) -> anyhow::Result<()> {
+        let local = if branch.is_remote {
+            let remote = self
+                .repo
+                .find_branch(&branch.name, git2::BranchType::Remote)?;
+            let local_name = branch
+                .name
+                .split_once('/')
+                .map(|(_, name)| name)
+                .unwrap_or(&branch.name);
+            match self.repo.find_branch(local_name, git2::BranchType::Local) {
+                Ok(local) => local,
+                Err(_) => {
+                    let mut local =
+                        self.repo
+                            .branch(local_name, &remote.get().peel_to_commit()?, false)?;
+                    local.set_upstream(Some(&branch.name))?;
+                    local
+                }
+            }
+        } else {
+            self.repo
+                .find_branch(&branch.name, git2::BranchType::Local)?
+        };
+        let reference = local.get();
+        let reference_name = reference
+            .name()
+            .ok_or_else(|| anyhow::anyhow!("The branch name is not valid UTF-8"))?;
+        self.repo.checkout_tree(
+            &reference.peel(git2::ObjectType::Tree)?,
+            Some(git2::build::CheckoutBuilder::new().safe()),
+        )?;
+        self.repo.set_head(reference_name)?;
+        Ok(())
+    }
+
+    /// Creates a local branch named `name` at the current commit, and checks it out.
+    pub(crate) fn create_branch(&self, name: &str) -> anyhow::Result<()> {
+        let head = self.repo.head()?.peel_to_commit()?;
+        self.repo.branch(name, &head, false)?;
+        self.checkout_branch(&Branch {
+            name: name.to_string(),
+            is_remote: false,
+            is_head: false,
+        })
+    }
+
+    /// Whether every commit of the given local branch is reachable from the head or from the upstream
+    /// of the branch, so that deleting the branch loses no commits.
+    pub(crate) fn is_merged(&self, branch: &Branch) -> anyhow::Result<bool> {
+        let local = self
+            .repo
+            .find_branch(&branch.name, git2::BranchType::Local)?;
+        let Some(tip) = local.get().target() else {
+            return Ok(false);
+        };
+        let head = self.repo.head()?.target();
+        let upstream = local
+            .upstream()
+            .ok()
+            .and_then(|upstream| upstream.get().target());
+        Ok([head, upstream].into_iter().flatten().any(|target| {
+            target == tip || self.repo.graph_descendant_of(target, tip).unwrap_or(false)
+        }))
+    }
+
+    /// Deletes the given local branch, even if it is not merged, like `git branch -D`.
+    /// See `Self::is_merged` for checking whether commits would be lost.
+    pub(crate) fn delete_branch(&self, branch: &Branch) -> anyhow::Result<()> {
+        if branch.is_remote {
+            return Err(anyhow::anyhow!(
+                "Cannot delete the remote branch {:?}",
+                branch.name
+            ));
+        }
+        if branch.is_head {
+            return Err(anyhow::anyhow!(
+                "Cannot delete the current branch {:?}",
+                branch.name
+            ));
+        }
+        self.repo
+            .find_branch(&branch.name, git2::BranchType::Local)?
+            .delete()?;
+        Ok(())
+    }
+
     pub(crate) fn diffs(&self, diff_mode: DiffMode) -> anyhow::Result<Vec<FileDiff>> {
         Ok(self
             .diff_entries(diff_mode)?
@@ -39,6 +221,96 @@ impl GitRepo {
         &self.path
     }
 
+    /// Returns `path` relative to the root of the working directory of the repository.
+    pub(crate) fn relative_path(
+        &self,
+        path: &CanonicalizedPath,
+    ) -> anyhow::Result<std::path::PathBuf> {
+        let workdir = self
+            .repo
+            .workdir()
+            .ok_or_else(|| anyhow::anyhow!("The repository has no working directory"))?;
+        Ok(path.to_path_buf().strip_prefix(workdir)?.to_path_buf())
+    }
+
+    /// The URL of the `origin` remote, or of the first remote if there is no `origin`.
+    pub(crate) fn remote_url(&self) -> anyhow::Result<String> {
+        let remotes = self.repo.remotes()?;
+        let name = remotes
+            .iter()
+            .flatten()
+            .find(|name| *name == "origin")
+            .or_else(|| remotes.iter().flatten().next())
+            .ok_or_else(|| anyhow::anyhow!("The repository has no remote"))?;
+        self.repo
+            .find_remote(name)?
+            .url()
+            .map(|url| url.to_string())
+            .ok_or_else(|| anyhow::anyhow!("The URL of the remote {:?} is not valid UTF-8", name))
+    }
+
+    pub(crate) fn is_ignored(&self, path: &CanonicalizedPath) -> anyhow::Result<bool> {
+        Ok(self.repo.is_path_ignored(self.relative_path(path)?)?)
+    }
+
+    /// Records in the index that the untracked `path` will be added later, like `git add --intent-to-add`,
+    /// so that its content is listed by `git diff` before it is staged.
+    pub(crate) fn intent_to_add(&self, path: &CanonicalizedPath) -> anyhow::Result<()> {
+        let relative_path = self.relative_path(path)?;
+        let mut index = self.repo.index()?;
+        if index.get_path(&relative_path, 0).is_some() {
+            return Err(anyhow::anyhow!(
+                "{} is already tracked",
+                relative_path.display()
+            ));
+        }
+        let zero = git2::IndexTime::new(0, 0);
+        index.add(&git2::IndexEntry {
+            ctime: zero,
+            mtime: zero,
+            dev: 0,
+            ino: 0,
+            mode: 0o100644,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            // Like Git, the entry is recorded with the content of an empty file
+            id: self.repo.blob(&[])?,
+            flags: git2::IndexEntryFlag::EXTENDED.bits(),
+            flags_extended: git2::IndexEntryExtendedFlag::INTENT_TO_ADD.bits(),
+            path: relative_path
+                .to_str()
+                .ok_or_else(|| anyhow::anyhow!("The path is not valid UTF-8"))?
+                .replace('\\', "/")
+                .into_bytes(),
+        })?;
+        index.write()?;
+        Ok(())
+    }
+
+    pub(crate) fn head_commit_hash(&self) -> anyhow::Result<String> {
+        Ok(self.repo.head()?.peel_to_commit()?.id().to_string())
+    }
+
+    /// Stages the move of `from` to `to` like `git mv`, where `to` is already moved on disk.
+    /// Returns false if `from` is not tracked.
+    pub(crate) fn stage_move(
+        &self,
+        from: &CanonicalizedPath,
+        to: &CanonicalizedPath,
+    ) -> anyhow::Result<bool> {
+        let from = self.relative_path(from)?;
+        let to = self.relative_path(to)?;
+        let mut index = self.repo.index()?;
+        if index.get_path(&from, 0).is_none() {
+            return Ok(false);
+        }
+        index.remove_path(&from)?;
+        index.add_path(&to)?;
+        index.write()?;
+        Ok(true)
+    }
+
     pub(crate) fn diff_entries(&self, diff_mode: DiffMode) -> anyhow::Result<Vec<DiffEntry>> {
         // Open the repository
         let repo = &self.repo;
@@ -50,6 +322,7 @@ impl GitRepo {
             // Generate the diff
             diff_options.recurse_untracked_dirs(true);
             diff_options.include_untracked(true);
+            diff_options.include_ignored(false);
 
             let tree = self.get_tree(&diff_mode)?;
             repo.diff_tree_to_workdir(Some(&tree), Some(&mut diff_options))?
@@ -112,17 +385,29 @@ impl GitRepo {
 
     fn get_tree(&self, diff_mode: &DiffMode) -> Result<git2::Tree<'_>, anyhow::Error> {
         match diff_mode {
-            DiffMode::UnstagedAgainstMainBranch => Ok(self
-                .repo
-                .find_reference("refs/heads/main")
-                .or_else(|_| self.repo.find_reference("refs/heads/master"))?
-                .peel_to_commit()?
-                .tree()?),
+            DiffMode::UnstagedAgainstMainBranch => {
+                Ok(self.main_branch()?.peel_to_commit()?.tree()?)
+            }
             DiffMode::UnstagedAgainstCurrentBranch => {
                 Ok(self.repo.head()?.peel_to_commit()?.tree()?)
             }
+            DiffMode::UnstagedAgainstMergeBase => {
+                let head = self.repo.head()?.peel_to_commit()?.id();
+                let main_branch = self.main_branch()?.peel_to_commit()?.id();
+                Ok(self
+                    .repo
+                    .find_commit(self.repo.merge_base(head, main_branch)?)?
+                    .tree()?)
+            }
         }
     }
+
+    fn main_branch(&self) -> anyhow::Result<git2::Reference<'_>> {
+        Ok(self
+            .repo
+            .find_reference("refs/heads/main")
+            .or_else(|_| self.repo.find_reference("refs/heads/master"))?)
+    }
 }
 
 pub(crate) struct FileDiff {
@@ -155,22 +440,22 @@ impl GitOperation for CanonicalizedPath {
         diff_mode: &DiffMode,
         repo_path: &CanonicalizedPath,
     ) -> anyhow::Result<FileDiff> {
-        if let Ok(latest_committed_content) =
-            self.content_at_last_commit(diff_mode, &repo_path.try_into()?)
-        {
-            let current_content = self.read()?;
-            let hunks = Hunk::get(&latest_committed_content, &current_content);
-
-            Ok(FileDiff {
-                path: self.clone(),
-                hunks,
-            })
-        } else {
-            Ok(FileDiff {
+        let repo: GitRepo = repo_path.try_into()?;
+        if repo.is_ignored(self).unwrap_or(false) {
+            return Ok(FileDiff {
                 path: self.clone(),
-                hunks: [Hunk::one_insert("[This file is untracked or renamed]")].to_vec(),
-            })
+                hunks: Vec::new(),
+            });
         }
+        let current_content = self.read()?;
+        let hunks = match self.content_at_last_commit(diff_mode, &repo) {
+            Ok(latest_committed_content) => Hunk::get(&latest_committed_content, &current_content),
+            Err(_) => Hunk::untracked(&current_content),
+        };
+        Ok(FileDiff {
+            path: self.clone(),
+            hunks,
+        })
     }
 
     fn content_at_last_commit(
@@ -200,18 +485,14 @@ pub(crate) struct DiffEntry {
 
 impl DiffEntry {
     fn file_diff(&self) -> anyhow::Result<FileDiff> {
-        if let Some(old_content) = &self.old_content {
-            let hunks = Hunk::get(old_content, &self.new_content);
-            Ok(FileDiff {
-                path: self.new_path.clone(),
-                hunks,
-            })
-        } else {
-            Ok(FileDiff {
-                path: self.new_path.clone(),
-                hunks: [Hunk::one_insert("[This file is untracked or renamed]")].to_vec(),
-            })
-        }
+        let hunks = match &self.old_content {
+            Some(old_content) => Hunk::get(old_content, &self.new_content),
+            None => Hunk::untracked(&self.new_content),
+        };
+        Ok(FileDiff {
+            path: self.new_path.clone(),
+            hunks,
+        })
     }
 
     pub(crate) fn new_path(&self) -> CanonicalizedPath {
@@ -223,6 +504,10 @@ impl DiffEntry {
 pub(crate) enum DiffMode {
     UnstagedAgainstMainBranch,
     UnstagedAgainstCurrentBranch,
+    /// Against the commit from which the current branch forked off the main branch,
+    /// so that only the changes of the current branch are shown,
+    /// without the changes committed to the main branch since then.
+    UnstagedAgainstMergeBase,
 }
 
 impl DiffMode {
@@ -230,12 +515,22 @@ impl DiffMode {
         match self {
             DiffMode::UnstagedAgainstMainBranch => "against main branch".to_string(),
             DiffMode::UnstagedAgainstCurrentBranch => "against current branch".to_string(),
+            DiffMode::UnstagedAgainstMergeBase => "against merge base".to_string(),
         }
     }
+
+    pub(crate) fn all() -> [DiffMode; 3] {
+        [
+            DiffMode::UnstagedAgainstCurrentBranch,
+            DiffMode::UnstagedAgainstMainBranch,
+            DiffMode::UnstagedAgainstMergeBase,
+        ]
+    }
 }
 
 #[cfg(test)]
 mod test_git {
+    use itertools::Itertools;
     use std::process::Command;
     use tempfile::tempdir;
 
@@ -247,6 +542,192 @@ mod test_git {
             .expect("Failed to run command");
     }
 
+    #[test]
+    fn test_ahead_behind() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let commit = |message: &str| {
+            run_command(&dir, "git", &["commit", "--allow-empty", "-m", message]);
+        };
+        run_command(&dir, "git", &["init"]);
+        commit("First commit");
+        run_command(&dir, "git", &["branch", "-M", "main"]);
+
+        // The branch has no upstream yet
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        assert_eq!(repo.ahead_behind()?, None);
+
+        run_command(&dir, "git", &["checkout", "-b", "feature"]);
+        run_command(&dir, "git", &["branch", "--set-upstream-to=main"]);
+        commit("Second commit");
+        commit("Third commit");
+        run_command(&dir, "git", &["checkout", "main"]);
+        commit("Fourth commit");
+        run_command(&dir, "git", &["checkout", "feature"]);
+
+        let ahead_behind = repo.ahead_behind()?.unwrap();
+        assert_eq!(
+            ahead_behind,
+            super::AheadBehind {
+                ahead: 2,
+                behind: 1
+            }
+        );
+        assert_eq!(ahead_behind.display(), "↑2 ↓1");
+        assert_eq!(super::AheadBehind::default().display(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_branches() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("file.txt");
+        run_command(&dir, "git", &["init"]);
+        std::fs::write(&file, "main\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        run_command(&dir, "git", &["commit", "-m", "First commit"]);
+        run_command(&dir, "git", &["branch", "-M", "main"]);
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let branch = |name: &str, is_head: bool| super::Branch {
+            name: name.to_string(),
+            is_remote: false,
+            is_head,
+        };
+
+        // Creating a branch checks it out
+        repo.create_branch("feature")?;
+        assert_eq!(
+            repo.branches()?,
+            [branch("feature", true), branch("main", false)]
+        );
+
+        std::fs::write(&file, "feature\n")?;
+        run_command(&dir, "git", &["commit", "-am", "Second commit"]);
+
+        // Checking out a branch updates the files of the working directory
+        repo.checkout_branch(&branch("main", false))?;
+        assert_eq!(std::fs::read_to_string(&file)?, "main\n");
+        assert_eq!(
+            repo.branches()?,
+            [branch("main", true), branch("feature", false)]
+        );
+
+        // Uncommitted changes that conflict with the branch are not overwritten
+        std::fs::write(&file, "modified\n")?;
+        assert!(repo.checkout_branch(&branch("feature", false)).is_err());
+        assert_eq!(std::fs::read_to_string(&file)?, "modified\n");
+
+        // The current branch cannot be deleted
+        assert!(repo.delete_branch(&branch("main", true)).is_err());
+
+        // The commit of `feature` is not reachable from `main`
+        assert!(!repo.is_merged(&branch("feature", false))?);
+        assert!(repo.is_merged(&branch("main", true))?);
+
+        repo.delete_branch(&branch("feature", false))?;
+        assert_eq!(repo.branches()?, [branch("main", true)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_untracked_files() -> anyhow::Result<()> {
+        use super::GitOperation;
+        let dir = tempdir().unwrap();
+        run_command(&dir, "git", &["init"]);
+        std::fs::write(dir.path().join(".gitignore"), "ignored.txt\n")?;
+        run_command(&dir, "git", &["add", "."]);
+        run_command(&dir, "git", &["commit", "-m", "First commit"]);
+        std::fs::write(dir.path().join("new.txt"), "hello\nworld\n")?;
+        std::fs::write(dir.path().join("ignored.txt"), "ignored\n")?;
+
+        let repo_path: shared::canonicalized_path::CanonicalizedPath = dir.path().try_into()?;
+        let repo = super::GitRepo::try_from(&repo_path)?;
+        let mode = super::DiffMode::UnstagedAgainstCurrentBranch;
+        let new_file = repo_path.join("new.txt")?;
+        let ignored_file = repo_path.join("ignored.txt")?;
+
+        // The content of an untracked file is inserted by one hunk, while ignored files have no hunks
+        let diffs = repo.diffs(mode.clone())?;
+        assert_eq!(
+            diffs.iter().map(|diff| diff.path().clone()).collect_vec(),
+            [new_file.clone()]
+        );
+        let line_ranges = |hunks: &[super::Hunk]| {
+            hunks
+                .iter()
+                .map(|hunk| hunk.line_range().clone())
+                .collect_vec()
+        };
+        assert_eq!(line_ranges(diffs[0].hunks()), [0..2]);
+        assert_eq!(
+            line_ranges(new_file.file_diff(&mode, &repo_path)?.hunks()),
+            [0..2]
+        );
+        assert!(ignored_file
+            .file_diff(&mode, &repo_path)?
+            .hunks()
+            .is_empty());
+
+        // Intent to add
+        repo.intent_to_add(&new_file)?;
+        let output = Command::new("git")
+            .args(["diff", "--name-only"])
+            .current_dir(dir.path())
+            .output()?;
+        assert_eq!(String::from_utf8(output.stdout)?, "new.txt\n");
+        assert_eq!(line_ranges(repo.diffs(mode)?[0].hunks()), [0..2]);
+        assert!(repo.intent_to_add(&new_file).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_base() -> anyhow::Result<()> {
+        let dir = tempdir().unwrap();
+        let commit = |file: &str, content: &str| {
+            std::fs::write(dir.path().join(file), content).unwrap();
+            run_command(&dir, "git", &["add", "."]);
+            run_command(&dir, "git", &["commit", "-m", content]);
+        };
+        run_command(&dir, "git", &["init"]);
+        commit("upstream.txt", "old\n");
+        run_command(&dir, "git", &["branch", "-M", "main"]);
+        run_command(&dir, "git", &["checkout", "-b", "feature"]);
+        commit("feature.txt", "feature\n");
+        run_command(&dir, "git", &["checkout", "main"]);
+        commit("upstream.txt", "new\n");
+        run_command(&dir, "git", &["checkout", "feature"]);
+        std::fs::write(dir.path().join("feature.txt"), "feature\nmodified\n")?;
+
+        let repo = super::GitRepo::try_from(&dir.path().try_into()?)?;
+        let paths = |mode: super::DiffMode| -> anyhow::Result<Vec<String>> {
+            Ok(repo
+                .diff_entries(mode)?
+                .into_iter()
+                .map(|entry| {
+                    entry
+                        .new_path()
+                        .to_path_buf()
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .sorted()
+                .collect_vec())
+        };
+
+        // The commit of the main branch after the fork is considered a change of the current branch
+        assert_eq!(
+            paths(super::DiffMode::UnstagedAgainstMainBranch)?,
+            ["feature.txt", "upstream.txt"]
+        );
+        assert_eq!(
+            paths(super::DiffMode::UnstagedAgainstMergeBase)?,
+            ["feature.txt"]
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_diff_entries() -> anyhow::Result<()> {
         let test = |mode: super::DiffMode, expected_old_content: &str| -> anyhow::Result<()> {
@@ -10,6 +10,7 @@ use itertools::Itertools;
 use my_proc_macros::hex;
 #[cfg(test)]
 use ropey::Rope;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthChar;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -31,6 +32,7 @@ pub(crate) struct Cell {
     /// decoration, e.g. Diagnostic
     pub(crate) source: Option<StyleKey>,
     pub(crate) is_bold: bool,
+    pub(crate) is_italic: bool,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash, Copy)]
@@ -54,7 +56,7 @@ impl Cell {
         }
     }
 
-    fn apply_update(&self, update: CellUpdate) -> Cell {
+    pub(crate) fn apply_update(&self, update: CellUpdate) -> Cell {
         Cell {
             symbol: update.symbol.clone().unwrap_or(self.symbol.clone()),
             foreground_color: update
@@ -69,6 +71,7 @@ impl Cell {
             is_cursor: update.is_cursor,
             source: update.source.or(self.source.clone()),
             is_bold: update.style.is_bold || self.is_bold,
+            is_italic: update.style.is_italic || self.is_italic,
         }
     }
 }
@@ -83,6 +86,7 @@ impl Default for Cell {
             is_cursor: false,
             source: None,
             is_bold: false,
+            is_italic: false,
         }
     }
 }
@@ -170,9 +174,20 @@ pub(crate) enum RenderContentLineNumber {
         /// 0-based
         start_line_index: usize,
         max_line_number: usize,
+        signs: Vec<LineSign>,
     },
 }
 
+/// A symbol shown in place of the line number separator of a line,
+/// such as the sign of a bookmark.
+#[derive(Debug, Clone)]
+pub(crate) struct LineSign {
+    /// 0-based
+    pub(crate) line_index: usize,
+    pub(crate) symbol: String,
+    pub(crate) style: Style,
+}
+
 impl Grid {
     pub(crate) fn new(dimension: Dimension) -> Grid {
         let mut cells: Vec<Vec<Cell>> = vec![];
@@ -294,21 +309,32 @@ impl Grid {
         let grid = self;
         let column_range =
             column_start.unwrap_or(0)..column_end.unwrap_or(dimension.width as usize);
-        // Trim or Pad end with spaces
-        let content = format!("{:<width$}", content, width = column_range.len());
+        // Each grapheme takes one cell, followed by padding cells if it is wider than one cell
+        let symbols = content
+            .graphemes(true)
+            .flat_map(|grapheme| {
+                std::iter::once(grapheme.to_string()).chain(
+                    std::iter::repeat(" ".to_string())
+                        .take(get_grapheme_width(grapheme).saturating_sub(1)),
+                )
+            })
+            .collect_vec();
+        // Pad end with spaces
+        let padding = column_range.len().saturating_sub(symbols.len());
         let take = grid.dimension().width as usize;
-        content
-            .chars()
+        symbols
+            .into_iter()
+            .chain(std::iter::repeat(" ".to_string()).take(padding))
             .take(take)
             .enumerate()
-            .map(|(char_index, character)| {
-                let column_index = column_range.start + char_index;
+            .map(|(cell_index, symbol)| {
+                let column_index = column_range.start + cell_index;
                 CellUpdate {
                     position: Position {
                         line: row_index,
                         column: column_index,
                     },
-                    symbol: Some(character.to_string()),
+                    symbol: Some(symbol),
                     style: *style,
                     ..CellUpdate::default()
                 }
@@ -339,6 +365,7 @@ impl Grid {
             RenderContentLineNumber::LineNumber {
                 start_line_index: start_line_number,
                 max_line_number,
+                ..
             } => (
                 start_line_number,
                 max_line_number.max(1).to_string().len(),
@@ -355,14 +382,21 @@ impl Grid {
                 .lines()
                 .enumerate()
                 .flat_map(|(line_index, line)| {
-                    line.chars()
+                    // The first character of a grapheme cluster renders the whole cluster,
+                    // the remaining characters of the cluster render nothing
+                    line.graphemes(true)
+                        .flat_map(|grapheme| {
+                            grapheme
+                                .char_indices()
+                                .map(move |(index, _)| (index == 0).then(|| grapheme.to_string()))
+                        })
                         .enumerate()
-                        .map(move |(column_index, character)| CellUpdate {
+                        .map(move |(column_index, symbol)| CellUpdate {
                             position: Position {
                                 line: line_index,
                                 column: column_index,
                             },
-                            symbol: Some(character.to_string()),
+                            symbol,
                             style: Style::default().foreground_color(theme.ui.text_foreground),
                             ..CellUpdate::default()
                         })
@@ -441,10 +475,7 @@ impl Grid {
         let line_numbers = {
             match line_number {
                 RenderContentLineNumber::NoLineNumber => Vec::new(),
-                RenderContentLineNumber::LineNumber {
-                    start_line_index: _,
-                    max_line_number: _,
-                } => line_numbers
+                RenderContentLineNumber::LineNumber { signs, .. } => line_numbers
                     .into_iter()
                     .enumerate()
                     .flat_map(
@@ -475,13 +506,18 @@ impl Grid {
                                 &theme.ui.line_number,
                             )
                             .into_iter()
-                            .chain(grid.get_row_cell_updates(
-                                line_index,
-                                Some(max_line_number_len),
-                                Some(max_line_number_len + 1),
-                                "│",
-                                &theme.ui.border,
-                            ))
+                            .chain({
+                                let sign = signs
+                                    .iter()
+                                    .find(|sign| !wrapped && sign.line_index == line_number);
+                                grid.get_row_cell_updates(
+                                    line_index,
+                                    Some(max_line_number_len),
+                                    Some(max_line_number_len + 1),
+                                    sign.map(|sign| sign.symbol.as_str()).unwrap_or("│"),
+                                    sign.map(|sign| &sign.style).unwrap_or(&theme.ui.border),
+                                )
+                            })
                             .map(|cell_update| {
                                 CalibratableCellUpdate {
                                     cell_update,
@@ -595,7 +631,34 @@ pub(crate) enum StyleKey {
 
 /// TODO: in the future, tab size should be configurable
 pub(crate) fn get_string_width(str: &str) -> usize {
-    str.chars().map(get_char_width).sum()
+    str.graphemes(true).map(get_grapheme_width).sum()
+}
+
+/// The width of a grapheme cluster is the width of its first character,
+/// except that emoji sequences (joined by ZWJ or carrying the emoji presentation selector)
+/// are always 2 cells wide.
+pub(crate) fn get_grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let Some(first) = chars.next() else {
+        return 0;
+    };
+    if chars.any(|c| c == '\u{200D}' || c == '\u{FE0F}') {
+        2
+    } else {
+        get_char_width(first)
+    }
+}
+
+/// Returns the width of each character of `str`.
+///
+/// The first character of each grapheme cluster takes the width of the whole cluster,
+/// while the remaining characters (combining marks, ZWJ-joined emojis, etc.) take 0.
+pub(crate) fn get_char_widths(str: &str) -> Vec<usize> {
+    str.graphemes(true)
+        .flat_map(|grapheme| {
+            std::iter::once(get_grapheme_width(grapheme)).chain(grapheme.chars().skip(1).map(|_| 0))
+        })
+        .collect()
 }
 
 pub(crate) fn get_char_width(c: char) -> usize {
@@ -624,7 +687,7 @@ mod test_grid {
         position::Position,
     };
 
-    use super::get_string_width;
+    use super::{get_char_widths, get_string_width};
 
     mod render_content {
         use crate::{
@@ -647,6 +710,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    signs: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -668,6 +732,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 10,
                     start_line_index: 10,
+                    signs: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -696,6 +761,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 0,
                     start_line_index: 0,
+                    signs: Vec::new(),
                 },
                 Vec::new(),
                 Vec::new(),
@@ -728,6 +794,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    signs: Vec::new(),
                 },
                 [CellUpdate {
                     symbol: Some(cursor.to_string()),
@@ -767,6 +834,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    signs: Vec::new(),
                 },
                 [CellUpdate {
                     symbol: Some(cursor.to_string()),
@@ -790,6 +858,39 @@ mod test_grid {
             )
         }
 
+        #[test]
+        /// No wrap, with grapheme clusters made of multiple characters
+        fn grapheme_clusters() {
+            let family = "👨\u{200d}👩\u{200d}👧";
+            let e_with_acute = "e\u{301}";
+            let cursor = '█';
+            let content = format!("{family}x {e_with_acute}中y");
+            let actual = Grid::new(Dimension {
+                height: 1,
+                width: 12,
+            })
+            .render_content(
+                &content,
+                RenderContentLineNumber::LineNumber {
+                    max_line_number: 1,
+                    start_line_index: 1,
+                    signs: Vec::new(),
+                },
+                [CellUpdate {
+                    symbol: Some(cursor.to_string()),
+                    position: Position::new(0, 10), // 'y'
+                    ..Default::default()
+                }]
+                .to_vec(),
+                Vec::new(),
+                &Theme::default(),
+            )
+            .to_string();
+            // The ZWJ emoji sequence and the double-width CJK character are padded by one space,
+            // while the combining mark stays in the cell of its base character
+            assert_eq!(actual, format!("2│{family} x {e_with_acute}中 {cursor}"))
+        }
+
         #[test]
         /// Line number width should follow max_line_numbers_len
         fn case_5() {
@@ -802,6 +903,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 100,
                     start_line_index: 1,
+                    signs: Vec::new(),
                 },
                 [].to_vec(),
                 Vec::new(),
@@ -826,6 +928,7 @@ mod test_grid {
                 RenderContentLineNumber::LineNumber {
                     max_line_number: 1,
                     start_line_index: 1,
+                    signs: Vec::new(),
                 },
                 [].to_vec(),
                 [LineUpdate {
@@ -866,6 +969,7 @@ mod test_grid {
                     RenderContentLineNumber::LineNumber {
                         max_line_number: 0,
                         start_line_index: 0,
+                        signs: Vec::new(),
                     },
                     Vec::new(),
                     Vec::new(),
@@ -1003,7 +1107,18 @@ x
 
     #[test]
     fn test_get_string_width() {
-        assert_eq!(get_string_width("\t\t"), 8)
+        assert_eq!(get_string_width("\t\t"), 8);
+        assert_eq!(get_string_width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(get_string_width("e\u{301}"), 1);
+        assert_eq!(get_string_width("中文"), 4);
+    }
+
+    #[test]
+    fn test_get_char_widths() {
+        assert_eq!(
+            get_char_widths("a👨\u{200d}👩e\u{301}中"),
+            [1, 2, 0, 0, 1, 0, 2].to_vec()
+        );
     }
 }
 
@@ -1023,6 +1138,7 @@ mod test_cell {
             is_cursor: true,
             source: Some(StyleKey::HunkNew),
             is_bold: true,
+            is_italic: false,
         };
         let cell = cell.apply_update(CellUpdate {
             position: Position::default(),
@@ -1,3 +1,5 @@
+use crate::quickfix_list::Location;
+
 pub(crate) struct History<T> {
     backward_history: Vec<T>,
     forward_history: Vec<T>,
@@ -45,6 +47,56 @@ impl<T> Default for History<T> {
     }
 }
 
+/// The maximum number of locations kept in a `ChangeList`.
+const MAX_CHANGE_LIST_LEN: usize = 100;
+
+/// The locations of the latest edits across all files, from the oldest.
+#[derive(Default)]
+pub(crate) struct ChangeList {
+    locations: Vec<Location>,
+    /// The index of the location last jumped to by `ChangeList::cycle`.
+    cycle_index: Option<usize>,
+}
+
+impl ChangeList {
+    /// Consecutive edits on the same line are recorded as one location.
+    pub(crate) fn push(&mut self, location: Location) {
+        match self.locations.last_mut() {
+            Some(last)
+                if last.path == location.path
+                    && last.range.start.line == location.range.start.line =>
+            {
+                *last = location
+            }
+            _ => self.locations.push(location),
+        }
+        if self.locations.len() > MAX_CHANGE_LIST_LEN {
+            self.locations.remove(0);
+        }
+        self.cycle_index = None;
+    }
+
+    /// Returns the location of the latest edit, from which `ChangeList::cycle` continues.
+    pub(crate) fn latest(&mut self) -> Option<Location> {
+        let location = self.locations.last().cloned();
+        self.cycle_index = self.locations.len().checked_sub(1);
+        location
+    }
+
+    /// Returns the location of the edit before the one last returned,
+    /// starting from the latest edit, and wrapping around after the oldest edit.
+    pub(crate) fn cycle(&mut self) -> Option<Location> {
+        let last_index = self.locations.len().checked_sub(1)?;
+        let index = match self.cycle_index {
+            Some(index) if index > 0 => index - 1,
+            Some(_) => last_index,
+            None => last_index,
+        };
+        self.cycle_index = Some(index);
+        self.locations.get(index).cloned()
+    }
+}
+
 #[cfg(test)]
 mod test_history {
     use super::*;
@@ -98,4 +150,32 @@ mod test_history {
         assert_eq!(history.undo(), Some(1));
         assert_eq!(history.undo(), None);
     }
+
+    #[test]
+    fn change_list() -> anyhow::Result<()> {
+        use crate::position::Position;
+        let directory = tempfile::tempdir()?;
+        let path: shared::canonicalized_path::CanonicalizedPath = directory.path().try_into()?;
+        let location = |line: usize, column: usize| Location {
+            path: path.clone(),
+            range: Position::new(line, column)..Position::new(line, column),
+        };
+        let mut change_list = ChangeList::default();
+        assert_eq!(change_list.cycle(), None);
+        change_list.push(location(0, 0));
+        change_list.push(location(1, 0));
+        // Edits on the same line are merged
+        change_list.push(location(1, 5));
+        change_list.push(location(2, 0));
+
+        assert_eq!(change_list.cycle(), Some(location(2, 0)));
+        assert_eq!(change_list.cycle(), Some(location(1, 5)));
+        assert_eq!(change_list.cycle(), Some(location(0, 0)));
+        // Wraps around
+        assert_eq!(change_list.cycle(), Some(location(2, 0)));
+
+        assert_eq!(change_list.latest(), Some(location(2, 0)));
+        assert_eq!(change_list.cycle(), Some(location(1, 5)));
+        Ok(())
+    }
 }
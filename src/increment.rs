@@ -0,0 +1,204 @@
+use std::ops::Range;
+
+use itertools::Itertools;
+
+/// Increments the number of `line` that contains `byte_index`, or else the first number after it.
+///
+/// Decimal, hexadecimal (`0x`), binary (`0b`) numbers and dates (`YYYY-MM-DD`, incremented by days)
+/// are supported, where the width of zero-padded numbers and the case of hexadecimal digits are preserved.
+///
+/// Returns the byte range of the number and its incremented text,
+/// or an error if there is no number or if the incremented number overflows.
+pub(crate) fn increment_number(
+    line: &str,
+    byte_index: usize,
+    by: i64,
+) -> anyhow::Result<(Range<usize>, String)> {
+    type Increment = fn(&str, i64) -> Option<String>;
+    let dates = lazy_regex::regex!(r"\b\d{4}-\d{2}-\d{2}\b")
+        .find_iter(line)
+        .filter(|m| parse_date(m.as_str()).is_some())
+        .map(|m| (m.range(), increment_date as Increment));
+    let numbers = lazy_regex::regex!(r"0[xX][0-9a-fA-F]+|0[bB][01]+|-?\d+")
+        .find_iter(line)
+        .map(|m| {
+            // A hyphen after a word, such as in `item-1`, is not a minus sign
+            let is_hyphen = m.as_str().starts_with('-')
+                && line[..m.start()]
+                    .chars()
+                    .last()
+                    .is_some_and(char::is_alphanumeric);
+            if is_hyphen {
+                m.start() + 1..m.end()
+            } else {
+                m.range()
+            }
+        })
+        .map(|range| {
+            let increment: Increment = match line[range.clone()].get(..2) {
+                Some("0x" | "0X") => |text, by| increment_radix(text, 16, by),
+                Some("0b" | "0B") => |text, by| increment_radix(text, 2, by),
+                _ => increment_decimal,
+            };
+            (range, increment)
+        });
+    let (range, increment) = dates
+        .chain(numbers)
+        .filter(|(range, _)| range.end > byte_index)
+        // Dates are chained first, so they are preferred over the numbers within them
+        .min_by_key(|(range, _)| range.start)
+        .ok_or_else(|| anyhow::anyhow!("No number under the cursor"))?;
+    let text = &line[range.clone()];
+    let incremented = increment(text, by)
+        .ok_or_else(|| anyhow::anyhow!("Incrementing {} by {} overflows", text, by))?;
+    Ok((range, incremented))
+}
+
+/// Returns `None` if the number or the result is too large.
+fn increment_decimal(text: &str, by: i64) -> Option<String> {
+    let digits = text.trim_start_matches('-');
+    let result = text.parse::<i128>().ok()?.checked_add(by as i128)?;
+    if digits.len() > 1 && digits.starts_with('0') {
+        let sign = if result < 0 { "-" } else { "" };
+        Some(format!(
+            "{sign}{:0width$}",
+            result.unsigned_abs(),
+            width = digits.len()
+        ))
+    } else {
+        Some(result.to_string())
+    }
+}
+
+/// The number wraps around on overflow, like the unsigned integers that they usually represent.
+fn increment_radix(text: &str, radix: u32, by: i64) -> Option<String> {
+    let (prefix, digits) = text.split_at(2);
+    let result = u64::from_str_radix(digits, radix)
+        .ok()?
+        .wrapping_add_signed(by);
+    let width = digits.len();
+    let formatted = match radix {
+        16 if digits.chars().any(|c| c.is_ascii_uppercase()) => {
+            format!("{result:0width$X}")
+        }
+        16 => format!("{result:0width$x}"),
+        _ => format!("{result:0width$b}"),
+    };
+    Some(format!("{prefix}{formatted}"))
+}
+
+/// Returns the number of days of the date `text` since 1970-01-01,
+/// or `None` if it is not a valid date.
+fn parse_date(text: &str) -> Option<i64> {
+    let (year, month, day) = text
+        .split('-')
+        .map(|part| part.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .collect_tuple()?;
+    let days = days_from_civil(year, month, day);
+    // Invalid dates such as `2024-13-45` are not round-tripped
+    (civil_from_days(days) == Some((year, month, day))).then_some(days)
+}
+
+/// Returns `None` if the date is invalid or if the result is too far away.
+fn increment_date(text: &str, by: i64) -> Option<String> {
+    let (year, month, day) = civil_from_days(parse_date(text)?.checked_add(by)?)?;
+    Some(format!("{year:04}-{month:02}-{day:02}"))
+}
+
+/// Refer http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Refer http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+///
+/// Returns `None` if `days` is too large.
+fn civil_from_days(days: i64) -> Option<(i64, i64, i64)> {
+    let days = days.checked_add(719468)?;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400;
+    Some((if month <= 2 { year + 1 } else { year }, month, day))
+}
+
+#[cfg(test)]
+mod test_increment {
+    use super::*;
+
+    fn increment(line: &str, byte_index: usize, by: i64) -> Option<String> {
+        increment_number(line, byte_index, by)
+            .ok()
+            .map(|(range, incremented)| {
+                format!(
+                    "{}{}{}",
+                    &line[..range.start],
+                    incremented,
+                    &line[range.end..]
+                )
+            })
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(increment("x = 9;", 0, 1).as_deref(), Some("x = 10;"));
+        assert_eq!(increment("x = 1;", 0, -3).as_deref(), Some("x = -2;"));
+        assert_eq!(increment("x = -1;", 4, 2).as_deref(), Some("x = 1;"));
+        assert_eq!(increment("item-1", 0, 1).as_deref(), Some("item-2"));
+        assert_eq!(increment("007", 1, 1).as_deref(), Some("008"));
+        assert_eq!(increment("1 2 3", 2, 10).as_deref(), Some("1 12 3"));
+        assert_eq!(increment("1 2 3", 5, 10), None);
+    }
+
+    #[test]
+    fn hexadecimal_and_binary() {
+        assert_eq!(increment("0xff", 0, 1).as_deref(), Some("0x100"));
+        assert_eq!(increment("0x0F", 0, 1).as_deref(), Some("0x10"));
+        assert_eq!(
+            increment("0x00", 0, -1).as_deref(),
+            Some("0xffffffffffffffff")
+        );
+        assert_eq!(increment("0b0111", 0, 1).as_deref(), Some("0b1000"));
+    }
+
+    #[test]
+    fn date() {
+        assert_eq!(
+            increment("due 2024-02-28", 0, 2).as_deref(),
+            Some("due 2024-03-01")
+        );
+        assert_eq!(
+            increment("due 2024-01-01", 12, -1).as_deref(),
+            Some("due 2023-12-31")
+        );
+        // Invalid dates are incremented as numbers
+        assert_eq!(increment("2024-13-45", 0, 1).as_deref(), Some("2025-13-45"));
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let error = |line: &str, by: i64| increment_number(line, 0, by).unwrap_err().to_string();
+        assert_eq!(
+            error("2024-01-01", i64::MAX),
+            format!("Incrementing 2024-01-01 by {} overflows", i64::MAX)
+        );
+        assert!(error("1".repeat(40).as_str(), 1).ends_with("overflows"));
+        assert_eq!(error("x", 1), "No number under the cursor");
+    }
+}
@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use itertools::Itertools;
+use shared::language::{self, Language};
+use tree_sitter::{Query, QueryCursor, Tree};
+
+/// A block of another language embedded in a document, such as a fenced code block of Markdown,
+/// as captured by the injection query of the language of the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Injection {
+    pub(crate) language: Language,
+    pub(crate) byte_range: Range<usize>,
+}
+
+/// Returns the injections of `content` whose language is known,
+/// see `Language::injection_query`.
+pub(crate) fn injections(
+    language: &Language,
+    tree: &Tree,
+    content: &str,
+) -> anyhow::Result<Vec<Injection>> {
+    let Some(injection_query) = language.injection_query() else {
+        return Ok(Vec::new());
+    };
+    let query = Query::new(&tree.language(), injection_query)?;
+    let (Some(language_index), Some(content_index)) = (
+        query.capture_index_for_name("injection.language"),
+        query.capture_index_for_name("injection.content"),
+    ) else {
+        return Err(anyhow::anyhow!(
+            "The injection query should capture `@injection.language` and `@injection.content`"
+        ));
+    };
+    let mut cursor = QueryCursor::new();
+    Ok(cursor
+        .matches(&query, tree.root_node(), content.as_bytes())
+        .filter_map(|query_match| {
+            let node = |index| {
+                query_match
+                    .captures
+                    .iter()
+                    .find(|capture| capture.index == index)
+                    .map(|capture| capture.node)
+            };
+            Some(Injection {
+                language: language::from_code_fence_info(
+                    content[node(language_index)?.byte_range()].trim(),
+                )?,
+                byte_range: whole_lines(content, node(content_index)?.byte_range()),
+            })
+        })
+        .collect())
+}
+
+/// Extends `range` to the start of its first line, and shrinks it to the end of its last line,
+/// if they are separated only by indentation.
+fn whole_lines(content: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = content[..range.start]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let start = if content[line_start..range.start].trim().is_empty() {
+        line_start
+    } else {
+        range.start
+    };
+    let end = match content[start..range.end].rfind('\n') {
+        Some(index) if content[start + index + 1..range.end].trim().is_empty() => start + index + 1,
+        _ => range.end,
+    };
+    start..end
+}
+
+/// Formats each of `injections` in `content` with the formatters of its language.
+///
+/// Injections that fail to format, such as snippets that are not complete programs,
+/// are left unchanged.
+pub(crate) fn format_injections(content: &str, injections: Vec<Injection>) -> String {
+    injections
+        .into_iter()
+        // Formatting from the last injection keeps the byte ranges of the earlier ones valid
+        .sorted_by_key(|injection| std::cmp::Reverse(injection.byte_range.start))
+        .fold(content.to_string(), |content, injection| {
+            let Range { start, end } = injection.byte_range;
+            match format_injection(&content[start..end], &injection.language) {
+                Ok(Some(formatted)) => {
+                    format!("{}{}{}", &content[..start], formatted, &content[end..])
+                }
+                Ok(None) => content,
+                Err(error) => {
+                    log::info!("Failed to format the injection at {start}..{end}: {error}");
+                    content
+                }
+            }
+        })
+}
+
+/// The indentation shared by the lines of `block`, such as that of a code block in a list item,
+/// is removed before formatting and restored afterwards.
+fn format_injection(block: &str, language: &Language) -> anyhow::Result<Option<String>> {
+    let Some(formatter) = language.formatter() else {
+        return Ok(None);
+    };
+    let indentation = block
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .min_by_key(|indentation| indentation.len())
+        .unwrap_or_default();
+    let trailing_newline = if block.ends_with('\n') { "\n" } else { "" };
+    let dedented = block
+        .lines()
+        .map(|line| line.strip_prefix(indentation).unwrap_or(line.trim_start()))
+        .join("\n");
+    let formatted = formatter.format(&format!("{dedented}{trailing_newline}"))?;
+    Ok(Some(format!(
+        "{}{}",
+        formatted
+            .lines()
+            .map(|line| if line.is_empty() {
+                line.to_string()
+            } else {
+                format!("{indentation}{line}")
+            })
+            .join("\n"),
+        trailing_newline
+    )))
+}
+
+#[cfg(test)]
+mod test_injection {
+    use super::*;
+
+    fn format(markdown: &str) -> anyhow::Result<String> {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&tree_sitter_md::language())?;
+        let tree = parser
+            .parse(markdown, None)
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse"))?;
+        let language = language::from_extension("md").unwrap();
+        Ok(format_injections(
+            markdown,
+            injections(&language, &tree, markdown)?,
+        ))
+    }
+
+    #[test]
+    fn fenced_code_blocks_are_formatted() -> anyhow::Result<()> {
+        assert_eq!(
+            format("# Title\n\n```rust\nfn  main ( ) {}\n```\n\n```rs\nfn  f ( ) {}\n```\n")?,
+            "# Title\n\n```rust\nfn main() {}\n```\n\n```rs\nfn f() {}\n```\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn indentation_is_preserved() -> anyhow::Result<()> {
+        assert_eq!(
+            format("- item\n\n  ```rust\n  fn  main ( ) {}\n  ```\n")?,
+            "- item\n\n  ```rust\n  fn main() {}\n  ```\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unformattable_and_unknown_blocks_are_unchanged() -> anyhow::Result<()> {
+        let markdown = "```rust\nfn main() {\n```\n\n```unknown\na  b\n```\n";
+        assert_eq!(format(markdown)?, markdown);
+        Ok(())
+    }
+}
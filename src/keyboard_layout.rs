@@ -0,0 +1,336 @@
+use crossterm::event::KeyCode;
+use event::KeyEvent;
+use itertools::Itertools;
+use serde::Deserialize;
+
+/// The keymaps of Ki are defined in terms of the physical positions of the keys on a QWERTY keyboard.
+const QWERTY_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl;", "zxcvbnm,./"];
+
+const BUILT_IN_LAYOUTS: [(&str, [&str; 3]); 3] = [
+    ("Qwerty", QWERTY_ROWS),
+    ("Dvorak", ["',.pyfgcrl", "aoeuidhtns", ";qjkxbmwvz"]),
+    ("Colemak", ["qwfpgjluy;", "arstdhneio", "zxcvbkm,./"]),
+];
+
+/// A keyboard layout describes the character produced by each of the 30 main keys
+/// (3 rows of 10 keys), so that key events can be mapped back to their QWERTY positions,
+/// which allows the keymaps to stay on the same physical keys regardless of the layout.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub(crate) struct KeyboardLayout {
+    name: String,
+    rows: [String; 3],
+    /// The characters labelling the jumps, in order of preference, see `jump_characters`.
+    #[serde(skip)]
+    jump_characters: Option<Vec<char>>,
+}
+
+impl Default for KeyboardLayout {
+    fn default() -> Self {
+        Self::built_in("Qwerty").unwrap()
+    }
+}
+
+/// The content of `keyboard_layout.toml` in the config directory, for example:
+///
+/// ```toml
+/// active = "Workman"
+/// jump_characters = "ashtneoi"
+///
+/// [[layouts]]
+/// name = "Workman"
+/// rows = ["qdrwbjfup;", "ashtgyneoi", "zxmcvkl,./"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+struct KeyboardLayoutConfig {
+    active: Option<String>,
+    /// Overrides the characters labelling the jumps, such as the home row of the layout.
+    jump_characters: Option<String>,
+    #[serde(default)]
+    layouts: Vec<KeyboardLayout>,
+}
+
+impl KeyboardLayout {
+    fn built_in(name: &str) -> Option<Self> {
+        BUILT_IN_LAYOUTS
+            .iter()
+            .find(|(built_in_name, _)| built_in_name.eq_ignore_ascii_case(name))
+            .map(|(name, rows)| Self {
+                name: name.to_string(),
+                rows: rows.map(|row| row.to_string()),
+                jump_characters: None,
+            })
+    }
+
+    /// Loads the active layout from `keyboard_layout.toml` of the config directory.
+    /// Returns the QWERTY layout if the file does not exist.
+    pub(crate) fn load() -> anyhow::Result<Self> {
+        let path = grammar::config_dir().join("keyboard_layout.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::from_config(&std::fs::read_to_string(path)?)
+    }
+
+    fn from_config(config: &str) -> anyhow::Result<Self> {
+        let config: KeyboardLayoutConfig = toml::from_str(config)?;
+        for layout in &config.layouts {
+            layout.validate()?;
+        }
+        let jump_characters = config
+            .jump_characters
+            .map(|jump_characters| -> anyhow::Result<Vec<char>> {
+                let jump_characters = jump_characters.chars().collect_vec();
+                if jump_characters.is_empty() {
+                    return Err(anyhow::anyhow!("jump_characters should not be empty"));
+                }
+                if let Some(character) = jump_characters
+                    .iter()
+                    .find(|character| character.is_whitespace())
+                {
+                    return Err(anyhow::anyhow!(
+                        "jump_characters should not contain whitespace, but got {:?}",
+                        character
+                    ));
+                }
+                if let Some(duplicate) = jump_characters.iter().duplicates().next() {
+                    return Err(anyhow::anyhow!(
+                        "jump_characters should not contain {:?} more than once",
+                        duplicate
+                    ));
+                }
+                Ok(jump_characters)
+            })
+            .transpose()?;
+        let layout = match config.active {
+            None => Self::default(),
+            Some(active) => config
+                .layouts
+                .into_iter()
+                .find(|layout| layout.name == active)
+                .or_else(|| Self::built_in(&active))
+                .ok_or_else(|| anyhow::anyhow!("Unknown keyboard layout: {:?}", active))?,
+        };
+        Ok(Self {
+            jump_characters,
+            ..layout
+        })
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        for (row, qwerty_row) in self.rows.iter().zip(QWERTY_ROWS) {
+            let length = row.chars().count();
+            if length != qwerty_row.len() {
+                return Err(anyhow::anyhow!(
+                    "Keyboard layout {:?}: expected {} keys in row {:?}, but got {}",
+                    self.name,
+                    qwerty_row.len(),
+                    row,
+                    length
+                ));
+            }
+        }
+        let duplicates = self.rows.iter().flat_map(|row| row.chars()).duplicates();
+        if let Some(duplicate) = duplicates.into_iter().next() {
+            return Err(anyhow::anyhow!(
+                "Keyboard layout {:?}: {:?} is assigned to more than one key",
+                self.name,
+                duplicate
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The characters labelling the jumps, in order of preference.
+    /// Unless configured, these are the letters of this layout followed by the digits,
+    /// which are typed without shift, and then the uppercase letters.
+    pub(crate) fn jump_characters(&self) -> Vec<char> {
+        if let Some(jump_characters) = &self.jump_characters {
+            return jump_characters.clone();
+        }
+        let letters = self
+            .rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .filter(|character| character.is_alphabetic() && character.is_lowercase())
+            .sorted()
+            .collect_vec();
+        letters
+            .iter()
+            .copied()
+            .chain('0'..='9')
+            .chain(letters.iter().flat_map(|letter| letter.to_uppercase()))
+            .collect_vec()
+    }
+
+    /// Translates a key event produced by this layout into the key event
+    /// that would be produced by the key at the same position on a QWERTY keyboard.
+    pub(crate) fn to_qwerty(&self, event: KeyEvent) -> KeyEvent {
+        let KeyCode::Char(c) = event.code else {
+            return event;
+        };
+        let lowercase = c.to_ascii_lowercase();
+        let Some(qwerty) = self
+            .rows
+            .iter()
+            .zip(QWERTY_ROWS)
+            .find_map(|(row, qwerty_row)| {
+                let index = row.chars().position(|key| key == lowercase)?;
+                qwerty_row.chars().nth(index)
+            })
+        else {
+            return event;
+        };
+        let qwerty = if c.is_ascii_uppercase() {
+            qwerty.to_ascii_uppercase()
+        } else {
+            qwerty
+        };
+        KeyEvent {
+            code: KeyCode::Char(qwerty),
+            ..event
+        }
+    }
+
+    /// The inverse of `to_qwerty`, for displaying keymaps (which are defined in QWERTY) in this layout.
+    /// For example, `ctrl+j` in QWERTY is `ctrl+n` in Workman.
+    pub(crate) fn from_qwerty(&self, key: &str) -> String {
+        let (modifiers, last) = match key.rsplit_once('+') {
+            Some((modifiers, last)) if !last.is_empty() => (format!("{modifiers}+"), last),
+            _ => (String::new(), key),
+        };
+        let mut chars = last.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return key.to_string();
+        };
+        let lowercase = c.to_ascii_lowercase();
+        let Some(translated) =
+            QWERTY_ROWS
+                .iter()
+                .zip(self.rows.iter())
+                .find_map(|(qwerty_row, row)| {
+                    let index = qwerty_row.chars().position(|key| key == lowercase)?;
+                    row.chars().nth(index)
+                })
+        else {
+            return key.to_string();
+        };
+        let translated = if c.is_ascii_uppercase() {
+            translated.to_ascii_uppercase()
+        } else {
+            translated
+        };
+        format!("{modifiers}{translated}")
+    }
+
+    /// Renders the keys of this layout, each followed by the QWERTY key that it is mapped to.
+    pub(crate) fn preview(&self) -> String {
+        self.rows
+            .iter()
+            .zip(QWERTY_ROWS)
+            .enumerate()
+            .map(|(index, (row, qwerty_row))| {
+                let indent = " ".repeat(index * 2);
+                let keys = row
+                    .chars()
+                    .zip(qwerty_row.chars())
+                    .map(|(key, qwerty)| {
+                        if key == qwerty {
+                            format!("{key}  ")
+                        } else {
+                            format!("{key}→{qwerty}")
+                        }
+                    })
+                    .join(" ");
+                format!("{indent}{keys}")
+            })
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test_keyboard_layout {
+    use my_proc_macros::key;
+
+    use super::KeyboardLayout;
+
+    #[test]
+    fn custom_layout_maps_keys_to_qwerty_positions() -> anyhow::Result<()> {
+        let layout = KeyboardLayout::from_config(
+            r#"
+active = "Workman"
+
+[[layouts]]
+name = "Workman"
+rows = ["qdrwbjfup;", "ashtgyneoi", "zxmcvkl,./"]
+"#,
+        )?;
+        assert_eq!(layout.name(), "Workman");
+        assert_eq!(layout.to_qwerty(key!("d")), key!("w"));
+        assert_eq!(layout.to_qwerty(key!("H")), key!("D"));
+        assert_eq!(layout.to_qwerty(key!("ctrl+n")), key!("ctrl+j"));
+        assert_eq!(layout.to_qwerty(key!("esc")), key!("esc"));
+        assert_eq!(layout.from_qwerty("w"), "d");
+        assert_eq!(layout.from_qwerty("ctrl+j"), "ctrl+n");
+        assert_eq!(layout.from_qwerty("ctrl++"), "ctrl++");
+        assert_eq!(layout.from_qwerty("space"), "space");
+        Ok(())
+    }
+
+    #[test]
+    fn built_in_layout_can_be_activated() -> anyhow::Result<()> {
+        let layout = KeyboardLayout::from_config(r#"active = "dvorak""#)?;
+        assert_eq!(layout.name(), "Dvorak");
+        assert_eq!(layout.to_qwerty(key!("o")), key!("s"));
+        Ok(())
+    }
+
+    #[test]
+    fn jump_characters() -> anyhow::Result<()> {
+        let default = KeyboardLayout::default().jump_characters();
+        assert_eq!(default[..3], ['a', 'b', 'c']);
+        // Characters typed without shift are preferred
+        assert_eq!(default[26..28], ['0', '1']);
+        assert_eq!(default[36], 'A');
+        assert_eq!(default.len(), 62);
+
+        let layout = KeyboardLayout::from_config(
+            r#"
+active = "colemak"
+jump_characters = "arstneio"
+"#,
+        )?;
+        assert_eq!(layout.name(), "Colemak");
+        assert_eq!(
+            layout.jump_characters(),
+            ['a', 'r', 's', 't', 'n', 'e', 'i', 'o']
+        );
+        assert!(KeyboardLayout::from_config(r#"jump_characters = "aa""#).is_err());
+        assert!(KeyboardLayout::from_config(r#"jump_characters = ''"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_layouts_are_rejected() {
+        assert!(KeyboardLayout::from_config(
+            r#"
+[[layouts]]
+name = "Short"
+rows = ["qwerty", "asdfghjkl;", "zxcvbnm,./"]
+"#
+        )
+        .is_err());
+        assert!(KeyboardLayout::from_config(
+            r#"
+[[layouts]]
+name = "Duplicated"
+rows = ["qqertyuiop", "asdfghjkl;", "zxcvbnm,./"]
+"#
+        )
+        .is_err());
+        assert!(KeyboardLayout::from_config(r#"active = "Unknown""#).is_err());
+    }
+}
@@ -1,13 +1,16 @@
-use crate::quickfix_list::QuickfixList;
+use crate::quickfix_list::{QuickfixList, QuickfixListPreview};
 use crate::ui_tree::{ComponentKind, KindedComponent, UiTree};
 use crate::{
     app::{Dimension, Dispatches},
-    buffer::Buffer,
+    buffer::{Buffer, ExternalChange},
     components::{
         component::{Component, ComponentId},
         editor::Editor,
         file_explorer::FileExplorer,
         keymap_legend::{KeymapLegend, KeymapLegendConfig},
+        outline::Outline,
+        pager::Pager,
+        peek::Peek,
         prompt::Prompt,
         suggestive_editor::{Info, SuggestiveEditor},
     },
@@ -19,8 +22,14 @@ use anyhow::anyhow;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use nary_tree::NodeId;
+use serde::{Deserialize, Serialize};
 use shared::canonicalized_path::CanonicalizedPath;
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
 /// The layout of the app is split into multiple sections: the main panel, info panel, quickfix
 /// lists, prompts, and etc.
@@ -28,14 +37,35 @@ use std::{cell::RefCell, rc::Rc};
 /// hover text, diagnostics, etc.
 pub(crate) struct Layout {
     background_suggestive_editors: IndexMap<CanonicalizedPath, Rc<RefCell<SuggestiveEditor>>>,
-    background_file_explorer: Rc<RefCell<FileExplorer>>,
+    /// The tick when each buffer was last visible, see `evict_background_buffers`.
+    buffer_view_ticks: HashMap<CanonicalizedPath, usize>,
+    view_tick: usize,
+    /// Editors of buffers without a path, which are kept until they are saved to a path.
+    scratch_editors: Vec<Rc<RefCell<SuggestiveEditor>>>,
+    background_file_explorer: Option<Rc<RefCell<FileExplorer>>>,
+    working_directory: CanonicalizedPath,
     background_quickfix_list: Option<Rc<RefCell<Editor>>>,
+    background_outline: Rc<RefCell<Outline>>,
 
     rectangles: Vec<Rectangle>,
     borders: Vec<Border>,
 
     terminal_dimension: Dimension,
     tree: UiTree,
+
+    /// Set by resizing windows or restoring layout presets,
+    /// otherwise the layout kind and the ratio of the main panel depend on the terminal dimension.
+    layout_kind_override: Option<LayoutKind>,
+    ratio_override: Option<f32>,
+}
+
+/// A named arrangement of windows that can be saved and restored, see `Layout::layout_preset`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct LayoutPreset {
+    kind: LayoutKind,
+    /// The ratio of the main panel to the terminal dimension.
+    ratio: f32,
+    file_explorer: bool,
 }
 
 impl Layout {
@@ -49,11 +79,18 @@ impl Layout {
         Ok(Layout {
             background_quickfix_list: None,
             background_suggestive_editors: IndexMap::new(),
-            background_file_explorer: Rc::new(RefCell::new(FileExplorer::new(working_directory)?)),
+            buffer_view_ticks: HashMap::new(),
+            view_tick: 0,
+            scratch_editors: Vec::new(),
+            background_file_explorer: None,
+            working_directory: working_directory.clone(),
+            background_outline: Rc::new(RefCell::new(Outline::new())),
             rectangles,
             borders,
             terminal_dimension,
             tree,
+            layout_kind_override: None,
+            ratio_override: None,
         })
     }
 
@@ -113,7 +150,7 @@ impl Layout {
     }
 
     pub(crate) fn recalculate_layout(&mut self) {
-        let (layout_kind, ratio) = layout_kind(&self.terminal_dimension);
+        let (layout_kind, ratio) = self.layout_kind_and_ratio();
 
         let (rectangles, borders) = Rectangle::generate(
             layout_kind,
@@ -135,6 +172,80 @@ impl Layout {
             });
     }
 
+    fn layout_kind_and_ratio(&self) -> (LayoutKind, f32) {
+        let (layout_kind, ratio) = layout_kind(&self.terminal_dimension);
+        (
+            self.layout_kind_override.unwrap_or(layout_kind),
+            self.ratio_override.unwrap_or(ratio),
+        )
+    }
+
+    /// Grows (or shrinks) the focused window, by moving the border between the main panel
+    /// and the other windows.
+    pub(crate) fn resize_focused_window(&mut self, grow: bool) {
+        const STEP: f32 = 0.05;
+        let (_, ratio) = self.layout_kind_and_ratio();
+        let main_panel_is_focused = self.components().first().is_some_and(|component| {
+            component.component().borrow().id() == self.focused_component_id()
+        });
+        let delta = if grow == main_panel_is_focused {
+            STEP
+        } else {
+            -STEP
+        };
+        // Rounded so that repeated resizing does not accumulate floating point errors
+        self.ratio_override = Some((((ratio + delta) * 100.0).round() / 100.0).clamp(0.2, 0.9));
+        self.recalculate_layout()
+    }
+
+    /// Makes the main panel as large as the other windows combined.
+    pub(crate) fn equalize_windows(&mut self) {
+        self.ratio_override = Some(0.5);
+        self.recalculate_layout()
+    }
+
+    /// Switches between placing the other windows beside (tall) or below (wide) the main panel.
+    pub(crate) fn switch_layout_kind(&mut self) {
+        let (layout_kind, _) = self.layout_kind_and_ratio();
+        self.layout_kind_override = Some(match layout_kind {
+            LayoutKind::Tall => LayoutKind::Wide,
+            LayoutKind::Wide => LayoutKind::Tall,
+        });
+        self.recalculate_layout()
+    }
+
+    pub(crate) fn layout_preset(&self) -> LayoutPreset {
+        let (kind, ratio) = self.layout_kind_and_ratio();
+        LayoutPreset {
+            kind,
+            ratio,
+            file_explorer: self
+                .tree
+                .get_component_by_kind(ComponentKind::FileExplorer)
+                .is_some(),
+        }
+    }
+
+    /// The focus is unchanged, even if the file explorer is opened.
+    pub(crate) fn apply_layout_preset(&mut self, preset: &LayoutPreset) -> anyhow::Result<()> {
+        self.layout_kind_override = Some(preset.kind);
+        self.ratio_override = Some(preset.ratio);
+        let file_explorer_is_open = self
+            .tree
+            .get_component_by_kind(ComponentKind::FileExplorer)
+            .is_some();
+        if preset.file_explorer && !file_explorer_is_open {
+            let file_explorer = self.file_explorer()?;
+            self.tree
+                .replace_root_node_child(ComponentKind::FileExplorer, file_explorer, false);
+        } else if !preset.file_explorer && file_explorer_is_open {
+            self.tree
+                .remove_node_child(self.tree.root_id(), ComponentKind::FileExplorer);
+        }
+        self.recalculate_layout();
+        Ok(())
+    }
+
     pub(crate) fn get_existing_editor(
         &self,
         path: &CanonicalizedPath,
@@ -185,14 +296,81 @@ impl Layout {
         }
     }
 
+    /// Evicts the buffers that are not among the `keep` most recently viewed ones,
+    /// see `Buffer::evict`, so that the memory used does not grow with the number of opened files.
+    ///
+    /// Returns the visible editors whose buffers were evicted and are now restored,
+    /// which have to be highlighted again.
+    pub(crate) fn evict_background_buffers(
+        &mut self,
+        keep: usize,
+        undo_history_limit: usize,
+    ) -> anyhow::Result<Vec<Rc<RefCell<SuggestiveEditor>>>> {
+        self.view_tick += 1;
+        let visible_paths = self
+            .components()
+            .into_iter()
+            .filter_map(|component| component.component().borrow().path())
+            .collect_vec();
+        for path in &visible_paths {
+            self.buffer_view_ticks.insert(path.clone(), self.view_tick);
+        }
+        self.buffer_view_ticks
+            .retain(|path, _| self.background_suggestive_editors.contains_key(path));
+
+        let restored = visible_paths
+            .iter()
+            .filter_map(|path| self.background_suggestive_editors.get(path))
+            .filter(|editor| {
+                editor
+                    .borrow_mut()
+                    .editor_mut()
+                    .buffer_mut()
+                    .restore_if_evicted()
+            })
+            .cloned()
+            .collect_vec();
+
+        // Buffers that were never visible (e.g. opened by a workspace edit) are evicted first
+        let least_recently_viewed = self
+            .background_suggestive_editors
+            .iter()
+            .sorted_by_key(|(path, _)| {
+                std::cmp::Reverse(self.buffer_view_ticks.get(*path).copied().unwrap_or(0))
+            })
+            .skip(keep)
+            .filter(|(path, _)| !visible_paths.contains(path));
+        for (_, editor) in least_recently_viewed {
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .evict(undo_history_limit)?;
+        }
+        Ok(restored)
+    }
+
     fn show_info_on(
         &mut self,
         node_id: NodeId,
         info: Info,
         kind: ComponentKind,
     ) -> anyhow::Result<()> {
-        let info_panel = Rc::new(RefCell::new(Editor::from_text(None, "")));
-        info_panel.borrow_mut().show_info(info)?;
+        // The existing panel is kept if its info is unchanged, so that its scroll position is kept
+        let is_unchanged = self
+            .tree
+            .get_node_child_component(node_id, kind)
+            .is_some_and(|component| {
+                component
+                    .borrow_mut()
+                    .as_any_mut()
+                    .downcast_mut::<Pager>()
+                    .is_some_and(|pager| pager.info() == &info)
+            });
+        if is_unchanged {
+            return Ok(());
+        }
+        let info_panel = Rc::new(RefCell::new(Pager::new(info)?));
         self.tree
             .replace_node_child(node_id, kind, info_panel, false);
         Ok(())
@@ -212,6 +390,11 @@ impl Layout {
         )
     }
 
+    pub(crate) fn show_peek(&mut self, peek: Rc<RefCell<Peek>>) {
+        self.tree
+            .replace_current_node_child(ComponentKind::Peek, peek, true);
+    }
+
     pub(crate) fn remain_only_current_component(&mut self) {
         self.tree.remain_only_current_component()
     }
@@ -235,33 +418,199 @@ impl Layout {
         &mut self,
         path: &CanonicalizedPath,
     ) -> anyhow::Result<Dispatches> {
-        let dispatches = self.background_file_explorer.borrow_mut().reveal(path)?;
-        self.open_file_explorer();
+        let dispatches = self.file_explorer()?.borrow_mut().reveal(path)?;
+        self.open_file_explorer()?;
 
         Ok(dispatches)
     }
 
+    /// Reveals `path` in the file explorer without focusing it, if the file explorer is opened.
+    pub(crate) fn follow_path_in_explorer(
+        &mut self,
+        path: &CanonicalizedPath,
+    ) -> anyhow::Result<()> {
+        let Some(file_explorer) = &self.background_file_explorer else {
+            return Ok(());
+        };
+        if self
+            .tree
+            .get_component_by_kind(ComponentKind::FileExplorer)
+            .is_none()
+        {
+            return Ok(());
+        }
+        // The dispatches are discarded, because they are meant for the focused component,
+        // which is not the file explorer
+        let _ = file_explorer.borrow_mut().reveal(path)?;
+        Ok(())
+    }
+
     pub(crate) fn remove_suggestive_editor(&mut self, path: &CanonicalizedPath) {
         self.background_suggestive_editors.shift_remove(path);
     }
 
+    pub(crate) fn scratch_editors(&self) -> &[Rc<RefCell<SuggestiveEditor>>] {
+        &self.scratch_editors
+    }
+
+    pub(crate) fn add_scratch_editor(&mut self, editor: Rc<RefCell<SuggestiveEditor>>) {
+        self.scratch_editors.push(editor.clone());
+        self.replace_and_focus_current_suggestive_editor(editor)
+    }
+
+    pub(crate) fn focus_scratch_editor(&mut self, index: usize) -> anyhow::Result<()> {
+        let editor = self
+            .scratch_editors
+            .get(index)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("There is no scratch buffer {}", index + 1))?;
+        self.replace_and_focus_current_suggestive_editor(editor);
+        Ok(())
+    }
+
+    /// Moves the scratch editors which are saved to a path to the editors of files.
+    pub(crate) fn store_saved_scratch_editors(&mut self) {
+        let (saved, scratch) = std::mem::take(&mut self.scratch_editors)
+            .into_iter()
+            .partition::<Vec<_>, _>(|editor| editor.borrow().path().is_some());
+        self.scratch_editors = scratch;
+        for editor in saved {
+            self.add_suggestive_editor(editor)
+        }
+    }
+
+    /// Keeps the editor of `from` at the same position, after its buffer is moved to `to`.
+    pub(crate) fn rename_suggestive_editor(
+        &mut self,
+        from: &CanonicalizedPath,
+        to: CanonicalizedPath,
+    ) {
+        self.background_suggestive_editors =
+            std::mem::take(&mut self.background_suggestive_editors)
+                .into_iter()
+                .map(|(path, editor)| {
+                    if &path == from {
+                        (to.clone(), editor)
+                    } else {
+                        (path, editor)
+                    }
+                })
+                .collect();
+    }
+
+    /// Remaps the editors of the files under the directory `from`, which has been moved to `to`,
+    /// so that they are kept open at their new paths.
+    pub(crate) fn move_suggestive_editors(
+        &mut self,
+        from: &CanonicalizedPath,
+        to: &CanonicalizedPath,
+    ) -> anyhow::Result<()> {
+        let editors = self
+            .background_suggestive_editors
+            .iter()
+            .map(|(path, editor)| {
+                let Ok(relative) = path.to_path_buf().strip_prefix(from.to_path_buf()) else {
+                    return Ok((path.clone(), editor.clone()));
+                };
+                let path: CanonicalizedPath = to.to_path_buf().join(relative).try_into()?;
+                Ok((path, editor.clone()))
+            })
+            .collect::<anyhow::Result<IndexMap<_, _>>>()?;
+        for (path, editor) in &editors {
+            editor
+                .borrow_mut()
+                .editor_mut()
+                .buffer_mut()
+                .set_path(path.clone());
+        }
+        self.background_suggestive_editors = editors;
+        Ok(())
+    }
+
     pub(crate) fn refresh_file_explorer(
         &self,
         working_directory: &CanonicalizedPath,
     ) -> anyhow::Result<()> {
-        self.background_file_explorer
-            .borrow_mut()
-            .refresh(working_directory)
+        // A file explorer that is not built yet will list the latest files when it is built
+        match &self.background_file_explorer {
+            Some(file_explorer) => file_explorer.borrow_mut().refresh(working_directory),
+            None => Ok(()),
+        }
     }
 
-    pub(crate) fn open_file_explorer(&mut self) {
+    /// A file explorer that is already built lists the new working directory in place,
+    /// so that it stays opened and focused if it is.
+    pub(crate) fn set_working_directory(
+        &mut self,
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<()> {
+        self.working_directory = working_directory.clone();
+        if let Some(file_explorer) = &self.background_file_explorer {
+            *file_explorer.borrow_mut() = FileExplorer::new(working_directory)?;
+        }
+        Ok(())
+    }
+
+    /// See `FileExplorer::refresh_paths`.
+    pub(crate) fn refresh_file_explorer_paths(
+        &self,
+        working_directory: &CanonicalizedPath,
+        paths: &[&Path],
+    ) -> anyhow::Result<()> {
+        match &self.background_file_explorer {
+            Some(file_explorer) => file_explorer
+                .borrow_mut()
+                .refresh_paths(working_directory, paths),
+            None => Ok(()),
+        }
+    }
+
+    /// Builds the file explorer on first use, instead of on startup,
+    /// because listing the working directory and parsing the listing slow down startup.
+    fn file_explorer(&mut self) -> anyhow::Result<Rc<RefCell<FileExplorer>>> {
+        if let Some(file_explorer) = &self.background_file_explorer {
+            return Ok(file_explorer.clone());
+        }
+        let file_explorer = Rc::new(RefCell::new(FileExplorer::new(&self.working_directory)?));
+        self.background_file_explorer = Some(file_explorer.clone());
+        Ok(file_explorer)
+    }
+
+    pub(crate) fn open_file_explorer(&mut self) -> anyhow::Result<()> {
+        let file_explorer = self.file_explorer()?;
         self.tree.remove_all_root_children();
+        self.tree
+            .replace_root_node_child(ComponentKind::FileExplorer, file_explorer, true);
+        debug_assert_eq!(self.tree.root().children().count(), 1);
+        Ok(())
+    }
+
+    pub(crate) fn open_outline(&mut self) -> anyhow::Result<()> {
         self.tree.replace_root_node_child(
-            ComponentKind::FileExplorer,
-            self.background_file_explorer.clone(),
+            ComponentKind::Outline,
+            self.background_outline.clone(),
             true,
         );
-        debug_assert_eq!(self.tree.root().children().count(), 1);
+        self.refresh_outline()
+    }
+
+    /// Updates the outline with the main editor, if the outline is opened.
+    pub(crate) fn refresh_outline(&self) -> anyhow::Result<()> {
+        if self
+            .tree
+            .get_component_by_kind(ComponentKind::Outline)
+            .is_none()
+        {
+            return Ok(());
+        }
+        let Some(editor) = self
+            .tree
+            .get_component_by_kind(ComponentKind::SuggestiveEditor)
+        else {
+            return Ok(());
+        };
+        let editor = editor.borrow();
+        self.background_outline.borrow_mut().track(editor.editor())
     }
 
     pub(crate) fn update_highlighted_spans(
@@ -310,6 +659,45 @@ impl Layout {
         Ok(())
     }
 
+    /// Reloads the buffers whose files were modified by another program.
+    /// Returns the dispatches of the reloaded buffers,
+    /// and the paths of the buffers that are not reloaded because they have unsaved changes.
+    pub(crate) fn reload_externally_changed_buffers(
+        &self,
+    ) -> anyhow::Result<(Dispatches, Vec<CanonicalizedPath>)> {
+        let mut dispatches = Dispatches::default();
+        let mut conflicts = Vec::new();
+        for (path, editor) in &self.background_suggestive_editors {
+            let mut editor = editor.borrow_mut();
+            let change = editor
+                .editor_mut()
+                .buffer_mut()
+                .reload_if_changed_externally()?;
+            match change {
+                ExternalChange::Unchanged => {}
+                ExternalChange::Reloaded => {
+                    dispatches =
+                        dispatches.chain(editor.editor_mut().get_document_did_change_dispatch())
+                }
+                ExternalChange::Conflict => conflicts.push(path.clone()),
+            }
+        }
+        Ok((dispatches, conflicts))
+    }
+
+    /// Saves the buffers with unsaved changes, without formatting them.
+    /// Returns the paths of the saved buffers.
+    pub(crate) fn save_modified_buffers(&self) -> anyhow::Result<Vec<CanonicalizedPath>> {
+        self.buffers()
+            .into_iter()
+            .filter(|buffer| {
+                let buffer = buffer.borrow();
+                buffer.is_modified() && !buffer.read_only()
+            })
+            .filter_map(|buffer| buffer.borrow_mut().save_without_formatting().transpose())
+            .collect()
+    }
+
     #[cfg(test)]
     pub(crate) fn completion_dropdown_is_open(&self) -> bool {
         self.current_completion_dropdown().is_some()
@@ -343,6 +731,22 @@ impl Layout {
         self.tree.remove_current_child(ComponentKind::Dropdown);
     }
 
+    /// Returns false if no visible component has the id of `component_id`.
+    pub(crate) fn focus_component(&mut self, component_id: ComponentId) -> bool {
+        self.tree.focus_component(component_id)
+    }
+
+    /// Returns false if the focused component has no editor info.
+    pub(crate) fn focus_editor_info(&mut self) -> bool {
+        match self.get_current_node_child_id(ComponentKind::EditorInfo) {
+            Some(id) => {
+                self.tree.set_focus_component_id(id);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub(crate) fn close_editor_info(&mut self) {
         self.tree.remove_current_child(ComponentKind::EditorInfo);
     }
@@ -376,6 +780,7 @@ impl Layout {
     pub(crate) fn show_quickfix_list(
         &mut self,
         quickfix_list: QuickfixList,
+        preview: Option<QuickfixListPreview>,
     ) -> anyhow::Result<Dispatches> {
         let render = quickfix_list.render();
         let editor = self.background_quickfix_list.get_or_insert_with(|| {
@@ -392,12 +797,30 @@ impl Layout {
             let mut editor = editor.borrow_mut();
             editor.set_content(&render.content)?;
             editor.set_decorations(&render.decorations);
-            editor.set_title("Quickfix list".to_string());
+            editor.set_title(format!("Quickfix list: {}", render.title));
             editor.select_line_at(render.highlight_line_index)?
         };
         if let Some(info) = render.info {
             self.show_info_on(node_id, info, ComponentKind::QuickfixListInfo)?;
         }
+        match preview {
+            Some(preview) => {
+                let mut editor = Editor::from_text(preview.language, &preview.content);
+                editor.set_title(preview.title);
+                editor.set_line_number_offset(preview.first_line_index);
+                editor.highlight_line_at(preview.highlight_line_index)?;
+                self.tree.replace_node_child(
+                    node_id,
+                    ComponentKind::QuickfixListPreview,
+                    Rc::new(RefCell::new(editor)),
+                    false,
+                );
+            }
+            None => {
+                self.tree
+                    .remove_node_child(node_id, ComponentKind::QuickfixListPreview);
+            }
+        }
         Ok(dispatches)
     }
 
@@ -445,7 +868,10 @@ impl Layout {
 
     #[cfg(test)]
     pub(crate) fn file_explorer_content(&self) -> String {
-        self.background_file_explorer.borrow().content()
+        self.background_file_explorer
+            .as_ref()
+            .map(|file_explorer| file_explorer.borrow().content())
+            .unwrap_or_default()
     }
 
     pub(crate) fn get_quickfix_list_items(
@@ -484,14 +910,17 @@ impl Layout {
                         .bookmarks()
                         .into_iter()
                         .filter_map(|bookmark| {
-                            let position_range =
-                                buffer.char_index_range_to_position_range(bookmark).ok()?;
+                            let position_range = buffer
+                                .char_index_range_to_position_range(bookmark.range)
+                                .ok()?;
                             Some(QuickfixListItem::new(
                                 Location {
                                     path: buffer.path()?,
                                     range: position_range,
                                 },
-                                None,
+                                bookmark
+                                    .note
+                                    .map(|note| Info::new("Bookmark".to_string(), note)),
                             ))
                         })
                         .collect_vec(),
@@ -507,6 +936,43 @@ impl Layout {
         }
     }
 
+    /// The locations of the items removed from the current quickfix list,
+    /// see `Buffer::remove_quickfix_list_item`.
+    pub(crate) fn removed_quickfix_list_locations(&self) -> Vec<Location> {
+        self.buffers()
+            .into_iter()
+            .flat_map(|buffer| {
+                let buffer = buffer.borrow();
+                let Some(path) = buffer.path() else {
+                    return Vec::new();
+                };
+                buffer
+                    .removed_quickfix_list_ranges()
+                    .into_iter()
+                    .map(|range| Location {
+                        path: path.clone(),
+                        range,
+                    })
+                    .collect_vec()
+            })
+            .collect_vec()
+    }
+
+    /// Replaces the removed items of the current quickfix list of every buffer.
+    pub(crate) fn set_removed_quickfix_list_locations(&mut self, locations: &[Location]) {
+        for buffer in self.buffers() {
+            let mut buffer = buffer.borrow_mut();
+            let path = buffer.path();
+            buffer.set_removed_quickfix_list_ranges(
+                locations
+                    .iter()
+                    .filter(|location| Some(&location.path) == path.as_ref())
+                    .map(|location| location.range.clone())
+                    .collect_vec(),
+            )
+        }
+    }
+
     pub(crate) fn replace_and_focus_current_suggestive_editor(
         &mut self,
         editor: Rc<RefCell<SuggestiveEditor>>,
@@ -554,3 +1020,34 @@ fn layout_kind(terminal_dimension: &Dimension) -> (LayoutKind, f32) {
         (LayoutKind::Wide, 0.80)
     }
 }
+
+fn layout_presets_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    crate::persistence::workspace_file("layout_presets", working_directory)
+}
+
+/// Returns the layout presets saved for `working_directory`, by their names.
+pub(crate) fn load_layout_presets(
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<BTreeMap<String, LayoutPreset>> {
+    let path = layout_presets_path(working_directory);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+/// Saves `preset` for `working_directory`, replacing the existing preset of the same name.
+pub(crate) fn save_layout_preset(
+    working_directory: &CanonicalizedPath,
+    name: &str,
+    preset: LayoutPreset,
+) -> anyhow::Result<()> {
+    let mut presets = load_layout_presets(working_directory)?;
+    presets.insert(name.to_string(), preset);
+    let path = layout_presets_path(working_directory);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(&presets)?)?;
+    Ok(())
+}
@@ -0,0 +1,285 @@
+mod bookmark;
+mod buffer;
+mod git;
+
+pub(crate) mod char_index_range;
+mod cli;
+mod clipboard;
+mod color_literal;
+pub(crate) mod command;
+mod components;
+mod context;
+mod edit;
+mod error_format;
+mod export;
+mod expression;
+pub(crate) mod frontend;
+mod grid;
+mod increment;
+mod injection;
+#[cfg(test)]
+mod integration_test;
+mod keyboard_layout;
+
+mod layout;
+pub(crate) mod list;
+mod local_completion;
+mod lsp;
+mod position;
+
+mod app;
+#[doc(hidden)]
+pub mod benchmark;
+pub(crate) mod history;
+mod non_empty_extensions;
+mod notes;
+mod path_or_url;
+mod permalink;
+mod persistence;
+mod process_completion_source;
+mod project_config;
+mod prompt_history;
+mod quickfix_list;
+mod rectangle;
+mod save;
+mod screen;
+mod search_toggles;
+mod selection;
+pub(crate) mod selection_mode;
+pub(crate) mod selection_range;
+mod shell;
+pub(crate) mod soft_wrap;
+mod startup_profile;
+pub(crate) mod style;
+pub(crate) mod surround;
+pub(crate) mod syntax_highlight;
+mod terminal;
+#[cfg(test)]
+mod test_app;
+mod test_runner;
+pub(crate) mod themes;
+pub(crate) mod transformation;
+pub(crate) mod tree_sitter_traversal;
+mod tutor;
+pub(crate) mod ui_tree;
+mod undo_history;
+pub(crate) mod undo_tree;
+mod unicode_input;
+mod user_config;
+mod utils;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use frontend::crossterm::Crossterm;
+use log::LevelFilter;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use app::App;
+use keyboard_layout::KeyboardLayout;
+
+use crate::app::AppMessage;
+
+/// Entry point of the `ki` binary.
+pub fn main() -> anyhow::Result<()> {
+    cli::cli()
+}
+
+#[derive(Default)]
+pub(crate) struct RunConfig {
+    pub(crate) entry_path: Option<CanonicalizedPath>,
+    pub(crate) working_directory: Option<CanonicalizedPath>,
+    pub(crate) tutor: bool,
+    pub(crate) show_fps: bool,
+    pub(crate) profile_startup: bool,
+}
+
+/// How long the terminal is polled for events before checking whether the reading is paused.
+const INPUT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+pub(crate) fn run(config: RunConfig) -> anyhow::Result<()> {
+    let mut startup_profile = config
+        .profile_startup
+        .then(startup_profile::StartupProfile::new);
+    std::fs::create_dir_all(grammar::cache_dir()).context("Failed to create cache_dir")?;
+    simple_logging::log_to_file(grammar::default_log_file(), LevelFilter::Info)?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let syntax_highlighter_sender = syntax_highlight::start_thread(sender.clone());
+    let working_directory: CanonicalizedPath = config.working_directory.unwrap_or(".".try_into()?);
+    // An invalid project configuration should not prevent opening the project, for example to fix it,
+    // so the default settings are used instead, and the error is shown once Ki is started
+    let (project_config, project_config_error) =
+        match project_config::ProjectConfig::load(&working_directory) {
+            Ok(project_config) => (project_config, None),
+            Err(error) => {
+                log::error!("Failed to load the project configuration: {:?}", error);
+                (Default::default(), Some(error))
+            }
+        };
+    let editor_config = project_config.editor;
+    let commands = user_config::UserConfig::load()?.commands;
+    let mut frontend = Crossterm::default();
+    if let Some(color) = editor_config.color {
+        frontend.set_color_support(
+            color
+                .parse()
+                .context("editor.color should be truecolor, 256 or 16")?,
+        );
+    }
+    let persistence = persistence::Persistence::new(working_directory.clone())
+        .set_undo_history(editor_config.persist_undo == Some(true))
+        .set_backup_count(editor_config.backup_count.unwrap_or_default());
+    if persistence.undo_history() {
+        if let Err(error) = undo_history::collect_garbage() {
+            log::error!("Failed to collect undo histories: {:?}", error);
+        }
+    }
+    let mut app = App::from_channel(
+        Arc::new(Mutex::new(frontend)),
+        working_directory,
+        sender,
+        receiver,
+    )?;
+    app.set_persistence(persistence);
+    if let Some(error) = project_config_error {
+        app.handle_dispatch(app::Dispatch::ShowGlobalInfo(
+            components::suggestive_editor::Info::new(
+                "Invalid .ki/config.toml, the default settings are used".to_string(),
+                format!("{error:#}"),
+            ),
+        ))?;
+    }
+    if let Some(profile) = startup_profile.as_mut() {
+        profile.record("persistence")
+    }
+    app.set_syntax_highlight_request_sender(syntax_highlighter_sender);
+    match KeyboardLayout::load() {
+        Ok(keyboard_layout) => app.set_keyboard_layout(keyboard_layout),
+        Err(error) => log::error!("Failed to load keyboard layout: {:?}", error),
+    }
+    if let Some(command) = commands.completion_source {
+        app.set_completion_source(Box::new(
+            process_completion_source::ProcessCompletionSource::new(&command, app.sender())?,
+        ));
+    }
+
+    if let Some(notes_directory) = editor_config.notes_directory {
+        app.set_notes_directory(notes_directory);
+    }
+
+    if let Some(url_opener) = commands.url_opener {
+        app.set_url_opener(url_opener);
+    }
+
+    if let Some(elevation_command) = commands.elevation_command {
+        app.set_elevation_command(elevation_command);
+    }
+
+    app.set_test_commands(commands.tests);
+
+    if let Some(terminal_title) = editor_config.terminal_title {
+        app.set_terminal_title_enabled(terminal_title);
+    }
+
+    if let Some(loaded_buffers_limit) = editor_config.loaded_buffers_limit {
+        app.set_loaded_buffers_limit(loaded_buffers_limit);
+    }
+
+    if let Some(autosave) = editor_config.autosave_on_focus_lost {
+        app.set_autosave_on_focus_lost(autosave);
+    }
+
+    if let Some(sticky_context) = editor_config.sticky_context {
+        app.set_sticky_context(sticky_context);
+    }
+
+    if let Some(auto_center) = editor_config.auto_center {
+        app.set_auto_center(auto_center);
+    }
+
+    if let Some(explorer_auto_follow) = editor_config.explorer_auto_follow {
+        app.set_explorer_auto_follow(explorer_auto_follow);
+    }
+
+    if let Some(scroll_lines) = editor_config.scroll_lines {
+        app.set_scroll_lines(scroll_lines.max(1));
+    }
+
+    if let Some(breadcrumbs) = editor_config.breadcrumbs {
+        app.set_breadcrumbs(breadcrumbs);
+    }
+
+    if let Some(inline_diagnostics) = editor_config.inline_diagnostics {
+        app.set_inline_diagnostics(inline_diagnostics);
+    }
+
+    if let Some(jump_labels) = editor_config.jump_labels {
+        app.set_jump_label_strategy(
+            jump_labels
+                .parse()
+                .context("editor.jump_labels should be first-char, word-starts or two-chars")?,
+        );
+    }
+
+    for error_format in &editor_config.error_formats {
+        app.add_error_format(error_format)?;
+    }
+
+    app.set_show_fps(config.show_fps);
+    if let Some(profile) = startup_profile.as_mut() {
+        profile.record("config");
+        // Grammars are loaded lazily by the syntax highlighter thread,
+        // so the grammar of the entry file is loaded here only to be measured
+        if let Some(language) = config
+            .entry_path
+            .as_ref()
+            .and_then(shared::language::from_path)
+        {
+            use syntax_highlight::GetHighlightConfig;
+            if let Err(error) = language.get_highlight_config() {
+                log::error!("Failed to load grammar: {:?}", error)
+            }
+        }
+        profile.record("grammars");
+        app.set_startup_profile(profile.clone());
+    }
+    if config.tutor {
+        app.start_tutor();
+    }
+
+    let sender = app.sender();
+    let input_gate = app.input_gate();
+
+    let crossterm_join_handle = std::thread::spawn(move || loop {
+        // Events are polled with a timeout, so that `InputGate::pause` does not wait for the next key
+        let event = input_gate.read(|| {
+            crossterm::event::poll(INPUT_POLL_INTERVAL)?
+                .then(crossterm::event::read)
+                .transpose()
+        });
+        match event {
+            None => std::thread::sleep(INPUT_POLL_INTERVAL),
+            Some(Ok(None)) => continue,
+            // Some terminals (e.g. on Windows) also report the release of each key,
+            // including the keys that commit an IME composition,
+            // which would otherwise insert every committed character twice
+            Some(Ok(Some(crossterm::event::Event::Key(crossterm::event::KeyEvent {
+                kind: crossterm::event::KeyEventKind::Release,
+                ..
+            })))) => continue,
+            Some(Ok(Some(event))) => {
+                if sender.send(AppMessage::Event(event.into())).is_err() {
+                    break;
+                }
+            }
+            Some(Err(_)) => break,
+        }
+    });
+
+    app.run(config.entry_path)
+        .map_err(|error| anyhow::anyhow!("screen.run {:?}", error))?;
+
+    crossterm_join_handle.join().unwrap();
+
+    Ok(())
+}
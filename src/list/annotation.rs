@@ -0,0 +1,171 @@
+use std::ops::Range;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tree_sitter::Tree;
+
+use crate::{
+    buffer::Buffer,
+    components::suggestive_editor::Info,
+    grid::StyleKey,
+    quickfix_list::{Location, QuickfixListItem},
+};
+
+use super::WalkBuilderConfig;
+
+/// The tag of an annotation comment, such as `// TODO: handle errors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tag {
+    Todo,
+    Fixme,
+    Hack,
+    Note,
+}
+
+impl Tag {
+    fn from_str(tag: &str) -> Option<Tag> {
+        match tag {
+            "TODO" => Some(Tag::Todo),
+            "FIXME" => Some(Tag::Fixme),
+            "HACK" => Some(Tag::Hack),
+            "NOTE" => Some(Tag::Note),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Tag::Todo => "TODO",
+            Tag::Fixme => "FIXME",
+            Tag::Hack => "HACK",
+            Tag::Note => "NOTE",
+        }
+    }
+
+    pub(crate) fn style_key(&self) -> StyleKey {
+        StyleKey::Syntax(
+            match self {
+                Tag::Todo => "comment.todo",
+                Tag::Fixme | Tag::Hack => "comment.warning",
+                Tag::Note => "comment.note",
+            }
+            .to_string(),
+        )
+    }
+}
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(TODO|FIXME|HACK|NOTE)\b").unwrap());
+
+/// Returns the tag and the byte range of each annotation tag in `text`.
+pub(crate) fn find_tags(text: &str) -> impl Iterator<Item = (Tag, Range<usize>)> + '_ {
+    TAG_REGEX
+        .find_iter(text)
+        .filter_map(|match_| Some((Tag::from_str(match_.as_str())?, match_.range())))
+}
+
+/// Returns true if `byte` is within a comment node of `tree`.
+fn is_in_comment(tree: &Tree, byte: usize) -> bool {
+    let mut node = tree.root_node().descendant_for_byte_range(byte, byte + 1);
+    while let Some(current) = node {
+        if current.kind().contains("comment") {
+            return true;
+        }
+        node = current.parent();
+    }
+    false
+}
+
+/// Returns the annotations in the comments of `buffer`.
+/// Every tag is considered an annotation if the buffer has no syntax tree.
+fn annotations(buffer: &Buffer) -> anyhow::Result<Vec<QuickfixListItem>> {
+    let Some(path) = buffer.path() else {
+        return Ok(Vec::new());
+    };
+    let content = buffer.content();
+    find_tags(&content)
+        .filter(|(_, range)| {
+            buffer
+                .tree()
+                .map(|tree| is_in_comment(tree, range.start))
+                .unwrap_or(true)
+        })
+        .map(|(tag, range)| {
+            let location = Location {
+                path: path.clone(),
+                range: buffer.byte_to_position(range.start)?..buffer.byte_to_position(range.end)?,
+            };
+            // The text after the tag, such as `handle errors` of `TODO: handle errors`
+            let description = content[range.end..]
+                .lines()
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches([':', ' '])
+                .trim()
+                .to_string();
+            Ok(QuickfixListItem::new(
+                location,
+                Some(Info::new(tag.as_str().to_string(), description)),
+            )
+            .set_group(format!(
+                "{} · {}",
+                tag.as_str(),
+                path.display_relative()
+                    .unwrap_or_else(|_| path.display_absolute())
+            )))
+        })
+        .collect()
+}
+
+/// Collects the TODO, FIXME, HACK and NOTE comments of the files walked by `walk_builder_config`.
+pub(crate) fn run(walk_builder_config: WalkBuilderConfig) -> anyhow::Result<Vec<QuickfixListItem>> {
+    Ok(walk_builder_config
+        .run(Box::new(|path, sender| {
+            // Only files with tags are parsed,
+            // because parsing every file with Tree-sitter is slow
+            let Ok(content) = std::fs::read_to_string(&path) else {
+                return Ok(());
+            };
+            if !TAG_REGEX.is_match(&content) {
+                return Ok(());
+            }
+            let buffer = Buffer::from_path(&path.try_into()?, true)?;
+            let _ = sender.send(annotations(&buffer)?).map_err(|error| {
+                log::error!("sender.send {:?}", error);
+            });
+            Ok(())
+        }))?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+#[cfg(test)]
+mod test_annotation {
+    use super::*;
+
+    #[test]
+    fn only_tags_in_comments_are_annotations() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("main.rs");
+        std::fs::write(
+            &path,
+            "// TODO: handle errors\nfn main() {\n    let TODO = 1; // NOTE: unused\n}\n",
+        )?;
+        let buffer = Buffer::from_path(&path.try_into()?, true)?;
+        let annotations = annotations(&buffer)?;
+        let tags = annotations
+            .iter()
+            .map(|item| {
+                (
+                    item.info().as_ref().unwrap().title(),
+                    item.location().range.start.line,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tags,
+            [("TODO".to_string(), 0), ("NOTE".to_string(), 2)].to_vec()
+        );
+        Ok(())
+    }
+}
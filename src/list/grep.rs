@@ -1,17 +1,19 @@
-use grep_regex::RegexMatcher;
-use grep_searcher::{sinks, SearcherBuilder};
+use std::path::{Path, PathBuf};
+
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkMatch};
 
 use regex::Regex;
 
 use crate::{
-    buffer::Buffer, context::LocalSearchConfig, quickfix_list::Location,
-    selection_mode::regex::get_regex,
+    buffer::Buffer, context::LocalSearchConfig, persistence::Persistence, position::Position,
+    quickfix_list::Location, selection_mode::regex::get_regex,
 };
 use shared::canonicalized_path::CanonicalizedPath;
 
 use super::WalkBuilderConfig;
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, serde::Serialize, serde::Deserialize)]
 pub(crate) struct RegexConfig {
     pub(crate) escaped: bool,
     pub(crate) case_sensitive: bool,
@@ -37,11 +39,13 @@ impl Default for RegexConfig {
 pub(crate) fn replace(
     walk_builder_config: WalkBuilderConfig,
     local_search_config: LocalSearchConfig,
+    persistence: Persistence,
 ) -> anyhow::Result<Vec<CanonicalizedPath>> {
     Ok(walk_builder_config
         .run(Box::new(move |path, sender| {
             let path = path.try_into()?;
             let mut buffer = Buffer::from_path(&path, local_search_config.require_tree_sitter())?;
+            buffer.set_persistence(persistence.clone());
             let (modified, _) = buffer.replace(local_search_config.clone(), Default::default())?;
             if modified {
                 buffer.save_without_formatting()?;
@@ -56,68 +60,244 @@ pub(crate) fn replace(
         .collect())
 }
 
+/// A file that could not be searched, e.g. because it is unreadable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileError {
+    pub(crate) path: PathBuf,
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct GrepOutput {
+    pub(crate) locations: Vec<Location>,
+    pub(crate) errors: Vec<FileError>,
+}
+
 pub(crate) fn run(
     pattern: &str,
     walk_builder_config: WalkBuilderConfig,
     grep_config: RegexConfig,
+) -> anyhow::Result<GrepOutput> {
+    let matcher = line_matcher(pattern, grep_config)?;
+    let regex = get_regex(pattern, grep_config)?;
+
+    let results = walk_builder_config.run(Box::new(move |path, sender| {
+        let result = search_file(&path, &matcher, &regex).map_err(|error| FileError {
+            path,
+            message: error.to_string(),
+        });
+        Ok(sender.send(result)?)
+    }))?;
+    let mut output = GrepOutput::default();
+    for result in results {
+        match result {
+            Ok(locations) => output.locations.extend(locations),
+            Err(error) => output.errors.push(error),
+        }
+    }
+    Ok(output)
+}
+
+/// Finds the lines that match `pattern`.
+///
+/// Patterns without regex metacharacters are searched as literals,
+/// which is much faster than running the regex engine on every line.
+fn line_matcher(pattern: &str, config: RegexConfig) -> anyhow::Result<RegexMatcher> {
+    let is_literal = config.escaped || regex::escape(pattern) == pattern;
+    // Matching whole words is left to the regex engine,
+    // because the word boundaries of `RegexMatcherBuilder::word` differ from `\b`
+    if is_literal && !config.match_whole_word {
+        Ok(RegexMatcherBuilder::new()
+            .case_insensitive(!config.case_sensitive)
+            .line_terminator(Some(b'\n'))
+            .build_literals(&[pattern])?)
+    } else {
+        let pattern = get_regex(pattern, config)?.as_str().to_string();
+        Ok(RegexMatcher::new_line_matcher(&pattern)?)
+    }
+}
+
+/// Binary files are skipped, they are detected by the presence of NUL bytes like ripgrep does.
+fn search_file(
+    path: &Path,
+    matcher: &RegexMatcher,
+    regex: &Regex,
 ) -> anyhow::Result<Vec<Location>> {
-    let pattern = get_regex(pattern, grep_config)?.as_str().to_string();
-    let matcher = RegexMatcher::new_line_matcher(&pattern)?;
-    let regex = Regex::new(&pattern)?;
+    let mut matched_lines = MatchedLines::default();
+    SearcherBuilder::new()
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build()
+        .search_path(matcher, path, &mut matched_lines)?;
+    if matched_lines.is_binary || matched_lines.lines.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    Ok(walk_builder_config
-        .run(Box::new(move |path, sender| {
-            let path = path.try_into()?;
-            let buffer = Buffer::from_path(&path, false)?;
-            // Tree-sitter should be disabled whenever possible during
-            // global search, because it will slow down the operation tremendously
-            debug_assert!(buffer.tree().is_none());
-            let mut searcher = SearcherBuilder::new().build();
-            searcher.search_path(
-                &matcher,
-                path.clone(),
-                sinks::UTF8(|line_number, line| {
-                    if let Ok(location) = to_location(
-                        &buffer,
-                        path.clone(),
-                        line_number as usize,
-                        line,
-                        regex.clone(),
-                    ) {
-                        let _ = sender.send(location).map_err(|error| {
-                            log::error!("sender.send {:?}", error);
-                        });
-                    }
-                    Ok(true)
-                }),
-            )?;
-            Ok(())
-        }))?
+    let path: CanonicalizedPath = path.try_into()?;
+    Ok(matched_lines
+        .lines
         .into_iter()
-        .flatten()
+        .flat_map(|(line_number, line)| to_locations(&path, line_number as usize, &line, regex))
         .collect())
 }
 
-fn to_location(
-    buffer: &Buffer,
-    path: CanonicalizedPath,
+#[derive(Default)]
+struct MatchedLines {
+    /// The line number (1-based) and the content of each matched line.
+    lines: Vec<(u64, String)>,
+    is_binary: bool,
+}
+
+impl Sink for MatchedLines {
+    type Error = std::io::Error;
+
+    /// Invalid UTF-8 is replaced, so that one such line does not prevent the rest of the file
+    /// from being searched.
+    fn matched(&mut self, _: &Searcher, matched: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        self.lines.push((
+            matched.line_number().unwrap_or_default(),
+            String::from_utf8_lossy(matched.bytes()).to_string(),
+        ));
+        Ok(true)
+    }
+
+    fn binary_data(&mut self, _: &Searcher, _: u64) -> Result<bool, Self::Error> {
+        self.is_binary = true;
+        Ok(false)
+    }
+}
+
+/// The columns of the positions are counted in characters of `line`,
+/// so the file does not have to be loaded.
+fn to_locations(
+    path: &CanonicalizedPath,
     line_number: usize,
     line: &str,
-    regex: Regex,
-) -> anyhow::Result<Vec<Location>> {
-    let start_byte = buffer.line_to_byte(line_number.saturating_sub(1))?;
-    let locations = regex
+    regex: &Regex,
+) -> Vec<Location> {
+    let position = |byte: usize| Position {
+        line: line_number.saturating_sub(1),
+        column: line[..byte].chars().count(),
+    };
+    regex
         .find_iter(line)
-        .flat_map(|match_| -> anyhow::Result<Location> {
-            let range = match_.range();
-            let start = buffer.byte_to_position(range.start + start_byte)?;
-            let end = buffer.byte_to_position(range.end + start_byte)?;
-            Ok(Location {
-                range: start..end,
-                path: path.clone(),
-            })
+        .map(|match_| Location {
+            range: position(match_.start())..position(match_.end()),
+            path: path.clone(),
         })
-        .collect();
+        .collect()
+}
+
+#[cfg(test)]
+mod test_grep {
+    use itertools::Itertools;
+
+    use crate::list::WalkBuilderConfig;
 
-    Ok(locations)
+    use super::{run, RegexConfig};
+
+    fn grep(
+        files: &[(&str, &[u8])],
+        pattern: &str,
+        config: RegexConfig,
+    ) -> anyhow::Result<(Vec<String>, Vec<String>)> {
+        let temp_dir = tempfile::tempdir()?;
+        for (path, content) in files {
+            std::fs::write(temp_dir.path().join(path), content)?;
+        }
+        let root = shared::canonicalized_path::CanonicalizedPath::try_from(temp_dir.path())?;
+        let output = run(pattern, WalkBuilderConfig::new(&root, None, None)?, config)?;
+        let locations = output
+            .locations
+            .into_iter()
+            .map(|location| {
+                format!(
+                    "{}:{}:{}",
+                    location.path.display_relative_to(&root).unwrap(),
+                    location.range.start.line,
+                    location.range.start.column
+                )
+            })
+            .sorted()
+            .collect_vec();
+        let errors = output
+            .errors
+            .into_iter()
+            .map(|error| {
+                error
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect_vec();
+        Ok((locations, errors))
+    }
+
+    #[test]
+    fn literal_pattern() -> anyhow::Result<()> {
+        let files: &[(&str, &[u8])] = &[("a.txt", b"a.b\naxb\nA.B")];
+        let escaped = RegexConfig::default();
+        assert_eq!(
+            grep(files, "a.b", escaped)?,
+            (
+                vec!["a.txt:0:0".to_string(), "a.txt:2:0".to_string()],
+                vec![]
+            )
+        );
+        let case_sensitive_regex = RegexConfig {
+            escaped: false,
+            case_sensitive: true,
+            match_whole_word: false,
+        };
+        assert_eq!(
+            grep(files, "a.b", case_sensitive_regex)?,
+            (
+                vec!["a.txt:0:0".to_string(), "a.txt:1:0".to_string()],
+                vec![]
+            )
+        );
+        assert_eq!(
+            grep(files, "xb", case_sensitive_regex)?,
+            (vec!["a.txt:1:1".to_string()], vec![])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn binary_files_are_skipped() -> anyhow::Result<()> {
+        let files: &[(&str, &[u8])] = &[("a.txt", b"hello"), ("b.bin", b"hello\0world")];
+        assert_eq!(
+            grep(files, "hello", RegexConfig::default())?,
+            (vec!["a.txt:0:0".to_string()], vec![])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_utf8_does_not_prevent_searching_a_file() -> anyhow::Result<()> {
+        let files: &[(&str, &[u8])] = &[("a.txt", b"\xff hello\nhello \xff")];
+        assert_eq!(
+            grep(files, "hello", RegexConfig::default())?,
+            (
+                vec!["a.txt:0:2".to_string(), "a.txt:1:0".to_string()],
+                vec![]
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn gitignore_negations_are_respected() -> anyhow::Result<()> {
+        let files: &[(&str, &[u8])] = &[
+            (".gitignore", b"*.log\n!keep.log"),
+            ("drop.log", b"hello"),
+            ("keep.log", b"hello"),
+        ];
+        assert_eq!(
+            grep(files, "hello", RegexConfig::default())?,
+            (vec!["keep.log:0:0".to_string()], vec![])
+        );
+        Ok(())
+    }
 }
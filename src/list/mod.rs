@@ -1,12 +1,18 @@
 use std::path::PathBuf;
 
 use crossbeam::channel::Sender;
-use globset::Glob;
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::{WalkBuilder, WalkState};
 use shared::canonicalized_path::CanonicalizedPath;
 
-use crate::{buffer::Buffer, quickfix_list::Location, selection_mode::ByteRange};
+use crate::{
+    buffer::Buffer,
+    project_config::{FilesConfig, ProjectConfig},
+    quickfix_list::Location,
+    selection_mode::ByteRange,
+};
 
+pub(crate) mod annotation;
 pub(crate) mod ast_grep;
 
 pub(crate) mod case_agnostic;
@@ -16,6 +22,10 @@ pub(crate) struct WalkBuilderConfig {
     pub(crate) root: PathBuf,
     pub(crate) include: Option<Glob>,
     pub(crate) exclude: Option<Glob>,
+    /// The file restrictions of the project, see `ProjectConfig`.
+    pub(crate) files: FilesConfig,
+    /// Globs relative to `root`, which every file must match.
+    pub(crate) scope: Vec<Glob>,
 }
 
 type SearchFn = dyn Fn(&Buffer) -> anyhow::Result<Vec<ByteRange>> + Send + Sync;
@@ -63,6 +73,8 @@ impl WalkBuilderConfig {
             root,
             include,
             exclude,
+            files,
+            scope,
         } = self;
         let (sender, receiver) = crossbeam::channel::unbounded::<T>();
         let build_matcher = |glob: Option<&Glob>| -> anyhow::Result<_> {
@@ -75,20 +87,53 @@ impl WalkBuilderConfig {
                 pattern.as_ref().map(|pattern| pattern.is_match(path))
             }))
         };
+        let build_glob_set = |globs: &[String]| -> anyhow::Result<Option<GlobSet>> {
+            if globs.is_empty() {
+                return Ok(None);
+            }
+            let mut builder = GlobSetBuilder::new();
+            for glob in globs {
+                builder.add(Glob::new(&root.join(glob).to_string_lossy())?);
+            }
+            Ok(Some(builder.build()?))
+        };
         let include_match = build_matcher(include.as_ref())?;
         let exclude_match = build_matcher(exclude.as_ref())?;
-        WalkBuilder::new(root)
+        let project_include = build_glob_set(&files.include)?;
+        let project_exclude = build_glob_set(&files.exclude)?;
+        let scope = scope
+            .iter()
+            .map(|glob| Ok(Glob::new(&root.join(glob.glob()).to_string_lossy())?.compile_matcher()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let mut walk_builder = WalkBuilder::new(&root);
+        for ignore_file in &files.ignore_files {
+            walk_builder.add_custom_ignore_filename(ignore_file);
+        }
+        walk_builder
             .filter_entry(move |entry| {
                 let path = entry.path().display().to_string();
+                // Excluded directories are skipped entirely, instead of skipping their files one by one
+                if project_exclude
+                    .as_ref()
+                    .map_or(false, |exclude| exclude.is_match(&path))
+                {
+                    return false;
+                }
 
                 entry
                     .file_type()
                     .map(|file_type| !file_type.is_file())
                     .unwrap_or(false)
                     || (include_match(&path).unwrap_or(true)
-                        && !exclude_match(&path).unwrap_or(false))
+                        && !exclude_match(&path).unwrap_or(false)
+                        && project_include
+                            .as_ref()
+                            .map_or(true, |include| include.is_match(&path))
+                        && scope.iter().all(|glob| glob.is_match(&path)))
             })
             .hidden(false)
+            // Apply `.gitignore` files (including their negated patterns) outside of git repositories too
+            .require_git(false)
             .build_parallel()
             .run(|| {
                 Box::new(|path| {
@@ -117,21 +162,53 @@ impl WalkBuilderConfig {
         Ok(receiver.into_iter().collect::<Vec<_>>())
     }
 
-    fn new(root: PathBuf) -> Self {
-        Self {
-            root,
-            include: None,
-            exclude: None,
-        }
+    /// Walks `root` with the file restrictions of its `.ki/config.toml`.
+    pub(crate) fn new(
+        root: &CanonicalizedPath,
+        include: Option<Glob>,
+        exclude: Option<Glob>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            root: root.to_path_buf().clone(),
+            include,
+            exclude,
+            files: ProjectConfig::load(root)?.files,
+            scope: Vec::new(),
+        })
+    }
+
+    /// One config per root of the workspace of `working_directory`, see `ProjectConfig::roots`.
+    pub(crate) fn workspace(
+        working_directory: &CanonicalizedPath,
+        include: Option<Glob>,
+        exclude: Option<Glob>,
+    ) -> anyhow::Result<Vec<Self>> {
+        ProjectConfig::roots(working_directory)?
+            .iter()
+            .map(|root| Self::new(root, include.clone(), exclude.clone()))
+            .collect()
     }
 
     /// This method returns `PathBuf` instead of `CanonicalizedPath`
     /// because constructing `CanonicalizedPath` is expensive.
     /// For reference: read https://blobfolio.com/2021/faster-path-canonicalization-rust/
     pub(crate) fn non_git_ignored_files(root: CanonicalizedPath) -> anyhow::Result<Vec<PathBuf>> {
-        WalkBuilderConfig::new(root.to_path_buf().clone())
+        WalkBuilderConfig::new(&root, None, None)?
             .run(Box::new(|path, sender| Ok(sender.send(path)?)))
     }
+
+    /// Like `non_git_ignored_files`, but covers every root of the workspace of `working_directory`.
+    pub(crate) fn workspace_non_git_ignored_files(
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<Vec<PathBuf>> {
+        Ok(WalkBuilderConfig::workspace(working_directory, None, None)?
+            .into_iter()
+            .map(|config| config.run(Box::new(|path, sender| Ok(sender.send(path)?))))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -149,6 +226,8 @@ mod test_walk_builder_config {
             root: "./tests/mock_repos/rust1".into(),
             include: None,
             exclude: Some(Glob::new("src/*.rs")?),
+            files: Default::default(),
+            scope: Vec::new(),
         };
         let paths = config.run(Box::new(|path, sender| {
             sender.send(path).unwrap();
@@ -171,6 +250,8 @@ mod test_walk_builder_config {
             root: "./tests/mock_repos/rust1".into(),
             include: Some(Glob::new("src/*.rs")?),
             exclude: None,
+            files: Default::default(),
+            scope: Vec::new(),
         };
         let paths = config.run(Box::new(|path, sender| {
             sender.send(path).unwrap();
@@ -185,6 +266,98 @@ mod test_walk_builder_config {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_project_config() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let write = |path: &str, content: &str| -> anyhow::Result<()> {
+            let path = temp_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            Ok(std::fs::write(path, content)?)
+        };
+        write(
+            ".ki/config.toml",
+            "[files]\nexclude = [\"target\", \"**/*.lock\"]\nignore_files = [\".kiignore\"]",
+        )?;
+        write(".kiignore", "generated/\n")?;
+        write("src/main.rs", "")?;
+        write("src/Cargo.lock", "")?;
+        write("target/debug/main", "")?;
+        write("generated/schema.rs", "")?;
+        let root = shared::canonicalized_path::CanonicalizedPath::try_from(temp_dir.path())?;
+        let paths = WalkBuilderConfig::non_git_ignored_files(root.clone())?
+            .into_iter()
+            .map(|path| {
+                path.strip_prefix(root.to_path_buf())
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(paths, [".ki/config.toml", ".kiignore", "src/main.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn gitignore_negation() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        for (path, content) in [
+            (".gitignore", "*.log\n!keep.log\n"),
+            ("debug.log", ""),
+            ("keep.log", ""),
+        ] {
+            std::fs::write(temp_dir.path().join(path), content)?;
+        }
+        let root = shared::canonicalized_path::CanonicalizedPath::try_from(temp_dir.path())?;
+        let paths = WalkBuilderConfig::non_git_ignored_files(root.clone())?
+            .into_iter()
+            .map(|path| {
+                path.strip_prefix(root.to_path_buf())
+                    .unwrap()
+                    .display()
+                    .to_string()
+            })
+            .sorted()
+            .collect_vec();
+        assert_eq!(paths, [".gitignore", "keep.log"]);
+        Ok(())
+    }
+
+    #[test]
+    fn workspace_folders() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        for (path, content) in [
+            ("app/.ki/config.toml", "[workspace]\nfolders = [\"../lib\"]"),
+            ("app/main.rs", ""),
+            ("lib/.ki/config.toml", "[files]\nexclude = [\"target\"]"),
+            ("lib/lib.rs", ""),
+            ("lib/target/lib.o", ""),
+        ] {
+            let path = temp_dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(path, content)?;
+        }
+        let working_directory =
+            shared::canonicalized_path::CanonicalizedPath::try_from(temp_dir.path().join("app"))?;
+        let root = temp_dir.path().canonicalize()?;
+        let paths = WalkBuilderConfig::workspace_non_git_ignored_files(&working_directory)?
+            .into_iter()
+            .map(|path| path.strip_prefix(&root).unwrap().display().to_string())
+            .sorted()
+            .collect_vec();
+        // Expect every root is walked with the file restrictions of its own config
+        assert_eq!(
+            paths,
+            [
+                "app/.ki/config.toml",
+                "app/main.rs",
+                "lib/.ki/config.toml",
+                "lib/lib.rs"
+            ]
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
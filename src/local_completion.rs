@@ -0,0 +1,161 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use itertools::Itertools;
+
+use crate::{
+    app::{Dispatch, Dispatches},
+    buffer::Buffer,
+    components::{dropdown::DropdownItem, editor::DispatchEditor},
+};
+
+const WORD_COMPLETION_MIN_LENGTH: usize = 2;
+
+/// Characters after which all local completion items are shown unfiltered,
+/// like the trigger characters of LSP servers.
+/// `/` lists the entries of the directory being typed.
+pub(crate) const TRIGGER_CHARACTERS: &[char] = &['/'];
+
+/// Completion items that do not require any LSP server, see `completion`.
+pub(crate) enum LocalCompletion {
+    Items(Vec<DropdownItem>),
+    /// The entries of this directory should be read off the UI thread with `read_directory`,
+    /// and then turned into items with `path_completion_items`.
+    Directory(PathBuf),
+}
+
+/// Completion items that do not require any LSP server, namely:
+/// 1. Filesystem paths, if the text before the cursor looks like a path (e.g. `./src/ma`)
+/// 2. Otherwise, words of all opened buffers
+///
+/// `directory` is used for resolving relative paths.
+pub(crate) fn completion(
+    line_before_cursor: &str,
+    current_word: &str,
+    directory: &Path,
+    buffers: &[Rc<RefCell<Buffer>>],
+) -> LocalCompletion {
+    if path_before_cursor(line_before_cursor).is_some() {
+        return match path_directory(line_before_cursor, directory) {
+            Some(directory) => LocalCompletion::Directory(directory),
+            None => LocalCompletion::Items(Vec::new()),
+        };
+    }
+    // Only suggest words when a word is being typed,
+    // otherwise every word will be suggested after typing a space
+    LocalCompletion::Items(
+        if line_before_cursor.ends_with(current_word)
+            && current_word.chars().count() >= WORD_COMPLETION_MIN_LENGTH
+        {
+            word_completion_items(current_word, buffers)
+        } else {
+            Vec::new()
+        },
+    )
+}
+
+/// Returns the path-like token before the cursor, i.e. a token that starts with
+/// `/`, `./`, `../` or `~/`.
+fn path_before_cursor(line_before_cursor: &str) -> Option<&str> {
+    let token = line_before_cursor
+        .rsplit(|c: char| c.is_whitespace() || "\"'`()[]{}<>=,;".contains(c))
+        .next()?;
+    ["/", "./", "../", "~/"]
+        .iter()
+        .any(|prefix| token.starts_with(prefix))
+        .then_some(token)
+}
+
+/// Returns the directory of the path being typed before the cursor, if any.
+///
+/// Only the directory part is used, the file name part is handled by the dropdown filter.
+pub(crate) fn path_directory(line_before_cursor: &str, directory: &Path) -> Option<PathBuf> {
+    let path = path_before_cursor(line_before_cursor)?;
+    let directory_part = &path[..path.rfind('/').map(|index| index + 1).unwrap_or(0)];
+    match directory_part.strip_prefix("~/") {
+        Some(rest) => Some(PathBuf::from(std::env::var("HOME").ok()?).join(rest)),
+        None => Some(directory.join(directory_part)),
+    }
+}
+
+/// Returns the sorted names of the entries of `directory`, where directories end with `/`.
+///
+/// This reads the file system, so it should not be called on the UI thread.
+pub(crate) fn read_directory(directory: &Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            Some(if entry.path().is_dir() {
+                format!("{name}/")
+            } else {
+                name
+            })
+        })
+        .sorted()
+        .collect_vec()
+}
+
+pub(crate) fn path_completion_items(entries: Vec<String>) -> Vec<DropdownItem> {
+    entries
+        .into_iter()
+        .map(|entry| {
+            DropdownItem::new(entry.clone()).set_dispatches(Dispatches::one(Dispatch::ToEditor(
+                DispatchEditor::TryReplaceCurrentPathSegment(entry),
+            )))
+        })
+        .collect_vec()
+}
+
+fn word_completion_items(current_word: &str, buffers: &[Rc<RefCell<Buffer>>]) -> Vec<DropdownItem> {
+    buffers
+        .iter()
+        .map(|buffer| buffer.borrow().words())
+        .collect_vec()
+        .iter()
+        .flat_map(|words| words.iter())
+        .unique()
+        // The word under the cursor is being typed, so it is not a useful suggestion
+        .filter(|word| *word != current_word)
+        .map(|word| {
+            DropdownItem::new(word.clone()).set_dispatches(Dispatches::one(Dispatch::ToEditor(
+                DispatchEditor::TryReplaceCurrentLongWord(word.clone()),
+            )))
+        })
+        .collect_vec()
+}
+
+#[cfg(test)]
+mod test_local_completion {
+    use super::*;
+
+    #[test]
+    fn test_path_before_cursor() {
+        assert_eq!(path_before_cursor("let x = \"./src/ma"), Some("./src/ma"));
+        assert_eq!(path_before_cursor("cat ../"), Some("../"));
+        assert_eq!(path_before_cursor("open(~/.config"), Some("~/.config"));
+        assert_eq!(path_before_cursor("/usr/lo"), Some("/usr/lo"));
+        assert_eq!(path_before_cursor("a / b"), None);
+        assert_eq!(path_before_cursor("hello wor"), None);
+    }
+
+    #[test]
+    fn test_path_directory() {
+        let directory = Path::new("/home/user/project");
+        assert_eq!(
+            path_directory("let x = \"./src/ma", directory),
+            Some(PathBuf::from("/home/user/project/./src/"))
+        );
+        assert_eq!(
+            path_directory("/usr/lo", directory),
+            Some(PathBuf::from("/usr/"))
+        );
+        assert_eq!(path_directory("hello wor", directory), None);
+    }
+}
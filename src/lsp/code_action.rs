@@ -1,6 +1,9 @@
 use itertools::Itertools;
 
-use crate::{app::Dispatch, components::dropdown::DropdownItem};
+use crate::{
+    app::{Dispatch, Dispatches},
+    components::dropdown::DropdownItem,
+};
 
 use super::workspace_edit::WorkspaceEdit;
 
@@ -11,6 +14,80 @@ pub(crate) struct CodeAction {
     pub(crate) kind: Option<String>,
     pub(crate) edit: Option<WorkspaceEdit>,
     pub(crate) command: Option<Command>,
+    /// Marked by the server as the most reasonable choice of action,
+    /// see `Dispatch::ApplyPreferredCodeAction`.
+    pub(crate) is_preferred: bool,
+    /// The original action, kept only if it has to be resolved via `codeAction/resolve`
+    /// before it can be applied, because the server leaves out its edit until then.
+    pub(crate) unresolved: Option<lsp_types::CodeAction>,
+}
+
+impl CodeAction {
+    /// The top-level kind of this action, which is one of `quickfix`, `refactor` and `source`,
+    /// for example `refactor` for `refactor.extract`.
+    fn top_level_kind(&self) -> Option<&str> {
+        self.kind
+            .as_deref()
+            .and_then(|kind| kind.split('.').next())
+            .filter(|kind| !kind.is_empty())
+    }
+
+    fn group(&self) -> String {
+        match self.top_level_kind() {
+            Some("quickfix") => "Quick Fix",
+            Some("refactor") => "Refactor",
+            Some("source") => "Source",
+            _ => "Misc.",
+        }
+        .to_string()
+    }
+
+    pub(crate) fn is_quickfix(&self) -> bool {
+        self.top_level_kind() == Some("quickfix")
+    }
+
+    /// The dispatches that apply this action, which are empty if it is unresolved.
+    pub(crate) fn dispatches(self) -> Dispatches {
+        self.edit
+            .map(Dispatch::ApplyWorkspaceEdit)
+            .into_iter()
+            // A command this code action executes. If a code action
+            // provides an edit and a command, first the edit is
+            // executed and then the command.
+            // Refer https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction
+            .chain(
+                self.command
+                    .map(|command| Dispatch::LspExecuteCommand { command }),
+            )
+            .collect_vec()
+            .into()
+    }
+}
+
+/// Returns the action to be applied without choosing from the menu,
+/// which is the only preferred action, or otherwise the only quick fix.
+pub(crate) fn preferred_code_action(code_actions: &[CodeAction]) -> Option<&CodeAction> {
+    let only = |mut actions: Vec<&CodeAction>| {
+        if actions.len() == 1 {
+            actions.pop()
+        } else {
+            None
+        }
+    };
+    only(
+        code_actions
+            .iter()
+            .filter(|action| action.is_preferred)
+            .collect_vec(),
+    )
+    .or_else(|| {
+        only(
+            code_actions
+                .iter()
+                .filter(|action| action.is_quickfix())
+                .collect_vec(),
+        )
+    })
 }
 
 #[derive(Debug, Clone)]
@@ -41,30 +118,11 @@ impl Eq for Command {}
 
 impl From<CodeAction> for DropdownItem {
     fn from(value: CodeAction) -> DropdownItem {
-        DropdownItem::new(value.title)
-            .set_group(Some(
-                value
-                    .kind
-                    .and_then(|kind| if kind.is_empty() { None } else { Some(kind) })
-                    .unwrap_or("Misc.".to_string()),
-            ))
-            .set_dispatches(
-                value
-                    .edit
-                    .map(Dispatch::ApplyWorkspaceEdit)
-                    .into_iter()
-                    // A command this code action executes. If a code action
-                    // provides an edit and a command, first the edit is
-                    // executed and then the command.
-                    // Refer https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction
-                    .chain(
-                        value
-                            .command
-                            .map(|command| Dispatch::LspExecuteCommand { command }),
-                    )
-                    .collect_vec()
-                    .into(),
-            )
+        DropdownItem::new(value.title.clone())
+            .set_group(Some(value.group()))
+            // Preferred actions are listed first in their group
+            .set_rank(Some(Box::new([if value.is_preferred { 0 } else { 1 }])))
+            .set_dispatches(Dispatches::one(Dispatch::ApplyCodeAction(value)))
     }
 }
 
@@ -85,13 +143,78 @@ impl TryFrom<lsp_types::CodeAction> for CodeAction {
 
     fn try_from(value: lsp_types::CodeAction) -> Result<Self, Self::Error> {
         log::info!("CodeAction: {:#?}", value);
-
-        let title = value.title;
+        let unresolved = if value.edit.is_none() && value.data.is_some() {
+            Some(value.clone())
+        } else {
+            None
+        };
         Ok(CodeAction {
-            title,
+            title: value.title,
             kind: value.kind.map(|kind| kind.as_str().to_string()),
             edit: value.edit.map(WorkspaceEdit::try_from).transpose()?,
             command: value.command.map(Command),
+            is_preferred: value.is_preferred.unwrap_or(false),
+            unresolved,
         })
     }
 }
+
+impl From<lsp_types::Command> for CodeAction {
+    /// A bare command returned in place of a code action, which is applied by executing it.
+    fn from(value: lsp_types::Command) -> Self {
+        CodeAction {
+            title: value.title.clone(),
+            kind: None,
+            edit: None,
+            command: Some(Command(value)),
+            is_preferred: false,
+            unresolved: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_code_action {
+    use super::*;
+
+    fn code_action(title: &str, kind: Option<&str>, is_preferred: bool) -> CodeAction {
+        CodeAction {
+            title: title.to_string(),
+            kind: kind.map(|kind| kind.to_string()),
+            edit: None,
+            command: None,
+            is_preferred,
+            unresolved: None,
+        }
+    }
+
+    #[test]
+    fn group() {
+        let group = |kind| code_action("a", kind, false).group();
+        assert_eq!(group(Some("quickfix")), "Quick Fix");
+        assert_eq!(group(Some("refactor.extract")), "Refactor");
+        assert_eq!(group(Some("source.organizeImports")), "Source");
+        assert_eq!(group(Some("")), "Misc.");
+        assert_eq!(group(None), "Misc.");
+    }
+
+    #[test]
+    fn preferred_code_action() {
+        let preferred = code_action("preferred", Some("refactor"), true);
+        let quickfix = code_action("quickfix", Some("quickfix"), false);
+        let refactor = code_action("refactor", Some("refactor"), false);
+        assert_eq!(
+            super::preferred_code_action(&[quickfix.clone(), preferred.clone()]),
+            Some(&preferred)
+        );
+        assert_eq!(
+            super::preferred_code_action(&[refactor.clone(), quickfix.clone()]),
+            Some(&quickfix)
+        );
+        assert_eq!(
+            super::preferred_code_action(&[quickfix.clone(), quickfix.clone()]),
+            None
+        );
+        assert_eq!(super::preferred_code_action(&[refactor]), None);
+    }
+}
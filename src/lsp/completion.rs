@@ -18,6 +18,35 @@ pub(crate) struct Completion {
     pub(crate) trigger_characters: Vec<String>,
 }
 
+impl Completion {
+    /// The items are ranked by their `sortText` (or their label if `sortText` is absent),
+    /// items with the same `sortText` share the same rank.
+    pub(crate) fn from_lsp_items(
+        items: Vec<lsp_types::CompletionItem>,
+        trigger_characters: Vec<String>,
+    ) -> Completion {
+        let items = items.into_iter().map(CompletionItem::from).collect_vec();
+        let sort_texts = items
+            .iter()
+            .map(|item| item.sort_text())
+            .sorted()
+            .dedup()
+            .collect_vec();
+        Completion {
+            items: items
+                .into_iter()
+                .map(|item| {
+                    let rank = sort_texts
+                        .binary_search(&item.sort_text())
+                        .unwrap_or_default();
+                    DropdownItem::from(item).set_rank(Some(Box::new([rank])))
+                })
+                .collect(),
+            trigger_characters,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct CompletionItem {
     pub(crate) label: String,
@@ -108,6 +137,10 @@ impl CompletionItem {
         self.label.clone()
     }
 
+    fn sort_text(&self) -> String {
+        self.sort_text.clone().unwrap_or_else(|| self.label())
+    }
+
     pub(crate) fn documentation(&self) -> Option<Documentation> {
         self.documentation.clone()
     }
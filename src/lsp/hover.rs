@@ -1,5 +1,15 @@
+use shared::language::Language;
+
+use crate::{
+    components::suggestive_editor::{Decoration, Info, Link},
+    grid::StyleKey,
+    selection_range::SelectionRange,
+    syntax_highlight::HighlighedSpans,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct Hover {
+    /// Each content is Markdown.
     pub(crate) contents: Vec<String>,
 }
 
@@ -13,7 +23,11 @@ impl From<lsp_types::Hover> for Hover {
                 .into_iter()
                 .map(marked_string_to_string)
                 .collect::<Vec<_>>(),
-            lsp_types::HoverContents::Markup(content) => vec![content.value],
+            lsp_types::HoverContents::Markup(content) => match content.kind {
+                lsp_types::MarkupKind::Markdown => vec![content.value],
+                // Plain text is wrapped in a code block so that it is rendered verbatim
+                lsp_types::MarkupKind::PlainText => vec![format!("```\n{}\n```", content.value)],
+            },
         };
         Hover { contents }
     }
@@ -22,6 +36,264 @@ impl From<lsp_types::Hover> for Hover {
 pub(crate) fn marked_string_to_string(marked_string: lsp_types::MarkedString) -> String {
     match marked_string {
         lsp_types::MarkedString::String(string) => string,
-        lsp_types::MarkedString::LanguageString(language_string) => language_string.value,
+        lsp_types::MarkedString::LanguageString(language_string) => format!(
+            "```{}\n{}\n```",
+            language_string.language, language_string.value
+        ),
+    }
+}
+
+impl Hover {
+    pub(crate) fn into_info(
+        self,
+        highlight: impl FnMut(Language, &str) -> anyhow::Result<HighlighedSpans>,
+    ) -> Info {
+        render_markdown("Hover Info", &self.contents.join("\n\n"), highlight)
+    }
+}
+
+/// Renders `markdown` as styled text.
+///
+/// Emphasis, headings, links and code spans are stripped of their markers and decorated,
+/// while fenced code blocks are highlighted using the language of their info string.
+pub(crate) fn render_markdown(
+    title: &str,
+    markdown: &str,
+    mut highlight: impl FnMut(Language, &str) -> anyhow::Result<HighlighedSpans>,
+) -> Info {
+    let mut rendered = Rendered::default();
+    // The language and the start byte of the fenced code block that is being rendered
+    let mut code_block: Option<(Option<Language>, usize)> = None;
+    for line in markdown.lines() {
+        let is_fence = line.trim_start().starts_with("```");
+        match (&code_block, is_fence) {
+            (None, true) => {
+                let info = line.trim_start().trim_start_matches('`').trim();
+                let language = info
+                    .split_whitespace()
+                    .next()
+                    .and_then(shared::language::from_code_fence_info);
+                code_block = Some((language, rendered.content.len()));
+            }
+            (Some((language, start)), true) => {
+                if let Some(language) = language {
+                    match highlight(language.clone(), &rendered.content[*start..]) {
+                        Ok(HighlighedSpans(spans)) => {
+                            rendered.decorations.extend(spans.into_iter().map(|span| {
+                                Decoration::new(
+                                    SelectionRange::Byte(
+                                        start + span.byte_range.start..start + span.byte_range.end,
+                                    ),
+                                    span.style_key,
+                                )
+                            }))
+                        }
+                        Err(error) => log::info!("hover_highlight_error = {:#?}", error),
+                    }
+                }
+                code_block = None;
+            }
+            (Some(_), false) => {
+                rendered.content.push_str(line);
+                rendered.content.push('\n');
+            }
+            (None, false) => {
+                let level = line.chars().take_while(|c| *c == '#').count();
+                match line[level..].strip_prefix(' ') {
+                    Some(heading) if (1..=6).contains(&level) => {
+                        let start = rendered.content.len();
+                        rendered.render_inline(heading);
+                        rendered.decorate(start, &format!("markup.heading.{level}"))
+                    }
+                    _ => rendered.render_inline(line),
+                }
+                rendered.content.push('\n');
+            }
+        }
+    }
+    let Rendered {
+        mut content,
+        decorations,
+        links,
+    } = rendered;
+    if content.ends_with('\n') {
+        content.pop();
+    }
+    Info::new(title.to_string(), content)
+        .set_decorations(decorations)
+        .set_links(links)
+}
+
+#[derive(Default)]
+struct Rendered {
+    content: String,
+    decorations: Vec<Decoration>,
+    links: Vec<Link>,
+}
+
+impl Rendered {
+    /// Decorates the content from `start` until the end.
+    fn decorate(&mut self, start: usize, highlight_name: &str) {
+        self.decorations.push(Decoration::new(
+            SelectionRange::Byte(start..self.content.len()),
+            StyleKey::Syntax(highlight_name.to_string()),
+        ))
+    }
+
+    fn render_inline(&mut self, text: &str) {
+        let mut rest = text;
+        while let Some(character) = rest.chars().next() {
+            if character == '\\' {
+                if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                    self.content.push(escaped);
+                    rest = &rest[1 + escaped.len_utf8()..];
+                    continue;
+                }
+            }
+            if let Some(after) = self
+                .render_link(rest)
+                .or_else(|| self.render_delimited(rest))
+            {
+                rest = after;
+                continue;
+            }
+            self.content.push(character);
+            rest = &rest[character.len_utf8()..];
+        }
+    }
+
+    /// Renders `[label](target)` as `label`.
+    /// Returns the text after the link if `text` starts with a link.
+    fn render_link<'a>(&mut self, text: &'a str) -> Option<&'a str> {
+        let label = text.strip_prefix('[')?;
+        let label_end = label.find("](")?;
+        let target_end = label[label_end..].find(')')? + label_end;
+        let start = self.content.len();
+        self.render_inline(&label[..label_end]);
+        self.decorate(start, "markup.link.label");
+        self.links.push(Link {
+            range: start..self.content.len(),
+            target: label[label_end + 2..target_end].to_string(),
+        });
+        Some(&label[target_end + 1..])
+    }
+
+    /// Renders emphasis and code spans.
+    /// Returns the text after the closing delimiter if `text` starts with a delimited span.
+    fn render_delimited<'a>(&mut self, text: &'a str) -> Option<&'a str> {
+        let (delimiter, highlight_name) = [
+            ("**", "markup.strong"),
+            ("__", "markup.strong"),
+            ("`", "markup.raw"),
+            ("*", "markup.italic"),
+            ("_", "markup.italic"),
+        ]
+        .into_iter()
+        .find(|(delimiter, _)| text.starts_with(delimiter))?;
+
+        // Underscores within a word, such as in `snake_case`, are not delimiters
+        if delimiter.starts_with('_')
+            && self
+                .content
+                .chars()
+                .last()
+                .is_some_and(char::is_alphanumeric)
+        {
+            return None;
+        }
+
+        let inner = &text[delimiter.len()..];
+        let end = inner.find(delimiter).filter(|end| *end > 0)?;
+        let start = self.content.len();
+        if delimiter == "`" {
+            self.content.push_str(&inner[..end]);
+        } else {
+            self.render_inline(&inner[..end]);
+        }
+        self.decorate(start, highlight_name);
+        Some(&inner[end + delimiter.len()..])
+    }
+}
+
+#[cfg(test)]
+mod test_hover {
+    use super::*;
+
+    fn decorated(info: &Info) -> Vec<(String, String)> {
+        info.decorations()
+            .iter()
+            .map(|decoration| {
+                let SelectionRange::Byte(range) = decoration.selection_range() else {
+                    unreachable!()
+                };
+                let StyleKey::Syntax(name) = decoration.style_key() else {
+                    unreachable!()
+                };
+                (info.content()[range.clone()].to_string(), name.clone())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn render_markdown_emphasis_and_headings() {
+        let info = render_markdown(
+            "",
+            "# Title\nsome **bold** and *italic* with `code` in snake_case [link](https://x.y)",
+            |_, _| Ok(Default::default()),
+        );
+        assert_eq!(
+            info.content(),
+            "Title\nsome bold and italic with code in snake_case link"
+        );
+        assert_eq!(
+            decorated(&info),
+            [
+                ("Title", "markup.heading.1"),
+                ("bold", "markup.strong"),
+                ("italic", "markup.italic"),
+                ("code", "markup.raw"),
+                ("link", "markup.link.label"),
+            ]
+            .map(|(text, name)| (text.to_string(), name.to_string()))
+        );
+        assert_eq!(
+            info.links(),
+            &[Link {
+                range: 51..55,
+                target: "https://x.y".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn render_markdown_highlights_fenced_code_block() {
+        let info = render_markdown("", "hello\n```rust\nfn main() {}\n```\nbye", |_, code| {
+            assert_eq!(code, "fn main() {}\n");
+            Ok(HighlighedSpans(vec![
+                crate::syntax_highlight::HighlighedSpan {
+                    byte_range: 0..2,
+                    style_key: StyleKey::Syntax("keyword".to_string()),
+                },
+            ]))
+        });
+        assert_eq!(info.content(), "hello\nfn main() {}\nbye");
+        assert_eq!(
+            decorated(&info),
+            [("fn".to_string(), "keyword".to_string())]
+        );
+    }
+
+    #[test]
+    fn plain_text_hover_is_rendered_verbatim() {
+        let hover = Hover::from(lsp_types::Hover {
+            contents: lsp_types::HoverContents::Markup(lsp_types::MarkupContent {
+                kind: lsp_types::MarkupKind::PlainText,
+                value: "*not italic*".to_string(),
+            }),
+            range: None,
+        });
+        let info = hover.into_info(|_, _| Ok(Default::default()));
+        assert_eq!(info.content(), "*not italic*");
+        assert!(info.decorations().is_empty());
     }
 }
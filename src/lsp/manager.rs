@@ -67,6 +67,20 @@ impl LspManager {
         )
     }
 
+    /// Sends `from_editor` to every LSP server process, for messages that are not about a single language,
+    /// such as file operations, which can be about folders.
+    pub(crate) fn broadcast_message(&mut self, from_editor: FromEditor) -> anyhow::Result<()> {
+        #[cfg(test)]
+        self.history
+            .insert(from_editor.variant(), from_editor.clone());
+
+        self.lsp_server_process_channels
+            .values()
+            .map(|channel| channel.send_from_editor(from_editor.clone()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(())
+    }
+
     /// Open file can do one of the following:
     /// 1. Start a new LSP server process if it is not started yet.
     /// 2. Notify the LSP server process that a new file is opened.
@@ -1,6 +1,7 @@
 use crate::app::{RequestParams, Scope};
 use anyhow::Context;
 use debounce::EventDebouncer;
+use itertools::Itertools;
 use lsp_types::notification::Notification;
 use lsp_types::request::{
     GotoDeclarationParams, GotoImplementationParams, GotoTypeDefinitionParams, Request,
@@ -11,6 +12,7 @@ use shared::canonicalized_path::CanonicalizedPath;
 use shared::language::Language;
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 
 use std::process::{self};
 use std::sync::mpsc::{Receiver, Sender};
@@ -21,13 +23,13 @@ use crate::app::AppMessage;
 use crate::utils::consolidate_errors;
 
 use super::code_action::CodeAction;
-use super::completion::{Completion, CompletionItem};
+use super::completion::Completion;
 use super::goto_definition_response::GotoDefinitionResponse;
 use super::hover::Hover;
 use super::prepare_rename_response::PrepareRenameResponse;
 use super::signature_help::SignatureHelp;
 use super::symbols::Symbols;
-use super::workspace_edit::WorkspaceEdit;
+use super::workspace_edit::{remap_moved_documents, WorkspaceEdit};
 use crate::quickfix_list::Location;
 
 struct LspServerProcess {
@@ -53,6 +55,8 @@ type RequestId = u64;
 struct PendingResponseRequest {
     method: String,
     context: ResponseContext,
+    /// The document of the request, for responses that do not mention their document.
+    path: Option<CanonicalizedPath>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -64,18 +68,35 @@ pub(crate) enum LspNotification {
     Definition(ResponseContext, GotoDefinitionResponse),
     References(ResponseContext, Vec<Location>),
     PrepareRenameResponse(PrepareRenameResponse),
+    /// The ranges that can be edited together with the requested position,
+    /// empty if the server does not support `textDocument/linkedEditingRange`.
+    LinkedEditingRanges(Vec<std::ops::Range<crate::position::Position>>),
+    /// The ranges of the occurrences of the symbol at the requested position in its document,
+    /// empty if the server does not support `textDocument/documentHighlight`.
+    DocumentHighlights(Vec<std::ops::Range<crate::position::Position>>),
     Error(String),
+    /// Also returned by `workspace/willRenameFiles`, such as updating the `mod` declarations
+    /// and imports of a moved Rust file.
     WorkspaceEdit(WorkspaceEdit),
     CodeAction(Vec<CodeAction>),
+    CodeActionResolve(CodeAction),
     SignatureHelp(Option<SignatureHelp>),
     Symbols(Symbols),
     CompletionItemResolve(lsp_types::CompletionItem),
+    DocumentColor {
+        path: CanonicalizedPath,
+        colors: Vec<lsp_types::ColorInformation>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub(crate) struct ResponseContext {
     pub(crate) scope: Option<Scope>,
     pub(crate) description: Option<String>,
+    /// Show the resulting locations in a peek window instead of the quickfix list.
+    pub(crate) peek: bool,
+    /// The path moved by the request and its destination, see `remap_moved_documents`.
+    pub(crate) moved_path: Option<(PathBuf, PathBuf)>,
 }
 impl ResponseContext {
     pub(crate) fn set_description(self, descrption: &str) -> Self {
@@ -120,6 +141,8 @@ pub(crate) enum FromEditor {
         file_path: CanonicalizedPath,
     },
     TextDocumentPrepareRename(RequestParams),
+    TextDocumentLinkedEditingRange(RequestParams),
+    TextDocumentDocumentHighlight(RequestParams),
     TextDocumentRename {
         params: RequestParams,
         new_name: String,
@@ -133,10 +156,22 @@ pub(crate) enum FromEditor {
     TextDocumentImplementation(RequestParams),
     TextDocumentTypeDefinition(RequestParams),
     TextDocumentDocumentSymbol(RequestParams),
+    /// Sent before renaming `old` to `new`, which does not exist yet.
+    WorkspaceWillRenameFiles {
+        old: CanonicalizedPath,
+        new: PathBuf,
+    },
     WorkspaceDidRenameFiles {
         old: CanonicalizedPath,
         new: CanonicalizedPath,
     },
+    WorkspaceDidCreateFiles {
+        path: CanonicalizedPath,
+    },
+    /// Sent after deleting `path`, which is kept to identify the deleted file or folder.
+    WorkspaceDidDeleteFiles {
+        path: CanonicalizedPath,
+    },
     WorkspaceExecuteCommand {
         params: RequestParams,
         command: super::code_action::Command,
@@ -145,6 +180,10 @@ pub(crate) enum FromEditor {
         completion_item: lsp_types::CompletionItem,
         params: RequestParams,
     },
+    CodeActionResolve {
+        code_action: lsp_types::CodeAction,
+        params: RequestParams,
+    },
 }
 
 impl FromEditor {
@@ -278,6 +317,26 @@ impl LspServerProcess {
     }
 
     fn initialize(&mut self) -> anyhow::Result<()> {
+        // Every root of the workspace is sent, so that the server can index the extra folders too
+        let workspace_folders =
+            crate::project_config::ProjectConfig::roots(&self.current_working_directory)
+                .unwrap_or_else(|error| {
+                    log::error!("Failed to load the workspace folders: {:?}", error);
+                    vec![self.current_working_directory.clone()]
+                })
+                .into_iter()
+                .map(|root| -> anyhow::Result<_> {
+                    Ok(WorkspaceFolder {
+                        uri: Url::parse(&format!("file://{}", root.display_absolute()))?,
+                        name: root
+                            .to_path_buf()
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .to_string(),
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
         self.send_request::<lsp_request!("initialize")>(
             ResponseContext::default(),
             InitializeParams {
@@ -291,6 +350,7 @@ impl LspServerProcess {
                 capabilities: ClientCapabilities {
                     workspace: Some(WorkspaceClientCapabilities {
                         apply_edit: Some(true),
+                        workspace_folders: Some(true),
                         workspace_edit: Some(WorkspaceEditClientCapabilities {
                             document_changes: Some(true),
                             resource_operations: Some(
@@ -305,7 +365,10 @@ impl LspServerProcess {
                             ..WorkspaceEditClientCapabilities::default()
                         }),
                         file_operations: Some(WorkspaceFileOperationsClientCapabilities {
+                            will_rename: Some(true),
                             did_rename: Some(true),
+                            did_create: Some(true),
+                            did_delete: Some(true),
                             ..Default::default()
                         }),
                         execute_command: Some(DynamicRegistrationClientCapabilities {
@@ -339,7 +402,7 @@ impl LspServerProcess {
                             ..CompletionClientCapabilities::default()
                         }),
                         hover: Some(HoverClientCapabilities {
-                            content_format: Some(vec![MarkupKind::PlainText]),
+                            content_format: Some(vec![MarkupKind::Markdown, MarkupKind::PlainText]),
                             ..HoverClientCapabilities::default()
                         }),
                         code_action: Some(CodeActionClientCapabilities {
@@ -361,12 +424,19 @@ impl LspServerProcess {
                                     .collect(),
                                 },
                             }),
+                            is_preferred_support: Some(true),
+                            data_support: Some(true),
+                            resolve_support: Some(CodeActionCapabilityResolveSupport {
+                                properties: vec!["edit".to_string()],
+                            }),
                             ..Default::default()
                         }),
                         rename: Some(RenameClientCapabilities {
                             prepare_support: Some(true),
                             ..Default::default()
                         }),
+                        linked_editing_range: Some(LinkedEditingRangeClientCapabilities::default()),
+                        document_highlight: Some(DocumentHighlightClientCapabilities::default()),
                         signature_help: Some(SignatureHelpClientCapabilities {
                             signature_information: Some(SignatureInformationSettings {
                                 documentation_format: Some(vec![MarkupKind::PlainText]),
@@ -385,7 +455,7 @@ impl LspServerProcess {
                     }),
                     ..ClientCapabilities::default()
                 },
-                workspace_folders: None,
+                workspace_folders: Some(workspace_folders),
                 ..InitializeParams::default()
             },
         )?;
@@ -528,8 +598,13 @@ impl LspServerProcess {
                 let request_id = reply.get("id").unwrap().as_u64().unwrap();
 
                 // Get the method of the request
-                let pending_response_request =
-                    self.pending_response_requests.remove(&request_id).unwrap();
+                let Some(pending_response_request) =
+                    self.pending_response_requests.remove(&request_id)
+                else {
+                    // The request was cancelled, so its response is stale
+                    log::info!("Ignoring response of cancelled request {request_id}");
+                    return Ok(());
+                };
 
                 // Parse the reply as a Response
                 let response = serde_json::from_value::<
@@ -560,6 +635,7 @@ impl LspServerProcess {
                 let PendingResponseRequest {
                     method,
                     context: response_context,
+                    path,
                 } = pending_response_request;
 
                 match method.as_str() {
@@ -588,17 +664,13 @@ impl LspServerProcess {
                             self.app_message_sender
                                 .send(AppMessage::LspNotification(LspNotification::Completion(
                                     response_context,
-                                    Completion {
-                                        trigger_characters: self.trigger_characters(),
-                                        items: match payload {
+                                    Completion::from_lsp_items(
+                                        match payload {
                                             CompletionResponse::Array(items) => items,
                                             CompletionResponse::List(list) => list.items,
-                                        }
-                                        .into_iter()
-                                        .map(CompletionItem::from)
-                                        .map(|item| item.into())
-                                        .collect(),
-                                    },
+                                        },
+                                        self.trigger_characters(),
+                                    ),
                                 )))
                                 .unwrap();
                         }
@@ -695,6 +767,43 @@ impl LspServerProcess {
                                 .unwrap();
                         }
                     }
+                    "textDocument/linkedEditingRange" => {
+                        let payload: <lsp_request!("textDocument/linkedEditingRange") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        // Sent even if there are no ranges, so that the editor can fall back
+                        // to `textDocument/documentHighlight`
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::LinkedEditingRanges(
+                                    payload
+                                        .map(|payload| payload.ranges)
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|range| range.start.into()..range.end.into())
+                                        .collect(),
+                                ),
+                            ))
+                            .unwrap();
+                    }
+                    "textDocument/documentHighlight" => {
+                        let payload: <lsp_request!("textDocument/documentHighlight") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::DocumentHighlights(
+                                    payload
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|highlight| {
+                                            highlight.range.start.into()..highlight.range.end.into()
+                                        })
+                                        .collect(),
+                                ),
+                            ))
+                            .unwrap();
+                    }
                     "textDocument/rename" => {
                         let payload: <lsp_request!("textDocument/rename") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -707,6 +816,22 @@ impl LspServerProcess {
                                 .unwrap();
                         }
                     }
+                    "workspace/willRenameFiles" => {
+                        let payload: <lsp_request!("workspace/willRenameFiles") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        if let Some(payload) = payload {
+                            let payload = match &response_context.moved_path {
+                                Some((old, new)) => remap_moved_documents(payload, old, new),
+                                None => payload,
+                            };
+                            self.app_message_sender
+                                .send(AppMessage::LspNotification(LspNotification::WorkspaceEdit(
+                                    payload.try_into()?,
+                                )))
+                                .unwrap();
+                        }
+                    }
                     "textDocument/codeAction" => {
                         let payload: <lsp_request!("textDocument/codeAction") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -717,7 +842,9 @@ impl LspServerProcess {
                                     payload
                                         .into_iter()
                                         .map(|r| match r {
-                                            CodeActionOrCommand::Command(_) => todo!(),
+                                            CodeActionOrCommand::Command(command) => {
+                                                Ok(command.into())
+                                            }
                                             CodeActionOrCommand::CodeAction(code_action) => {
                                                 code_action.try_into()
                                             }
@@ -749,6 +876,31 @@ impl LspServerProcess {
                                 .unwrap();
                         }
                     }
+                    "textDocument/documentColor" => {
+                        let payload: <lsp_request!("textDocument/documentColor") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        if let Some(path) = path {
+                            self.app_message_sender
+                                .send(AppMessage::LspNotification(
+                                    LspNotification::DocumentColor {
+                                        path,
+                                        colors: payload,
+                                    },
+                                ))
+                                .unwrap();
+                        }
+                    }
+                    "codeAction/resolve" => {
+                        let payload: <lsp_request!("codeAction/resolve") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::CodeActionResolve(payload.try_into()?),
+                            ))
+                            .unwrap();
+                    }
                     "completionItem/resolve" => {
                         let payload: <lsp_request!("completionItem/resolve") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -885,6 +1037,27 @@ impl LspServerProcess {
         Ok(())
     }
 
+    /// Cancels the pending requests that are superseded by a new request of `method` for `path`,
+    /// so that the LSP server can stop working on them, and their responses are ignored.
+    fn cancel_superseded_requests(
+        &mut self,
+        method: &str,
+        path: Option<&CanonicalizedPath>,
+    ) -> anyhow::Result<()> {
+        for id in superseded_request_ids(&self.pending_response_requests, method, path) {
+            self.pending_response_requests.remove(&id);
+            // `CancelParams` only holds 32-bit IDs, requests with larger IDs are not cancelled,
+            // but their responses are still ignored
+            let Ok(id) = i32::try_from(id) else {
+                continue;
+            };
+            self.send_notification::<lsp_notification!("$/cancelRequest")>(CancelParams {
+                id: NumberOrString::Number(id),
+            })?;
+        }
+        Ok(())
+    }
+
     /// Returns the request ID
     fn send_request<R: Request>(
         &mut self,
@@ -894,6 +1067,20 @@ impl LspServerProcess {
     where
         R::Params: serde::Serialize,
     {
+        self.send_document_request::<R>(context, None, params)
+    }
+
+    /// Same as `send_request`, but the response is associated with the document of `path`.
+    fn send_document_request<R: Request>(
+        &mut self,
+        context: ResponseContext,
+        path: Option<CanonicalizedPath>,
+        params: R::Params,
+    ) -> anyhow::Result<()>
+    where
+        R::Params: serde::Serialize,
+    {
+        self.cancel_superseded_requests(R::METHOD, path.as_ref())?;
         let id = {
             let result = self.next_request_id;
             self.next_request_id += 1;
@@ -914,6 +1101,7 @@ impl LspServerProcess {
             PendingResponseRequest {
                 context,
                 method: R::METHOD.to_string(),
+                path,
             },
         );
 
@@ -930,13 +1118,14 @@ impl LspServerProcess {
         self.send_notification::<lsp_notification!("textDocument/didOpen")>(
             DidOpenTextDocumentParams {
                 text_document: TextDocumentItem {
-                    uri: path_buf_to_url(file_path)?,
+                    uri: path_buf_to_url(file_path.clone())?,
                     language_id,
                     version: version as i32,
                     text: content,
                 },
             },
-        )
+        )?;
+        self.text_document_document_color(file_path)
     }
 
     fn text_document_did_change(
@@ -948,7 +1137,7 @@ impl LspServerProcess {
         self.send_notification::<lsp_notification!("textDocument/didChange")>(
             DidChangeTextDocumentParams {
                 text_document: VersionedTextDocumentIdentifier {
-                    uri: path_buf_to_url(file_path)?,
+                    uri: path_buf_to_url(file_path.clone())?,
                     version,
                 },
                 content_changes: vec![TextDocumentContentChangeEvent {
@@ -957,6 +1146,27 @@ impl LspServerProcess {
                     text: content,
                 }],
             },
+        )?;
+        self.text_document_document_color(file_path)
+    }
+
+    /// The colors are requested whenever the document is opened or changed,
+    /// because they are rendered as decorations instead of being requested by the user.
+    fn text_document_document_color(
+        &mut self,
+        file_path: CanonicalizedPath,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.color_provider.is_some()) {
+            return Ok(());
+        }
+        self.send_document_request::<lsp_request!("textDocument/documentColor")>(
+            ResponseContext::default(),
+            Some(file_path.clone()),
+            DocumentColorParams {
+                text_document: path_buf_to_text_document_identifier(file_path)?,
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
         )
     }
 
@@ -972,15 +1182,72 @@ impl LspServerProcess {
         )
     }
 
+    fn workspace_will_rename_files(
+        &mut self,
+        old: CanonicalizedPath,
+        new: PathBuf,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| {
+            c.workspace
+                .as_ref()
+                .and_then(|workspace| workspace.file_operations.as_ref())
+                .and_then(|file_operations| file_operations.will_rename.as_ref())
+                .is_some()
+        }) {
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("workspace/willRenameFiles")>(
+            ResponseContext {
+                moved_path: Some((old.to_path_buf(), new.clone())),
+                ..Default::default()
+            },
+            RenameFilesParams {
+                files: [file_rename(old.to_path_buf(), &new)?].to_vec(),
+            },
+        )
+    }
+
     fn workspace_did_rename_files(
         &mut self,
         old: CanonicalizedPath,
         new: CanonicalizedPath,
     ) -> Result<(), anyhow::Error> {
         self.send_notification::<lsp_notification!("workspace/didRenameFiles")>(RenameFilesParams {
-            files: [FileRename {
-                old_uri: old.display_absolute(),
-                new_uri: new.display_absolute(),
+            files: [file_rename(old.to_path_buf(), new.to_path_buf())?].to_vec(),
+        })
+    }
+
+    fn workspace_did_create_files(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        if !self.has_capability(|c| {
+            c.workspace
+                .as_ref()
+                .and_then(|workspace| workspace.file_operations.as_ref())
+                .and_then(|file_operations| file_operations.did_create.as_ref())
+                .is_some()
+        }) {
+            return Ok(());
+        }
+        self.send_notification::<lsp_notification!("workspace/didCreateFiles")>(CreateFilesParams {
+            files: [FileCreate {
+                uri: path_to_uri(path.to_path_buf())?,
+            }]
+            .to_vec(),
+        })
+    }
+
+    fn workspace_did_delete_files(&mut self, path: CanonicalizedPath) -> anyhow::Result<()> {
+        if !self.has_capability(|c| {
+            c.workspace
+                .as_ref()
+                .and_then(|workspace| workspace.file_operations.as_ref())
+                .and_then(|file_operations| file_operations.did_delete.as_ref())
+                .is_some()
+        }) {
+            return Ok(());
+        }
+        self.send_notification::<lsp_notification!("workspace/didDeleteFiles")>(DeleteFilesParams {
+            files: [FileDelete {
+                uri: path_to_uri(path.to_path_buf())?,
             }]
             .to_vec(),
         })
@@ -1165,6 +1432,51 @@ impl LspServerProcess {
         )
     }
 
+    fn text_document_linked_editing_range(
+        &mut self,
+        params: RequestParams,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.linked_editing_range_provider.is_some()) {
+            self.app_message_sender.send(AppMessage::LspNotification(
+                LspNotification::LinkedEditingRanges(Vec::new()),
+            ))?;
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("textDocument/linkedEditingRange")>(
+            params.context,
+            LinkedEditingRangeParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    position: params.position.into(),
+                    text_document: path_buf_to_text_document_identifier(params.path)?,
+                },
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
+    fn text_document_document_highlight(
+        &mut self,
+        params: RequestParams,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.document_highlight_provider.is_some()) {
+            self.app_message_sender.send(AppMessage::LspNotification(
+                LspNotification::DocumentHighlights(Vec::new()),
+            ))?;
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("textDocument/documentHighlight")>(
+            params.context,
+            DocumentHighlightParams {
+                text_document_position_params: TextDocumentPositionParams {
+                    position: params.position.into(),
+                    text_document: path_buf_to_text_document_identifier(params.path)?,
+                },
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+            },
+        )
+    }
+
     fn text_document_rename(
         &mut self,
         params: RequestParams,
@@ -1286,6 +1598,25 @@ impl LspServerProcess {
         self.send_request::<lsp_request!("completionItem/resolve")>(params.context, completion_item)
     }
 
+    fn code_action_resolve(
+        &mut self,
+        params: RequestParams,
+        code_action: lsp_types::CodeAction,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| {
+            matches!(
+                c.code_action_provider,
+                Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    resolve_provider: Some(true),
+                    ..
+                }))
+            )
+        }) {
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("codeAction/resolve")>(params.context, code_action)
+    }
+
     fn handle_from_editor(&mut self, from_editor: &FromEditor) {
         log::info!(
             "LspServerProcess::handle_from_editor = {}",
@@ -1312,6 +1643,12 @@ impl LspServerProcess {
             FromEditor::TextDocumentPrepareRename(params) => {
                 self.text_document_prepare_rename(params)
             }
+            FromEditor::TextDocumentLinkedEditingRange(params) => {
+                self.text_document_linked_editing_range(params)
+            }
+            FromEditor::TextDocumentDocumentHighlight(params) => {
+                self.text_document_document_highlight(params)
+            }
             FromEditor::TextDocumentCodeAction {
                 params,
                 diagnostics,
@@ -1336,9 +1673,14 @@ impl LspServerProcess {
             FromEditor::TextDocumentSignatureHelp(params) => {
                 self.text_document_signature_help(params)
             }
+            FromEditor::WorkspaceWillRenameFiles { old, new } => {
+                self.workspace_will_rename_files(old, new)
+            }
             FromEditor::WorkspaceDidRenameFiles { old, new } => {
                 self.workspace_did_rename_files(old, new)
             }
+            FromEditor::WorkspaceDidCreateFiles { path } => self.workspace_did_create_files(path),
+            FromEditor::WorkspaceDidDeleteFiles { path } => self.workspace_did_delete_files(path),
             FromEditor::WorkspaceExecuteCommand { params, command } => {
                 self.workspace_execute_command(params, command)
             }
@@ -1346,6 +1688,10 @@ impl LspServerProcess {
                 completion_item,
                 params,
             } => self.completion_item_resolve(params, completion_item),
+            FromEditor::CodeActionResolve {
+                code_action,
+                params,
+            } => self.code_action_resolve(params, code_action),
         }
         .unwrap_or_else(|error| {
             log::info!("LspServerProcess::handle_from_editor | error={:?}", error);
@@ -1353,10 +1699,55 @@ impl LspServerProcess {
     }
 }
 
+/// Requests of these methods are fired by navigation or typing,
+/// so only the response of the latest request of each method is relevant.
+const SUPERSEDABLE_METHODS: &[&str] = &[
+    "textDocument/hover",
+    "textDocument/completion",
+    "textDocument/references",
+    "textDocument/signatureHelp",
+    "completionItem/resolve",
+];
+
+/// Requests of these methods are fired by every change of a document,
+/// and their responses are not tied to a version of the document,
+/// so only the response of the latest request of each method for the same document is relevant.
+const DOCUMENT_SUPERSEDABLE_METHODS: &[&str] = &["textDocument/documentColor"];
+
+fn superseded_request_ids(
+    pending_response_requests: &HashMap<RequestId, PendingResponseRequest>,
+    method: &str,
+    path: Option<&CanonicalizedPath>,
+) -> Vec<RequestId> {
+    let same_document = DOCUMENT_SUPERSEDABLE_METHODS.contains(&method);
+    if !same_document && !SUPERSEDABLE_METHODS.contains(&method) {
+        return Vec::new();
+    }
+    pending_response_requests
+        .iter()
+        .filter(|(_, request)| {
+            request.method == method && (!same_document || request.path.as_ref() == path)
+        })
+        .map(|(id, _)| *id)
+        .sorted()
+        .collect()
+}
+
 fn path_buf_to_url(path: CanonicalizedPath) -> Result<Url, anyhow::Error> {
     Ok(Url::parse(&format!("file://{}", path.display_absolute()))?)
 }
 
+fn path_to_uri(path: &Path) -> anyhow::Result<String> {
+    Ok(Url::parse(&format!("file://{}", path.display()))?.to_string())
+}
+
+fn file_rename(old: &Path, new: &Path) -> Result<FileRename, anyhow::Error> {
+    Ok(FileRename {
+        old_uri: path_to_uri(old)?,
+        new_uri: path_to_uri(new)?,
+    })
+}
+
 fn path_buf_to_text_document_identifier(
     path: CanonicalizedPath,
 ) -> Result<TextDocumentIdentifier, anyhow::Error> {
@@ -1364,3 +1755,79 @@ fn path_buf_to_text_document_identifier(
         uri: path_buf_to_url(path)?,
     })
 }
+
+#[cfg(test)]
+mod test_process {
+    use std::collections::HashMap;
+
+    use super::{
+        superseded_request_ids, CanonicalizedPath, PendingResponseRequest, ResponseContext,
+    };
+
+    #[test]
+    fn only_pending_requests_of_the_same_supersedable_method_are_superseded() {
+        let pending_response_requests = [
+            (0, "textDocument/hover"),
+            (1, "textDocument/completion"),
+            (2, "textDocument/hover"),
+            (3, "textDocument/rename"),
+        ]
+        .into_iter()
+        .map(|(id, method)| {
+            (
+                id,
+                PendingResponseRequest {
+                    method: method.to_string(),
+                    context: ResponseContext::default(),
+                    path: None,
+                },
+            )
+        })
+        .collect::<HashMap<_, _>>();
+        assert_eq!(
+            superseded_request_ids(&pending_response_requests, "textDocument/hover", None),
+            [0, 2]
+        );
+        assert!(
+            superseded_request_ids(&pending_response_requests, "textDocument/rename", None)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn document_color_requests_are_only_superseded_by_requests_of_the_same_document(
+    ) -> anyhow::Result<()> {
+        let main: CanonicalizedPath = "src/main.rs".to_string().try_into()?;
+        let lib: CanonicalizedPath = "src/lib.rs".to_string().try_into()?;
+        let pending_response_requests = [(0, main.clone()), (1, lib.clone()), (2, main.clone())]
+            .into_iter()
+            .map(|(id, path)| {
+                (
+                    id,
+                    PendingResponseRequest {
+                        method: "textDocument/documentColor".to_string(),
+                        context: ResponseContext::default(),
+                        path: Some(path),
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(
+            superseded_request_ids(
+                &pending_response_requests,
+                "textDocument/documentColor",
+                Some(&main)
+            ),
+            [0, 2]
+        );
+        assert_eq!(
+            superseded_request_ids(
+                &pending_response_requests,
+                "textDocument/documentColor",
+                Some(&lib)
+            ),
+            [1]
+        );
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use itertools::Itertools;
 use shared::canonicalized_path::CanonicalizedPath;
@@ -10,6 +10,39 @@ pub(crate) struct WorkspaceEdit {
     pub(crate) edits: Vec<TextDocumentEdit>,
     pub(crate) resource_operations: Vec<ResourceOperation>,
 }
+
+impl WorkspaceEdit {
+    /// The paths of the files with text edits, in the order of their first edit.
+    pub(crate) fn affected_paths(&self) -> Vec<CanonicalizedPath> {
+        self.edits
+            .iter()
+            .map(|edit| edit.path.clone())
+            .unique()
+            .collect()
+    }
+
+    /// Edits spanning multiple files (or creating/renaming/deleting files)
+    /// should be previewed before being applied.
+    pub(crate) fn requires_preview(&self) -> bool {
+        self.affected_paths().len() > 1 || !self.resource_operations.is_empty()
+    }
+
+    pub(crate) fn reject_path(self, path: &CanonicalizedPath) -> Self {
+        Self {
+            edits: self
+                .edits
+                .into_iter()
+                .filter(|edit| &edit.path != path)
+                .collect(),
+            ..self
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.edits.is_empty() && self.resource_operations.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum ResourceOperation {
     Create(String),
@@ -42,6 +75,58 @@ impl TryFrom<lsp_types::ResourceOp> for ResourceOperation {
     }
 }
 
+/// Returns `value` with the documents under `old` replaced by the same documents under `new`,
+/// because the edits of `workspace/willRenameFiles` are received after `old` is moved to `new`.
+pub(crate) fn remap_moved_documents(
+    mut value: lsp_types::WorkspaceEdit,
+    old: &Path,
+    new: &Path,
+) -> lsp_types::WorkspaceEdit {
+    let remap = |uri: lsp_types::Url| -> lsp_types::Url {
+        uri.to_file_path()
+            .ok()
+            .and_then(|path| {
+                let relative = path.strip_prefix(old).ok()?;
+                let path = if relative.as_os_str().is_empty() {
+                    new.to_path_buf()
+                } else {
+                    new.join(relative)
+                };
+                lsp_types::Url::from_file_path(path).ok()
+            })
+            .unwrap_or(uri)
+    };
+    value.changes = value.changes.map(|changes| {
+        changes
+            .into_iter()
+            .map(|(uri, edits)| (remap(uri), edits))
+            .collect()
+    });
+    let remap_edit = |mut edit: lsp_types::TextDocumentEdit| {
+        edit.text_document.uri = remap(edit.text_document.uri);
+        edit
+    };
+    value.document_changes = value.document_changes.map(|changes| match changes {
+        lsp_types::DocumentChanges::Edits(edits) => {
+            lsp_types::DocumentChanges::Edits(edits.into_iter().map(remap_edit).collect())
+        }
+        lsp_types::DocumentChanges::Operations(operations) => {
+            lsp_types::DocumentChanges::Operations(
+                operations
+                    .into_iter()
+                    .map(|operation| match operation {
+                        lsp_types::DocumentChangeOperation::Edit(edit) => {
+                            lsp_types::DocumentChangeOperation::Edit(remap_edit(edit))
+                        }
+                        operation => operation,
+                    })
+                    .collect(),
+            )
+        }
+    });
+    value
+}
+
 impl TryFrom<lsp_types::WorkspaceEdit> for WorkspaceEdit {
     type Error = anyhow::Error;
     fn try_from(value: lsp_types::WorkspaceEdit) -> Result<Self, Self::Error> {
@@ -134,3 +219,45 @@ pub(crate) struct TextDocumentEdit {
     pub(crate) path: CanonicalizedPath,
     pub(crate) edits: Vec<PositionalEdit>,
 }
+
+#[cfg(test)]
+mod test_workspace_edit {
+    use std::{collections::HashMap, path::Path};
+
+    use lsp_types::Url;
+
+    #[test]
+    fn remap_moved_documents() {
+        let url = |path: &str| Url::from_file_path(path).unwrap();
+        let edit = lsp_types::WorkspaceEdit {
+            changes: Some(HashMap::from([
+                (url("/project/old/a.rs"), Vec::new()),
+                (url("/project/old.rs"), Vec::new()),
+                (url("/project/main.rs"), Vec::new()),
+            ])),
+            ..Default::default()
+        };
+        let directory = super::remap_moved_documents(
+            edit.clone(),
+            Path::new("/project/old"),
+            Path::new("/project/new"),
+        );
+        let mut uris = directory.changes.unwrap().into_keys().collect::<Vec<_>>();
+        uris.sort();
+        assert_eq!(
+            uris,
+            [
+                url("/project/main.rs"),
+                url("/project/new/a.rs"),
+                url("/project/old.rs")
+            ]
+        );
+
+        let file = super::remap_moved_documents(
+            edit,
+            Path::new("/project/old.rs"),
+            Path::new("/project/new.rs"),
+        );
+        assert!(file.changes.unwrap().contains_key(&url("/project/new.rs")));
+    }
+}
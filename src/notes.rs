@@ -0,0 +1,71 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Returns the path of the note of the day of `now` in `directory`,
+/// creating the note with a heading of the date if it does not exist yet.
+///
+/// The date is in UTC, since the local time zone is not known.
+pub(crate) fn daily_note(directory: &Path, now: SystemTime) -> anyhow::Result<PathBuf> {
+    let date = date(now)?;
+    let path = directory.join(format!("{}.md", date));
+    if !path.exists() {
+        std::fs::create_dir_all(directory)?;
+        std::fs::write(&path, format!("# {}\n\n", date))?;
+    }
+    Ok(path)
+}
+
+/// Formats the date of `time` as `YYYY-MM-DD`.
+pub(crate) fn date(time: SystemTime) -> anyhow::Result<String> {
+    let days = (time.duration_since(UNIX_EPOCH)?.as_secs() / 86400) as i64;
+    // Converts days since 1970-01-01 into a date of the proleptic Gregorian calendar,
+    // see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    Ok(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+#[cfg(test)]
+mod test_notes {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn date() -> anyhow::Result<()> {
+        let date = |seconds: u64| super::date(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(date(0)?, "1970-01-01");
+        assert_eq!(date(951_782_400)?, "2000-02-29");
+        assert_eq!(date(1_735_689_599)?, "2024-12-31");
+        assert_eq!(date(1_735_689_600)?, "2025-01-01");
+        Ok(())
+    }
+
+    #[test]
+    fn daily_note_is_created_once() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path().join("notes");
+        let now = UNIX_EPOCH + Duration::from_secs(1_735_689_600);
+        let path = daily_note(&directory, now)?;
+        assert_eq!(path, directory.join("2025-01-01.md"));
+        assert_eq!(std::fs::read_to_string(&path)?, "# 2025-01-01\n\n");
+        std::fs::write(&path, "edited")?;
+        daily_note(&directory, now)?;
+        assert_eq!(std::fs::read_to_string(&path)?, "edited");
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::position::Position;
+
+/// A URL or a path of an existing file or directory, found under the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PathOrUrl {
+    Url(String),
+    Path {
+        path: CanonicalizedPath,
+        /// Parsed from the `:line` or `:line:column` suffix of the path.
+        position: Option<Position>,
+    },
+}
+
+/// Returns the word of `line` that contains `byte_index`,
+/// where brackets, quotes and trailing periods are not considered part of the word,
+/// because they usually surround URLs and paths in prose.
+pub(crate) fn word_at(line: &str, byte_index: usize) -> Option<&str> {
+    lazy_regex::regex!(r#"[^\s"'`<>()\[\]{},;]+"#)
+        .find_iter(line)
+        .find(|m| m.start() <= byte_index && byte_index < m.end())
+        .map(|m| m.as_str().trim_end_matches('.'))
+        .filter(|word| !word.is_empty())
+}
+
+/// Parses `text` as a URL, or as a path that exists.
+///
+/// Relative paths are resolved against `directory`,
+/// and the 1-based `:line` or `:line:column` suffix of a path is parsed as its position.
+pub(crate) fn parse(text: &str, directory: &CanonicalizedPath) -> Option<PathOrUrl> {
+    let text = text.trim();
+    if lazy_regex::regex_is_match!(r"^[a-zA-Z][a-zA-Z0-9+.-]*://\S+$", text) {
+        return Some(PathOrUrl::Url(text.to_string()));
+    }
+    let (path, position) = match lazy_regex::regex_captures!(r"^(.+?):(\d+)(?::(\d+))?:?$", text) {
+        Some((_, path, line, column)) => (
+            path,
+            Some(Position::new(
+                line.parse::<usize>().ok()?.saturating_sub(1),
+                column
+                    .parse::<usize>()
+                    .unwrap_or_default()
+                    .saturating_sub(1),
+            )),
+        ),
+        None => (text, None),
+    };
+    let path = match path.strip_prefix("~/") {
+        Some(relative_to_home) => CanonicalizedPath::try_from(std::env::var("HOME").ok()?)
+            .ok()?
+            .join(relative_to_home),
+        None => directory.join(path),
+    }
+    .ok()?;
+    Some(PathOrUrl::Path { path, position })
+}
+
+#[cfg(test)]
+mod test_path_or_url {
+    use super::*;
+
+    #[test]
+    fn word_at_excludes_surrounding_punctuation() {
+        let line = "See (https://ki-editor.github.io/ki-editor/). Or [src/main.rs:3:5]";
+        assert_eq!(
+            word_at(line, 10),
+            Some("https://ki-editor.github.io/ki-editor/")
+        );
+        assert_eq!(word_at(line, 55), Some("src/main.rs:3:5"));
+        assert_eq!(word_at(line, 3), None);
+    }
+
+    #[test]
+    fn parse_urls_and_paths_with_positions() -> anyhow::Result<()> {
+        let directory: CanonicalizedPath = std::env::current_dir()?.try_into()?;
+        assert_eq!(
+            parse("https://example.com/a?b=c", &directory),
+            Some(PathOrUrl::Url("https://example.com/a?b=c".to_string()))
+        );
+        assert_eq!(
+            parse("src/main.rs:3:5", &directory),
+            Some(PathOrUrl::Path {
+                path: directory.join("src/main.rs")?,
+                position: Some(Position::new(2, 4)),
+            })
+        );
+        assert_eq!(
+            parse("src/main.rs:10", &directory),
+            Some(PathOrUrl::Path {
+                path: directory.join("src/main.rs")?,
+                position: Some(Position::new(9, 0)),
+            })
+        );
+        assert_eq!(
+            parse("./Cargo.toml", &directory),
+            Some(PathOrUrl::Path {
+                path: directory.join("Cargo.toml")?,
+                position: None,
+            })
+        );
+        assert_eq!(parse("src/does_not_exist.rs", &directory), None);
+        Ok(())
+    }
+}
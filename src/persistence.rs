@@ -0,0 +1,138 @@
+use std::path::{Path, PathBuf};
+
+use shared::canonicalized_path::CanonicalizedPath;
+
+/// What is persisted across sessions, see `Context::persistence`.
+///
+/// Nothing is persisted by default, so that tests do not share state through the cache directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Persistence {
+    /// The quickfix list, prompt histories, search toggles and bookmarks are persisted
+    /// per workspace, and restored when the editor is started again in the same workspace.
+    workspace: Option<CanonicalizedPath>,
+    /// Persist the undo history of each file when it is saved,
+    /// and restore it when the file is opened again with the same content.
+    undo_history: bool,
+    /// The number of previous versions kept for each saved file, see `save::save`.
+    backup_count: usize,
+}
+
+impl Persistence {
+    pub(crate) fn new(workspace: CanonicalizedPath) -> Persistence {
+        Persistence {
+            workspace: Some(workspace),
+            ..Default::default()
+        }
+    }
+
+    /// Persist the state of `workspace` instead, unless nothing is persisted.
+    pub(crate) fn set_workspace(self, workspace: CanonicalizedPath) -> Persistence {
+        Persistence {
+            workspace: self.workspace.map(|_| workspace),
+            ..self
+        }
+    }
+
+    pub(crate) fn set_undo_history(self, undo_history: bool) -> Persistence {
+        Persistence {
+            undo_history,
+            ..self
+        }
+    }
+
+    pub(crate) fn set_backup_count(self, backup_count: usize) -> Persistence {
+        Persistence {
+            backup_count,
+            ..self
+        }
+    }
+
+    pub(crate) fn workspace(&self) -> Option<&CanonicalizedPath> {
+        self.workspace.as_ref()
+    }
+
+    pub(crate) fn undo_history(&self) -> bool {
+        self.undo_history
+    }
+
+    pub(crate) fn backup_count(&self) -> usize {
+        self.backup_count
+    }
+}
+
+/// The readable part of `file_name` is truncated to this many bytes,
+/// so that the file name stays within the limit of 255 bytes of most file systems.
+const MAX_READABLE_LEN: usize = 200;
+
+/// Returns a file name that is unique to `path`.
+///
+/// It starts with `path` with every non-alphanumeric character replaced by `_`, so that it is
+/// recognizable, and ends with the hash of `path`, so that paths differing only
+/// by such characters (for example `a-b` and `a_b`) do not share the same name.
+/// Only the end of long paths is kept in the readable part.
+pub(crate) fn file_name(path: &Path) -> String {
+    let readable = path
+        .display()
+        .to_string()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let start = readable
+        .char_indices()
+        .map(|(index, _)| index)
+        .find(|index| readable.len() - index <= MAX_READABLE_LEN)
+        .unwrap_or(readable.len());
+    format!(
+        "{}_{:016x}",
+        &readable[start..],
+        stable_hash(path.as_os_str().as_encoded_bytes())
+    )
+}
+
+/// The 64-bit FNV-1a hash of `bytes`.
+///
+/// Unlike `DefaultHasher`, whose algorithm may change between Rust releases,
+/// it stays the same, so that persisted files are found again after upgrading.
+pub(crate) fn stable_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns the path of the `kind` file persisted for `working_directory`,
+/// which is `<cache_dir>/<kind>/<file_name>.json`.
+pub(crate) fn workspace_file(kind: &str, working_directory: &CanonicalizedPath) -> PathBuf {
+    grammar::cache_dir()
+        .join(kind)
+        .join(format!("{}.json", file_name(working_directory.as_ref())))
+}
+
+#[cfg(test)]
+mod test_persistence {
+    use super::*;
+
+    #[test]
+    fn file_name_is_unique() {
+        let a = file_name(Path::new("/home/user/a-b"));
+        let b = file_name(Path::new("/home/user/a_b"));
+        assert!(a.starts_with("_home_user_a_b_"));
+        assert!(b.starts_with("_home_user_a_b_"));
+        assert_ne!(a, b);
+        assert_eq!(a, file_name(Path::new("/home/user/a-b")));
+    }
+
+    #[test]
+    fn file_name_of_long_path_is_truncated() {
+        let path = format!("/{}/é", "a".repeat(300));
+        let name = file_name(Path::new(&path));
+        assert!(name.len() <= MAX_READABLE_LEN + 17);
+        assert!(name.starts_with("aaa"));
+        assert!(name.contains("_é_"));
+    }
+
+    #[test]
+    fn stable_hash_is_fnv_1a() {
+        assert_eq!(stable_hash(b""), 0xcbf29ce484222325);
+        assert_eq!(stable_hash(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}
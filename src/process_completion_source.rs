@@ -0,0 +1,190 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, Command, Stdio},
+    sync::{mpsc::Sender, Arc, Condvar, Mutex},
+};
+
+use crate::{
+    app::AppMessage,
+    components::suggestive_editor::{CompletionSource, InlineSuggestion, InlineSuggestionRequest},
+};
+
+/// A `CompletionSource` backed by a long-running subprocess.
+///
+/// The protocol is line-delimited JSON:
+/// each line written to the stdin of the subprocess is an `InlineSuggestionRequest`,
+/// each line read from the stdout of the subprocess should be an `InlineSuggestion`.
+pub(crate) struct ProcessCompletionSource {
+    /// The requests are written to the stdin of the subprocess by another thread,
+    /// because writing blocks once the pipe is full, such as when the subprocess is busy.
+    pending_request: Arc<(Mutex<PendingRequest>, Condvar)>,
+    /// Killed when this source is dropped, since dropping a `Child` does not kill it.
+    child: Child,
+}
+
+/// Only the latest request is kept while the subprocess is busy,
+/// because the suggestions of older requests would be discarded anyway.
+#[derive(Default)]
+struct PendingRequest {
+    request: Option<String>,
+    /// Set when the stdin of the subprocess is closed or the source is dropped,
+    /// which stops the thread that writes the requests.
+    closed: bool,
+}
+
+impl ProcessCompletionSource {
+    pub(crate) fn new(command: &str, sender: Sender<AppMessage>) -> anyhow::Result<Self> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to take stdin of {:?}", command))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to take stdout of {:?}", command))?;
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                match serde_json::from_str::<InlineSuggestion>(&line) {
+                    Ok(suggestion) => {
+                        if sender
+                            .send(AppMessage::InlineSuggestion(suggestion))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Err(error) => {
+                        log::error!("Invalid inline suggestion {:?}: {:?}", line, error)
+                    }
+                }
+            }
+        });
+        let pending_request = Arc::<(Mutex<PendingRequest>, Condvar)>::default();
+        std::thread::spawn({
+            let pending_request = pending_request.clone();
+            move || {
+                let (pending, condvar) = &*pending_request;
+                loop {
+                    let request = {
+                        let mut pending = pending.lock().unwrap();
+                        loop {
+                            if pending.closed {
+                                return;
+                            }
+                            if let Some(request) = pending.request.take() {
+                                break request;
+                            }
+                            pending = condvar.wait(pending).unwrap();
+                        }
+                    };
+                    if writeln!(stdin, "{request}")
+                        .and_then(|_| stdin.flush())
+                        .is_err()
+                    {
+                        pending.lock().unwrap().closed = true;
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            pending_request,
+            child,
+        })
+    }
+}
+
+impl CompletionSource for ProcessCompletionSource {
+    fn request(&mut self, request: InlineSuggestionRequest) -> anyhow::Result<()> {
+        let request = serde_json::to_string(&request)?;
+        let (pending, condvar) = &*self.pending_request;
+        let mut pending = pending.lock().unwrap();
+        if pending.closed {
+            return Err(anyhow::anyhow!("The completion source has exited"));
+        }
+        pending.request = Some(request);
+        condvar.notify_one();
+        Ok(())
+    }
+}
+
+impl Drop for ProcessCompletionSource {
+    fn drop(&mut self) {
+        let (pending, condvar) = &*self.pending_request;
+        pending.lock().unwrap().closed = true;
+        condvar.notify_one();
+        if let Err(error) = self.child.kill() {
+            log::error!("Failed to kill the completion source: {:?}", error)
+        }
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test_process_completion_source {
+    use super::*;
+
+    #[test]
+    fn requests_do_not_block_and_the_subprocess_is_killed_on_drop() -> anyhow::Result<()> {
+        let (sender, _receiver) = std::sync::mpsc::channel();
+        // The subprocess never reads its stdin, so writing blocks once the pipe is full
+        let mut source = ProcessCompletionSource::new("sleep 10", sender)?;
+        let (done_sender, done) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            for id in 0..4 {
+                source
+                    .request(InlineSuggestionRequest {
+                        id,
+                        path: "main.rs".to_string(),
+                        prefix: "x".repeat(100_000),
+                        suffix: String::new(),
+                    })
+                    .unwrap();
+            }
+            // Waiting for the subprocess would take 10 seconds if it were not killed
+            drop(source);
+            done_sender.send(()).unwrap();
+        });
+        done.recv_timeout(std::time::Duration::from_secs(5))?;
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_latest_request_is_written_while_the_subprocess_is_busy() -> anyhow::Result<()> {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        // Replies to each request with its id, after being busy for a while
+        let mut source = ProcessCompletionSource::new(
+            r#"sleep 1; sed -E 's/^\{"id":([0-9]+).*/{"id":\1,"text":""}/'"#,
+            sender,
+        )?;
+        for id in 0..4 {
+            source.request(InlineSuggestionRequest {
+                id,
+                path: "main.rs".to_string(),
+                // Larger than the pipe, so that writing the first request blocks
+                prefix: "x".repeat(100_000),
+                suffix: String::new(),
+            })?;
+        }
+        let mut ids = Vec::new();
+        while let Ok(AppMessage::InlineSuggestion(suggestion)) =
+            receiver.recv_timeout(std::time::Duration::from_secs(3))
+        {
+            ids.push(suggestion.id);
+            if suggestion.id == 3 {
+                break;
+            }
+        }
+        // The first request might be written before the others are made
+        assert!(ids == [0, 3] || ids == [3], "{ids:?}");
+        Ok(())
+    }
+}
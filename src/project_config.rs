@@ -0,0 +1,219 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use itertools::Itertools;
+use serde::Deserialize;
+use shared::canonicalized_path::CanonicalizedPath;
+
+/// The content of `.ki/config.toml` in the working directory, for example:
+///
+/// ```toml
+/// [files]
+/// exclude = ["target/**", "**/node_modules/**"]
+/// ignore_files = [".kiignore"]
+///
+/// [workspace]
+/// folders = ["../shared-lib"]
+///
+/// [permalinks]
+/// "git.example.com" = "https://{host}/{repo}/-/blob/{commit}/{path}#L{start}-{end}"
+///
+/// [editor]
+/// sticky_context = false
+/// scroll_lines = 5
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProjectConfig {
+    #[serde(default)]
+    pub(crate) files: FilesConfig,
+    #[serde(default)]
+    pub(crate) workspace: WorkspaceConfig,
+    /// Permalink templates keyed by the host of the remote, see `permalink::permalink`.
+    #[serde(default)]
+    pub(crate) permalinks: HashMap<String, String>,
+    #[serde(default)]
+    pub(crate) editor: EditorConfig,
+}
+
+/// Restricts the files walked by the file picker and global search,
+/// in addition to `.gitignore`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct FilesConfig {
+    /// Globs relative to the working directory.
+    /// If non-empty, only files matching at least one of them are walked.
+    #[serde(default)]
+    pub(crate) include: Vec<String>,
+    /// Globs relative to the working directory, files and directories matching any of them are skipped.
+    #[serde(default)]
+    pub(crate) exclude: Vec<String>,
+    /// Names of files with the syntax of `.gitignore`, which are respected in every directory like `.gitignore`.
+    #[serde(default)]
+    pub(crate) ignore_files: Vec<String>,
+}
+
+/// Extra root folders of the session, in addition to the working directory.
+/// The file picker, global search, git status and the file explorer cover every root.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct WorkspaceConfig {
+    /// Paths of folders, either absolute or relative to the working directory.
+    #[serde(default)]
+    pub(crate) folders: Vec<String>,
+}
+
+/// The settings of the editor, which are applied on startup.
+/// Settings that are not set keep their defaults.
+///
+/// Settings that run commands are not allowed here, see `UserConfig`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct EditorConfig {
+    /// Overrides the colors detected from the terminal, see `ColorSupport`.
+    pub(crate) color: Option<String>,
+    pub(crate) notes_directory: Option<PathBuf>,
+    /// The number of previous versions kept per saved file.
+    pub(crate) backup_count: Option<usize>,
+    pub(crate) persist_undo: Option<bool>,
+    pub(crate) terminal_title: Option<bool>,
+    pub(crate) loaded_buffers_limit: Option<usize>,
+    pub(crate) autosave_on_focus_lost: Option<bool>,
+    pub(crate) sticky_context: Option<bool>,
+    pub(crate) auto_center: Option<bool>,
+    pub(crate) explorer_auto_follow: Option<bool>,
+    pub(crate) scroll_lines: Option<usize>,
+    pub(crate) breadcrumbs: Option<bool>,
+    pub(crate) inline_diagnostics: Option<bool>,
+    /// See `JumpLabelStrategy`.
+    pub(crate) jump_labels: Option<String>,
+    /// Regexes of the build output formats recognized in addition to the built-in ones.
+    #[serde(default)]
+    pub(crate) error_formats: Vec<String>,
+}
+
+impl ProjectConfig {
+    /// Loads `.ki/config.toml` of `working_directory`.
+    /// Returns the default config if the file does not exist.
+    pub(crate) fn load(working_directory: &CanonicalizedPath) -> anyhow::Result<Self> {
+        let path = working_directory
+            .to_path_buf()
+            .join(".ki")
+            .join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::from_toml(&std::fs::read_to_string(&path)?)
+            .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))
+    }
+
+    /// The working directory followed by the extra folders of its `[workspace]` section.
+    ///
+    /// Folders that do not exist are skipped, so that a stale entry does not prevent Ki from working.
+    pub(crate) fn roots(
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<Vec<CanonicalizedPath>> {
+        let folders = Self::load(working_directory)?.workspace.folders;
+        Ok(Some(working_directory.clone())
+            .into_iter()
+            .chain(folders.into_iter().filter_map(|folder| {
+                CanonicalizedPath::try_from(working_directory.to_path_buf().join(&folder))
+                    .map_err(|error| {
+                        log::error!("Skipped workspace folder {:?}: {:?}", folder, error)
+                    })
+                    .ok()
+            }))
+            .filter(|root| root.to_path_buf().is_dir())
+            .unique()
+            .collect())
+    }
+
+    fn from_toml(config: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(config)?;
+        for glob in config.files.include.iter().chain(&config.files.exclude) {
+            globset::Glob::new(glob)?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod test_project_config {
+    use super::*;
+
+    #[test]
+    fn from_toml() -> anyhow::Result<()> {
+        let config = ProjectConfig::from_toml(
+            r#"
+[files]
+exclude = ["target/**"]
+ignore_files = [".kiignore"]
+
+[workspace]
+folders = ["../shared", "/tmp/docs"]
+
+[permalinks]
+"git.example.com" = "https://{host}/{repo}/blob/{commit}/{path}"
+
+[editor]
+sticky_context = false
+scroll_lines = 5
+error_formats = ["^(?P<file>\\S+):(?P<line>\\d+) (?P<message>.+)$"]
+"#,
+        )?;
+        assert_eq!(
+            config.files,
+            FilesConfig {
+                include: Vec::new(),
+                exclude: vec!["target/**".to_string()],
+                ignore_files: vec![".kiignore".to_string()],
+            }
+        );
+        assert_eq!(
+            config.workspace.folders,
+            ["../shared".to_string(), "/tmp/docs".to_string()]
+        );
+        assert_eq!(
+            config.permalinks,
+            HashMap::from([(
+                "git.example.com".to_string(),
+                "https://{host}/{repo}/blob/{commit}/{path}".to_string()
+            )])
+        );
+        assert_eq!(
+            config.editor,
+            EditorConfig {
+                sticky_context: Some(false),
+                scroll_lines: Some(5),
+                error_formats: vec![r"^(?P<file>\S+):(?P<line>\d+) (?P<message>.+)$".to_string()],
+                ..EditorConfig::default()
+            }
+        );
+        assert_eq!(ProjectConfig::from_toml("")?, ProjectConfig::default());
+        assert!(ProjectConfig::from_toml("[files]\nexclude = [\"a/{b\"]").is_err());
+        assert!(ProjectConfig::from_toml("[files]\nexcludes = []").is_err());
+        assert!(ProjectConfig::from_toml("[editor]\nsticky_context = \"no\"").is_err());
+        assert!(ProjectConfig::from_toml("[editor]\nurl_opener = \"sh\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn roots() -> anyhow::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        for folder in ["app/.ki", "lib"] {
+            std::fs::create_dir_all(temp_dir.path().join(folder))?;
+        }
+        std::fs::write(
+            temp_dir.path().join("app/.ki/config.toml"),
+            "[workspace]\nfolders = [\"../lib\", \"../missing\", \".\"]",
+        )?;
+        let working_directory = CanonicalizedPath::try_from(temp_dir.path().join("app"))?;
+        assert_eq!(
+            ProjectConfig::roots(&working_directory)?,
+            [
+                working_directory,
+                CanonicalizedPath::try_from(temp_dir.path().join("lib"))?
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use indexmap::IndexSet;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::components::prompt::PromptHistoryKey;
+
+/// The maximum number of entries kept in the history of each prompt.
+pub(crate) const MAX_ENTRIES: usize = 100;
+
+fn persisted_prompt_histories_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    crate::persistence::workspace_file("prompt_histories", working_directory)
+}
+
+/// Persist the prompt histories of `working_directory`, from the oldest entry to the latest.
+pub(crate) fn persist(
+    working_directory: &CanonicalizedPath,
+    histories: &HashMap<PromptHistoryKey, IndexSet<String>>,
+) -> anyhow::Result<()> {
+    let path = persisted_prompt_histories_path(working_directory);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // JSON objects only have string keys, so the histories are persisted as a list of pairs
+    let histories = histories
+        .iter()
+        .map(|(key, entries)| (key, entries.iter().collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    std::fs::write(path, serde_json::to_string(&histories)?)?;
+    Ok(())
+}
+
+/// Returns the persisted prompt histories of `working_directory`.
+pub(crate) fn load_persisted(
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<HashMap<PromptHistoryKey, IndexSet<String>>> {
+    let path = persisted_prompt_histories_path(working_directory);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let histories: Vec<(PromptHistoryKey, Vec<String>)> =
+        serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    Ok(histories
+        .into_iter()
+        .map(|(key, entries)| (key, entries.into_iter().collect()))
+        .collect())
+}
+
+#[cfg(test)]
+mod test_prompt_history {
+    use super::*;
+    use crate::app::Scope;
+
+    #[test]
+    fn persist_and_load() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        assert!(load_persisted(&working_directory)?.is_empty());
+        let histories = HashMap::from([
+            (
+                PromptHistoryKey::Search(Scope::Global),
+                IndexSet::from(["foo".to_string(), "bar".to_string()]),
+            ),
+            (
+                PromptHistoryKey::PipeToShell,
+                IndexSet::from(["sort".to_string()]),
+            ),
+        ]);
+        persist(&working_directory, &histories)?;
+        assert_eq!(load_persisted(&working_directory)?, histories);
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
-use std::{cell::RefCell, ops::Range, rc::Rc};
+use std::{cell::RefCell, ops::Range, path::PathBuf, rc::Rc};
 
 use itertools::Itertools;
 use lsp_types::DiagnosticSeverity;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     app::Dispatches,
@@ -28,27 +29,44 @@ impl QuickfixListItem {
             format!("{}:{}  {}", line + 1, column + 1, content)
         })
         .set_info(self.info.clone())
-        .set_group({
+        .set_group(self.group.clone().or_else(|| {
             let path = self.location().path.clone();
             Some(
                 path.display_relative()
                     .unwrap_or_else(|_| path.display_absolute()),
             )
-        })
+        }))
         .set_dispatches(Dispatches::one(crate::app::Dispatch::GotoLocation(
             self.location().to_owned(),
         )))
         .set_rank(Some(Box::new([line, column])))
     }
 
+    /// The `regex` is tested against the path, the line content and the info of this item.
+    pub(crate) fn matches(&self, regex: &regex::Regex, buffers: &[Rc<RefCell<Buffer>>]) -> bool {
+        let location = self.location();
+        regex.is_match(&location.path.display_absolute())
+            || location
+                .read_from_buffers(buffers)
+                .map(|line| regex.is_match(&line))
+                .unwrap_or(false)
+            || self
+                .info
+                .as_ref()
+                .map(|info| regex.is_match(info.content()))
+                .unwrap_or(false)
+    }
+
     pub(crate) fn set_location_range(self, range: Range<Position>) -> QuickfixListItem {
         let QuickfixListItem {
             location: Location { path, .. },
             info,
+            group,
         } = self;
         QuickfixListItem {
             info,
             location: Location { path, range },
+            group,
         }
     }
 }
@@ -74,12 +92,16 @@ impl QuickfixList {
             .sorted_by_key(|item| item.location.clone())
             .group_by(|item| item.location.clone())
             .into_iter()
-            .map(|(location, items)| QuickfixListItem {
-                location,
-                info: items
-                    .into_iter()
-                    .flat_map(|item| item.info)
-                    .reduce(Info::join),
+            .map(|(location, items)| {
+                let items = items.collect_vec();
+                QuickfixListItem {
+                    location,
+                    group: items.iter().find_map(|item| item.group.clone()),
+                    info: items
+                        .into_iter()
+                        .flat_map(|item| item.info)
+                        .reduce(Info::join),
+                }
             })
             .collect_vec();
         dropdown.set_items(
@@ -118,12 +140,31 @@ impl QuickfixList {
         self.dropdown.set_current_item_index(item_index);
         self
     }
+
+    pub(crate) fn set_title(mut self, title: String) -> Self {
+        self.dropdown.set_title(title);
+        self
+    }
+
+    pub(crate) fn current_item_location(&self) -> Option<Location> {
+        self.dropdown
+            .current_item()?
+            .dispatches
+            .into_vec()
+            .into_iter()
+            .find_map(|dispatch| match dispatch {
+                crate::app::Dispatch::GotoLocation(location) => Some(location),
+                _ => None,
+            })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct QuickfixListItem {
     location: Location,
     info: Option<Info>,
+    /// The group of this item in the list, which is the path of its file by default.
+    group: Option<String>,
 }
 
 impl PartialOrd for QuickfixListItem {
@@ -143,13 +184,25 @@ impl From<Location> for QuickfixListItem {
         QuickfixListItem {
             location: value,
             info: None,
+            group: None,
         }
     }
 }
 
 impl QuickfixListItem {
     pub(crate) fn new(location: Location, info: Option<Info>) -> QuickfixListItem {
-        QuickfixListItem { location, info }
+        QuickfixListItem {
+            location,
+            info,
+            group: None,
+        }
+    }
+
+    pub(crate) fn set_group(self, group: String) -> Self {
+        Self {
+            group: Some(group),
+            ..self
+        }
     }
 
     pub(crate) fn location(&self) -> &Location {
@@ -265,6 +318,148 @@ impl DiagnosticSeverityRange {
     }
 }
 
+/// Number of lines shown above and below the target line in the preview.
+const PREVIEW_CONTEXT_LINES: usize = 5;
+
+/// An excerpt around the location of a quickfix list item,
+/// which is shown without opening the file in an editor.
+pub(crate) struct QuickfixListPreview {
+    pub(crate) title: String,
+    pub(crate) content: String,
+    pub(crate) language: Option<tree_sitter::Language>,
+    /// The index of the target line, relative to `content`.
+    pub(crate) highlight_line_index: usize,
+    /// The index of the first line of `content` in the file,
+    /// so that the preview shows the line numbers of the file.
+    pub(crate) first_line_index: usize,
+}
+
+impl QuickfixListPreview {
+    pub(crate) fn new(
+        location: &Location,
+        buffers: &[Rc<RefCell<Buffer>>],
+    ) -> anyhow::Result<QuickfixListPreview> {
+        let content = match buffers
+            .iter()
+            .find(|buffer| buffer.borrow().path().as_ref() == Some(&location.path))
+        {
+            Some(buffer) => buffer.borrow().content(),
+            None => location.path.read()?,
+        };
+        // The location might be past the end of the file, for example if lines were removed
+        // since the quickfix list was populated, in which case the last line is the target
+        let target_line = location
+            .range
+            .start
+            .line
+            .min(content.lines().count().saturating_sub(1));
+        let first_line = target_line.saturating_sub(PREVIEW_CONTEXT_LINES);
+        let content = content
+            .lines()
+            .skip(first_line)
+            .take(target_line - first_line + PREVIEW_CONTEXT_LINES + 1)
+            .join("\n");
+        Ok(QuickfixListPreview {
+            title: format!(
+                "Preview: {}:{}",
+                location
+                    .path
+                    .display_relative()
+                    .unwrap_or_else(|_| location.path.display_absolute()),
+                location.range.start.line + 1
+            ),
+            content,
+            language: shared::language::from_path(&location.path)
+                .and_then(|language| language.tree_sitter_language()),
+            highlight_line_index: target_line - first_line,
+            first_line_index: first_line,
+        })
+    }
+}
+
+/// The persisted form of a `QuickfixListItem`.
+/// The decorations of `info` are not persisted.
+#[derive(Serialize, Deserialize)]
+struct PersistedQuickfixListItem {
+    path: PathBuf,
+    start: (usize, usize),
+    end: (usize, usize),
+    info: Option<(String, String)>,
+    #[serde(default)]
+    group: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedQuickfixList {
+    title: String,
+    items: Vec<PersistedQuickfixListItem>,
+}
+
+fn persisted_quickfix_list_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    crate::persistence::workspace_file("quickfix_lists", working_directory)
+}
+
+/// Persist the given list as the last quickfix list of `working_directory`.
+pub(crate) fn persist(
+    working_directory: &CanonicalizedPath,
+    title: &str,
+    items: &[QuickfixListItem],
+) -> anyhow::Result<()> {
+    let list = PersistedQuickfixList {
+        title: title.to_string(),
+        items: items
+            .iter()
+            .map(|item| {
+                let Range { start, end } = item.location.range;
+                PersistedQuickfixListItem {
+                    path: item.location.path.to_path_buf().clone(),
+                    start: (start.line, start.column),
+                    end: (end.line, end.column),
+                    info: item
+                        .info
+                        .as_ref()
+                        .map(|info| (info.title(), info.content().clone())),
+                    group: item.group.clone(),
+                }
+            })
+            .collect_vec(),
+    };
+    let path = persisted_quickfix_list_path(working_directory);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(&list)?)?;
+    Ok(())
+}
+
+/// Returns the title and the items of the last quickfix list of `working_directory`.
+/// Items pointing to files that no longer exist are omitted.
+pub(crate) fn load_persisted(
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<Option<(String, Vec<QuickfixListItem>)>> {
+    let path = persisted_quickfix_list_path(working_directory);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let list: PersistedQuickfixList = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let items = list
+        .items
+        .into_iter()
+        .filter_map(|item| {
+            Some(QuickfixListItem {
+                location: Location {
+                    path: item.path.try_into().ok()?,
+                    range: Position::new(item.start.0, item.start.1)
+                        ..Position::new(item.end.0, item.end.1),
+                },
+                info: item.info.map(|(title, content)| Info::new(title, content)),
+                group: item.group,
+            })
+        })
+        .collect_vec();
+    Ok(Some((list.title, items)))
+}
+
 #[cfg(test)]
 mod test_quickfix_list {
     use crate::{components::suggestive_editor::Info, position::Position};
@@ -280,6 +475,7 @@ mod test_quickfix_list {
                 range: Position { line: 1, column: 2 }..Position { line: 1, column: 3 },
             },
             info: None,
+            group: None,
         };
         let bar = QuickfixListItem {
             location: Location {
@@ -287,6 +483,7 @@ mod test_quickfix_list {
                 range: Position { line: 1, column: 1 }..Position { line: 1, column: 2 },
             },
             info: None,
+            group: None,
         };
         let spam = QuickfixListItem {
             location: Location {
@@ -294,6 +491,7 @@ mod test_quickfix_list {
                 range: Position { line: 1, column: 1 }..Position { line: 1, column: 2 },
             },
             info: None,
+            group: None,
         };
         let quickfix_list =
             QuickfixList::new(vec![foo.clone(), bar.clone(), spam.clone()], Vec::new());
@@ -309,6 +507,7 @@ mod test_quickfix_list {
                     range: Position { line: 1, column: 1 }..Position { line: 1, column: 2 },
                 },
                 info: Some(Info::new("Title 1".to_string(), "spongebob".to_string())),
+                group: None,
             },
             QuickfixListItem {
                 location: Location {
@@ -316,6 +515,7 @@ mod test_quickfix_list {
                     range: Position { line: 1, column: 1 }..Position { line: 1, column: 2 },
                 },
                 info: Some(Info::new("Title 2".to_string(), "squarepants".to_string())),
+                group: None,
             },
         ]
         .to_vec();
@@ -332,7 +532,8 @@ mod test_quickfix_list {
                 info: Some(Info::new(
                     "Title 1".to_string(),
                     ["spongebob", "squarepants"].join("\n==========\n")
-                ))
+                )),
+                group: None,
             }]
         )
     }
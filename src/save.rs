@@ -0,0 +1,156 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Writes `content` into the file of `path`, backing up its previous content first.
+///
+/// The previous `backup_count` versions of the file are kept in `cache_dir()/backups`,
+/// no backups are kept if it is 0.
+/// A failure to back up is logged rather than returned, so that it never prevents saving.
+pub(crate) fn save(path: &Path, content: &str, backup_count: usize) -> anyhow::Result<()> {
+    if backup_count > 0 && path.exists() {
+        if let Err(error) = backup(path, &grammar::cache_dir().join("backups"), backup_count) {
+            log::error!("Failed to back up {}: {:?}", path.display(), error);
+        }
+    }
+    write_atomically(path, content)
+}
+
+/// Writes `content` into a temporary file next to `path`, then renames it to `path`,
+/// so that a crash in the middle of writing never leaves a truncated file behind.
+///
+/// The permissions and the ownership of the existing file are carried over.
+/// Falls back to overwriting the file in place only if the temporary file cannot be prepared,
+/// for example when the directory is not writable or the ownership cannot be changed.
+/// Failures after that, such as running out of disk space, are returned without touching the file.
+fn write_atomically(path: &Path, content: &str) -> anyhow::Result<()> {
+    write_atomically_with(path, content, |file, content| file.write_all(content))
+}
+
+fn write_atomically_with(
+    path: &Path,
+    content: &str,
+    write: impl FnOnce(&mut std::fs::File, &[u8]) -> std::io::Result<()>,
+) -> anyhow::Result<()> {
+    let mut file = match temporary_file(path) {
+        Ok(file) => file,
+        Err(error) => {
+            log::info!(
+                "Overwriting {} in place, since it cannot be replaced: {:?}",
+                path.display(),
+                error
+            );
+            std::fs::write(path, content)?;
+            return Ok(());
+        }
+    };
+    write(file.as_file_mut(), content.as_bytes())?;
+    file.as_file().sync_all()?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Creates an empty temporary file next to `path`,
+/// with the permissions and the ownership of the file of `path` if it exists.
+fn temporary_file(path: &Path) -> anyhow::Result<tempfile::NamedTempFile> {
+    let directory = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("{} has no parent", path.display()))?;
+    let file = tempfile::NamedTempFile::new_in(directory)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        file.as_file().set_permissions(metadata.permissions())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let temporary = file.as_file().metadata()?;
+            if (temporary.uid(), temporary.gid()) != (metadata.uid(), metadata.gid()) {
+                std::os::unix::fs::fchown(
+                    file.as_file(),
+                    Some(metadata.uid()),
+                    Some(metadata.gid()),
+                )?;
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// Copies the file of `path` into `directory` as the latest of its `count` backups,
+/// which are named `<path>.1` (the latest) to `<path>.<count>` (the oldest),
+/// where `<path>` is the name of the absolute path, see `crate::persistence::file_name`.
+fn backup(path: &Path, directory: &Path, count: usize) -> anyhow::Result<()> {
+    let name = crate::persistence::file_name(path);
+    let backup_path = |index: usize| -> PathBuf { directory.join(format!("{name}.{index}")) };
+    std::fs::create_dir_all(directory)?;
+    for index in (1..count).rev() {
+        let from = backup_path(index);
+        if from.exists() {
+            std::fs::rename(from, backup_path(index + 1))?;
+        }
+    }
+    std::fs::copy(path, backup_path(1))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_save {
+    use super::*;
+
+    #[test]
+    fn write_atomically_preserves_permissions() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("script.sh");
+        std::fs::write(&path, "old")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o750))?;
+        }
+        write_atomically(&path, "new")?;
+        assert_eq!(std::fs::read_to_string(&path)?, "new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(
+                std::fs::metadata(&path)?.permissions().mode() & 0o777,
+                0o750
+            );
+        }
+        // Only the saved file is left in the directory
+        assert_eq!(std::fs::read_dir(directory.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn write_atomically_keeps_the_file_if_writing_fails() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("a.txt");
+        std::fs::write(&path, "old")?;
+        let result = write_atomically_with(&path, "new", |_, _| {
+            Err(std::io::Error::other("No space left on device"))
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&path)?, "old");
+        // The temporary file is removed
+        assert_eq!(std::fs::read_dir(directory.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn backups_are_rotated() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("a.txt");
+        let backups = directory.path().join("backups");
+        for content in ["1", "2", "3"] {
+            std::fs::write(&path, content)?;
+            backup(&path, &backups, 2)?;
+        }
+        let mut contents = std::fs::read_dir(&backups)?
+            .map(|entry| Ok(std::fs::read_to_string(entry?.path())?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        contents.sort();
+        assert_eq!(contents, ["2", "3"]);
+        Ok(())
+    }
+}
@@ -1,3 +1,8 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
 use itertools::Itertools;
 
 use crate::{
@@ -7,6 +12,44 @@ use crate::{
     style::Style,
 };
 
+/// Statistics of the recently rendered frames, for measuring the rendering performance.
+#[derive(Default)]
+pub(crate) struct RenderMetrics {
+    /// The time of each frame rendered within the last second.
+    frames: VecDeque<Instant>,
+    last_frame_duration: Duration,
+    last_repainted_cells: usize,
+}
+
+impl RenderMetrics {
+    pub(crate) fn record(&mut self, started_at: Instant, repainted_cells: usize) {
+        let now = Instant::now();
+        self.last_frame_duration = now.duration_since(started_at);
+        self.last_repainted_cells = repainted_cells;
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now.duration_since(*frame) > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    pub(crate) fn last_frame(&self) -> Option<Instant> {
+        self.frames.back().copied()
+    }
+
+    pub(crate) fn display(&self) -> String {
+        format!(
+            "{} fps | {:.1}ms | {} cells",
+            self.frames.len(),
+            self.last_frame_duration.as_secs_f64() * 1000.0,
+            self.last_repainted_cells
+        )
+    }
+}
+
 #[derive(Default, Clone)]
 pub(crate) struct Screen {
     windows: Vec<Window>,
@@ -149,9 +192,20 @@ mod test_screen {
         app::Dimension,
         grid::{Cell, Grid, PositionedCell},
         position::Position,
-        screen::{Screen, Window},
+        screen::{RenderMetrics, Screen, Window},
     };
 
+    #[test]
+    fn render_metrics_only_count_frames_of_last_second() {
+        let mut metrics = RenderMetrics::default();
+        let now = std::time::Instant::now();
+        metrics.record(now, 10);
+        metrics.record(now, 3);
+        assert_eq!(metrics.last_frame().map(|frame| frame >= now), Some(true));
+        assert!(metrics.display().starts_with("2 fps"));
+        assert!(metrics.display().ends_with("| 3 cells"));
+    }
+
     #[test]
     fn diff_same_size() {
         let dimension = Dimension {
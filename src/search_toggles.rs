@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{app::Scope, context::LocalSearchConfigMode};
+
+/// The toggles of the search config of a scope, which are remembered across sessions,
+/// unlike the search and the replacement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SearchToggles {
+    pub(crate) mode: LocalSearchConfigMode,
+    pub(crate) smart_case: bool,
+    #[serde(default)]
+    pub(crate) preserve_case: bool,
+}
+
+fn persisted_search_toggles_path(working_directory: &CanonicalizedPath) -> PathBuf {
+    crate::persistence::workspace_file("search_toggles", working_directory)
+}
+
+/// Persist the search toggles of each scope of `working_directory`.
+pub(crate) fn persist(
+    working_directory: &CanonicalizedPath,
+    toggles: &[(Scope, SearchToggles)],
+) -> anyhow::Result<()> {
+    let path = persisted_search_toggles_path(working_directory);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string(toggles)?)?;
+    Ok(())
+}
+
+/// Returns the persisted search toggles of each scope of `working_directory`.
+pub(crate) fn load_persisted(
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<Vec<(Scope, SearchToggles)>> {
+    let path = persisted_search_toggles_path(working_directory);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod test_search_toggles {
+    use super::*;
+    use crate::list::grep::RegexConfig;
+
+    #[test]
+    fn persist_and_load() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let working_directory: CanonicalizedPath = directory.path().try_into()?;
+        assert!(load_persisted(&working_directory)?.is_empty());
+        let toggles = [
+            (
+                Scope::Local,
+                SearchToggles {
+                    mode: LocalSearchConfigMode::Regex(RegexConfig {
+                        escaped: false,
+                        case_sensitive: false,
+                        match_whole_word: true,
+                    }),
+                    smart_case: true,
+                    preserve_case: true,
+                },
+            ),
+            (
+                Scope::Global,
+                SearchToggles {
+                    mode: LocalSearchConfigMode::AstGrep,
+                    smart_case: false,
+                    preserve_case: false,
+                },
+            ),
+        ];
+        persist(&working_directory, &toggles)?;
+        assert_eq!(load_persisted(&working_directory)?, toggles);
+        Ok(())
+    }
+}
@@ -83,17 +83,21 @@ impl Filter {
                 .map(|rope| rope.to_string()),
             FilterTarget::Info => item.info().as_ref().map(|info| info.content().clone()),
         }?;
+        self.matches(&target).then_some(item)
+    }
+
+    /// Returns true if `text` should be kept by this filter.
+    pub(crate) fn matches(&self, text: &str) -> bool {
         let matched: bool = match &self.mechanism {
             FilterMechanism::Literal(literal) => {
-                target.to_lowercase().contains(&literal.to_lowercase())
+                text.to_lowercase().contains(&literal.to_lowercase())
             }
-            FilterMechanism::Regex(regex) => regex.is_match(&target),
+            FilterMechanism::Regex(regex) => regex.is_match(text),
         };
         match self.kind {
             FilterKind::Keep => matched,
             FilterKind::Remove => !matched,
         }
-        .then_some(item)
     }
 
     pub(crate) fn new(kind: FilterKind, target: FilterTarget, mechanism: FilterMechanism) -> Self {
@@ -196,7 +200,9 @@ impl SelectionSet {
     pub(crate) fn move_left(&mut self, cursor_direction: &Direction) {
         self.apply_mut(|selection| {
             let cursor_char_index = selection.to_char_index(cursor_direction);
-            selection.range = (cursor_char_index - 1..cursor_char_index - 1).into()
+            *selection = selection
+                .clone()
+                .set_range((cursor_char_index - 1..cursor_char_index - 1).into())
         });
     }
 
@@ -204,7 +210,46 @@ impl SelectionSet {
         self.apply_mut(|selection| {
             let cursor_char_index = selection.to_char_index(cursor_direction);
             let next = (cursor_char_index + 1).min(CharIndex(len_chars));
-            selection.range = (next..next).into()
+            *selection = selection.clone().set_range((next..next).into())
+        });
+    }
+
+    /// Moves each cursor to the start of the current or previous word,
+    /// or to the end of the current or next word, like `alt+b` and `alt+f` of GNU Readline.
+    pub(crate) fn move_word(
+        &mut self,
+        cursor_direction: &Direction,
+        rope: &ropey::Rope,
+        direction: &Direction,
+    ) {
+        let len_chars = rope.len_chars();
+        let is_word_char = |char_index: usize| {
+            rope.get_char(char_index)
+                .map_or(false, |c| c.is_alphanumeric() || c == '_')
+        };
+        self.apply_mut(|selection| {
+            let mut index = selection.to_char_index(cursor_direction).0.min(len_chars);
+            match direction {
+                Direction::Start => {
+                    while index > 0 && !is_word_char(index - 1) {
+                        index -= 1
+                    }
+                    while index > 0 && is_word_char(index - 1) {
+                        index -= 1
+                    }
+                }
+                Direction::End => {
+                    while index < len_chars && !is_word_char(index) {
+                        index += 1
+                    }
+                    while index < len_chars && is_word_char(index) {
+                        index += 1
+                    }
+                }
+            }
+            *selection = selection
+                .clone()
+                .set_range((CharIndex(index)..CharIndex(index)).into())
         });
     }
 
@@ -411,6 +456,78 @@ impl SelectionSet {
         }
     }
 
+    /// Keeps only the selections for which `predicate`, given their indices, returns true.
+    /// The primary selection remains primary if it is kept, otherwise the first kept selection becomes primary.
+    /// Returns `None` if no selection is kept.
+    pub(crate) fn retain(
+        &self,
+        predicate: impl Fn(usize, &Selection) -> bool,
+    ) -> Option<SelectionSet> {
+        let kept = self
+            .selections
+            .iter()
+            .enumerate()
+            .filter(|(index, selection)| predicate(*index, selection))
+            .collect_vec();
+        let cursor_index = kept
+            .iter()
+            .position(|(index, _)| *index == self.cursor_index)
+            .unwrap_or(0);
+        let selections = NonEmpty::from_vec(
+            kept.into_iter()
+                .map(|(_, selection)| selection.clone())
+                .collect(),
+        )?;
+        Some(SelectionSet {
+            cursor_index,
+            selections,
+            ..self.clone()
+        })
+    }
+
+    /// Makes the selection after the primary selection by position primary,
+    /// or the one before it if `direction` is `Start`, wrapping around.
+    pub(crate) fn cycle_primary_selection(&mut self, direction: &Direction) {
+        let indices = self.indices_by_position();
+        let Some(position) = indices.iter().position(|index| *index == self.cursor_index) else {
+            return;
+        };
+        let position = match direction {
+            Direction::End => (position + 1) % indices.len(),
+            Direction::Start => (position + indices.len() - 1) % indices.len(),
+        };
+        self.cursor_index = indices[position];
+    }
+
+    /// The indices of the selections, sorted by the positions of the selections.
+    pub(crate) fn indices_by_position(&self) -> Vec<usize> {
+        self.selections
+            .iter()
+            .enumerate()
+            .sorted_by_key(|(_, selection)| selection.extended_range().start)
+            .map(|(index, _)| index)
+            .collect_vec()
+    }
+
+    /// Adds `selection` as the primary selection.
+    /// If `replace_primary` is true, the current primary selection is removed.
+    pub(crate) fn add_primary_selection(
+        self,
+        selection: Selection,
+        replace_primary: bool,
+    ) -> SelectionSet {
+        let mut selections = self.selections.clone().into_iter().collect_vec();
+        if replace_primary && self.cursor_index < selections.len() {
+            selections.remove(self.cursor_index);
+        }
+        selections.push(selection);
+        SelectionSet {
+            cursor_index: selections.len() - 1,
+            selections: NonEmpty::from_vec(selections).unwrap_or_else(|| self.selections.clone()),
+            ..self
+        }
+    }
+
     pub(crate) fn set_selections(self, selections: NonEmpty<Selection>) -> SelectionSet {
         Self { selections, ..self }
     }
@@ -505,23 +622,28 @@ impl SelectionMode {
             filters,
         };
         Ok(match self {
-            SelectionMode::WordShort => Box::new(selection_mode::WordShort::as_regex(buffer)?),
-            SelectionMode::WordLong => Box::new(selection_mode::WordLong::as_regex(buffer)?),
+            SelectionMode::WordShort => Box::new(selection_mode::WordShort::as_regex()?),
+            SelectionMode::WordLong => Box::new(selection_mode::WordLong::as_regex()?),
             SelectionMode::LineTrimmed => Box::new(selection_mode::LineTrimmed),
             SelectionMode::LineFull => Box::new(selection_mode::LineFull),
             SelectionMode::Column => {
-                let current_column = buffer
-                    .char_to_position(current_selection.to_char_index(cursor_direction))?
-                    .column;
+                let current_column = match current_selection.sticky_column() {
+                    Some(column) => column,
+                    None => {
+                        buffer
+                            .char_to_position(current_selection.to_char_index(cursor_direction))?
+                            .column
+                    }
+                };
                 Box::new(selection_mode::Column::new(current_column))
             }
             SelectionMode::Custom => {
                 Box::new(selection_mode::Custom::new(current_selection.clone()))
             }
             SelectionMode::Find { search } => match search.mode {
-                LocalSearchConfigMode::Regex(regex) => Box::new(
-                    selection_mode::Regex::from_config(buffer, &search.search, regex)?,
-                ),
+                LocalSearchConfigMode::Regex(regex) => {
+                    Box::new(selection_mode::Regex::from_config(&search.search, regex)?)
+                }
                 LocalSearchConfigMode::AstGrep => {
                     Box::new(selection_mode::AstGrep::new(buffer, &search.search)?)
                 }
@@ -541,7 +663,7 @@ impl SelectionMode {
                 Box::new(selection_mode::GitHunk::new(diff_mode, buffer)?)
             }
             SelectionMode::Bookmark => Box::new(selection_mode::Bookmark),
-            SelectionMode::EmptyLine => Box::new(selection_mode::Regex::new(buffer, r"(?m)^\s*$")?),
+            SelectionMode::EmptyLine => Box::new(selection_mode::Regex::new(r"(?m)^\s*$")?),
             SelectionMode::LocalQuickfix { .. } => {
                 Box::new(selection_mode::LocalQuickfix::new(params))
             }
@@ -580,6 +702,11 @@ pub(crate) struct Selection {
 
     /// For example, used for Diagnostic and Git Hunk
     info: Option<Info>,
+
+    /// The column that vertical movements aim for, so that moving across a shorter line
+    /// does not lose the column of the cursor.
+    /// Forgotten whenever the range is changed by any other movement.
+    sticky_column: Option<usize>,
 }
 
 impl Selection {
@@ -625,6 +752,7 @@ impl Selection {
             range: (CharIndex(0)..CharIndex(0)).into(),
             initial_range: None,
             info: None,
+            sticky_column: None,
         }
     }
 
@@ -636,6 +764,32 @@ impl Selection {
         cursor_direction: &Direction,
         filters: &Filters,
     ) -> anyhow::Result<Option<ApplyMovementResult>> {
+        let cursor = current_selection.to_char_index(cursor_direction);
+        let line = buffer.char_to_line(cursor)?;
+        let column = match current_selection.sticky_column {
+            Some(column) => column,
+            None => buffer.char_to_position(cursor)?.column,
+        };
+
+        // Entering another selection mode (for example leaving Line mode)
+        // starts from the sticky column instead of the start of the current selection
+        let repositioned;
+        let current_selection = match current_selection.sticky_column {
+            Some(column) if *direction == Movement::Current && !current_selection.is_extended() => {
+                let start = buffer.position_to_char(Position::new(
+                    line,
+                    column.min(buffer.line_len_without_newline(line).saturating_sub(1)),
+                ))?;
+                let end = (start + 1).min(CharIndex(buffer.len_chars()));
+                repositioned = current_selection
+                    .clone()
+                    .set_range((start..end).into())
+                    .set_sticky_column(Some(column));
+                &repositioned
+            }
+            _ => current_selection,
+        };
+
         let selection_mode = mode.to_selection_mode_trait_object(
             buffer,
             current_selection,
@@ -650,7 +804,27 @@ impl Selection {
             filters,
         };
 
-        selection_mode.apply_movement(params, *direction)
+        let Some(result) = selection_mode.apply_movement(params, *direction)? else {
+            return Ok(None);
+        };
+        let keeps_column = match direction {
+            Movement::Up | Movement::Down => true,
+            Movement::Current => {
+                let range = result.selection.extended_range();
+                if current_selection.sticky_column.is_some() {
+                    buffer.char_to_line(range.start)? == line
+                } else {
+                    range.contains(&cursor)
+                }
+            }
+            _ => false,
+        };
+        Ok(Some(ApplyMovementResult {
+            selection: result
+                .selection
+                .set_sticky_column(keeps_column.then_some(column)),
+            ..result
+        }))
     }
     #[cfg(test)]
     pub(crate) fn escape_highlight_mode(&mut self) {
@@ -677,6 +851,7 @@ impl Selection {
             range,
             initial_range: self.initial_range,
             info: self.info.clone(),
+            sticky_column: self.sticky_column,
         }
     }
 
@@ -685,7 +860,23 @@ impl Selection {
     }
 
     pub(crate) fn set_range(self, range: CharIndexRange) -> Selection {
-        Selection { range, ..self }
+        let sticky_column = self.sticky_column.filter(|_| range == self.range);
+        Selection {
+            range,
+            sticky_column,
+            ..self
+        }
+    }
+
+    pub(crate) fn sticky_column(&self) -> Option<usize> {
+        self.sticky_column
+    }
+
+    pub(crate) fn set_sticky_column(self, sticky_column: Option<usize>) -> Selection {
+        Selection {
+            sticky_column,
+            ..self
+        }
     }
 
     /// WARNING: You should always use `extended_range` unless you know what you are doing
@@ -728,6 +919,7 @@ impl Add<usize> for Selection {
             range: (self.range.start + rhs..self.range.end + rhs).into(),
             initial_range: self.initial_range,
             info: self.info,
+            sticky_column: self.sticky_column,
         }
     }
 }
@@ -740,6 +932,7 @@ impl Sub<usize> for Selection {
             range: (self.range.start - rhs..self.range.end - rhs).into(),
             initial_range: self.initial_range,
             info: self.info,
+            sticky_column: self.sticky_column,
         }
     }
 }
@@ -9,7 +9,8 @@ impl SelectionMode for Bookmark {
     ) -> anyhow::Result<Box<dyn Iterator<Item = super::ByteRange> + 'a>> {
         let buffer = params.buffer;
         Ok(Box::new(buffer.bookmarks().into_iter().filter_map(
-            |range| {
+            |bookmark| {
+                let range = bookmark.range;
                 let start = buffer.char_to_byte(range.start).ok()?;
                 let end = buffer.char_to_byte(range.end).ok()?;
                 Some(super::ByteRange::new(start..end))
@@ -1,4 +1,4 @@
-use crate::{buffer::Buffer, git::GitOperation};
+use crate::{buffer::Buffer, char_index_range::CharIndexRange, git::GitOperation};
 use itertools::Itertools;
 
 use super::{ByteRange, SelectionMode};
@@ -30,6 +30,29 @@ impl GitHunk {
             .collect_vec();
         Ok(GitHunk { ranges })
     }
+
+    /// The ranges of the hunks of `buffer`, with their old content (see `Hunk::old_content`).
+    ///
+    /// Like the ranges of this selection mode, they are computed from the saved content of the file.
+    pub(crate) fn old_contents(
+        diff_mode: &crate::git::DiffMode,
+        buffer: &Buffer,
+    ) -> anyhow::Result<Vec<(CharIndexRange, String)>> {
+        let Some(path) = buffer.path() else {
+            return Ok(Vec::new());
+        };
+        let file_diff = path.file_diff(diff_mode, &".".try_into()?)?;
+        Ok(file_diff
+            .hunks()
+            .iter()
+            .filter_map(|hunk| {
+                let range = buffer
+                    .line_range_to_char_index_range(hunk.line_range().clone())
+                    .ok()?;
+                Some((range, hunk.old_content().to_string()))
+            })
+            .collect_vec())
+    }
 }
 
 impl SelectionMode for GitHunk {
@@ -19,8 +19,8 @@ impl SelectionMode for LineFull {
         let buffer = params.buffer;
         let len_lines = buffer.len_lines();
 
-        Ok(Box::new(
-            (0..len_lines)
+        super::iter_cached(buffer, "line_full", || {
+            Ok((0..len_lines)
                 .take(
                     // This is a weird hack, because `rope.len_lines`
                     // returns an extra line which is empty if the rope ends with the newline character
@@ -37,8 +37,9 @@ impl SelectionMode for LineFull {
                     let end = start + len_bytes;
 
                     Some(super::ByteRange::new(start..end))
-                }),
-        ))
+                })
+                .collect())
+        })
     }
 }
 
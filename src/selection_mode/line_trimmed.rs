@@ -19,8 +19,8 @@ impl SelectionMode for LineTrimmed {
         let buffer = params.buffer;
         let len_lines = buffer.len_lines();
 
-        Ok(Box::new(
-            (0..len_lines)
+        super::iter_cached(buffer, "line_trimmed", || {
+            Ok((0..len_lines)
                 .take(
                     // This is a weird hack, because `rope.len_lines`
                     // returns an extra line which is empty if the rope ends with the newline character
@@ -43,8 +43,9 @@ impl SelectionMode for LineTrimmed {
                         };
                     let start = trim_leading_spaces(start, &line.to_string()).min(end);
                     Some(super::ByteRange::new(start..end))
-                }),
-        ))
+                })
+                .collect())
+        })
     }
 }
 
@@ -104,6 +104,19 @@ impl Ord for ByteRange {
     }
 }
 
+/// Iterates the selections cached in `buffer` under `key`, see `Buffer::cached_byte_ranges`.
+/// Only selection modes whose selections depend solely on the buffer should use this.
+pub(crate) fn iter_cached<'a>(
+    buffer: &Buffer,
+    key: &str,
+    compute: impl FnOnce() -> anyhow::Result<Vec<ByteRange>>,
+) -> anyhow::Result<Box<dyn Iterator<Item = ByteRange> + 'a>> {
+    let byte_ranges = buffer.cached_byte_ranges(key, compute)?;
+    Ok(Box::new(
+        (0..byte_ranges.len()).map(move |index| byte_ranges[index].clone()),
+    ))
+}
+
 #[derive(Clone)]
 pub(crate) struct SelectionModeParams<'a> {
     pub(crate) buffer: &'a Buffer,
@@ -259,8 +272,11 @@ pub trait SelectionMode {
             ..
         } = params;
         let start = current_selection.range().start;
-        let current_position = buffer.char_to_position(start)?;
         let current_line = buffer.char_to_line(start)?;
+        let current_column = match current_selection.sticky_column() {
+            Some(column) => column,
+            None => buffer.char_to_position(start)?.column,
+        };
         let selection = self
             .iter_filtered(params)?
             .filter_map(|range| {
@@ -271,7 +287,7 @@ pub trait SelectionMode {
             .sorted_by_key(|(position, _)| {
                 (
                     current_line.abs_diff(position.line),
-                    position.column.abs_diff(current_position.column),
+                    position.column.abs_diff(current_column),
                 )
             })
             .next()
@@ -1,10 +1,9 @@
-use crate::{buffer::Buffer, list::grep::RegexConfig};
+use crate::list::grep::RegexConfig;
 
 use super::{ByteRange, SelectionMode};
 
 pub(crate) struct Regex {
     regex: regex::Regex,
-    content: String,
 }
 
 pub(crate) fn get_regex(pattern: &str, config: RegexConfig) -> anyhow::Result<regex::Regex> {
@@ -28,19 +27,12 @@ pub(crate) fn get_regex(pattern: &str, config: RegexConfig) -> anyhow::Result<re
 }
 
 impl Regex {
-    pub(crate) fn from_config(
-        buffer: &Buffer,
-        pattern: &str,
-        config: RegexConfig,
-    ) -> anyhow::Result<Self> {
+    pub(crate) fn from_config(pattern: &str, config: RegexConfig) -> anyhow::Result<Self> {
         let regex = get_regex(pattern, config)?;
-        Ok(Self {
-            regex,
-            content: buffer.rope().to_string(),
-        })
+        Ok(Self { regex })
     }
 
-    pub(crate) fn new(buffer: &Buffer, pattern: &str) -> anyhow::Result<Self> {
+    pub(crate) fn new(pattern: &str) -> anyhow::Result<Self> {
         let regex = get_regex(
             pattern,
             RegexConfig {
@@ -49,22 +41,23 @@ impl Regex {
                 match_whole_word: false,
             },
         )?;
-        Ok(Self {
-            regex,
-            content: buffer.rope().to_string(),
-        })
+        Ok(Self { regex })
     }
 }
 
 impl SelectionMode for Regex {
     fn iter<'a>(
         &'a self,
-        _params: super::SelectionModeParams<'a>,
+        params: super::SelectionModeParams<'a>,
     ) -> anyhow::Result<Box<dyn Iterator<Item = ByteRange> + 'a>> {
-        let matches = self.regex.find_iter(&self.content);
-        Ok(Box::new(matches.map(move |matches| {
-            ByteRange::new(matches.start()..matches.end())
-        })))
+        let buffer = params.buffer;
+        super::iter_cached(buffer, &format!("regex:{}", self.regex.as_str()), || {
+            Ok(self
+                .regex
+                .find_iter(&buffer.rope().to_string())
+                .map(|matches| ByteRange::new(matches.start()..matches.end()))
+                .collect())
+        })
     }
 }
 
@@ -78,7 +71,6 @@ mod test_regex {
     fn escaped() {
         let buffer = Buffer::new(None, "fn main() { let x = m.in; }");
         crate::selection_mode::Regex::from_config(
-            &buffer,
             "m.in",
             RegexConfig {
                 escaped: true,
@@ -94,7 +86,6 @@ mod test_regex {
     fn unescaped() {
         let buffer = Buffer::new(None, "fn main() { let x = m.in; }");
         crate::selection_mode::Regex::from_config(
-            &buffer,
             "m.in",
             RegexConfig {
                 escaped: false,
@@ -114,7 +105,6 @@ mod test_regex {
     fn ignore_case() {
         let buffer = Buffer::new(None, "fn Main() { let x = m.in; }");
         crate::selection_mode::Regex::from_config(
-            &buffer,
             "m.in",
             RegexConfig {
                 escaped: false,
@@ -134,7 +124,6 @@ mod test_regex {
     fn match_whole_word() {
         let buffer = Buffer::new(None, "fn Main() { let x = main_war; }");
         crate::selection_mode::Regex::from_config(
-            &buffer,
             "m.in",
             RegexConfig {
                 escaped: false,
@@ -158,7 +147,6 @@ mod test_regex {
 ",
         );
         crate::selection_mode::Regex::from_config(
-            &buffer,
             r"^- \[ \](.*)$",
             RegexConfig {
                 escaped: false,
@@ -13,14 +13,15 @@ impl SelectionMode for Token {
         let tree = buffer
             .tree()
             .ok_or(anyhow::anyhow!("Unable to find Treesitter language"))?;
-        Ok(Box::new(
-            crate::tree_sitter_traversal::traverse(
+        super::iter_cached(buffer, "token", || {
+            Ok(crate::tree_sitter_traversal::traverse(
                 tree.walk(),
                 crate::tree_sitter_traversal::Order::Post,
             )
             .filter(|node| node.child_count() == 0)
-            .map(|node| ByteRange::new(node.byte_range())),
-        ))
+            .map(|node| ByteRange::new(node.byte_range()))
+            .collect())
+        })
     }
 
     fn parent(
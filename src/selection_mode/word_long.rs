@@ -1,11 +1,8 @@
-use crate::buffer::Buffer;
-
 pub struct WordLong;
 
 impl WordLong {
-    pub(crate) fn as_regex(buffer: &Buffer) -> anyhow::Result<super::Regex> {
+    pub(crate) fn as_regex() -> anyhow::Result<super::Regex> {
         super::Regex::from_config(
-            buffer,
             r"((\w|-)+)|([^a-zA-Z\d\s])",
             crate::list::grep::RegexConfig {
                 escaped: false,
@@ -28,7 +25,7 @@ mod test_word_long {
             None,
             "snake_case camelCase PascalCase UPPER_SNAKE kebab-case ->() 123 <_>",
         );
-        WordLong::as_regex(&buffer).unwrap().assert_all_selections(
+        WordLong::as_regex().unwrap().assert_all_selections(
             &buffer,
             Selection::default(),
             &[
@@ -1,11 +1,8 @@
-use crate::buffer::Buffer;
-
 pub struct WordShort;
 
 impl WordShort {
-    pub(crate) fn as_regex(buffer: &Buffer) -> anyhow::Result<super::Regex> {
+    pub(crate) fn as_regex() -> anyhow::Result<super::Regex> {
         super::Regex::from_config(
-            buffer,
             r"((([a-z]+)|(([A-Z]{2,})+)|([A-Z][a-z]*))_*)|([^\w\s]|_)|[0-9]+",
             crate::list::grep::RegexConfig {
                 escaped: false,
@@ -28,7 +25,7 @@ mod test_word_short {
             None,
             "snake_case camelCase PascalCase UPPER_SNAKE ->() 123 <_>",
         );
-        WordShort::as_regex(&buffer).unwrap().assert_all_selections(
+        WordShort::as_regex().unwrap().assert_all_selections(
             &buffer,
             Selection::default(),
             &[
@@ -0,0 +1,99 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// Runs `command` with `sh`, and returns its output.
+///
+/// If `command` contains the `{}` placeholder, it is substituted with the shell-quoted `input`,
+/// otherwise `input` is written to the stdin of the command.
+///
+/// The trailing newline of the output is removed unless `input` also ends with a newline,
+/// because most commands end their output with a newline.
+pub(crate) fn pipe(command: &str, input: &str) -> anyhow::Result<String> {
+    let output = if command.contains("{}") {
+        execute(&command.replace("{}", &quote(input)), None, &[])?
+    } else {
+        execute(command, Some(input), &[])?
+    };
+    Ok(match output.strip_suffix('\n') {
+        Some(stripped) if !input.ends_with('\n') => stripped.to_string(),
+        _ => output,
+    })
+}
+
+/// Runs `command` with `sh` and the environment variables `envs`, and returns its output
+/// without the trailing newline.
+pub(crate) fn run(command: &str, envs: &[(&str, &str)]) -> anyhow::Result<String> {
+    let output = execute(command, None, envs)?;
+    Ok(output
+        .strip_suffix('\n')
+        .map(|output| output.to_string())
+        .unwrap_or(output))
+}
+
+fn execute(command: &str, stdin: Option<&str>, envs: &[(&str, &str)]) -> anyhow::Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(envs.iter().copied())
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let (Some(mut child_stdin), Some(input)) = (child.stdin.take(), stdin) {
+        let input = input.to_string();
+        // Written in another thread, so that a large output cannot block the command from reading its input.
+        // Write errors are ignored, because the command is allowed to not read its input.
+        std::thread::spawn(move || child_stdin.write_all(input.as_bytes()));
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{:?} failed with {}: {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Quotes `string` with single quotes, so that it is interpreted literally by `sh`.
+fn quote(string: &str) -> String {
+    format!("'{}'", string.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod test_shell {
+    use super::*;
+
+    #[test]
+    fn pipe_input_to_stdin() -> anyhow::Result<()> {
+        assert_eq!(pipe("tr a-z A-Z", "hello")?, "HELLO");
+        assert_eq!(pipe("tr a-z A-Z", "hello\n")?, "HELLO\n");
+        Ok(())
+    }
+
+    #[test]
+    fn substitute_placeholder_with_quoted_input() -> anyhow::Result<()> {
+        assert_eq!(pipe("echo {}-{}", "it's $HOME")?, "it's $HOME-it's $HOME");
+        Ok(())
+    }
+
+    #[test]
+    fn run_with_environment_variables() -> anyhow::Result<()> {
+        assert_eq!(run("echo \"$A $B\"", &[("A", "x"), ("B", "y")])?, "x y");
+        Ok(())
+    }
+
+    #[test]
+    fn failed_command() {
+        let error = pipe("echo oops >&2; exit 3", "").unwrap_err().to_string();
+        assert!(error.contains("oops"), "{error}");
+    }
+}
@@ -1,10 +1,10 @@
 use std::fmt::Display;
 
 use itertools::Itertools;
-use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    grid::{get_char_width, get_string_width},
+    grid::{get_char_widths, get_grapheme_width, get_string_width},
     position::Position,
 };
 
@@ -91,7 +91,10 @@ pub(crate) struct WrappedLine {
     wrapped: Vec<String>,
     /// This can be computed on demand, but it is stored as cache to
     /// greatly improve the performace of `WrappedLines::calibrate`
-    chars_with_line_index: Vec<(usize /* line index (0-based) */, char)>,
+    chars_with_line_index: Vec<(
+        usize, /* line index (0-based) */
+        usize, /* char width */
+    )>,
 }
 impl Display for WrappedLine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -123,18 +126,31 @@ impl WrappedLine {
             .split_first()
             .map(|((line, _), _)| line)
             .or_else(|| Some(&chars_with_line_index.last()?.0))?;
-        let previous_columns_chars = left.iter().filter(|(line_, _)| line == line_).collect_vec();
-
-        let char_width = right
-            .first()
-            .map(|(_, char)| get_char_width(*char))
-            .unwrap_or(1);
-        let previous_columns_chars_total_width: usize = get_string_width(
-            &previous_columns_chars
-                .into_iter()
-                .map(|(_, char)| char)
-                .join(""),
-        );
+        let previous_columns_chars_total_width: usize = left
+            .iter()
+            .filter(|(line_, _)| line == line_)
+            .map(|(_, width)| width)
+            .sum();
+
+        let char_width = right.first().map(|(_, width)| *width).unwrap_or(1);
+        if char_width == 0 {
+            // A non-leading character of a grapheme cluster is placed at the first cell of the cluster,
+            // which starts at the last preceding character with a width
+            let leading_char_width = left
+                .iter()
+                .rev()
+                .filter(|(line_, _)| line == line_)
+                .map(|(_, width)| *width)
+                .find(|width| *width > 0)
+                .unwrap_or(0);
+            return Some(
+                [Position {
+                    line: *line,
+                    column: previous_columns_chars_total_width.saturating_sub(leading_char_width),
+                }]
+                .to_vec(),
+            );
+        }
         Some(
             (0..char_width)
                 .map(|column| {
@@ -155,8 +171,6 @@ impl WrappedLine {
 }
 
 pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
-    let re = Regex::new(r"\b").unwrap();
-
     // Need to reduce the width by 1 for wrapping,
     // that one space is reserved for rendering cursor at the last column
     let wrap_width = width.saturating_sub(1);
@@ -164,8 +178,9 @@ pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
         .lines()
         .enumerate()
         .filter_map(|(line_number, line)| {
-            let wrapped_lines: Vec<String> = re
-                .split(line)
+            // Split by word boundaries, which never break a grapheme cluster
+            let wrapped_lines: Vec<String> = line
+                .split_word_bounds()
                 .flat_map(|chunk| chop_str(chunk, wrap_width))
                 .fold(
                     vec![],
@@ -191,11 +206,10 @@ pub(crate) fn soft_wrap(text: &str, width: usize) -> WrappedLines {
                 line_number,
                 wrapped: wrapped.to_vec(),
                 chars_with_line_index: wrapped_lines
-                    .into_iter()
+                    .iter()
                     .enumerate()
-                    .flat_map(|(line_index, line)| {
-                        line.chars().map(|char| (line_index, char)).collect_vec()
-                    })
+                    .flat_map(|(line_index, line)| line.chars().map(move |_| line_index))
+                    .zip(get_char_widths(line))
                     .collect_vec(),
             })
         })
@@ -221,21 +235,22 @@ fn chop_str(s: &str, max_width: usize) -> Vec<(usize, String)> {
             return vec![(width, s.to_string())];
         }
         let mut result = vec![];
-        let mut current = vec![];
+        let mut current = String::new();
         let mut current_width = 0;
-        for c in s.chars() {
-            let char_width = get_char_width(c);
-            if char_width + current_width <= max_width {
-                current.push(c);
-                current_width += char_width;
+        // Chop by graphemes so that a grapheme cluster is never split into different chunks
+        for grapheme in s.graphemes(true) {
+            let grapheme_width = get_grapheme_width(grapheme);
+            if grapheme_width + current_width <= max_width {
+                current.push_str(grapheme);
+                current_width += grapheme_width;
             } else {
-                result.push((current_width, current.drain(..).join("")));
-                current_width = char_width;
-                current = vec![c];
+                result.push((current_width, std::mem::take(&mut current)));
+                current_width = grapheme_width;
+                current = grapheme.to_string();
             }
         }
         if !current.is_empty() {
-            result.push((current_width, current.drain(..).join("")));
+            result.push((current_width, current));
         }
 
         result
@@ -283,6 +298,31 @@ mod test_soft_wrap {
         )
     }
 
+    #[test]
+    fn grapheme_clusters_should_not_be_split() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        assert_eq!(
+            chop_str(&format!("a{family}"), 2),
+            vec![(1, "a".to_string()), (2, family.to_string())]
+        );
+
+        let content = format!("a{family}b");
+        let wrapped_lines = soft_wrap(&content, 10);
+        assert_eq!(
+            wrapped_lines.calibrate(Position::new(0, 1)),
+            Ok(vec![Position::new(0, 1), Position::new(0, 2)])
+        );
+        // The characters after the first character of the cluster are placed at the cell of the cluster
+        assert_eq!(
+            wrapped_lines.calibrate(Position::new(0, 3)),
+            Ok(vec![Position::new(0, 1)])
+        );
+        assert_eq!(
+            wrapped_lines.calibrate(Position::new(0, 6)),
+            Ok(vec![Position::new(0, 3)])
+        );
+    }
+
     #[test]
     fn consider_unicode_width_1() {
         let content = "→ abc";
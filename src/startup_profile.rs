@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use itertools::Itertools;
+
+/// The time spent in each phase of startup, see the `--profile-startup` flag.
+#[derive(Debug, Clone)]
+pub(crate) struct StartupProfile {
+    started_at: Instant,
+    last_recorded_at: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl StartupProfile {
+    pub(crate) fn new() -> StartupProfile {
+        let now = Instant::now();
+        StartupProfile {
+            started_at: now,
+            last_recorded_at: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// Records the time elapsed since the previous phase ended as the duration of `phase`.
+    pub(crate) fn record(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        self.phases
+            .push((phase, now.saturating_duration_since(self.last_recorded_at)));
+        self.last_recorded_at = now;
+    }
+
+    pub(crate) fn display(&self) -> String {
+        let total = self
+            .last_recorded_at
+            .saturating_duration_since(self.started_at);
+        let width = self
+            .phases
+            .iter()
+            .map(|(phase, _)| phase.len())
+            .chain(["total".len()])
+            .max()
+            .unwrap_or_default();
+        self.phases
+            .iter()
+            .map(|(phase, duration)| (*phase, *duration))
+            .chain([("total", total)])
+            .map(|(phase, duration)| {
+                format!(
+                    "{:<width$}  {:>8.2}ms",
+                    phase,
+                    duration.as_secs_f64() * 1000.0
+                )
+            })
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod test_startup_profile {
+    use std::time::Duration;
+
+    use super::StartupProfile;
+
+    #[test]
+    fn display() {
+        let mut profile = StartupProfile::new();
+        profile.phases = [
+            ("config", Duration::from_micros(1500)),
+            ("first render", Duration::from_millis(20)),
+        ]
+        .to_vec();
+        profile.last_recorded_at = profile.started_at + Duration::from_micros(21500);
+        assert_eq!(
+            profile.display(),
+            [
+                "config            1.50ms",
+                "first render     20.00ms",
+                "total            21.50ms",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn record() {
+        let mut profile = StartupProfile::new();
+        profile.record("a");
+        profile.record("b");
+        assert_eq!(
+            profile
+                .phases
+                .iter()
+                .map(|(phase, _)| *phase)
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+}
@@ -9,6 +9,7 @@ pub(crate) struct Style {
     pub(crate) background_color: Option<Color>,
     pub(crate) line: Option<CellLine>,
     pub(crate) is_bold: bool,
+    pub(crate) is_italic: bool,
 }
 
 pub const fn fg(color: Color) -> Style {
@@ -22,6 +23,7 @@ impl Style {
             background_color: None,
             line: None,
             is_bold: false,
+            is_italic: false,
         }
     }
 
@@ -77,4 +79,11 @@ impl Style {
             ..self
         }
     }
+
+    pub(crate) const fn italic(self) -> Style {
+        Style {
+            is_italic: true,
+            ..self
+        }
+    }
 }
@@ -69,6 +69,29 @@ pub(crate) fn get_surrounding_indices(
     Some((open_index, close_index))
 }
 
+/// Return the open index and close index of the innermost bracket pair
+/// that encloses `cursor_char_index`, or whose open or close symbol is under it.
+///
+/// Quotes and backticks are not considered, because their open and close symbols are the same,
+/// so there is no telling which way the matching symbol is.
+pub(crate) fn get_innermost_bracket_indices(
+    content: &str,
+    cursor_char_index: CharIndex,
+) -> Option<(CharIndex, CharIndex)> {
+    if !(0..content.chars().count()).contains(&cursor_char_index.0) {
+        return None;
+    }
+    [
+        EnclosureKind::Parentheses,
+        EnclosureKind::CurlyBraces,
+        EnclosureKind::SquareBrackets,
+        EnclosureKind::AngularBrackets,
+    ]
+    .into_iter()
+    .filter_map(|kind| get_surrounding_indices(content, kind, cursor_char_index))
+    .max_by_key(|(open, _)| *open)
+}
+
 impl EnclosureKind {
     pub(crate) const fn open_close_symbols(&self) -> (char, char) {
         match self {
@@ -133,6 +156,23 @@ mod test_surround {
         run_test("(a (b) c)", Parentheses, 7, Some((0, 8)));
     }
 
+    #[test]
+    fn test_get_innermost_bracket_indices() {
+        let run_test =
+            |content: &str, cursor_char_index: usize, expected: Option<(usize, usize)>| {
+                assert_eq!(
+                    get_innermost_bracket_indices(content, CharIndex(cursor_char_index)),
+                    expected.map(|(open, close)| (CharIndex(open), CharIndex(close)))
+                )
+            };
+        run_test("{ f(a[0]) }", 4, Some((3, 8)));
+        run_test("{ f(a[0]) }", 6, Some((5, 7)));
+        run_test("{ f(a[0]) }", 8, Some((3, 8)));
+        run_test("{ f(a[0]) }", 1, Some((0, 10)));
+        run_test("hello", 1, None);
+        run_test("", 0, None);
+    }
+
     #[test]
     /// Cursor is on the open symbol
     fn test_get_surrounding_indices_2() {
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, ops::Range, sync::mpsc::Sender, time::Duration};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::Duration,
+};
 
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
@@ -107,39 +112,84 @@ pub(crate) struct SyntaxHighlightRequest {
     pub(crate) source_code: String,
 }
 
+/// Sources smaller than this are highlighted without debouncing, because highlighting them is fast,
+/// and debouncing them causes the edited text to flash unstyled.
+const DEBOUNCE_THRESHOLD_BYTES: usize = 64 * 1024;
+
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(150);
+
 pub(crate) fn start_thread(callback: Sender<AppMessage>) -> Sender<SyntaxHighlightRequest> {
     let (sender, receiver) = std::sync::mpsc::channel::<SyntaxHighlightRequest>();
     use debounce::EventDebouncer;
-    struct Event(SyntaxHighlightRequest);
+    struct Event {
+        /// Used for discarding a debounced request which is outdated by a later immediate request.
+        id: usize,
+        request: SyntaxHighlightRequest,
+    }
     impl PartialEq for Event {
         fn eq(&self, other: &Self) -> bool {
-            self.0.component_id == other.0.component_id
+            self.request.component_id == other.request.component_id
         }
     }
 
     std::thread::spawn(move || {
-        let mut highlight_configs = HighlightConfigs::new();
-        let debounce = EventDebouncer::new(Duration::from_millis(150), move |Event(request)| {
-            match highlight_configs.highlight(request.language, &request.source_code) {
-                Ok(highlighted_spans) => {
-                    let _ = callback.send(AppMessage::SyntaxHighlightResponse {
-                        component_id: request.component_id,
-                        highlighted_spans,
-                    });
+        let highlight_configs = Arc::new(Mutex::new(HighlightConfigs::new()));
+        // The ID of the latest request of each component that is highlighted without debouncing
+        let latest_immediate_request_ids =
+            Arc::new(Mutex::new(HashMap::<ComponentId, usize>::new()));
+        let debounce = {
+            let highlight_configs = highlight_configs.clone();
+            let latest_immediate_request_ids = latest_immediate_request_ids.clone();
+            let callback = callback.clone();
+            EventDebouncer::new(DEBOUNCE_DURATION, move |Event { id, request }| {
+                let is_outdated = latest_immediate_request_ids
+                    .lock()
+                    .map(|ids| {
+                        ids.get(&request.component_id)
+                            .is_some_and(|immediate_id| immediate_id > &id)
+                    })
+                    .unwrap_or(false);
+                if !is_outdated {
+                    highlight(&highlight_configs, &callback, request)
                 }
-                Err(error) => {
-                    log::info!("syntax_highlight_error = {:#?}", error)
+            })
+        };
+
+        for (id, request) in receiver.iter().enumerate() {
+            if request.source_code.len() < DEBOUNCE_THRESHOLD_BYTES {
+                if let Ok(mut ids) = latest_immediate_request_ids.lock() {
+                    ids.insert(request.component_id, id);
                 }
+                highlight(&highlight_configs, &callback, request)
+            } else {
+                debounce.put(Event { id, request })
             }
-        });
-
-        while let Ok(request) = receiver.recv() {
-            debounce.put(Event(request))
         }
     });
 
     sender
 }
+
+fn highlight(
+    highlight_configs: &Mutex<HighlightConfigs>,
+    callback: &Sender<AppMessage>,
+    request: SyntaxHighlightRequest,
+) {
+    let Ok(mut highlight_configs) = highlight_configs.lock() else {
+        return;
+    };
+    match highlight_configs.highlight(request.language, &request.source_code) {
+        Ok(highlighted_spans) => {
+            let _ = callback.send(AppMessage::SyntaxHighlightResponse {
+                component_id: request.component_id,
+                highlighted_spans,
+            });
+        }
+        Err(error) => {
+            log::info!("syntax_highlight_error = {:#?}", error)
+        }
+    }
+}
 type TreeSitterGrammarId = String;
 /// We have to cache the highlight configurations because they load slowly.
 pub(crate) struct HighlightConfigs(
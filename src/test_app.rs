@@ -11,8 +11,11 @@ use serial_test::serial;
 
 use std::{
     ops::Range,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 pub(crate) use Dispatch::*;
 pub(crate) use DispatchEditor::*;
@@ -23,7 +26,10 @@ pub(crate) use SelectionMode::*;
 use shared::canonicalized_path::CanonicalizedPath;
 
 use crate::{
-    app::{App, Dimension, Dispatch, LocalSearchConfigUpdate, RequestParams, Scope},
+    app::{
+        App, AppMessage, Dimension, Dispatch, GlobalSearchConfigUpdate, LocalSearchConfigUpdate,
+        RequestParams, Scope,
+    },
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
     components::{
@@ -46,7 +52,7 @@ use crate::{
     },
     position::Position,
     quickfix_list::{DiagnosticSeverityRange, Location, QuickfixListItem},
-    selection::SelectionMode,
+    selection::{CharIndex, SelectionMode},
     style::Style,
     themes::Theme,
     ui_tree::ComponentKind,
@@ -104,13 +110,18 @@ pub(crate) enum ExpectKind {
     BufferQuickfixListItems(Vec<Range<Position>>),
     ComponentCount(usize),
     CurrentComponentPath(Option<CanonicalizedPath>),
+    TerminalTitle(String),
     OpenedFilesCount(usize),
     QuickfixListInfo(&'static str),
+    QuickfixListPreview(Option<&'static str>),
     ComponentsOrder(Vec<ComponentKind>),
     CurrentComponentTitle(&'static str),
     CurrentSelectionMode(SelectionMode),
     LspRequestSent(FromEditor),
     CurrentCopiedTextHistoryOffset(isize),
+    CurrentScrollOffset(u16),
+    CurrentComponentWidth(u16),
+    ComponentReadOnly(/*Title*/ &'static str, bool),
 }
 fn log<T: std::fmt::Debug>(s: T) {
     println!("===========\n{s:?}",);
@@ -325,6 +336,7 @@ impl ExpectKind {
                     .collect_vec(),
             ),
             ComponentCount(expected) => contextualize(expected, &app.components().len()),
+            TerminalTitle(expected) => contextualize(expected, &app.terminal_title()),
             CurrentComponentPath(expected) => {
                 contextualize(expected, &app.current_component().borrow().path())
             }
@@ -332,6 +344,11 @@ impl ExpectKind {
             QuickfixListInfo(expected) => {
                 contextualize(*expected, &app.quickfix_list_info().unwrap())
             }
+            QuickfixListPreview(expected) => contextualize(
+                expected.map(|expected| expected.to_string()),
+                app.get_component_by_kind(ComponentKind::QuickfixListPreview)
+                    .map(|component| component.borrow().content()),
+            ),
             ComponentsOrder(expected) => contextualize(expected, &app.components_order()),
             CurrentComponentTitle(expected) => {
                 contextualize(*expected, &app.current_component().borrow().title(context))
@@ -348,6 +365,22 @@ impl ExpectKind {
                     .editor()
                     .copied_text_history_offset(),
             ),
+            CurrentComponentWidth(expected) => contextualize(
+                *expected,
+                app.current_component().borrow().rectangle().width,
+            ),
+            CurrentScrollOffset(expected) => contextualize(
+                expected,
+                &app.current_component().borrow().editor().scroll_offset(),
+            ),
+            ComponentReadOnly(title, expected) => contextualize(
+                Some(*expected),
+                app.components()
+                    .into_iter()
+                    .map(|component| component.component())
+                    .find(|component| component.borrow().title(context) == *title)
+                    .map(|component| component.borrow().editor().buffer().read_only()),
+            ),
         })
     }
 }
@@ -434,6 +467,79 @@ fn run_test(
     })
 }
 
+#[test]
+fn unchanged_screen_is_not_rendered_again() -> anyhow::Result<()> {
+    TestRunner::run(|temp_dir| {
+        let frontend = Arc::new(Mutex::new(MockFrontend::default()));
+        let mut app = App::new(frontend.clone(), temp_dir.clone())?;
+        app.disable_lsp();
+        let shown_cursor_count = || frontend.lock().unwrap().shown_cursor_count();
+        app.handle_dispatch(OpenFile(temp_dir.join("src/main.rs")?))?;
+        app.render()?;
+        assert_eq!(shown_cursor_count(), 1);
+        app.render()?;
+        assert_eq!(shown_cursor_count(), 1);
+        app.handle_dispatch_editor(EnterInsertMode(Direction::Start))?;
+        app.handle_dispatch_editor(Insert("x".to_owned()))?;
+        app.render()?;
+        assert_eq!(shown_cursor_count(), 2);
+        Ok(())
+    })
+}
+
+#[test]
+fn rendering_is_not_held_back_by_a_steady_stream_of_messages() -> anyhow::Result<()> {
+    TestRunner::run(|temp_dir| {
+        let frontend = Arc::new(Mutex::new(MockFrontend::default()));
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut app =
+            App::from_channel(frontend.clone(), temp_dir.clone(), sender.clone(), receiver)?;
+        app.disable_lsp();
+        app.handle_dispatch(OpenFile(temp_dir.join("src/main.rs")?))?;
+        app.handle_dispatch_editor(EnterInsertMode(Direction::Start))?;
+        app.render()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let keep_sending = std::thread::spawn({
+            let stop = stop.clone();
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    if sender
+                        .send(AppMessage::Event(event::event::Event::Key(key!("x"))))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        });
+        let should_quit =
+            app.handle_message_and_render(AppMessage::Event(event::event::Event::Key(key!("x"))));
+        stop.store(true, Ordering::Relaxed);
+        keep_sending.join().unwrap();
+        assert!(!should_quit?);
+        // Expect the inserted characters to be rendered before the messages stop arriving
+        assert_eq!(frontend.lock().unwrap().shown_cursor_count(), 2);
+        Ok(())
+    })
+}
+
+#[test]
+fn paste_event_is_inserted_as_one_edit() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("x".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            // Expect carriage returns to be converted into newlines
+            App(HandlePaste("a\r\nb\rc".to_string())),
+            Expect(CurrentComponentContent("a\nb\ncx")),
+            // Expect one undo to revert the whole paste
+            Editor(Undo),
+            Expect(CurrentComponentContent("x")),
+        ])
+    })
+}
+
 #[test]
 fn copy_replace_from_different_file() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -745,6 +851,28 @@ fn signature_help() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn revert_git_hunk_is_blocked_in_read_only_buffers() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let original_content = s.foo_rs().read().unwrap();
+        let edited_content = format!("// Hello{original_content}");
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(Insert("// Hello".to_string())),
+            App(SaveAll),
+            Editor(SetSelectionMode(GitHunk(
+                crate::git::DiffMode::UnstagedAgainstCurrentBranch,
+            ))),
+            Editor(SetReadOnly(true)),
+            Editor(RevertGitHunk),
+            Expect(FileContent(s.foo_rs(), edited_content)),
+            Editor(SetReadOnly(false)),
+            Editor(RevertGitHunk),
+            Expect(FileContent(s.foo_rs(), original_content)),
+        ])
+    })
+}
+
 #[test]
 pub(crate) fn repo_git_hunks() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -779,7 +907,7 @@ pub(crate) fn repo_git_hunks() -> Result<(), anyhow::Error> {
                             path: path_new_file.clone().try_into().unwrap(),
                             range: Position { line: 0, column: 0 }..Position { line: 0, column: 0 },
                         },
-                        strs_to_strings(&["[This file is untracked or renamed]"]),
+                        strs_to_strings(&["[This file is untracked and empty]"]),
                     ),
                     QuickfixListItem::new(
                         Location {
@@ -971,6 +1099,8 @@ fn local_lsp_references() -> anyhow::Result<()> {
                 crate::lsp::process::ResponseContext {
                     scope: Some(Scope::Local),
                     description: None,
+                    peek: false,
+                    moved_path: None,
                 },
                 [
                     Location {
@@ -1232,6 +1362,254 @@ foo a // Line 10
     })
 }
 
+#[test]
+fn save_as_and_rename_file() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let main_rs = s.main_rs();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {}".to_string())),
+            App(SaveAs(s.new_path("src/copy.rs"))),
+            Expect(CurrentComponentPath(Some(
+                s.new_path("src/copy.rs").try_into().unwrap(),
+            ))),
+            // The original file is untouched by save-as
+            ExpectCustom(Box::new(move || {
+                assert_ne!(main_rs.read().unwrap(), "fn main() {}");
+            })),
+            // The content is formatted already, so that it stays the same when saved
+            Editor(SetContent("fn copy() {}\n".to_string())),
+            App(RenameFile(s.new_path("src/renamed.rs"))),
+            Expect(CurrentComponentPath(Some(
+                s.new_path("src/renamed.rs").try_into().unwrap(),
+            ))),
+            Expect(CurrentComponentContent("fn copy() {}\n")),
+            ExpectCustom(Box::new(move || {
+                let path = |path: &str| temp_dir.to_path_buf().join(path);
+                assert!(!path("src/copy.rs").exists());
+                assert_eq!(
+                    std::fs::read_to_string(path("src/renamed.rs")).unwrap(),
+                    "fn copy() {}\n"
+                );
+            })),
+            // Renaming a file tracked by git stages the move
+            App(OpenFile(s.foo_rs())),
+            App(RenameFile(s.new_path("src/bar.rs"))),
+            ExpectCustom(Box::new({
+                let temp_dir = s.temp_dir();
+                move || {
+                    let index = git2::Repository::open(&temp_dir).unwrap().index().unwrap();
+                    assert!(index.get_path(Path::new("src/foo.rs"), 0).is_none());
+                    assert!(index.get_path(Path::new("src/bar.rs"), 0).is_some());
+                }
+            })),
+        ])
+    })
+}
+
+#[test]
+fn scratch_buffers() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        Box::new([
+            App(NewScratchBuffer),
+            Expect(CurrentComponentTitle("[Scratch 1]")),
+            Editor(SetContent("first".to_string())),
+            App(NewScratchBuffer),
+            Expect(CurrentComponentTitle("[Scratch 2]")),
+            Expect(CurrentComponentContent("")),
+            App(SwitchScratchBuffer(0)),
+            Expect(CurrentComponentContent("first")),
+            App(SaveAs(s.new_path("notes/first.txt"))),
+            Expect(CurrentComponentPath(Some(
+                s.new_path("notes/first.txt").try_into().unwrap(),
+            ))),
+            ExpectCustom(Box::new(move || {
+                assert_eq!(
+                    std::fs::read_to_string(temp_dir.to_path_buf().join("notes/first.txt"))
+                        .unwrap(),
+                    "first"
+                );
+            })),
+            // The saved buffer is no longer a scratch buffer
+            App(SwitchScratchBuffer(0)),
+            Expect(CurrentComponentTitle("[Scratch 2]")),
+        ])
+    })
+}
+
+#[test]
+fn global_search_scope() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let cargo_toml: CanonicalizedPath = s.new_path("Cargo.toml").try_into().unwrap();
+        let update = |update: GlobalSearchConfigUpdate| UpdateGlobalSearchConfig { update };
+        let main_rs_needle = || {
+            Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.main_rs(),
+                    range: Position::new(0, 0)..Position::new(0, 6),
+                },
+                None,
+            )]))
+        };
+        Box::new([
+            App(OpenFile(cargo_toml)),
+            Editor(SetContent("needle".to_string())),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("needle".to_string())),
+            App(SaveAll),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("needle".to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }),
+            App(update(GlobalSearchConfigUpdate::SetFileExtension(Some(
+                "rs".to_string(),
+            )))),
+            Expect(main_rs_needle()),
+            App(update(GlobalSearchConfigUpdate::SetFileExtension(None))),
+            App(update(GlobalSearchConfigUpdate::SetDirectory(Some(
+                "src".to_string(),
+            )))),
+            Expect(main_rs_needle()),
+        ])
+    })
+}
+
+#[test]
+fn quickfix_lists_switching_filtering_and_removing() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let search = |search: &str| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search(search.to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo d\nfoo c\nbar e".to_string())),
+            App(SaveAll),
+            App(search("foo")),
+            Expect(CurrentLine("foo d")),
+            App(search("bar")),
+            Expect(CurrentLine("bar e")),
+            // Go back to the previous list
+            App(SwitchQuickfixList(Movement::Previous)),
+            Expect(CurrentLine("foo d")),
+            App(SwitchQuickfixList(Movement::Next)),
+            Expect(CurrentLine("bar e")),
+            App(SwitchQuickfixList(Movement::First)),
+            Expect(CurrentLine("foo d")),
+            // Filter
+            App(SetQuickfixListFilter(Some("foo c$".to_string()))),
+            Expect(CurrentLine("foo c")),
+            App(SetQuickfixListFilter(None)),
+            Expect(CurrentLine("foo d")),
+            // Remove
+            App(RemoveCurrentQuickfixListItem),
+            Expect(CurrentLine("foo c")),
+            Expect(QuickfixListContent(format!(
+                "■┬ {}\n └─ 2:1  foo c",
+                s.main_rs().display_absolute()
+            ))),
+        ])
+    })
+}
+
+#[test]
+fn removed_quickfix_list_items_stay_removed_after_edits() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let search = |search: &str| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search(search.to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }
+        };
+        let content = |first_line: usize| {
+            QuickfixListContent(format!(
+                "■┬ {}\n ├─ {}:1  foo a\n └─ {}:1  foo c",
+                s.main_rs().display_absolute(),
+                first_line,
+                first_line + 2
+            ))
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo a\nfoo b\nfoo c".to_string())),
+            App(SaveAll),
+            App(search("foo")),
+            App(GotoQuickfixListItem(Movement::Next)),
+            Expect(CurrentLine("foo b")),
+            App(RemoveCurrentQuickfixListItem),
+            Expect(content(1)),
+            // Insert a line above the removed item, which moves it to the next line
+            App(GotoQuickfixListItem(Movement::First)),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("\n".to_string())),
+            Editor(EnterNormalMode),
+            App(GotoQuickfixListItem(Movement::Current)),
+            Expect(content(2)),
+            // The removed item is restored along with its list
+            App(search("foo c")),
+            App(SwitchQuickfixList(Movement::Previous)),
+            Expect(content(2)),
+        ])
+    })
+}
+
+#[test]
+fn quickfix_list_preview() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let item = |line: usize| {
+            QuickfixListItem::new(
+                Location {
+                    path: s.foo_rs(),
+                    range: Position { line, column: 0 }..Position { line, column: 1 },
+                },
+                None,
+            )
+        };
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent(
+                (0..20).map(|index| format!("line {index}")).join("\n"),
+            )),
+            App(OpenFile(s.main_rs())),
+            App(ToggleQuickfixListPreview),
+            App(SetQuickfixList(
+                crate::quickfix_list::QuickfixListType::Items([item(1), item(10), item(30)].to_vec()),
+            )),
+            // The file of the item should not be opened
+            Expect(CurrentPath(s.main_rs())),
+            Expect(QuickfixListPreview(Some(
+                "line 0\nline 1\nline 2\nline 3\nline 4\nline 5\nline 6",
+            ))),
+            App(GotoQuickfixListItem(Next)),
+            Expect(CurrentPath(s.main_rs())),
+            Expect(QuickfixListPreview(Some(
+                "line 5\nline 6\nline 7\nline 8\nline 9\nline 10\nline 11\nline 12\nline 13\nline 14\nline 15",
+            ))),
+            // Expect the preview to show the line numbers of the file
+            Expect(AppGridContains("11│line 10")),
+            // Expect an item past the end of the file to preview the end of the file
+            App(GotoQuickfixListItem(Next)),
+            Expect(QuickfixListPreview(Some(
+                "line 14\nline 15\nline 16\nline 17\nline 18\nline 19",
+            ))),
+            Expect(AppGridContains("20│line 19")),
+            App(GotoQuickfixListItem(Previous)),
+            // Turning off the preview opens the current item
+            App(ToggleQuickfixListPreview),
+            Expect(QuickfixListPreview(None)),
+            Expect(CurrentPath(s.foo_rs())),
+            Expect(CurrentLine("line 10")),
+        ])
+    })
+}
+
 #[test]
 fn quickfix_list_show_info_if_possible() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1431,6 +1809,8 @@ fn code_action() -> anyhow::Result<()> {
                 resource_operations: Vec::new(),
             }),
             command: None,
+            is_preferred: false,
+            unresolved: None,
         };
         Box::new([
             App(OpenFile(s.main_rs())),
@@ -1444,6 +1824,99 @@ fn code_action() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn apply_preferred_code_action() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let code_action = |new_text: &str, is_preferred: bool| CodeAction {
+            title: format!("Use {}", new_text),
+            kind: Some("quickfix".to_string()),
+            edit: Some(WorkspaceEdit {
+                edits: [TextDocumentEdit {
+                    path: s.main_rs(),
+                    edits: [PositionalEdit {
+                        range: Position::new(0, 2)..Position::new(0, 6),
+                        new_text: new_text.to_string(),
+                    }]
+                    .to_vec(),
+                }]
+                .to_vec(),
+                resource_operations: Vec::new(),
+            }),
+            command: None,
+            is_preferred,
+            unresolved: None,
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a.to_s".to_string())),
+            App(ApplyPreferredCodeAction {
+                diagnostics: Vec::new(),
+            }),
+            App(ReceiveCodeActions(
+                [
+                    code_action("to_soup", false),
+                    code_action("to_string", true),
+                ]
+                .to_vec(),
+            )),
+            // The preferred action is applied without opening the menu
+            Expect(CurrentComponentContent("a.to_string")),
+            Expect(ComponentCount(1)),
+        ])
+    })
+}
+
+#[test]
+fn unresolved_code_action_is_resolved_before_applied() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let unresolved = lsp_types::CodeAction {
+            title: "Use to_string".to_string(),
+            data: Some(serde_json::json!({ "id": 1 })),
+            ..Default::default()
+        };
+        let resolved = CodeAction {
+            title: "Use to_string".to_string(),
+            kind: None,
+            edit: Some(WorkspaceEdit {
+                edits: [TextDocumentEdit {
+                    path: s.main_rs(),
+                    edits: [PositionalEdit {
+                        range: Position::new(0, 2)..Position::new(0, 6),
+                        new_text: "to_string".to_string(),
+                    }]
+                    .to_vec(),
+                }]
+                .to_vec(),
+                resource_operations: Vec::new(),
+            }),
+            command: None,
+            is_preferred: false,
+            unresolved: None,
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a.to_s".to_string())),
+            App(ReceiveCodeActions(
+                [unresolved.clone().try_into().unwrap()].to_vec(),
+            )),
+            App(HandleKeyEvent(key!("enter"))),
+            Expect(CurrentComponentContent("a.to_s")),
+            Expect(LspRequestSent(FromEditor::CodeActionResolve {
+                code_action: unresolved.clone(),
+                params: RequestParams {
+                    path: s.main_rs(),
+                    position: Position::new(0, 0),
+                    context: Default::default(),
+                },
+            })),
+            App(HandleLspNotification(LspNotification::CodeActionResolve(
+                resolved.clone(),
+            ))),
+            Expect(CurrentComponentContent("a.to_string")),
+        ])
+    })
+}
+
 #[test]
 fn opening_new_file_should_replace_current_window() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1778,6 +2251,7 @@ fn open_search_prompt_in_file_explorer() -> anyhow::Result<()> {
             Expect(CurrentComponentTitle("File Explorer")),
             App(OpenSearchPrompt {
                 scope: Scope::Local,
+                current_line: None,
             }),
             Expect(Not(Box::new(CurrentComponentTitle("File Explorer")))),
             App(HandleKeyEvents(keys!("m a i n enter").to_vec())),
@@ -1786,6 +2260,40 @@ fn open_search_prompt_in_file_explorer() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn toggle_smart_case_in_search_prompt() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo Foo FOO".to_string())),
+            App(OpenSearchPrompt {
+                scope: Scope::Local,
+                current_line: None,
+            }),
+            App(HandleKeyEvent(key!("alt+s"))),
+            Expect(AppGridContains("Local search (Literal(Smart-case))")),
+            // The search is case-sensitive because it contains uppercase characters
+            App(HandleKeyEvents(keys!("F o o enter").to_vec())),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["Foo"])),
+            Editor(CursorKeepPrimaryOnly),
+            App(OpenSearchPrompt {
+                scope: Scope::Local,
+                current_line: None,
+            }),
+            App(HandleKeyEvents(keys!("f o o enter").to_vec())),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["foo", "Foo", "FOO"])),
+            // Smart case is only remembered for the toggled scope
+            App(OpenSearchPrompt {
+                scope: Scope::Global,
+                current_line: None,
+            }),
+            Expect(AppGridContains("Global search (Literal)")),
+        ])
+    })
+}
+
 #[test]
 fn global_search_should_not_using_empty_pattern() -> anyhow::Result<()> {
     execute_test(|_| {
@@ -1828,27 +2336,294 @@ fn workspace_edit() -> anyhow::Result<()> {
 }
 
 #[test]
-fn request_signature_help() -> anyhow::Result<()> {
+fn multi_file_workspace_edit_is_previewed_before_applied() -> anyhow::Result<()> {
     execute_test(|s| {
+        let edit = |path: CanonicalizedPath| TextDocumentEdit {
+            path,
+            edits: [PositionalEdit {
+                range: Position::new(0, 0)..Position::new(0, 0),
+                new_text: "hello ".to_string(),
+            }]
+            .to_vec(),
+        };
         Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("foo".to_string())),
             App(OpenFile(s.main_rs())),
-            Editor(SetContent("f()".to_string())),
-            Editor(MatchLiteral("f()".to_string())),
-            Editor(EnterInsertMode(Direction::End)),
-            Expect(ExpectKind::LspRequestSent(
-                FromEditor::TextDocumentSignatureHelp(RequestParams {
-                    path: s.main_rs(),
-                    position: Position::new(0, 3),
-                    context: Default::default(),
-                }),
-            )),
-            App(HandleKeyEvent(key!("left"))),
-            Expect(ExpectKind::LspRequestSent(
-                FromEditor::TextDocumentSignatureHelp(RequestParams {
-                    path: s.main_rs(),
-                    position: Position::new(0, 2),
-                    context: Default::default(),
-                }),
+            Editor(SetContent("main".to_string())),
+            App(Dispatch::ApplyWorkspaceEdit(WorkspaceEdit {
+                edits: [edit(s.main_rs()), edit(s.foo_rs())].to_vec(),
+                resource_operations: Vec::new(),
+            })),
+            Expect(CurrentComponentTitle("Workspace edit")),
+            Expect(ComponentReadOnly("Workspace edit preview", true)),
+            // Reject the edits of foo.rs
+            App(HandleKeyEvents(keys!("f f o o enter").to_vec())),
+            Expect(CurrentComponentTitle("Workspace edit")),
+            App(HandleKeyEvents(keys!("a").to_vec())),
+            Expect(CurrentComponentContent("hello main")),
+            App(OpenFile(s.foo_rs())),
+            Expect(CurrentComponentContent("foo")),
+        ])
+    })
+}
+
+#[test]
+fn workspace_edit_preview_keys_work_after_dismissing_legend() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let edit = |path: CanonicalizedPath| TextDocumentEdit {
+            path,
+            edits: [PositionalEdit {
+                range: Position::new(0, 0)..Position::new(0, 0),
+                new_text: "hello ".to_string(),
+            }]
+            .to_vec(),
+        };
+        let workspace_edit = WorkspaceEdit {
+            edits: [edit(s.main_rs()), edit(s.foo_rs())].to_vec(),
+            resource_operations: Vec::new(),
+        };
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("foo".to_string())),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("main".to_string())),
+            App(Dispatch::ApplyWorkspaceEdit(workspace_edit.clone())),
+            App(HandleKeyEvent(key!("esc"))),
+            Expect(CurrentComponentTitle("Workspace edit preview")),
+            App(HandleKeyEvents(keys!("a").to_vec())),
+            Expect(CurrentComponentContent("hello main")),
+            App(OpenFile(s.foo_rs())),
+            Expect(CurrentComponentContent("hello foo")),
+            // Discarding works the same way
+            App(Dispatch::ApplyWorkspaceEdit(workspace_edit)),
+            App(HandleKeyEvent(key!("esc"))),
+            App(HandleKeyEvents(keys!("x").to_vec())),
+            Expect(CurrentComponentContent("hello foo")),
+            Expect(CurrentComponentPath(Some(s.foo_rs()))),
+        ])
+    })
+}
+
+#[test]
+fn rename_edits_all_occurrences_before_committing_via_lsp() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let foo = 1; foo + food".to_string())),
+            Editor(MatchLiteral("foo".to_string())),
+            App(HandleLspNotification(
+                LspNotification::PrepareRenameResponse(
+                    crate::lsp::prepare_rename_response::PrepareRenameResponse {
+                        range: Some(Position::new(0, 4)..Position::new(0, 7)),
+                        placeholder: None,
+                    },
+                ),
+            )),
+            Expect(LspRequestSent(FromEditor::TextDocumentLinkedEditingRange(
+                RequestParams {
+                    path: s.main_rs(),
+                    position: Position::new(0, 4),
+                    context: Default::default(),
+                },
+            ))),
+            App(HandleLspNotification(LspNotification::LinkedEditingRanges(
+                [
+                    Position::new(0, 4)..Position::new(0, 7),
+                    Position::new(0, 13)..Position::new(0, 16),
+                ]
+                .to_vec(),
+            ))),
+            App(HandleKeyEvents(keys!("b a r").to_vec())),
+            Expect(CurrentComponentContent("let foobar = 1; foobar + food")),
+            App(HandleKeyEvent(key!("enter"))),
+            // The original content is restored, waiting for the LSP rename edits
+            Expect(CurrentComponentContent("let foo = 1; foo + food")),
+            Expect(LspRequestSent(FromEditor::TextDocumentRename {
+                params: RequestParams {
+                    path: s.main_rs(),
+                    position: Position::new(0, 4),
+                    context: Default::default(),
+                },
+                new_name: "foobar".to_string(),
+            })),
+        ])
+    })
+}
+
+#[test]
+fn cancelling_rename_undoes_the_linked_edits() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let foo = 1; foo + food".to_string())),
+            Editor(MatchLiteral("1".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x".to_owned())),
+            Editor(EnterNormalMode),
+            Expect(CurrentComponentContent("let foo = x1; foo + food")),
+            Editor(MatchLiteral("foo".to_string())),
+            App(HandleLspNotification(
+                LspNotification::PrepareRenameResponse(
+                    crate::lsp::prepare_rename_response::PrepareRenameResponse {
+                        range: Some(Position::new(0, 4)..Position::new(0, 7)),
+                        placeholder: None,
+                    },
+                ),
+            )),
+            App(HandleLspNotification(LspNotification::LinkedEditingRanges(
+                [
+                    Position::new(0, 4)..Position::new(0, 7),
+                    Position::new(0, 14)..Position::new(0, 17),
+                ]
+                .to_vec(),
+            ))),
+            App(HandleKeyEvents(keys!("b a r").to_vec())),
+            Expect(CurrentComponentContent("let foobar = x1; foobar + food")),
+            App(HandleKeyEvent(key!("esc"))),
+            Expect(CurrentComponentContent("let foo = x1; foo + food")),
+            // Expect the next undo to undo the edit before the rename
+            Editor(Undo),
+            Expect(CurrentComponentContent("let foo = 1; foo + food")),
+        ])
+    })
+}
+
+#[test]
+fn rename_edits_only_the_occurrences_highlighted_by_lsp() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let params = RequestParams {
+            path: s.main_rs(),
+            position: Position::new(1, 8),
+            context: Default::default(),
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "
+fn main() {
+    let count = 1;
+    // count is one
+    let f = |count: i32| count;
+    f(count)
+}"
+                .trim()
+                .to_string(),
+            )),
+            Editor(MatchLiteral("count".to_string())),
+            App(HandleLspNotification(
+                LspNotification::PrepareRenameResponse(
+                    crate::lsp::prepare_rename_response::PrepareRenameResponse {
+                        range: Some(Position::new(1, 8)..Position::new(1, 13)),
+                        placeholder: None,
+                    },
+                ),
+            )),
+            // Expect document highlights to be requested if there are no linked editing ranges
+            App(HandleLspNotification(LspNotification::LinkedEditingRanges(
+                Vec::new(),
+            ))),
+            Expect(LspRequestSent(FromEditor::TextDocumentDocumentHighlight(
+                params,
+            ))),
+            App(HandleLspNotification(LspNotification::DocumentHighlights(
+                [
+                    Position::new(1, 8)..Position::new(1, 13),
+                    Position::new(4, 6)..Position::new(4, 11),
+                ]
+                .to_vec(),
+            ))),
+            App(HandleKeyEvents(keys!("s").to_vec())),
+            // Expect the shadowing closure parameter and the comment to be left alone
+            Expect(CurrentComponentContent(
+                "
+fn main() {
+    let counts = 1;
+    // count is one
+    let f = |count: i32| count;
+    f(counts)
+}"
+                .trim(),
+            )),
+        ])
+    })
+}
+
+#[test]
+fn rename_without_lsp_occurrences_edits_the_name_in_the_enclosing_function() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "
+fn main() {
+    let count = 1;
+    // count is one
+    count + 1
+}
+
+fn other() {
+    let count = 2;
+}"
+                .trim()
+                .to_string(),
+            )),
+            Editor(MatchLiteral("count".to_string())),
+            App(HandleLspNotification(
+                LspNotification::PrepareRenameResponse(
+                    crate::lsp::prepare_rename_response::PrepareRenameResponse {
+                        range: Some(Position::new(1, 8)..Position::new(1, 13)),
+                        placeholder: None,
+                    },
+                ),
+            )),
+            App(HandleLspNotification(LspNotification::LinkedEditingRanges(
+                Vec::new(),
+            ))),
+            App(HandleLspNotification(LspNotification::DocumentHighlights(
+                Vec::new(),
+            ))),
+            App(HandleKeyEvents(keys!("s").to_vec())),
+            // Expect the comment and the name in the other function to be left alone
+            Expect(CurrentComponentContent(
+                "
+fn main() {
+    let counts = 1;
+    // count is one
+    counts + 1
+}
+
+fn other() {
+    let count = 2;
+}"
+                .trim(),
+            )),
+        ])
+    })
+}
+
+#[test]
+fn request_signature_help() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("f()".to_string())),
+            Editor(MatchLiteral("f()".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            Expect(ExpectKind::LspRequestSent(
+                FromEditor::TextDocumentSignatureHelp(RequestParams {
+                    path: s.main_rs(),
+                    position: Position::new(0, 3),
+                    context: Default::default(),
+                }),
+            )),
+            App(HandleKeyEvent(key!("left"))),
+            Expect(ExpectKind::LspRequestSent(
+                FromEditor::TextDocumentSignatureHelp(RequestParams {
+                    path: s.main_rs(),
+                    position: Position::new(0, 2),
+                    context: Default::default(),
+                }),
             )),
         ])
     })
@@ -1947,3 +2722,982 @@ c1 c2 a1\nb1\nc1
         }
     })
 }
+
+#[test]
+fn keymap_cheat_sheet() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(ShowKeymapCheatSheet),
+            Expect(CurrentComponentTitle("Keymap cheat sheet")),
+            Expect(AppGridContains("## Normal mode")),
+            Expect(CurrentComponentPath(None)),
+            Expect(ComponentReadOnly("Keymap cheat sheet", true)),
+        ])
+    })
+}
+
+#[test]
+fn tutor_advances_when_lesson_is_passed() -> anyhow::Result<()> {
+    execute_test(|_| {
+        Box::new([
+            App(StartTutor),
+            Expect(CurrentComponentTitle("Tutor 1/5: Word selection")),
+            App(HandleKeyEvents(keys!("w").to_vec())),
+            Expect(CurrentComponentTitle("Tutor 1/5: Word selection")),
+            App(HandleKeyEvents(keys!("l").to_vec())),
+            Expect(CurrentComponentTitle("Tutor 2/5: Line selection")),
+            Expect(CurrentComponentContent(
+                "first line\nsecond line\nthe target line\nlast line",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn every_tutor_lesson_can_be_passed() -> anyhow::Result<()> {
+    execute_test(|_| {
+        let lessons = [
+            ("Tutor 1/5: Word selection", keys!("w l").to_vec()),
+            ("Tutor 2/5: Line selection", keys!("e l l").to_vec()),
+            ("Tutor 3/5: Delete", keys!("w l l d").to_vec()),
+            // Moving word by word instead of jumping, because the jump labels vary
+            ("Tutor 4/5: Jump", keys!("w l l l l l l l").to_vec()),
+            (
+                "Tutor 5/5: Multi-cursor",
+                keys!("e space a i - space esc").to_vec(),
+            ),
+        ];
+        assert_eq!(lessons.len(), crate::tutor::LESSONS.len());
+        Some(App(StartTutor))
+            .into_iter()
+            .chain(lessons.into_iter().flat_map(|(title, keys)| {
+                [
+                    Expect(CurrentComponentTitle(title)),
+                    App(HandleKeyEvents(keys)),
+                ]
+            }))
+            .chain(Some(Expect(AppGridContains(
+                "Congratulations, you have completed all lessons!",
+            ))))
+            .collect_vec()
+            .into_boxed_slice()
+    })
+}
+
+#[test]
+fn color_literal_swatch_and_edit_color() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let x = \"#ff0000\";".to_string())),
+            Editor(SetRectangle(crate::rectangle::Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 2,
+            })),
+            Expect(EditorGrid("🦀  src/main.rs\n1│█et x = \"#ff0000\"; ■")),
+            Editor(MatchLiteral("ff00".to_string())),
+            App(HandleKeyEvents(keys!("space C").to_vec())),
+            Expect(CurrentComponentTitle("Edit color")),
+            Editor(SetContent("rgb(0, 0, 255)".to_string())),
+            App(HandleKeyEvent(key!("enter"))),
+            Expect(CurrentComponentContent("let x = \"rgb(0, 0, 255)\";")),
+        ])
+    })
+}
+
+#[test]
+fn document_colors_from_lsp_replace_detected_color_literals() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let x = RED; // #ff0000".to_string())),
+            Editor(SetRectangle(crate::rectangle::Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 2,
+            })),
+            App(HandleLspNotification(LspNotification::DocumentColor {
+                path: s.main_rs(),
+                colors: [lsp_types::ColorInformation {
+                    range: lsp_types::Range::new(
+                        lsp_types::Position::new(0, 8),
+                        lsp_types::Position::new(0, 11),
+                    ),
+                    color: lsp_types::Color {
+                        red: 1.0,
+                        green: 0.0,
+                        blue: 0.0,
+                        alpha: 1.0,
+                    },
+                }]
+                .to_vec(),
+            })),
+            Editor(MatchLiteral("RED".to_string())),
+            Expect(CurrentSelectedTexts(&["RED"])),
+            // Only one swatch is rendered, because the comment is not a color according to LSP
+            Expect(EditorGrid("🦀  src/main.rs\n1│let x = █ED; // #ff0000 ■")),
+        ])
+    })
+}
+
+#[test]
+fn open_path_under_cursor_relative_to_the_current_file() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("// See (foo.rs:2:3).".to_string())),
+            Editor(MatchLiteral("foo".to_string())),
+            Editor(OpenPathOrUrlUnderCursor),
+            Expect(CurrentComponentPath(Some(s.foo_rs()))),
+            Expect(EditorCursorPosition(Position::new(1, 2))),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let x = \"../Cargo.toml\";".to_string())),
+            Editor(MatchLiteral("Cargo".to_string())),
+            Editor(OpenPathOrUrlUnderCursor),
+            Expect(CurrentComponentPath(Some(
+                s.temp_dir().join("Cargo.toml").unwrap(),
+            ))),
+        ])
+    })
+}
+
+#[test]
+fn resize_windows_and_restore_layout_preset() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(TerminalDimensionChanged(Dimension {
+                width: 200,
+                height: 20,
+            })),
+            App(OpenFile(s.main_rs())),
+            App(RevealInExplorer(s.main_rs())),
+            Expect(CurrentComponentWidth(59)),
+            App(EqualizeWindows),
+            Expect(CurrentComponentWidth(99)),
+            App(ResizeWindow { grow: true }),
+            Expect(CurrentComponentWidth(109)),
+            App(SaveLayoutPreset("code + explorer".to_string())),
+            App(CloseCurrentWindow),
+            Expect(CurrentComponentWidth(200)),
+            App(RestoreLayoutPreset("code + explorer".to_string())),
+            Expect(CurrentComponentWidth(90)),
+            App(OtherWindow),
+            Expect(CurrentComponentWidth(109)),
+        ])
+    })
+}
+
+#[test]
+fn toggle_sticky_context() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 200,
+                height: 6,
+            })),
+            Editor(SetContent(
+                "fn first () {\n  second();\n  third();\n  fourth();\n  fifth();\n}".to_string(),
+            )),
+            Editor(MatchLiteral("fifth()".to_string())),
+            Editor(AlignViewTop),
+            Expect(AppGridContains("1│fn first () {")),
+            App(ToggleStickyContext),
+            Expect(Not(Box::new(AppGridContains("1│fn first () {")))),
+            Expect(AppGridContains("5│  █ifth();")),
+        ])
+    })
+}
+
+#[test]
+fn receive_test_run() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let test_case = |name: &str, line: usize| crate::test_runner::TestCase {
+            name: name.to_string(),
+            range: Position::new(line, 0)..Position::new(line + 1, 1),
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 100,
+                height: 20,
+            })),
+            Editor(SetContent(
+                "#[test]\nfn a() {\n}\n#[test]\nfn b() {\n}".to_string(),
+            )),
+            App(ReceiveTestRun(crate::test_runner::TestRun {
+                path: s.main_rs(),
+                tests: [test_case("a", 1), test_case("b", 4)].to_vec(),
+                names: shared::language::TestNames::RustPath,
+                output: "test a ... ok\ntest b ... FAILED\n---- b stdout ----\nboom\n".to_string(),
+            })),
+            Expect(AppGridContains("2│fn a() { ✔")),
+            Expect(AppGridContains("5│fn b() { ✘")),
+            Expect(Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.main_rs(),
+                    range: Position::new(4, 0)..Position::new(5, 1),
+                },
+                Some(Info::new(
+                    "Test \"b\" failed".to_string(),
+                    "boom".to_string(),
+                )),
+            )]))),
+            // The markers are hidden once the buffer is modified
+            Editor(SetContent("fn a() {\n}".to_string())),
+            Expect(Not(Box::new(AppGridContains("✔")))),
+        ])
+    })
+}
+
+#[test]
+fn receive_build_output() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n  let x = 1;\n}".to_string())),
+            App(ReceiveBuildOutput(
+                "src/main.rs:2:7: warning: unused variable `x`\nsrc/missing.rs:1:1: error: oops\n"
+                    .to_string(),
+            )),
+            Expect(DiagnosticsRanges(
+                [CharIndexRange::from(CharIndex(18)..CharIndex(19))].to_vec(),
+            )),
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+            Expect(CurrentSelectedTexts(&["x"])),
+            // Diagnostics of the previous build are cleared
+            App(ReceiveBuildOutput("Finished".to_string())),
+            Expect(DiagnosticsRanges(Vec::new())),
+        ])
+    })
+}
+
+#[test]
+fn build_diagnostics_are_kept_apart_from_lsp_diagnostics() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let publish_diagnostics =
+            LspNotification::PublishDiagnostics(lsp_types::PublishDiagnosticsParams {
+                uri: s.main_rs().to_url().unwrap(),
+                diagnostics: [lsp_types::Diagnostic {
+                    range: lsp_types::Range::new(
+                        lsp_types::Position {
+                            line: 0,
+                            character: 3,
+                        },
+                        lsp_types::Position {
+                            line: 0,
+                            character: 7,
+                        },
+                    ),
+                    message: "unused function".to_string(),
+                    ..Default::default()
+                }]
+                .to_vec(),
+                version: None,
+            });
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n  let x = 1;\n}".to_string())),
+            App(HandleLspNotification(publish_diagnostics.clone())),
+            App(ReceiveBuildOutput(
+                "src/main.rs:2:7: warning: unused variable `x`\n".to_string(),
+            )),
+            // The build does not overwrite the diagnostics of LSP servers
+            Expect(DiagnosticsRanges(
+                [
+                    CharIndexRange::from(CharIndex(3)..CharIndex(7)),
+                    CharIndexRange::from(CharIndex(18)..CharIndex(19)),
+                ]
+                .to_vec(),
+            )),
+            // LSP servers do not overwrite the diagnostics of the build
+            App(HandleLspNotification(publish_diagnostics)),
+            Expect(DiagnosticsRanges(
+                [
+                    CharIndexRange::from(CharIndex(3)..CharIndex(7)),
+                    CharIndexRange::from(CharIndex(18)..CharIndex(19)),
+                ]
+                .to_vec(),
+            )),
+            // A new build only clears its own diagnostics
+            App(ReceiveBuildOutput("Finished".to_string())),
+            Expect(DiagnosticsRanges(
+                [CharIndexRange::from(CharIndex(3)..CharIndex(7))].to_vec(),
+            )),
+        ])
+    })
+}
+
+#[test]
+fn breadcrumbs() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 200,
+                height: 6,
+            })),
+            Editor(SetContent(
+                "mod m {\n  fn first() {\n    second();\n  }\n}".to_string(),
+            )),
+            Editor(MatchLiteral("second".to_string())),
+            Expect(Not(Box::new(AppGridContains("src/main.rs › m › first")))),
+            App(ToggleBreadcrumbs),
+            Expect(AppGridContains("src/main.rs › m › first")),
+            Editor(MatchLiteral("mod".to_string())),
+            Expect(Not(Box::new(AppGridContains("src/main.rs › m › first")))),
+            Expect(AppGridContains("src/main.rs › m")),
+            Editor(MatchLiteral("second".to_string())),
+            App(OpenBreadcrumbsPrompt),
+            App(HandleKeyEvents(keys!("m").to_vec())),
+            App(HandleKeyEvent(key!("enter"))),
+            Expect(CurrentSelectedTexts(&[
+                "mod m {\n  fn first() {\n    second();\n  }\n}",
+            ])),
+        ])
+    })
+}
+
+#[test]
+fn privileged_save() -> anyhow::Result<()> {
+    run_test(|mut app, temp_dir| {
+        let path = temp_dir.join("src/main.rs")?;
+        app.set_elevation_command("tee".to_string());
+        app.handle_dispatch(OpenFile(path.clone()))?;
+        app.handle_dispatch_editor(SetContent("fn main() {}\n".to_string()))?;
+        app.handle_dispatch(PrivilegedSave(path.clone()))?;
+        assert_eq!(path.read()?, "fn main() {}\n");
+
+        app.set_elevation_command("false".to_string());
+        assert!(app.handle_dispatch(PrivilegedSave(path)).is_err());
+        Ok(())
+    })
+}
+
+#[cfg(unix)]
+#[test]
+fn privileged_save_suspends_the_terminal_and_the_input() -> anyhow::Result<()> {
+    use crate::frontend::Frontend;
+    use std::os::unix::fs::PermissionsExt;
+    TestRunner::run(|temp_dir| {
+        let frontend = Arc::new(Mutex::new(MockFrontend::default()));
+        frontend.lock().unwrap().enable_raw_mode()?;
+        let mut app = App::new(frontend.clone(), temp_dir.clone())?;
+        app.disable_lsp();
+        // The elevation command takes a while, so that its state can be observed meanwhile
+        let elevation_command = temp_dir.to_path_buf().join("elevate.sh");
+        std::fs::write(&elevation_command, "#!/bin/sh\nsleep 0.5\ncat > \"$1\"\n")?;
+        std::fs::set_permissions(&elevation_command, std::fs::Permissions::from_mode(0o755))?;
+        app.set_elevation_command(elevation_command.display().to_string());
+
+        let path = temp_dir.join("src/main.rs")?;
+        app.handle_dispatch(OpenFile(path.clone()))?;
+        app.handle_dispatch_editor(SetContent("fn main() {}\n".to_string()))?;
+        let input_gate = app.input_gate();
+        let observer = {
+            let frontend = frontend.clone();
+            let input_gate = input_gate.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                (
+                    input_gate.read(|| ()).is_some(),
+                    frontend.lock().unwrap().raw_mode_enabled(),
+                )
+            })
+        };
+        app.handle_dispatch(PrivilegedSave(path.clone()))?;
+
+        // Expect neither events to be read nor raw mode to be enabled while the command runs
+        assert_eq!(observer.join().unwrap(), (false, false));
+        // Expect both to be resumed afterwards
+        assert!(input_gate.read(|| ()).is_some());
+        assert!(frontend.lock().unwrap().raw_mode_enabled());
+        assert_eq!(path.read()?, "fn main() {}\n");
+        Ok(())
+    })
+}
+
+#[test]
+fn moving_directory_keeps_opened_files() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("unsaved".to_string())),
+            App(MoveFile {
+                from: s.temp_dir().join("src").unwrap(),
+                to: s.new_path("lib"),
+            }),
+            ExpectLater(Box::new(move || {
+                CurrentComponentPath(Some(temp_dir.join("lib/main.rs").unwrap()))
+            })),
+            Expect(CurrentComponentContent("unsaved")),
+        ])
+    })
+}
+
+#[test]
+fn prompt_history_is_restored() -> anyhow::Result<()> {
+    use crate::app::DispatchPrompt;
+    use crate::components::prompt::{PromptConfig, PromptHistoryKey};
+    use crate::persistence::Persistence;
+    run_test(|mut app, temp_dir| {
+        app.set_persistence(Persistence::new(temp_dir.clone()));
+        app.handle_dispatch(PushPromptHistory {
+            key: PromptHistoryKey::Null,
+            line: "hello".to_string(),
+        })?;
+
+        // Nothing is restored without persistence
+        let app_without_persistence = App::new(
+            Arc::new(Mutex::new(MockFrontend::default())),
+            temp_dir.clone(),
+        )?;
+        assert!(app_without_persistence
+            .context()
+            .prompt_histories()
+            .is_empty());
+
+        // A new session in the same working directory
+        let mut app = App::new(
+            Arc::new(Mutex::new(MockFrontend::default())),
+            temp_dir.clone(),
+        )?;
+        app.disable_lsp();
+        app.set_persistence(Persistence::new(temp_dir));
+        app.handle_dispatch(OpenPrompt {
+            key: PromptHistoryKey::Null,
+            current_line: None,
+            config: PromptConfig {
+                on_enter: DispatchPrompt::Null,
+                items: Default::default(),
+                title: "".to_string(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+        })?;
+        CurrentComponentContent("\nhello").run(&mut app);
+        Ok(())
+    })
+}
+
+#[test]
+fn edit_locations_across_files() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {}\nfn a() {}".to_string())),
+            Editor(MatchLiteral("a()".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("b".to_string())),
+            App(OpenFile(s.foo_rs())),
+            Editor(SetContent("fn foo() {}".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x".to_string())),
+            App(OpenFile(s.main_rs())),
+            App(GoToLastEdit),
+            Expect(CurrentComponentPath(Some(s.foo_rs()))),
+            App(CycleEditLocations),
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+            Expect(CurrentLine("fn ba() {}")),
+        ])
+    })
+}
+
+#[test]
+fn bookmarks_picker_shows_notes() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(ToggleBookmark),
+            Editor(MoveSelection(Next)),
+            App(OpenBookmarkNotePrompt),
+            App(HandleKeyEvents(
+                keys!("c h e c k space t h i s enter").to_vec(),
+            )),
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+            App(OpenBookmarksPicker),
+            Expect(AppGridContains("src/main.rs")),
+            Expect(AppGridContains("1:1 mod")),
+            Expect(AppGridContains("1:5 check this")),
+            App(HandleKeyEvents(keys!("c h e c k enter").to_vec())),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            // Toggling a bookmark with a note removes it together with its note
+            Editor(ToggleBookmark),
+            App(OpenBookmarksPicker),
+            Expect(Not(Box::new(AppGridContains("check this")))),
+        ])
+    })
+}
+
+#[test]
+fn jump_across_panes() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            App(RevealInExplorer(s.main_rs())),
+            Expect(CurrentComponentTitle("File Explorer")),
+            App(ShowJumpsAcrossPanes),
+            // Typing the character of a pane focuses it and relabels its jumps
+            App(HandleKeyEvent(key!("a"))),
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+            Expect(JumpChars(&['a', 'b'])),
+            App(HandleKeyEvent(key!("b"))),
+            Expect(CurrentSelectedTexts(&["world"])),
+        ])
+    })
+}
+
+#[test]
+fn diagnostics_picker() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let publish = |message: &str| {
+            App(Dispatch::HandleLspNotification(
+                LspNotification::PublishDiagnostics(lsp_types::PublishDiagnosticsParams {
+                    uri: Url::from_file_path(s.foo_rs()).unwrap(),
+                    diagnostics: [lsp_types::Diagnostic {
+                        range: lsp_types::Range::new(
+                            lsp_types::Position::new(0, 18),
+                            lsp_types::Position::new(0, 21),
+                        ),
+                        severity: Some(lsp_types::DiagnosticSeverity::WARNING),
+                        source: Some("rustc".to_string()),
+                        message: message.to_string(),
+                        ..Default::default()
+                    }]
+                    .to_vec(),
+                    version: None,
+                }),
+            ))
+        };
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            publish("unused function"),
+            App(OpenDiagnosticsPicker),
+            Expect(AppGridContains("src/foo.rs (Warning)")),
+            Expect(AppGridContains("1:19 [rustc] unused function")),
+            // The picker is updated as new diagnostics arrive
+            publish("renamed function"),
+            Expect(AppGridContains("1:19 [rustc] renamed function")),
+            App(HandleKeyEvents(keys!("r u s t c enter").to_vec())),
+            Expect(CurrentComponentPath(Some(s.foo_rs()))),
+            Expect(CurrentSelectedTexts(&["Foo"])),
+        ])
+    })
+}
+
+#[test]
+fn terminal_title() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let workspace = s
+            .temp_dir()
+            .to_path_buf()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Expect(TerminalTitle(format!("main.rs — {workspace}"))),
+            Editor(EnterInsertMode(Direction::Start)),
+            App(HandleKeyEvents(keys!("a").to_vec())),
+            Expect(TerminalTitle(format!("main.rs — {workspace} (modified)"))),
+            Editor(Save),
+            Expect(TerminalTitle(format!("main.rs — {workspace}"))),
+        ])
+    })
+}
+
+#[test]
+fn status_line_diagnostics_count() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let diagnostic = |severity: lsp_types::DiagnosticSeverity| lsp_types::Diagnostic {
+            range: lsp_types::Range::new(
+                lsp_types::Position::new(0, 0),
+                lsp_types::Position::new(0, 1),
+            ),
+            severity: Some(severity),
+            message: "message".to_string(),
+            ..Default::default()
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 300,
+                height: 6,
+            })),
+            Expect(Not(Box::new(AppGridContains("] E")))),
+            App(Dispatch::HandleLspNotification(
+                LspNotification::PublishDiagnostics(lsp_types::PublishDiagnosticsParams {
+                    uri: Url::from_file_path(s.main_rs()).unwrap(),
+                    diagnostics: [
+                        diagnostic(lsp_types::DiagnosticSeverity::ERROR),
+                        diagnostic(lsp_types::DiagnosticSeverity::ERROR),
+                        diagnostic(lsp_types::DiagnosticSeverity::WARNING),
+                        diagnostic(lsp_types::DiagnosticSeverity::HINT),
+                    ]
+                    .to_vec(),
+                    version: None,
+                }),
+            )),
+            Expect(AppGridContains("] E2 W1")),
+        ])
+    })
+}
+
+#[test]
+fn status_line_find_match_index() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 300,
+                height: 6,
+            })),
+            Editor(SetContent("foo bar foo baz foo".to_string())),
+            Expect(Not(Box::new(AppGridContains(" match ")))),
+            Editor(MatchLiteral("foo".to_string())),
+            Expect(AppGridContains("] match 1/3")),
+            Editor(MoveSelection(Next)),
+            Expect(AppGridContains("] match 2/3")),
+            Editor(MoveSelection(Last)),
+            Expect(AppGridContains("] match 3/3")),
+            // The count is recomputed after the buffer is modified
+            Editor(SetContent("foo foo foo foo".to_string())),
+            Editor(MatchLiteral("foo".to_string())),
+            Expect(AppGridContains("] match 1/4")),
+        ])
+    })
+}
+
+#[test]
+fn inline_diagnostics() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let diagnostic = |line: u32, column: u32, message: &str| lsp_types::Diagnostic {
+            range: lsp_types::Range::new(
+                lsp_types::Position::new(line, column),
+                lsp_types::Position::new(line, column + 1),
+            ),
+            message: message.to_string(),
+            ..Default::default()
+        };
+        let long_message = "long ".repeat(100);
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(TerminalDimensionChanged(Dimension {
+                width: 200,
+                height: 6,
+            })),
+            Editor(SetContent("fn main() {}\nlet x = 1;".to_string())),
+            App(Dispatch::HandleLspNotification(
+                LspNotification::PublishDiagnostics(lsp_types::PublishDiagnosticsParams {
+                    uri: Url::from_file_path(s.main_rs()).unwrap(),
+                    diagnostics: [
+                        diagnostic(0, 3, &long_message),
+                        diagnostic(1, 8, "second"),
+                        diagnostic(1, 4, "unused variable\nhelp: prefix it with _"),
+                    ]
+                    .to_vec(),
+                    version: None,
+                }),
+            )),
+            Expect(Not(Box::new(AppGridContains("unused variable")))),
+            App(ToggleInlineDiagnostics),
+            // Only the first line of the first diagnostic of each line is shown
+            Expect(AppGridContains("let x = 1;  unused variable")),
+            Expect(Not(Box::new(AppGridContains("second")))),
+            Expect(Not(Box::new(AppGridContains("help")))),
+            // Long messages are truncated instead of being wrapped
+            Expect(AppGridContains("fn main() {}  long long")),
+            Expect(AppGridContains("…")),
+            App(ToggleInlineDiagnostics),
+            Expect(Not(Box::new(AppGridContains("unused variable")))),
+        ])
+    })
+}
+
+#[test]
+fn insert_unicode_code_point_and_picked_character() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            App(OpenUnicodeCodePointPrompt),
+            App(HandleKeyEvents(keys!("U + 0 0 e 9 enter").to_vec())),
+            Expect(CurrentComponentContent("é")),
+            App(OpenCharacterPicker),
+            App(HandleKeyEvents(keys!("r o c k e t enter").to_vec())),
+            Expect(CurrentComponentContent("é🚀")),
+        ])
+    })
+}
+
+#[test]
+fn auto_center_after_large_movements() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let content = (0..40)
+            .map(|line| match line {
+                2 => "gamma",
+                4 => "beta",
+                15 => "alpha",
+                _ => "x",
+            })
+            .join("\n");
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(content)),
+            Editor(SetRectangle(crate::rectangle::Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 20,
+            })),
+            App(ToggleAutoCenter),
+            // Small movements only keep the cursor in view
+            Editor(MatchLiteral("gamma".to_string())),
+            Editor(MatchLiteral("beta".to_string())),
+            Expect(CurrentScrollOffset(0)),
+            Expect(CurrentViewAlignment(None)),
+            // The cursor is still in view, but it moved further than half of the view
+            Editor(MatchLiteral("alpha".to_string())),
+            Expect(CurrentLine("alpha")),
+            Expect(CurrentScrollOffset(5)),
+            Expect(CurrentViewAlignment(Some(ViewAlignment::Center))),
+        ])
+    })
+}
+
+#[test]
+fn copy_path() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let copy_and_paste = |format: crate::app::PathFormat, expected: &'static str| {
+            [
+                Editor(SetContent("".to_string())),
+                App(CopyPath {
+                    path: s.main_rs(),
+                    format,
+                }),
+                Editor(Paste {
+                    direction: Direction::End,
+                    use_system_clipboard: false,
+                }),
+                Expect(CurrentComponentContent(expected)),
+            ]
+        };
+        Box::new(
+            [App(OpenFile(s.main_rs()))]
+                .into_iter()
+                .chain(copy_and_paste(
+                    crate::app::PathFormat::Relative,
+                    "src/main.rs",
+                ))
+                .chain(copy_and_paste(
+                    crate::app::PathFormat::RelativeWithLine(1),
+                    "src/main.rs:2",
+                ))
+                .chain(copy_and_paste(crate::app::PathFormat::FileName, "main.rs"))
+                .collect_vec()
+                .into_boxed_slice(),
+        )
+    })
+}
+
+#[test]
+fn change_working_directory() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(RevealCurrentFileInExplorer),
+            App(ChangeWorkingDirectory("src".into())),
+            // Expect the opened file explorer lists the new working directory
+            Expect(FileExplorerContent(
+                " - 🦀  foo.rs\n - 🦀  main.rs".to_string(),
+            )),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("".to_string())),
+            // Expect paths are relative to the new working directory
+            App(CopyPath {
+                path: s.main_rs(),
+                format: crate::app::PathFormat::Relative,
+            }),
+            Editor(Paste {
+                direction: Direction::End,
+                use_system_clipboard: false,
+            }),
+            Expect(CurrentComponentContent("main.rs")),
+        ])
+    })
+}
+
+#[test]
+fn change_working_directory_restores_the_persisted_state_of_the_new_workspace() -> anyhow::Result<()>
+{
+    use crate::components::prompt::PromptHistoryKey;
+    use crate::persistence::Persistence;
+    run_test(|mut app, temp_dir| {
+        app.set_persistence(Persistence::new(temp_dir.clone()));
+        let history = |app: &App<MockFrontend>| {
+            app.context()
+                .prompt_histories()
+                .get(&PromptHistoryKey::Null)
+                .into_iter()
+                .flatten()
+                .cloned()
+                .collect_vec()
+        };
+        let push = |app: &mut App<MockFrontend>, line: &str| {
+            app.handle_dispatch(PushPromptHistory {
+                key: PromptHistoryKey::Null,
+                line: line.to_string(),
+            })
+        };
+        push(&mut app, "root")?;
+
+        app.handle_dispatch(ChangeWorkingDirectory("src".into()))?;
+        assert_eq!(
+            app.context().persistence().workspace(),
+            Some(&temp_dir.join("src")?)
+        );
+        assert!(history(&app).is_empty());
+        push(&mut app, "src")?;
+
+        app.handle_dispatch(ChangeWorkingDirectory("..".into()))?;
+        assert_eq!(history(&app), ["root".to_string()]);
+        Ok(())
+    })
+}
+
+#[test]
+fn file_operations_are_sent_to_lsp_servers() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let new_path = s.new_path("src/bar.rs");
+        let edit = |path: CanonicalizedPath| TextDocumentEdit {
+            path,
+            edits: [PositionalEdit {
+                range: Position::new(0, 0)..Position::new(0, 0),
+                new_text: "// moved\n".to_string(),
+            }]
+            .to_vec(),
+        };
+        Box::new([
+            App(MoveFile {
+                from: s.foo_rs(),
+                to: new_path.clone(),
+            }),
+            Expect(LspRequestSent(FromEditor::WorkspaceWillRenameFiles {
+                old: s.foo_rs(),
+                new: new_path,
+            })),
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {}".to_string())),
+            // Expect the edits returned for a file operation are previewed like other workspace edits
+            App(HandleLspNotification(LspNotification::WorkspaceEdit(
+                WorkspaceEdit {
+                    edits: [edit(s.main_rs()), edit(s.gitignore())].to_vec(),
+                    resource_operations: Vec::new(),
+                },
+            ))),
+            Expect(CurrentComponentTitle("Workspace edit")),
+            App(HandleKeyEvents(keys!("a").to_vec())),
+            App(OpenFile(s.main_rs())),
+            Expect(CurrentComponentContent("// moved\nfn main() {}")),
+            App(DeletePath(s.gitignore())),
+            Expect(LspRequestSent(FromEditor::WorkspaceDidDeleteFiles {
+                path: s.gitignore(),
+            })),
+        ])
+    })
+}
+
+#[test]
+fn create_and_checkout_branch() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let head = move || {
+            git2::Repository::open(temp_dir.to_path_buf())
+                .unwrap()
+                .head()
+                .unwrap()
+                .shorthand()
+                .unwrap()
+                .to_string()
+        };
+        let head_ = head.clone();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(CreateBranch("feature".to_string())),
+            ExpectCustom(Box::new(move || assert_eq!(head_(), "feature"))),
+            App(OpenBranchPicker),
+            Expect(CurrentComponentTitle("Branches")),
+            App(CloseCurrentWindow),
+            App(CreateBranch("other".to_string())),
+            App(CheckoutBranch(crate::git::Branch {
+                name: "feature".to_string(),
+                is_remote: false,
+                is_head: false,
+            })),
+            ExpectCustom(Box::new(move || assert_eq!(head(), "feature"))),
+        ])
+    })
+}
+
+#[test]
+fn delete_branch_after_confirmation() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let temp_dir = s.temp_dir();
+        let branch_exists = move |name: &str| {
+            git2::Repository::open(temp_dir.to_path_buf())
+                .unwrap()
+                .find_branch(name, git2::BranchType::Local)
+                .is_ok()
+        };
+        let branch_exists_ = branch_exists.clone();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(CreateBranch("feature".to_string())),
+            App(CreateBranch("other".to_string())),
+            App(ConfirmDeleteBranch(crate::git::Branch {
+                name: "feature".to_string(),
+                is_remote: false,
+                is_head: false,
+            })),
+            Expect(AppGridContains("Delete branch \"feature\"?")),
+            ExpectCustom(Box::new(move || assert!(branch_exists_("feature")))),
+            App(HandleKeyEvents(keys!("y").to_vec())),
+            ExpectCustom(Box::new(move || assert!(!branch_exists("feature")))),
+        ])
+    })
+}
+
+#[test]
+fn commit_log() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let hash = git2::Repository::open(s.temp_dir().to_path_buf())
+            .unwrap()
+            .head()
+            .unwrap()
+            .peel_to_commit()
+            .unwrap()
+            .id()
+            .to_string();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(OpenCommitLog {
+                current_file: false,
+            }),
+            Expect(CurrentComponentTitle("Commit log")),
+            App(CloseCurrentWindow),
+            App(OpenCommitLog { current_file: true }),
+            Expect(CurrentComponentTitle("Commit log (src/main.rs)")),
+            App(CloseCurrentWindow),
+            App(ShowCommit(hash)),
+            Expect(AppGridContains("    init")),
+        ])
+    })
+}
@@ -0,0 +1,423 @@
+use std::{ops::Range, path::Path, sync::mpsc::Sender};
+
+use itertools::Itertools;
+use shared::{
+    canonicalized_path::CanonicalizedPath,
+    language::{TestNames, TestRunner},
+    process_command::ProcessCommand,
+};
+use tree_sitter::{Query, QueryCursor};
+
+use crate::{app::AppMessage, buffer::Buffer, position::Position, user_config::TestCommands};
+
+/// A test detected in a buffer by the query of its `TestRunner`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TestCase {
+    pub(crate) name: String,
+    pub(crate) range: Range<Position>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TestStatus {
+    Passed,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TestResult {
+    pub(crate) test: TestCase,
+    pub(crate) status: TestStatus,
+    /// The output of the test runner about the failure of this test
+    pub(crate) message: Option<String>,
+}
+
+/// The output of running `tests` of the file at `path`, see `run`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TestRun {
+    pub(crate) path: CanonicalizedPath,
+    pub(crate) tests: Vec<TestCase>,
+    pub(crate) names: TestNames,
+    pub(crate) output: String,
+}
+
+pub(crate) fn detect_tests(
+    buffer: &Buffer,
+    test_runner: &TestRunner,
+) -> anyhow::Result<Vec<TestCase>> {
+    let Some(tree) = buffer.tree() else {
+        return Ok(Vec::new());
+    };
+    let query = Query::new(&tree.language(), test_runner.query())?;
+    let (Some(test_index), Some(name_index)) = (
+        query.capture_index_for_name("test"),
+        query.capture_index_for_name("name"),
+    ) else {
+        return Err(anyhow::anyhow!(
+            "The test query should capture `@test` and `@name`"
+        ));
+    };
+    let module_indices = (
+        query.capture_index_for_name("module"),
+        query.capture_index_for_name("module.name"),
+    );
+    let content = buffer.content();
+    let mut cursor = QueryCursor::new();
+    let mut modules = Vec::new();
+    let mut tests = Vec::new();
+    for query_match in cursor.matches(&query, tree.root_node(), content.as_bytes()) {
+        let node = |index| {
+            query_match
+                .captures
+                .iter()
+                .find(|capture| capture.index == index)
+                .map(|capture| capture.node)
+        };
+        if let (Some(test), Some(name)) = (node(test_index), node(name_index)) {
+            tests.push((test, name))
+        } else if let (Some(module_index), Some(module_name_index)) = module_indices {
+            if let (Some(module), Some(name)) = (node(module_index), node(module_name_index)) {
+                modules.push((module.byte_range(), &content[name.byte_range()]))
+            }
+        }
+    }
+    let file_modules = match (test_runner.names(), buffer.path()) {
+        (TestNames::RustPath, Some(path)) => rust_file_modules(path.as_ref()),
+        _ => Vec::new(),
+    };
+    tests
+        .into_iter()
+        .map(|(test, name)| {
+            let name = content[name.byte_range()].trim_matches(['"', '\'', '`']);
+            let name = match test_runner.names() {
+                TestNames::Plain => name.to_string(),
+                TestNames::RustPath => file_modules
+                    .iter()
+                    .map(String::as_str)
+                    .chain(
+                        modules
+                            .iter()
+                            .filter(|(range, _)| {
+                                range.start <= test.start_byte() && test.end_byte() <= range.end
+                            })
+                            .map(|(_, module)| *module),
+                    )
+                    .chain([name])
+                    .join("::"),
+            };
+            Ok(TestCase {
+                name,
+                range: buffer.byte_to_position(test.start_byte())?
+                    ..buffer.byte_to_position(test.end_byte())?,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map(|tests| tests.into_iter().unique().collect())
+}
+
+/// Returns the modules of the Rust file at `path` in its crate,
+/// such as `["foo", "bar"]` for `src/foo/bar.rs` or `src/foo/bar/mod.rs`.
+///
+/// Files outside of `src`, such as integration tests, and binaries under `src/bin` are crate roots.
+fn rust_file_modules(path: &Path) -> Vec<String> {
+    let components = path
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect_vec();
+    let Some(src) = components.iter().rposition(|component| component == "src") else {
+        return Vec::new();
+    };
+    let mut modules = components[src + 1..].to_vec();
+    if let Some(file) = modules.pop() {
+        match file.strip_suffix(".rs") {
+            Some("lib" | "main" | "mod") | None => {}
+            Some(module) => modules.push(module.to_string()),
+        }
+    }
+    if modules.first().map(String::as_str) == Some("bin") {
+        return Vec::new();
+    }
+    modules
+}
+
+/// Runs `tests` of the file at `path` in another thread,
+/// and sends the output back as `AppMessage::TestRun` when the command exits.
+///
+/// If `all` is false, only the first test of `tests` is run.
+/// The commands of `test_commands` replace the built-in commands of `test_runner`.
+pub(crate) fn run(
+    test_runner: &TestRunner,
+    test_commands: Option<&TestCommands>,
+    working_directory: &CanonicalizedPath,
+    path: CanonicalizedPath,
+    tests: Vec<TestCase>,
+    all: bool,
+    sender: Sender<AppMessage>,
+) -> anyhow::Result<String> {
+    let file = path
+        .display_relative_to(working_directory)
+        .unwrap_or_else(|_| path.display_absolute());
+    let names = tests.iter().map(|test| test.name.as_str()).collect_vec();
+    let command = command(test_runner, test_commands, &file, &names, all);
+    let description = command.to_string();
+    let test_names = test_runner.names();
+    let child = command.spawn()?;
+    std::thread::spawn(move || {
+        let output = match child.wait_with_output() {
+            Ok(output) => format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+            Err(error) => error.to_string(),
+        };
+        let _ = sender.send(AppMessage::TestRun(TestRun {
+            path,
+            tests,
+            names: test_names,
+            output,
+        }));
+    });
+    Ok(description)
+}
+
+fn command(
+    test_runner: &TestRunner,
+    test_commands: Option<&TestCommands>,
+    file: &str,
+    names: &[&str],
+    all: bool,
+) -> ProcessCommand {
+    let configured = |command: Option<&Vec<String>>, tests: &[&str]| {
+        let (program, args) = command?.split_first()?;
+        let args = args.iter().map(String::as_str).collect_vec();
+        Some(TestRunner::substitute(program, &args, file, tests))
+    };
+    match (all, names.first()) {
+        (false, Some(name)) => configured(
+            test_commands.and_then(|commands| commands.test_command.as_ref()),
+            &[*name],
+        )
+        .unwrap_or_else(|| test_runner.test_command(file, name)),
+        _ => configured(
+            test_commands.and_then(|commands| commands.file_command.as_ref()),
+            names,
+        )
+        .unwrap_or_else(|| test_runner.file_command(file, names)),
+    }
+}
+
+/// Finds the status of each of `tests` in `output`.
+///
+/// The output of test runners differs, so a test is considered passed or failed
+/// if a line mentioning its name also contains a pass or fail marker, such as `ok` or `FAILED`.
+/// The failure message of a test is the section under a header line mentioning its name,
+/// such as `---- tests::name stdout ----`, until the next header line.
+///
+/// A `TestNames::RustPath` name is only mentioned by a line if it is not part of a longer path,
+/// so that the test `a` of the crate root is not mistaken for `tests::a`.
+pub(crate) fn parse_results(tests: &[TestCase], names: TestNames, output: &str) -> Vec<TestResult> {
+    let lines = output.lines().collect_vec();
+    let is_header = |line: &str| {
+        let line = line.trim_start();
+        ["----", "___", "===", "●"]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            || line == "failures:"
+    };
+    tests
+        .iter()
+        .filter_map(|test| {
+            let boundary = match names {
+                TestNames::Plain => r"\W",
+                TestNames::RustPath => r"[^\w:]",
+            };
+            let name = regex::Regex::new(&format!(
+                r"(^|{boundary}){}({boundary}|$)",
+                regex::escape(&test.name)
+            ))
+            .ok()?;
+            let status = lines
+                .iter()
+                .filter(|line| name.is_match(line) && !is_header(line))
+                .filter_map(|line| {
+                    if lazy_regex::regex_is_match!(r"\b(FAILED|FAIL|failed)\b|✕|✗|×", line) {
+                        Some(TestStatus::Failed)
+                    } else if lazy_regex::regex_is_match!(r"\b(ok|PASSED|passed)\b|✓|✔|√", line)
+                    {
+                        Some(TestStatus::Passed)
+                    } else {
+                        None
+                    }
+                })
+                .last()?;
+            let message = lines
+                .iter()
+                .position(|line| is_header(line) && name.is_match(line))
+                .map(|header| {
+                    lines[header + 1..]
+                        .iter()
+                        .take_while(|line| !is_header(line))
+                        .join("\n")
+                        .trim()
+                        .to_string()
+                })
+                .filter(|message| !message.is_empty());
+            Some(TestResult {
+                test: test.clone(),
+                status,
+                message: message.filter(|_| status == TestStatus::Failed),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_test_runner {
+    use super::*;
+
+    fn test_case(name: &str, line: usize) -> TestCase {
+        TestCase {
+            name: name.to_string(),
+            range: Position::new(line, 0)..Position::new(line + 1, 0),
+        }
+    }
+
+    #[test]
+    fn configured_test_commands_replace_the_built_in_ones() {
+        let test_runner = shared::language::from_extension("rs")
+            .unwrap()
+            .test_runner()
+            .unwrap();
+        let test_commands = TestCommands {
+            test_command: Some(
+                ["cargo", "nextest", "run", "{test}"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            file_command: None,
+        };
+        assert_eq!(
+            command(
+                &test_runner,
+                Some(&test_commands),
+                "src/a.rs",
+                &["a::b"],
+                false
+            )
+            .to_string(),
+            "cargo nextest run a::b"
+        );
+        // Commands that are not configured keep their defaults
+        assert_eq!(
+            command(
+                &test_runner,
+                Some(&test_commands),
+                "src/a.rs",
+                &["a::b", "a::c"],
+                true
+            )
+            .to_string(),
+            "cargo test -- --exact a::b a::c"
+        );
+        assert_eq!(
+            command(&test_runner, None, "src/a.rs", &["a::b"], false).to_string(),
+            "cargo test -- --exact a::b"
+        );
+    }
+
+    #[test]
+    fn detect_rust_tests() -> anyhow::Result<()> {
+        let language = shared::language::from_extension("rs").unwrap();
+        let buffer = Buffer::new(
+            language.tree_sitter_language(),
+            "#[test]\nfn a() {}\n\nfn helper() {}\n\nmod tests {\n    #[tokio::test]\n    #[ignore]\n    async fn b() {}\n\n    mod nested {\n        #[test]\n        fn a() {}\n    }\n}",
+        );
+        let tests = detect_tests(&buffer, &language.test_runner().unwrap())?;
+        assert_eq!(
+            tests
+                .iter()
+                .map(|test| (test.name.as_str(), test.range.start.line))
+                .collect_vec(),
+            [("a", 1), ("tests::b", 8), ("tests::nested::a", 12)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rust_file_modules_follow_the_crate_layout() {
+        let modules = |path: &str| rust_file_modules(Path::new(path));
+        assert_eq!(modules("/repo/src/lib.rs"), Vec::<String>::new());
+        assert_eq!(modules("/repo/src/foo.rs"), ["foo"]);
+        assert_eq!(modules("/repo/src/foo/bar.rs"), ["foo", "bar"]);
+        assert_eq!(modules("/repo/src/foo/bar/mod.rs"), ["foo", "bar"]);
+        assert_eq!(modules("/repo/shared/src/language.rs"), ["language"]);
+        assert_eq!(modules("/repo/src/bin/tool.rs"), Vec::<String>::new());
+        assert_eq!(modules("/repo/tests/integration.rs"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn parse_libtest_output() {
+        let output = "
+running 3 tests
+test tests::a ... ok
+test tests::b ... FAILED
+test tests::ab ... ok
+
+failures:
+
+---- tests::b stdout ----
+thread 'tests::b' panicked at src/lib.rs:10:5:
+assertion failed: false
+
+failures:
+    tests::b
+";
+        let tests = [test_case("a", 0), test_case("b", 5), test_case("c", 9)];
+        let results = parse_results(&tests, TestNames::Plain, output);
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (result.test.name.as_str(), result.status))
+                .collect_vec(),
+            [("a", TestStatus::Passed), ("b", TestStatus::Failed)]
+        );
+        assert_eq!(results[0].message, None);
+        assert_eq!(
+            results[1].message.as_deref(),
+            Some("thread 'tests::b' panicked at src/lib.rs:10:5:\nassertion failed: false")
+        );
+    }
+
+    #[test]
+    fn parse_libtest_output_of_rust_paths() {
+        let output = "
+running 3 tests
+test a ... FAILED
+test tests::a ... ok
+test other::tests::a ... FAILED
+
+failures:
+
+---- a stdout ----
+thread 'a' panicked at src/lib.rs:2:5:
+boom
+
+---- other::tests::a stdout ----
+thread 'other::tests::a' panicked at src/other.rs:8:9:
+not this one
+";
+        let tests = [test_case("a", 0), test_case("tests::a", 5)];
+        let results = parse_results(&tests, TestNames::RustPath, output);
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (result.test.name.as_str(), result.status))
+                .collect_vec(),
+            [("a", TestStatus::Failed), ("tests::a", TestStatus::Passed)]
+        );
+        assert_eq!(
+            results[0].message.as_deref(),
+            Some("thread 'a' panicked at src/lib.rs:2:5:\nboom")
+        );
+    }
+}
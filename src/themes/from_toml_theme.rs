@@ -0,0 +1,439 @@
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+use anyhow::Context;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{
+    grid::{CellLine, CellLineStyle},
+    style::Style,
+};
+
+use super::{
+    vscode_dark, vscode_light, Color, DiagnosticStyles, HighlightName, HunkStyles, SyntaxStyles,
+    Theme, UiStyles,
+};
+
+/// A theme defined in a TOML file, for example:
+///
+/// ```toml
+/// name = "My theme"
+/// base = "dark"
+///
+/// [ui]
+/// background_color = "#1e1e1e"
+/// global_title = { foreground = "#ffffff", background = "#3478c6", bold = true }
+///
+/// [syntax]
+/// keyword = { foreground = "#569cd6" }
+///
+/// [diagnostic]
+/// error = { undercurl = "#ff0000" }
+///
+/// [hunk]
+/// old_background = "#47221f"
+/// ```
+///
+/// Styles that are not specified are inherited from the base theme,
+/// which is either `dark` (VS Code (Dark), the default) or `light` (VS Code (Light)).
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlTheme {
+    name: String,
+    #[serde(default)]
+    base: Base,
+    #[serde(default)]
+    ui: TomlUiStyles,
+    #[serde(default)]
+    syntax: BTreeMap<String, TomlStyle>,
+    #[serde(default)]
+    diagnostic: TomlDiagnosticStyles,
+    #[serde(default)]
+    hunk: TomlHunkStyles,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum Base {
+    #[default]
+    Dark,
+    Light,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "String")]
+struct HexColor(Color);
+
+impl TryFrom<String> for HexColor {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(HexColor(Color::from_hex(&value)?))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlStyle {
+    foreground: Option<HexColor>,
+    background: Option<HexColor>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underline: Option<HexColor>,
+    undercurl: Option<HexColor>,
+}
+
+impl TomlStyle {
+    /// Only the specified attributes of `base` are overridden.
+    fn apply(&self, base: Style) -> Style {
+        let line = match (self.underline, self.undercurl) {
+            (_, Some(HexColor(color))) => Some(CellLine {
+                color,
+                style: CellLineStyle::Undercurl,
+            }),
+            (Some(HexColor(color)), None) => Some(CellLine {
+                color,
+                style: CellLineStyle::Underline,
+            }),
+            (None, None) => base.line,
+        };
+        Style {
+            foreground_color: self
+                .foreground
+                .map(|HexColor(color)| color)
+                .or(base.foreground_color),
+            background_color: self
+                .background
+                .map(|HexColor(color)| color)
+                .or(base.background_color),
+            line,
+            is_bold: self.bold.unwrap_or(base.is_bold),
+            is_italic: self.italic.unwrap_or(base.is_italic),
+        }
+    }
+}
+
+fn style(toml: &Option<TomlStyle>, base: Style) -> Style {
+    toml.as_ref().map(|toml| toml.apply(base)).unwrap_or(base)
+}
+
+fn color(toml: Option<HexColor>, base: Color) -> Color {
+    toml.map(|HexColor(color)| color).unwrap_or(base)
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct TomlUiStyles {
+    fuzzy_matched_char: Option<TomlStyle>,
+    global_title: Option<TomlStyle>,
+    window_title_focused: Option<TomlStyle>,
+    window_title_unfocused: Option<TomlStyle>,
+    parent_lines_background: Option<HexColor>,
+    jump_mark_odd: Option<TomlStyle>,
+    jump_mark_even: Option<TomlStyle>,
+    text_foreground: Option<HexColor>,
+    background_color: Option<HexColor>,
+    primary_selection_background: Option<HexColor>,
+    primary_selection_anchor_background: Option<HexColor>,
+    primary_selection_secondary_cursor: Option<TomlStyle>,
+    secondary_selection_background: Option<HexColor>,
+    secondary_selection_anchor_background: Option<HexColor>,
+    possible_selection_background: Option<HexColor>,
+    secondary_selection_primary_cursor: Option<TomlStyle>,
+    secondary_selection_secondary_cursor: Option<TomlStyle>,
+    line_number: Option<TomlStyle>,
+    border: Option<TomlStyle>,
+    bookmark: Option<TomlStyle>,
+    keymap_key: Option<TomlStyle>,
+    keymap_arrow: Option<TomlStyle>,
+    keymap_hint: Option<TomlStyle>,
+    ghost_text: Option<TomlStyle>,
+}
+
+impl TomlUiStyles {
+    fn apply(&self, base: UiStyles) -> UiStyles {
+        UiStyles {
+            fuzzy_matched_char: style(&self.fuzzy_matched_char, base.fuzzy_matched_char),
+            global_title: style(&self.global_title, base.global_title),
+            window_title_focused: style(&self.window_title_focused, base.window_title_focused),
+            window_title_unfocused: style(
+                &self.window_title_unfocused,
+                base.window_title_unfocused,
+            ),
+            parent_lines_background: color(
+                self.parent_lines_background,
+                base.parent_lines_background,
+            ),
+            jump_mark_odd: style(&self.jump_mark_odd, base.jump_mark_odd),
+            jump_mark_even: style(&self.jump_mark_even, base.jump_mark_even),
+            text_foreground: color(self.text_foreground, base.text_foreground),
+            background_color: color(self.background_color, base.background_color),
+            primary_selection_background: color(
+                self.primary_selection_background,
+                base.primary_selection_background,
+            ),
+            primary_selection_anchor_background: color(
+                self.primary_selection_anchor_background,
+                base.primary_selection_anchor_background,
+            ),
+            primary_selection_secondary_cursor: style(
+                &self.primary_selection_secondary_cursor,
+                base.primary_selection_secondary_cursor,
+            ),
+            secondary_selection_background: color(
+                self.secondary_selection_background,
+                base.secondary_selection_background,
+            ),
+            secondary_selection_anchor_background: color(
+                self.secondary_selection_anchor_background,
+                base.secondary_selection_anchor_background,
+            ),
+            possible_selection_background: color(
+                self.possible_selection_background,
+                base.possible_selection_background,
+            ),
+            secondary_selection_primary_cursor: style(
+                &self.secondary_selection_primary_cursor,
+                base.secondary_selection_primary_cursor,
+            ),
+            secondary_selection_secondary_cursor: style(
+                &self.secondary_selection_secondary_cursor,
+                base.secondary_selection_secondary_cursor,
+            ),
+            line_number: style(&self.line_number, base.line_number),
+            border: style(&self.border, base.border),
+            bookmark: style(&self.bookmark, base.bookmark),
+            keymap_key: style(&self.keymap_key, base.keymap_key),
+            keymap_arrow: style(&self.keymap_arrow, base.keymap_arrow),
+            keymap_hint: style(&self.keymap_hint, base.keymap_hint),
+            ghost_text: style(&self.ghost_text, base.ghost_text),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct TomlDiagnosticStyles {
+    error: Option<TomlStyle>,
+    warning: Option<TomlStyle>,
+    info: Option<TomlStyle>,
+    hint: Option<TomlStyle>,
+    default: Option<TomlStyle>,
+}
+
+impl TomlDiagnosticStyles {
+    fn apply(&self, base: DiagnosticStyles) -> DiagnosticStyles {
+        DiagnosticStyles {
+            error: style(&self.error, base.error),
+            warning: style(&self.warning, base.warning),
+            info: style(&self.info, base.info),
+            hint: style(&self.hint, base.hint),
+            default: style(&self.default, base.default),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct TomlHunkStyles {
+    old_background: Option<HexColor>,
+    new_background: Option<HexColor>,
+    old_emphasized_background: Option<HexColor>,
+    new_emphasized_background: Option<HexColor>,
+}
+
+impl TomlHunkStyles {
+    fn apply(&self, base: HunkStyles) -> HunkStyles {
+        HunkStyles {
+            old_background: color(self.old_background, base.old_background),
+            new_background: color(self.new_background, base.new_background),
+            old_emphasized_background: color(
+                self.old_emphasized_background,
+                base.old_emphasized_background,
+            ),
+            new_emphasized_background: color(
+                self.new_emphasized_background,
+                base.new_emphasized_background,
+            ),
+        }
+    }
+}
+
+fn apply_syntax(
+    toml: &BTreeMap<String, TomlStyle>,
+    base: SyntaxStyles,
+) -> anyhow::Result<SyntaxStyles> {
+    let mut groups = base.groups;
+    for (name, toml_style) in toml {
+        let highlight_name = HighlightName::from_str(name)
+            .map_err(|_| anyhow::anyhow!("Unknown syntax highlight group: {:?}", name))?;
+        match groups
+            .iter_mut()
+            .find(|(group, _)| group == &highlight_name)
+        {
+            Some((_, style)) => *style = toml_style.apply(*style),
+            None => groups.push((highlight_name, toml_style.apply(Style::new()))),
+        }
+    }
+    Ok(SyntaxStyles::new(&groups))
+}
+
+pub(crate) fn from_toml_theme(content: &str) -> anyhow::Result<Theme> {
+    let toml: TomlTheme = toml::from_str(content)?;
+    let base = match toml.base {
+        Base::Dark => vscode_dark(),
+        Base::Light => vscode_light(),
+    };
+    Ok(Theme {
+        name: toml.name,
+        syntax: apply_syntax(&toml.syntax, base.syntax)?,
+        ui: toml.ui.apply(base.ui),
+        diagnostic: toml.diagnostic.apply(base.diagnostic),
+        hunk: toml.hunk.apply(base.hunk),
+    })
+}
+
+/// Loads every `*.toml` file of `directory` as a theme.
+///
+/// Invalid theme files are skipped, so that they do not hide the other themes,
+/// and their validation errors are returned alongside the themes, one per file.
+pub(crate) fn from_toml_theme_directory(directory: &Path) -> (Vec<Theme>, Vec<String>) {
+    if !directory.exists() {
+        return (Vec::new(), Vec::new());
+    }
+    let paths = match std::fs::read_dir(directory) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension().and_then(|extension| extension.to_str()) == Some("toml")
+            })
+            .sorted()
+            .collect_vec(),
+        Err(error) => {
+            return (
+                Vec::new(),
+                vec![format!("Unable to read {}: {}", directory.display(), error)],
+            )
+        }
+    };
+    let (themes, errors): (Vec<_>, Vec<_>) = paths
+        .into_iter()
+        .map(|path| {
+            std::fs::read_to_string(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| from_toml_theme(&content))
+                .map_err(|error| {
+                    let error = format!("Invalid theme file {}: {}", path.display(), error);
+                    log::error!("{}", error);
+                    error
+                })
+        })
+        .partition_result();
+    (themes, errors)
+}
+
+#[cfg(test)]
+mod test_from_toml_theme {
+    use my_proc_macros::hex;
+
+    use crate::{
+        grid::StyleKey,
+        style::{fg, Style},
+        themes::vscode_light,
+    };
+
+    use super::{from_toml_theme, from_toml_theme_directory};
+
+    #[test]
+    fn unspecified_styles_are_inherited_from_base_theme() -> anyhow::Result<()> {
+        let theme = from_toml_theme(
+            r##"
+name = "Custom"
+base = "light"
+
+[ui]
+background_color = "#123456"
+global_title = { foreground = "#ffffff", bold = true }
+
+[syntax]
+keyword = { foreground = "#abcdef" }
+"variable.parameter" = { foreground = "#111111" }
+"##,
+        )?;
+        let base = vscode_light();
+        assert_eq!(theme.name, "Custom");
+        assert_eq!(theme.ui.background_color, hex!("#123456"));
+        assert_eq!(
+            theme.ui.global_title,
+            base.ui
+                .global_title
+                .foreground_color(hex!("#ffffff"))
+                .bold()
+        );
+        assert_eq!(theme.ui.line_number, base.ui.line_number);
+        assert_eq!(theme.hunk, base.hunk);
+        assert_eq!(
+            theme.get_style(&StyleKey::Syntax("keyword".to_string())),
+            fg(hex!("#abcdef"))
+        );
+        assert_eq!(
+            theme.get_style(&StyleKey::Syntax("variable.parameter".to_string())),
+            Style::new().foreground_color(hex!("#111111"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let error = from_toml_theme(
+            r##"
+name = "Custom"
+[ui]
+backgroud_color = "#123456"
+"##,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("unknown field `backgroud_color`"));
+
+        let error = from_toml_theme(
+            r##"
+name = "Custom"
+[syntax]
+keywords = { foreground = "#123456" }
+"##,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("Unknown syntax highlight group"));
+
+        assert!(from_toml_theme(
+            r##"
+name = "Custom"
+[ui]
+background_color = "blue"
+"##,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_theme_files_are_skipped() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        std::fs::write(directory.path().join("a.toml"), "name = \"A\"")?;
+        std::fs::write(directory.path().join("b.toml"), "nam = \"B\"")?;
+        std::fs::write(directory.path().join("c.toml"), "name = \"C\"")?;
+        std::fs::write(directory.path().join("d.txt"), "name = \"D\"")?;
+        let (themes, errors) = from_toml_theme_directory(directory.path());
+        let names = themes
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["A", "C"]);
+        // The error names the file, the key and the reason
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("b.toml"));
+        assert!(errors[0].contains("unknown field `nam`"));
+        Ok(())
+    }
+}
@@ -105,6 +105,13 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                         to_style(PunctuationDelimiter, get("punctuation.delimiter")),
                         to_style(PunctuationSpecial, get("punctuation.special")),
                         to_style(CommentDocumentation, get("comment.documentation")),
+                        to_style(MarkupStrong, get("emphasis.strong"))
+                            .map(|(name, style)| (name, style.bold())),
+                        to_style(MarkupItalic, get("emphasis"))
+                            .map(|(name, style)| (name, style.italic())),
+                        to_style(MarkupHeading, get("title"))
+                            .map(|(name, style)| (name, style.bold())),
+                        to_style(MarkupRaw, get("text.literal")),
                     ]
                     .into_iter()
                     .flatten()
@@ -156,6 +163,9 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                     keymap_arrow: Style::new().set_some_foreground_color(
                         theme.style.text_muted.and_then(|hex| from_hex(&hex).ok()),
                     ),
+                    ghost_text: Style::new().set_some_foreground_color(
+                        theme.style.text_muted.and_then(|hex| from_hex(&hex).ok()),
+                    ),
                     fuzzy_matched_char: Style::new()
                         .foreground_color(text_accent)
                         .underline(text_accent),
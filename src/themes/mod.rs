@@ -1,3 +1,4 @@
+pub(crate) mod from_toml_theme;
 pub mod from_zed_theme;
 pub mod vscode_dark;
 pub(crate) mod vscode_light;
@@ -143,6 +144,7 @@ pub(crate) struct UiStyles {
     pub(crate) keymap_key: Style,
     pub(crate) keymap_arrow: Style,
     pub(crate) keymap_hint: Style,
+    pub(crate) ghost_text: Style,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
@@ -506,6 +508,16 @@ impl Color {
         Ok(Color { r, g, b, a })
     }
 
+    /// The alpha channel is ignored.
+    pub(crate) fn to_rgb(self) -> (u8, u8, u8) {
+        (self.r, self.g, self.b)
+    }
+
+    /// Formats as `#rrggbb`, ignoring the alpha channel.
+    pub(crate) fn to_hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
     /// Refer https://docs.rs/colorsys/latest/src/colorsys/rgb/transform.rs.html#61
     /// Refer https://sl.bing.net/b69EKNHqrLw
     pub(crate) fn get_contrasting_color(&self) -> Color {
@@ -556,17 +568,25 @@ const ZED_THEME_LINKS: &[&str] = &[
     "https://raw.githubusercontent.com/catppuccin/zed/main/themes/catppuccin-mauve.json",
 ];
 
-pub(crate) fn themes() -> anyhow::Result<Vec<Theme>> {
+/// Returns the available themes, along with the errors of the invalid theme files,
+/// which are skipped, see `from_toml_theme::from_toml_theme_directory`.
+pub(crate) fn themes() -> anyhow::Result<(Vec<Theme>, Vec<String>)> {
     use rayon::prelude::*;
 
     let zed_themes: Vec<_> = ZED_THEME_LINKS
         .par_iter()
         .map(|link| from_zed_theme::from_zed_theme(link))
         .collect::<Result<Vec<_>, _>>()?;
-    Ok(vec![vscode_dark().clone(), vscode_light().clone()]
-        .into_iter()
-        .chain(zed_themes.into_iter().flatten())
-        .collect_vec())
+    let (toml_themes, errors) =
+        from_toml_theme::from_toml_theme_directory(&grammar::config_dir().join("themes"));
+    Ok((
+        toml_themes
+            .into_iter()
+            .chain([vscode_dark().clone(), vscode_light().clone()])
+            .chain(zed_themes.into_iter().flatten())
+            .collect_vec(),
+        errors,
+    ))
 }
 
 #[cfg(test)]
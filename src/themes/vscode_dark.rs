@@ -21,6 +21,10 @@ pub fn vscode_dark() -> Theme {
                 (Comment, fg(hex!("#74985d"))),
                 (Tag, fg(hex!("#71c6b1"))),
                 (TagAttribute, fg(hex!("#aadafa"))),
+                (MarkupStrong, Style::new().bold()),
+                (MarkupItalic, Style::new().italic()),
+                (MarkupHeading, fg(hex!("#679ad1")).bold()),
+                (MarkupRaw, fg(hex!("#c5947c"))),
             ]
         }),
         ui: UiStyles {
@@ -64,6 +68,7 @@ pub fn vscode_dark() -> Theme {
             keymap_hint: Style::new().underline(hex!("#af00db")),
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
             keymap_arrow: Style::new().foreground_color(hex!("#808080")),
+            ghost_text: Style::new().foreground_color(hex!("#6e6e6e")),
             fuzzy_matched_char: Style::new().foreground_color(hex!("#55A8F8")),
         },
         diagnostic: DiagnosticStyles::default(),
@@ -21,6 +21,10 @@ pub fn vscode_light() -> Theme {
                 (Comment, fg(hex!("#008000"))),
                 (Tag, fg(hex!("#267f99"))),
                 (TagAttribute, fg(hex!("#e50000"))),
+                (MarkupStrong, Style::new().bold()),
+                (MarkupItalic, Style::new().italic()),
+                (MarkupHeading, fg(hex!("#0000ff")).bold()),
+                (MarkupRaw, fg(hex!("#a31515"))),
             ]
         }),
         ui: UiStyles {
@@ -64,6 +68,7 @@ pub fn vscode_light() -> Theme {
             keymap_hint: Style::new().underline(hex!("#af00db")),
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
             keymap_arrow: Style::new().foreground_color(hex!("#808080")),
+            ghost_text: Style::new().foreground_color(hex!("#a0a0a0")),
             fuzzy_matched_char: Style::new().foreground_color(hex!("#ff0000")),
         },
         diagnostic: DiagnosticStyles::default(),
@@ -1,4 +1,7 @@
+use std::cmp::Ordering;
+
 use convert_case::Casing;
+use itertools::Itertools;
 
 use crate::soft_wrap::soft_wrap;
 
@@ -7,11 +10,33 @@ pub(crate) enum Transformation {
     Case(convert_case::Case),
     Join,
     Wrap,
+    /// Sorts the items, ignoring their indentation.
+    Sort {
+        /// Compare the first number of each item instead of the text.
+        numeric: bool,
+        /// A regex that extracts the part (its first capture group, or else the whole match)
+        /// of each item to be compared, where items without a match are placed last.
+        key: Option<String>,
+    },
+    Reverse,
+    /// Removes the items that are duplicates of a previous item, ignoring their indentation.
+    Unique,
 }
 impl Transformation {
     pub(crate) fn apply(&self, string: String) -> String {
         match self {
-            Transformation::Case(case) => string.to_case(*case),
+            Transformation::Sort { .. } | Transformation::Reverse | Transformation::Unique => {
+                let (lines, trailing_newline) = match string.strip_suffix('\n') {
+                    Some(lines) => (lines, "\n"),
+                    None => (string.as_str(), ""),
+                };
+                let lines = lines.split('\n').map(|line| line.to_string()).collect();
+                format!(
+                    "{}{trailing_newline}",
+                    self.apply_to_items(lines).join("\n")
+                )
+            }
+            Transformation::Case(case) => convert_naming_convention(&string, *case),
             Transformation::Join => regex::Regex::new(r"\s*\n+\s*")
                 .unwrap()
                 .replace_all(&string, " ")
@@ -19,12 +44,148 @@ impl Transformation {
             Transformation::Wrap => soft_wrap(&string, 80).to_string(),
         }
     }
+
+    /// Whether this transformation rearranges a list of items, which are the lines of a selection,
+    /// or the selections themselves when there are multiple selections.
+    pub(crate) fn rearranges_items(&self) -> bool {
+        matches!(
+            self,
+            Transformation::Sort { .. } | Transformation::Reverse | Transformation::Unique
+        )
+    }
+
+    pub(crate) fn apply_to_items(&self, items: Vec<String>) -> Vec<String> {
+        match self {
+            Transformation::Sort { numeric, key } => {
+                let key = key.as_ref().and_then(|key| regex::Regex::new(key).ok());
+                let sort_key = |item: &String| -> Option<String> {
+                    let item = item.trim_start();
+                    match &key {
+                        Some(key) => key
+                            .captures(item)
+                            .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+                            .map(|m| m.as_str().to_string()),
+                        None => Some(item.to_string()),
+                    }
+                };
+                items
+                    .into_iter()
+                    .map(|item| (sort_key(&item), item))
+                    .sorted_by(|(a, _), (b, _)| compare_sort_keys(a, b, *numeric))
+                    .map(|(_, item)| item)
+                    .collect()
+            }
+            Transformation::Reverse => items.into_iter().rev().collect(),
+            Transformation::Unique => items
+                .into_iter()
+                .unique_by(|item| item.trim_start().to_string())
+                .collect(),
+            _ => items.into_iter().map(|item| self.apply(item)).collect(),
+        }
+    }
+}
+
+/// Joins the lines of `string` into one line, where the indentation of each line except the first
+/// is replaced with a space, or with nothing when the line is joined right after an opening bracket,
+/// or when it starts with a closing bracket, a comma or a dot (such as a method chain).
+///
+/// A trailing comma before a closing bracket is removed, and when the first line is a line comment,
+/// the comment prefixes of the following lines are removed, so that they continue the same comment.
+pub(crate) fn join_lines(string: &str, line_comment_prefix: Option<&str>) -> String {
+    let (lines, trailing_newline) = match string.strip_suffix('\n') {
+        Some(lines) => (lines, "\n"),
+        None => (string, ""),
+    };
+    let mut lines = lines.split('\n');
+    let Some(first_line) = lines.next() else {
+        return string.to_string();
+    };
+    let continued_comment_prefix =
+        line_comment_prefix.filter(|prefix| first_line.trim_start().starts_with(prefix));
+    let joined = lines.fold(first_line.trim_end().to_string(), |mut joined, line| {
+        let line = line.trim();
+        let line = continued_comment_prefix
+            .and_then(|prefix| line.strip_prefix(prefix))
+            .map(|line| line.trim_start())
+            .unwrap_or(line);
+        if line.is_empty() {
+            return joined;
+        }
+        if line.starts_with([')', ']']) && joined.ends_with(',') {
+            joined.pop();
+        }
+        if !(joined.is_empty()
+            || joined.ends_with(['(', '['])
+            || line.starts_with([')', ']', ',', '.']))
+        {
+            joined.push(' ');
+        }
+        joined.push_str(line);
+        joined
+    });
+    format!("{joined}{trailing_newline}")
+}
+
+/// Missing keys, and keys without numbers when `numeric` is true, are ordered last.
+fn compare_sort_keys(a: &Option<String>, b: &Option<String>, numeric: bool) -> Ordering {
+    let number = |key: &str| {
+        lazy_regex::regex_find!(r"-?\d+(\.\d+)?", key).and_then(|number| number.parse::<f64>().ok())
+    };
+    match (a, b) {
+        (Some(a), Some(b)) if numeric => match (number(a), number(b)) {
+            (Some(x), Some(y)) => x.total_cmp(&y).then_with(|| a.cmp(b)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.cmp(b),
+        },
+        (Some(a), Some(b)) => a.cmp(b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// Converts `string` as a whole if it is a phrase or a single identifier (such as `hello world` or `helloWorld`),
+/// otherwise each identifier of `string` is converted separately,
+/// so that the punctuation and whitespace between them (such as in `let fooBar = baz_qux;`) are preserved.
+fn convert_naming_convention(string: &str, case: convert_case::Case) -> String {
+    if lazy_regex::regex_is_match!(r"^[\p{Alphabetic}\p{N}_\- \t]*$", string) {
+        return string.to_case(case);
+    }
+    lazy_regex::regex!(r"[\p{Alphabetic}\p{N}_\-]+")
+        .replace_all(string, |captures: &regex::Captures| {
+            captures[0].to_case(case)
+        })
+        .to_string()
 }
 
 #[cfg(test)]
 mod test_transformation {
+    use convert_case::Case;
+
     use super::Transformation;
 
+    #[test]
+    fn naming_convention_of_phrase_or_identifier() {
+        let apply = |case, string: &str| Transformation::Case(case).apply(string.to_string());
+        assert_eq!(apply(Case::Snake, "helloWorld"), "hello_world");
+        assert_eq!(apply(Case::Camel, "hello world"), "helloWorld");
+        assert_eq!(apply(Case::Pascal, "hello-world"), "HelloWorld");
+        assert_eq!(apply(Case::UpperSnake, "HelloWorld"), "HELLO_WORLD");
+        assert_eq!(apply(Case::Kebab, "HELLO_WORLD"), "hello-world");
+        assert_eq!(apply(Case::Title, "hello_world"), "Hello World");
+    }
+
+    #[test]
+    fn naming_convention_of_each_identifier() {
+        let result = Transformation::Case(Case::Snake)
+            .apply("let fooBar = bazQux(spamEggs);\nfooBar.helloWorld()".to_string());
+        assert_eq!(
+            result,
+            "let foo_bar = baz_qux(spam_eggs);\nfoo_bar.hello_world()"
+        )
+    }
+
     #[test]
     fn join() {
         let result = Transformation::Join.apply(
@@ -42,6 +203,21 @@ pineapple?
         assert_eq!(result, "who lives in a pineapple?")
     }
 
+    #[test]
+    fn join_lines() {
+        use super::join_lines;
+        assert_eq!(
+            join_lines("foo(\n    a,\n    b,\n)\n    .bar()", Some("//")),
+            "foo(a, b).bar()"
+        );
+        assert_eq!(
+            join_lines("    // hello\n    // world\n", Some("//")),
+            "    // hello world\n"
+        );
+        assert_eq!(join_lines("x = 1\n// y", Some("//")), "x = 1 // y");
+        assert_eq!(join_lines("a  \n\n  b", None), "a b");
+    }
+
     #[test]
     fn wrap() {
         let result = Transformation::Wrap
@@ -51,4 +227,34 @@ who lives in a pineapple under the sea? Spongebob Squarepants! absorbent and yel
             .trim().to_string());
         assert_eq!(result, "who lives in a pineapple under the sea? Spongebob Squarepants! absorbent and \nyellow and porous is he? Spongebob Squarepants")
     }
+
+    #[test]
+    fn sort_lines_preserving_indentation() {
+        let sort = |numeric, key: Option<&str>, string: &str| {
+            Transformation::Sort {
+                numeric,
+                key: key.map(|key| key.to_string()),
+            }
+            .apply(string.to_string())
+        };
+        assert_eq!(sort(false, None, "  b\na\n    c\n"), "a\n  b\n    c\n");
+        assert_eq!(sort(false, None, "10\n9\nx\n-1"), "-1\n10\n9\nx");
+        assert_eq!(sort(true, None, "10\n9\nx\n-1"), "-1\n9\n10\nx");
+        assert_eq!(
+            sort(true, Some(r"age=(\d+)"), "b age=30\nc\na age=4"),
+            "a age=4\nb age=30\nc"
+        );
+    }
+
+    #[test]
+    fn reverse_and_unique_lines() {
+        assert_eq!(
+            Transformation::Reverse.apply("a\n  b\nc".to_string()),
+            "c\n  b\na"
+        );
+        assert_eq!(
+            Transformation::Unique.apply("a\n  b\na\nb\nc\n".to_string()),
+            "a\n  b\nc\n"
+        );
+    }
 }
@@ -0,0 +1,88 @@
+use crate::components::{component::ComponentId, editor::Editor};
+
+/// A lesson is passed as soon as its buffer satisfies the goal.
+pub(crate) enum LessonGoal {
+    SelectedTexts(&'static [&'static str]),
+    Content(&'static str),
+}
+
+pub(crate) struct Lesson {
+    pub(crate) title: &'static str,
+    pub(crate) instruction: &'static str,
+    pub(crate) content: &'static str,
+    pub(crate) goal: LessonGoal,
+}
+
+impl Lesson {
+    pub(crate) fn is_passed(&self, editor: &Editor) -> bool {
+        match self.goal {
+            LessonGoal::SelectedTexts(expected) => editor.get_selected_texts() == expected,
+            LessonGoal::Content(expected) => editor.buffer().content() == expected,
+        }
+    }
+}
+
+pub(crate) const LESSONS: &[Lesson] = &[
+    Lesson {
+        title: "Word selection",
+        instruction: "Press `w` to select a word, then press `l` to select the next word, until `world` is selected.",
+        content: "Hello world, welcome to Ki!",
+        goal: LessonGoal::SelectedTexts(&["world"]),
+    },
+    Lesson {
+        title: "Line selection",
+        instruction: "Press `e` to select a line, then press `l` (next) or `h` (previous), until `the target line` is selected.",
+        content: "first line\nsecond line\nthe target line\nlast line",
+        goal: LessonGoal::SelectedTexts(&["the target line"]),
+    },
+    Lesson {
+        title: "Delete",
+        instruction: "Select the word `useless` (with `w` and `l`), then press `d` to delete it.",
+        content: "remove the useless word",
+        goal: LessonGoal::Content("remove the word"),
+    },
+    Lesson {
+        title: "Jump",
+        instruction: "Press `w` to select words, then press `s` to show jump labels, then type the label shown on `target`.",
+        content: "one two three four five six seven target eight nine ten",
+        goal: LessonGoal::SelectedTexts(&["target"]),
+    },
+    Lesson {
+        title: "Multi-cursor",
+        instruction: "Press `e` to select a line, then `space` `a` to add a cursor to every line, then press `i`, type `- `, and press `esc`.",
+        content: "apple\nbanana\ncherry",
+        goal: LessonGoal::Content("- apple\n- banana\n- cherry"),
+    },
+];
+
+/// The progress of the interactive tutorial.
+#[derive(Default)]
+pub(crate) struct Tutor {
+    lesson_index: usize,
+    /// The component showing the buffer of the current lesson,
+    /// only key events of this component can pass the lesson.
+    component_id: Option<ComponentId>,
+}
+
+impl Tutor {
+    pub(crate) fn current_lesson(&self) -> Option<&'static Lesson> {
+        LESSONS.get(self.lesson_index)
+    }
+
+    pub(crate) fn lesson_index(&self) -> usize {
+        self.lesson_index
+    }
+
+    pub(crate) fn component_id(&self) -> Option<ComponentId> {
+        self.component_id
+    }
+
+    pub(crate) fn set_component_id(&mut self, component_id: ComponentId) {
+        self.component_id = Some(component_id)
+    }
+
+    pub(crate) fn next_lesson(&mut self) {
+        self.lesson_index += 1;
+        self.component_id = None;
+    }
+}
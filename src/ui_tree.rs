@@ -3,7 +3,10 @@ use std::{cell::RefCell, rc::Rc};
 use itertools::Itertools;
 use nary_tree::{NodeId, NodeMut, NodeRef, RemoveBehavior};
 
-use crate::components::{component::Component, editor::Editor};
+use crate::components::{
+    component::{Component, ComponentId},
+    editor::Editor,
+};
 
 pub(crate) struct UiTree {
     tree: nary_tree::Tree<KindedComponent>,
@@ -181,6 +184,20 @@ impl UiTree {
         };
     }
 
+    /// Returns false if no node holds the component of `component_id`.
+    pub(crate) fn focus_component(&mut self, component_id: ComponentId) -> bool {
+        let Some(node_id) = self
+            .root()
+            .traverse_pre_order()
+            .find(|node| node.data().component().borrow().id() == component_id)
+            .map(|node| node.node_id())
+        else {
+            return false;
+        };
+        self.set_focus_component_id(node_id);
+        true
+    }
+
     pub(crate) fn focused_component_id(&self) -> NodeId {
         self.focused_component_id
     }
@@ -274,6 +291,18 @@ impl UiTree {
         )
     }
 
+    pub(crate) fn get_node_child_component(
+        &self,
+        node_id: NodeId,
+        kind: ComponentKind,
+    ) -> Option<Rc<RefCell<dyn Component>>> {
+        Some(
+            self.get(self.get_node_child_id(node_id, kind)?)?
+                .data()
+                .component(),
+        )
+    }
+
     fn get_node_child_id(&self, node_id: NodeId, kind: ComponentKind) -> Option<NodeId> {
         Some(
             self.get(node_id)?
@@ -329,11 +358,14 @@ pub(crate) enum ComponentKind {
     GlobalInfo,
     QuickfixList,
     QuickfixListInfo,
+    QuickfixListPreview,
     Prompt,
     Dropdown,
     DropdownInfo,
     EditorInfo,
     KeymapLegend,
+    Peek,
+    Outline,
     /// The root should not be rendered
     Root,
 }
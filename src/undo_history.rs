@@ -0,0 +1,178 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+/// Only the latest patches are persisted for each file.
+const MAX_PATCHES: usize = 1000;
+/// Histories larger than this are not persisted at all.
+const MAX_BYTES: usize = 10 * 1024 * 1024;
+/// Histories not saved for this long are removed by `collect_garbage`.
+const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// Only the most recently saved histories are kept by `collect_garbage`.
+const MAX_FILES: usize = 1000;
+
+/// The persisted form of an undo entry, see `crate::buffer::Patch`.
+/// The selections before and after the edit are not persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PersistedPatch {
+    pub(crate) old_to_new: String,
+    pub(crate) new_to_old: String,
+}
+
+fn directory() -> PathBuf {
+    grammar::cache_dir().join("undo_histories")
+}
+
+/// The history of a file is keyed by its path and the hash of its content,
+/// so that a history is never restored onto content it does not lead to.
+fn history_path(directory: &Path, path: &Path, content: &str) -> PathBuf {
+    directory.join(format!(
+        "{}_{:016x}.json",
+        crate::persistence::file_name(path),
+        crate::persistence::stable_hash(content.as_bytes())
+    ))
+}
+
+/// Persist `patches`, which lead to `content`, as the undo history of the file of `path`.
+pub(crate) fn persist(
+    path: &Path,
+    content: &str,
+    patches: &[PersistedPatch],
+) -> anyhow::Result<()> {
+    persist_in(&directory(), path, content, patches)
+}
+
+fn persist_in(
+    directory: &Path,
+    path: &Path,
+    content: &str,
+    patches: &[PersistedPatch],
+) -> anyhow::Result<()> {
+    if patches.is_empty() {
+        return Ok(());
+    }
+    let patches = &patches[patches.len().saturating_sub(MAX_PATCHES)..];
+    let json = serde_json::to_string(patches)?;
+    if json.len() > MAX_BYTES {
+        log::info!(
+            "The undo history of {} is not persisted, because it is too large",
+            path.display()
+        );
+        return Ok(());
+    }
+    std::fs::create_dir_all(directory)?;
+    std::fs::write(history_path(directory, path, content), json)?;
+    Ok(())
+}
+
+/// Returns the persisted undo history of the file of `path` with `content`, from the oldest patch.
+pub(crate) fn load(path: &Path, content: &str) -> anyhow::Result<Vec<PersistedPatch>> {
+    load_in(&directory(), path, content)
+}
+
+fn load_in(directory: &Path, path: &Path, content: &str) -> anyhow::Result<Vec<PersistedPatch>> {
+    let history_path = history_path(directory, path, content);
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&std::fs::read_to_string(
+        history_path,
+    )?)?)
+}
+
+/// Removes the histories which are older than `MAX_AGE`, or not among the latest `MAX_FILES`.
+/// This includes the histories of content that has been changed since.
+pub(crate) fn collect_garbage() -> anyhow::Result<()> {
+    collect_garbage_in(&directory(), SystemTime::now())
+}
+
+fn collect_garbage_in(directory: &Path, now: SystemTime) -> anyhow::Result<()> {
+    if !directory.exists() {
+        return Ok(());
+    }
+    let histories = std::fs::read_dir(directory)?
+        .map(|entry| {
+            let path = entry?.path();
+            let modified = std::fs::metadata(&path)?.modified()?;
+            Ok((path, modified))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter()
+        .sorted_by_key(|(_, modified)| std::cmp::Reverse(*modified))
+        .collect_vec();
+    for (index, (path, modified)) in histories.into_iter().enumerate() {
+        let stale = now
+            .duration_since(modified)
+            .map(|age| age > MAX_AGE)
+            .unwrap_or(false);
+        if stale || index >= MAX_FILES {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_undo_history {
+    use super::*;
+
+    #[test]
+    fn history_path_is_keyed_by_path_and_content() {
+        let directory = Path::new("/cache");
+        let history = history_path(directory, Path::new("/src/a-b.rs"), "x");
+        assert!(history
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("_src_a_b_rs_"));
+        assert_eq!(
+            history,
+            history_path(directory, Path::new("/src/a-b.rs"), "x")
+        );
+        assert_ne!(
+            history,
+            history_path(directory, Path::new("/src/a-b.rs"), "y")
+        );
+        assert_ne!(
+            history,
+            history_path(directory, Path::new("/src/a_b.rs"), "x")
+        );
+    }
+
+    #[test]
+    fn persist_and_load() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path();
+        let path = Path::new("/a.txt");
+        let patches = [PersistedPatch {
+            old_to_new: "a".to_string(),
+            new_to_old: "b".to_string(),
+        }];
+        persist_in(directory, path, "content", &patches)?;
+        assert_eq!(load_in(directory, path, "content")?, patches);
+        // The history does not apply to other content or other files
+        assert_eq!(load_in(directory, path, "changed")?, []);
+        assert_eq!(load_in(directory, Path::new("/b.txt"), "content")?, []);
+        Ok(())
+    }
+
+    #[test]
+    fn collect_garbage() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let directory = directory.path();
+        let patches = [PersistedPatch {
+            old_to_new: "a".to_string(),
+            new_to_old: "b".to_string(),
+        }];
+        persist_in(directory, Path::new("/a.txt"), "content", &patches)?;
+        collect_garbage_in(directory, SystemTime::now())?;
+        assert_eq!(std::fs::read_dir(directory)?.count(), 1);
+        collect_garbage_in(directory, SystemTime::now() + MAX_AGE * 2)?;
+        assert_eq!(std::fs::read_dir(directory)?.count(), 0);
+        Ok(())
+    }
+}
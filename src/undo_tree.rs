@@ -50,6 +50,19 @@ impl<T: Applicable> UndoTree<T> {
         }
     }
 
+    /// The number of entries from the root to the current entry.
+    pub(crate) fn depth(&self) -> usize {
+        self.history.head().index
+    }
+
+    /// Returns the entries from the root to the current entry, from the oldest.
+    pub(crate) fn current_branch(&self) -> Vec<OldNew<T>> {
+        (0..self.history.head().index)
+            .filter_map(|index| self.history.get_entry(index))
+            .map(|entry| entry.get().clone())
+            .collect()
+    }
+
     pub(crate) fn display(&self) -> String {
         self.history.display().detailed(false).to_string()
     }
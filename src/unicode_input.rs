@@ -0,0 +1,173 @@
+/// Parses a Unicode code point written in hexadecimal, optionally prefixed with `U+`, `u+` or `0x`,
+/// such as `e9`, `U+00E9` or `0x1F980`.
+pub(crate) fn parse_code_point(text: &str) -> anyhow::Result<char> {
+    let text = text.trim();
+    let hex = ["U+", "u+", "0x", "0X"]
+        .into_iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+        .unwrap_or(text);
+    let code_point = u32::from_str_radix(hex, 16)
+        .map_err(|_| anyhow::anyhow!("{text:?} is not a hexadecimal code point"))?;
+    char::from_u32(code_point)
+        .ok_or_else(|| anyhow::anyhow!("U+{code_point:X} is not a valid Unicode scalar value"))
+}
+
+/// The characters that can be searched by their names in the character picker.
+pub(crate) const CHARACTERS: &[(&str, char)] = &[
+    // Greek letters
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("phi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("capital gamma", 'Γ'),
+    ("capital delta", 'Δ'),
+    ("capital theta", 'Θ'),
+    ("capital lambda", 'Λ'),
+    ("capital pi", 'Π'),
+    ("capital sigma", 'Σ'),
+    ("capital phi", 'Φ'),
+    ("capital psi", 'Ψ'),
+    ("capital omega", 'Ω'),
+    // Arrows
+    ("left arrow", '←'),
+    ("up arrow", '↑'),
+    ("right arrow", '→'),
+    ("down arrow", '↓'),
+    ("left right arrow", '↔'),
+    ("up down arrow", '↕'),
+    ("left double arrow", '⇐'),
+    ("right double arrow", '⇒'),
+    ("left right double arrow", '⇔'),
+    ("maps to", '↦'),
+    ("return", '↵'),
+    // Mathematics
+    ("plus minus", '±'),
+    ("multiplication", '×'),
+    ("division", '÷'),
+    ("not equal", '≠'),
+    ("less than or equal", '≤'),
+    ("greater than or equal", '≥'),
+    ("almost equal", '≈'),
+    ("identical", '≡'),
+    ("infinity", '∞'),
+    ("square root", '√'),
+    ("summation", '∑'),
+    ("product", '∏'),
+    ("integral", '∫'),
+    ("partial differential", '∂'),
+    ("nabla", '∇'),
+    ("for all", '∀'),
+    ("there exists", '∃'),
+    ("empty set", '∅'),
+    ("element of", '∈'),
+    ("not element of", '∉'),
+    ("subset of", '⊂'),
+    ("superset of", '⊃'),
+    ("intersection", '∩'),
+    ("union", '∪'),
+    ("logical and", '∧'),
+    ("logical or", '∨'),
+    ("not", '¬'),
+    ("degree", '°'),
+    ("superscript two", '²'),
+    ("superscript three", '³'),
+    ("one half", '½'),
+    ("one quarter", '¼'),
+    // Currencies
+    ("euro", '€'),
+    ("pound", '£'),
+    ("yen", '¥'),
+    ("cent", '¢'),
+    ("bitcoin", '₿'),
+    // Punctuation and typography
+    ("em dash", '—'),
+    ("en dash", '–'),
+    ("ellipsis", '…'),
+    ("bullet", '•'),
+    ("middle dot", '·'),
+    ("left double quotation mark", '“'),
+    ("right double quotation mark", '”'),
+    ("left single quotation mark", '‘'),
+    ("right single quotation mark", '’'),
+    ("left guillemet", '«'),
+    ("right guillemet", '»'),
+    ("section", '§'),
+    ("pilcrow", '¶'),
+    ("dagger", '†'),
+    ("copyright", '©'),
+    ("registered", '®'),
+    ("trade mark", '™'),
+    ("non breaking space", '\u{a0}'),
+    ("zero width space", '\u{200b}'),
+    // Symbols
+    ("check mark", '✓'),
+    ("cross mark", '✗'),
+    ("star", '★'),
+    ("heart", '♥'),
+    ("warning", '⚠'),
+    ("lightning", '⚡'),
+    // Emojis
+    ("grinning face", '😀'),
+    ("face with tears of joy", '😂'),
+    ("smiling face with heart eyes", '😍'),
+    ("thinking face", '🤔'),
+    ("thumbs up", '👍'),
+    ("thumbs down", '👎'),
+    ("clapping hands", '👏'),
+    ("party popper", '🎉'),
+    ("fire", '🔥'),
+    ("rocket", '🚀'),
+    ("sparkles", '✨'),
+    ("bug", '🐛'),
+    ("crab", '🦀'),
+    ("snake", '🐍'),
+    ("eyes", '👀'),
+    ("hundred points", '💯'),
+    ("white heavy check mark", '✅'),
+    ("cross mark button", '❌'),
+    ("construction", '🚧'),
+    ("memo", '📝'),
+    ("light bulb", '💡'),
+    ("lock", '🔒'),
+    ("hammer and wrench", '🛠'),
+    ("package", '📦'),
+];
+
+#[cfg(test)]
+mod test_unicode_input {
+    use super::*;
+
+    #[test]
+    fn parse_code_points() {
+        assert_eq!(parse_code_point("e9").unwrap(), 'é');
+        assert_eq!(parse_code_point("U+00E9").unwrap(), 'é');
+        assert_eq!(parse_code_point(" 0x1F980 ").unwrap(), '🦀');
+        assert!(parse_code_point("xyz").is_err());
+        assert!(parse_code_point("D800").is_err());
+    }
+
+    #[test]
+    fn character_names_are_unique() {
+        use itertools::Itertools;
+        assert!(CHARACTERS.iter().map(|(name, _)| name).all_unique());
+        assert!(CHARACTERS.iter().map(|(_, c)| c).all_unique());
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// The content of `config.toml` in the config directory of Ki, for example:
+///
+/// ```toml
+/// [commands]
+/// completion_source = "my-assistant --stdio"
+/// url_opener = "firefox"
+/// elevation_command = "doas tee"
+///
+/// [commands.tests.rust]
+/// test_command = ["cargo", "nextest", "run", "{test}"]
+/// ```
+///
+/// Settings that run commands are only read from here, never from `.ki/config.toml`,
+/// so that opening a cloned repository cannot run commands chosen by that repository.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct UserConfig {
+    #[serde(default)]
+    pub(crate) commands: CommandsConfig,
+}
+
+/// Commands run by the editor.
+/// Commands that are not set keep their defaults.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct CommandsConfig {
+    /// A command providing extra completions, see `ProcessCompletionSource`.
+    pub(crate) completion_source: Option<String>,
+    pub(crate) url_opener: Option<String>,
+    pub(crate) elevation_command: Option<String>,
+    /// Keyed by the language ID, such as `rust` or `typescript`.
+    #[serde(default)]
+    pub(crate) tests: HashMap<String, TestCommands>,
+}
+
+/// Commands replacing the built-in commands of the `TestRunner` of a language,
+/// each being the program followed by its arguments, with the same placeholders.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TestCommands {
+    /// Runs a single test.
+    pub(crate) test_command: Option<Vec<String>>,
+    /// Runs all tests of a file.
+    pub(crate) file_command: Option<Vec<String>>,
+}
+
+impl UserConfig {
+    /// Loads `config.toml` of the config directory of Ki.
+    /// Returns the default config if the file does not exist.
+    pub(crate) fn load() -> anyhow::Result<Self> {
+        let path = grammar::config_dir().join("config.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Self::from_toml(&std::fs::read_to_string(&path)?)
+            .map_err(|error| anyhow::anyhow!("Invalid {}: {}", path.display(), error))
+    }
+
+    fn from_toml(config: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(config)?)
+    }
+}
+
+#[cfg(test)]
+mod test_user_config {
+    use super::*;
+
+    #[test]
+    fn from_toml() -> anyhow::Result<()> {
+        let config = UserConfig::from_toml(
+            r#"
+[commands]
+completion_source = "my-assistant --stdio"
+url_opener = "firefox"
+elevation_command = "doas tee"
+
+[commands.tests.rust]
+test_command = ["cargo", "nextest", "run", "{test}"]
+"#,
+        )?;
+        assert_eq!(
+            config.commands,
+            CommandsConfig {
+                completion_source: Some("my-assistant --stdio".to_string()),
+                url_opener: Some("firefox".to_string()),
+                elevation_command: Some("doas tee".to_string()),
+                tests: HashMap::from([(
+                    "rust".to_string(),
+                    TestCommands {
+                        test_command: Some(
+                            ["cargo", "nextest", "run", "{test}"]
+                                .map(String::from)
+                                .to_vec()
+                        ),
+                        file_command: None,
+                    }
+                )]),
+            }
+        );
+        assert_eq!(UserConfig::from_toml("")?, UserConfig::default());
+        assert!(UserConfig::from_toml("[commands]\nsource = \"x\"").is_err());
+        Ok(())
+    }
+}